@@ -0,0 +1,377 @@
+use crate::{ ASTNode, Token, Parser, PythonCoreParser, Tokenizer };
+use crate::parser::tokenizer::PythonCoreTokenizer;
+use crate::parser::blocks::Blocks;
+use crate::diagnostics::Diagnostic;
+use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+use crate::semantic::symbols::SymbolTable;
+use crate::semantic::validation::validate;
+use crate::semantic::dataflow;
+use crate::edits::{ TextEdit, apply_edits, diff_edits };
+
+
+/// Start rule of grammar used for parsing source, same names as the 'mode' argument of CPython compile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Exec,
+    Eval,
+    Single,
+    FuncType
+}
+
+/// Outcome of parsing source. Tree is missing when parser stopped at a syntax error, which is then
+/// found in diagnostics. Tokens are all tokens of source up to end of file or first lexical error.
+#[derive(Clone, Debug)]
+pub struct ParseResult {
+    pub tree: Option<Box<ASTNode>>,
+    pub tokens: Vec<Box<Token>>,
+    pub diagnostics: Vec<Diagnostic>
+}
+
+/// Kind of token the parser may read next, see 'expected_tokens'.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpectedToken {
+    Keyword(&'static str),
+    SoftKeyword(&'static str),
+    Operator(&'static str),
+    Name,
+    Number,
+    String,
+    Newline
+}
+
+/// Reserved words of the grammar, soft keywords are names.
+pub const KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+    "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal", "not", "or",
+    "pass", "raise", "return", "try", "while", "with", "yield"
+];
+
+/// Names that are keywords in some places of the grammar.
+pub const SOFT_KEYWORDS: [&str; 4] = [ "_", "case", "match", "type" ];
+
+/// Operators and delimiters of the grammar.
+pub const OPERATORS: [&str; 47] = [
+    "+", "-", "*", "**", "/", "//", "%", "@", "<<", ">>", "&", "|", "^", "~", ":=", "<", ">", "<=", ">=", "==", "!=",
+    "(", ")", "[", "]", "{", "}", ",", ":", ".", ";", "=", "->", "+=", "-=", "*=", "/=", "//=", "%=", "@=", "&=", "|=",
+    "^=", ">>=", "<<=", "**=", "..."
+];
+
+
+impl ParseResult {
+    pub fn is_ok(&self) -> bool {
+        self.tree.is_some() && !self.diagnostics.iter().any( |d| d.is_error() )
+    }
+}
+
+/// Parses a whole file, like compile with mode 'exec'.
+pub fn parse_module(source: &str) -> ParseResult {
+    parse(source, Mode::Exec)
+}
+
+/// Parses a single expression, like compile with mode 'eval'.
+pub fn parse_expression(source: &str) -> ParseResult {
+    parse(source, Mode::Eval)
+}
+
+/// Parses a single interactive statement, like compile with mode 'single'.
+pub fn parse_interactive(source: &str) -> ParseResult {
+    parse(source, Mode::Single)
+}
+
+/// Parses a function type comment like '(int, str) -> bool', like compile with mode 'func_type'.
+pub fn parse_func_type(source: &str) -> ParseResult {
+    parse(source, Mode::FuncType)
+}
+
+/// Parses source with start rule given by mode. A missing newline at end of source is added, which
+/// leaves all positions unchanged. Line endings must be '\n', see 'normalize_source'.
+pub fn parse(source: &str, mode: Mode) -> ParseResult {
+    let text = with_final_newline(source);
+    let mut lexer = Box::new( PythonCoreTokenizer::new(text.clone()) );
+    lexer.set_interactive(mode == Mode::Single);
+    let mut parser = PythonCoreParser::new(lexer);
+    let res = match mode {
+        Mode::Exec => parser.parse_blocks_file_input(),
+        Mode::Eval => parser.parse_blocks_eval_input(),
+        Mode::Single => parser.parse_blocks_single_input(),
+        Mode::FuncType => parser.parse_blocks_func_type_input()
+    };
+    let ( tokens, _ ) = tokenize_with(&text, mode == Mode::Single);
+    match res {
+        Ok( tree ) => ParseResult { tree: Some( tree ), tokens, diagnostics: Vec::new() },
+        Err( e ) => {
+            let span = match &parser.symbol {
+                Ok( t ) => t.get_span(),
+                Err( _ ) => None
+            };
+            let mut diagnostic = Diagnostic::from_error_text(&e, 0);
+            let ( start, end ) = span.unwrap_or( ( parser.lexer.get_position(), parser.lexer.get_position() ) );
+            diagnostic.start = start;
+            diagnostic.end = end;
+            ParseResult { tree: None, tokens, diagnostics: vec![ diagnostic ] }
+        }
+    }
+}
+
+/// Parses, lowers and validates source. Returns every error CPython compile reports, the first one
+/// is the error CPython would show.
+pub fn check(source: &str, mode: Mode) -> Vec<Diagnostic> {
+    let text = with_final_newline(source);
+    let res = parse(&text, mode);
+    let tree = match res.tree {
+        Some( t ) => t,
+        None => return res.diagnostics
+    };
+    match PythonCoreLowering::new(&text).lower_mod(&tree) {
+        Ok( module ) => validate(&module, &SymbolTable::build(&module, &text), &text),
+        Err( e ) => vec![ Diagnostic::from_error_text(&e, 0) ]
+    }
+}
+
+/// Like 'check', and when source has no errors adds the warnings of dataflow analyses about
+/// unreachable code, unused local variables and locals read before assignment.
+pub fn analyze(source: &str, mode: Mode) -> Vec<Diagnostic> {
+    let text = with_final_newline(source);
+    let tree = match parse(&text, mode) {
+        ParseResult { tree: Some( t ), .. } => t,
+        res => return res.diagnostics
+    };
+    match PythonCoreLowering::new(&text).lower_mod(&tree) {
+        Ok( module ) => {
+            let symbols = SymbolTable::build(&module, &text);
+            let errors = validate(&module, &symbols, &text);
+            match errors.is_empty() {
+                true => dataflow::analyze(&module, &symbols),
+                _ => errors
+            }
+        },
+        Err( e ) => vec![ Diagnostic::from_error_text(&e, 0) ]
+    }
+}
+
+/// Tokens the parser accepts at the end of source read with start rule 'exec'. The tokenizer ends
+/// source with a token no rule accepts, and the token kinds the parser tested there are its
+/// expectation set. Empty when parsing fails in front of the end, source must not end inside a token.
+pub fn expected_tokens(source: &str) -> Vec<ExpectedToken> {
+    let end = source.chars().count() as u32;
+    let mut lexer = Box::new( PythonCoreTokenizer::new(source.to_string()) );
+    lexer.set_probe(true);
+    let mut parser = PythonCoreParser::new(lexer);
+    if parser.parse_blocks_file_input().is_ok() || parser.furthest.get() != end {
+        return Vec::new()
+    }
+    let expected = parser.expected.into_inner();
+    let tested = |text: &str| kind_of(text).is_some_and( |k| expected.contains(&k) );
+    let mut res: Vec<ExpectedToken> = KEYWORDS.iter().filter( |k| tested(k) ).map( |k| ExpectedToken::Keyword(k) ).collect();
+    res.extend( SOFT_KEYWORDS.iter().filter( |k| expected.contains(k) ).map( |k| ExpectedToken::SoftKeyword(k) ) );
+    res.extend( OPERATORS.iter().filter( |o| tested(o) ).map( |o| ExpectedToken::Operator(o) ) );
+    for ( kind, token ) in [ ( "AtomName", ExpectedToken::Name ), ( "AtomNumber", ExpectedToken::Number ),
+                             ( "AtomString", ExpectedToken::String ), ( "Newline", ExpectedToken::Newline ) ] {
+        if expected.contains(&kind) {
+            res.push(token)
+        }
+    }
+    res
+}
+
+/// Name of the token kind of a keyword or operator. Closing brackets are read after their opening
+/// one, as the tokenizer rejects them otherwise.
+fn kind_of(text: &str) -> Option<&'static str> {
+    let source = match text {
+        ")" => "()",
+        "]" => "[]",
+        "}" => "{}",
+        _ => text
+    };
+    let mut lexer = PythonCoreTokenizer::new(source.to_string());
+    let mut token = lexer.get_symbol().ok()?;
+    if source != text {
+        token = lexer.get_symbol().ok()?
+    }
+    Some( token.kind_name() )
+}
+
+/// All tokens of source including end of file, or the tokens in front of a lexical error and the error.
+pub fn tokenize(source: &str) -> ( Vec<Box<Token>>, Option<Diagnostic> ) {
+    tokenize_with(source, false)
+}
+
+/// Like 'tokenize', interactive mode ends blocks at an empty line as the REPL does.
+pub fn tokenize_with(source: &str, interactive: bool) -> ( Vec<Box<Token>>, Option<Diagnostic> ) {
+    let mut lexer = PythonCoreTokenizer::new(with_final_newline(source));
+    lexer.set_interactive(interactive);
+    let mut tokens = Vec::new();
+    loop {
+        match lexer.get_symbol() {
+            Ok( tok ) => {
+                let eof = matches!(&*tok, Token::EOF(..));
+                tokens.push(tok);
+                if eof {
+                    return ( tokens, None )
+                }
+            },
+            Err( e ) => return ( tokens, Some( Diagnostic::from_error_text(&e, lexer.get_position()) ) )
+        }
+    }
+}
+
+/// Source with '\r\n' and '\r' line endings replaced by '\n' and a newline added at end when missing.
+pub fn normalize_source(text: &str) -> String {
+    with_final_newline( &text.replace("\r\n", "\n").replace('\r', "\n") )
+}
+
+/// Changes from 'source', the normalized 'original', to 'changed' applied to 'original', which keeps
+/// its byte order mark, line endings and missing newline at end. New line breaks get the line ending
+/// of the first line of 'original'.
+pub fn restore_source(original: &str, source: &str, changed: &str) -> String {
+    let ( bom, text ) = match original.strip_prefix('\u{feff}') {
+        Some( rest ) => ( "\u{feff}", rest ),
+        None => ( "", original )
+    };
+    let newline = match text.find(['\r', '\n']).map( |i| &text[i ..] ) {
+        Some( rest ) if rest.starts_with("\r\n") => "\r\n",
+        Some( rest ) if rest.starts_with('\r') => "\r",
+        _ => "\n"
+    };
+    // Position in text of every character position of source, '\r\n' counts as one character there.
+    let chars: Vec<char> = text.chars().collect();
+    let mut positions = Vec::with_capacity(chars.len() + 2);
+    let mut i = 0usize;
+    for _ in source.chars() {
+        positions.push(i.min(chars.len()) as u32);
+        i += match ( chars.get(i), chars.get(i + 1) ) {
+            ( Some( '\r' ), Some( '\n' ) ) => 2,
+            _ => 1
+        }
+    }
+    positions.push(i.min(chars.len()) as u32);
+    let edits: Vec<TextEdit> = diff_edits(source, changed, 0).into_iter()
+        .map( |e| TextEdit::new(positions[e.start as usize], positions[e.end as usize], &e.text.replace('\n', newline)) ).collect();
+    format!("{}{}", bom, apply_edits(text, &edits))
+}
+
+fn with_final_newline(source: &str) -> String {
+    let mut text = source.to_string();
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n')
+    }
+    text
+}
+
+
+// UnitTests for top level parse api ///////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ASTNode;
+    use crate::api::{ Mode, ExpectedToken, parse_module, parse_expression, parse_interactive, parse_func_type, tokenize, expected_tokens, normalize_source, restore_source, check, analyze };
+    use crate::diagnostics::{ Diagnostic, split_error_text };
+
+
+    #[test]
+    fn api_parse_module() {
+        let res = parse_module("x = 1\nprint(x)\n");
+        assert!(res.is_ok());
+        assert!(matches!(res.tree.as_deref(), Some( ASTNode::FileInput(..) )));
+        assert_eq!(res.tokens.len(), 10);
+    }
+
+    #[test]
+    fn api_parse_module_without_final_newline() {
+        let res = parse_module("pass");
+        assert!(res.is_ok());
+        assert_eq!(res.tree.unwrap().get_span(), ( 0, 5 ));
+    }
+
+    #[test]
+    fn api_parse_expression() {
+        let res = parse_expression("a + b\n");
+        assert!(res.is_ok());
+        assert!(matches!(res.tree.as_deref(), Some( ASTNode::EvalInput(..) )));
+    }
+
+    #[test]
+    fn api_parse_interactive() {
+        let res = parse_interactive("if a:\n    pass\n\n");
+        assert!(res.is_ok());
+        assert!(matches!(res.tree.as_deref(), Some( ASTNode::SingleInput(..) )));
+    }
+
+    #[test]
+    fn api_parse_func_type() {
+        let res = parse_func_type("(int, str) -> bool\n");
+        assert!(res.is_ok());
+        assert!(matches!(res.tree.as_deref(), Some( ASTNode::FuncTypeInput(..) )));
+    }
+
+    #[test]
+    fn api_parse_error_diagnostic() {
+        let res = parse_module("if a\n    pass\n");
+        assert!(!res.is_ok());
+        assert!(res.tree.is_none());
+        assert_eq!(res.diagnostics, vec![ Diagnostic::error(4, 5, "Expecting ':' in if statement!") ]);
+        assert_eq!(res.tokens.len(), 8);
+    }
+
+    #[test]
+    fn api_check() {
+        assert!(check("def f():\n    return 1\n", Mode::Exec).is_empty());
+        assert_eq!(check("return 1\n", Mode::Exec), vec![ Diagnostic::error(0, 8, "'return' outside function") ]);
+        assert_eq!(check("x = b'a' 'b'\n", Mode::Exec)[0].message, "cannot mix bytes and nonbytes literals");
+    }
+
+    #[test]
+    fn api_analyze() {
+        assert_eq!(analyze("def f():\n    x = 1\n", Mode::Exec), vec![ Diagnostic::warning(13, 14, "local variable 'x' is assigned to but never used") ]);
+        assert_eq!(analyze("return 1\n", Mode::Exec), vec![ Diagnostic::error(0, 8, "'return' outside function") ]);
+        assert!(analyze("def f(a):\n    return a\n", Mode::Exec).is_empty());
+    }
+
+    #[test]
+    fn api_tokenize() {
+        let ( tokens, error ) = tokenize("a.b");
+        assert_eq!(tokens.len(), 5);
+        assert!(error.is_none());
+    }
+
+    #[test]
+    fn api_expected_tokens() {
+        assert_eq!(expected_tokens("import "), vec![ ExpectedToken::Name ]);
+        assert_eq!(expected_tokens("def f("), vec![ ExpectedToken::Operator("*"), ExpectedToken::Operator("**"),
+            ExpectedToken::Operator("/"), ExpectedToken::Operator(")"), ExpectedToken::Name ]);
+        let expected = expected_tokens("x = 1");
+        assert!(expected.contains(&ExpectedToken::Keyword("if")) && expected.contains(&ExpectedToken::Newline));
+        assert!(!expected.contains(&ExpectedToken::Keyword("else")) && !expected.contains(&ExpectedToken::Number));
+        let expected = expected_tokens("if x:\n    pass\n");
+        assert!(expected.contains(&ExpectedToken::Keyword("else")) && expected.contains(&ExpectedToken::Keyword("def")));
+        assert!(expected.contains(&ExpectedToken::SoftKeyword("match")) && !expected.contains(&ExpectedToken::Keyword("except")));
+        assert_eq!(expected_tokens("class A"), vec![ ExpectedToken::Operator("("), ExpectedToken::Operator(":") ]);
+        assert!(expected_tokens("x = [1, ").contains(&ExpectedToken::Operator("]")));
+        let expected = expected_tokens("f(a, ");
+        assert!(expected.contains(&ExpectedToken::Operator(")")) && expected.contains(&ExpectedToken::Name));
+        assert!(!expected.contains(&ExpectedToken::Operator(",")) && !expected_tokens("x = [1, ").contains(&ExpectedToken::Operator(",")));
+        assert!(expected_tokens("x = ) + ").is_empty());
+    }
+
+    #[test]
+    fn api_normalize_source() {
+        assert_eq!(normalize_source("a\r\nb\rc"), "a\nb\nc\n");
+        assert_eq!(normalize_source(""), "");
+    }
+
+    #[test]
+    fn api_restore_source() {
+        let original = "\u{feff}a = 1\r\nif a == None:\r\n    b = 2";
+        let source = normalize_source(&original[3 ..]);
+        assert_eq!(restore_source(original, &source, &source.replace("==", "is")), "\u{feff}a = 1\r\nif a is None:\r\n    b = 2");
+        assert_eq!(restore_source(original, &source, "a = 1\n"), "\u{feff}a = 1\r\n");
+        assert_eq!(restore_source("x\ry\n", "x\ny\n", "x\nz\nw\ny\n"), "x\rz\rw\ry\n");
+        assert_eq!(restore_source("x", "x\n", "x\n"), "x");
+    }
+
+    #[test]
+    fn api_split_error_text() {
+        assert_eq!(split_error_text("SyntaxError at 12: bad"), ( Some( 12 ), "bad".to_string() ));
+        assert_eq!(split_error_text("bad"), ( None, "bad".to_string() ));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod nodes;
+pub mod location;
+pub mod lowering;
+pub mod dump;
+pub mod visitor;
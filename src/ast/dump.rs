@@ -0,0 +1,610 @@
+use crate::ast::nodes::{ Location, Mod, Stmt, StmtKind, Expr, ExprKind, Constant, ExprContext, BoolOperator, Operator,
+                         UnaryOperator, CmpOperator, Comprehension, ExceptHandler, MatchCase,
+                         Pattern, PatternKind, Arguments, Arg, Keyword, Alias, WithItem };
+
+
+/// Text output matching 'ast.dump(node, include_attributes=...)' of CPython 3.11. Fields are
+/// written in the order of the CPython ast classes, optional fields being None are left out.
+pub trait Dumper {
+    fn dump(&self, include_attributes: bool) -> String;
+}
+
+
+impl Dumper for Mod {
+    fn dump(&self, include_attributes: bool) -> String {
+        let ia = include_attributes;
+        match self {
+            Mod::Module { body, type_ignores } => {
+                let ignores = type_ignores.iter().map( |t| format!("TypeIgnore(lineno={}, tag={})", t.lineno, str_repr(&t.tag)) ).collect::<Vec<String>>();
+                node("Module", vec![ ( "body", Some( list(body, ia) ) ), ( "type_ignores", Some( format!("[{}]", ignores.join(", ")) ) ) ], None, ia)
+            },
+            Mod::Interactive { body } => node("Interactive", vec![ ( "body", Some( list(body, ia) ) ) ], None, ia),
+            Mod::Expression { body } => node("Expression", vec![ ( "body", Some( body.dump(ia) ) ) ], None, ia),
+            Mod::FunctionType { argtypes, returns } => {
+                node("FunctionType", vec![ ( "argtypes", Some( list(argtypes, ia) ) ), ( "returns", Some( returns.dump(ia) ) ) ], None, ia)
+            }
+        }
+    }
+}
+
+impl Dumper for Stmt {
+    fn dump(&self, include_attributes: bool) -> String {
+        let ia = include_attributes;
+        let loc = Some( &self.location );
+        match &self.node {
+            StmtKind::FunctionDef { name, args, body, decorator_list, returns, type_comment } |
+            StmtKind::AsyncFunctionDef { name, args, body, decorator_list, returns, type_comment } => {
+                let class = match &self.node { StmtKind::FunctionDef { .. } => "FunctionDef", _ => "AsyncFunctionDef" };
+                node(class, vec![
+                    ( "name", Some( str_repr(name) ) ),
+                    ( "args", Some( args.dump(ia) ) ),
+                    ( "body", Some( list(body, ia) ) ),
+                    ( "decorator_list", Some( list(decorator_list, ia) ) ),
+                    ( "returns", optional(returns, ia) ),
+                    ( "type_comment", type_comment.as_ref().map( |t| str_repr(t) ) )
+                ], loc, ia)
+            },
+            StmtKind::ClassDef { name, bases, keywords, body, decorator_list } => {
+                node("ClassDef", vec![
+                    ( "name", Some( str_repr(name) ) ),
+                    ( "bases", Some( list(bases, ia) ) ),
+                    ( "keywords", Some( list(keywords, ia) ) ),
+                    ( "body", Some( list(body, ia) ) ),
+                    ( "decorator_list", Some( list(decorator_list, ia) ) )
+                ], loc, ia)
+            },
+            StmtKind::Return { value } => node("Return", vec![ ( "value", optional(value, ia) ) ], loc, ia),
+            StmtKind::Delete { targets } => node("Delete", vec![ ( "targets", Some( list(targets, ia) ) ) ], loc, ia),
+            StmtKind::Assign { targets, value, type_comment } => {
+                node("Assign", vec![
+                    ( "targets", Some( list(targets, ia) ) ),
+                    ( "value", Some( value.dump(ia) ) ),
+                    ( "type_comment", type_comment.as_ref().map( |t| str_repr(t) ) )
+                ], loc, ia)
+            },
+            StmtKind::AugAssign { target, op, value } => {
+                node("AugAssign", vec![
+                    ( "target", Some( target.dump(ia) ) ),
+                    ( "op", Some( format!("{}()", operator_name(*op)) ) ),
+                    ( "value", Some( value.dump(ia) ) )
+                ], loc, ia)
+            },
+            StmtKind::AnnAssign { target, annotation, value, simple } => {
+                node("AnnAssign", vec![
+                    ( "target", Some( target.dump(ia) ) ),
+                    ( "annotation", Some( annotation.dump(ia) ) ),
+                    ( "value", optional(value, ia) ),
+                    ( "simple", Some( simple.to_string() ) )
+                ], loc, ia)
+            },
+            StmtKind::For { target, iter, body, orelse, type_comment } |
+            StmtKind::AsyncFor { target, iter, body, orelse, type_comment } => {
+                let class = match &self.node { StmtKind::For { .. } => "For", _ => "AsyncFor" };
+                node(class, vec![
+                    ( "target", Some( target.dump(ia) ) ),
+                    ( "iter", Some( iter.dump(ia) ) ),
+                    ( "body", Some( list(body, ia) ) ),
+                    ( "orelse", Some( list(orelse, ia) ) ),
+                    ( "type_comment", type_comment.as_ref().map( |t| str_repr(t) ) )
+                ], loc, ia)
+            },
+            StmtKind::While { test, body, orelse } |
+            StmtKind::If { test, body, orelse } => {
+                let class = match &self.node { StmtKind::While { .. } => "While", _ => "If" };
+                node(class, vec![
+                    ( "test", Some( test.dump(ia) ) ),
+                    ( "body", Some( list(body, ia) ) ),
+                    ( "orelse", Some( list(orelse, ia) ) )
+                ], loc, ia)
+            },
+            StmtKind::With { items, body, type_comment } |
+            StmtKind::AsyncWith { items, body, type_comment } => {
+                let class = match &self.node { StmtKind::With { .. } => "With", _ => "AsyncWith" };
+                node(class, vec![
+                    ( "items", Some( list(items, ia) ) ),
+                    ( "body", Some( list(body, ia) ) ),
+                    ( "type_comment", type_comment.as_ref().map( |t| str_repr(t) ) )
+                ], loc, ia)
+            },
+            StmtKind::Raise { exc, cause } => node("Raise", vec![ ( "exc", optional(exc, ia) ), ( "cause", optional(cause, ia) ) ], loc, ia),
+            StmtKind::Match { subject, cases } => {
+                node("Match", vec![ ( "subject", Some( subject.dump(ia) ) ), ( "cases", Some( list(cases, ia) ) ) ], loc, ia)
+            },
+            StmtKind::Try { body, handlers, orelse, finalbody } |
+            StmtKind::TryStar { body, handlers, orelse, finalbody } => {
+                let class = match &self.node { StmtKind::Try { .. } => "Try", _ => "TryStar" };
+                node(class, vec![
+                    ( "body", Some( list(body, ia) ) ),
+                    ( "handlers", Some( list(handlers, ia) ) ),
+                    ( "orelse", Some( list(orelse, ia) ) ),
+                    ( "finalbody", Some( list(finalbody, ia) ) )
+                ], loc, ia)
+            },
+            StmtKind::Assert { test, msg } => node("Assert", vec![ ( "test", Some( test.dump(ia) ) ), ( "msg", optional(msg, ia) ) ], loc, ia),
+            StmtKind::Import { names } => node("Import", vec![ ( "names", Some( list(names, ia) ) ) ], loc, ia),
+            StmtKind::ImportFrom { module, names, level } => {
+                node("ImportFrom", vec![
+                    ( "module", module.as_ref().map( |m| str_repr(m) ) ),
+                    ( "names", Some( list(names, ia) ) ),
+                    ( "level", Some( level.to_string() ) )
+                ], loc, ia)
+            },
+            StmtKind::Global { names } |
+            StmtKind::Nonlocal { names } => {
+                let class = match &self.node { StmtKind::Global { .. } => "Global", _ => "Nonlocal" };
+                let names = names.iter().map( |n| str_repr(n) ).collect::<Vec<String>>();
+                node(class, vec![ ( "names", Some( format!("[{}]", names.join(", ")) ) ) ], loc, ia)
+            },
+            StmtKind::Expr { value } => node("Expr", vec![ ( "value", Some( value.dump(ia) ) ) ], loc, ia),
+            StmtKind::Pass => node("Pass", Vec::new(), loc, ia),
+            StmtKind::Break => node("Break", Vec::new(), loc, ia),
+            StmtKind::Continue => node("Continue", Vec::new(), loc, ia)
+        }
+    }
+}
+
+impl Dumper for Expr {
+    fn dump(&self, include_attributes: bool) -> String {
+        let ia = include_attributes;
+        let loc = Some( &self.location );
+        match &self.node {
+            ExprKind::BoolOp { op, values } => {
+                let op = match op { BoolOperator::And => "And()", BoolOperator::Or => "Or()" };
+                node("BoolOp", vec![ ( "op", Some( op.to_string() ) ), ( "values", Some( list(values, ia) ) ) ], loc, ia)
+            },
+            ExprKind::NamedExpr { target, value } => {
+                node("NamedExpr", vec![ ( "target", Some( target.dump(ia) ) ), ( "value", Some( value.dump(ia) ) ) ], loc, ia)
+            },
+            ExprKind::BinOp { left, op, right } => {
+                node("BinOp", vec![
+                    ( "left", Some( left.dump(ia) ) ),
+                    ( "op", Some( format!("{}()", operator_name(*op)) ) ),
+                    ( "right", Some( right.dump(ia) ) )
+                ], loc, ia)
+            },
+            ExprKind::UnaryOp { op, operand } => {
+                let op = match op {
+                    UnaryOperator::Invert => "Invert()",
+                    UnaryOperator::Not => "Not()",
+                    UnaryOperator::UAdd => "UAdd()",
+                    UnaryOperator::USub => "USub()"
+                };
+                node("UnaryOp", vec![ ( "op", Some( op.to_string() ) ), ( "operand", Some( operand.dump(ia) ) ) ], loc, ia)
+            },
+            ExprKind::Lambda { args, body } => node("Lambda", vec![ ( "args", Some( args.dump(ia) ) ), ( "body", Some( body.dump(ia) ) ) ], loc, ia),
+            ExprKind::IfExp { test, body, orelse } => {
+                node("IfExp", vec![
+                    ( "test", Some( test.dump(ia) ) ),
+                    ( "body", Some( body.dump(ia) ) ),
+                    ( "orelse", Some( orelse.dump(ia) ) )
+                ], loc, ia)
+            },
+            ExprKind::Dict { keys, values } => node("Dict", vec![ ( "keys", Some( list(keys, ia) ) ), ( "values", Some( list(values, ia) ) ) ], loc, ia),
+            ExprKind::Set { elts } => node("Set", vec![ ( "elts", Some( list(elts, ia) ) ) ], loc, ia),
+            ExprKind::ListComp { elt, generators } |
+            ExprKind::SetComp { elt, generators } |
+            ExprKind::GeneratorExp { elt, generators } => {
+                let class = match &self.node {
+                    ExprKind::ListComp { .. } => "ListComp",
+                    ExprKind::SetComp { .. } => "SetComp",
+                    _ => "GeneratorExp"
+                };
+                node(class, vec![ ( "elt", Some( elt.dump(ia) ) ), ( "generators", Some( list(generators, ia) ) ) ], loc, ia)
+            },
+            ExprKind::DictComp { key, value, generators } => {
+                node("DictComp", vec![
+                    ( "key", Some( key.dump(ia) ) ),
+                    ( "value", Some( value.dump(ia) ) ),
+                    ( "generators", Some( list(generators, ia) ) )
+                ], loc, ia)
+            },
+            ExprKind::Await { value } => node("Await", vec![ ( "value", Some( value.dump(ia) ) ) ], loc, ia),
+            ExprKind::Yield { value } => node("Yield", vec![ ( "value", optional(value, ia) ) ], loc, ia),
+            ExprKind::YieldFrom { value } => node("YieldFrom", vec![ ( "value", Some( value.dump(ia) ) ) ], loc, ia),
+            ExprKind::Compare { left, ops, comparators } => {
+                let ops = ops.iter().map( |o| format!("{}()", cmp_operator_name(*o)) ).collect::<Vec<String>>();
+                node("Compare", vec![
+                    ( "left", Some( left.dump(ia) ) ),
+                    ( "ops", Some( format!("[{}]", ops.join(", ")) ) ),
+                    ( "comparators", Some( list(comparators, ia) ) )
+                ], loc, ia)
+            },
+            ExprKind::Call { func, args, keywords } => {
+                node("Call", vec![
+                    ( "func", Some( func.dump(ia) ) ),
+                    ( "args", Some( list(args, ia) ) ),
+                    ( "keywords", Some( list(keywords, ia) ) )
+                ], loc, ia)
+            },
+            ExprKind::FormattedValue { value, conversion, format_spec } => {
+                node("FormattedValue", vec![
+                    ( "value", Some( value.dump(ia) ) ),
+                    ( "conversion", Some( conversion.to_string() ) ),
+                    ( "format_spec", optional(format_spec, ia) )
+                ], loc, ia)
+            },
+            ExprKind::JoinedStr { values } => node("JoinedStr", vec![ ( "values", Some( list(values, ia) ) ) ], loc, ia),
+            ExprKind::Constant { value, kind } => {
+                node("Constant", vec![ ( "value", Some( constant_repr(value) ) ), ( "kind", kind.as_ref().map( |k| str_repr(k) ) ) ], loc, ia)
+            },
+            ExprKind::Attribute { value, attr, ctx } => {
+                node("Attribute", vec![
+                    ( "value", Some( value.dump(ia) ) ),
+                    ( "attr", Some( str_repr(attr) ) ),
+                    ( "ctx", Some( context_name(*ctx) ) )
+                ], loc, ia)
+            },
+            ExprKind::Subscript { value, slice, ctx } => {
+                node("Subscript", vec![
+                    ( "value", Some( value.dump(ia) ) ),
+                    ( "slice", Some( slice.dump(ia) ) ),
+                    ( "ctx", Some( context_name(*ctx) ) )
+                ], loc, ia)
+            },
+            ExprKind::Starred { value, ctx } => node("Starred", vec![ ( "value", Some( value.dump(ia) ) ), ( "ctx", Some( context_name(*ctx) ) ) ], loc, ia),
+            ExprKind::Name { id, ctx } => node("Name", vec![ ( "id", Some( str_repr(id) ) ), ( "ctx", Some( context_name(*ctx) ) ) ], loc, ia),
+            ExprKind::List { elts, ctx } |
+            ExprKind::Tuple { elts, ctx } => {
+                let class = match &self.node { ExprKind::List { .. } => "List", _ => "Tuple" };
+                node(class, vec![ ( "elts", Some( list(elts, ia) ) ), ( "ctx", Some( context_name(*ctx) ) ) ], loc, ia)
+            },
+            ExprKind::Slice { lower, upper, step } => {
+                node("Slice", vec![ ( "lower", optional(lower, ia) ), ( "upper", optional(upper, ia) ), ( "step", optional(step, ia) ) ], loc, ia)
+            }
+        }
+    }
+}
+
+impl Dumper for Option<Expr> {
+    fn dump(&self, include_attributes: bool) -> String {
+        match self {
+            Some( e ) => e.dump(include_attributes),
+            None => "None".to_string()
+        }
+    }
+}
+
+impl Dumper for Comprehension {
+    fn dump(&self, include_attributes: bool) -> String {
+        let ia = include_attributes;
+        node("comprehension", vec![
+            ( "target", Some( self.target.dump(ia) ) ),
+            ( "iter", Some( self.iter.dump(ia) ) ),
+            ( "ifs", Some( list(&self.ifs, ia) ) ),
+            ( "is_async", Some( self.is_async.to_string() ) )
+        ], None, ia)
+    }
+}
+
+impl Dumper for ExceptHandler {
+    fn dump(&self, include_attributes: bool) -> String {
+        let ia = include_attributes;
+        node("ExceptHandler", vec![
+            ( "type", optional(&self.type_, ia) ),
+            ( "name", self.name.as_ref().map( |n| str_repr(n) ) ),
+            ( "body", Some( list(&self.body, ia) ) )
+        ], Some( &self.location ), ia)
+    }
+}
+
+impl Dumper for MatchCase {
+    fn dump(&self, include_attributes: bool) -> String {
+        let ia = include_attributes;
+        node("match_case", vec![
+            ( "pattern", Some( self.pattern.dump(ia) ) ),
+            ( "guard", optional(&self.guard, ia) ),
+            ( "body", Some( list(&self.body, ia) ) )
+        ], None, ia)
+    }
+}
+
+impl Dumper for Pattern {
+    fn dump(&self, include_attributes: bool) -> String {
+        let ia = include_attributes;
+        let loc = Some( &self.location );
+        let names = |names: &[String]| format!("[{}]", names.iter().map( |n| str_repr(n) ).collect::<Vec<String>>().join(", "));
+        match &self.node {
+            PatternKind::MatchValue { value } => node("MatchValue", vec![ ( "value", Some( value.dump(ia) ) ) ], loc, ia),
+            PatternKind::MatchSingleton { value } => node("MatchSingleton", vec![ ( "value", Some( constant_repr(value) ) ) ], loc, ia),
+            PatternKind::MatchSequence { patterns } => node("MatchSequence", vec![ ( "patterns", Some( list(patterns, ia) ) ) ], loc, ia),
+            PatternKind::MatchMapping { keys, patterns, rest } => {
+                node("MatchMapping", vec![
+                    ( "keys", Some( list(keys, ia) ) ),
+                    ( "patterns", Some( list(patterns, ia) ) ),
+                    ( "rest", rest.as_ref().map( |r| str_repr(r) ) )
+                ], loc, ia)
+            },
+            PatternKind::MatchClass { cls, patterns, kwd_attrs, kwd_patterns } => {
+                node("MatchClass", vec![
+                    ( "cls", Some( cls.dump(ia) ) ),
+                    ( "patterns", Some( list(patterns, ia) ) ),
+                    ( "kwd_attrs", Some( names(kwd_attrs) ) ),
+                    ( "kwd_patterns", Some( list(kwd_patterns, ia) ) )
+                ], loc, ia)
+            },
+            PatternKind::MatchStar { name } => node("MatchStar", vec![ ( "name", name.as_ref().map( |n| str_repr(n) ) ) ], loc, ia),
+            PatternKind::MatchAs { pattern, name } => {
+                node("MatchAs", vec![ ( "pattern", optional(pattern, ia) ), ( "name", name.as_ref().map( |n| str_repr(n) ) ) ], loc, ia)
+            },
+            PatternKind::MatchOr { patterns } => node("MatchOr", vec![ ( "patterns", Some( list(patterns, ia) ) ) ], loc, ia)
+        }
+    }
+}
+
+impl Dumper for Arguments {
+    fn dump(&self, include_attributes: bool) -> String {
+        let ia = include_attributes;
+        node("arguments", vec![
+            ( "posonlyargs", Some( list(&self.posonlyargs, ia) ) ),
+            ( "args", Some( list(&self.args, ia) ) ),
+            ( "vararg", optional(&self.vararg, ia) ),
+            ( "kwonlyargs", Some( list(&self.kwonlyargs, ia) ) ),
+            ( "kw_defaults", Some( list(&self.kw_defaults, ia) ) ),
+            ( "kwarg", optional(&self.kwarg, ia) ),
+            ( "defaults", Some( list(&self.defaults, ia) ) )
+        ], None, ia)
+    }
+}
+
+impl Dumper for Arg {
+    fn dump(&self, include_attributes: bool) -> String {
+        let ia = include_attributes;
+        node("arg", vec![
+            ( "arg", Some( str_repr(&self.arg) ) ),
+            ( "annotation", optional(&self.annotation, ia) ),
+            ( "type_comment", self.type_comment.as_ref().map( |t| str_repr(t) ) )
+        ], Some( &self.location ), ia)
+    }
+}
+
+impl Dumper for Keyword {
+    fn dump(&self, include_attributes: bool) -> String {
+        let ia = include_attributes;
+        node("keyword", vec![
+            ( "arg", self.arg.as_ref().map( |a| str_repr(a) ) ),
+            ( "value", Some( self.value.dump(ia) ) )
+        ], Some( &self.location ), ia)
+    }
+}
+
+impl Dumper for Alias {
+    fn dump(&self, include_attributes: bool) -> String {
+        node("alias", vec![
+            ( "name", Some( str_repr(&self.name) ) ),
+            ( "asname", self.asname.as_ref().map( |a| str_repr(a) ) )
+        ], Some( &self.location ), include_attributes)
+    }
+}
+
+impl Dumper for WithItem {
+    fn dump(&self, include_attributes: bool) -> String {
+        let ia = include_attributes;
+        node("withitem", vec![
+            ( "context_expr", Some( self.context_expr.dump(ia) ) ),
+            ( "optional_vars", optional(&self.optional_vars, ia) )
+        ], None, ia)
+    }
+}
+
+
+fn node(class: &str, fields: Vec<( &str, Option<String> )>, location: Option<&Location>, include_attributes: bool) -> String {
+    let mut parts : Vec<String> = fields.into_iter().filter_map( |( name, value )| value.map( |v| format!("{}={}", name, v) ) ).collect();
+    if let ( Some( loc ), true ) = ( location, include_attributes ) {
+        parts.push( format!("lineno={}, col_offset={}, end_lineno={}, end_col_offset={}", loc.lineno, loc.col_offset, loc.end_lineno, loc.end_col_offset) )
+    }
+    format!("{}({})", class, parts.join(", "))
+}
+
+fn list<T: Dumper>(items: &[T], include_attributes: bool) -> String {
+    format!("[{}]", items.iter().map( |i| i.dump(include_attributes) ).collect::<Vec<String>>().join(", "))
+}
+
+fn optional<T: Dumper>(item: &Option<Box<T>>, include_attributes: bool) -> Option<String> {
+    item.as_ref().map( |i| i.dump(include_attributes) )
+}
+
+fn context_name(ctx: ExprContext) -> String {
+    match ctx {
+        ExprContext::Load => "Load()",
+        ExprContext::Store => "Store()",
+        ExprContext::Del => "Del()"
+    }.to_string()
+}
+
+fn operator_name(op: Operator) -> &'static str {
+    match op {
+        Operator::Add => "Add",
+        Operator::Sub => "Sub",
+        Operator::Mult => "Mult",
+        Operator::MatMult => "MatMult",
+        Operator::Div => "Div",
+        Operator::Mod => "Mod",
+        Operator::Pow => "Pow",
+        Operator::LShift => "LShift",
+        Operator::RShift => "RShift",
+        Operator::BitOr => "BitOr",
+        Operator::BitXor => "BitXor",
+        Operator::BitAnd => "BitAnd",
+        Operator::FloorDiv => "FloorDiv"
+    }
+}
+
+fn cmp_operator_name(op: CmpOperator) -> &'static str {
+    match op {
+        CmpOperator::Eq => "Eq",
+        CmpOperator::NotEq => "NotEq",
+        CmpOperator::Lt => "Lt",
+        CmpOperator::LtE => "LtE",
+        CmpOperator::Gt => "Gt",
+        CmpOperator::GtE => "GtE",
+        CmpOperator::Is => "Is",
+        CmpOperator::IsNot => "IsNot",
+        CmpOperator::In => "In",
+        CmpOperator::NotIn => "NotIn"
+    }
+}
+
+pub fn constant_repr(value: &Constant) -> String {
+    match value {
+        Constant::None => "None".to_string(),
+        Constant::Bool( true ) => "True".to_string(),
+        Constant::Bool( false ) => "False".to_string(),
+        Constant::Str( s ) => str_repr(s),
+        Constant::Bytes( b ) => bytes_repr(b),
+        Constant::Int( i ) => i.clone(),
+        Constant::Float( f ) => float_repr(*f),
+        Constant::Complex( re, im ) if *re == 0.0 => format!("{}j", float_repr(*im).trim_end_matches(".0")),
+        Constant::Complex( re, im ) => {
+            let imag = float_repr(*im);
+            let sign = match imag.starts_with('-') { true => "", _ => "+" };
+            format!("({}{}{}j)", float_repr(*re).trim_end_matches(".0"), sign, imag.trim_end_matches(".0"))
+        },
+        Constant::Tuple( t ) if t.len() == 1 => format!("({},)", constant_repr(&t[0])),
+        Constant::Tuple( t ) => format!("({})", t.iter().map(constant_repr).collect::<Vec<String>>().join(", ")),
+        Constant::Ellipsis => "Ellipsis".to_string()
+    }
+}
+
+/// Shortest round trip representation of float, the same as 'repr' of Python float.
+pub fn float_repr(value: f64) -> String {
+    if value.is_nan() {
+        return "nan".to_string()
+    }
+    if value.is_infinite() {
+        return match value > 0.0 { true => "inf", _ => "-inf" }.to_string()
+    }
+    let scientific = format!("{:e}", value.abs());
+    let ( mantissa, exponent ) = scientific.split_once('e').unwrap_or( ( &scientific, "0" ) );
+    let exponent : i32 = exponent.parse().unwrap_or(0);
+    let digits : String = mantissa.chars().filter( |c| *c != '.' ).collect();
+    let sign = match value.is_sign_negative() { true => "-", _ => "" };
+    if !(-4 .. 16).contains(&exponent) {
+        let mantissa = match digits.len() { 1 => digits.clone(), _ => format!("{}.{}", &digits[.. 1], &digits[1 ..]) };
+        let exp_sign = match exponent < 0 { true => "-", _ => "+" };
+        return format!("{}{}e{}{:02}", sign, mantissa, exp_sign, exponent.abs())
+    }
+    if exponent < 0 {
+        return format!("{}0.{}{}", sign, "0".repeat((-exponent - 1) as usize), digits)
+    }
+    let integer_length = exponent as usize + 1;
+    match digits.len() > integer_length {
+        true => format!("{}{}.{}", sign, &digits[.. integer_length], &digits[integer_length ..]),
+        _ => format!("{}{}{}.0", sign, digits, "0".repeat(integer_length - digits.len()))
+    }
+}
+
+fn is_printable(c: char) -> bool {
+    let v = c as u32;
+    if c == ' ' {
+        return true
+    }
+    !( c.is_control() || c.is_whitespace() ||
+       v == 0xad || (0x600 ..= 0x605).contains(&v) || v == 0x61c || v == 0x6dd || v == 0x70f ||
+       (0x200b ..= 0x200f).contains(&v) || (0x2028 ..= 0x202e).contains(&v) || (0x2060 ..= 0x206f).contains(&v) ||
+       v == 0xfeff || (0xfff9 ..= 0xfffb).contains(&v) || (0xe000 ..= 0xf8ff).contains(&v) || v >= 0xf0000 )
+}
+
+/// Representation of string the same as 'repr' of Python str.
+pub fn str_repr(value: &str) -> String {
+    let quote = match value.contains('\'') && !value.contains('"') { true => '"', _ => '\'' };
+    let mut res = String::new();
+    res.push(quote);
+    for c in value.chars() {
+        match c {
+            '\\' => res.push_str("\\\\"),
+            '\t' => res.push_str("\\t"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            _ if c == quote => {
+                res.push('\\');
+                res.push(c)
+            },
+            _ if is_printable(c) => res.push(c),
+            _ if (c as u32) < 0x100 => res.push_str( &format!("\\x{:02x}", c as u32) ),
+            _ if (c as u32) < 0x10000 => res.push_str( &format!("\\u{:04x}", c as u32) ),
+            _ => res.push_str( &format!("\\U{:08x}", c as u32) )
+        }
+    }
+    res.push(quote);
+    res
+}
+
+/// Representation of bytes the same as 'repr' of Python bytes.
+pub fn bytes_repr(value: &[u8]) -> String {
+    let quote = match value.contains(&b'\'') && !value.contains(&b'"') { true => b'"', _ => b'\'' };
+    let mut res = String::from("b");
+    res.push(quote as char);
+    for b in value {
+        match *b {
+            b'\\' => res.push_str("\\\\"),
+            b'\t' => res.push_str("\\t"),
+            b'\n' => res.push_str("\\n"),
+            b'\r' => res.push_str("\\r"),
+            c if c == quote => {
+                res.push('\\');
+                res.push(c as char)
+            },
+            c if !(0x20 .. 0x7f).contains(&c) => res.push_str( &format!("\\x{:02x}", c) ),
+            c => res.push(c as char)
+        }
+    }
+    res.push(quote as char);
+    res
+}
+
+
+// UnitTests for abstract tree dump ///////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::dump::{ Dumper, float_repr, str_repr, bytes_repr, constant_repr };
+    use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+    use crate::ast::nodes::Constant;
+
+
+    #[test]
+    fn dump_float_repr() {
+        assert_eq!(float_repr(1.0), "1.0");
+        assert_eq!(float_repr(0.1), "0.1");
+        assert_eq!(float_repr(100.0), "100.0");
+        assert_eq!(float_repr(1.5e-5), "1.5e-05");
+        assert_eq!(float_repr(1e16), "1e+16");
+        assert_eq!(float_repr(1.5e300), "1.5e+300");
+        assert_eq!(float_repr(0.0001), "0.0001");
+        assert_eq!(float_repr(f64::INFINITY), "inf");
+    }
+
+    #[test]
+    fn dump_complex_repr() {
+        assert_eq!(constant_repr(&Constant::Complex(0.0, 2.0)), "2j");
+        assert_eq!(constant_repr(&Constant::Complex(0.0, 2.5)), "2.5j");
+        assert_eq!(constant_repr(&Constant::Complex(1.0, -2.0)), "(1-2j)");
+    }
+
+    #[test]
+    fn dump_str_repr() {
+        assert_eq!(str_repr("a'b"), "\"a'b\"");
+        assert_eq!(str_repr("a'\"b"), "'a\\'\"b'");
+        assert_eq!(str_repr("\t\u{2028}\u{a0}\u{7f}é"), "'\\t\\u2028\\xa0\\x7fé'");
+    }
+
+    #[test]
+    fn dump_bytes_repr() {
+        assert_eq!(bytes_repr(b"x\x00\x80'"), "b\"x\\x00\\x80'\"");
+    }
+
+    #[test]
+    fn dump_leaves_out_none_fields() {
+        let dump = |source: &str| PythonCoreLowering::new(source).lower_mod(&crate::api::parse_module(source).tree.unwrap()).unwrap().dump(false);
+        assert_eq!(dump("def f(a): pass\nx = 1\nfor i in y: pass\nwith a: pass\n"), concat!(
+            "Module(body=[FunctionDef(name='f', args=arguments(posonlyargs=[], args=[arg(arg='a')], kwonlyargs=[], kw_defaults=[], defaults=[]), ",
+            "body=[Pass()], decorator_list=[]), Assign(targets=[Name(id='x', ctx=Store())], value=Constant(value=1)), ",
+            "For(target=Name(id='i', ctx=Store()), iter=Name(id='y', ctx=Load()), body=[Pass()], orelse=[]), ",
+            "With(items=[withitem(context_expr=Name(id='a', ctx=Load()))], body=[Pass()])], type_ignores=[])"));
+        assert_eq!(dump("x = 1  # type: int\n"),
+            "Module(body=[Assign(targets=[Name(id='x', ctx=Store())], value=Constant(value=1), type_comment='int')], type_ignores=[])");
+        assert_eq!(dump("def f(a=None, *, b): return\n"), concat!(
+            "Module(body=[FunctionDef(name='f', args=arguments(posonlyargs=[], args=[arg(arg='a')], kwonlyargs=[arg(arg='b')], ",
+            "kw_defaults=[None], defaults=[Constant(value=None)]), body=[Return()], decorator_list=[])], type_ignores=[])"));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::ast::nodes::Location;
+
+
+/// Maps character offsets used by tokenizer and parser into line numbers and UTF-8 byte columns.
+pub struct LineIndex {
+    line_starts: Vec<u32>,
+    byte_offsets: Vec<u32>
+}
+
+
+impl LineIndex {
+    pub fn new(source: &str) -> LineIndex {
+        let chars : Vec<char> = source.chars().collect();
+        let mut line_starts : Vec<u32> = vec![ 0 ];
+        let mut byte_offsets : Vec<u32> = Vec::with_capacity(chars.len() + 1);
+        let mut bytes = 0u32;
+        for (i, c) in chars.iter().enumerate() {
+            byte_offsets.push(bytes);
+            bytes += c.len_utf8() as u32;
+            match c {
+                '\n' => line_starts.push(i as u32 + 1),
+                '\r' if chars.get(i + 1) != Some(&'\n') => line_starts.push(i as u32 + 1),
+                _ => {}
+            }
+        }
+        byte_offsets.push(bytes);
+        LineIndex { line_starts, byte_offsets }
+    }
+
+    /// Returns line number counted from 1 and UTF-8 byte column for character offset.
+    pub fn line_col(&self, pos: u32) -> ( u32, u32 ) {
+        let pos = pos.min( self.byte_offsets.len() as u32 - 1 );
+        let line = match self.line_starts.binary_search(&pos) {
+            Ok(n) => n,
+            Err(n) => n - 1
+        };
+        let line_start = self.line_starts[line] as usize;
+        ( line as u32 + 1, self.byte_offsets[pos as usize] - self.byte_offsets[line_start] )
+    }
+
+    /// Returns line number counted from 1 and column counted in characters for character offset.
+    pub fn line_char_col(&self, pos: u32) -> ( u32, u32 ) {
+        let pos = pos.min( self.byte_offsets.len() as u32 - 1 );
+        let line = match self.line_starts.binary_search(&pos) {
+            Ok(n) => n,
+            Err(n) => n - 1
+        };
+        ( line as u32 + 1, pos - self.line_starts[line] )
+    }
+
+    pub fn location(&self, start: u32, end: u32) -> Location {
+        let ( lineno, col_offset ) = self.line_col(start);
+        let ( end_lineno, end_col_offset ) = self.line_col(end);
+        Location { start, end, lineno, col_offset, end_lineno, end_col_offset }
+    }
+}
+
+
+// UnitTests for line index ///////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::location::LineIndex;
+
+    #[test]
+    fn line_index_single_line() {
+        let index = LineIndex::new("abc = 1\n");
+        assert_eq!(index.line_col(0), (1, 0));
+        assert_eq!(index.line_col(6), (1, 6));
+        assert_eq!(index.line_col(8), (2, 0));
+    }
+
+    #[test]
+    fn line_index_mixed_line_endings() {
+        let index = LineIndex::new("a\r\nb\rc\nd");
+        assert_eq!(index.line_col(3), (2, 0));
+        assert_eq!(index.line_col(5), (3, 0));
+        assert_eq!(index.line_col(7), (4, 0));
+    }
+
+    #[test]
+    fn line_index_utf8_columns() {
+        let index = LineIndex::new("s = 'åé' + x\n");
+        assert_eq!(index.line_col(11), (1, 13));
+        assert_eq!(index.line_char_col(11), (1, 11));
+    }
+}
@@ -0,0 +1,1640 @@
+use std::rc::Rc;
+use crate::{ ASTNode, Token, Parser, PythonCoreParser, Tokenizer, Expressions };
+use crate::parser::tokenizer::PythonCoreTokenizer;
+use crate::ast::location::LineIndex;
+use crate::ast::nodes::{ Location, Mod, TypeIgnore, Stmt, StmtKind, Expr, ExprKind, Constant, ExprContext, BoolOperator, Operator,
+                         UnaryOperator, CmpOperator, Comprehension, ExceptHandler, Arguments, Arg, Keyword, Alias, WithItem };
+
+
+/// Lowers the concrete syntax tree from parser into abstract tree shaped after CPython 'ast' module.
+/// Positions are calculated from a table of token spans, so that trailing trivia never counts
+/// as part of a node.
+pub struct PythonCoreLowering {
+    line_index: Rc<LineIndex>,
+    tokens: Vec<( u32, u32 )>,
+    type_ignores: Vec<( u32, String )>,
+    offset: u32
+}
+
+pub trait Lowering {
+    fn new(source: &str) -> Self;
+    fn lower_mod(&self, node: &ASTNode) -> Result<Mod, String>;
+    fn lower_stmt(&self, node: &ASTNode) -> Result<Vec<Stmt>, String>;
+    fn lower_expr(&self, node: &ASTNode) -> Result<Expr, String>;
+}
+
+/// Part of a string literal concatenation before adjacent literal text is merged.
+enum StringPart {
+    Literal(String),
+    Value(Expr)
+}
+
+/// One string token split into prefix flags and the characters between the quotes.
+struct StringPiece {
+    start: u32,
+    end: u32,
+    body_start: u32,
+    body: Vec<char>,
+    is_raw: bool,
+    is_bytes: bool,
+    is_format: bool,
+    is_unicode: bool
+}
+
+
+impl Lowering for PythonCoreLowering {
+    fn new(source: &str) -> PythonCoreLowering {
+        let ( tokens, type_ignores ) = scan_tokens(source);
+        PythonCoreLowering {
+            line_index: Rc::new( LineIndex::new(source) ),
+            tokens,
+            type_ignores,
+            offset: 0
+        }
+    }
+
+    fn lower_mod(&self, node: &ASTNode) -> Result<Mod, String> {
+        match node {
+            ASTNode::FileInput(_, _, nodes, _, _) => {
+                let mut body = Vec::new();
+                for n in sorted_nodes(nodes) {
+                    body.extend( self.lower_stmt(n)? )
+                }
+                let type_ignores = self.type_ignores.iter().map( |( pos, tag )| {
+                    TypeIgnore { lineno: self.line_index.line_col(*pos).0, tag: tag.clone() }
+                } ).collect();
+                Ok( Mod::Module { body, type_ignores } )
+            },
+            ASTNode::SingleInput(_, _, right, _) => {
+                let body = match right {
+                    Some( n ) => self.lower_stmt(n)?,
+                    None => Vec::new()
+                };
+                Ok( Mod::Interactive { body } )
+            },
+            ASTNode::EvalInput(_, _, right, _, _) => {
+                Ok( Mod::Expression { body: Box::new( self.lower_expr(right)? ) } )
+            },
+            ASTNode::FuncTypeInput(_, _, right, _, _) => {
+                match &**right {
+                    ASTNode::FuncType(_, _, _, types, _, _, returns) => {
+                        let mut argtypes = Vec::new();
+                        if let Some( t ) = types {
+                            if let ASTNode::TypeList(_, _, nodes, _, _, mul, _, power) = &**t {
+                                for n in sorted_nodes(nodes) {
+                                    argtypes.push( self.lower_expr(n)? )
+                                }
+                                if let Some( m ) = mul {
+                                    argtypes.push( self.lower_expr(m)? )
+                                }
+                                if let Some( p ) = power {
+                                    argtypes.push( self.lower_expr(p)? )
+                                }
+                            }
+                        }
+                        Ok( Mod::FunctionType { argtypes, returns: Box::new( self.lower_expr(returns)? ) } )
+                    },
+                    _ => Err(format!("SyntaxError at {}: Expecting function type in lowering!", right.get_span().0))
+                }
+            },
+            _ => Err(format!("SyntaxError at {}: Expecting file, single, eval or function type input in lowering!", node.get_span().0))
+        }
+    }
+
+    fn lower_stmt(&self, node: &ASTNode) -> Result<Vec<Stmt>, String> {
+        match node {
+            ASTNode::SimpleStmtList(_, _, nodes, _, _) => {
+                let mut res = Vec::new();
+                for n in sorted_nodes(nodes) {
+                    res.push( self.lower_small_stmt(n)? )
+                }
+                Ok(res)
+            },
+            ASTNode::IfStmt(..) |
+            ASTNode::WhileStmt(..) |
+            ASTNode::ForStmt(..) |
+            ASTNode::TryStmt(..) |
+            ASTNode::WithStmt(..) |
+            ASTNode::FuncDef(..) |
+            ASTNode::ClassDef(..) |
+            ASTNode::Decorated(..) |
+            ASTNode::AsyncStmt(..) => Ok( vec![ self.lower_compound_stmt(node)? ] ),
+            _ => Ok( vec![ self.lower_small_stmt(node)? ] )
+        }
+    }
+
+    fn lower_expr(&self, node: &ASTNode) -> Result<Expr, String> {
+        let location = self.node_location(node);
+        let kind = match node {
+            ASTNode::NamedExpr(_, _, left, _, right) => {
+                ExprKind::NamedExpr {
+                    target: Box::new( set_context(self.lower_expr(left)?, ExprContext::Store) ),
+                    value: Box::new( self.lower_expr(right)? )
+                }
+            },
+            ASTNode::Test(_, _, body, _, test, _, orelse) => {
+                ExprKind::IfExp {
+                    test: Box::new( self.lower_expr(test)? ),
+                    body: Box::new( self.lower_expr(body)? ),
+                    orelse: Box::new( self.lower_expr(orelse)? )
+                }
+            },
+            ASTNode::Lambda(_, _, _, args, _, body) => {
+                ExprKind::Lambda {
+                    args: Box::new( self.lower_parameters(args.as_deref())? ),
+                    body: Box::new( self.lower_expr(body)? )
+                }
+            },
+            ASTNode::OrTest(..) |
+            ASTNode::AndTest(..) => {
+                let op = match node { ASTNode::OrTest(..) => BoolOperator::Or, _ => BoolOperator::And };
+                let mut operands : Vec<&ASTNode> = Vec::new();
+                let mut current = node;
+                loop {
+                    match ( op, current ) {
+                        ( BoolOperator::Or, ASTNode::OrTest(_, _, left, _, right) ) |
+                        ( BoolOperator::And, ASTNode::AndTest(_, _, left, _, right) ) => {
+                            operands.push(right);
+                            current = left
+                        },
+                        _ => {
+                            operands.push(current);
+                            break
+                        }
+                    }
+                }
+                operands.reverse();
+                let mut values = Vec::new();
+                for n in operands {
+                    values.push( self.lower_expr(n)? )
+                }
+                ExprKind::BoolOp { op, values }
+            },
+            ASTNode::NotTest(_, _, _, right) => {
+                ExprKind::UnaryOp { op: UnaryOperator::Not, operand: Box::new( self.lower_expr(right)? ) }
+            },
+            ASTNode::LessComparison(..) |
+            ASTNode::LessEqualComparison(..) |
+            ASTNode::EqualComparison(..) |
+            ASTNode::GreaterComparison(..) |
+            ASTNode::GreaterEqualComparison(..) |
+            ASTNode::NotEqualComparison(..) |
+            ASTNode::InComparison(..) |
+            ASTNode::IsComparison(..) |
+            ASTNode::NotInComparison(..) |
+            ASTNode::IsNotComparison(..) => {
+                let mut chain : Vec<( CmpOperator, &ASTNode )> = Vec::new();
+                let mut current = node;
+                while let Some( ( left, op, right ) ) = comparison_parts(current) {
+                    chain.push( ( op, right ) );
+                    current = left
+                }
+                chain.reverse();
+                let mut ops = Vec::new();
+                let mut comparators = Vec::new();
+                for ( op, n ) in chain {
+                    ops.push(op);
+                    comparators.push( self.lower_expr(n)? )
+                }
+                ExprKind::Compare { left: Box::new( self.lower_expr(current)? ), ops, comparators }
+            },
+            ASTNode::StarExpr(_, _, _, right) => {
+                ExprKind::Starred { value: Box::new( self.lower_expr(right)? ), ctx: ExprContext::Load }
+            },
+            ASTNode::Expr(_, _, left, _, right) => self.lower_binary(left, Operator::BitOr, right)?,
+            ASTNode::XorExpr(_, _, left, _, right) => self.lower_binary(left, Operator::BitXor, right)?,
+            ASTNode::AndExpr(_, _, left, _, right) => self.lower_binary(left, Operator::BitAnd, right)?,
+            ASTNode::ShiftLeftExpr(_, _, left, _, right) => self.lower_binary(left, Operator::LShift, right)?,
+            ASTNode::ShiftRightExpr(_, _, left, _, right) => self.lower_binary(left, Operator::RShift, right)?,
+            ASTNode::PlusArithExpr(_, _, left, _, right) => self.lower_binary(left, Operator::Add, right)?,
+            ASTNode::MinusArithExpr(_, _, left, _, right) => self.lower_binary(left, Operator::Sub, right)?,
+            ASTNode::MulTerm(_, _, left, _, right) => self.lower_binary(left, Operator::Mult, right)?,
+            ASTNode::DivTerm(_, _, left, _, right) => self.lower_binary(left, Operator::Div, right)?,
+            ASTNode::FloorDivTerm(_, _, left, _, right) => self.lower_binary(left, Operator::FloorDiv, right)?,
+            ASTNode::ModuloTerm(_, _, left, _, right) => self.lower_binary(left, Operator::Mod, right)?,
+            ASTNode::MatriceTerm(_, _, left, _, right) => self.lower_binary(left, Operator::MatMult, right)?,
+            ASTNode::PowerExpr(_, _, left, _, right) => self.lower_binary(left, Operator::Pow, right)?,
+            ASTNode::UnaryPlus(_, _, _, right) => {
+                ExprKind::UnaryOp { op: UnaryOperator::UAdd, operand: Box::new( self.lower_expr(right)? ) }
+            },
+            ASTNode::UnaryMinus(_, _, _, right) => {
+                ExprKind::UnaryOp { op: UnaryOperator::USub, operand: Box::new( self.lower_expr(right)? ) }
+            },
+            ASTNode::UnaryInvert(_, _, _, right) => {
+                ExprKind::UnaryOp { op: UnaryOperator::Invert, operand: Box::new( self.lower_expr(right)? ) }
+            },
+            ASTNode::AtomExpr(_, _, await_symbol, atom, trailers) => {
+                let start = atom.get_span().0;
+                let mut current = self.lower_expr(atom)?;
+                for trailer in trailers.iter() {
+                    let end = self.node_end(trailer);
+                    let value = Box::new( current );
+                    let kind = match &**trailer {
+                        ASTNode::CallTrailer(trailer_start, _, _, args, _) => {
+                            let ( args, keywords ) = self.lower_call_arguments(args.as_deref(), ( *trailer_start, end ))?;
+                            ExprKind::Call { func: value, args, keywords }
+                        },
+                        ASTNode::IndexTrailer(_, _, _, slice, _) => {
+                            ExprKind::Subscript { value, slice: Box::new( self.lower_slice(slice)? ), ctx: ExprContext::Load }
+                        },
+                        ASTNode::DotNameTrailer(_, _, _, name) => {
+                            ExprKind::Attribute { value, attr: name_text(name), ctx: ExprContext::Load }
+                        },
+                        _ => return Err(format!("SyntaxError at {}: Expecting trailer in lowering!", trailer.get_span().0))
+                    };
+                    current = Expr { node: kind, location: self.location(start, end) }
+                }
+                match await_symbol {
+                    Some( _ ) => ExprKind::Await { value: Box::new( current ) },
+                    None => return Ok(current)
+                }
+            },
+            ASTNode::AtomName(_, _, name) => {
+                ExprKind::Name { id: name_text(name), ctx: ExprContext::Load }
+            },
+            ASTNode::AtomNumber(start, _, number) => {
+                match &**number {
+                    Token::AtomNumber(_, _, _, text) => ExprKind::Constant { value: lower_number(text, *start + self.offset)?, kind: None },
+                    _ => return Err(format!("SyntaxError at {}: Expecting number literal in lowering!", start + self.offset))
+                }
+            },
+            ASTNode::AtomString(_, _, tokens) => self.lower_string(node, tokens)?,
+            ASTNode::AtomElipsis(..) => ExprKind::Constant { value: Constant::Ellipsis, kind: None },
+            ASTNode::AtomNone(..) => ExprKind::Constant { value: Constant::None, kind: None },
+            ASTNode::AtomTrue(..) => ExprKind::Constant { value: Constant::Bool(true), kind: None },
+            ASTNode::AtomFalse(..) => ExprKind::Constant { value: Constant::Bool(false), kind: None },
+            ASTNode::AtomTuple(_, _, _, right, _) => {
+                match right.as_deref() {
+                    None => ExprKind::Tuple { elts: Vec::new(), ctx: ExprContext::Load },
+                    Some( ASTNode::TestListComp(_, _, nodes, separators) ) => {
+                        let ( elements, comp ) = split_comprehension(nodes);
+                        match comp {
+                            Some( c ) => {
+                                ExprKind::GeneratorExp {
+                                    elt: Box::new( self.lower_expr(elements[0])? ),
+                                    generators: self.lower_comprehension(c)?
+                                }
+                            },
+                            None if elements.len() == 1 && separators.is_empty() => return self.lower_expr(elements[0]),
+                            None => ExprKind::Tuple { elts: self.lower_exprs(&elements)?, ctx: ExprContext::Load }
+                        }
+                    },
+                    Some( n ) => return self.lower_expr(n)
+                }
+            },
+            ASTNode::AtomList(_, _, _, right, _) => {
+                match right.as_deref() {
+                    Some( ASTNode::TestListComp(_, _, nodes, _) ) => {
+                        let ( elements, comp ) = split_comprehension(nodes);
+                        match comp {
+                            Some( c ) => {
+                                ExprKind::ListComp {
+                                    elt: Box::new( self.lower_expr(elements[0])? ),
+                                    generators: self.lower_comprehension(c)?
+                                }
+                            },
+                            None => ExprKind::List { elts: self.lower_exprs(&elements)?, ctx: ExprContext::Load }
+                        }
+                    },
+                    _ => ExprKind::List { elts: Vec::new(), ctx: ExprContext::Load }
+                }
+            },
+            ASTNode::AtomDictionary(_, _, _, right, _) => {
+                match right.as_deref() {
+                    Some( ASTNode::DictionaryContainer(_, _, nodes, _) ) => {
+                        let ( elements, comp ) = split_comprehension(nodes);
+                        match ( comp, elements.first() ) {
+                            ( Some( c ), Some( ASTNode::DictionaryEntry(_, _, key, _, value) ) ) => {
+                                ExprKind::DictComp {
+                                    key: Box::new( self.lower_expr(key)? ),
+                                    value: Box::new( self.lower_expr(value)? ),
+                                    generators: self.lower_comprehension(c)?
+                                }
+                            },
+                            ( Some( c ), _ ) => {
+                                return Err(format!("SyntaxError at {}: dict unpacking cannot be used in dict comprehension", c.get_span().0 + self.offset))
+                            },
+                            _ => {
+                                let mut keys = Vec::new();
+                                let mut values = Vec::new();
+                                for n in elements {
+                                    match n {
+                                        ASTNode::DictionaryEntry(_, _, key, _, value) => {
+                                            keys.push( Some( self.lower_expr(key)? ) );
+                                            values.push( self.lower_expr(value)? )
+                                        },
+                                        ASTNode::PowerDictionary(_, _, _, value) => {
+                                            keys.push( None );
+                                            values.push( self.lower_expr(value)? )
+                                        },
+                                        _ => return Err(format!("SyntaxError at {}: Expecting dictionary entry in lowering!", n.get_span().0 + self.offset))
+                                    }
+                                }
+                                ExprKind::Dict { keys, values }
+                            }
+                        }
+                    },
+                    _ => ExprKind::Dict { keys: Vec::new(), values: Vec::new() }
+                }
+            },
+            ASTNode::AtomSet(_, _, _, right, _) => {
+                match right.as_deref() {
+                    Some( ASTNode::SetContainer(_, _, nodes, _) ) => {
+                        let ( elements, comp ) = split_comprehension(nodes);
+                        match comp {
+                            Some( c ) => {
+                                ExprKind::SetComp {
+                                    elt: Box::new( self.lower_expr(elements[0])? ),
+                                    generators: self.lower_comprehension(c)?
+                                }
+                            },
+                            None => ExprKind::Set { elts: self.lower_exprs(&elements)? }
+                        }
+                    },
+                    _ => return Err(format!("SyntaxError at {}: Expecting set elements in lowering!", location.start))
+                }
+            },
+            ASTNode::MulSet(_, _, _, right) => {
+                ExprKind::Starred { value: Box::new( self.lower_expr(right)? ), ctx: ExprContext::Load }
+            },
+            ASTNode::TestListStarExpr(_, _, nodes, _) |
+            ASTNode::TestList(_, _, nodes, _) |
+            ASTNode::ExprList(_, _, nodes, _) => {
+                ExprKind::Tuple { elts: self.lower_exprs(&sorted_nodes(nodes))?, ctx: ExprContext::Load }
+            },
+            ASTNode::YieldExpr(_, _, _, right) => {
+                ExprKind::Yield { value: match right { Some( n ) => Some( Box::new( self.lower_expr(n)? ) ), None => None } }
+            },
+            ASTNode::YieldFromExpr(_, _, _, _, right) => {
+                ExprKind::YieldFrom { value: Box::new( self.lower_expr(right)? ) }
+            },
+            _ => return Err(format!("SyntaxError at {}: Unexpected node in lowering of expression!", location.start))
+        };
+        Ok( Expr { node: kind, location } )
+    }
+}
+
+
+impl PythonCoreLowering {
+    /// Returns end of last token inside node, or end field of node when it holds no tokens.
+    fn node_end(&self, node: &ASTNode) -> u32 {
+        let ( start, end ) = node.get_span();
+        let index = self.tokens.partition_point( |t| t.0 < end );
+        match index {
+            0 => end,
+            _ => self.tokens[index - 1].1.max(start)
+        }
+    }
+
+    fn location(&self, start: u32, end: u32) -> Location {
+        self.line_index.location(start + self.offset, end + self.offset)
+    }
+
+    fn node_location(&self, node: &ASTNode) -> Location {
+        self.location(node.get_span().0, self.node_end(node))
+    }
+
+    fn token_location(&self, token: &Token) -> Location {
+        let ( start, end ) = token.get_span().unwrap_or( ( 0, 0 ) );
+        self.location(start, end)
+    }
+
+    fn lower_exprs(&self, nodes: &[&ASTNode]) -> Result<Vec<Expr>, String> {
+        let mut res = Vec::new();
+        for n in nodes {
+            res.push( self.lower_expr(n)? )
+        }
+        Ok(res)
+    }
+
+    fn lower_optional(&self, node: Option<&ASTNode>) -> Result<Option<Box<Expr>>, String> {
+        match node {
+            Some( n ) => Ok( Some( Box::new( self.lower_expr(n)? ) ) ),
+            None => Ok( None )
+        }
+    }
+
+    fn lower_binary(&self, left: &ASTNode, op: Operator, right: &ASTNode) -> Result<ExprKind, String> {
+        Ok( ExprKind::BinOp { left: Box::new( self.lower_expr(left)? ), op, right: Box::new( self.lower_expr(right)? ) } )
+    }
+
+    fn lower_target(&self, node: &ASTNode, ctx: ExprContext) -> Result<Expr, String> {
+        Ok( set_context(self.lower_expr(node)?, ctx) )
+    }
+
+    fn lower_suite(&self, node: &ASTNode) -> Result<Vec<Stmt>, String> {
+        match node {
+            ASTNode::SuiteStmt(_, _, _, _, nodes, _) |
+            ASTNode::FuncBodySuite(_, _, _, _, _, _, nodes, _) => {
+                let mut res = Vec::new();
+                for n in sorted_nodes(nodes) {
+                    res.extend( self.lower_stmt(n)? )
+                }
+                Ok(res)
+            },
+            _ => self.lower_stmt(node)
+        }
+    }
+
+    fn lower_else(&self, node: Option<&ASTNode>) -> Result<Vec<Stmt>, String> {
+        match node {
+            Some( ASTNode::ElseStmt(_, _, _, _, suite) ) |
+            Some( ASTNode::FinallyStmt(_, _, _, _, suite) ) => self.lower_suite(suite),
+            Some( n ) => Err(format!("SyntaxError at {}: Expecting 'else' or 'finally' block in lowering!", n.get_span().0 + self.offset)),
+            None => Ok( Vec::new() )
+        }
+    }
+
+    fn lower_small_stmt(&self, node: &ASTNode) -> Result<Stmt, String> {
+        let location = self.node_location(node);
+        let kind = match node {
+            ASTNode::PassStmt(..) => StmtKind::Pass,
+            ASTNode::BreakStmt(..) => StmtKind::Break,
+            ASTNode::ContinueStmt(..) => StmtKind::Continue,
+            ASTNode::ReturnStmt(_, _, _, right) => StmtKind::Return { value: self.lower_optional(right.as_deref())? },
+            ASTNode::RaiseStmt(_, _, _, right) => {
+                match right {
+                    Some( ( exc, cause ) ) => {
+                        StmtKind::Raise {
+                            exc: Some( Box::new( self.lower_expr(exc)? ) ),
+                            cause: match cause { Some( ( _, c ) ) => Some( Box::new( self.lower_expr(c)? ) ), None => None }
+                        }
+                    },
+                    None => StmtKind::Raise { exc: None, cause: None }
+                }
+            },
+            ASTNode::DelStmt(_, _, _, right) => {
+                let targets = match &**right {
+                    ASTNode::ExprList(_, _, nodes, _) => {
+                        let mut res = Vec::new();
+                        for n in sorted_nodes(nodes) {
+                            res.push( self.lower_target(n, ExprContext::Del)? )
+                        }
+                        res
+                    },
+                    _ => vec![ self.lower_target(right, ExprContext::Del)? ]
+                };
+                StmtKind::Delete { targets }
+            },
+            ASTNode::ImportNameStmt(_, _, _, right) => {
+                let mut names = Vec::new();
+                match &**right {
+                    ASTNode::DottedAsNamesStmt(_, _, nodes, _) => {
+                        for n in sorted_nodes(nodes) {
+                            names.push( self.lower_dotted_as_name(n)? )
+                        }
+                    },
+                    _ => names.push( self.lower_dotted_as_name(right)? )
+                }
+                StmtKind::Import { names }
+            },
+            ASTNode::ImportFromStmt(_, _, _, dots, module, _, symbol, right, _) => {
+                let level = dots.iter().map( |d| match &**d { Token::PyElipsis(..) => 3, _ => 1 } ).sum();
+                let module = module.as_deref().map( dotted_name_text );
+                let mut names = Vec::new();
+                match ( symbol.as_deref(), right ) {
+                    ( Some( star @ Token::PyMul(..) ), _ ) => {
+                        names.push( Alias { name: "*".to_string(), asname: None, location: self.token_location(star) } )
+                    },
+                    ( _, Some( r ) ) => {
+                        match &**r {
+                            ASTNode::ImportAsNamesStmt(_, _, nodes, _) => {
+                                for n in sorted_nodes(nodes) {
+                                    names.push( self.lower_import_as_name(n)? )
+                                }
+                            },
+                            _ => names.push( self.lower_import_as_name(r)? )
+                        }
+                    },
+                    _ => return Err(format!("SyntaxError at {}: Expecting names in 'from' import statement lowering!", location.start))
+                }
+                StmtKind::ImportFrom { module, names, level }
+            },
+            ASTNode::GlobalStmt(_, _, _, names, _) => StmtKind::Global { names: sorted_tokens(names).iter().map( |t| name_text(t) ).collect() },
+            ASTNode::NonLocalStmt(_, _, _, names, _) => StmtKind::Nonlocal { names: sorted_tokens(names).iter().map( |t| name_text(t) ).collect() },
+            ASTNode::AssertStmt(_, _, _, test, msg) => {
+                StmtKind::Assert {
+                    test: Box::new( self.lower_expr(test)? ),
+                    msg: match msg { Some( ( _, m ) ) => Some( Box::new( self.lower_expr(m)? ) ), None => None }
+                }
+            },
+            ASTNode::AssignmentStmt(_, _, left, right, type_comment) => {
+                let mut targets = vec![ self.lower_target(left, ExprContext::Store)? ];
+                for ( i, pair ) in right.iter().enumerate() {
+                    if i + 1 < right.len() {
+                        targets.push( self.lower_target(&pair.1, ExprContext::Store)? )
+                    }
+                }
+                let value = match right.last() {
+                    Some( pair ) => self.lower_expr(&pair.1)?,
+                    None => return Err(format!("SyntaxError at {}: Expecting value in assignment statement lowering!", location.start))
+                };
+                if let ( Some( text ), Some( ( _, end ) ) ) = ( type_comment_text(type_comment.as_deref()), type_comment.as_ref().and_then( |t| t.get_span() ) ) {
+                    return Ok( Stmt {
+                        node: StmtKind::Assign { targets, value: Box::new( value ), type_comment: Some( text ) },
+                        location: self.location(node.get_span().0, end)
+                    } )
+                }
+                StmtKind::Assign { targets, value: Box::new( value ), type_comment: None }
+            },
+            ASTNode::AnnAssignStmt(_, _, left, _, annotation, right) => {
+                StmtKind::AnnAssign {
+                    target: Box::new( self.lower_target(left, ExprContext::Store)? ),
+                    annotation: Box::new( self.lower_expr(annotation)? ),
+                    value: match right { Some( ( _, r ) ) => Some( Box::new( self.lower_expr(r)? ) ), None => None },
+                    simple: match &**left { ASTNode::AtomName(..) => 1, _ => 0 }
+                }
+            },
+            _ => {
+                match augmented_assignment_parts(node) {
+                    Some( ( left, op, right ) ) => {
+                        StmtKind::AugAssign {
+                            target: Box::new( self.lower_target(left, ExprContext::Store)? ),
+                            op,
+                            value: Box::new( self.lower_expr(right)? )
+                        }
+                    },
+                    None => StmtKind::Expr { value: Box::new( self.lower_expr(node)? ) }
+                }
+            }
+        };
+        Ok( Stmt { node: kind, location } )
+    }
+
+    fn lower_compound_stmt(&self, node: &ASTNode) -> Result<Stmt, String> {
+        let location = self.node_location(node);
+        let kind = match node {
+            ASTNode::IfStmt(_, _, _, test, _, body, elifs, else_node) => {
+                let mut orelse = self.lower_else(else_node.as_deref())?;
+                for elif in sorted_nodes(elifs).iter().rev() {
+                    match elif {
+                        ASTNode::ElifStmt(start, _, _, elif_test, _, elif_body) => {
+                            let elif_body = self.lower_suite(elif_body)?;
+                            let end = match ( orelse.last(), elif_body.last() ) {
+                                ( Some( s ), _ ) | ( None, Some( s ) ) => s.location.end - self.offset,
+                                _ => self.node_end(elif)
+                            };
+                            let stmt = Stmt {
+                                node: StmtKind::If { test: Box::new( self.lower_expr(elif_test)? ), body: elif_body, orelse },
+                                location: self.location(*start, end)
+                            };
+                            orelse = vec![ stmt ]
+                        },
+                        _ => return Err(format!("SyntaxError at {}: Expecting 'elif' block in lowering!", elif.get_span().0 + self.offset))
+                    }
+                }
+                StmtKind::If { test: Box::new( self.lower_expr(test)? ), body: self.lower_suite(body)?, orelse }
+            },
+            ASTNode::WhileStmt(_, _, _, test, _, body, else_node) => {
+                StmtKind::While {
+                    test: Box::new( self.lower_expr(test)? ),
+                    body: self.lower_suite(body)?,
+                    orelse: self.lower_else(else_node.as_deref())?
+                }
+            },
+            ASTNode::ForStmt(_, _, _, target, _, iter, _, type_comment, body, else_node) => {
+                StmtKind::For {
+                    target: Box::new( self.lower_target(target, ExprContext::Store)? ),
+                    iter: Box::new( self.lower_expr(iter)? ),
+                    body: self.lower_suite(body)?,
+                    orelse: self.lower_else(else_node.as_deref())?,
+                    type_comment: type_comment_text(type_comment.as_deref())
+                }
+            },
+            ASTNode::TryStmt(_, _, _, _, body, handlers, else_node, finally_node) => {
+                let mut is_star = false;
+                let mut lowered = Vec::new();
+                if let Some( h ) = handlers {
+                    for n in sorted_nodes(h) {
+                        if let ASTNode::ExceptStmt(_, _, clause, _, _) = n {
+                            is_star |= matches!( &**clause, ASTNode::ExceptClauseStmt(_, _, _, Some( _ ), _) )
+                        }
+                        lowered.push( self.lower_except_handler(n)? )
+                    }
+                }
+                let body = self.lower_suite(body)?;
+                let orelse = self.lower_else(else_node.as_deref())?;
+                let finalbody = self.lower_else(finally_node.as_deref())?;
+                match is_star {
+                    true => StmtKind::TryStar { body, handlers: lowered, orelse, finalbody },
+                    _ => StmtKind::Try { body, handlers: lowered, orelse, finalbody }
+                }
+            },
+            ASTNode::WithStmt(_, _, _, _, nodes, _, _, _, body) => {
+                let mut items = Vec::new();
+                for n in sorted_nodes(nodes) {
+                    match n {
+                        ASTNode::WithItem(_, _, left, right) => {
+                            items.push( WithItem {
+                                context_expr: self.lower_expr(left)?,
+                                optional_vars: match right { Some( ( _, r ) ) => Some( Box::new( self.lower_target(r, ExprContext::Store)? ) ), None => None }
+                            } )
+                        },
+                        _ => return Err(format!("SyntaxError at {}: Expecting with item in lowering!", n.get_span().0 + self.offset))
+                    }
+                }
+                StmtKind::With { items, body: self.lower_suite(body)?, type_comment: None }
+            },
+            ASTNode::FuncDef(_, _, _, name, parameters, returns, _, type_comment, body) => {
+                let args = match parameters.as_deref() {
+                    Some( ASTNode::Parameter(_, _, _, right, _) ) => self.lower_parameters(right.as_deref())?,
+                    _ => Arguments::empty()
+                };
+                let type_comment = match ( type_comment, &**body ) {
+                    ( Some( tc ), _ ) => type_comment_text(Some( tc )),
+                    ( None, ASTNode::FuncBodySuite(_, _, _, tc, _, _, _, _) ) => type_comment_text(tc.as_deref()),
+                    _ => None
+                };
+                StmtKind::FunctionDef {
+                    name: name_text(name),
+                    args: Box::new( args ),
+                    body: self.lower_suite(body)?,
+                    decorator_list: Vec::new(),
+                    returns: match returns { Some( r ) => Some( Box::new( self.lower_expr(&r.1)? ) ), None => None },
+                    type_comment
+                }
+            },
+            ASTNode::ClassDef(_, _, _, name, left_paren, arguments, _, _, body) => {
+                let ( bases, keywords ) = match left_paren {
+                    Some( lp ) => {
+                        let start = lp.get_span().map( |s| s.0 ).unwrap_or(0);
+                        self.lower_call_arguments(arguments.as_deref(), ( start, start ))?
+                    },
+                    None => ( Vec::new(), Vec::new() )
+                };
+                StmtKind::ClassDef { name: name_text(name), bases, keywords, body: self.lower_suite(body)?, decorator_list: Vec::new() }
+            },
+            ASTNode::Decorated(_, _, decorators, right) => {
+                let mut decorator_list = Vec::new();
+                if let ASTNode::Decorators(_, _, nodes) = &**decorators {
+                    for n in sorted_nodes(nodes) {
+                        decorator_list.push( self.lower_decorator(n)? )
+                    }
+                }
+                let mut stmt = self.lower_compound_stmt(right)?;
+                match &mut stmt.node {
+                    StmtKind::FunctionDef { decorator_list: d, .. } |
+                    StmtKind::AsyncFunctionDef { decorator_list: d, .. } |
+                    StmtKind::ClassDef { decorator_list: d, .. } => *d = decorator_list,
+                    _ => return Err(format!("SyntaxError at {}: Expecting function or class after decorators!", location.start))
+                }
+                return Ok(stmt)
+            },
+            ASTNode::AsyncStmt(_, _, _, right) => {
+                match self.lower_compound_stmt(right)?.node {
+                    StmtKind::FunctionDef { name, args, body, decorator_list, returns, type_comment } => {
+                        StmtKind::AsyncFunctionDef { name, args, body, decorator_list, returns, type_comment }
+                    },
+                    StmtKind::For { target, iter, body, orelse, type_comment } => {
+                        StmtKind::AsyncFor { target, iter, body, orelse, type_comment }
+                    },
+                    StmtKind::With { items, body, type_comment } => {
+                        StmtKind::AsyncWith { items, body, type_comment }
+                    },
+                    _ => return Err(format!("SyntaxError at {}: Expecting 'def', 'for' or 'with' after 'async'!", location.start))
+                }
+            },
+            _ => return Err(format!("SyntaxError at {}: Unexpected node in lowering of compound statement!", location.start))
+        };
+        Ok( Stmt { node: kind, location } )
+    }
+
+    fn lower_except_handler(&self, node: &ASTNode) -> Result<ExceptHandler, String> {
+        match node {
+            ASTNode::ExceptStmt(_, _, clause, _, body) => {
+                let ( type_, name ) = match &**clause {
+                    ASTNode::ExceptClauseStmt(_, _, _, _, Some( ( test, alias ) )) => {
+                        ( Some( Box::new( self.lower_expr(test)? ) ), alias.as_ref().map( |a| name_text(&a.1) ) )
+                    },
+                    _ => ( None, None )
+                };
+                Ok( ExceptHandler { type_, name, body: self.lower_suite(body)?, location: self.node_location(node) } )
+            },
+            _ => Err(format!("SyntaxError at {}: Expecting 'except' block in lowering!", node.get_span().0 + self.offset))
+        }
+    }
+
+    fn lower_decorator(&self, node: &ASTNode) -> Result<Expr, String> {
+        match node {
+            ASTNode::Decorator(_, _, _, dotted_name, _, arguments, right_paren, _) => {
+                let names = match &**dotted_name {
+                    ASTNode::DottedNameStmt(_, _, names, _) => sorted_tokens(names),
+                    _ => return Err(format!("SyntaxError at {}: Expecting dotted name in decorator!", dotted_name.get_span().0 + self.offset))
+                };
+                let start = names[0].get_span().map( |s| s.0 ).unwrap_or(0);
+                let mut current = Expr { node: ExprKind::Name { id: name_text(names[0]), ctx: ExprContext::Load }, location: self.token_location(names[0]) };
+                for n in &names[1..] {
+                    let end = n.get_span().map( |s| s.1 ).unwrap_or(0);
+                    current = Expr {
+                        node: ExprKind::Attribute { value: Box::new( current ), attr: name_text(n), ctx: ExprContext::Load },
+                        location: self.location(start, end)
+                    }
+                }
+                match right_paren {
+                    Some( rp ) => {
+                        let end = rp.get_span().map( |s| s.1 ).unwrap_or(0);
+                        let ( args, keywords ) = self.lower_call_arguments(arguments.as_deref(), ( start, end ))?;
+                        Ok( Expr { node: ExprKind::Call { func: Box::new( current ), args, keywords }, location: self.location(start, end) } )
+                    },
+                    None => Ok(current)
+                }
+            },
+            _ => Err(format!("SyntaxError at {}: Expecting decorator in lowering!", node.get_span().0 + self.offset))
+        }
+    }
+
+    fn lower_dotted_as_name(&self, node: &ASTNode) -> Result<Alias, String> {
+        match node {
+            ASTNode::DottedAsNameStmt(_, _, dotted_name, alias) => {
+                Ok( Alias { name: dotted_name_text(dotted_name), asname: alias.as_ref().map( |a| name_text(&a.1) ), location: self.node_location(node) } )
+            },
+            _ => Err(format!("SyntaxError at {}: Expecting dotted name in import statement lowering!", node.get_span().0 + self.offset))
+        }
+    }
+
+    fn lower_import_as_name(&self, node: &ASTNode) -> Result<Alias, String> {
+        match node {
+            ASTNode::ImportAsName(_, _, name, alias) => {
+                Ok( Alias { name: name_text(name), asname: alias.as_ref().map( |a| name_text(&a.1) ), location: self.node_location(node) } )
+            },
+            _ => Err(format!("SyntaxError at {}: Expecting name in import statement lowering!", node.get_span().0 + self.offset))
+        }
+    }
+
+    /// Lowers argument list of call or class definition. 'parens' is span of the parenthesis,
+    /// used as position of a generator expression being sole argument.
+    fn lower_call_arguments(&self, node: Option<&ASTNode>, parens: ( u32, u32 )) -> Result<( Vec<Expr>, Vec<Keyword> ), String> {
+        let mut args = Vec::new();
+        let mut keywords = Vec::new();
+        let nodes = match node {
+            Some( ASTNode::ArgList(_, _, nodes, _) ) => sorted_nodes(nodes),
+            Some( n ) => vec![ n ],
+            None => Vec::new()
+        };
+        let sole = nodes.len() == 1;
+        for n in nodes {
+            let location = self.node_location(n);
+            match n {
+                ASTNode::Argument(_, _, None, Some( symbol ), Some( right )) => {
+                    match &**symbol {
+                        Token::PyMul(..) if keywords.iter().any( |k: &Keyword| k.arg.is_none() ) => {
+                            return Err(format!("SyntaxError at {}: iterable argument unpacking follows keyword argument unpacking", location.start))
+                        },
+                        Token::PyMul(..) => {
+                            args.push( Expr { node: ExprKind::Starred { value: Box::new( self.lower_expr(right)? ), ctx: ExprContext::Load }, location } )
+                        },
+                        _ => keywords.push( Keyword { arg: None, value: self.lower_expr(right)?, location } )
+                    }
+                },
+                ASTNode::Argument(_, _, Some( left ), None, Some( comp )) => {
+                    let location = match sole {
+                        true => self.location(parens.0, parens.1),
+                        _ => location
+                    };
+                    args.push( Expr {
+                        node: ExprKind::GeneratorExp { elt: Box::new( self.lower_expr(left)? ), generators: self.lower_comprehension(comp)? },
+                        location
+                    } )
+                },
+                ASTNode::Argument(_, _, Some( left ), Some( symbol ), Some( right )) => {
+                    match ( &**symbol, &**left ) {
+                        ( Token::PyColonAssign(..), _ ) => {
+                            args.push( Expr {
+                                node: ExprKind::NamedExpr {
+                                    target: Box::new( self.lower_target(left, ExprContext::Store)? ),
+                                    value: Box::new( self.lower_expr(right)? )
+                                },
+                                location
+                            } )
+                        },
+                        ( _, ASTNode::AtomName(_, _, name) ) => {
+                            keywords.push( Keyword { arg: Some( name_text(name) ), value: self.lower_expr(right)?, location } )
+                        },
+                        _ => return Err(format!("SyntaxError at {}: expression cannot contain assignment, perhaps you meant \"==\"?", location.start))
+                    }
+                },
+                ASTNode::Argument(_, _, Some( left ), None, None) => args.push( self.lower_expr(left)? ),
+                _ => args.push( self.lower_expr(n)? )
+            }
+        }
+        Ok( ( args, keywords ) )
+    }
+
+    fn lower_slice(&self, node: &ASTNode) -> Result<Expr, String> {
+        match node {
+            ASTNode::SubscriptList(_, _, nodes, separators) if nodes.len() == 1 && separators.is_empty() => self.lower_slice(&nodes[0]),
+            ASTNode::SubscriptList(_, _, nodes, _) => {
+                let mut elts = Vec::new();
+                for n in sorted_nodes(nodes) {
+                    elts.push( self.lower_slice(n)? )
+                }
+                Ok( Expr { node: ExprKind::Tuple { elts, ctx: ExprContext::Load }, location: self.node_location(node) } )
+            },
+            ASTNode::Subscript(_, _, Some( first ), None, _, _, _) => self.lower_expr(first),
+            ASTNode::Subscript(_, _, lower, _, upper, _, step) => {
+                Ok( Expr {
+                    node: ExprKind::Slice {
+                        lower: self.lower_optional(lower.as_deref())?,
+                        upper: self.lower_optional(upper.as_deref())?,
+                        step: self.lower_optional(step.as_deref())?
+                    },
+                    location: self.node_location(node)
+                } )
+            },
+            _ => self.lower_expr(node)
+        }
+    }
+
+    fn lower_comprehension(&self, node: &ASTNode) -> Result<Vec<Comprehension>, String> {
+        let mut generators : Vec<Comprehension> = Vec::new();
+        let mut current = Some( node );
+        while let Some( n ) = current {
+            match n {
+                ASTNode::CompForComprehension(_, _, _, right) => {
+                    match &**right {
+                        ASTNode::SyncCompForComprehension(_, _, _, target, _, iter, next) => {
+                            generators.push( Comprehension {
+                                target: self.lower_target(target, ExprContext::Store)?,
+                                iter: self.lower_expr(iter)?,
+                                ifs: Vec::new(),
+                                is_async: 1
+                            } );
+                            current = next.as_deref()
+                        },
+                        _ => return Err(format!("SyntaxError at {}: Expecting 'for' after 'async' in comprehension!", right.get_span().0 + self.offset))
+                    }
+                },
+                ASTNode::SyncCompForComprehension(_, _, _, target, _, iter, next) => {
+                    generators.push( Comprehension {
+                        target: self.lower_target(target, ExprContext::Store)?,
+                        iter: self.lower_expr(iter)?,
+                        ifs: Vec::new(),
+                        is_async: 0
+                    } );
+                    current = next.as_deref()
+                },
+                ASTNode::CompIfComprehension(_, _, _, test, next) => {
+                    let test = self.lower_expr(test)?;
+                    match generators.last_mut() {
+                        Some( g ) => g.ifs.push( test ),
+                        None => return Err(format!("SyntaxError at {}: Expecting 'for' before 'if' in comprehension!", n.get_span().0 + self.offset))
+                    }
+                    current = next.as_deref()
+                },
+                _ => return Err(format!("SyntaxError at {}: Expecting comprehension in lowering!", n.get_span().0 + self.offset))
+            }
+        }
+        Ok(generators)
+    }
+
+    /// Lowers typed arguments list of function definition or variable arguments list of lambda.
+    fn lower_parameters(&self, node: Option<&ASTNode>) -> Result<Arguments, String> {
+        let mut res = Arguments::empty();
+        let ( nodes, markers, mul, power ) = match node {
+            Some( ASTNode::TypedArgsList(_, _, nodes, _, markers, mul, power) ) |
+            Some( ASTNode::VarArgsList(_, _, nodes, _, markers, mul, power) ) => ( nodes, markers, mul, power ),
+            Some( n ) => return Err(format!("SyntaxError at {}: Expecting parameters in lowering!", n.get_span().0 + self.offset)),
+            None => return Ok(res)
+        };
+        let mut slash_position : Option<u32> = None;
+        let mut star_position : Option<u32> = None;
+        for m in markers.iter() {
+            match ( &**m, m.get_span() ) {
+                ( Token::PyDiv(..), Some( ( start, _ ) ) ) => slash_position = Some( start ),
+                ( _, Some( ( start, _ ) ) ) => star_position = Some( start ),
+                _ => {}
+            }
+        }
+        let bare_star = star_position;
+        if let Some( ( tok, _ ) ) = mul {
+            star_position = tok.get_span().map( |s| s.0 )
+        }
+        let mut seen_default = false;
+        for n in sorted_nodes(nodes) {
+            let start = n.get_span().0;
+            let ( def, default ) = match n {
+                ASTNode::TFPAssign(_, _, left, _, right) |
+                ASTNode::VFPAssign(_, _, left, _, right) => ( &**left, Some( self.lower_expr(right)? ) ),
+                _ => ( n, None )
+            };
+            let arg = self.lower_arg(def)?;
+            let is_keyword_only = matches!(star_position, Some( star ) if start > star);
+            if !is_keyword_only {
+                if default.is_none() && seen_default {
+                    return Err(format!("SyntaxError at {}: non-default argument follows default argument", start + self.offset))
+                }
+                seen_default |= default.is_some()
+            }
+            match ( slash_position, star_position ) {
+                ( _, Some( star ) ) if start > star => {
+                    res.kwonlyargs.push( arg );
+                    res.kw_defaults.push( default )
+                },
+                ( Some( slash ), _ ) if start < slash => {
+                    res.posonlyargs.push( arg );
+                    res.defaults.extend( default )
+                },
+                _ => {
+                    res.args.push( arg );
+                    res.defaults.extend( default )
+                }
+            }
+        }
+        if let Some( star ) = bare_star {
+            if res.kwonlyargs.is_empty() {
+                return Err(format!("SyntaxError at {}: named arguments must follow bare *", star + self.offset))
+            }
+        }
+        if let Some( ( _, def ) ) = mul {
+            res.vararg = Some( Box::new( self.lower_arg(def)? ) )
+        }
+        if let Some( ( _, def ) ) = power {
+            res.kwarg = Some( Box::new( self.lower_arg(def)? ) )
+        }
+        Ok(res)
+    }
+
+    fn lower_arg(&self, node: &ASTNode) -> Result<Arg, String> {
+        match node {
+            ASTNode::TFPDef(_, _, name, annotation) => {
+                Ok( Arg {
+                    arg: name_text(name),
+                    annotation: match annotation { Some( a ) => Some( Box::new( self.lower_expr(&a.1)? ) ), None => None },
+                    type_comment: None,
+                    location: self.node_location(node)
+                } )
+            },
+            ASTNode::VFPDef(_, _, name) => {
+                Ok( Arg { arg: name_text(name), annotation: None, type_comment: None, location: self.node_location(node) } )
+            },
+            _ => Err(format!("SyntaxError at {}: Expecting parameter name in lowering!", node.get_span().0 + self.offset))
+        }
+    }
+
+    fn lower_string(&self, node: &ASTNode, tokens: &[Box<Token>]) -> Result<ExprKind, String> {
+        let mut pieces = Vec::new();
+        for t in tokens {
+            pieces.push( split_string_token(t)? )
+        }
+        let is_bytes = pieces[0].is_bytes;
+        if pieces.iter().any( |p| p.is_bytes != is_bytes ) {
+            return Err(format!("SyntaxError at {}: cannot mix bytes and nonbytes literals", node.get_span().0 + self.offset))
+        }
+        if is_bytes {
+            let mut value : Vec<u8> = Vec::new();
+            for p in &pieces {
+                for c in decode_escapes(&p.body, p.is_raw, true, p.start + self.offset)? {
+                    value.push( c as u8 )
+                }
+            }
+            return Ok( ExprKind::Constant { value: Constant::Bytes(value), kind: None } )
+        }
+        if !pieces.iter().any( |p| p.is_format ) {
+            let mut value = String::new();
+            for p in &pieces {
+                value.push_str( &decode_string(&p.body, p.is_raw, p.start + self.offset)? )
+            }
+            let kind = match pieces[0].is_unicode { true => Some( "u".to_string() ), _ => None };
+            return Ok( ExprKind::Constant { value: Constant::Str(value), kind } )
+        }
+        let whole = self.node_location(node);
+        let mut parts = Vec::new();
+        for p in &pieces {
+            match p.is_format {
+                true => self.lower_format_string(p, &whole, &mut parts)?,
+                _ => parts.push( StringPart::Literal( decode_string(&p.body, p.is_raw, p.start + self.offset)? ) )
+            }
+        }
+        Ok( ExprKind::JoinedStr { values: finish_string_parts(parts, &whole) } )
+    }
+
+    fn lower_format_string(&self, piece: &StringPiece, whole: &Location, parts: &mut Vec<StringPart>) -> Result<(), String> {
+        let body = &piece.body;
+        let mut literal : Vec<char> = Vec::new();
+        let mut i = 0;
+        while i < body.len() {
+            match ( body[i], body.get(i + 1) ) {
+                ( '\\', Some( '{' ) ) | ( '\\', Some( '}' ) ) if !piece.is_raw => {
+                    literal.push( '\\' );
+                    i += 1
+                },
+                ( '\\', Some( _ ) ) if !piece.is_raw => {
+                    i = copy_escape(body, i, &mut literal)
+                },
+                ( '{', Some( '{' ) ) | ( '}', Some( '}' ) ) => {
+                    literal.push( body[i] );
+                    i += 2
+                },
+                ( '{', _ ) => {
+                    parts.push( StringPart::Literal( decode_string(&literal, piece.is_raw, piece.start + self.offset)? ) );
+                    literal.clear();
+                    i = self.lower_format_field(piece, i, whole, parts)?
+                },
+                ( '}', _ ) => return Err(format!("SyntaxError at {}: f-string: single '}}' is not allowed", piece.body_start + i as u32 + self.offset)),
+                ( c, _ ) => {
+                    literal.push( c );
+                    i += 1
+                }
+            }
+        }
+        parts.push( StringPart::Literal( decode_string(&literal, piece.is_raw, piece.start + self.offset)? ) );
+        Ok(())
+    }
+
+    /// Lowers replacement field starting with '{' at index 'start' of string body, returns index after closing '}'.
+    fn lower_format_field(&self, piece: &StringPiece, start: usize, whole: &Location, parts: &mut Vec<StringPart>) -> Result<usize, String> {
+        let body = &piece.body;
+        let error_position = piece.body_start + start as u32 + self.offset;
+        let expression_start = start + 1;
+        let mut i = expression_start;
+        let mut depth = 0;
+        loop {
+            let c = match body.get(i) {
+                Some( c ) => *c,
+                None => return Err(format!("SyntaxError at {}: f-string: expecting '}}'", error_position))
+            };
+            let next = body.get(i + 1).copied();
+            match c {
+                '\'' | '"' => {
+                    i += 1;
+                    while i < body.len() && body[i] != c { i += 1 }
+                },
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' if depth > 0 => depth -= 1,
+                '}' if depth > 0 => depth -= 1,
+                '}' => break,
+                ')' | ']' => return Err(format!("SyntaxError at {}: f-string: unmatched '{}'", error_position, c)),
+                '#' => return Err(format!("SyntaxError at {}: f-string expression part cannot include '#'", error_position)),
+                '=' | '!' | '<' | '>' if next == Some( '=' ) => i += 1,
+                '!' | ':' | '=' if depth == 0 => break,
+                _ => {}
+            }
+            i += 1
+        }
+        let expression : Vec<char> = body[expression_start .. i].to_vec();
+        if expression.iter().all( |c| c.is_whitespace() ) {
+            return Err(format!("SyntaxError at {}: f-string: empty expression not allowed", error_position))
+        }
+        let mut debug_text : Option<String> = None;
+        if body[i] == '=' {
+            i += 1;
+            while i < body.len() && body[i].is_whitespace() { i += 1 }
+            debug_text = Some( body[expression_start .. i].iter().collect() )
+        }
+        let mut conversion = -1;
+        if body.get(i) == Some( &'!' ) {
+            conversion = match body.get(i + 1) {
+                Some( 's' ) => 115,
+                Some( 'r' ) => 114,
+                Some( 'a' ) => 97,
+                _ => return Err(format!("SyntaxError at {}: f-string: invalid conversion character: expected 's', 'r', or 'a'", error_position))
+            };
+            i += 2
+        }
+        let mut format_spec : Option<Box<Expr>> = None;
+        if body.get(i) == Some( &':' ) {
+            i += 1;
+            let mut spec_parts = Vec::new();
+            let mut literal : Vec<char> = Vec::new();
+            loop {
+                match ( body.get(i), body.get(i + 1) ) {
+                    ( None, _ ) => return Err(format!("SyntaxError at {}: f-string: expecting '}}'", error_position)),
+                    ( Some( '}' ), _ ) => break,
+                    ( Some( '{' ), _ ) => {
+                        spec_parts.push( StringPart::Literal( decode_string(&literal, piece.is_raw, error_position)? ) );
+                        literal.clear();
+                        i = self.lower_format_field(piece, i, whole, &mut spec_parts)?
+                    },
+                    ( Some( '\\' ), Some( _ ) ) if !piece.is_raw => {
+                        i = copy_escape(body, i, &mut literal)
+                    },
+                    ( Some( c ), _ ) => {
+                        literal.push( *c );
+                        i += 1
+                    }
+                }
+            }
+            spec_parts.push( StringPart::Literal( decode_string(&literal, piece.is_raw, error_position)? ) );
+            format_spec = Some( Box::new( Expr {
+                node: ExprKind::JoinedStr { values: finish_string_parts(spec_parts, whole) },
+                location: self.location(piece.start, piece.end)
+            } ) )
+        }
+        if body.get(i) != Some( &'}' ) {
+            return Err(format!("SyntaxError at {}: f-string: expecting '}}'", error_position))
+        }
+        if debug_text.is_some() && conversion == -1 && format_spec.is_none() {
+            conversion = 114
+        }
+        let value = self.lower_format_expression(&expression, piece.body_start + expression_start as u32)?;
+        if let Some( text ) = debug_text {
+            parts.push( StringPart::Literal( text ) )
+        }
+        parts.push( StringPart::Value( Expr {
+            node: ExprKind::FormattedValue { value: Box::new( value ), conversion, format_spec },
+            location: whole.clone()
+        } ) );
+        Ok( i + 1 )
+    }
+
+    /// Parses expression of a replacement field in parenthesis, the same way as CPython does.
+    fn lower_format_expression(&self, expression: &[char], start: u32) -> Result<Expr, String> {
+        let source = format!("({})", expression.iter().collect::<String>());
+        let lexer = Box::new( PythonCoreTokenizer::new(source.clone()) );
+        let mut parser = PythonCoreParser::new(lexer);
+        parser.advance();
+        let node = parser.parse_expressions_atom()?;
+        match &parser.symbol {
+            Ok( s ) if matches!( &**s, Token::EOF(..) | Token::Newline(..) ) => { },
+            _ => return Err(format!("SyntaxError at {}: f-string: invalid syntax", start + self.offset))
+        }
+        let lowering = PythonCoreLowering {
+            line_index: self.line_index.clone(),
+            tokens: scan_tokens(&source).0,
+            type_ignores: Vec::new(),
+            offset: start + self.offset - 1
+        };
+        lowering.lower_expr(&node)
+    }
+}
+
+
+type TokenScan = ( Vec<( u32, u32 )>, Vec<( u32, String )> );
+
+/// Collects start and end of all tokens with a visible position, sorted by position, together
+/// with position and tag of all '# type: ignore' comments.
+fn scan_tokens(source: &str) -> TokenScan {
+    let mut lexer = PythonCoreTokenizer::new(source.to_string());
+    let mut spans = Vec::new();
+    let mut type_ignores = Vec::new();
+    while let Ok( tok ) = lexer.get_symbol() {
+        match &*tok {
+            Token::EOF(..) => break,
+            Token::TypeComment(start, _, _, txt) => {
+                if let Some( tag ) = type_ignore_tag(txt) {
+                    type_ignores.push( ( *start, tag ) )
+                }
+            },
+            Token::Newline(..) => { },
+            _ => {
+                if let Some( span ) = tok.get_span() {
+                    spans.push( span )
+                }
+            }
+        }
+    }
+    ( spans, type_ignores )
+}
+
+fn sorted_nodes(nodes: &[Box<ASTNode>]) -> Vec<&ASTNode> {
+    let mut res : Vec<&ASTNode> = nodes.iter().map( |n| &**n ).collect();
+    res.sort_by_key( |n| n.get_span().0 );
+    res
+}
+
+fn sorted_tokens(tokens: &[Box<Token>]) -> Vec<&Token> {
+    let mut res : Vec<&Token> = tokens.iter().map( |t| &**t ).collect();
+    res.sort_by_key( |t| t.get_span().map( |s| s.0 ).unwrap_or(0) );
+    res
+}
+
+/// Splits elements of a container from the trailing comprehension, if any.
+fn split_comprehension(nodes: &[Box<ASTNode>]) -> ( Vec<&ASTNode>, Option<&ASTNode> ) {
+    let mut elements = Vec::new();
+    let mut comp = None;
+    for n in sorted_nodes(nodes) {
+        match n {
+            ASTNode::SyncCompForComprehension(..) |
+            ASTNode::CompForComprehension(..) => comp = Some( n ),
+            _ => elements.push( n )
+        }
+    }
+    ( elements, comp )
+}
+
+fn name_text(token: &Token) -> String {
+    match token {
+        Token::AtomName(_, _, _, txt) => txt.to_string(),
+        _ => String::new()
+    }
+}
+
+fn dotted_name_text(node: &ASTNode) -> String {
+    match node {
+        ASTNode::DottedNameStmt(_, _, names, _) => {
+            sorted_tokens(names).iter().map( |t| name_text(t) ).collect::<Vec<String>>().join(".")
+        },
+        _ => String::new()
+    }
+}
+
+/// Returns tag of a '# type: ignore' comment, that is the text following 'ignore'.
+fn type_ignore_tag(text: &str) -> Option<String> {
+    let rest = text.trim_start_matches('#').trim_start().strip_prefix("type:")?.trim_start().strip_prefix("ignore")?;
+    match rest.chars().next() {
+        Some( c ) if c.is_alphanumeric() || c == '_' => None,
+        _ => Some( rest.trim_end().to_string() )
+    }
+}
+
+fn type_comment_text(token: Option<&Token>) -> Option<String> {
+    match token {
+        Some( Token::TypeComment(_, _, _, txt) ) if type_ignore_tag(txt).is_none() => Some( txt.trim_start_matches("# type:").trim().to_string() ),
+        _ => None
+    }
+}
+
+fn comparison_parts(node: &ASTNode) -> Option<( &ASTNode, CmpOperator, &ASTNode )> {
+    match node {
+        ASTNode::LessComparison(_, _, left, _, right) => Some( ( left, CmpOperator::Lt, right ) ),
+        ASTNode::LessEqualComparison(_, _, left, _, right) => Some( ( left, CmpOperator::LtE, right ) ),
+        ASTNode::EqualComparison(_, _, left, _, right) => Some( ( left, CmpOperator::Eq, right ) ),
+        ASTNode::GreaterComparison(_, _, left, _, right) => Some( ( left, CmpOperator::Gt, right ) ),
+        ASTNode::GreaterEqualComparison(_, _, left, _, right) => Some( ( left, CmpOperator::GtE, right ) ),
+        ASTNode::NotEqualComparison(_, _, left, _, right) => Some( ( left, CmpOperator::NotEq, right ) ),
+        ASTNode::InComparison(_, _, left, _, right) => Some( ( left, CmpOperator::In, right ) ),
+        ASTNode::IsComparison(_, _, left, _, right) => Some( ( left, CmpOperator::Is, right ) ),
+        ASTNode::NotInComparison(_, _, left, _, _, right) => Some( ( left, CmpOperator::NotIn, right ) ),
+        ASTNode::IsNotComparison(_, _, left, _, _, right) => Some( ( left, CmpOperator::IsNot, right ) ),
+        _ => None
+    }
+}
+
+fn augmented_assignment_parts(node: &ASTNode) -> Option<( &ASTNode, Operator, &ASTNode )> {
+    match node {
+        ASTNode::PlusAssignStmt(_, _, left, _, right) => Some( ( left, Operator::Add, right ) ),
+        ASTNode::MinusAssignStmt(_, _, left, _, right) => Some( ( left, Operator::Sub, right ) ),
+        ASTNode::MulAssignStmt(_, _, left, _, right) => Some( ( left, Operator::Mult, right ) ),
+        ASTNode::DivAssignStmt(_, _, left, _, right) => Some( ( left, Operator::Div, right ) ),
+        ASTNode::FloorDivAssignStmt(_, _, left, _, right) => Some( ( left, Operator::FloorDiv, right ) ),
+        ASTNode::ModuloAssignStmt(_, _, left, _, right) => Some( ( left, Operator::Mod, right ) ),
+        ASTNode::MatriceAssignStmt(_, _, left, _, right) => Some( ( left, Operator::MatMult, right ) ),
+        ASTNode::PowerAssignStmt(_, _, left, _, right) => Some( ( left, Operator::Pow, right ) ),
+        ASTNode::ShiftLeftAssignStmt(_, _, left, _, right) => Some( ( left, Operator::LShift, right ) ),
+        ASTNode::ShiftRightAssignStmt(_, _, left, _, right) => Some( ( left, Operator::RShift, right ) ),
+        ASTNode::BitOrAssignStmt(_, _, left, _, right) => Some( ( left, Operator::BitOr, right ) ),
+        ASTNode::BitXorAssignStmt(_, _, left, _, right) => Some( ( left, Operator::BitXor, right ) ),
+        ASTNode::BitAndAssignStmt(_, _, left, _, right) => Some( ( left, Operator::BitAnd, right ) ),
+        _ => None
+    }
+}
+
+/// Sets 'Store' or 'Del' context on target and nested targets of tuples, lists and starred.
+pub fn set_context(expr: Expr, ctx: ExprContext) -> Expr {
+    let Expr { node, location } = expr;
+    let node = match node {
+        ExprKind::Name { id, .. } => ExprKind::Name { id, ctx },
+        ExprKind::Attribute { value, attr, .. } => ExprKind::Attribute { value, attr, ctx },
+        ExprKind::Subscript { value, slice, .. } => ExprKind::Subscript { value, slice, ctx },
+        ExprKind::Starred { value, .. } => ExprKind::Starred { value: Box::new( set_context(*value, ctx) ), ctx },
+        ExprKind::Tuple { elts, .. } => ExprKind::Tuple { elts: elts.into_iter().map( |e| set_context(e, ctx) ).collect(), ctx },
+        ExprKind::List { elts, .. } => ExprKind::List { elts: elts.into_iter().map( |e| set_context(e, ctx) ).collect(), ctx },
+        other => other
+    };
+    Expr { node, location }
+}
+
+fn lower_number(text: &str, position: u32) -> Result<Constant, String> {
+    let txt : String = text.chars().filter( |c| *c != '_' ).collect::<String>().to_lowercase();
+    let radix = match txt.get(0 .. 2) {
+        Some( "0x" ) => 16,
+        Some( "0o" ) => 8,
+        Some( "0b" ) => 2,
+        _ => 10
+    };
+    if radix != 10 {
+        return Ok( Constant::Int( radix_to_decimal(&txt[2 ..], radix, position)? ) )
+    }
+    if let Some( imag ) = txt.strip_suffix('j') {
+        return match imag.parse::<f64>() {
+            Ok( v ) => Ok( Constant::Complex(0.0, v) ),
+            Err( _ ) => Err(format!("SyntaxError at {}: invalid imaginary literal", position))
+        }
+    }
+    if txt.contains('.') || txt.contains('e') {
+        return match txt.parse::<f64>() {
+            Ok( v ) => Ok( Constant::Float(v) ),
+            Err( _ ) => Err(format!("SyntaxError at {}: invalid decimal literal", position))
+        }
+    }
+    let digits = txt.trim_start_matches('0');
+    match digits.is_empty() {
+        true => Ok( Constant::Int( "0".to_string() ) ),
+        _ => Ok( Constant::Int( digits.to_string() ) )
+    }
+}
+
+/// Converts digits in given radix into decimal digits, using limbs of nine decimal digits.
+fn radix_to_decimal(digits: &str, radix: u32, position: u32) -> Result<String, String> {
+    let mut limbs : Vec<u64> = vec![ 0 ];
+    for c in digits.chars() {
+        let mut carry = match c.to_digit(radix) {
+            Some( d ) => d as u64,
+            None => return Err(format!("SyntaxError at {}: invalid digit '{}' in number literal", position, c))
+        };
+        for limb in limbs.iter_mut() {
+            let v = *limb * radix as u64 + carry;
+            *limb = v % 1_000_000_000;
+            carry = v / 1_000_000_000
+        }
+        if carry > 0 {
+            limbs.push( carry )
+        }
+    }
+    let mut res = limbs.last().copied().unwrap_or(0).to_string();
+    for limb in limbs.iter().rev().skip(1) {
+        res.push_str( &format!("{:09}", limb) )
+    }
+    Ok(res)
+}
+
+fn split_string_token(token: &Token) -> Result<StringPiece, String> {
+    match token {
+        Token::AtomString(start, end, _, text, prefix) => {
+            let prefix = prefix.clone().unwrap_or_default().to_lowercase();
+            let chars : Vec<char> = text.chars().collect();
+            let quotes = match chars.len() >= 6 && chars[0] == chars[1] && chars[1] == chars[2] {
+                true => 3,
+                _ => 1
+            };
+            Ok( StringPiece {
+                start: *start,
+                end: *end,
+                body_start: *start + prefix.chars().count() as u32 + quotes as u32,
+                body: chars[quotes .. chars.len() - quotes].to_vec(),
+                is_raw: prefix.contains('r'),
+                is_bytes: prefix.contains('b'),
+                is_format: prefix.contains('f'),
+                is_unicode: prefix.contains('u')
+            } )
+        },
+        _ => Err("SyntaxError: Expecting string literal in lowering!".to_string())
+    }
+}
+
+/// Copies escape sequence starting at backslash unchanged, returns index after sequence.
+fn copy_escape(body: &[char], start: usize, literal: &mut Vec<char>) -> usize {
+    let mut i = start;
+    literal.push( body[i] );
+    literal.push( body[i + 1] );
+    i += 2;
+    if body[start + 1] == 'N' && body.get(i) == Some( &'{' ) {
+        while i < body.len() {
+            literal.push( body[i] );
+            i += 1;
+            if body[i - 1] == '}' { break }
+        }
+    }
+    i
+}
+
+fn decode_string(body: &[char], is_raw: bool, position: u32) -> Result<String, String> {
+    let mut res = String::new();
+    for c in decode_escapes(body, is_raw, false, position)? {
+        match char::from_u32(c) {
+            Some( ch ) => res.push( ch ),
+            None => return Err(format!("SyntaxError at {}: (unicode error) illegal Unicode character", position))
+        }
+    }
+    Ok(res)
+}
+
+/// Decodes escape sequences of string or bytes literal into code points or byte values.
+fn decode_escapes(body: &[char], is_raw: bool, is_bytes: bool, position: u32) -> Result<Vec<u32>, String> {
+    let mut res : Vec<u32> = Vec::new();
+    let mut i = 0;
+    while i < body.len() {
+        let c = body[i];
+        if is_bytes && c as u32 > 127 {
+            return Err(format!("SyntaxError at {}: bytes can only contain ASCII literal characters", position))
+        }
+        if c != '\\' || is_raw || i + 1 >= body.len() {
+            res.push( c as u32 );
+            i += 1;
+            continue
+        }
+        let next = body[i + 1];
+        i += 2;
+        match next {
+            '\n' => { },
+            '\r' => {
+                if body.get(i) == Some( &'\n' ) { i += 1 }
+            },
+            '\\' | '\'' | '"' => res.push( next as u32 ),
+            'a' => res.push( 7 ),
+            'b' => res.push( 8 ),
+            'f' => res.push( 12 ),
+            'n' => res.push( 10 ),
+            'r' => res.push( 13 ),
+            't' => res.push( 9 ),
+            'v' => res.push( 11 ),
+            '0' ..= '7' => {
+                let mut value = next.to_digit(8).unwrap_or(0);
+                let mut count = 1;
+                while count < 3 && i < body.len() && ('0' ..= '7').contains(&body[i]) {
+                    value = value * 8 + body[i].to_digit(8).unwrap_or(0);
+                    i += 1;
+                    count += 1
+                }
+                res.push( match is_bytes { true => value & 0xff, _ => value } )
+            },
+            'x' | 'u' | 'U' if next == 'x' || !is_bytes => {
+                let count = match next { 'x' => 2, 'u' => 4, _ => 8 };
+                let digits : String = body.iter().skip(i).take(count).collect();
+                match ( digits.chars().count() == count, u32::from_str_radix(&digits, 16) ) {
+                    ( true, Ok( v ) ) => res.push( v ),
+                    _ => return Err(format!("SyntaxError at {}: (unicode error) truncated \\{} escape", position, next))
+                }
+                i += count
+            },
+            'N' if !is_bytes => {
+                return Err(format!("SyntaxError at {}: (unicode error) \\N{{...}} escapes are not supported", position))
+            },
+            _ => {
+                res.push( '\\' as u32 );
+                res.push( next as u32 )
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// Merges adjacent literal parts and drops empty ones, all parts gets the position of the whole string.
+fn finish_string_parts(parts: Vec<StringPart>, whole: &Location) -> Vec<Expr> {
+    let mut res = Vec::new();
+    let mut literal = String::new();
+    for p in parts {
+        match p {
+            StringPart::Literal( txt ) => literal.push_str( &txt ),
+            StringPart::Value( e ) => {
+                if !literal.is_empty() {
+                    res.push( Expr { node: ExprKind::Constant { value: Constant::Str( literal.clone() ), kind: None }, location: whole.clone() } );
+                    literal.clear()
+                }
+                res.push( e )
+            }
+        }
+    }
+    if !literal.is_empty() {
+        res.push( Expr { node: ExprKind::Constant { value: Constant::Str( literal ), kind: None }, location: whole.clone() } )
+    }
+    res
+}
+
+// UnitTests for lowering into abstract tree //////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+    use crate::ast::dump::Dumper;
+    use crate::parser::blocks::Blocks;
+    use crate::parser::tokenizer::{ PythonCoreTokenizer, Tokenizer };
+    use crate::parser::parser::{ Parser, PythonCoreParser };
+
+
+    fn dump_source(source: &str) -> String {
+        let lexer = Box::new( PythonCoreTokenizer::new(source.to_string()) );
+        let mut parser = PythonCoreParser::new(lexer);
+        let node = parser.parse_blocks_file_input().unwrap();
+        PythonCoreLowering::new(source).lower_mod(&node).unwrap().dump(true)
+    }
+
+    fn lower_error(source: &str) -> String {
+        let lexer = Box::new( PythonCoreTokenizer::new(source.to_string()) );
+        let mut parser = PythonCoreParser::new(lexer);
+        let node = parser.parse_blocks_file_input().unwrap();
+        PythonCoreLowering::new(source).lower_mod(&node).unwrap_err()
+    }
+
+    #[test]
+    fn lowering_assignment_chain() {
+        assert_eq!(dump_source("a, b = c = 1, 2\n"), "Module(body=[Assign(targets=[Tuple(elts=[Name(id='a', ctx=Store(), lineno=1, col_offset=0, end_lineno=1, end_col_offset=1), Name(id='b', ctx=Store(), lineno=1, col_offset=3, end_lineno=1, end_col_offset=4)], ctx=Store(), lineno=1, col_offset=0, end_lineno=1, end_col_offset=4), Name(id='c', ctx=Store(), lineno=1, col_offset=7, end_lineno=1, end_col_offset=8)], value=Tuple(elts=[Constant(value=1, lineno=1, col_offset=11, end_lineno=1, end_col_offset=12), Constant(value=2, lineno=1, col_offset=14, end_lineno=1, end_col_offset=15)], ctx=Load(), lineno=1, col_offset=11, end_lineno=1, end_col_offset=15), lineno=1, col_offset=0, end_lineno=1, end_col_offset=15)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_annotated_assignment() {
+        assert_eq!(dump_source("x: int = 3\n"), "Module(body=[AnnAssign(target=Name(id='x', ctx=Store(), lineno=1, col_offset=0, end_lineno=1, end_col_offset=1), annotation=Name(id='int', ctx=Load(), lineno=1, col_offset=3, end_lineno=1, end_col_offset=6), value=Constant(value=3, lineno=1, col_offset=9, end_lineno=1, end_col_offset=10), simple=1, lineno=1, col_offset=0, end_lineno=1, end_col_offset=10)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_augmented_assignment() {
+        assert_eq!(dump_source("x **= 2\n"), "Module(body=[AugAssign(target=Name(id='x', ctx=Store(), lineno=1, col_offset=0, end_lineno=1, end_col_offset=1), op=Pow(), value=Constant(value=2, lineno=1, col_offset=6, end_lineno=1, end_col_offset=7), lineno=1, col_offset=0, end_lineno=1, end_col_offset=7)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_import_from_relative() {
+        assert_eq!(dump_source("from ..a import (b as c, d)\n"), "Module(body=[ImportFrom(module='a', names=[alias(name='b', asname='c', lineno=1, col_offset=17, end_lineno=1, end_col_offset=23), alias(name='d', lineno=1, col_offset=25, end_lineno=1, end_col_offset=26)], level=2, lineno=1, col_offset=0, end_lineno=1, end_col_offset=27)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_elif_chain() {
+        assert_eq!(dump_source("if a:\n    pass\nelif b:\n    x\nelse:\n    y\n"), "Module(body=[If(test=Name(id='a', ctx=Load(), lineno=1, col_offset=3, end_lineno=1, end_col_offset=4), body=[Pass(lineno=2, col_offset=4, end_lineno=2, end_col_offset=8)], orelse=[If(test=Name(id='b', ctx=Load(), lineno=3, col_offset=5, end_lineno=3, end_col_offset=6), body=[Expr(value=Name(id='x', ctx=Load(), lineno=4, col_offset=4, end_lineno=4, end_col_offset=5), lineno=4, col_offset=4, end_lineno=4, end_col_offset=5)], orelse=[Expr(value=Name(id='y', ctx=Load(), lineno=6, col_offset=4, end_lineno=6, end_col_offset=5), lineno=6, col_offset=4, end_lineno=6, end_col_offset=5)], lineno=3, col_offset=0, end_lineno=6, end_col_offset=5)], lineno=1, col_offset=0, end_lineno=6, end_col_offset=5)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_try_except_finally() {
+        assert_eq!(dump_source("try:\n    a\nexcept E as e:\n    b\nfinally:\n    c\n"), "Module(body=[Try(body=[Expr(value=Name(id='a', ctx=Load(), lineno=2, col_offset=4, end_lineno=2, end_col_offset=5), lineno=2, col_offset=4, end_lineno=2, end_col_offset=5)], handlers=[ExceptHandler(type=Name(id='E', ctx=Load(), lineno=3, col_offset=7, end_lineno=3, end_col_offset=8), name='e', body=[Expr(value=Name(id='b', ctx=Load(), lineno=4, col_offset=4, end_lineno=4, end_col_offset=5), lineno=4, col_offset=4, end_lineno=4, end_col_offset=5)], lineno=3, col_offset=0, end_lineno=4, end_col_offset=5)], orelse=[], finalbody=[Expr(value=Name(id='c', ctx=Load(), lineno=6, col_offset=4, end_lineno=6, end_col_offset=5), lineno=6, col_offset=4, end_lineno=6, end_col_offset=5)], lineno=1, col_offset=0, end_lineno=6, end_col_offset=5)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_function_parameters() {
+        assert_eq!(dump_source("def f(a, b=1, /, c=2, *d, e, f=3, **g) -> int:\n    return a\n"), "Module(body=[FunctionDef(name='f', args=arguments(posonlyargs=[arg(arg='a', lineno=1, col_offset=6, end_lineno=1, end_col_offset=7), arg(arg='b', lineno=1, col_offset=9, end_lineno=1, end_col_offset=10)], args=[arg(arg='c', lineno=1, col_offset=17, end_lineno=1, end_col_offset=18)], vararg=arg(arg='d', lineno=1, col_offset=23, end_lineno=1, end_col_offset=24), kwonlyargs=[arg(arg='e', lineno=1, col_offset=26, end_lineno=1, end_col_offset=27), arg(arg='f', lineno=1, col_offset=29, end_lineno=1, end_col_offset=30)], kw_defaults=[None, Constant(value=3, lineno=1, col_offset=31, end_lineno=1, end_col_offset=32)], kwarg=arg(arg='g', lineno=1, col_offset=36, end_lineno=1, end_col_offset=37), defaults=[Constant(value=1, lineno=1, col_offset=11, end_lineno=1, end_col_offset=12), Constant(value=2, lineno=1, col_offset=19, end_lineno=1, end_col_offset=20)]), body=[Return(value=Name(id='a', ctx=Load(), lineno=2, col_offset=11, end_lineno=2, end_col_offset=12), lineno=2, col_offset=4, end_lineno=2, end_col_offset=12)], decorator_list=[], returns=Name(id='int', ctx=Load(), lineno=1, col_offset=42, end_lineno=1, end_col_offset=45), lineno=1, col_offset=0, end_lineno=2, end_col_offset=12)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_decorated_class() {
+        assert_eq!(dump_source("@dec.a(1, k=2)\nclass A(B, metaclass=M):\n    pass\n"), "Module(body=[ClassDef(name='A', bases=[Name(id='B', ctx=Load(), lineno=2, col_offset=8, end_lineno=2, end_col_offset=9)], keywords=[keyword(arg='metaclass', value=Name(id='M', ctx=Load(), lineno=2, col_offset=21, end_lineno=2, end_col_offset=22), lineno=2, col_offset=11, end_lineno=2, end_col_offset=22)], body=[Pass(lineno=3, col_offset=4, end_lineno=3, end_col_offset=8)], decorator_list=[Call(func=Attribute(value=Name(id='dec', ctx=Load(), lineno=1, col_offset=1, end_lineno=1, end_col_offset=4), attr='a', ctx=Load(), lineno=1, col_offset=1, end_lineno=1, end_col_offset=6), args=[Constant(value=1, lineno=1, col_offset=7, end_lineno=1, end_col_offset=8)], keywords=[keyword(arg='k', value=Constant(value=2, lineno=1, col_offset=12, end_lineno=1, end_col_offset=13), lineno=1, col_offset=10, end_lineno=1, end_col_offset=13)], lineno=1, col_offset=1, end_lineno=1, end_col_offset=14)], lineno=2, col_offset=0, end_lineno=3, end_col_offset=8)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_async_function() {
+        assert_eq!(dump_source("async def f():\n    async for a in b:\n        await a\n"), "Module(body=[AsyncFunctionDef(name='f', args=arguments(posonlyargs=[], args=[], kwonlyargs=[], kw_defaults=[], defaults=[]), body=[AsyncFor(target=Name(id='a', ctx=Store(), lineno=2, col_offset=14, end_lineno=2, end_col_offset=15), iter=Name(id='b', ctx=Load(), lineno=2, col_offset=19, end_lineno=2, end_col_offset=20), body=[Expr(value=Await(value=Name(id='a', ctx=Load(), lineno=3, col_offset=14, end_lineno=3, end_col_offset=15), lineno=3, col_offset=8, end_lineno=3, end_col_offset=15), lineno=3, col_offset=8, end_lineno=3, end_col_offset=15)], orelse=[], lineno=2, col_offset=4, end_lineno=3, end_col_offset=15)], decorator_list=[], lineno=1, col_offset=0, end_lineno=3, end_col_offset=15)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_comparison_chain() {
+        assert_eq!(dump_source("a < b is not c\n"), "Module(body=[Expr(value=Compare(left=Name(id='a', ctx=Load(), lineno=1, col_offset=0, end_lineno=1, end_col_offset=1), ops=[Lt(), IsNot()], comparators=[Name(id='b', ctx=Load(), lineno=1, col_offset=4, end_lineno=1, end_col_offset=5), Name(id='c', ctx=Load(), lineno=1, col_offset=13, end_lineno=1, end_col_offset=14)], lineno=1, col_offset=0, end_lineno=1, end_col_offset=14), lineno=1, col_offset=0, end_lineno=1, end_col_offset=14)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_call_generator_argument() {
+        assert_eq!(dump_source("f(x for x in y)\n"), "Module(body=[Expr(value=Call(func=Name(id='f', ctx=Load(), lineno=1, col_offset=0, end_lineno=1, end_col_offset=1), args=[GeneratorExp(elt=Name(id='x', ctx=Load(), lineno=1, col_offset=2, end_lineno=1, end_col_offset=3), generators=[comprehension(target=Name(id='x', ctx=Store(), lineno=1, col_offset=8, end_lineno=1, end_col_offset=9), iter=Name(id='y', ctx=Load(), lineno=1, col_offset=13, end_lineno=1, end_col_offset=14), ifs=[], is_async=0)], lineno=1, col_offset=1, end_lineno=1, end_col_offset=15)], keywords=[], lineno=1, col_offset=0, end_lineno=1, end_col_offset=15), lineno=1, col_offset=0, end_lineno=1, end_col_offset=15)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_subscript_slices() {
+        assert_eq!(dump_source("a[1:2, ::3]\n"), "Module(body=[Expr(value=Subscript(value=Name(id='a', ctx=Load(), lineno=1, col_offset=0, end_lineno=1, end_col_offset=1), slice=Tuple(elts=[Slice(lower=Constant(value=1, lineno=1, col_offset=2, end_lineno=1, end_col_offset=3), upper=Constant(value=2, lineno=1, col_offset=4, end_lineno=1, end_col_offset=5), lineno=1, col_offset=2, end_lineno=1, end_col_offset=5), Slice(step=Constant(value=3, lineno=1, col_offset=9, end_lineno=1, end_col_offset=10), lineno=1, col_offset=7, end_lineno=1, end_col_offset=10)], ctx=Load(), lineno=1, col_offset=2, end_lineno=1, end_col_offset=10), ctx=Load(), lineno=1, col_offset=0, end_lineno=1, end_col_offset=11), lineno=1, col_offset=0, end_lineno=1, end_col_offset=11)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_dictionary_comprehension() {
+        assert_eq!(dump_source("{k: v for k, v in d if k}\n"), "Module(body=[Expr(value=DictComp(key=Name(id='k', ctx=Load(), lineno=1, col_offset=1, end_lineno=1, end_col_offset=2), value=Name(id='v', ctx=Load(), lineno=1, col_offset=4, end_lineno=1, end_col_offset=5), generators=[comprehension(target=Tuple(elts=[Name(id='k', ctx=Store(), lineno=1, col_offset=10, end_lineno=1, end_col_offset=11), Name(id='v', ctx=Store(), lineno=1, col_offset=13, end_lineno=1, end_col_offset=14)], ctx=Store(), lineno=1, col_offset=10, end_lineno=1, end_col_offset=14), iter=Name(id='d', ctx=Load(), lineno=1, col_offset=18, end_lineno=1, end_col_offset=19), ifs=[Name(id='k', ctx=Load(), lineno=1, col_offset=23, end_lineno=1, end_col_offset=24)], is_async=0)], lineno=1, col_offset=0, end_lineno=1, end_col_offset=25), lineno=1, col_offset=0, end_lineno=1, end_col_offset=25)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_number_literals() {
+        assert_eq!(dump_source("0x1F + 1_000 + 1.5e-5 + 2j\n"), "Module(body=[Expr(value=BinOp(left=BinOp(left=BinOp(left=Constant(value=31, lineno=1, col_offset=0, end_lineno=1, end_col_offset=4), op=Add(), right=Constant(value=1000, lineno=1, col_offset=7, end_lineno=1, end_col_offset=12), lineno=1, col_offset=0, end_lineno=1, end_col_offset=12), op=Add(), right=Constant(value=1.5e-05, lineno=1, col_offset=15, end_lineno=1, end_col_offset=21), lineno=1, col_offset=0, end_lineno=1, end_col_offset=21), op=Add(), right=Constant(value=2j, lineno=1, col_offset=24, end_lineno=1, end_col_offset=26), lineno=1, col_offset=0, end_lineno=1, end_col_offset=26), lineno=1, col_offset=0, end_lineno=1, end_col_offset=26)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_string_concatenation() {
+        assert_eq!(dump_source("'a' \"b\\n\"\n"), "Module(body=[Expr(value=Constant(value='ab\\n', lineno=1, col_offset=0, end_lineno=1, end_col_offset=9), lineno=1, col_offset=0, end_lineno=1, end_col_offset=9)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_bytes_literal() {
+        assert_eq!(dump_source("b'x\\x00'\n"), "Module(body=[Expr(value=Constant(value=b'x\\x00', lineno=1, col_offset=0, end_lineno=1, end_col_offset=8), lineno=1, col_offset=0, end_lineno=1, end_col_offset=8)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_format_string() {
+        assert_eq!(dump_source("f'a{b!r:>{w}}c'\n"), "Module(body=[Expr(value=JoinedStr(values=[Constant(value='a', lineno=1, col_offset=0, end_lineno=1, end_col_offset=15), FormattedValue(value=Name(id='b', ctx=Load(), lineno=1, col_offset=4, end_lineno=1, end_col_offset=5), conversion=114, format_spec=JoinedStr(values=[Constant(value='>', lineno=1, col_offset=0, end_lineno=1, end_col_offset=15), FormattedValue(value=Name(id='w', ctx=Load(), lineno=1, col_offset=10, end_lineno=1, end_col_offset=11), conversion=-1, lineno=1, col_offset=0, end_lineno=1, end_col_offset=15)], lineno=1, col_offset=0, end_lineno=1, end_col_offset=15), lineno=1, col_offset=0, end_lineno=1, end_col_offset=15), Constant(value='c', lineno=1, col_offset=0, end_lineno=1, end_col_offset=15)], lineno=1, col_offset=0, end_lineno=1, end_col_offset=15), lineno=1, col_offset=0, end_lineno=1, end_col_offset=15)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_format_string_debug() {
+        assert_eq!(dump_source("f'{a = }'\n"), "Module(body=[Expr(value=JoinedStr(values=[Constant(value='a = ', lineno=1, col_offset=0, end_lineno=1, end_col_offset=9), FormattedValue(value=Name(id='a', ctx=Load(), lineno=1, col_offset=3, end_lineno=1, end_col_offset=4), conversion=114, lineno=1, col_offset=0, end_lineno=1, end_col_offset=9)], lineno=1, col_offset=0, end_lineno=1, end_col_offset=9), lineno=1, col_offset=0, end_lineno=1, end_col_offset=9)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_utf8_columns() {
+        assert_eq!(dump_source("x = 'åé' + y\n"), "Module(body=[Assign(targets=[Name(id='x', ctx=Store(), lineno=1, col_offset=0, end_lineno=1, end_col_offset=1)], value=BinOp(left=Constant(value='åé', lineno=1, col_offset=4, end_lineno=1, end_col_offset=10), op=Add(), right=Name(id='y', ctx=Load(), lineno=1, col_offset=13, end_lineno=1, end_col_offset=14), lineno=1, col_offset=4, end_lineno=1, end_col_offset=14), lineno=1, col_offset=0, end_lineno=1, end_col_offset=14)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_type_comment() {
+        assert_eq!(dump_source("x = 1  # type: int\n"), "Module(body=[Assign(targets=[Name(id='x', ctx=Store(), lineno=1, col_offset=0, end_lineno=1, end_col_offset=1)], value=Constant(value=1, lineno=1, col_offset=4, end_lineno=1, end_col_offset=5), type_comment='int', lineno=1, col_offset=0, end_lineno=1, end_col_offset=18)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_type_ignore() {
+        assert_eq!(dump_source("import x  # type: ignore\nf()  # type: ignore[attr]\n"), "Module(body=[Import(names=[alias(name='x', lineno=1, col_offset=7, end_lineno=1, end_col_offset=8)], lineno=1, col_offset=0, end_lineno=1, end_col_offset=8), Expr(value=Call(func=Name(id='f', ctx=Load(), lineno=2, col_offset=0, end_lineno=2, end_col_offset=1), args=[], keywords=[], lineno=2, col_offset=0, end_lineno=2, end_col_offset=3), lineno=2, col_offset=0, end_lineno=2, end_col_offset=3)], type_ignores=[TypeIgnore(lineno=1, tag=''), TypeIgnore(lineno=2, tag='[attr]')])");
+    }
+
+    #[test]
+    fn lowering_blank_lines_in_class() {
+        assert_eq!(dump_source("class A:\n    def f(self):\n        pass\n\n    x = 1\n"), "Module(body=[ClassDef(name='A', bases=[], keywords=[], body=[FunctionDef(name='f', args=arguments(posonlyargs=[], args=[arg(arg='self', lineno=2, col_offset=10, end_lineno=2, end_col_offset=14)], kwonlyargs=[], kw_defaults=[], defaults=[]), body=[Pass(lineno=3, col_offset=8, end_lineno=3, end_col_offset=12)], decorator_list=[], lineno=2, col_offset=4, end_lineno=3, end_col_offset=12), Assign(targets=[Name(id='x', ctx=Store(), lineno=5, col_offset=4, end_lineno=5, end_col_offset=5)], value=Constant(value=1, lineno=5, col_offset=8, end_lineno=5, end_col_offset=9), lineno=5, col_offset=4, end_lineno=5, end_col_offset=9)], decorator_list=[], lineno=1, col_offset=0, end_lineno=5, end_col_offset=9)], type_ignores=[])");
+    }
+
+    #[test]
+    fn lowering_mixed_bytes_and_string() {
+        assert_eq!(lower_error("b'a' 'b'\n"), "SyntaxError at 0: cannot mix bytes and nonbytes literals");
+    }
+
+    #[test]
+    fn lowering_format_string_single_brace() {
+        assert_eq!(lower_error("f'a}'\n"), "SyntaxError at 3: f-string: single '}' is not allowed");
+    }
+
+    #[test]
+    fn lowering_parameter_order_errors() {
+        assert_eq!(lower_error("def f(a=1, b): pass\n"), "SyntaxError at 11: non-default argument follows default argument");
+        assert_eq!(lower_error("def f(*, **k): pass\n"), "SyntaxError at 6: named arguments must follow bare *");
+        assert!(dump_source("def f(a, b=1, *, c, d=2): pass\n").contains("kw_defaults=[None, Constant(value=2, lineno=1, col_offset=22"));
+    }
+
+    #[test]
+    fn lowering_unpacking_after_keyword_unpacking() {
+        assert_eq!(lower_error("f(**k, *a)\n"), "SyntaxError at 7: iterable argument unpacking follows keyword argument unpacking");
+    }
+}
@@ -0,0 +1,281 @@
+
+/// Position of an abstract node. 'start' and 'end' are character offsets into source, while
+/// 'lineno', 'col_offset', 'end_lineno' and 'end_col_offset' follows CPython ast module, lines
+/// counted from 1 and columns as UTF-8 byte offset into line.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Location {
+    pub start: u32,
+    pub end: u32,
+    pub lineno: u32,
+    pub col_offset: u32,
+    pub end_lineno: u32,
+    pub end_col_offset: u32
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mod {
+    Module { body: Vec<Stmt>, type_ignores: Vec<TypeIgnore> },
+    Interactive { body: Vec<Stmt> },
+    Expression { body: Box<Expr> },
+    FunctionType { argtypes: Vec<Expr>, returns: Box<Expr> }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeIgnore {
+    pub lineno: u32,
+    pub tag: String
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stmt {
+    pub node: StmtKind,
+    pub location: Location
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StmtKind {
+    FunctionDef { name: String, args: Box<Arguments>, body: Vec<Stmt>, decorator_list: Vec<Expr>, returns: Option<Box<Expr>>, type_comment: Option<String> },
+    AsyncFunctionDef { name: String, args: Box<Arguments>, body: Vec<Stmt>, decorator_list: Vec<Expr>, returns: Option<Box<Expr>>, type_comment: Option<String> },
+    ClassDef { name: String, bases: Vec<Expr>, keywords: Vec<Keyword>, body: Vec<Stmt>, decorator_list: Vec<Expr> },
+    Return { value: Option<Box<Expr>> },
+    Delete { targets: Vec<Expr> },
+    Assign { targets: Vec<Expr>, value: Box<Expr>, type_comment: Option<String> },
+    AugAssign { target: Box<Expr>, op: Operator, value: Box<Expr> },
+    AnnAssign { target: Box<Expr>, annotation: Box<Expr>, value: Option<Box<Expr>>, simple: u32 },
+    For { target: Box<Expr>, iter: Box<Expr>, body: Vec<Stmt>, orelse: Vec<Stmt>, type_comment: Option<String> },
+    AsyncFor { target: Box<Expr>, iter: Box<Expr>, body: Vec<Stmt>, orelse: Vec<Stmt>, type_comment: Option<String> },
+    While { test: Box<Expr>, body: Vec<Stmt>, orelse: Vec<Stmt> },
+    If { test: Box<Expr>, body: Vec<Stmt>, orelse: Vec<Stmt> },
+    With { items: Vec<WithItem>, body: Vec<Stmt>, type_comment: Option<String> },
+    AsyncWith { items: Vec<WithItem>, body: Vec<Stmt>, type_comment: Option<String> },
+    Raise { exc: Option<Box<Expr>>, cause: Option<Box<Expr>> },
+    Match { subject: Box<Expr>, cases: Vec<MatchCase> },
+    Try { body: Vec<Stmt>, handlers: Vec<ExceptHandler>, orelse: Vec<Stmt>, finalbody: Vec<Stmt> },
+    TryStar { body: Vec<Stmt>, handlers: Vec<ExceptHandler>, orelse: Vec<Stmt>, finalbody: Vec<Stmt> },
+    Assert { test: Box<Expr>, msg: Option<Box<Expr>> },
+    Import { names: Vec<Alias> },
+    ImportFrom { module: Option<String>, names: Vec<Alias>, level: u32 },
+    Global { names: Vec<String> },
+    Nonlocal { names: Vec<String> },
+    Expr { value: Box<Expr> },
+    Pass,
+    Break,
+    Continue
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expr {
+    pub node: ExprKind,
+    pub location: Location
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExprKind {
+    BoolOp { op: BoolOperator, values: Vec<Expr> },
+    NamedExpr { target: Box<Expr>, value: Box<Expr> },
+    BinOp { left: Box<Expr>, op: Operator, right: Box<Expr> },
+    UnaryOp { op: UnaryOperator, operand: Box<Expr> },
+    Lambda { args: Box<Arguments>, body: Box<Expr> },
+    IfExp { test: Box<Expr>, body: Box<Expr>, orelse: Box<Expr> },
+    Dict { keys: Vec<Option<Expr>>, values: Vec<Expr> },
+    Set { elts: Vec<Expr> },
+    ListComp { elt: Box<Expr>, generators: Vec<Comprehension> },
+    SetComp { elt: Box<Expr>, generators: Vec<Comprehension> },
+    DictComp { key: Box<Expr>, value: Box<Expr>, generators: Vec<Comprehension> },
+    GeneratorExp { elt: Box<Expr>, generators: Vec<Comprehension> },
+    Await { value: Box<Expr> },
+    Yield { value: Option<Box<Expr>> },
+    YieldFrom { value: Box<Expr> },
+    Compare { left: Box<Expr>, ops: Vec<CmpOperator>, comparators: Vec<Expr> },
+    Call { func: Box<Expr>, args: Vec<Expr>, keywords: Vec<Keyword> },
+    FormattedValue { value: Box<Expr>, conversion: i32, format_spec: Option<Box<Expr>> },
+    JoinedStr { values: Vec<Expr> },
+    Constant { value: Constant, kind: Option<String> },
+    Attribute { value: Box<Expr>, attr: String, ctx: ExprContext },
+    Subscript { value: Box<Expr>, slice: Box<Expr>, ctx: ExprContext },
+    Starred { value: Box<Expr>, ctx: ExprContext },
+    Name { id: String, ctx: ExprContext },
+    List { elts: Vec<Expr>, ctx: ExprContext },
+    Tuple { elts: Vec<Expr>, ctx: ExprContext },
+    Slice { lower: Option<Box<Expr>>, upper: Option<Box<Expr>>, step: Option<Box<Expr>> }
+}
+
+/// Constant values, integers are kept as decimal digits as they have no size limit. Tuples only
+/// come from constant folding, like in CPython.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constant {
+    None,
+    Bool(bool),
+    Str(String),
+    Bytes(Vec<u8>),
+    Int(String),
+    Float(f64),
+    Complex(f64, f64),
+    Tuple(Vec<Constant>),
+    Ellipsis
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExprContext {
+    Load,
+    Store,
+    Del
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoolOperator {
+    And,
+    Or
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mult,
+    MatMult,
+    Div,
+    Mod,
+    Pow,
+    LShift,
+    RShift,
+    BitOr,
+    BitXor,
+    BitAnd,
+    FloorDiv
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Invert,
+    Not,
+    UAdd,
+    USub
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CmpOperator {
+    Eq,
+    NotEq,
+    Lt,
+    LtE,
+    Gt,
+    GtE,
+    Is,
+    IsNot,
+    In,
+    NotIn
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comprehension {
+    pub target: Expr,
+    pub iter: Expr,
+    pub ifs: Vec<Expr>,
+    pub is_async: u32
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExceptHandler {
+    pub type_: Option<Box<Expr>>,
+    pub name: Option<String>,
+    pub body: Vec<Stmt>,
+    pub location: Location
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchCase {
+    pub pattern: Pattern,
+    pub guard: Option<Box<Expr>>,
+    pub body: Vec<Stmt>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub node: PatternKind,
+    pub location: Location
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatternKind {
+    MatchValue { value: Box<Expr> },
+    MatchSingleton { value: Constant },
+    MatchSequence { patterns: Vec<Pattern> },
+    MatchMapping { keys: Vec<Expr>, patterns: Vec<Pattern>, rest: Option<String> },
+    MatchClass { cls: Box<Expr>, patterns: Vec<Pattern>, kwd_attrs: Vec<String>, kwd_patterns: Vec<Pattern> },
+    MatchStar { name: Option<String> },
+    MatchAs { pattern: Option<Box<Pattern>>, name: Option<String> },
+    MatchOr { patterns: Vec<Pattern> }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arguments {
+    pub posonlyargs: Vec<Arg>,
+    pub args: Vec<Arg>,
+    pub vararg: Option<Box<Arg>>,
+    pub kwonlyargs: Vec<Arg>,
+    pub kw_defaults: Vec<Option<Expr>>,
+    pub kwarg: Option<Box<Arg>>,
+    pub defaults: Vec<Expr>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arg {
+    pub arg: String,
+    pub annotation: Option<Box<Expr>>,
+    pub type_comment: Option<String>,
+    pub location: Location
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyword {
+    pub arg: Option<String>,
+    pub value: Expr,
+    pub location: Location
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alias {
+    pub name: String,
+    pub asname: Option<String>,
+    pub location: Location
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WithItem {
+    pub context_expr: Expr,
+    pub optional_vars: Option<Box<Expr>>
+}
+
+impl MatchCase {
+    /// Case matching every subject, that is without guard and with a capture or wildcard pattern,
+    /// possibly inside 'as' or as an alternative of '|'.
+    pub fn is_irrefutable(&self) -> bool {
+        self.guard.is_none() && self.pattern.is_irrefutable()
+    }
+}
+
+impl Pattern {
+    pub fn is_irrefutable(&self) -> bool {
+        match &self.node {
+            PatternKind::MatchAs { pattern: None, .. } => true,
+            PatternKind::MatchAs { pattern: Some( p ), .. } => p.is_irrefutable(),
+            PatternKind::MatchOr { patterns } => patterns.iter().any( |p| p.is_irrefutable() ),
+            _ => false
+        }
+    }
+}
+
+impl Arguments {
+    pub fn empty() -> Arguments {
+        Arguments {
+            posonlyargs: Vec::new(),
+            args: Vec::new(),
+            vararg: None,
+            kwonlyargs: Vec::new(),
+            kw_defaults: Vec::new(),
+            kwarg: None,
+            defaults: Vec::new()
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use crate::ast::nodes::{ Mod, Stmt, StmtKind, Expr, ExprKind, Comprehension, ExceptHandler, MatchCase, Pattern, PatternKind, Arguments, Arg, Keyword, Alias, WithItem };
+
+
+/// Read only visitor over the abstract tree. Default methods walk into all children, so that an
+/// implementation overrides the nodes it cares about and calls the matching 'walk_' function to
+/// continue below them.
+pub trait Visitor {
+    fn visit_mod(&mut self, module: &Mod) {
+        walk_mod(self, module)
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt)
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr)
+    }
+
+    fn visit_arguments(&mut self, arguments: &Arguments) {
+        walk_arguments(self, arguments)
+    }
+
+    fn visit_arg(&mut self, arg: &Arg) {
+        walk_arg(self, arg)
+    }
+
+    fn visit_keyword(&mut self, keyword: &Keyword) {
+        self.visit_expr(&keyword.value)
+    }
+
+    fn visit_alias(&mut self, _alias: &Alias) { }
+
+    fn visit_with_item(&mut self, item: &WithItem) {
+        walk_with_item(self, item)
+    }
+
+    fn visit_except_handler(&mut self, handler: &ExceptHandler) {
+        walk_except_handler(self, handler)
+    }
+
+    fn visit_comprehension(&mut self, comprehension: &Comprehension) {
+        walk_comprehension(self, comprehension)
+    }
+
+    fn visit_match_case(&mut self, case: &MatchCase) {
+        walk_match_case(self, case)
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        walk_pattern(self, pattern)
+    }
+}
+
+
+pub fn walk_mod<V: Visitor + ?Sized>(visitor: &mut V, module: &Mod) {
+    match module {
+        Mod::Module { body, .. } |
+        Mod::Interactive { body } => walk_body(visitor, body),
+        Mod::Expression { body } => visitor.visit_expr(body),
+        Mod::FunctionType { argtypes, returns } => {
+            walk_exprs(visitor, argtypes);
+            visitor.visit_expr(returns)
+        }
+    }
+}
+
+pub fn walk_body<V: Visitor + ?Sized>(visitor: &mut V, body: &[Stmt]) {
+    for stmt in body {
+        visitor.visit_stmt(stmt)
+    }
+}
+
+pub fn walk_exprs<V: Visitor + ?Sized>(visitor: &mut V, exprs: &[Expr]) {
+    for expr in exprs {
+        visitor.visit_expr(expr)
+    }
+}
+
+fn walk_optional<V: Visitor + ?Sized>(visitor: &mut V, expr: &Option<Box<Expr>>) {
+    if let Some( e ) = expr {
+        visitor.visit_expr(e)
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match &stmt.node {
+        StmtKind::FunctionDef { args, body, decorator_list, returns, .. } |
+        StmtKind::AsyncFunctionDef { args, body, decorator_list, returns, .. } => {
+            walk_exprs(visitor, decorator_list);
+            visitor.visit_arguments(args);
+            walk_optional(visitor, returns);
+            walk_body(visitor, body)
+        },
+        StmtKind::ClassDef { bases, keywords, body, decorator_list, .. } => {
+            walk_exprs(visitor, decorator_list);
+            walk_exprs(visitor, bases);
+            for k in keywords {
+                visitor.visit_keyword(k)
+            }
+            walk_body(visitor, body)
+        },
+        StmtKind::Return { value } => walk_optional(visitor, value),
+        StmtKind::Delete { targets } => walk_exprs(visitor, targets),
+        StmtKind::Assign { targets, value, .. } => {
+            visitor.visit_expr(value);
+            walk_exprs(visitor, targets)
+        },
+        StmtKind::AugAssign { target, value, .. } => {
+            visitor.visit_expr(target);
+            visitor.visit_expr(value)
+        },
+        StmtKind::AnnAssign { target, annotation, value, .. } => {
+            visitor.visit_expr(annotation);
+            walk_optional(visitor, value);
+            visitor.visit_expr(target)
+        },
+        StmtKind::For { target, iter, body, orelse, .. } |
+        StmtKind::AsyncFor { target, iter, body, orelse, .. } => {
+            visitor.visit_expr(iter);
+            visitor.visit_expr(target);
+            walk_body(visitor, body);
+            walk_body(visitor, orelse)
+        },
+        StmtKind::While { test, body, orelse } |
+        StmtKind::If { test, body, orelse } => {
+            visitor.visit_expr(test);
+            walk_body(visitor, body);
+            walk_body(visitor, orelse)
+        },
+        StmtKind::With { items, body, .. } |
+        StmtKind::AsyncWith { items, body, .. } => {
+            for item in items {
+                visitor.visit_with_item(item)
+            }
+            walk_body(visitor, body)
+        },
+        StmtKind::Raise { exc, cause } => {
+            walk_optional(visitor, exc);
+            walk_optional(visitor, cause)
+        },
+        StmtKind::Match { subject, cases } => {
+            visitor.visit_expr(subject);
+            for c in cases {
+                visitor.visit_match_case(c)
+            }
+        },
+        StmtKind::Try { body, handlers, orelse, finalbody } |
+        StmtKind::TryStar { body, handlers, orelse, finalbody } => {
+            walk_body(visitor, body);
+            for h in handlers {
+                visitor.visit_except_handler(h)
+            }
+            walk_body(visitor, orelse);
+            walk_body(visitor, finalbody)
+        },
+        StmtKind::Assert { test, msg } => {
+            visitor.visit_expr(test);
+            walk_optional(visitor, msg)
+        },
+        StmtKind::Import { names } |
+        StmtKind::ImportFrom { names, .. } => {
+            for a in names {
+                visitor.visit_alias(a)
+            }
+        },
+        StmtKind::Expr { value } => visitor.visit_expr(value),
+        StmtKind::Global { .. } |
+        StmtKind::Nonlocal { .. } |
+        StmtKind::Pass |
+        StmtKind::Break |
+        StmtKind::Continue => { }
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match &expr.node {
+        ExprKind::BoolOp { values, .. } => walk_exprs(visitor, values),
+        ExprKind::NamedExpr { target, value } => {
+            visitor.visit_expr(value);
+            visitor.visit_expr(target)
+        },
+        ExprKind::BinOp { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right)
+        },
+        ExprKind::UnaryOp { operand, .. } => visitor.visit_expr(operand),
+        ExprKind::Lambda { args, body } => {
+            visitor.visit_arguments(args);
+            visitor.visit_expr(body)
+        },
+        ExprKind::IfExp { test, body, orelse } => {
+            visitor.visit_expr(test);
+            visitor.visit_expr(body);
+            visitor.visit_expr(orelse)
+        },
+        ExprKind::Dict { keys, values } => {
+            for ( k, v ) in keys.iter().zip(values.iter()) {
+                if let Some( k ) = k {
+                    visitor.visit_expr(k)
+                }
+                visitor.visit_expr(v)
+            }
+        },
+        ExprKind::Set { elts } |
+        ExprKind::List { elts, .. } |
+        ExprKind::Tuple { elts, .. } => walk_exprs(visitor, elts),
+        ExprKind::ListComp { elt, generators } |
+        ExprKind::SetComp { elt, generators } |
+        ExprKind::GeneratorExp { elt, generators } => {
+            for g in generators {
+                visitor.visit_comprehension(g)
+            }
+            visitor.visit_expr(elt)
+        },
+        ExprKind::DictComp { key, value, generators } => {
+            for g in generators {
+                visitor.visit_comprehension(g)
+            }
+            visitor.visit_expr(key);
+            visitor.visit_expr(value)
+        },
+        ExprKind::Await { value } |
+        ExprKind::YieldFrom { value } |
+        ExprKind::Attribute { value, .. } |
+        ExprKind::Starred { value, .. } => visitor.visit_expr(value),
+        ExprKind::Yield { value } => walk_optional(visitor, value),
+        ExprKind::Compare { left, comparators, .. } => {
+            visitor.visit_expr(left);
+            walk_exprs(visitor, comparators)
+        },
+        ExprKind::Call { func, args, keywords } => {
+            visitor.visit_expr(func);
+            walk_exprs(visitor, args);
+            for k in keywords {
+                visitor.visit_keyword(k)
+            }
+        },
+        ExprKind::FormattedValue { value, format_spec, .. } => {
+            visitor.visit_expr(value);
+            walk_optional(visitor, format_spec)
+        },
+        ExprKind::JoinedStr { values } => walk_exprs(visitor, values),
+        ExprKind::Subscript { value, slice, .. } => {
+            visitor.visit_expr(value);
+            visitor.visit_expr(slice)
+        },
+        ExprKind::Slice { lower, upper, step } => {
+            walk_optional(visitor, lower);
+            walk_optional(visitor, upper);
+            walk_optional(visitor, step)
+        },
+        ExprKind::Constant { .. } |
+        ExprKind::Name { .. } => { }
+    }
+}
+
+pub fn walk_arguments<V: Visitor + ?Sized>(visitor: &mut V, arguments: &Arguments) {
+    walk_exprs(visitor, &arguments.defaults);
+    for d in arguments.kw_defaults.iter().flatten() {
+        visitor.visit_expr(d)
+    }
+    for a in arguments.posonlyargs.iter().chain(arguments.args.iter()) {
+        visitor.visit_arg(a)
+    }
+    if let Some( a ) = &arguments.vararg {
+        visitor.visit_arg(a)
+    }
+    for a in &arguments.kwonlyargs {
+        visitor.visit_arg(a)
+    }
+    if let Some( a ) = &arguments.kwarg {
+        visitor.visit_arg(a)
+    }
+}
+
+pub fn walk_arg<V: Visitor + ?Sized>(visitor: &mut V, arg: &Arg) {
+    walk_optional(visitor, &arg.annotation)
+}
+
+pub fn walk_with_item<V: Visitor + ?Sized>(visitor: &mut V, item: &WithItem) {
+    visitor.visit_expr(&item.context_expr);
+    walk_optional(visitor, &item.optional_vars)
+}
+
+pub fn walk_except_handler<V: Visitor + ?Sized>(visitor: &mut V, handler: &ExceptHandler) {
+    walk_optional(visitor, &handler.type_);
+    walk_body(visitor, &handler.body)
+}
+
+pub fn walk_comprehension<V: Visitor + ?Sized>(visitor: &mut V, comprehension: &Comprehension) {
+    visitor.visit_expr(&comprehension.iter);
+    visitor.visit_expr(&comprehension.target);
+    walk_exprs(visitor, &comprehension.ifs)
+}
+
+pub fn walk_match_case<V: Visitor + ?Sized>(visitor: &mut V, case: &MatchCase) {
+    visitor.visit_pattern(&case.pattern);
+    walk_optional(visitor, &case.guard);
+    walk_body(visitor, &case.body)
+}
+
+pub fn walk_pattern<V: Visitor + ?Sized>(visitor: &mut V, pattern: &Pattern) {
+    match &pattern.node {
+        PatternKind::MatchValue { value } => visitor.visit_expr(value),
+        PatternKind::MatchSingleton { .. } |
+        PatternKind::MatchStar { .. } => { },
+        PatternKind::MatchSequence { patterns } |
+        PatternKind::MatchOr { patterns } => walk_patterns(visitor, patterns),
+        PatternKind::MatchMapping { keys, patterns, .. } => {
+            walk_exprs(visitor, keys);
+            walk_patterns(visitor, patterns)
+        },
+        PatternKind::MatchClass { cls, patterns, kwd_patterns, .. } => {
+            visitor.visit_expr(cls);
+            walk_patterns(visitor, patterns);
+            walk_patterns(visitor, kwd_patterns)
+        },
+        PatternKind::MatchAs { pattern, .. } => {
+            if let Some( p ) = pattern {
+                visitor.visit_pattern(p)
+            }
+        }
+    }
+}
+
+fn walk_patterns<V: Visitor + ?Sized>(visitor: &mut V, patterns: &[Pattern]) {
+    for p in patterns {
+        visitor.visit_pattern(p)
+    }
+}
@@ -0,0 +1,5 @@
+pub mod code;
+pub mod compiler;
+pub mod dis;
+pub mod optimizer;
+pub mod vm;
@@ -0,0 +1,420 @@
+use std::rc::Rc;
+use crate::ast::nodes::{ Operator, CmpOperator };
+use crate::runtime::bigint::Int;
+use crate::runtime::format::{ float_repr, str_repr, bytes_repr };
+
+
+pub const CO_OPTIMIZED: u32 = 0x01;
+pub const CO_NEWLOCALS: u32 = 0x02;
+pub const CO_VARARGS: u32 = 0x04;
+pub const CO_VARKEYWORDS: u32 = 0x08;
+pub const CO_NESTED: u32 = 0x10;
+pub const CO_GENERATOR: u32 = 0x20;
+
+/// Flags of 'MAKE_FUNCTION', telling which values are on the stack below the code object.
+pub const MAKE_DEFAULTS: u32 = 0x01;
+pub const MAKE_KWDEFAULTS: u32 = 0x02;
+pub const MAKE_CLOSURE: u32 = 0x08;
+
+/// Flags of 'FORMAT_VALUE', the low bits select the conversion.
+pub const FORMAT_STR: u32 = 0x01;
+pub const FORMAT_REPR: u32 = 0x02;
+pub const FORMAT_ASCII: u32 = 0x03;
+pub const FORMAT_SPEC: u32 = 0x04;
+
+/// Operations of the stack machine, named after their CPython 3.12 counterparts. The argument of
+/// jumps is the index of the target instruction. 'SETUP_FINALLY', 'SETUP_CLEANUP' and 'POP_BLOCK'
+/// are pseudo instructions that only exist while compiling, the assembler turns them into the
+/// exception table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Nop,
+    PopTop,
+    Copy,
+    Swap,
+    LoadConst,
+    LoadName,
+    StoreName,
+    DeleteName,
+    LoadGlobal,
+    StoreGlobal,
+    DeleteGlobal,
+    LoadFast,
+    StoreFast,
+    DeleteFast,
+    LoadDeref,
+    StoreDeref,
+    DeleteDeref,
+    LoadClosure,
+    LoadClassDeref,
+    LoadAttr,
+    StoreAttr,
+    DeleteAttr,
+    BinarySubscr,
+    StoreSubscr,
+    DeleteSubscr,
+    BinaryOp,
+    UnaryNegative,
+    UnaryPositive,
+    UnaryInvert,
+    UnaryNot,
+    CompareOp,
+    IsOp,
+    ContainsOp,
+    BuildTuple,
+    BuildList,
+    BuildSet,
+    BuildMap,
+    BuildSlice,
+    BuildString,
+    ListAppend,
+    SetAdd,
+    MapAdd,
+    ListExtend,
+    SetUpdate,
+    DictUpdate,
+    DictMerge,
+    ListToTuple,
+    FormatValue,
+    UnpackSequence,
+    UnpackEx,
+    Jump,
+    PopJumpIfFalse,
+    PopJumpIfTrue,
+    GetIter,
+    ForIter,
+    ReturnValue,
+    PrintExpr,
+    YieldValue,
+    GetYieldFromIter,
+    Send,
+    RaiseVarargs,
+    Reraise,
+    PushExcInfo,
+    PopExcept,
+    CheckExcMatch,
+    LoadAssertionError,
+    BeforeWith,
+    WithExceptStart,
+    MakeFunction,
+    LoadBuildClass,
+    Call,
+    CallKw,
+    CallFunctionEx,
+    ImportName,
+    ImportFrom,
+    ImportStar,
+    SetupFinally,
+    SetupCleanup,
+    PopBlock
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub arg: u32
+}
+
+/// Constant of a code object, nested functions, classes and comprehensions appear as code constants.
+#[derive(Clone, Debug)]
+pub enum Const {
+    None,
+    Ellipsis,
+    Bool(bool),
+    Int(Int),
+    Float(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    Tuple(Vec<Const>),
+    Code(Rc<CodeObject>)
+}
+
+/// Instructions from 'start' up to but not including 'end' are protected by the handler at 'target'.
+/// When an exception is raised there, the value stack is cut to 'depth' and the exception pushed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExceptionEntry {
+    pub start: u32,
+    pub end: u32,
+    pub target: u32,
+    pub depth: u32
+}
+
+/// Compiled body of a module, class, function, lambda or comprehension. Local variables are found
+/// by index in 'varnames', variables shared with nested scopes in 'cellvars' followed by 'freevars',
+/// other names in 'names'. Entries of 'line_table' give the line of instructions from their index
+/// on up to the next entry.
+#[derive(Clone, Debug)]
+pub struct CodeObject {
+    pub name: String,
+    pub qualname: String,
+    pub filename: String,
+    pub first_line: u32,
+    pub argcount: u32,
+    pub posonlyargcount: u32,
+    pub kwonlyargcount: u32,
+    pub flags: u32,
+    pub stacksize: u32,
+    pub instructions: Vec<Instruction>,
+    pub constants: Vec<Const>,
+    pub names: Vec<String>,
+    pub varnames: Vec<String>,
+    pub cellvars: Vec<String>,
+    pub freevars: Vec<String>,
+    pub line_table: Vec<( u32, u32 )>,
+    pub exception_table: Vec<ExceptionEntry>
+}
+
+
+/// Arguments of 'BINARY_OP' in CPython order, in place variants follow at offset 13.
+const BINARY_OPS: [( Operator, &str ); 13] = [
+    ( Operator::Add, "+" ), ( Operator::BitAnd, "&" ), ( Operator::FloorDiv, "//" ), ( Operator::LShift, "<<" ),
+    ( Operator::MatMult, "@" ), ( Operator::Mult, "*" ), ( Operator::Mod, "%" ), ( Operator::BitOr, "|" ),
+    ( Operator::Pow, "**" ), ( Operator::RShift, ">>" ), ( Operator::Sub, "-" ), ( Operator::Div, "/" ),
+    ( Operator::BitXor, "^" )
+];
+
+const COMPARE_OPS: [( CmpOperator, &str ); 6] = [
+    ( CmpOperator::Lt, "<" ), ( CmpOperator::LtE, "<=" ), ( CmpOperator::Eq, "==" ),
+    ( CmpOperator::NotEq, "!=" ), ( CmpOperator::Gt, ">" ), ( CmpOperator::GtE, ">=" )
+];
+
+pub fn binary_op_arg(op: Operator, inplace: bool) -> u32 {
+    let index = BINARY_OPS.iter().position( |( o, _ )| *o == op ).unwrap_or(0) as u32;
+    if inplace { index + 13 } else { index }
+}
+
+/// Operator of 'BINARY_OP' argument and whether it is the in place variant.
+pub fn binary_op_of(arg: u32) -> ( Operator, bool ) {
+    ( BINARY_OPS[arg as usize % 13].0, arg >= 13 )
+}
+
+pub fn compare_op_arg(op: CmpOperator) -> u32 {
+    COMPARE_OPS.iter().position( |( o, _ )| *o == op ).unwrap_or(0) as u32
+}
+
+pub fn compare_op_of(arg: u32) -> CmpOperator {
+    COMPARE_OPS[arg as usize].0
+}
+
+/// Operator symbol shown by the disassembler for 'BINARY_OP'.
+pub fn binary_op_symbol(arg: u32) -> String {
+    let symbol = BINARY_OPS[arg as usize % 13].1;
+    if arg >= 13 { format!("{}=", symbol) } else { symbol.to_string() }
+}
+
+pub fn compare_op_symbol(arg: u32) -> &'static str {
+    COMPARE_OPS[arg as usize].1
+}
+
+impl Opcode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Opcode::Nop => "NOP",
+            Opcode::PopTop => "POP_TOP",
+            Opcode::Copy => "COPY",
+            Opcode::Swap => "SWAP",
+            Opcode::LoadConst => "LOAD_CONST",
+            Opcode::LoadName => "LOAD_NAME",
+            Opcode::StoreName => "STORE_NAME",
+            Opcode::DeleteName => "DELETE_NAME",
+            Opcode::LoadGlobal => "LOAD_GLOBAL",
+            Opcode::StoreGlobal => "STORE_GLOBAL",
+            Opcode::DeleteGlobal => "DELETE_GLOBAL",
+            Opcode::LoadFast => "LOAD_FAST",
+            Opcode::StoreFast => "STORE_FAST",
+            Opcode::DeleteFast => "DELETE_FAST",
+            Opcode::LoadDeref => "LOAD_DEREF",
+            Opcode::StoreDeref => "STORE_DEREF",
+            Opcode::DeleteDeref => "DELETE_DEREF",
+            Opcode::LoadClosure => "LOAD_CLOSURE",
+            Opcode::LoadClassDeref => "LOAD_CLASSDEREF",
+            Opcode::LoadAttr => "LOAD_ATTR",
+            Opcode::StoreAttr => "STORE_ATTR",
+            Opcode::DeleteAttr => "DELETE_ATTR",
+            Opcode::BinarySubscr => "BINARY_SUBSCR",
+            Opcode::StoreSubscr => "STORE_SUBSCR",
+            Opcode::DeleteSubscr => "DELETE_SUBSCR",
+            Opcode::BinaryOp => "BINARY_OP",
+            Opcode::UnaryNegative => "UNARY_NEGATIVE",
+            Opcode::UnaryPositive => "UNARY_POSITIVE",
+            Opcode::UnaryInvert => "UNARY_INVERT",
+            Opcode::UnaryNot => "UNARY_NOT",
+            Opcode::CompareOp => "COMPARE_OP",
+            Opcode::IsOp => "IS_OP",
+            Opcode::ContainsOp => "CONTAINS_OP",
+            Opcode::BuildTuple => "BUILD_TUPLE",
+            Opcode::BuildList => "BUILD_LIST",
+            Opcode::BuildSet => "BUILD_SET",
+            Opcode::BuildMap => "BUILD_MAP",
+            Opcode::BuildSlice => "BUILD_SLICE",
+            Opcode::BuildString => "BUILD_STRING",
+            Opcode::ListAppend => "LIST_APPEND",
+            Opcode::SetAdd => "SET_ADD",
+            Opcode::MapAdd => "MAP_ADD",
+            Opcode::ListExtend => "LIST_EXTEND",
+            Opcode::SetUpdate => "SET_UPDATE",
+            Opcode::DictUpdate => "DICT_UPDATE",
+            Opcode::DictMerge => "DICT_MERGE",
+            Opcode::ListToTuple => "LIST_TO_TUPLE",
+            Opcode::FormatValue => "FORMAT_VALUE",
+            Opcode::UnpackSequence => "UNPACK_SEQUENCE",
+            Opcode::UnpackEx => "UNPACK_EX",
+            Opcode::Jump => "JUMP",
+            Opcode::PopJumpIfFalse => "POP_JUMP_IF_FALSE",
+            Opcode::PopJumpIfTrue => "POP_JUMP_IF_TRUE",
+            Opcode::GetIter => "GET_ITER",
+            Opcode::ForIter => "FOR_ITER",
+            Opcode::ReturnValue => "RETURN_VALUE",
+            Opcode::PrintExpr => "PRINT_EXPR",
+            Opcode::YieldValue => "YIELD_VALUE",
+            Opcode::GetYieldFromIter => "GET_YIELD_FROM_ITER",
+            Opcode::Send => "SEND",
+            Opcode::RaiseVarargs => "RAISE_VARARGS",
+            Opcode::Reraise => "RERAISE",
+            Opcode::PushExcInfo => "PUSH_EXC_INFO",
+            Opcode::PopExcept => "POP_EXCEPT",
+            Opcode::CheckExcMatch => "CHECK_EXC_MATCH",
+            Opcode::LoadAssertionError => "LOAD_ASSERTION_ERROR",
+            Opcode::BeforeWith => "BEFORE_WITH",
+            Opcode::WithExceptStart => "WITH_EXCEPT_START",
+            Opcode::MakeFunction => "MAKE_FUNCTION",
+            Opcode::LoadBuildClass => "LOAD_BUILD_CLASS",
+            Opcode::Call => "CALL",
+            Opcode::CallKw => "CALL_KW",
+            Opcode::CallFunctionEx => "CALL_FUNCTION_EX",
+            Opcode::ImportName => "IMPORT_NAME",
+            Opcode::ImportFrom => "IMPORT_FROM",
+            Opcode::ImportStar => "IMPORT_STAR",
+            Opcode::SetupFinally => "SETUP_FINALLY",
+            Opcode::SetupCleanup => "SETUP_CLEANUP",
+            Opcode::PopBlock => "POP_BLOCK"
+        }
+    }
+
+    /// Whether the argument is an instruction index.
+    pub fn is_jump(&self) -> bool {
+        matches!(self, Opcode::Jump | Opcode::PopJumpIfFalse | Opcode::PopJumpIfTrue | Opcode::ForIter | Opcode::Send |
+                       Opcode::SetupFinally | Opcode::SetupCleanup)
+    }
+
+    pub fn is_pseudo(&self) -> bool {
+        matches!(self, Opcode::SetupFinally | Opcode::SetupCleanup | Opcode::PopBlock)
+    }
+
+    /// Whether execution never continues with the next instruction.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Opcode::Jump | Opcode::ReturnValue | Opcode::RaiseVarargs | Opcode::Reraise)
+    }
+
+    /// Whether the instruction takes an argument, shown by the disassembler.
+    pub fn has_arg(&self) -> bool {
+        !matches!(self, Opcode::Nop | Opcode::PopTop | Opcode::BinarySubscr | Opcode::StoreSubscr | Opcode::DeleteSubscr |
+                        Opcode::UnaryNegative | Opcode::UnaryPositive | Opcode::UnaryInvert | Opcode::UnaryNot |
+                        Opcode::ListToTuple | Opcode::GetIter | Opcode::ReturnValue | Opcode::PrintExpr | Opcode::GetYieldFromIter |
+                        Opcode::PushExcInfo | Opcode::PopExcept | Opcode::CheckExcMatch | Opcode::LoadAssertionError |
+                        Opcode::BeforeWith | Opcode::WithExceptStart | Opcode::LoadBuildClass | Opcode::ImportStar |
+                        Opcode::PopBlock)
+    }
+
+    /// Change of stack depth, 'jump' selects the effect when the jump is taken. The jump of the
+    /// setup pseudo instructions is to their handler, reached with the exception pushed.
+    pub fn stack_effect(&self, arg: u32, jump: bool) -> i32 {
+        let n = arg as i32;
+        match self {
+            Opcode::Nop | Opcode::Swap | Opcode::DeleteName | Opcode::DeleteGlobal | Opcode::DeleteFast | Opcode::DeleteDeref |
+            Opcode::LoadAttr | Opcode::UnaryNegative | Opcode::UnaryPositive | Opcode::UnaryInvert | Opcode::UnaryNot |
+            Opcode::ListToTuple | Opcode::Jump | Opcode::GetIter | Opcode::YieldValue | Opcode::GetYieldFromIter |
+            Opcode::CheckExcMatch | Opcode::PopBlock => 0,
+            Opcode::Copy | Opcode::LoadConst | Opcode::LoadName | Opcode::LoadGlobal | Opcode::LoadFast | Opcode::LoadDeref |
+            Opcode::LoadClosure | Opcode::LoadClassDeref | Opcode::PushExcInfo | Opcode::LoadAssertionError |
+            Opcode::BeforeWith | Opcode::WithExceptStart | Opcode::LoadBuildClass | Opcode::ImportFrom => 1,
+            Opcode::PopTop | Opcode::StoreName | Opcode::StoreGlobal | Opcode::StoreFast | Opcode::StoreDeref |
+            Opcode::DeleteAttr | Opcode::BinarySubscr | Opcode::BinaryOp | Opcode::CompareOp | Opcode::IsOp |
+            Opcode::ContainsOp | Opcode::ListAppend | Opcode::SetAdd | Opcode::ListExtend | Opcode::SetUpdate |
+            Opcode::DictUpdate | Opcode::DictMerge | Opcode::PopJumpIfFalse | Opcode::PopJumpIfTrue |
+            Opcode::ReturnValue | Opcode::PrintExpr | Opcode::Reraise | Opcode::PopExcept | Opcode::ImportName | Opcode::ImportStar => -1,
+            Opcode::StoreAttr | Opcode::DeleteSubscr | Opcode::MapAdd => -2,
+            Opcode::StoreSubscr => -3,
+            Opcode::BuildTuple | Opcode::BuildList | Opcode::BuildSet | Opcode::BuildSlice | Opcode::BuildString => 1 - n,
+            Opcode::BuildMap => 1 - 2 * n,
+            Opcode::FormatValue => if arg & FORMAT_SPEC != 0 { -1 } else { 0 },
+            Opcode::UnpackSequence => n - 1,
+            Opcode::UnpackEx => ( n & 0xff ) + ( n >> 8 ),
+            Opcode::ForIter => if jump { -1 } else { 1 },
+            Opcode::Send => if jump { -1 } else { 0 },
+            Opcode::RaiseVarargs => -n,
+            Opcode::MakeFunction => -( arg.count_ones() as i32 ),
+            Opcode::Call => -n,
+            Opcode::CallKw => -n - 1,
+            Opcode::CallFunctionEx => -1 - ( n & 1 ),
+            Opcode::SetupFinally | Opcode::SetupCleanup => if jump { 1 } else { 0 }
+        }
+    }
+}
+
+impl Const {
+    /// Whether constants are interchangeable, as CPython merges them. Numbers of different type
+    /// and floats of different sign or 'nan' payload stay apart, code objects are never merged.
+    pub fn same(&self, other: &Const) -> bool {
+        match ( self, other ) {
+            ( Const::None, Const::None ) | ( Const::Ellipsis, Const::Ellipsis ) => true,
+            ( Const::Bool(a), Const::Bool(b) ) => a == b,
+            ( Const::Int(a), Const::Int(b) ) => a == b,
+            ( Const::Float(a), Const::Float(b) ) => a.to_bits() == b.to_bits(),
+            ( Const::Str(a), Const::Str(b) ) => a == b,
+            ( Const::Bytes(a), Const::Bytes(b) ) => a == b,
+            ( Const::Tuple(a), Const::Tuple(b) ) => a.len() == b.len() && a.iter().zip(b).all( |( x, y )| x.same(y) ),
+            ( Const::Code(a), Const::Code(b) ) => Rc::ptr_eq(a, b),
+            _ => false
+        }
+    }
+
+    pub fn repr(&self) -> String {
+        match self {
+            Const::None => "None".to_string(),
+            Const::Ellipsis => "Ellipsis".to_string(),
+            Const::Bool(true) => "True".to_string(),
+            Const::Bool(false) => "False".to_string(),
+            Const::Int(i) => i.to_string(),
+            Const::Float(f) => float_repr(*f),
+            Const::Str(s) => str_repr(s),
+            Const::Bytes(b) => bytes_repr(b),
+            Const::Tuple(t) if t.len() == 1 => format!("({},)", t[0].repr()),
+            Const::Tuple(t) => format!("({})", t.iter().map( |c| c.repr() ).collect::<Vec<String>>().join(", ")),
+            Const::Code(c) => c.to_string()
+        }
+    }
+}
+
+impl CodeObject {
+    pub fn line_of(&self, index: usize) -> u32 {
+        match self.line_table.partition_point( |( start, _ )| *start as usize <= index ) {
+            0 => self.first_line,
+            k => self.line_table[k - 1].1
+        }
+    }
+
+    /// Innermost handler protecting instruction.
+    pub fn handler_of(&self, index: usize) -> Option<&ExceptionEntry> {
+        self.exception_table.iter().find( |e| e.start as usize <= index && index < e.end as usize )
+    }
+
+    pub fn is_generator(&self) -> bool {
+        self.flags & CO_GENERATOR != 0
+    }
+
+    /// Name of variable shared with nested scopes, by index of 'LOAD_DEREF' and related instructions.
+    pub fn deref_name(&self, index: u32) -> &str {
+        let index = index as usize;
+        match self.cellvars.get(index) {
+            Some( name ) => name,
+            None => &self.freevars[index - self.cellvars.len()]
+        }
+    }
+}
+
+impl std::fmt::Display for CodeObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<code object {}, file \"{}\", line {}>", self.name, self.filename, self.first_line)
+    }
+}
@@ -0,0 +1,1622 @@
+use std::rc::Rc;
+use crate::ast::nodes::{ Mod, Stmt, StmtKind, Expr, ExprKind, ExprContext, Constant, Comprehension, Arguments, Keyword, ExceptHandler,
+                         WithItem, Alias, BoolOperator, UnaryOperator, CmpOperator };
+use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+use crate::api::{ Mode, parse };
+use crate::semantic::symbols::{ SymbolTable, ScopeId, ScopeKind, SymbolScope };
+use crate::runtime::bigint::Int;
+use crate::bytecode::optimizer::PythonCoreOptimizer;
+use crate::bytecode::code::{ Opcode, Instruction, Const, CodeObject, ExceptionEntry, binary_op_arg, compare_op_arg,
+                             CO_OPTIMIZED, CO_NEWLOCALS, CO_VARARGS, CO_VARKEYWORDS, CO_NESTED, CO_GENERATOR,
+                             MAKE_DEFAULTS, MAKE_KWDEFAULTS, MAKE_CLOSURE, FORMAT_STR, FORMAT_REPR, FORMAT_ASCII, FORMAT_SPEC };
+
+
+type Label = u32;
+
+/// Handler target and stack depth of each active setup block, innermost last.
+type HandlerStack = Vec<( u32, u32 )>;
+
+/// Construct enclosing the code being compiled that 'return', 'break' and 'continue' leave early,
+/// as CPython's frame blocks. Leaving one emits what its normal end would do.
+#[derive(Clone, Copy)]
+enum FrameBlock<'a> {
+    WhileLoop { start: Label, end: Label },
+    /// Loop with its iterator on the stack.
+    ForLoop { start: Label, end: Label },
+    TryExcept,
+    /// Body of 'try' with the 'finally' block that runs when leaving it.
+    FinallyTry(&'a [Stmt]),
+    /// 'finally' block run for an exception, with the previous and the current exception on the stack.
+    FinallyEnd,
+    /// Body of 'with' statement with the '__exit__' method on the stack.
+    With,
+    /// Body of 'except' clause with the previous exception on the stack and the name it binds.
+    Handler(Option<&'a str>),
+    /// 'finally' block run for 'return' with the returned value on the stack.
+    PopValue
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    Load,
+    Store,
+    Delete
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ComprehensionKind {
+    List,
+    Set,
+    Dict,
+    Generator
+}
+
+/// Code object being compiled for a scope.
+struct Unit<'a> {
+    scope: ScopeId,
+    kind: ScopeKind,
+    name: String,
+    qualname: String,
+    first_line: u32,
+    argcount: u32,
+    posonlyargcount: u32,
+    kwonlyargcount: u32,
+    flags: u32,
+    /// Instructions with their line, jumps refer to labels until assembled.
+    instructions: Vec<( Instruction, u32 )>,
+    labels: Vec<Option<usize>>,
+    constants: Vec<Const>,
+    names: Vec<String>,
+    varnames: Vec<String>,
+    cellvars: Vec<String>,
+    freevars: Vec<String>,
+    blocks: Vec<FrameBlock<'a>>,
+    line: u32
+}
+
+/// Compiler of the lowered abstract tree to code objects for a stack machine modeled on CPython.
+/// Names are resolved with the symbol table, which decides between fast locals, cells, globals
+/// and name lookups the way CPython does.
+pub struct PythonCoreCompiler<'a> {
+    symbols: &'a SymbolTable,
+    filename: String,
+    /// Module is interactive input, where expression statements of the module print their value.
+    interactive: bool,
+    units: Vec<Unit<'a>>
+}
+
+
+/// Parses, lowers, optimizes at level and compiles module source. Constants are folded at every
+/// level, like CPython does.
+pub fn compile_source(source: &str, filename: &str, optimize: u32) -> Result<Rc<CodeObject>, String> {
+    compile(source, filename, Mode::Exec, optimize)
+}
+
+/// Like CPython 'compile', mode 'single' compiles one interactive statement that prints the
+/// values of its expression statements.
+pub fn compile(source: &str, filename: &str, mode: Mode, optimize: u32) -> Result<Rc<CodeObject>, String> {
+    let res = parse(source, mode);
+    let tree = match ( res.tree, res.diagnostics.iter().find( |d| d.is_error() ) ) {
+        ( Some( t ), None ) => t,
+        ( _, Some( d ) ) => return Err( format!("SyntaxError at {}: {}", d.start, d.message) ),
+        ( None, None ) => return Err( "SyntaxError at 0: invalid syntax".to_string() )
+    };
+    let mut module = PythonCoreLowering::new(source).lower_mod(&tree)?;
+    PythonCoreOptimizer::new(source, optimize).optimize_mod(&mut module);
+    let symbols = SymbolTable::build(&module, source);
+    PythonCoreCompiler::new(&symbols, filename).compile_module(&module)
+}
+
+fn is_docstring(body: &[Stmt]) -> Option<&str> {
+    match body.first().map( |s| &s.node ) {
+        Some( StmtKind::Expr { value } ) => match &value.node {
+            ExprKind::Constant { value: Constant::Str(s), .. } => Some( s ),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+fn has_starred(exprs: &[Expr]) -> bool {
+    exprs.iter().any( |e| matches!(e.node, ExprKind::Starred { .. }) )
+}
+
+fn index_of(list: &mut Vec<String>, name: &str) -> u32 {
+    match list.iter().position( |n| n == name ) {
+        Some( i ) => i as u32,
+        None => {
+            list.push(name.to_string());
+            list.len() as u32 - 1
+        }
+    }
+}
+
+fn unsupported<T>(start: u32, what: &str) -> Result<T, String> {
+    Err( format!("SyntaxError at {}: {} not supported by the bytecode compiler", start, what) )
+}
+
+impl<'a> PythonCoreCompiler<'a> {
+    pub fn new(symbols: &'a SymbolTable, filename: &str) -> PythonCoreCompiler<'a> {
+        PythonCoreCompiler { symbols, filename: filename.to_string(), interactive: false, units: Vec::new() }
+    }
+
+    /// Code object of module, expressions evaluate to their value.
+    pub fn compile_module(&mut self, module: &'a Mod) -> Result<Rc<CodeObject>, String> {
+        self.enter_unit(0, "<module>", "<module>".to_string(), 1);
+        match module {
+            Mod::Module { body, .. } | Mod::Interactive { body } => {
+                if let Some( first ) = body.first() {
+                    self.unit().first_line = first.location.lineno
+                }
+                self.interactive = matches!(module, Mod::Interactive { .. });
+                if !self.interactive {
+                    self.docstring(body)
+                }
+                self.body(body)?;
+                self.load_const(Const::None);
+                self.emit(Opcode::ReturnValue, 0)
+            },
+            Mod::Expression { body } => {
+                self.unit().first_line = body.location.lineno;
+                self.expr(body)?;
+                self.emit(Opcode::ReturnValue, 0)
+            },
+            Mod::FunctionType { .. } => return unsupported(0, "function type input is")
+        }
+        Ok( Rc::new( self.leave_unit() ) )
+    }
+
+    fn unit(&mut self) -> &mut Unit<'a> {
+        self.units.last_mut().expect("unit")
+    }
+
+    fn scope_kind(&self) -> ScopeKind {
+        self.units.last().map( |u| u.kind ).unwrap_or(ScopeKind::Module)
+    }
+
+    /// Starts code object of scope, its locals are parameters in CPython order followed by the other
+    /// local variables, and its cells and free variables are sorted by name.
+    fn enter_unit(&mut self, scope: ScopeId, name: &str, qualname: String, first_line: u32) {
+        let info = self.symbols.scope(scope);
+        let mut varnames : Vec<String> = Vec::new();
+        let mut cellvars : Vec<String> = Vec::new();
+        let mut freevars : Vec<String> = Vec::new();
+        if info.is_function_like() {
+            varnames.extend(info.symbols.iter().filter( |s| s.is_parameter ).map( |s| s.name.clone() ));
+            varnames.extend(info.symbols.iter().filter( |s| !s.is_parameter && s.scope == SymbolScope::Local ).map( |s| s.name.clone() ));
+            cellvars.extend(info.symbols.iter().filter( |s| s.scope == SymbolScope::Cell ).map( |s| s.name.clone() ));
+        }
+        if info.kind == ScopeKind::Class && info.needs_class_closure {
+            cellvars.push("__class__".to_string())
+        }
+        if info.kind != ScopeKind::Module {
+            freevars.extend(info.symbols.iter().filter( |s| matches!(s.scope, SymbolScope::Free | SymbolScope::Nonlocal) ).map( |s| s.name.clone() ))
+        }
+        cellvars.sort();
+        freevars.sort();
+        let nested = self.units.last().map( |u| u.kind != ScopeKind::Module && u.kind != ScopeKind::Class ).unwrap_or(false);
+        let mut flags = if info.is_function_like() { CO_OPTIMIZED | CO_NEWLOCALS } else { 0 };
+        if nested {
+            flags |= CO_NESTED
+        }
+        if info.is_generator {
+            flags |= CO_GENERATOR
+        }
+        self.units.push(Unit {
+            scope,
+            kind: info.kind,
+            name: name.to_string(),
+            qualname,
+            first_line,
+            argcount: 0,
+            posonlyargcount: 0,
+            kwonlyargcount: 0,
+            flags,
+            instructions: Vec::new(),
+            labels: Vec::new(),
+            constants: Vec::new(),
+            names: Vec::new(),
+            varnames,
+            cellvars,
+            freevars,
+            blocks: Vec::new(),
+            line: first_line
+        })
+    }
+
+    fn leave_unit(&mut self) -> CodeObject {
+        let unit = self.units.pop().expect("unit");
+        assemble(unit, &self.filename)
+    }
+
+    /// Qualified name of function or class defined in the current unit.
+    fn qualname(&self, name: &str) -> String {
+        match self.units.last() {
+            Some( u ) if u.kind == ScopeKind::Class => format!("{}.{}", u.qualname, name),
+            Some( u ) if u.kind != ScopeKind::Module => format!("{}.<locals>.{}", u.qualname, name),
+            _ => name.to_string()
+        }
+    }
+
+    fn child_scope(&self, span: ( u32, u32 )) -> Result<ScopeId, String> {
+        match self.symbols.scope_of_node(span) {
+            Some( s ) => Ok( s ),
+            None => Err( format!("SyntaxError at {}: no scope for node in symbol table", span.0) )
+        }
+    }
+
+
+    // Emitting ////////////////////////////////////////////////////////////////////////////////////
+
+    fn emit(&mut self, opcode: Opcode, arg: u32) {
+        let unit = self.unit();
+        let line = unit.line;
+        unit.instructions.push(( Instruction { opcode, arg }, line ))
+    }
+
+    fn new_label(&mut self) -> Label {
+        let unit = self.unit();
+        unit.labels.push(None);
+        unit.labels.len() as Label - 1
+    }
+
+    fn bind(&mut self, label: Label) {
+        let unit = self.unit();
+        unit.labels[label as usize] = Some( unit.instructions.len() )
+    }
+
+    fn const_index(&mut self, value: Const) -> u32 {
+        let constants = &mut self.unit().constants;
+        match constants.iter().position( |c| c.same(&value) ) {
+            Some( i ) => i as u32,
+            None => {
+                constants.push(value);
+                constants.len() as u32 - 1
+            }
+        }
+    }
+
+    fn load_const(&mut self, value: Const) {
+        let index = self.const_index(value);
+        self.emit(Opcode::LoadConst, index)
+    }
+
+    fn name_index(&mut self, name: &str) -> u32 {
+        index_of(&mut self.unit().names, name)
+    }
+
+    fn deref_index(&mut self, name: &str) -> u32 {
+        let unit = self.unit();
+        match unit.cellvars.iter().position( |n| n == name ) {
+            Some( i ) => i as u32,
+            None => unit.cellvars.len() as u32 + index_of(&mut unit.freevars, name)
+        }
+    }
+
+    fn set_line(&mut self, line: u32) {
+        self.unit().line = line
+    }
+
+    /// Emits load, store or delete of name with the instruction its scope calls for.
+    fn name_op(&mut self, name: &str, context: Context) {
+        let scope = self.units.last().map( |u| u.scope ).unwrap_or(0);
+        let kind = self.scope_kind();
+        let function_like = matches!(kind, ScopeKind::Function | ScopeKind::Lambda | ScopeKind::Comprehension);
+        let symbol = self.symbols.lookup(scope, name);
+        let ( symbol_scope, declared_global ) = match symbol {
+            Some( s ) => ( Some( s.scope ), s.is_declared_global ),
+            None => ( None, false )
+        };
+        let ( load, store, delete, index ) = match symbol_scope {
+            Some( SymbolScope::Free | SymbolScope::Nonlocal | SymbolScope::Cell ) => {
+                let load = if kind == ScopeKind::Class { Opcode::LoadClassDeref } else { Opcode::LoadDeref };
+                ( load, Opcode::StoreDeref, Opcode::DeleteDeref, self.deref_index(name) )
+            },
+            Some( SymbolScope::Local ) if function_like => ( Opcode::LoadFast, Opcode::StoreFast, Opcode::DeleteFast, index_of(&mut self.unit().varnames, name) ),
+            Some( SymbolScope::Global ) if declared_global || function_like => ( Opcode::LoadGlobal, Opcode::StoreGlobal, Opcode::DeleteGlobal, self.name_index(name) ),
+            None if function_like => ( Opcode::LoadGlobal, Opcode::StoreGlobal, Opcode::DeleteGlobal, self.name_index(name) ),
+            _ => ( Opcode::LoadName, Opcode::StoreName, Opcode::DeleteName, self.name_index(name) )
+        };
+        let opcode = match context {
+            Context::Load => load,
+            Context::Store => store,
+            Context::Delete => delete
+        };
+        self.emit(opcode, index)
+    }
+
+    fn store(&mut self, name: &str) {
+        self.name_op(name, Context::Store)
+    }
+
+    fn docstring(&mut self, body: &'a [Stmt]) {
+        if let Some( doc ) = is_docstring(body) {
+            if self.scope_kind() == ScopeKind::Module || self.scope_kind() == ScopeKind::Class {
+                self.set_line(body[0].location.lineno);
+                self.load_const(Const::Str(doc.to_string()));
+                self.store("__doc__")
+            } else {
+                self.const_index(Const::Str(doc.to_string()));
+            }
+        }
+    }
+
+
+    // Statements //////////////////////////////////////////////////////////////////////////////////
+
+    fn body(&mut self, body: &'a [Stmt]) -> Result<(), String> {
+        for stmt in body {
+            self.stmt(stmt)?
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &'a Stmt) -> Result<(), String> {
+        self.set_line(stmt.location.lineno);
+        let start = stmt.location.start;
+        match &stmt.node {
+            StmtKind::FunctionDef { name, args, body, decorator_list, .. } => {
+                for d in decorator_list {
+                    self.expr(d)?
+                }
+                self.set_line(stmt.location.lineno);
+                let scope = self.child_scope(( stmt.location.start, stmt.location.end ))?;
+                let code = self.function_code(scope, name, args, stmt.location.lineno, body, None)?;
+                self.make_function(code, Some( args ))?;
+                for _ in decorator_list {
+                    self.emit(Opcode::Call, 1)
+                }
+                self.store(name)
+            },
+            StmtKind::ClassDef { name, bases, keywords, body, decorator_list } => {
+                for d in decorator_list {
+                    self.expr(d)?
+                }
+                self.set_line(stmt.location.lineno);
+                let scope = self.child_scope(( stmt.location.start, stmt.location.end ))?;
+                self.emit(Opcode::LoadBuildClass, 0);
+                let code = self.class_code(scope, name, stmt.location.lineno, body)?;
+                self.make_function(code, None)?;
+                self.load_const(Const::Str(name.clone()));
+                self.call_arguments(2, bases, keywords)?;
+                for _ in decorator_list {
+                    self.emit(Opcode::Call, 1)
+                }
+                self.store(name)
+            },
+            StmtKind::AsyncFunctionDef { .. } => return unsupported(start, "coroutines are"),
+            StmtKind::AsyncFor { .. } => return unsupported(start, "'async for' is"),
+            StmtKind::AsyncWith { .. } => return unsupported(start, "'async with' is"),
+            StmtKind::Return { value } => {
+                match value {
+                    Some( v ) => self.expr(v)?,
+                    None => self.load_const(Const::None)
+                }
+                self.unwind_blocks(None, true)?;
+                self.set_line(stmt.location.lineno);
+                self.emit(Opcode::ReturnValue, 0)
+            },
+            StmtKind::Delete { targets } => {
+                for t in targets {
+                    self.delete(t)?
+                }
+            },
+            StmtKind::Assign { targets, value, .. } => {
+                self.expr(value)?;
+                for ( i, t ) in targets.iter().enumerate() {
+                    if i + 1 < targets.len() {
+                        self.emit(Opcode::Copy, 1)
+                    }
+                    self.assign(t)?
+                }
+            },
+            StmtKind::AugAssign { target, op, value } => {
+                let arg = binary_op_arg(*op, true);
+                match &target.node {
+                    ExprKind::Name { id, .. } => {
+                        self.name_op(id, Context::Load);
+                        self.expr(value)?;
+                        self.emit(Opcode::BinaryOp, arg);
+                        self.store(id)
+                    },
+                    ExprKind::Attribute { value: object, attr, .. } => {
+                        self.expr(object)?;
+                        self.emit(Opcode::Copy, 1);
+                        let index = self.name_index(attr);
+                        self.emit(Opcode::LoadAttr, index);
+                        self.expr(value)?;
+                        self.emit(Opcode::BinaryOp, arg);
+                        self.emit(Opcode::Swap, 2);
+                        self.emit(Opcode::StoreAttr, index)
+                    },
+                    ExprKind::Subscript { value: object, slice, .. } => {
+                        self.expr(object)?;
+                        self.expr(slice)?;
+                        self.emit(Opcode::Copy, 2);
+                        self.emit(Opcode::Copy, 2);
+                        self.emit(Opcode::BinarySubscr, 0);
+                        self.expr(value)?;
+                        self.emit(Opcode::BinaryOp, arg);
+                        self.emit(Opcode::Swap, 3);
+                        self.emit(Opcode::Swap, 2);
+                        self.emit(Opcode::StoreSubscr, 0)
+                    },
+                    _ => return Err( format!("SyntaxError at {}: illegal expression for augmented assignment", start) )
+                }
+            },
+            StmtKind::AnnAssign { target, value, .. } => {
+                if let Some( v ) = value {
+                    self.expr(v)?;
+                    self.assign(target)?
+                }
+            },
+            StmtKind::For { target, iter, body, orelse, .. } => {
+                let ( start_label, cleanup, end ) = ( self.new_label(), self.new_label(), self.new_label() );
+                self.expr(iter)?;
+                self.emit(Opcode::GetIter, 0);
+                self.bind(start_label);
+                self.set_line(stmt.location.lineno);
+                self.emit(Opcode::ForIter, cleanup);
+                self.assign(target)?;
+                self.unit().blocks.push(FrameBlock::ForLoop { start: start_label, end });
+                self.body(body)?;
+                self.unit().blocks.pop();
+                self.set_line(stmt.location.lineno);
+                self.emit(Opcode::Jump, start_label);
+                self.bind(cleanup);
+                self.body(orelse)?;
+                self.bind(end)
+            },
+            StmtKind::While { test, body, orelse } => {
+                let ( start_label, orelse_label, end ) = ( self.new_label(), self.new_label(), self.new_label() );
+                self.bind(start_label);
+                self.expr(test)?;
+                self.emit(Opcode::PopJumpIfFalse, orelse_label);
+                self.unit().blocks.push(FrameBlock::WhileLoop { start: start_label, end });
+                self.body(body)?;
+                self.unit().blocks.pop();
+                self.set_line(stmt.location.lineno);
+                self.emit(Opcode::Jump, start_label);
+                self.bind(orelse_label);
+                self.body(orelse)?;
+                self.bind(end)
+            },
+            StmtKind::If { test, body, orelse } => {
+                let ( orelse_label, end ) = ( self.new_label(), self.new_label() );
+                self.expr(test)?;
+                self.emit(Opcode::PopJumpIfFalse, orelse_label);
+                self.body(body)?;
+                if !orelse.is_empty() {
+                    self.emit(Opcode::Jump, end)
+                }
+                self.bind(orelse_label);
+                self.body(orelse)?;
+                self.bind(end)
+            },
+            StmtKind::With { items, body, .. } => self.with(items, body, stmt.location.lineno)?,
+            StmtKind::Raise { exc, cause } => {
+                let mut count = 0;
+                if let Some( e ) = exc {
+                    self.expr(e)?;
+                    count = 1;
+                    if let Some( c ) = cause {
+                        self.expr(c)?;
+                        count = 2
+                    }
+                }
+                self.set_line(stmt.location.lineno);
+                self.emit(Opcode::RaiseVarargs, count)
+            },
+            StmtKind::Try { body, handlers, orelse, finalbody } => {
+                if finalbody.is_empty() {
+                    self.try_except(body, handlers, orelse)?
+                } else {
+                    self.try_finally(body, handlers, orelse, finalbody)?
+                }
+            },
+            StmtKind::TryStar { .. } => return unsupported(start, "'except*' is"),
+            StmtKind::Match { .. } => return unsupported(start, "'match' is"),
+            StmtKind::Assert { test, msg } => {
+                let end = self.new_label();
+                self.expr(test)?;
+                self.emit(Opcode::PopJumpIfTrue, end);
+                self.emit(Opcode::LoadAssertionError, 0);
+                if let Some( m ) = msg {
+                    self.expr(m)?;
+                    self.emit(Opcode::Call, 1)
+                }
+                self.set_line(stmt.location.lineno);
+                self.emit(Opcode::RaiseVarargs, 1);
+                self.bind(end)
+            },
+            StmtKind::Import { names } => {
+                for alias in names {
+                    self.import(alias)
+                }
+            },
+            StmtKind::ImportFrom { module, names, level } => self.import_from(module.as_deref().unwrap_or(""), names, *level),
+            StmtKind::Expr { value } if self.interactive && self.scope_kind() == ScopeKind::Module => {
+                self.expr(value)?;
+                self.emit(Opcode::PrintExpr, 0)
+            },
+            StmtKind::Expr { value } => {
+                if !matches!(value.node, ExprKind::Constant { .. }) {
+                    self.expr(value)?;
+                    self.emit(Opcode::PopTop, 0)
+                }
+            },
+            StmtKind::Global { .. } | StmtKind::Nonlocal { .. } | StmtKind::Pass => (),
+            StmtKind::Break => {
+                let ( index, end ) = match self.innermost_loop() {
+                    Some( ( i, FrameBlock::WhileLoop { end, .. } | FrameBlock::ForLoop { end, .. } ) ) => ( i, end ),
+                    _ => return Err( format!("SyntaxError at {}: 'break' outside loop", start) )
+                };
+                self.unwind_blocks(Some( index ), false)?;
+                if let FrameBlock::ForLoop { .. } = self.unit().blocks[index] {
+                    self.emit(Opcode::PopTop, 0)
+                }
+                self.set_line(stmt.location.lineno);
+                self.emit(Opcode::Jump, end)
+            },
+            StmtKind::Continue => {
+                let ( index, start_label ) = match self.innermost_loop() {
+                    Some( ( i, FrameBlock::WhileLoop { start, .. } | FrameBlock::ForLoop { start, .. } ) ) => ( i, start ),
+                    _ => return Err( format!("SyntaxError at {}: 'continue' not properly in loop", start) )
+                };
+                self.unwind_blocks(Some( index ), false)?;
+                self.set_line(stmt.location.lineno);
+                self.emit(Opcode::Jump, start_label)
+            }
+        }
+        Ok(())
+    }
+
+    fn innermost_loop(&mut self) -> Option<( usize, FrameBlock<'a> )> {
+        self.unit().blocks.iter().enumerate().rev()
+            .find( |( _, b )| matches!(b, FrameBlock::WhileLoop { .. } | FrameBlock::ForLoop { .. }) )
+            .map( |( i, b )| ( i, *b ) )
+    }
+
+    /// Emits leaving of the blocks above 'stop', or of all blocks for 'return'. With 'preserve' the
+    /// returned value stays on top of the stack.
+    fn unwind_blocks(&mut self, stop: Option<usize>, preserve: bool) -> Result<(), String> {
+        let bottom = stop.map( |s| s + 1 ).unwrap_or(0);
+        let mut popped = Vec::new();
+        while self.unit().blocks.len() > bottom {
+            let block = self.unit().blocks.pop().expect("block");
+            self.unwind_block(block, preserve)?;
+            popped.push(block)
+        }
+        while let Some( block ) = popped.pop() {
+            self.unit().blocks.push(block)
+        }
+        Ok(())
+    }
+
+    fn unwind_block(&mut self, block: FrameBlock<'a>, preserve: bool) -> Result<(), String> {
+        match block {
+            FrameBlock::WhileLoop { .. } => (),
+            FrameBlock::ForLoop { .. } => {
+                if preserve {
+                    self.emit(Opcode::Swap, 2)
+                }
+                self.emit(Opcode::PopTop, 0)
+            },
+            FrameBlock::TryExcept => self.emit(Opcode::PopBlock, 0),
+            FrameBlock::FinallyTry(finalbody) => {
+                self.emit(Opcode::PopBlock, 0);
+                let line = self.unit().line;
+                if preserve {
+                    self.unit().blocks.push(FrameBlock::PopValue)
+                }
+                self.body(finalbody)?;
+                if preserve {
+                    self.unit().blocks.pop();
+                }
+                self.set_line(line)
+            },
+            FrameBlock::PopValue => {
+                if preserve {
+                    self.emit(Opcode::Swap, 2)
+                }
+                self.emit(Opcode::PopTop, 0)
+            },
+            FrameBlock::FinallyEnd => {
+                if preserve {
+                    self.emit(Opcode::Swap, 2)
+                }
+                self.emit(Opcode::PopTop, 0);
+                if preserve {
+                    self.emit(Opcode::Swap, 2)
+                }
+                self.emit(Opcode::PopBlock, 0);
+                self.emit(Opcode::PopExcept, 0)
+            },
+            FrameBlock::With => {
+                self.emit(Opcode::PopBlock, 0);
+                if preserve {
+                    self.emit(Opcode::Swap, 2)
+                }
+                self.call_exit()
+            },
+            FrameBlock::Handler(name) => {
+                if name.is_some() {
+                    self.emit(Opcode::PopBlock, 0)
+                }
+                self.emit(Opcode::PopBlock, 0);
+                if preserve {
+                    self.emit(Opcode::Swap, 2)
+                }
+                self.emit(Opcode::PopExcept, 0);
+                if let Some( n ) = name {
+                    self.clear_name(n)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls '__exit__' on top of the stack without an exception and drops the result.
+    fn call_exit(&mut self) {
+        for _ in 0 .. 3 {
+            self.load_const(Const::None)
+        }
+        self.emit(Opcode::Call, 3);
+        self.emit(Opcode::PopTop, 0)
+    }
+
+    /// Unbinds name of an 'except' clause, so that the exception does not outlive the clause.
+    fn clear_name(&mut self, name: &str) {
+        self.load_const(Const::None);
+        self.store(name);
+        self.name_op(name, Context::Delete)
+    }
+
+    /// Handler restoring the previous exception and raising the one on top of the stack again.
+    fn pop_except_and_reraise(&mut self) {
+        self.emit(Opcode::Swap, 2);
+        self.emit(Opcode::PopExcept, 0);
+        self.emit(Opcode::Reraise, 0)
+    }
+
+    fn try_except(&mut self, body: &'a [Stmt], handlers: &'a [ExceptHandler], orelse: &'a [Stmt]) -> Result<(), String> {
+        let ( handler_label, cleanup, end ) = ( self.new_label(), self.new_label(), self.new_label() );
+        self.emit(Opcode::SetupFinally, handler_label);
+        self.unit().blocks.push(FrameBlock::TryExcept);
+        self.body(body)?;
+        self.unit().blocks.pop();
+        self.emit(Opcode::PopBlock, 0);
+        self.body(orelse)?;
+        self.emit(Opcode::Jump, end);
+        self.bind(handler_label);
+        self.emit(Opcode::SetupCleanup, cleanup);
+        self.emit(Opcode::PushExcInfo, 0);
+        for handler in handlers {
+            self.set_line(handler.location.lineno);
+            let next = self.new_label();
+            if let Some( t ) = &handler.type_ {
+                self.expr(t)?;
+                self.emit(Opcode::CheckExcMatch, 0);
+                self.emit(Opcode::PopJumpIfFalse, next)
+            }
+            match &handler.name {
+                Some( name ) => {
+                    let name_cleanup = self.new_label();
+                    self.store(name);
+                    self.emit(Opcode::SetupCleanup, name_cleanup);
+                    self.unit().blocks.push(FrameBlock::Handler(Some( name )));
+                    self.body(&handler.body)?;
+                    self.unit().blocks.pop();
+                    self.emit(Opcode::PopBlock, 0);
+                    self.emit(Opcode::PopBlock, 0);
+                    self.emit(Opcode::PopExcept, 0);
+                    self.clear_name(name);
+                    self.emit(Opcode::Jump, end);
+                    self.bind(name_cleanup);
+                    self.clear_name(name);
+                    self.emit(Opcode::Reraise, 0)
+                },
+                None => {
+                    self.emit(Opcode::PopTop, 0);
+                    self.unit().blocks.push(FrameBlock::Handler(None));
+                    self.body(&handler.body)?;
+                    self.unit().blocks.pop();
+                    self.emit(Opcode::PopBlock, 0);
+                    self.emit(Opcode::PopExcept, 0);
+                    self.emit(Opcode::Jump, end)
+                }
+            }
+            self.bind(next)
+        }
+        self.emit(Opcode::Reraise, 0);
+        self.bind(cleanup);
+        self.pop_except_and_reraise();
+        self.bind(end);
+        Ok(())
+    }
+
+    /// Body of 'finally' is compiled once for falling through, once for exceptions and once for
+    /// every 'return', 'break' and 'continue' leaving the body.
+    fn try_finally(&mut self, body: &'a [Stmt], handlers: &'a [ExceptHandler], orelse: &'a [Stmt], finalbody: &'a [Stmt]) -> Result<(), String> {
+        let ( exception, cleanup, end ) = ( self.new_label(), self.new_label(), self.new_label() );
+        self.emit(Opcode::SetupFinally, exception);
+        self.unit().blocks.push(FrameBlock::FinallyTry(finalbody));
+        if handlers.is_empty() {
+            self.body(body)?
+        } else {
+            self.try_except(body, handlers, orelse)?
+        }
+        self.unit().blocks.pop();
+        self.emit(Opcode::PopBlock, 0);
+        self.body(finalbody)?;
+        self.emit(Opcode::Jump, end);
+        self.bind(exception);
+        self.emit(Opcode::SetupCleanup, cleanup);
+        self.emit(Opcode::PushExcInfo, 0);
+        self.unit().blocks.push(FrameBlock::FinallyEnd);
+        self.body(finalbody)?;
+        self.unit().blocks.pop();
+        self.emit(Opcode::Reraise, 0);
+        self.bind(cleanup);
+        self.pop_except_and_reraise();
+        self.bind(end);
+        Ok(())
+    }
+
+    /// Items after the first are compiled as nested 'with' statements.
+    fn with(&mut self, items: &'a [WithItem], body: &'a [Stmt], line: u32) -> Result<(), String> {
+        let item = &items[0];
+        let ( exception, cleanup, suppress, end ) = ( self.new_label(), self.new_label(), self.new_label(), self.new_label() );
+        self.expr(&item.context_expr)?;
+        self.set_line(line);
+        self.emit(Opcode::BeforeWith, 0);
+        match &item.optional_vars {
+            Some( target ) => self.assign(target)?,
+            None => self.emit(Opcode::PopTop, 0)
+        }
+        self.emit(Opcode::SetupFinally, exception);
+        self.unit().blocks.push(FrameBlock::With);
+        if items.len() > 1 {
+            self.with(&items[1 ..], body, line)?
+        } else {
+            self.body(body)?
+        }
+        self.unit().blocks.pop();
+        self.emit(Opcode::PopBlock, 0);
+        self.set_line(line);
+        self.call_exit();
+        self.emit(Opcode::Jump, end);
+        self.bind(exception);
+        self.emit(Opcode::SetupCleanup, cleanup);
+        self.emit(Opcode::PushExcInfo, 0);
+        self.emit(Opcode::WithExceptStart, 0);
+        self.emit(Opcode::PopJumpIfTrue, suppress);
+        self.emit(Opcode::Reraise, 0);
+        self.bind(suppress);
+        self.emit(Opcode::PopBlock, 0);
+        self.emit(Opcode::PopTop, 0);
+        self.emit(Opcode::PopExcept, 0);
+        self.emit(Opcode::PopTop, 0);
+        self.emit(Opcode::Jump, end);
+        self.bind(cleanup);
+        self.pop_except_and_reraise();
+        self.bind(end);
+        Ok(())
+    }
+
+    fn import(&mut self, alias: &'a Alias) {
+        self.load_const(Const::Int(Int::from(0)));
+        self.load_const(Const::None);
+        let index = self.name_index(&alias.name);
+        self.emit(Opcode::ImportName, index);
+        match &alias.asname {
+            Some( asname ) => {
+                let mut parts = alias.name.split('.').skip(1).peekable();
+                while let Some( part ) = parts.next() {
+                    let index = self.name_index(part);
+                    self.emit(Opcode::ImportFrom, index);
+                    self.emit(Opcode::Swap, 2);
+                    self.emit(Opcode::PopTop, 0);
+                    if parts.peek().is_none() {
+                        break
+                    }
+                }
+                self.store(asname)
+            },
+            None => {
+                let top = alias.name.split('.').next().unwrap_or("").to_string();
+                self.store(&top)
+            }
+        }
+    }
+
+    fn import_from(&mut self, module: &str, names: &'a [Alias], level: u32) {
+        self.load_const(Const::Int(Int::from(level as i64)));
+        let fromlist = names.iter().map( |a| Const::Str(a.name.clone()) ).collect();
+        self.load_const(Const::Tuple(fromlist));
+        let index = self.name_index(module);
+        self.emit(Opcode::ImportName, index);
+        if names.len() == 1 && names[0].name == "*" {
+            self.emit(Opcode::ImportStar, 0);
+            return
+        }
+        for alias in names {
+            let index = self.name_index(&alias.name);
+            self.emit(Opcode::ImportFrom, index);
+            self.store(alias.asname.as_deref().unwrap_or(&alias.name))
+        }
+        self.emit(Opcode::PopTop, 0)
+    }
+
+    fn assign(&mut self, target: &'a Expr) -> Result<(), String> {
+        match &target.node {
+            ExprKind::Name { id, .. } => self.store(id),
+            ExprKind::Attribute { value, attr, .. } => {
+                self.expr(value)?;
+                let index = self.name_index(attr);
+                self.emit(Opcode::StoreAttr, index)
+            },
+            ExprKind::Subscript { value, slice, .. } => {
+                self.expr(value)?;
+                self.expr(slice)?;
+                self.emit(Opcode::StoreSubscr, 0)
+            },
+            ExprKind::Tuple { elts, .. } | ExprKind::List { elts, .. } => {
+                match elts.iter().position( |e| matches!(e.node, ExprKind::Starred { .. }) ) {
+                    Some( star ) => {
+                        let after = ( elts.len() - star - 1 ) as u32;
+                        self.emit(Opcode::UnpackEx, star as u32 | ( after << 8 ))
+                    },
+                    None => self.emit(Opcode::UnpackSequence, elts.len() as u32)
+                }
+                for e in elts {
+                    match &e.node {
+                        ExprKind::Starred { value, .. } => self.assign(value)?,
+                        _ => self.assign(e)?
+                    }
+                }
+            },
+            _ => return Err( format!("SyntaxError at {}: cannot assign to expression", target.location.start) )
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, target: &'a Expr) -> Result<(), String> {
+        match &target.node {
+            ExprKind::Name { id, .. } => self.name_op(id, Context::Delete),
+            ExprKind::Attribute { value, attr, .. } => {
+                self.expr(value)?;
+                let index = self.name_index(attr);
+                self.emit(Opcode::DeleteAttr, index)
+            },
+            ExprKind::Subscript { value, slice, .. } => {
+                self.expr(value)?;
+                self.expr(slice)?;
+                self.emit(Opcode::DeleteSubscr, 0)
+            },
+            ExprKind::Tuple { elts, .. } | ExprKind::List { elts, .. } => {
+                for e in elts {
+                    self.delete(e)?
+                }
+            },
+            _ => return Err( format!("SyntaxError at {}: cannot delete expression", target.location.start) )
+        }
+        Ok(())
+    }
+
+
+    // Functions and classes ///////////////////////////////////////////////////////////////////////
+
+    /// Compiles body of function or lambda to a code object, a lambda has 'expr' as body.
+    fn function_code(&mut self, scope: ScopeId, name: &str, args: &'a Arguments, line: u32, body: &'a [Stmt], expr: Option<&'a Expr>) -> Result<CodeObject, String> {
+        let qualname = self.qualname(name);
+        self.enter_unit(scope, name, qualname, line);
+        let unit = self.unit();
+        unit.argcount = ( args.posonlyargs.len() + args.args.len() ) as u32;
+        unit.posonlyargcount = args.posonlyargs.len() as u32;
+        unit.kwonlyargcount = args.kwonlyargs.len() as u32;
+        let mut order : Vec<String> = args.posonlyargs.iter().chain(&args.args).chain(&args.kwonlyargs).map( |a| a.arg.clone() ).collect();
+        if let Some( a ) = &args.vararg {
+            unit.flags |= CO_VARARGS;
+            order.push(a.arg.clone())
+        }
+        if let Some( a ) = &args.kwarg {
+            unit.flags |= CO_VARKEYWORDS;
+            order.push(a.arg.clone())
+        }
+        let others : Vec<String> = unit.varnames.iter().filter( |n| !order.contains(n) ).cloned().collect();
+        unit.varnames = order.into_iter().chain(others).collect();
+        match expr {
+            Some( e ) => {
+                self.expr(e)?;
+                self.emit(Opcode::ReturnValue, 0)
+            },
+            None => {
+                self.docstring(body);
+                self.body(body)?;
+                self.load_const(Const::None);
+                self.emit(Opcode::ReturnValue, 0)
+            }
+        }
+        Ok( self.leave_unit() )
+    }
+
+    /// Class body is a function run with the class namespace as locals, it returns the cell of
+    /// '__class__' when methods use it so that the class can fill it.
+    fn class_code(&mut self, scope: ScopeId, name: &str, line: u32, body: &'a [Stmt]) -> Result<CodeObject, String> {
+        let qualname = self.qualname(name);
+        self.enter_unit(scope, name, qualname.clone(), line);
+        self.name_op("__name__", Context::Load);
+        self.store("__module__");
+        self.load_const(Const::Str(qualname));
+        self.store("__qualname__");
+        self.docstring(body);
+        self.body(body)?;
+        if self.symbols.scope(scope).needs_class_closure {
+            let index = self.deref_index("__class__");
+            self.emit(Opcode::LoadClosure, index);
+            self.emit(Opcode::Copy, 1);
+            self.store("__classcell__")
+        } else {
+            self.load_const(Const::None)
+        }
+        self.emit(Opcode::ReturnValue, 0);
+        Ok( self.leave_unit() )
+    }
+
+    /// Pushes function of code object, with defaults of arguments and cells of its free variables.
+    fn make_function(&mut self, code: CodeObject, args: Option<&'a Arguments>) -> Result<(), String> {
+        let mut flags = 0;
+        if let Some( args ) = args {
+            if !args.defaults.is_empty() {
+                for d in &args.defaults {
+                    self.expr(d)?
+                }
+                self.emit(Opcode::BuildTuple, args.defaults.len() as u32);
+                flags |= MAKE_DEFAULTS
+            }
+            let mut count = 0;
+            for ( arg, default ) in args.kwonlyargs.iter().zip(&args.kw_defaults) {
+                if let Some( d ) = default {
+                    self.load_const(Const::Str(arg.arg.clone()));
+                    self.expr(d)?;
+                    count += 1
+                }
+            }
+            if count > 0 {
+                self.emit(Opcode::BuildMap, count);
+                flags |= MAKE_KWDEFAULTS
+            }
+        }
+        if !code.freevars.is_empty() {
+            for name in &code.freevars {
+                let index = self.deref_index(name);
+                self.emit(Opcode::LoadClosure, index)
+            }
+            self.emit(Opcode::BuildTuple, code.freevars.len() as u32);
+            flags |= MAKE_CLOSURE
+        }
+        self.load_const(Const::Code(Rc::new(code)));
+        self.emit(Opcode::MakeFunction, flags);
+        Ok(())
+    }
+
+    /// Emits call of callable below 'pushed' arguments already on the stack.
+    fn call_arguments(&mut self, pushed: u32, args: &'a [Expr], keywords: &'a [Keyword]) -> Result<(), String> {
+        if !has_starred(args) && keywords.iter().all( |k| k.arg.is_some() ) {
+            for a in args {
+                self.expr(a)?
+            }
+            let count = pushed + ( args.len() + keywords.len() ) as u32;
+            if keywords.is_empty() {
+                self.emit(Opcode::Call, count)
+            } else {
+                for k in keywords {
+                    self.expr(&k.value)?
+                }
+                let names = keywords.iter().map( |k| Const::Str(k.arg.clone().unwrap_or_default()) ).collect();
+                self.load_const(Const::Tuple(names));
+                self.emit(Opcode::CallKw, count)
+            }
+            return Ok(())
+        }
+        self.emit(Opcode::BuildList, pushed);
+        for a in args {
+            match &a.node {
+                ExprKind::Starred { value, .. } => {
+                    self.expr(value)?;
+                    self.emit(Opcode::ListExtend, 1)
+                },
+                _ => {
+                    self.expr(a)?;
+                    self.emit(Opcode::ListAppend, 1)
+                }
+            }
+        }
+        self.emit(Opcode::ListToTuple, 0);
+        if keywords.is_empty() {
+            self.emit(Opcode::CallFunctionEx, 0);
+            return Ok(())
+        }
+        self.emit(Opcode::BuildMap, 0);
+        for k in keywords {
+            match &k.arg {
+                Some( name ) => {
+                    self.load_const(Const::Str(name.clone()));
+                    self.expr(&k.value)?;
+                    self.emit(Opcode::MapAdd, 1)
+                },
+                None => {
+                    self.expr(&k.value)?;
+                    self.emit(Opcode::DictMerge, 1)
+                }
+            }
+        }
+        self.emit(Opcode::CallFunctionEx, 1);
+        Ok(())
+    }
+
+    /// Zero argument 'super()' gets the class from the '__class__' cell and the instance from the
+    /// first parameter, passed explicitly as the two argument form.
+    fn zero_argument_super(&mut self) -> bool {
+        let unit = match self.units.last() {
+            Some( u ) if matches!(u.kind, ScopeKind::Function | ScopeKind::Lambda) && u.argcount > 0 => u,
+            _ => return false
+        };
+        let first = unit.varnames[0].clone();
+        let has_class = self.symbols.lookup(unit.scope, "__class__").map( |s| s.scope == SymbolScope::Free ).unwrap_or(false);
+        if !has_class {
+            return false
+        }
+        self.name_op("super", Context::Load);
+        let index = self.deref_index("__class__");
+        self.emit(Opcode::LoadDeref, index);
+        self.name_op(&first, Context::Load);
+        self.emit(Opcode::Call, 2);
+        true
+    }
+
+
+    // Expressions /////////////////////////////////////////////////////////////////////////////////
+
+    fn expr(&mut self, expr: &'a Expr) -> Result<(), String> {
+        let line = self.unit().line;
+        self.set_line(expr.location.lineno);
+        let res = self.expr_kind(expr);
+        self.set_line(line);
+        res
+    }
+
+    fn expr_kind(&mut self, expr: &'a Expr) -> Result<(), String> {
+        let start = expr.location.start;
+        match &expr.node {
+            ExprKind::BoolOp { op, values } => {
+                let end = self.new_label();
+                let jump = if *op == BoolOperator::And { Opcode::PopJumpIfFalse } else { Opcode::PopJumpIfTrue };
+                for ( i, v ) in values.iter().enumerate() {
+                    self.expr(v)?;
+                    if i + 1 < values.len() {
+                        self.emit(Opcode::Copy, 1);
+                        self.emit(jump, end);
+                        self.emit(Opcode::PopTop, 0)
+                    }
+                }
+                self.bind(end)
+            },
+            ExprKind::NamedExpr { target, value } => {
+                self.expr(value)?;
+                self.emit(Opcode::Copy, 1);
+                self.assign(target)?
+            },
+            ExprKind::BinOp { left, op, right } => {
+                self.expr(left)?;
+                self.expr(right)?;
+                self.emit(Opcode::BinaryOp, binary_op_arg(*op, false))
+            },
+            ExprKind::UnaryOp { op, operand } => {
+                self.expr(operand)?;
+                let opcode = match op {
+                    UnaryOperator::Invert => Opcode::UnaryInvert,
+                    UnaryOperator::Not => Opcode::UnaryNot,
+                    UnaryOperator::UAdd => Opcode::UnaryPositive,
+                    UnaryOperator::USub => Opcode::UnaryNegative
+                };
+                self.emit(opcode, 0)
+            },
+            ExprKind::Lambda { args, body } => {
+                let scope = self.child_scope(( expr.location.start, expr.location.end ))?;
+                let code = self.function_code(scope, "<lambda>", args, expr.location.lineno, &[], Some( body ))?;
+                self.make_function(code, Some( args ))?
+            },
+            ExprKind::IfExp { test, body, orelse } => {
+                let ( orelse_label, end ) = ( self.new_label(), self.new_label() );
+                self.expr(test)?;
+                self.emit(Opcode::PopJumpIfFalse, orelse_label);
+                self.expr(body)?;
+                self.emit(Opcode::Jump, end);
+                self.bind(orelse_label);
+                self.expr(orelse)?;
+                self.bind(end)
+            },
+            ExprKind::Dict { keys, values } => {
+                if keys.iter().all( |k| k.is_some() ) {
+                    for ( k, v ) in keys.iter().zip(values) {
+                        if let Some( k ) = k {
+                            self.expr(k)?
+                        }
+                        self.expr(v)?
+                    }
+                    self.emit(Opcode::BuildMap, keys.len() as u32)
+                } else {
+                    self.emit(Opcode::BuildMap, 0);
+                    for ( k, v ) in keys.iter().zip(values) {
+                        match k {
+                            Some( k ) => {
+                                self.expr(k)?;
+                                self.expr(v)?;
+                                self.emit(Opcode::MapAdd, 1)
+                            },
+                            None => {
+                                self.expr(v)?;
+                                self.emit(Opcode::DictUpdate, 1)
+                            }
+                        }
+                    }
+                }
+            },
+            ExprKind::Set { elts } => self.sequence(elts, Opcode::BuildSet)?,
+            ExprKind::ListComp { elt, generators } => self.comprehension(expr, ComprehensionKind::List, elt, None, generators)?,
+            ExprKind::SetComp { elt, generators } => self.comprehension(expr, ComprehensionKind::Set, elt, None, generators)?,
+            ExprKind::DictComp { key, value, generators } => self.comprehension(expr, ComprehensionKind::Dict, key, Some( value ), generators)?,
+            ExprKind::GeneratorExp { elt, generators } => self.comprehension(expr, ComprehensionKind::Generator, elt, None, generators)?,
+            ExprKind::Await { .. } => return unsupported(start, "'await' is"),
+            ExprKind::Yield { value } => {
+                match value {
+                    Some( v ) => self.expr(v)?,
+                    None => self.load_const(Const::None)
+                }
+                self.emit(Opcode::YieldValue, 0)
+            },
+            ExprKind::YieldFrom { value } => {
+                self.expr(value)?;
+                self.emit(Opcode::GetYieldFromIter, 0);
+                self.load_const(Const::None);
+                self.yield_from()
+            },
+            ExprKind::Compare { left, ops, comparators } => {
+                self.expr(left)?;
+                if ops.len() == 1 {
+                    self.expr(&comparators[0])?;
+                    self.compare_op(ops[0])
+                } else {
+                    let ( cleanup, end ) = ( self.new_label(), self.new_label() );
+                    for ( i, ( op, right ) ) in ops.iter().zip(comparators).enumerate() {
+                        self.expr(right)?;
+                        if i + 1 < ops.len() {
+                            self.emit(Opcode::Swap, 2);
+                            self.emit(Opcode::Copy, 2);
+                            self.compare_op(*op);
+                            self.emit(Opcode::Copy, 1);
+                            self.emit(Opcode::PopJumpIfFalse, cleanup);
+                            self.emit(Opcode::PopTop, 0)
+                        } else {
+                            self.compare_op(*op)
+                        }
+                    }
+                    self.emit(Opcode::Jump, end);
+                    self.bind(cleanup);
+                    self.emit(Opcode::Swap, 2);
+                    self.emit(Opcode::PopTop, 0);
+                    self.bind(end)
+                }
+            },
+            ExprKind::Call { func, args, keywords } => {
+                if let ExprKind::Name { id, .. } = &func.node {
+                    if id == "super" && args.is_empty() && keywords.is_empty() && self.zero_argument_super() {
+                        return Ok(())
+                    }
+                }
+                self.expr(func)?;
+                self.call_arguments(0, args, keywords)?
+            },
+            ExprKind::FormattedValue { value, conversion, format_spec } => {
+                self.expr(value)?;
+                let mut flags = match *conversion {
+                    115 => FORMAT_STR,
+                    114 => FORMAT_REPR,
+                    97 => FORMAT_ASCII,
+                    _ => 0
+                };
+                if let Some( spec ) = format_spec {
+                    self.expr(spec)?;
+                    flags |= FORMAT_SPEC
+                }
+                self.emit(Opcode::FormatValue, flags)
+            },
+            ExprKind::JoinedStr { values } => {
+                for v in values {
+                    self.expr(v)?
+                }
+                match values.len() {
+                    0 => self.load_const(Const::Str(String::new())),
+                    1 => (),
+                    n => self.emit(Opcode::BuildString, n as u32)
+                }
+            },
+            ExprKind::Constant { value, .. } => {
+                let value = self.constant(value, start)?;
+                self.load_const(value)
+            },
+            ExprKind::Attribute { value, attr, ctx } => {
+                self.expr(value)?;
+                let index = self.name_index(attr);
+                let opcode = match ctx {
+                    ExprContext::Store => Opcode::StoreAttr,
+                    ExprContext::Del => Opcode::DeleteAttr,
+                    _ => Opcode::LoadAttr
+                };
+                self.emit(opcode, index)
+            },
+            ExprKind::Subscript { value, slice, .. } => {
+                self.expr(value)?;
+                self.expr(slice)?;
+                self.emit(Opcode::BinarySubscr, 0)
+            },
+            ExprKind::Starred { .. } => return Err( format!("SyntaxError at {}: can't use starred expression here", start) ),
+            ExprKind::Name { id, .. } => self.name_op(id, Context::Load),
+            ExprKind::List { elts, .. } => self.sequence(elts, Opcode::BuildList)?,
+            ExprKind::Tuple { elts, .. } => self.sequence(elts, Opcode::BuildTuple)?,
+            ExprKind::Slice { lower, upper, step } => {
+                for part in [ lower, upper ] {
+                    match part {
+                        Some( e ) => self.expr(e)?,
+                        None => self.load_const(Const::None)
+                    }
+                }
+                match step {
+                    Some( e ) => {
+                        self.expr(e)?;
+                        self.emit(Opcode::BuildSlice, 3)
+                    },
+                    None => self.emit(Opcode::BuildSlice, 2)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn constant(&self, value: &Constant, start: u32) -> Result<Const, String> {
+        Ok( match value {
+            Constant::None => Const::None,
+            Constant::Bool(b) => Const::Bool(*b),
+            Constant::Str(s) => Const::Str(s.clone()),
+            Constant::Bytes(b) => Const::Bytes(b.clone()),
+            Constant::Int(digits) => match Int::parse(digits, 10) {
+                Some( i ) => Const::Int(i),
+                None => return Err( format!("SyntaxError at {}: invalid integer literal", start) )
+            },
+            Constant::Float(f) => Const::Float(*f),
+            Constant::Complex(..) => return unsupported(start, "complex numbers are"),
+            Constant::Tuple(t) => Const::Tuple(t.iter().map( |c| self.constant(c, start) ).collect::<Result<Vec<Const>, String>>()?),
+            Constant::Ellipsis => Const::Ellipsis
+        } )
+    }
+
+    fn compare_op(&mut self, op: CmpOperator) {
+        match op {
+            CmpOperator::Is => self.emit(Opcode::IsOp, 0),
+            CmpOperator::IsNot => self.emit(Opcode::IsOp, 1),
+            CmpOperator::In => self.emit(Opcode::ContainsOp, 0),
+            CmpOperator::NotIn => self.emit(Opcode::ContainsOp, 1),
+            _ => self.emit(Opcode::CompareOp, compare_op_arg(op))
+        }
+    }
+
+    /// Display of list, tuple or set. Starred elements are unpacked into a list built step by step.
+    fn sequence(&mut self, elts: &'a [Expr], build: Opcode) -> Result<(), String> {
+        if !has_starred(elts) {
+            for e in elts {
+                self.expr(e)?
+            }
+            self.emit(build, elts.len() as u32);
+            return Ok(())
+        }
+        let ( create, add, update ) = match build {
+            Opcode::BuildSet => ( Opcode::BuildSet, Opcode::SetAdd, Opcode::SetUpdate ),
+            _ => ( Opcode::BuildList, Opcode::ListAppend, Opcode::ListExtend )
+        };
+        self.emit(create, 0);
+        for e in elts {
+            match &e.node {
+                ExprKind::Starred { value, .. } => {
+                    self.expr(value)?;
+                    self.emit(update, 1)
+                },
+                _ => {
+                    self.expr(e)?;
+                    self.emit(add, 1)
+                }
+            }
+        }
+        if build == Opcode::BuildTuple {
+            self.emit(Opcode::ListToTuple, 0)
+        }
+        Ok(())
+    }
+
+    /// Delegation to the iterator below the value to send, leaves the value it returns.
+    fn yield_from(&mut self) {
+        let ( send, exit ) = ( self.new_label(), self.new_label() );
+        self.bind(send);
+        self.emit(Opcode::Send, exit);
+        self.emit(Opcode::YieldValue, 1);
+        self.emit(Opcode::Jump, send);
+        self.bind(exit)
+    }
+
+    /// Comprehensions run as nested functions taking the iterator of their first clause as '.0'.
+    fn comprehension(&mut self, expr: &'a Expr, kind: ComprehensionKind, elt: &'a Expr, value: Option<&'a Expr>, generators: &'a [Comprehension]) -> Result<(), String> {
+        if generators.iter().any( |g| g.is_async != 0 ) {
+            return unsupported(expr.location.start, "asynchronous comprehensions are")
+        }
+        let scope = self.child_scope(( expr.location.start, expr.location.end ))?;
+        let name = match kind {
+            ComprehensionKind::List => "<listcomp>",
+            ComprehensionKind::Set => "<setcomp>",
+            ComprehensionKind::Dict => "<dictcomp>",
+            ComprehensionKind::Generator => "<genexpr>"
+        };
+        let qualname = self.qualname(name);
+        self.enter_unit(scope, name, qualname, expr.location.lineno);
+        self.unit().argcount = 1;
+        match kind {
+            ComprehensionKind::List => self.emit(Opcode::BuildList, 0),
+            ComprehensionKind::Set => self.emit(Opcode::BuildSet, 0),
+            ComprehensionKind::Dict => self.emit(Opcode::BuildMap, 0),
+            ComprehensionKind::Generator => ()
+        }
+        self.comprehension_clause(kind, elt, value, generators, 0)?;
+        if kind == ComprehensionKind::Generator {
+            self.load_const(Const::None)
+        }
+        self.emit(Opcode::ReturnValue, 0);
+        let code = self.leave_unit();
+        self.make_function(code, None)?;
+        self.expr(&generators[0].iter)?;
+        self.emit(Opcode::GetIter, 0);
+        self.emit(Opcode::Call, 1);
+        Ok(())
+    }
+
+    fn comprehension_clause(&mut self, kind: ComprehensionKind, elt: &'a Expr, value: Option<&'a Expr>, generators: &'a [Comprehension], level: usize) -> Result<(), String> {
+        let clause = &generators[level];
+        let ( start, end ) = ( self.new_label(), self.new_label() );
+        if level == 0 {
+            self.emit(Opcode::LoadFast, 0)
+        } else {
+            self.expr(&clause.iter)?;
+            self.emit(Opcode::GetIter, 0)
+        }
+        self.bind(start);
+        self.emit(Opcode::ForIter, end);
+        self.assign(&clause.target)?;
+        for condition in &clause.ifs {
+            self.expr(condition)?;
+            self.emit(Opcode::PopJumpIfFalse, start)
+        }
+        if level + 1 < generators.len() {
+            self.comprehension_clause(kind, elt, value, generators, level + 1)?
+        } else {
+            let depth = level as u32 + 2;
+            self.expr(elt)?;
+            match ( kind, value ) {
+                ( ComprehensionKind::List, _ ) => self.emit(Opcode::ListAppend, depth),
+                ( ComprehensionKind::Set, _ ) => self.emit(Opcode::SetAdd, depth),
+                ( ComprehensionKind::Dict, Some( v ) ) => {
+                    self.expr(v)?;
+                    self.emit(Opcode::MapAdd, depth)
+                },
+                _ => {
+                    self.emit(Opcode::YieldValue, 0);
+                    self.emit(Opcode::PopTop, 0)
+                }
+            }
+        }
+        self.emit(Opcode::Jump, start);
+        self.bind(end);
+        Ok(())
+    }
+}
+
+
+// Assembler ///////////////////////////////////////////////////////////////////////////////////////
+
+/// Resolves labels, drops unreachable code and pseudo instructions, and computes stack size,
+/// line table and exception table by following control flow from the entry and from handlers.
+fn assemble(unit: Unit, filename: &str) -> CodeObject {
+    let code : Vec<( Instruction, u32 )> = unit.instructions.iter().map( |( i, line )| {
+        let arg = if i.opcode.is_jump() { unit.labels[i.arg as usize].unwrap_or(unit.instructions.len()) as u32 } else { i.arg };
+        ( Instruction { opcode: i.opcode, arg }, *line )
+    } ).collect();
+    let n = code.len();
+    let mut depths : Vec<Option<i32>> = vec![ None; n ];
+    let mut handlers : Vec<Option<( u32, u32 )>> = vec![ None; n ];
+    let mut work : Vec<( usize, i32, HandlerStack )> = vec![ ( 0, 0, Vec::new() ) ];
+    let mut stacksize = 0;
+    while let Some( ( mut index, mut depth, mut stack ) ) = work.pop() {
+        while index < n && depths[index].is_none() {
+            depths[index] = Some( depth );
+            handlers[index] = stack.last().cloned();
+            stacksize = stacksize.max(depth);
+            let ( instruction, _ ) = code[index];
+            let op = instruction.opcode;
+            match op {
+                Opcode::SetupFinally | Opcode::SetupCleanup => {
+                    work.push(( instruction.arg as usize, depth + 1, stack.clone() ));
+                    stack.push(( instruction.arg, depth as u32 ))
+                },
+                Opcode::PopBlock => {
+                    stack.pop();
+                },
+                _ if op.is_jump() => work.push(( instruction.arg as usize, depth + op.stack_effect(instruction.arg, true), stack.clone() )),
+                _ => ()
+            }
+            if op.is_terminal() {
+                break
+            }
+            depth += op.stack_effect(instruction.arg, false);
+            index += 1
+        }
+    }
+    let kept : Vec<bool> = ( 0 .. n ).map( |i| depths[i].is_some() && !code[i].0.opcode.is_pseudo() ).collect();
+    let mut position = vec![ 0u32; n + 1 ];
+    let mut next = 0u32;
+    for i in 0 .. n {
+        position[i] = next;
+        if kept[i] {
+            next += 1
+        }
+    }
+    position[n] = next;
+    let mut instructions = Vec::new();
+    let mut line_table : Vec<( u32, u32 )> = Vec::new();
+    let mut exception_table : Vec<ExceptionEntry> = Vec::new();
+    for i in ( 0 .. n ).filter( |i| kept[*i] ) {
+        let ( mut instruction, line ) = code[i];
+        if instruction.opcode.is_jump() {
+            instruction.arg = position[instruction.arg as usize]
+        }
+        let index = instructions.len() as u32;
+        instructions.push(instruction);
+        if line_table.last().map( |( _, l )| *l != line ).unwrap_or(true) {
+            line_table.push(( index, line ))
+        }
+        if let Some( ( target, depth ) ) = handlers[i] {
+            let target = position[target as usize];
+            match exception_table.last_mut() {
+                Some( e ) if e.end == index && e.target == target && e.depth == depth => e.end = index + 1,
+                _ => exception_table.push(ExceptionEntry { start: index, end: index + 1, target, depth })
+            }
+        }
+    }
+    CodeObject {
+        name: unit.name,
+        qualname: unit.qualname,
+        filename: filename.to_string(),
+        first_line: unit.first_line,
+        argcount: unit.argcount,
+        posonlyargcount: unit.posonlyargcount,
+        kwonlyargcount: unit.kwonlyargcount,
+        flags: unit.flags,
+        stacksize: stacksize as u32,
+        instructions,
+        constants: unit.constants,
+        names: unit.names,
+        varnames: unit.varnames,
+        cellvars: unit.cellvars,
+        freevars: unit.freevars,
+        line_table,
+        exception_table
+    }
+}
+
+
+// UnitTests for bytecode compiler /////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::api::Mode;
+    use crate::bytecode::compiler::{ compile_source, compile as compile_mode };
+    use crate::bytecode::code::{ Const, CodeObject, CO_GENERATOR, CO_VARARGS };
+
+    fn compile(source: &str) -> Rc<CodeObject> {
+        compile_source(source, "test.py", 0).unwrap()
+    }
+
+    fn ops(code: &CodeObject) -> String {
+        code.instructions.iter().map( |i| i.opcode.name() ).collect::<Vec<&str>>().join(" ")
+    }
+
+    fn nested(code: &CodeObject, name: &str) -> Rc<CodeObject> {
+        code.constants.iter().find_map( |c| match c {
+            Const::Code(c) if c.name == name => Some( c.clone() ),
+            _ => None
+        } ).unwrap()
+    }
+
+    #[test]
+    fn compiler_module_names() {
+        let code = compile("x = 1\ny = x + 2\n");
+        assert_eq!(ops(&code), "LOAD_CONST STORE_NAME LOAD_NAME LOAD_CONST BINARY_OP STORE_NAME LOAD_CONST RETURN_VALUE");
+        assert_eq!(code.names, vec![ "x", "y" ]);
+        assert_eq!(code.line_table, vec![ ( 0, 1 ), ( 2, 2 ) ]);
+        assert_eq!(code.stacksize, 2);
+    }
+
+    #[test]
+    fn compiler_interactive() {
+        let code = compile_mode("'doc'\n", "<stdin>", Mode::Single, 0).unwrap();
+        assert_eq!(ops(&code), "LOAD_CONST PRINT_EXPR LOAD_CONST RETURN_VALUE");
+        let code = compile_mode("for i in x: i\n\n", "<stdin>", Mode::Single, 0).unwrap();
+        assert!(ops(&code).contains("FOR_ITER STORE_NAME LOAD_NAME PRINT_EXPR"));
+    }
+
+    #[test]
+    fn compiler_function_variables() {
+        let code = compile("def f(a, b=2, *args, c, **kw):\n    y = a\n    def g():\n        return y + z\n    return g\n");
+        let f = nested(&code, "f");
+        assert_eq!(f.varnames, vec![ "a", "b", "c", "args", "kw", "g" ]);
+        assert_eq!(( f.argcount, f.kwonlyargcount ), ( 2, 1 ));
+        assert_eq!(f.cellvars, vec![ "y" ]);
+        assert!(f.flags & CO_VARARGS != 0);
+        let g = nested(&f, "g");
+        assert_eq!(g.qualname, "f.<locals>.g");
+        assert_eq!(g.freevars, vec![ "y" ]);
+        assert_eq!(ops(&g), "LOAD_DEREF LOAD_GLOBAL BINARY_OP RETURN_VALUE");
+    }
+
+    #[test]
+    fn compiler_classes() {
+        let code = compile("class C:\n    '''doc'''\n    def m(self):\n        return super().m()\n");
+        let c = nested(&code, "C");
+        assert_eq!(c.cellvars, vec![ "__class__" ]);
+        assert_eq!(c.names, vec![ "__name__", "__module__", "__qualname__", "__doc__", "m", "__classcell__" ]);
+        let m = nested(&c, "m");
+        assert_eq!(m.qualname, "C.m");
+        assert_eq!(ops(&m), "LOAD_GLOBAL LOAD_DEREF LOAD_FAST CALL LOAD_ATTR CALL RETURN_VALUE");
+    }
+
+    #[test]
+    fn compiler_comprehensions_and_generators() {
+        let code = compile("def f(xs):\n    yield from [x for x in xs]\n");
+        let f = nested(&code, "f");
+        assert!(f.flags & CO_GENERATOR != 0);
+        let comprehension = nested(&f, "<listcomp>");
+        assert_eq!(comprehension.varnames, vec![ ".0", "x" ]);
+        assert_eq!(ops(&comprehension), "BUILD_LIST LOAD_FAST FOR_ITER STORE_FAST LOAD_FAST LIST_APPEND JUMP RETURN_VALUE");
+        assert!(ops(&f).contains("GET_YIELD_FROM_ITER LOAD_CONST SEND YIELD_VALUE JUMP"));
+    }
+
+    #[test]
+    fn compiler_exception_table() {
+        let code = compile("try:\n    f()\nexcept E:\n    pass\n");
+        assert_eq!(ops(&code), "LOAD_NAME CALL POP_TOP JUMP PUSH_EXC_INFO LOAD_NAME CHECK_EXC_MATCH POP_JUMP_IF_FALSE POP_TOP \
+                                POP_EXCEPT JUMP RERAISE SWAP POP_EXCEPT RERAISE LOAD_CONST RETURN_VALUE");
+        let table : Vec<( u32, u32, u32, u32 )> = code.exception_table.iter().map( |e| ( e.start, e.end, e.target, e.depth ) ).collect();
+        assert_eq!(table, vec![ ( 0, 3, 4, 0 ), ( 4, 9, 12, 1 ), ( 11, 12, 12, 1 ) ]);
+    }
+
+    #[test]
+    fn compiler_unwinds_blocks() {
+        let code = compile("def f():\n    for x in y:\n        try:\n            return x\n        finally:\n            g()\n");
+        let f = nested(&code, "f");
+        assert!(ops(&f).starts_with("LOAD_GLOBAL GET_ITER FOR_ITER STORE_FAST LOAD_FAST LOAD_GLOBAL CALL POP_TOP SWAP POP_TOP RETURN_VALUE"));
+    }
+
+    #[test]
+    fn compiler_unsupported() {
+        assert!(compile_source("async def f():\n    pass\n", "test.py", 0).unwrap_err().contains("not supported"));
+        assert!(compile_source("x = (\n", "test.py", 0).unwrap_err().starts_with("SyntaxError"));
+    }
+}
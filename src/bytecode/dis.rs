@@ -0,0 +1,95 @@
+use crate::bytecode::code::{ Opcode, Instruction, Const, CodeObject, binary_op_symbol, compare_op_symbol,
+                             MAKE_DEFAULTS, MAKE_KWDEFAULTS, MAKE_CLOSURE, FORMAT_SPEC };
+
+
+/// Listing of code object and every code object nested in its constants, laid out like the output
+/// of CPython 'dis.dis'. Offsets count instructions, not bytes.
+pub fn disassemble(code: &CodeObject) -> String {
+    let mut out = String::new();
+    disassemble_recursive(code, &mut out);
+    out
+}
+
+fn disassemble_recursive(code: &CodeObject, out: &mut String) {
+    disassemble_code(code, out);
+    for c in &code.constants {
+        if let Const::Code(nested) = c {
+            out.push_str(&format!("\nDisassembly of {}:\n", nested));
+            disassemble_recursive(nested, out)
+        }
+    }
+}
+
+/// Instructions of one code object with source lines, jump targets and the exception table.
+pub fn disassemble_code(code: &CodeObject, out: &mut String) {
+    let mut targets : Vec<u32> = code.instructions.iter().filter( |i| i.opcode.is_jump() ).map( |i| i.arg ).collect();
+    targets.extend(code.exception_table.iter().map( |e| e.target ));
+    let mut line_starts = code.line_table.iter().peekable();
+    for ( index, instruction ) in code.instructions.iter().enumerate() {
+        let mut fields = Vec::new();
+        match line_starts.peek() {
+            Some( ( start, line ) ) if *start as usize == index => {
+                if index > 0 {
+                    out.push('\n')
+                }
+                fields.push(format!("{:>3}", line));
+                line_starts.next();
+            },
+            _ => fields.push("   ".to_string())
+        }
+        fields.push("   ".to_string());
+        fields.push(if targets.contains(&( index as u32 )) { ">>".to_string() } else { "  ".to_string() });
+        fields.push(format!("{:>4}", index));
+        fields.push(format!("{:<20}", instruction.opcode.name()));
+        if instruction.opcode.has_arg() {
+            fields.push(format!("{:>5}", instruction.arg));
+            if let Some( text ) = argument_repr(code, instruction) {
+                fields.push(format!("({})", text))
+            }
+        }
+        out.push_str(fields.join(" ").trim_end());
+        out.push('\n')
+    }
+    if !code.exception_table.is_empty() {
+        out.push_str("ExceptionTable:\n");
+        for e in &code.exception_table {
+            out.push_str(&format!("  {} to {} -> {} [{}]\n", e.start, e.end - 1, e.target, e.depth))
+        }
+    }
+}
+
+/// Meaning of instruction argument, shown in parentheses after it.
+fn argument_repr(code: &CodeObject, instruction: &Instruction) -> Option<String> {
+    let arg = instruction.arg;
+    let text = match instruction.opcode {
+        Opcode::LoadConst => code.constants[arg as usize].repr(),
+        Opcode::LoadName | Opcode::StoreName | Opcode::DeleteName | Opcode::LoadGlobal | Opcode::StoreGlobal |
+        Opcode::DeleteGlobal | Opcode::LoadAttr | Opcode::StoreAttr | Opcode::DeleteAttr | Opcode::ImportName |
+        Opcode::ImportFrom => code.names[arg as usize].clone(),
+        Opcode::LoadFast | Opcode::StoreFast | Opcode::DeleteFast => code.varnames[arg as usize].clone(),
+        Opcode::LoadDeref | Opcode::StoreDeref | Opcode::DeleteDeref | Opcode::LoadClosure |
+        Opcode::LoadClassDeref => code.deref_name(arg).to_string(),
+        Opcode::BinaryOp => binary_op_symbol(arg),
+        Opcode::CompareOp => compare_op_symbol(arg).to_string(),
+        Opcode::FormatValue => {
+            let conversion = [ "", "str", "repr", "ascii" ][( arg & 3 ) as usize];
+            match ( conversion, arg & FORMAT_SPEC != 0 ) {
+                ( "", false ) => return None,
+                ( "", true ) => "with format".to_string(),
+                ( c, true ) => format!("{}, with format", c),
+                ( c, false ) => c.to_string()
+            }
+        },
+        Opcode::MakeFunction => {
+            let flags = [ ( MAKE_DEFAULTS, "defaults" ), ( MAKE_KWDEFAULTS, "kwdefaults" ), ( MAKE_CLOSURE, "closure" ) ];
+            let names : Vec<&str> = flags.iter().filter( |( f, _ )| arg & f != 0 ).map( |( _, n )| *n ).collect();
+            if names.is_empty() {
+                return None
+            }
+            names.join(", ")
+        },
+        op if op.is_jump() => format!("to {}", arg),
+        _ => return None
+    };
+    Some( text )
+}
@@ -0,0 +1,630 @@
+use std::rc::Rc;
+use crate::ast::nodes::{ Mod, Stmt, StmtKind, Expr, ExprKind, ExprContext, Constant, Operator, UnaryOperator, CmpOperator,
+                         Comprehension, Arguments, ExceptHandler };
+use crate::ast::visitor::{ Visitor, walk_stmt, walk_expr, walk_exprs, walk_body, walk_except_handler };
+use crate::ast::dump::constant_repr;
+use crate::runtime::bigint::Int;
+use crate::runtime::value::Value;
+use crate::runtime::ops::{ binary_op, unary_op, truthy };
+use crate::bytecode::vm::VirtualMachine;
+
+
+/// Limits of CPython 'Python/ast_opt.c' on folded results, larger results are computed at run time
+/// so that compiling stays fast and code objects stay small.
+const MAX_INT_SIZE: u64 = 128;
+const MAX_COLLECTION_SIZE: usize = 256;
+const MAX_STR_SIZE: usize = 4096;
+const MAX_TOTAL_ITEMS: usize = 1024;
+
+/// Rewrite made by the optimizer, 'start' and 'end' are character offsets of the replaced node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub start: u32,
+    pub end: u32,
+    pub message: String
+}
+
+/// Optimizer of the abstract tree before compiling, like CPython 'Python/ast_opt.c'. Operations on
+/// constants are folded with the objects of the runtime, so a fold gives the value the operation
+/// would give when run and operations that raise are kept. Level 1 also removes asserts and
+/// statements behind constant conditions, as 'python -O', level 2 also removes docstrings.
+pub struct PythonCoreOptimizer {
+    source: Vec<char>,
+    level: u32,
+    runtime: VirtualMachine<'static>,
+    in_function: bool,
+    changes: Vec<Change>
+}
+
+/// Effects of statements that removing them would change even when they are never run.
+#[derive(Default)]
+struct Effects {
+    /// A name is bound, which makes it local when inside a function.
+    binds: bool,
+    /// Names are declared global or nonlocal, or 'yield' and 'await' change the kind of function.
+    changes_scope: bool
+}
+
+
+fn as_int(value: &Constant) -> Option<Int> {
+    match value {
+        Constant::Int(digits) => Int::parse(digits, 10),
+        Constant::Bool(b) => Some( Int::from(*b as i64) ),
+        _ => None
+    }
+}
+
+fn total_items(value: &Constant) -> usize {
+    match value {
+        Constant::Tuple(t) => t.len() + t.iter().map(total_items).sum::<usize>(),
+        _ => 0
+    }
+}
+
+/// Whether repeating sequence count times stays within the limits.
+fn repeat_within_limits(sequence: &Constant, count: &Int) -> bool {
+    let count = match count.to_i64() {
+        Some( n ) if n <= 0 => return true,
+        Some( n ) => n as usize,
+        None => return count.is_negative()
+    };
+    let ( size, limit ) = match sequence {
+        Constant::Str(s) => ( s.chars().count(), MAX_STR_SIZE ),
+        Constant::Bytes(b) => ( b.len(), MAX_STR_SIZE ),
+        Constant::Tuple(t) => {
+            if total_items(sequence).saturating_mul(count) > MAX_TOTAL_ITEMS {
+                return false
+            }
+            ( t.len(), MAX_COLLECTION_SIZE )
+        },
+        _ => return true
+    };
+    size == 0 || count <= limit / size
+}
+
+/// Whether result of binary operation on constants is small enough to fold.
+fn within_limits(op: Operator, left: &Constant, right: &Constant) -> bool {
+    match ( op, as_int(left), as_int(right) ) {
+        ( Operator::Mult, Some( a ), Some( b ) ) => a.is_zero() || b.is_zero() || a.bit_length() + b.bit_length() <= MAX_INT_SIZE,
+        ( Operator::Mult, None, Some( n ) ) => repeat_within_limits(left, &n),
+        ( Operator::Mult, Some( n ), None ) => repeat_within_limits(right, &n),
+        ( Operator::Pow, Some( a ), Some( b ) ) => match b.to_i64() {
+            Some( e ) if e >= 0 => a.bit_length().checked_mul(e as u64).map( |bits| bits <= MAX_INT_SIZE ).unwrap_or(false),
+            Some( _ ) => true,
+            None => b.is_negative() || a.bit_length() <= 1
+        },
+        ( Operator::LShift, Some( a ), Some( b ) ) => match b.to_i64() {
+            Some( n ) if n >= 0 => a.is_zero() || a.bit_length() + n as u64 <= MAX_INT_SIZE,
+            _ => a.is_zero()
+        },
+        ( Operator::Mod, _, _ ) => !matches!(left, Constant::Str(_) | Constant::Bytes(_)),
+        _ => true
+    }
+}
+
+fn to_value(value: &Constant) -> Option<Value<'static>> {
+    Some( match value {
+        Constant::None => Value::None,
+        Constant::Bool(b) => Value::Bool(*b),
+        Constant::Str(s) => Value::str(s),
+        Constant::Bytes(b) => Value::Bytes(Rc::from(b.as_slice())),
+        Constant::Int(digits) => Value::Int(Int::parse(digits, 10)?),
+        Constant::Float(f) => Value::Float(*f),
+        Constant::Tuple(t) => Value::tuple(t.iter().map(to_value).collect::<Option<Vec<Value>>>()?),
+        Constant::Ellipsis => Value::Ellipsis,
+        Constant::Complex(..) => return None
+    } )
+}
+
+fn from_value(value: &Value) -> Option<Constant> {
+    Some( match value {
+        Value::None => Constant::None,
+        Value::Bool(b) => Constant::Bool(*b),
+        Value::Str(s) => Constant::Str(s.to_string()),
+        Value::Bytes(b) => Constant::Bytes(b.to_vec()),
+        Value::Int(i) => Constant::Int(i.to_string()),
+        Value::Float(f) => Constant::Float(*f),
+        Value::Tuple(t) => Constant::Tuple(t.iter().map(from_value).collect::<Option<Vec<Constant>>>()?),
+        Value::Ellipsis => Constant::Ellipsis,
+        _ => return None
+    } )
+}
+
+fn constant_of(expr: &Expr) -> Option<&Constant> {
+    match &expr.node {
+        ExprKind::Constant { value, .. } => Some( value ),
+        _ => None
+    }
+}
+
+/// Tuple of the values of expressions when all of them are constants.
+fn constant_tuple(elts: &[Expr]) -> Option<Constant> {
+    elts.iter().map( |e| constant_of(e).cloned() ).collect::<Option<Vec<Constant>>>().map(Constant::Tuple)
+}
+
+fn is_docstring(stmt: &Stmt) -> bool {
+    match &stmt.node {
+        StmtKind::Expr { value } => matches!(constant_of(value), Some( Constant::Str(_) )),
+        _ => false
+    }
+}
+
+fn inverted(op: CmpOperator) -> Option<CmpOperator> {
+    match op {
+        CmpOperator::In => Some( CmpOperator::NotIn ),
+        CmpOperator::NotIn => Some( CmpOperator::In ),
+        CmpOperator::Is => Some( CmpOperator::IsNot ),
+        CmpOperator::IsNot => Some( CmpOperator::Is ),
+        _ => None
+    }
+}
+
+fn cmp_text(op: CmpOperator) -> &'static str {
+    match op {
+        CmpOperator::In => "in",
+        CmpOperator::NotIn => "not in",
+        CmpOperator::Is => "is",
+        _ => "is not"
+    }
+}
+
+impl Visitor for Effects {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.node {
+            StmtKind::FunctionDef { .. } | StmtKind::AsyncFunctionDef { .. } | StmtKind::ClassDef { .. } |
+            StmtKind::Import { .. } | StmtKind::ImportFrom { .. } => self.binds = true,
+            StmtKind::Global { .. } | StmtKind::Nonlocal { .. } => self.changes_scope = true,
+            _ => walk_stmt(self, stmt)
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.node {
+            ExprKind::Name { ctx, .. } => self.binds |= *ctx != ExprContext::Load,
+            ExprKind::Yield { .. } | ExprKind::YieldFrom { .. } | ExprKind::Await { .. } => self.changes_scope = true,
+            ExprKind::Lambda { .. } => return,
+            _ => ()
+        }
+        walk_expr(self, expr)
+    }
+
+    fn visit_except_handler(&mut self, handler: &ExceptHandler) {
+        self.binds |= handler.name.is_some();
+        walk_except_handler(self, handler)
+    }
+
+    /// Targets of comprehensions are bound in the scope of the comprehension.
+    fn visit_comprehension(&mut self, comprehension: &Comprehension) {
+        self.visit_expr(&comprehension.iter);
+        walk_exprs(self, &comprehension.ifs)
+    }
+}
+
+impl PythonCoreOptimizer {
+    pub fn new(source: &str, level: u32) -> PythonCoreOptimizer {
+        PythonCoreOptimizer {
+            source: source.chars().collect(),
+            level,
+            runtime: VirtualMachine::new(),
+            in_function: false,
+            changes: Vec::new()
+        }
+    }
+
+    /// Optimizes module in place and returns the changes in source order.
+    pub fn optimize_mod(mut self, module: &mut Mod) -> Vec<Change> {
+        match module {
+            Mod::Module { body, .. } => {
+                self.strip_docstring(body);
+                self.body(body, false)
+            },
+            Mod::Interactive { body } => self.body(body, false),
+            Mod::Expression { body } => self.expr(body),
+            Mod::FunctionType { .. } => ()
+        }
+        self.changes.sort_by_key( |c| ( c.start, c.end ) );
+        self.changes
+    }
+
+    fn text(&self, start: u32, end: u32) -> String {
+        self.source[( start as usize ).min(self.source.len()) .. ( end as usize ).min(self.source.len())].iter().collect()
+    }
+
+    fn report(&mut self, start: u32, end: u32, message: String) {
+        self.changes.push(Change { start, end, message })
+    }
+
+    /// Replaces expression by node, changes already reported inside of it are merged into one.
+    fn replace(&mut self, expr: &mut Expr, node: ExprKind, message: String) {
+        let ( start, end ) = ( expr.location.start, expr.location.end );
+        while self.changes.last().map( |c| c.start >= start && c.end <= end ).unwrap_or(false) {
+            self.changes.pop();
+        }
+        self.report(start, end, message);
+        expr.node = node
+    }
+
+    fn fold(&mut self, expr: &mut Expr, value: Constant) {
+        let message = format!("folded '{}' to {}", self.text(expr.location.start, expr.location.end), constant_repr(&value));
+        self.replace(expr, ExprKind::Constant { value, kind: None }, message)
+    }
+
+    /// Whether removing statements that are never run keeps the meaning of the code around them.
+    fn removable(&self, stmts: &[Stmt]) -> bool {
+        let mut effects = Effects::default();
+        walk_body(&mut effects, stmts);
+        !( effects.changes_scope || self.in_function && effects.binds )
+    }
+
+    fn strip_docstring(&mut self, body: &mut Vec<Stmt>) {
+        if self.level >= 2 && body.first().map(is_docstring).unwrap_or(false) {
+            let doc = body.remove(0);
+            self.report(doc.location.start, doc.location.end, "removed docstring".to_string())
+        }
+    }
+
+    /// Optimizes statements of body, a required body that ends up empty gets a 'pass'.
+    fn body(&mut self, body: &mut Vec<Stmt>, required: bool) {
+        let stmts = std::mem::take(body);
+        let first = stmts.first().map( |s| s.location.clone() );
+        for stmt in stmts {
+            self.stmt(stmt, body)
+        }
+        if let ( true, true, Some( location ) ) = ( body.is_empty(), required, first ) {
+            body.push(Stmt { node: StmtKind::Pass, location })
+        }
+    }
+
+    fn scope_body(&mut self, body: &mut Vec<Stmt>, in_function: bool) {
+        let outer = std::mem::replace(&mut self.in_function, in_function);
+        self.strip_docstring(body);
+        self.body(body, true);
+        self.in_function = outer
+    }
+
+    /// Adds optimized statement to out, or the statements replacing it.
+    fn stmt(&mut self, mut stmt: Stmt, out: &mut Vec<Stmt>) {
+        let ( start, end ) = ( stmt.location.start, stmt.location.end );
+        match &mut stmt.node {
+            StmtKind::FunctionDef { args, body, decorator_list, returns, .. } |
+            StmtKind::AsyncFunctionDef { args, body, decorator_list, returns, .. } => {
+                self.exprs(decorator_list);
+                self.arguments(args);
+                self.optional(returns);
+                self.scope_body(body, true)
+            },
+            StmtKind::ClassDef { bases, keywords, body, decorator_list, .. } => {
+                self.exprs(decorator_list);
+                self.exprs(bases);
+                for k in keywords.iter_mut() {
+                    self.expr(&mut k.value)
+                }
+                self.scope_body(body, false)
+            },
+            StmtKind::Return { value } => self.optional(value),
+            StmtKind::Delete { targets } => self.exprs(targets),
+            StmtKind::Assign { targets, value, .. } => {
+                self.exprs(targets);
+                self.expr(value)
+            },
+            StmtKind::AugAssign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value)
+            },
+            StmtKind::AnnAssign { target, annotation, value, .. } => {
+                self.expr(target);
+                self.expr(annotation);
+                self.optional(value)
+            },
+            StmtKind::For { target, iter, body, orelse, .. } |
+            StmtKind::AsyncFor { target, iter, body, orelse, .. } => {
+                self.expr(target);
+                self.iterable(iter);
+                self.body(body, true);
+                self.body(orelse, false)
+            },
+            StmtKind::While { test, body, orelse } => {
+                self.expr(test);
+                self.body(body, true);
+                self.body(orelse, false);
+                let never = constant_of(test).and_then(to_value).map( |v| !truthy(&mut self.runtime, &v).unwrap_or(true) ).unwrap_or(false);
+                if self.level >= 1 && never && self.removable(body) {
+                    self.report(start, end, "removed 'while' loop with false condition".to_string());
+                    out.append(orelse);
+                    return
+                }
+            },
+            StmtKind::If { test, body, orelse } => {
+                self.expr(test);
+                self.body(body, true);
+                self.body(orelse, false);
+                let value = constant_of(test).and_then(to_value).and_then( |v| truthy(&mut self.runtime, &v).ok() );
+                if let ( true, Some( value ) ) = ( self.level >= 1, value ) {
+                    let ( kept, dropped ) = if value { ( body, orelse ) } else { ( orelse, body ) };
+                    if self.removable(dropped) {
+                        let message = format!("removed 'if' statement with constant condition {}", if value { "True" } else { "False" });
+                        self.report(start, end, message);
+                        out.append(kept);
+                        return
+                    }
+                }
+            },
+            StmtKind::With { items, body, .. } |
+            StmtKind::AsyncWith { items, body, .. } => {
+                for item in items.iter_mut() {
+                    self.expr(&mut item.context_expr);
+                    self.optional(&mut item.optional_vars)
+                }
+                self.body(body, true)
+            },
+            StmtKind::Raise { exc, cause } => {
+                self.optional(exc);
+                self.optional(cause)
+            },
+            StmtKind::Match { subject, cases } => {
+                self.expr(subject);
+                for c in cases.iter_mut() {
+                    self.optional(&mut c.guard);
+                    self.body(&mut c.body, true)
+                }
+            },
+            StmtKind::Try { body, handlers, orelse, finalbody } |
+            StmtKind::TryStar { body, handlers, orelse, finalbody } => {
+                self.body(body, true);
+                for h in handlers.iter_mut() {
+                    self.optional(&mut h.type_);
+                    self.body(&mut h.body, true)
+                }
+                self.body(orelse, false);
+                self.body(finalbody, handlers.is_empty())
+            },
+            StmtKind::Assert { test, msg } => {
+                self.expr(test);
+                self.optional(msg);
+            },
+            StmtKind::Expr { value } => self.expr(value),
+            StmtKind::Import { .. } | StmtKind::ImportFrom { .. } | StmtKind::Global { .. } | StmtKind::Nonlocal { .. } |
+            StmtKind::Pass | StmtKind::Break | StmtKind::Continue => ()
+        }
+        if let ( StmtKind::Assert { .. }, true ) = ( &stmt.node, self.level >= 1 ) {
+            if self.removable(std::slice::from_ref(&stmt)) {
+                self.report(start, end, "removed assert statement".to_string());
+                return
+            }
+        }
+        out.push(stmt)
+    }
+
+    fn arguments(&mut self, args: &mut Arguments) {
+        for arg in args.posonlyargs.iter_mut().chain(args.args.iter_mut()).chain(args.kwonlyargs.iter_mut())
+                       .chain(args.vararg.iter_mut().map( |a| a.as_mut() )).chain(args.kwarg.iter_mut().map( |a| a.as_mut() )) {
+            self.optional(&mut arg.annotation)
+        }
+        self.exprs(&mut args.defaults);
+        for d in args.kw_defaults.iter_mut().flatten() {
+            self.expr(d)
+        }
+    }
+
+    fn exprs(&mut self, exprs: &mut [Expr]) {
+        for e in exprs {
+            self.expr(e)
+        }
+    }
+
+    fn optional(&mut self, expr: &mut Option<Box<Expr>>) {
+        if let Some( e ) = expr {
+            self.expr(e)
+        }
+    }
+
+    /// Iterable of 'for' loop or comprehension, where a list of constants becomes a tuple.
+    fn iterable(&mut self, expr: &mut Expr) {
+        self.expr(expr);
+        if let ExprKind::List { elts, .. } = &expr.node {
+            if let Some( value ) = constant_tuple(elts) {
+                self.fold(expr, value)
+            }
+        }
+    }
+
+    fn comprehensions(&mut self, generators: &mut [Comprehension]) {
+        for g in generators {
+            self.expr(&mut g.target);
+            self.iterable(&mut g.iter);
+            self.exprs(&mut g.ifs)
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match &mut expr.node {
+            ExprKind::BoolOp { values, .. } => self.exprs(values),
+            ExprKind::NamedExpr { value, .. } => self.expr(value),
+            ExprKind::BinOp { left, right, .. } => {
+                self.expr(left);
+                self.expr(right)
+            },
+            ExprKind::UnaryOp { operand, .. } => self.expr(operand),
+            ExprKind::Lambda { args, body } => {
+                self.arguments(args);
+                self.expr(body)
+            },
+            ExprKind::IfExp { test, body, orelse } => {
+                self.expr(test);
+                self.expr(body);
+                self.expr(orelse)
+            },
+            ExprKind::Dict { keys, values } => {
+                for k in keys.iter_mut().flatten() {
+                    self.expr(k)
+                }
+                self.exprs(values)
+            },
+            ExprKind::Set { elts } | ExprKind::List { elts, .. } | ExprKind::Tuple { elts, .. } => self.exprs(elts),
+            ExprKind::ListComp { elt, generators } | ExprKind::SetComp { elt, generators } |
+            ExprKind::GeneratorExp { elt, generators } => {
+                self.expr(elt);
+                self.comprehensions(generators)
+            },
+            ExprKind::DictComp { key, value, generators } => {
+                self.expr(key);
+                self.expr(value);
+                self.comprehensions(generators)
+            },
+            ExprKind::Await { value } | ExprKind::YieldFrom { value } | ExprKind::Attribute { value, .. } |
+            ExprKind::Starred { value, .. } => self.expr(value),
+            ExprKind::Yield { value } => self.optional(value),
+            ExprKind::Compare { left, ops, comparators } => {
+                self.expr(left);
+                self.exprs(comparators);
+                if let ( Some( CmpOperator::In | CmpOperator::NotIn ), Some( last ) ) = ( ops.last(), comparators.last_mut() ) {
+                    if let ExprKind::List { elts, .. } = &last.node {
+                        if let Some( value ) = constant_tuple(elts) {
+                            self.fold(last, value)
+                        }
+                    }
+                }
+            },
+            ExprKind::Call { func, args, keywords } => {
+                self.expr(func);
+                self.exprs(args);
+                for k in keywords.iter_mut() {
+                    self.expr(&mut k.value)
+                }
+            },
+            ExprKind::FormattedValue { value, format_spec, .. } => {
+                self.expr(value);
+                self.optional(format_spec)
+            },
+            ExprKind::JoinedStr { values } => self.exprs(values),
+            ExprKind::Subscript { value, slice, .. } => {
+                self.expr(value);
+                self.expr(slice)
+            },
+            ExprKind::Slice { lower, upper, step } => {
+                self.optional(lower);
+                self.optional(upper);
+                self.optional(step)
+            },
+            ExprKind::Constant { .. } | ExprKind::Name { .. } => ()
+        }
+        self.fold_expr(expr)
+    }
+
+    /// Folds expression whose operands are already optimized.
+    fn fold_expr(&mut self, expr: &mut Expr) {
+        let value = match &expr.node {
+            ExprKind::BinOp { left, op, right } => match ( constant_of(left), constant_of(right) ) {
+                ( Some( a ), Some( b ) ) if within_limits(*op, a, b) => match ( to_value(a), to_value(b) ) {
+                    ( Some( x ), Some( y ) ) => binary_op(&mut self.runtime, *op, &x, &y).ok().as_ref().and_then(from_value),
+                    _ => None
+                },
+                _ => None
+            },
+            ExprKind::UnaryOp { op, operand } => match ( op, constant_of(operand).and_then(to_value) ) {
+                ( UnaryOperator::Not, Some( v ) ) => truthy(&mut self.runtime, &v).ok().map( |b| Constant::Bool(!b) ),
+                ( _, Some( v ) ) => unary_op(&mut self.runtime, *op, &v).ok().as_ref().and_then(from_value),
+                ( UnaryOperator::Not, None ) => return self.fold_not(expr),
+                _ => None
+            },
+            ExprKind::Tuple { elts, ctx: ExprContext::Load } => constant_tuple(elts),
+            ExprKind::Name { id, ctx: ExprContext::Load } if id == "__debug__" => {
+                let value = Constant::Bool(self.level == 0);
+                let message = format!("replaced '__debug__' with {}", constant_repr(&value));
+                return self.replace(expr, ExprKind::Constant { value, kind: None }, message)
+            },
+            _ => None
+        };
+        if let Some( value ) = value {
+            self.fold(expr, value)
+        }
+    }
+
+    /// Moves 'not' into a single membership or identity test, so 'not not a in b' becomes 'a in b'.
+    fn fold_not(&mut self, expr: &mut Expr) {
+        let compare = match &expr.node {
+            ExprKind::UnaryOp { operand, .. } => match &operand.node {
+                ExprKind::Compare { left, ops, comparators } if ops.len() == 1 =>
+                    inverted(ops[0]).map( |op| ( left.clone(), op, comparators.clone() ) ),
+                _ => None
+            },
+            _ => None
+        };
+        if let Some( ( left, op, comparators ) ) = compare {
+            let message = format!("folded '{}' to '{} {} {}'", self.text(expr.location.start, expr.location.end),
+                                  self.text(left.location.start, left.location.end), cmp_text(op),
+                                  self.text(comparators[0].location.start, comparators[0].location.end));
+            self.replace(expr, ExprKind::Compare { left, ops: vec![ op ], comparators }, message)
+        }
+    }
+}
+
+
+// UnitTests for AST optimizer ////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{ Mode, parse };
+    use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+    use crate::ast::dump::Dumper;
+    use crate::bytecode::optimizer::{ PythonCoreOptimizer, Change };
+
+
+    fn optimize(source: &str, level: u32) -> ( String, Vec<Change> ) {
+        let tree = parse(source, Mode::Exec).tree.unwrap();
+        let mut module = PythonCoreLowering::new(source).lower_mod(&tree).unwrap();
+        let changes = PythonCoreOptimizer::new(source, level).optimize_mod(&mut module);
+        ( module.dump(false), changes )
+    }
+
+    fn messages(source: &str, level: u32) -> Vec<String> {
+        optimize(source, level).1.into_iter().map( |c| c.message ).collect()
+    }
+
+    #[test]
+    fn optimizer_folds_arithmetic() {
+        assert_eq!(messages("x = 2 ** 10 * 3\n", 0), [ "folded '2 ** 10 * 3' to 3072" ]);
+        assert_eq!(messages("x = 'ab' * 3 + 'c'\ny = -5\nz = 7 / 2\n", 0),
+                   [ "folded ''ab' * 3 + 'c'' to 'abababc'", "folded '-5' to -5", "folded '7 / 2' to 3.5" ]);
+        assert_eq!(messages("x = (1, (2, 3))\ny = not 0\n", 0), [ "folded '(1, (2, 3))' to (1, (2, 3))", "folded 'not 0' to True" ]);
+        let ( dump, _ ) = optimize("x = 1 + 2\n", 0);
+        assert_eq!(dump, "Module(body=[Assign(targets=[Name(id='x', ctx=Store())], value=Constant(value=3))], type_ignores=[])");
+    }
+
+    #[test]
+    fn optimizer_keeps_errors_and_large_results() {
+        assert!(messages("x = 1 / 0\ny = 'a' + 1\nz = '%s' % 1\n", 0).is_empty());
+        assert!(messages("x = 2 ** 200\ny = 'ab' * 5000\nw = 1 << 200\n", 0).is_empty());
+        assert_eq!(messages("z = (1,) * 300\n", 0), [ "folded '(1,)' to (1,)" ]);
+        assert_eq!(messages("x = 2 ** 64\ny = 'ab' * 2048\n", 0).len(), 2);
+    }
+
+    #[test]
+    fn optimizer_folds_membership() {
+        assert_eq!(messages("if not not a in b: pass\n", 0), [ "folded 'not not a in b' to 'a in b'" ]);
+        assert_eq!(messages("if not a is None: pass\n", 0), [ "folded 'not a is None' to 'a is not None'" ]);
+        assert_eq!(messages("for i in [1, 2]: pass\nx in [3, 4]\n", 0), [ "folded '[1, 2]' to (1, 2)", "folded '[3, 4]' to (3, 4)" ]);
+        assert!(messages("x = [1, 2]\nnot a < b\n", 0).is_empty());
+    }
+
+    #[test]
+    fn optimizer_levels() {
+        let source = "'doc'\nassert x\nif False:\n    a()\nelse:\n    b()\nwhile 0:\n    pass\nif __debug__:\n    c()\n";
+        assert_eq!(messages(source, 0), [ "replaced '__debug__' with True" ]);
+        assert_eq!(messages(source, 1), [ "removed assert statement", "removed 'if' statement with constant condition False",
+                                          "removed 'while' loop with false condition", "removed 'if' statement with constant condition False",
+                                          "replaced '__debug__' with False" ]);
+        assert_eq!(messages(source, 2).len(), 6);
+        let ( dump, _ ) = optimize(source, 2);
+        assert_eq!(dump, "Module(body=[Expr(value=Call(func=Name(id='b', ctx=Load()), args=[], keywords=[]))], type_ignores=[])");
+    }
+
+    #[test]
+    fn optimizer_keeps_scope_of_names() {
+        assert!(messages("def f():\n    if False:\n        x = 1\n    return x\n", 1).is_empty());
+        assert!(messages("def f():\n    if False:\n        yield\n", 1).is_empty());
+        assert_eq!(messages("if False:\n    x = 1\n", 1).len(), 1);
+        let ( dump, _ ) = optimize("def f():\n    assert x\n", 1);
+        assert!(dump.contains("body=[Pass()]"));
+    }
+}
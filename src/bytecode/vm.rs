@@ -0,0 +1,1112 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::mpsc::{ channel, Sender, Receiver };
+use crate::ast::nodes::UnaryOperator;
+use crate::bytecode::code::{ Opcode, Instruction, Const, CodeObject, binary_op_of, compare_op_of, CO_VARARGS, CO_VARKEYWORDS,
+                             MAKE_DEFAULTS, MAKE_KWDEFAULTS, MAKE_CLOSURE, FORMAT_STR, FORMAT_REPR, FORMAT_ASCII, FORMAT_SPEC };
+use crate::api::Mode;
+use crate::bytecode::compiler::{ compile_source, compile };
+use crate::runtime::value::{ Value, RunResult, Runtime, Builtins, Cell, Globals, Code, Function, Signature, Generator, Suspension, Resume,
+                             Step, TypeKind, Dict, TracebackEntry };
+use crate::runtime::ops::{ throw, error, call, bind_arguments, truthy, repr, to_str, binary_op, inplace_op, unary_op, compare, contains,
+                           hash_key, get_attr, set_attr, del_attr, get_item, set_item, del_item, iter, next, collect, dict_update,
+                           set_from, to_index, is_instance, is_exception, is_same, class_of, type_name };
+use crate::runtime::builtins::new_class;
+use crate::runtime::format::format_value;
+use crate::runtime::interpreter::{ format_traceback, link_context, RECURSION_LIMIT, STACK_SIZE };
+
+
+/// Activation of a code object with its instruction pointer, value stack and variables.
+pub struct Frame<'a> {
+    code: Rc<CodeObject>,
+    pc: usize,
+    stack: Vec<Value<'a>>,
+    fast: Vec<Option<Value<'a>>>,
+    /// Cells of the variables in 'cellvars' followed by those of 'freevars'.
+    cells: Vec<Cell<'a>>,
+    /// Namespace of class body, module code keeps its names in the globals.
+    locals: Option<HashMap<String, Value<'a>>>,
+    globals: Globals<'a>,
+    /// Number of 'except' clauses and 'finally' blocks being run, with the exception they handle
+    /// while a generator is suspended.
+    handling: usize,
+    handled: Value<'a>
+}
+
+/// How running a frame stopped.
+enum Exit<'a> {
+    Return(Value<'a>),
+    Yield(Value<'a>)
+}
+
+/// Exception raised by an instruction. A reraised exception keeps its traceback unchanged.
+enum Fault<'a> {
+    Raise(Value<'a>),
+    Reraise(Value<'a>)
+}
+
+/// Virtual machine running code objects of the bytecode compiler on the object model of the tree
+/// walking interpreter. Frames find handlers in the exception table of their code, so a generator
+/// suspends by keeping its frame.
+pub struct VirtualMachine<'a> {
+    builtins: Rc<Builtins<'a>>,
+    globals: Globals<'a>,
+    /// Exception handled by the innermost 'except' clause or 'finally' block, or None.
+    exc_info: Value<'a>,
+    depth: usize,
+    output: String
+}
+
+/// Virtual machine on a thread of its own, with a stack deep enough for the recursion limit, that
+/// keeps its globals between the statements of an interactive session.
+pub struct Session {
+    requests: Sender<String>,
+    replies: Receiver<( String, Result<(), String> )>
+}
+
+
+impl<'a> From<Value<'a>> for Fault<'a> {
+    fn from(exception: Value<'a>) -> Fault<'a> {
+        Fault::Raise(exception)
+    }
+}
+
+/// Compiles module at optimization level and runs it on a thread with a stack deep enough for the
+/// recursion limit. Returns the printed output and the traceback of an uncaught exception.
+pub fn run_bytecode(source: &str, file: &str, optimize: u32) -> ( String, Result<(), String> ) {
+    let ( source, file ) = ( source.to_string(), file.to_string() );
+    let worker = std::thread::Builder::new().stack_size(STACK_SIZE).spawn(move || {
+        let code = match compile_source(&source, &file, optimize) {
+            Ok( c ) => c,
+            Err( e ) => return ( String::new(), Err( format!("{}\n", e) ) )
+        };
+        let mut vm = VirtualMachine::new();
+        let res = vm.run_code(code).map_err( |e| format_traceback(&mut vm, &e, &file, &source) );
+        ( vm.take_output(), res )
+    });
+    match worker.map( |w| w.join() ) {
+        Ok( Ok( res ) ) => res,
+        _ => ( String::new(), Err( "Fatal Python error: virtual machine thread failed\n".to_string() ) )
+    }
+}
+
+impl Session {
+    pub fn new(filename: &str, optimize: u32) -> Session {
+        let ( requests, inputs ) = channel::<String>();
+        let ( results, replies ) = channel();
+        let filename = filename.to_string();
+        let _ = std::thread::Builder::new().stack_size(STACK_SIZE).spawn(move || {
+            let mut vm = VirtualMachine::new();
+            for source in inputs {
+                let res = match compile(&source, &filename, Mode::Single, optimize) {
+                    Ok( code ) => vm.run_code(code).map_err( |e| format_traceback(&mut vm, &e, &filename, "") ),
+                    Err( e ) => Err( format!("{}\n", e) )
+                };
+                if results.send(( vm.take_output(), res )).is_err() {
+                    break
+                }
+            }
+        });
+        Session { requests, replies }
+    }
+
+    /// Runs one interactive statement, returns the printed output and the traceback of an uncaught
+    /// exception. Source lines are left out of tracebacks, as earlier inputs are gone.
+    pub fn run(&self, source: &str) -> ( String, Result<(), String> ) {
+        match self.requests.send(source.to_string()).ok().and_then( |_| self.replies.recv().ok() ) {
+            Some( res ) => res,
+            None => ( String::new(), Err( "Fatal Python error: virtual machine thread failed\n".to_string() ) )
+        }
+    }
+}
+
+fn signature_of(code: &CodeObject) -> Signature {
+    let ( npos, nkw ) = ( code.argcount as usize, code.kwonlyargcount as usize );
+    let mut rest = code.varnames[npos + nkw ..].iter();
+    Signature {
+        positional: code.varnames[.. npos].to_vec(),
+        posonly: code.posonlyargcount as usize,
+        vararg: if code.flags & CO_VARARGS != 0 { rest.next().cloned() } else { None },
+        kwonly: code.varnames[npos .. npos + nkw].to_vec(),
+        kwarg: if code.flags & CO_VARKEYWORDS != 0 { rest.next().cloned() } else { None }
+    }
+}
+
+fn constant<'a>(value: &Const) -> Value<'a> {
+    match value {
+        Const::None => Value::None,
+        Const::Ellipsis => Value::Ellipsis,
+        Const::Bool(b) => Value::Bool(*b),
+        Const::Int(i) => Value::Int(i.clone()),
+        Const::Float(f) => Value::Float(*f),
+        Const::Str(s) => Value::str(s),
+        Const::Bytes(b) => Value::Bytes(Rc::from(b.as_slice())),
+        Const::Tuple(t) => Value::tuple(t.iter().map(constant).collect()),
+        Const::Code(c) => Value::Code(c.clone())
+    }
+}
+
+/// Placeholder of the builtin that creates classes, calls of it are run by the virtual machine.
+fn build_class_stub<'a>(rt: &mut dyn Runtime<'a>, _args: Vec<Value<'a>>, _kwargs: Vec<( String, Value<'a> )>) -> RunResult<'a> {
+    throw(rt, "TypeError", "__build_class__ is only available to the bytecode virtual machine")
+}
+
+impl<'a> Frame<'a> {
+    /// Frame with unbound locals, new cells for its own shared variables and the cells of its free
+    /// variables taken from closure.
+    fn new(code: Rc<CodeObject>, globals: Globals<'a>, locals: Option<HashMap<String, Value<'a>>>, closure: &HashMap<String, Cell<'a>>) -> Frame<'a> {
+        let mut cells : Vec<Cell<'a>> = code.cellvars.iter().map( |_| Rc::new( RefCell::new(None) ) ).collect();
+        cells.extend(code.freevars.iter().map( |n| closure.get(n).cloned().unwrap_or_default() ));
+        Frame {
+            fast: vec![ None; code.varnames.len() ],
+            code,
+            pc: 0,
+            stack: Vec::new(),
+            cells,
+            locals,
+            globals,
+            handling: 0,
+            handled: Value::None
+        }
+    }
+
+    fn pop(&mut self) -> Value<'a> {
+        self.stack.pop().expect("value stack underflow")
+    }
+
+    fn top(&self) -> Value<'a> {
+        self.stack.last().cloned().expect("value stack underflow")
+    }
+
+    fn pop_n(&mut self, n: usize) -> Vec<Value<'a>> {
+        let at = self.stack.len() - n;
+        self.stack.split_off(at)
+    }
+
+    fn peek(&self, n: usize) -> Value<'a> {
+        self.stack[self.stack.len() - n].clone()
+    }
+}
+
+impl<'a> Default for VirtualMachine<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> VirtualMachine<'a> {
+    pub fn new() -> VirtualMachine<'a> {
+        let mut globals = HashMap::new();
+        globals.insert("__name__".to_string(), Value::str("__main__"));
+        VirtualMachine {
+            builtins: Rc::new( Builtins::new() ),
+            globals: Rc::new( RefCell::new(globals) ),
+            exc_info: Value::None,
+            depth: 0,
+            output: String::new()
+        }
+    }
+
+    /// Runs module code in the globals of the virtual machine, returns the uncaught exception.
+    pub fn run_code(&mut self, code: Rc<CodeObject>) -> Result<(), Value<'a>> {
+        let mut frame = Frame::new(code, self.globals.clone(), None, &HashMap::new());
+        self.invoke(&mut frame).map( |_| () )
+    }
+
+    /// Output printed since last call.
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Runs frame of a call, the exception being handled by the caller is restored after it.
+    fn invoke(&mut self, frame: &mut Frame<'a>) -> RunResult<'a> {
+        if self.depth >= RECURSION_LIMIT {
+            return throw(self, "RecursionError", "maximum recursion depth exceeded")
+        }
+        self.depth += 1;
+        let exc_info = self.exc_info.clone();
+        let res = self.run(frame, None);
+        self.depth -= 1;
+        self.exc_info = exc_info;
+        match res {
+            Ok( Exit::Return(v) | Exit::Yield(v) ) => Ok( v ),
+            Err( e ) => Err( e )
+        }
+    }
+
+    /// Executes instructions of frame until it returns, yields or lets an exception escape. Input
+    /// is the value sent or thrown into a resumed generator.
+    fn run(&mut self, frame: &mut Frame<'a>, input: Option<Resume<'a>>) -> Result<Exit<'a>, Value<'a>> {
+        let mut fault = match input {
+            None => None,
+            Some( Resume::Send(v) ) => {
+                frame.stack.push(v);
+                None
+            },
+            Some( Resume::Throw(e) ) => match self.throw_into(frame, e) {
+                Ok( Some( exit ) ) => return Ok( exit ),
+                Ok( None ) => None,
+                Err( f ) => Some( f )
+            }
+        };
+        loop {
+            if let Some( f ) = fault.take() {
+                let index = frame.pc.saturating_sub(1);
+                let exception = match f {
+                    Fault::Raise(e) => {
+                        self.record(frame, index, &e);
+                        link_context(&self.exc_info, &e);
+                        e
+                    },
+                    Fault::Reraise(e) => e
+                };
+                match frame.code.handler_of(index) {
+                    Some( handler ) => {
+                        frame.stack.truncate(handler.depth as usize);
+                        frame.stack.push(exception);
+                        frame.pc = handler.target as usize
+                    },
+                    None => return Err( exception )
+                }
+            }
+            let instruction = frame.code.instructions[frame.pc];
+            frame.pc += 1;
+            match self.step(frame, instruction) {
+                Ok( None ) => (),
+                Ok( Some( exit ) ) => return Ok( exit ),
+                Err( f ) => fault = Some( f )
+            }
+        }
+    }
+
+    /// Raises exception thrown into generator at its 'yield'. A generator delegating with 'yield
+    /// from' passes it on to the generator it delegates to first.
+    fn throw_into(&mut self, frame: &mut Frame<'a>, exception: Value<'a>) -> Result<Option<Exit<'a>>, Fault<'a>> {
+        let delegating = frame.pc > 0 && frame.code.instructions[frame.pc - 1] == Instruction { opcode: Opcode::YieldValue, arg: 1 };
+        let generator = match frame.stack.last() {
+            Some( Value::Generator(g) ) if delegating => g.clone(),
+            _ => return Err( Fault::Raise(exception) )
+        };
+        match self.resume(&generator, Resume::Throw(exception))? {
+            Step::Yield(v) => Ok( Some( Exit::Yield(v) ) ),
+            Step::Return(v) => {
+                frame.pop();
+                frame.stack.push(v);
+                let send = frame.code.instructions[frame.pc].arg as usize;
+                frame.pc = frame.code.instructions[send].arg as usize;
+                Ok( None )
+            }
+        }
+    }
+
+    /// Adds frame at instruction to the traceback of exception.
+    fn record(&self, frame: &Frame<'a>, index: usize, exception: &Value<'a>) {
+        if let Value::Instance(i) = exception {
+            i.traceback.borrow_mut().push(TracebackEntry { line: frame.code.line_of(index), name: frame.code.name.clone() })
+        }
+    }
+
+    fn step(&mut self, frame: &mut Frame<'a>, instruction: Instruction) -> Result<Option<Exit<'a>>, Fault<'a>> {
+        let code = frame.code.clone();
+        let arg = instruction.arg as usize;
+        match instruction.opcode {
+            Opcode::Nop | Opcode::SetupFinally | Opcode::SetupCleanup | Opcode::PopBlock => (),
+            Opcode::PopTop => {
+                frame.pop();
+            },
+            Opcode::Copy => {
+                let v = frame.peek(arg);
+                frame.stack.push(v)
+            },
+            Opcode::Swap => {
+                let len = frame.stack.len();
+                frame.stack.swap(len - 1, len - arg)
+            },
+            Opcode::LoadConst => frame.stack.push(constant(&code.constants[arg])),
+            Opcode::LoadName => {
+                let name = &code.names[arg];
+                let v = match frame.locals.as_ref().and_then( |l| l.get(name) ) {
+                    Some( v ) => v.clone(),
+                    None => self.load_global(frame, name)?
+                };
+                frame.stack.push(v)
+            },
+            Opcode::StoreName => {
+                let v = frame.pop();
+                match &mut frame.locals {
+                    Some( locals ) => {
+                        locals.insert(code.names[arg].clone(), v);
+                    },
+                    None => {
+                        frame.globals.borrow_mut().insert(code.names[arg].clone(), v);
+                    }
+                }
+            },
+            Opcode::DeleteName => {
+                let name = &code.names[arg];
+                let found = match &mut frame.locals {
+                    Some( locals ) => locals.remove(name).is_some(),
+                    None => frame.globals.borrow_mut().remove(name).is_some()
+                };
+                if !found {
+                    return throw(self, "NameError", format!("name '{}' is not defined", name)).map_err(Fault::from)
+                }
+            },
+            Opcode::LoadGlobal => {
+                let v = self.load_global(frame, &code.names[arg])?;
+                frame.stack.push(v)
+            },
+            Opcode::StoreGlobal => {
+                let v = frame.pop();
+                frame.globals.borrow_mut().insert(code.names[arg].clone(), v);
+            },
+            Opcode::DeleteGlobal => {
+                if frame.globals.borrow_mut().remove(&code.names[arg]).is_none() {
+                    return Err( error(self, "NameError", format!("name '{}' is not defined", code.names[arg])).into() )
+                }
+            },
+            Opcode::LoadFast => match &frame.fast[arg] {
+                Some( v ) => {
+                    let v = v.clone();
+                    frame.stack.push(v)
+                },
+                None => return Err( self.unbound_local(&code.varnames[arg]).into() )
+            },
+            Opcode::StoreFast => frame.fast[arg] = Some( frame.pop() ),
+            Opcode::DeleteFast => {
+                if frame.fast[arg].take().is_none() {
+                    return Err( self.unbound_local(&code.varnames[arg]).into() )
+                }
+            },
+            Opcode::LoadDeref => {
+                let v = self.load_deref(frame, arg)?;
+                frame.stack.push(v)
+            },
+            Opcode::StoreDeref => {
+                let v = frame.pop();
+                *frame.cells[arg].borrow_mut() = Some( v )
+            },
+            Opcode::DeleteDeref => {
+                if frame.cells[arg].borrow_mut().take().is_none() {
+                    return Err( self.unbound_deref(frame, arg).into() )
+                }
+            },
+            Opcode::LoadClosure => frame.stack.push(Value::Cell(frame.cells[arg].clone())),
+            Opcode::LoadClassDeref => {
+                let name = code.deref_name(instruction.arg);
+                let v = match frame.locals.as_ref().and_then( |l| l.get(name) ) {
+                    Some( v ) => v.clone(),
+                    None => self.load_deref(frame, arg)?
+                };
+                frame.stack.push(v)
+            },
+            Opcode::LoadAttr => {
+                let object = frame.pop();
+                let v = get_attr(self, &object, &code.names[arg])?;
+                frame.stack.push(v)
+            },
+            Opcode::StoreAttr => {
+                let object = frame.pop();
+                let v = frame.pop();
+                set_attr(self, &object, &code.names[arg], v)?
+            },
+            Opcode::DeleteAttr => {
+                let object = frame.pop();
+                del_attr(self, &object, &code.names[arg])?
+            },
+            Opcode::BinarySubscr => {
+                let index = frame.pop();
+                let container = frame.pop();
+                let v = get_item(self, &container, &index)?;
+                frame.stack.push(v)
+            },
+            Opcode::StoreSubscr => {
+                let index = frame.pop();
+                let container = frame.pop();
+                let v = frame.pop();
+                set_item(self, &container, &index, v)?
+            },
+            Opcode::DeleteSubscr => {
+                let index = frame.pop();
+                let container = frame.pop();
+                del_item(self, &container, &index)?
+            },
+            Opcode::BinaryOp => {
+                let b = frame.pop();
+                let a = frame.pop();
+                let v = match binary_op_of(instruction.arg) {
+                    ( op, true ) => inplace_op(self, op, &a, &b)?,
+                    ( op, false ) => binary_op(self, op, &a, &b)?
+                };
+                frame.stack.push(v)
+            },
+            Opcode::UnaryNegative | Opcode::UnaryPositive | Opcode::UnaryInvert => {
+                let op = match instruction.opcode {
+                    Opcode::UnaryNegative => UnaryOperator::USub,
+                    Opcode::UnaryPositive => UnaryOperator::UAdd,
+                    _ => UnaryOperator::Invert
+                };
+                let operand = frame.pop();
+                let v = unary_op(self, op, &operand)?;
+                frame.stack.push(v)
+            },
+            Opcode::UnaryNot => {
+                let operand = frame.pop();
+                let v = truthy(self, &operand)?;
+                frame.stack.push(Value::Bool(!v))
+            },
+            Opcode::CompareOp => {
+                let b = frame.pop();
+                let a = frame.pop();
+                let v = compare(self, compare_op_of(instruction.arg), &a, &b)?;
+                frame.stack.push(Value::Bool(v))
+            },
+            Opcode::IsOp => {
+                let b = frame.pop();
+                let a = frame.pop();
+                frame.stack.push(Value::Bool(is_same(&a, &b) != ( arg == 1 )))
+            },
+            Opcode::ContainsOp => {
+                let container = frame.pop();
+                let item = frame.pop();
+                let v = contains(self, &container, &item)?;
+                frame.stack.push(Value::Bool(v != ( arg == 1 )))
+            },
+            Opcode::BuildTuple => {
+                let values = frame.pop_n(arg);
+                frame.stack.push(Value::tuple(values))
+            },
+            Opcode::BuildList => {
+                let values = frame.pop_n(arg);
+                frame.stack.push(Value::list(values))
+            },
+            Opcode::BuildSet => {
+                let values = frame.pop_n(arg);
+                let set = set_from(self, values)?;
+                frame.stack.push(Value::set(set))
+            },
+            Opcode::BuildMap => {
+                let values = frame.pop_n(2 * arg);
+                let mut dict = Dict::new();
+                for pair in values.chunks(2) {
+                    let key = hash_key(self, &pair[0])?;
+                    dict.insert(key, pair[0].clone(), pair[1].clone())
+                }
+                frame.stack.push(Value::dict(dict))
+            },
+            Opcode::BuildSlice => {
+                let step = if arg == 3 { frame.pop() } else { Value::None };
+                let upper = frame.pop();
+                let lower = frame.pop();
+                frame.stack.push(Value::Slice(Rc::new( [ lower, upper, step ] )))
+            },
+            Opcode::BuildString => {
+                let values = frame.pop_n(arg);
+                let mut text = String::new();
+                for v in &values {
+                    text.push_str(&to_str(self, v)?)
+                }
+                frame.stack.push(Value::str(&text))
+            },
+            Opcode::ListAppend | Opcode::ListExtend => {
+                let v = frame.pop();
+                if let Value::List(l) = frame.peek(arg) {
+                    if instruction.opcode == Opcode::ListAppend {
+                        l.borrow_mut().push(v)
+                    } else {
+                        let values = collect(self, &v)?;
+                        l.borrow_mut().extend(values)
+                    }
+                }
+            },
+            Opcode::SetAdd | Opcode::SetUpdate => {
+                let v = frame.pop();
+                let values = if instruction.opcode == Opcode::SetAdd { vec![ v ] } else { collect(self, &v)? };
+                if let Value::Set(s) = frame.peek(arg) {
+                    for v in values {
+                        let key = hash_key(self, &v)?;
+                        s.borrow_mut().insert(key, v, Value::None)
+                    }
+                }
+            },
+            Opcode::MapAdd => {
+                let v = frame.pop();
+                let key = frame.pop();
+                if let Value::Dict(d) = frame.peek(arg) {
+                    let hash = hash_key(self, &key)?;
+                    d.borrow_mut().insert(hash, key, v)
+                }
+            },
+            Opcode::DictUpdate | Opcode::DictMerge => {
+                let v = frame.pop();
+                if let Value::Dict(d) = frame.peek(arg) {
+                    dict_update(self, &d, &v)?
+                }
+            },
+            Opcode::ListToTuple => {
+                let v = frame.pop();
+                let values = collect(self, &v)?;
+                frame.stack.push(Value::tuple(values))
+            },
+            Opcode::FormatValue => {
+                let spec = if instruction.arg & FORMAT_SPEC != 0 { to_str(self, &frame.pop())? } else { String::new() };
+                let v = frame.pop();
+                let v = match instruction.arg & 3 {
+                    FORMAT_STR => Value::str(&to_str(self, &v)?),
+                    FORMAT_REPR | FORMAT_ASCII => Value::str(&repr(self, &v)?),
+                    _ => v
+                };
+                let text = format_value(self, &v, &spec)?;
+                frame.stack.push(Value::str(&text))
+            },
+            Opcode::UnpackSequence => {
+                let v = frame.pop();
+                let values = collect(self, &v)?;
+                if values.len() < arg {
+                    return throw(self, "ValueError", format!("not enough values to unpack (expected {}, got {})", arg, values.len())).map_err(Fault::from)
+                }
+                if values.len() > arg {
+                    return throw(self, "ValueError", format!("too many values to unpack (expected {})", arg)).map_err(Fault::from)
+                }
+                frame.stack.extend(values.into_iter().rev())
+            },
+            Opcode::UnpackEx => {
+                let ( before, after ) = ( arg & 0xff, arg >> 8 );
+                let v = frame.pop();
+                let mut values = collect(self, &v)?;
+                if values.len() < before + after {
+                    return throw(self, "ValueError", format!("not enough values to unpack (expected at least {}, got {})", before + after, values.len())).map_err(Fault::from)
+                }
+                let tail = values.split_off(values.len() - after);
+                let middle = values.split_off(before);
+                frame.stack.extend(tail.into_iter().rev());
+                frame.stack.push(Value::list(middle));
+                frame.stack.extend(values.into_iter().rev())
+            },
+            Opcode::Jump => frame.pc = arg,
+            Opcode::PopJumpIfFalse | Opcode::PopJumpIfTrue => {
+                let v = frame.pop();
+                if truthy(self, &v)? == ( instruction.opcode == Opcode::PopJumpIfTrue ) {
+                    frame.pc = arg
+                }
+            },
+            Opcode::GetIter => {
+                let v = frame.pop();
+                let it = iter(self, &v)?;
+                frame.stack.push(it)
+            },
+            Opcode::ForIter => {
+                let it = frame.top();
+                match next(self, &it)? {
+                    Some( v ) => frame.stack.push(v),
+                    None => {
+                        frame.pop();
+                        frame.pc = arg
+                    }
+                }
+            },
+            Opcode::ReturnValue => return Ok( Some( Exit::Return(frame.pop()) ) ),
+            Opcode::PrintExpr => {
+                let v = frame.pop();
+                if !v.is_none() {
+                    let text = repr(self, &v)?;
+                    self.write(&format!("{}\n", text));
+                    frame.globals.borrow_mut().insert("_".to_string(), v);
+                }
+            },
+            Opcode::YieldValue => return Ok( Some( Exit::Yield(frame.pop()) ) ),
+            Opcode::GetYieldFromIter => {
+                let v = frame.pop();
+                let it = if let Value::Generator(_) = v { v } else { iter(self, &v)? };
+                frame.stack.push(it)
+            },
+            Opcode::Send => {
+                let v = frame.pop();
+                let it = frame.top();
+                match self.send(&it, v)? {
+                    Step::Yield(v) => frame.stack.push(v),
+                    Step::Return(v) => {
+                        frame.pop();
+                        frame.stack.push(v);
+                        frame.pc = arg
+                    }
+                }
+            },
+            Opcode::RaiseVarargs => {
+                let exception = match arg {
+                    0 if self.exc_info.is_none() => return throw(self, "RuntimeError", "No active exception to reraise").map_err(Fault::from),
+                    0 => return Err( Fault::Reraise(self.exc_info.clone()) ),
+                    1 => {
+                        let v = frame.pop();
+                        self.exception_value(v)?
+                    },
+                    _ => {
+                        let cause = frame.pop();
+                        let v = frame.pop();
+                        let exception = self.exception_value(v)?;
+                        let cause = if cause.is_none() { cause } else { self.exception_value(cause)? };
+                        if let Value::Instance(i) = &exception {
+                            let mut dict = i.dict.borrow_mut();
+                            dict.insert("__cause__".to_string(), cause);
+                            dict.insert("__suppress_context__".to_string(), Value::Bool(true));
+                        }
+                        exception
+                    }
+                };
+                return Err( Fault::Raise(exception) )
+            },
+            Opcode::Reraise => return Err( Fault::Reraise(frame.pop()) ),
+            Opcode::PushExcInfo => {
+                let exception = frame.pop();
+                let previous = std::mem::replace(&mut self.exc_info, exception.clone());
+                frame.stack.push(previous);
+                frame.stack.push(exception);
+                frame.handling += 1
+            },
+            Opcode::PopExcept => {
+                self.exc_info = frame.pop();
+                frame.handling = frame.handling.saturating_sub(1)
+            },
+            Opcode::CheckExcMatch => {
+                let class = frame.pop();
+                let exception = frame.top();
+                let v = self.matches(&class, &exception)?;
+                frame.stack.push(Value::Bool(v))
+            },
+            Opcode::LoadAssertionError => frame.stack.push(Value::Class(self.builtins.exception("AssertionError"))),
+            Opcode::BeforeWith => {
+                let manager = frame.pop();
+                let ( enter, exit ) = self.context_methods(&manager)?;
+                let v = call(self, &enter, Vec::new(), Vec::new())?;
+                frame.stack.push(exit);
+                frame.stack.push(v)
+            },
+            Opcode::WithExceptStart => {
+                let exception = frame.top();
+                let exit = frame.peek(3);
+                let class = Value::Class(class_of(self, &exception));
+                let v = call(self, &exit, vec![ class, exception, Value::None ], Vec::new())?;
+                frame.stack.push(v)
+            },
+            Opcode::MakeFunction => {
+                let function = self.make_function(frame, instruction.arg)?;
+                frame.stack.push(function)
+            },
+            Opcode::LoadBuildClass => frame.stack.push(Value::builtin("__build_class__", build_class_stub)),
+            Opcode::Call => {
+                let args = frame.pop_n(arg);
+                let func = frame.pop();
+                let v = self.call_value(&func, args, Vec::new())?;
+                frame.stack.push(v)
+            },
+            Opcode::CallKw => {
+                let names = match frame.pop() {
+                    Value::Tuple(t) => t,
+                    _ => Rc::from(Vec::new())
+                };
+                let mut args = frame.pop_n(arg);
+                let values = args.split_off(args.len() - names.len());
+                let kwargs = names.iter().zip(values).map( |( n, v )| match n {
+                    Value::Str(s) => ( s.to_string(), v ),
+                    _ => ( String::new(), v )
+                } ).collect();
+                let func = frame.pop();
+                let v = self.call_value(&func, args, kwargs)?;
+                frame.stack.push(v)
+            },
+            Opcode::CallFunctionEx => {
+                let kwargs = if arg & 1 != 0 { self.keyword_arguments(frame.pop())? } else { Vec::new() };
+                let args = frame.pop();
+                let args = collect(self, &args)?;
+                let func = frame.pop();
+                let v = self.call_value(&func, args, kwargs)?;
+                frame.stack.push(v)
+            },
+            Opcode::ImportName => {
+                let _fromlist = frame.pop();
+                let level = frame.pop();
+                let level = to_index(self, &level)?;
+                let name = &code.names[arg];
+                match ( name.as_str(), level ) {
+                    ( "__future__", 0 ) => frame.stack.push(Value::None),
+                    ( _, 0 ) => return throw(self, "ModuleNotFoundError", format!("No module named '{}'", name.split('.').next().unwrap_or(name))).map_err(Fault::from),
+                    _ => return throw(self, "ImportError", "attempted relative import with no known parent package").map_err(Fault::from)
+                }
+            },
+            Opcode::ImportFrom => {
+                let module = frame.top();
+                let v = if module.is_none() { Value::None } else { get_attr(self, &module, &code.names[arg])? };
+                frame.stack.push(v)
+            },
+            Opcode::ImportStar => {
+                frame.pop();
+            }
+        }
+        Ok( None )
+    }
+
+    fn load_global(&self, frame: &Frame<'a>, name: &str) -> RunResult<'a> {
+        if let Some( v ) = frame.globals.borrow().get(name) {
+            return Ok( v.clone() )
+        }
+        match self.builtins.namespace.get(name) {
+            Some( v ) => Ok( v.clone() ),
+            None => throw(self, "NameError", format!("name '{}' is not defined", name))
+        }
+    }
+
+    fn load_deref(&self, frame: &Frame<'a>, index: usize) -> RunResult<'a> {
+        match frame.cells[index].borrow().clone() {
+            Some( v ) => Ok( v ),
+            None => Err( self.unbound_deref(frame, index) )
+        }
+    }
+
+    fn unbound_local(&self, name: &str) -> Value<'a> {
+        error(self, "UnboundLocalError", format!("cannot access local variable '{}' where it is not associated with a value", name))
+    }
+
+    fn unbound_deref(&self, frame: &Frame<'a>, index: usize) -> Value<'a> {
+        let name = frame.code.deref_name(index as u32);
+        if index < frame.code.cellvars.len() {
+            self.unbound_local(name)
+        } else {
+            error(self, "NameError", format!("cannot access free variable '{}' where it is not associated with a value in enclosing scope", name))
+        }
+    }
+
+    fn exception_value(&mut self, value: Value<'a>) -> RunResult<'a> {
+        match &value {
+            Value::Class(c) if c.kind == TypeKind::Exception => {
+                let instance = call(self, &value, Vec::new(), Vec::new())?;
+                self.exception_value(instance)
+            },
+            Value::Instance(i) if i.class.kind == TypeKind::Exception => Ok( value ),
+            _ => throw(self, "TypeError", "exceptions must derive from BaseException")
+        }
+    }
+
+    fn matches(&self, class: &Value<'a>, exception: &Value<'a>) -> Result<bool, Value<'a>> {
+        match class {
+            Value::Class(c) if c.kind == TypeKind::Exception => Ok( is_instance(self, exception, c) ),
+            Value::Tuple(t) => {
+                for c in t.iter() {
+                    if self.matches(c, exception)? {
+                        return Ok( true )
+                    }
+                }
+                Ok( false )
+            },
+            _ => throw(self, "TypeError", "catching classes that do not inherit from BaseException is not allowed")
+        }
+    }
+
+    /// Bound '__enter__' and '__exit__' methods of context manager.
+    fn context_methods(&mut self, manager: &Value<'a>) -> Result<( Value<'a>, Value<'a> ), Value<'a>> {
+        let methods = match manager {
+            Value::Instance(_) => get_attr(self, manager, "__enter__").and_then( |enter| Ok( ( enter, get_attr(self, manager, "__exit__")? ) ) ),
+            _ => Err( Value::None )
+        };
+        match methods {
+            Ok( m ) => Ok( m ),
+            Err( e ) if e.is_none() || is_exception(self, &e, "AttributeError") =>
+                throw(self, "TypeError", format!("'{}' object does not support the context manager protocol", type_name(self, manager))),
+            Err( e ) => Err( e )
+        }
+    }
+
+    /// Sends value to iterator a 'yield from' delegates to.
+    fn send(&mut self, it: &Value<'a>, value: Value<'a>) -> Result<Step<'a>, Value<'a>> {
+        match it {
+            Value::Generator(g) => self.resume(g, Resume::Send(value)),
+            _ if value.is_none() => next(self, it).map( |v| match v {
+                Some( v ) => Step::Yield(v),
+                None => Step::Return(Value::None)
+            } ),
+            _ => match get_attr(self, it, "send").and_then( |send| call(self, &send, vec![ value ], Vec::new()) ) {
+                Ok( v ) => Ok( Step::Yield(v) ),
+                Err( e ) if is_exception(self, &e, "StopIteration") => get_attr(self, &e, "value").map(Step::Return),
+                Err( e ) => Err( e )
+            }
+        }
+    }
+
+    fn keyword_arguments(&mut self, mapping: Value<'a>) -> Result<Vec<( String, Value<'a> )>, Value<'a>> {
+        let items = match &mapping {
+            Value::Dict(d) => d.borrow().items(),
+            _ => return throw(self, "TypeError", format!("argument after ** must be a mapping, not {}", type_name(self, &mapping)))
+        };
+        let mut res = Vec::new();
+        for ( k, v ) in items {
+            match k {
+                Value::Str(s) => res.push(( s.to_string(), v )),
+                _ => return throw(self, "TypeError", "keywords must be strings")
+            }
+        }
+        Ok( res )
+    }
+
+    /// Function of code object on top of the stack, with the defaults and closure below it as
+    /// selected by flags.
+    fn make_function(&mut self, frame: &mut Frame<'a>, flags: u32) -> RunResult<'a> {
+        let code = match frame.pop() {
+            Value::Code(c) => c,
+            v => return throw(self, "SystemError", format!("expected code object, got {}", type_name(self, &v)))
+        };
+        let mut closure = HashMap::new();
+        if flags & MAKE_CLOSURE != 0 {
+            if let Value::Tuple(cells) = frame.pop() {
+                for ( name, cell ) in code.freevars.iter().zip(cells.iter()) {
+                    if let Value::Cell(c) = cell {
+                        closure.insert(name.clone(), c.clone());
+                    }
+                }
+            }
+        }
+        let mut kw_defaults = HashMap::new();
+        if flags & MAKE_KWDEFAULTS != 0 {
+            if let Value::Dict(d) = frame.pop() {
+                for ( k, v ) in d.borrow().items() {
+                    kw_defaults.insert(to_str(self, &k)?, v);
+                }
+            }
+        }
+        let defaults = if flags & MAKE_DEFAULTS != 0 { collect(self, &frame.pop())? } else { Vec::new() };
+        Ok( Value::Function( Rc::new( Function {
+            name: code.name.clone(),
+            qualname: code.qualname.clone(),
+            signature: signature_of(&code),
+            defaults,
+            kw_defaults,
+            is_generator: code.is_generator(),
+            code: Code::Bytecode(code),
+            closure,
+            globals: frame.globals.clone(),
+            dict: RefCell::new( HashMap::new() )
+        } ) ) )
+    }
+
+    fn call_value(&mut self, func: &Value<'a>, args: Vec<Value<'a>>, kwargs: Vec<( String, Value<'a> )>) -> RunResult<'a> {
+        match func {
+            Value::Builtin(b) if b.name == "__build_class__" => self.build_class(args, kwargs),
+            _ => call(self, func, args, kwargs)
+        }
+    }
+
+    /// Creates class by running its body function with a namespace of its own, as CPython
+    /// '__build_class__'. The body returns the cell of '__class__' when methods use it.
+    fn build_class(&mut self, args: Vec<Value<'a>>, kwargs: Vec<( String, Value<'a> )>) -> RunResult<'a> {
+        if !kwargs.is_empty() {
+            return throw(self, "NotImplementedError", "class keyword arguments are not supported")
+        }
+        let ( function, name, bases ) = match args.as_slice() {
+            [ Value::Function(f), Value::Str(n), bases @ .. ] => ( f.clone(), n.to_string(), bases.to_vec() ),
+            _ => return throw(self, "TypeError", "__build_class__: func must be a function")
+        };
+        let base = match bases.as_slice() {
+            [] => self.builtins.class(TypeKind::Object),
+            [ Value::Class(c) ] => c.clone(),
+            [ _ ] => return throw(self, "TypeError", "bases must be types"),
+            _ => return throw(self, "NotImplementedError", "multiple inheritance is not supported")
+        };
+        let code = match &function.code {
+            Code::Bytecode(c) => c.clone(),
+            _ => return throw(self, "TypeError", "__build_class__: func must be compiled to bytecode")
+        };
+        let mut frame = Frame::new(code, function.globals.clone(), Some( HashMap::new() ), &function.closure);
+        let cell = self.invoke(&mut frame)?;
+        let mut namespace = frame.locals.take().unwrap_or_default();
+        namespace.remove("__classcell__");
+        let class = new_class(self, &name, base, namespace)?;
+        if let Value::Cell(c) = cell {
+            *c.borrow_mut() = Some( class.clone() )
+        }
+        Ok( class )
+    }
+}
+
+impl<'a> Runtime<'a> for VirtualMachine<'a> {
+    fn builtins(&self) -> Rc<Builtins<'a>> {
+        self.builtins.clone()
+    }
+
+    /// Binds arguments to the fast locals of a new frame, parameters shared with nested scopes
+    /// move into their cells. Calling a generator function returns the suspended frame.
+    fn call_function(&mut self, function: &Rc<Function<'a>>, args: Vec<Value<'a>>, kwargs: Vec<( String, Value<'a> )>) -> RunResult<'a> {
+        let code = match &function.code {
+            Code::Bytecode(c) => c.clone(),
+            _ => return throw(self, "TypeError", "function was not compiled to bytecode")
+        };
+        let values = bind_arguments(self, function, args, kwargs)?;
+        let mut frame = Frame::new(code.clone(), function.globals.clone(), None, &function.closure);
+        let ( npos, nkw ) = ( code.argcount as usize, code.kwonlyargcount as usize );
+        let mut slots : Vec<usize> = ( 0 .. npos ).collect();
+        if code.flags & CO_VARARGS != 0 {
+            slots.push(npos + nkw)
+        }
+        slots.extend(npos .. npos + nkw);
+        if code.flags & CO_VARKEYWORDS != 0 {
+            slots.push(npos + nkw + ( code.flags & CO_VARARGS != 0 ) as usize)
+        }
+        for ( slot, value ) in slots.iter().zip(values) {
+            frame.fast[*slot] = Some( value )
+        }
+        for ( i, name ) in code.cellvars.iter().enumerate() {
+            if let Some( slot ) = slots.iter().find( |s| code.varnames[**s] == *name ) {
+                *frame.cells[i].borrow_mut() = frame.fast[*slot].take()
+            }
+        }
+        if code.is_generator() {
+            return Ok( Value::Generator( Rc::new( RefCell::new( Generator {
+                name: function.qualname.clone(),
+                frame: Some( Suspension::Bytecode(Box::new(frame)) ),
+                running: false
+            } ) ) ) )
+        }
+        self.invoke(&mut frame)
+    }
+
+    fn resume(&mut self, generator: &Rc<RefCell<Generator<'a>>>, input: Resume<'a>) -> Result<Step<'a>, Value<'a>> {
+        let mut frame = {
+            let mut g = generator.borrow_mut();
+            if g.running {
+                drop(g);
+                return throw(self, "ValueError", "generator already executing")
+            }
+            match g.frame.take() {
+                Some( Suspension::Bytecode(f) ) => f,
+                Some( other ) => {
+                    g.frame = Some( other );
+                    drop(g);
+                    return throw(self, "TypeError", "generator was created by another engine")
+                },
+                None => return match input {
+                    Resume::Send(_) => Ok( Step::Return(Value::None) ),
+                    Resume::Throw(e) => Err( e )
+                }
+            }
+        };
+        let input = match ( frame.pc > 0, input ) {
+            ( true, input ) => Some( input ),
+            ( false, Resume::Send(v) ) if !v.is_none() => {
+                generator.borrow_mut().frame = Some( Suspension::Bytecode(frame) );
+                return throw(self, "TypeError", "can't send non-None value to a just-started generator")
+            },
+            ( false, Resume::Throw(e) ) => return Err( e ),
+            ( false, Resume::Send(_) ) => None
+        };
+        if self.depth >= RECURSION_LIMIT {
+            generator.borrow_mut().frame = Some( Suspension::Bytecode(frame) );
+            return throw(self, "RecursionError", "maximum recursion depth exceeded")
+        }
+        generator.borrow_mut().running = true;
+        self.depth += 1;
+        let outer = self.exc_info.clone();
+        if frame.handling > 0 {
+            self.exc_info = frame.handled.clone()
+        }
+        let res = self.run(&mut frame, input);
+        self.depth -= 1;
+        let res = match res {
+            Ok( Exit::Yield(v) ) => {
+                frame.handled = self.exc_info.clone();
+                generator.borrow_mut().frame = Some( Suspension::Bytecode(frame) );
+                Ok( Step::Yield(v) )
+            },
+            Ok( Exit::Return(v) ) => Ok( Step::Return(v) ),
+            Err( e ) if is_exception(self, &e, "StopIteration") => {
+                let replaced = error(self, "RuntimeError", "generator raised StopIteration");
+                self.record(&frame, frame.pc.saturating_sub(1), &replaced);
+                if let Value::Instance(i) = &replaced {
+                    i.dict.borrow_mut().insert("__cause__".to_string(), e);
+                }
+                Err( replaced )
+            },
+            Err( e ) => Err( e )
+        };
+        self.exc_info = outer;
+        generator.borrow_mut().running = false;
+        res
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text)
+    }
+}
+
+
+// UnitTests for bytecode virtual machine //////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use crate::bytecode::vm::run_bytecode;
+    use crate::runtime::interpreter::run_source;
+
+
+    fn output(source: &str) -> String {
+        let ( out, res ) = run_bytecode(source, "test.py", 0);
+        assert_eq!(res, Ok(()));
+        out
+    }
+
+    fn traceback(source: &str) -> String {
+        run_bytecode(source, "test.py", 0).1.unwrap_err()
+    }
+
+    #[test]
+    fn vm_values() {
+        assert_eq!(output("print(2 ** 100, -7 // 2, [1, (2, 'a')], {'k': None})\n"), "1267650600228229401496703205376 -4 [1, (2, 'a')] {'k': None}\n");
+        assert_eq!(output("a, *b, c = 'wxyz'\nprint(a, b, c, f'{c!r:>4}|')\n"), "w ['x', 'y'] z  'z'|\n");
+    }
+
+    #[test]
+    fn vm_closures_and_classes() {
+        assert_eq!(output("fs = [lambda: i for i in range(3)]\nprint([f() for f in fs])\n"), "[2, 2, 2]\n");
+        let source = "class A:\n    def f(self):\n        return 1\nclass B(A):\n    def f(self):\n        return super().f() + 1\nprint(B().f())\n";
+        assert_eq!(output(source), "2\n");
+    }
+
+    #[test]
+    fn vm_generators() {
+        let source = "def g():\n    x = yield 1\n    yield x * 2\nit = g()\nprint(next(it), it.send(4), list(it))\n";
+        assert_eq!(output(source), "1 8 []\n");
+        let source = "def g():\n    r = yield from range(2)\n    return r\nprint(list(g()))\n";
+        assert_eq!(output(source), "[0, 1]\n");
+        assert!(traceback("def g():\n    yield 1\ng().send(2)\n").ends_with("TypeError: can't send non-None value to a just-started generator\n"));
+    }
+
+    #[test]
+    fn vm_tracebacks() {
+        assert_eq!(traceback("def f():\n    return 1 / 0\nf()\n"), concat!(
+            "Traceback (most recent call last):\n",
+            "  File \"test.py\", line 3, in <module>\n",
+            "    f()\n",
+            "  File \"test.py\", line 2, in f\n",
+            "    return 1 / 0\n",
+            "ZeroDivisionError: division by zero\n"));
+        let source = "try:\n    x\nexcept NameError:\n    raise KeyError(1)\n";
+        assert!(traceback(source).contains("During handling of the above exception, another exception occurred:"));
+    }
+
+    /// Every program in 'tests/programs' prints the text of the '.out' file next to it, followed by
+    /// the traceback of an uncaught exception, on both the virtual machine and the interpreter.
+    #[test]
+    fn vm_programs() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("programs");
+        let mut paths : Vec<_> = std::fs::read_dir(&dir).unwrap().map( |e| e.unwrap().path() )
+            .filter( |p| p.extension().map( |e| e == "py" ).unwrap_or(false) ).collect();
+        paths.sort();
+        assert!(!paths.is_empty());
+        for path in paths {
+            let source = std::fs::read_to_string(&path).unwrap();
+            let expected = std::fs::read_to_string(path.with_extension("out")).unwrap();
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            for ( engine, ( out, res ) ) in [ ( "vm", run_bytecode(&source, &name, 0) ), ( "interpreter", run_source(&source, &name) ) ] {
+                let actual = out + &res.err().unwrap_or_default();
+                assert_eq!(actual, expected, "{} on {}", name, engine)
+            }
+        }
+    }
+}
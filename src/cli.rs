@@ -0,0 +1,875 @@
+use std::io::{ Read, Write, BufReader };
+use std::path::{ Path, PathBuf };
+use crate::{ Token, Tokenizer };
+use crate::parser::tokenizer::PythonCoreTokenizer;
+use crate::parser::sexpr::SExpression;
+use crate::api::{ Mode, parse, check, analyze, normalize_source, restore_source };
+use crate::diagnostics::Diagnostic;
+use crate::ast::location::LineIndex;
+use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+use crate::ast::dump::Dumper;
+use crate::semantic::cfg::{ ControlFlowGraph, build_graphs };
+use crate::runtime::interpreter::run_source;
+use crate::bytecode::compiler::compile_source;
+use crate::bytecode::dis::disassemble;
+use crate::bytecode::vm::run_bytecode;
+use crate::bytecode::optimizer::PythonCoreOptimizer;
+use crate::repl::{ Repl, ReplOptions, HISTORY_FILE };
+use crate::format::printer::{ FormatOptions, format_source };
+use crate::lint::linter::{ LintOptions, lint_source, fix_source };
+use crate::edits::unified_diff;
+use crate::lsp::server::PythonCoreLanguageServer;
+use crate::highlight::{ highlight_ansi, highlight_html };
+
+
+const USAGE: &str = "Usage: python_core_rust <command> [options] [paths...]
+
+Commands:
+  tokens <file>       Print tokens of file
+  parse <file>        Print syntax tree of file
+  check <paths...>    Report syntax errors, directories are searched for '.py' files
+  cfg <file>          Print control flow graphs of module and functions in DOT language
+  run <file>          Run file with the tree walking interpreter
+  dis <file>          Print bytecode of file like CPython 'dis.dis'
+  optimize <file>     Report constants folded and statements removed by the optimizer
+  repl                Read, evaluate and print statements typed at '>>> ' and '... ' prompts
+  format <file>       Print file formatted in the style of Black
+  lint <paths...>     Report unused imports, undefined names and other problems with their codes
+  lsp                 Serve the language server protocol on standard input and output
+  highlight <file>    Print file with colors for the terminal, or as HTML page with '--html'
+
+Options:
+  --mode exec|eval|single|func_type    Start rule of grammar, default is exec
+  --trivia                             Include trivia in tree
+  --ast                                Print tree in the format of CPython 'ast.dump'
+  --attributes                         Include positions with '--ast'
+  --function <name>                    Only graph of function with qualified name, with 'cfg'
+  --warnings                           Also report unreachable code and unused or unbound locals, with 'check'
+  --vm                                 Run compiled bytecode on the virtual machine, with 'run'
+  --optimize 0|1|2                     Optimization level like 'python -O', for bytecode and with 'parse --ast'
+  --history <file>                     File that 'repl' appends statements to, default is '.python_core_history'
+  --line-length <n>                    Width that 'format' splits lines at, default is 88
+  --check                              Report files that 'format' would change instead of printing them
+  --select <codes>                     Codes or prefixes of codes of the rules 'lint' runs, separated by commas
+  --ignore <codes>                     Codes or prefixes of codes of rules 'lint' skips, separated by commas
+  --fix                                Apply the fixes of 'lint' diagnostics to files until none is left
+  --diff                               Print the changes '--fix' would make instead of writing them
+  --html                               Print a standalone HTML page with CSS classes, with 'highlight'
+
+A path of '-' or no path at all reads source from standard input.
+";
+
+struct Options {
+    mode: Mode,
+    trivia: bool,
+    ast: bool,
+    attributes: bool,
+    function: Option<String>,
+    warnings: bool,
+    vm: bool,
+    optimize: Option<u32>,
+    history: Option<String>,
+    line_length: Option<usize>,
+    check: bool,
+    select: Vec<String>,
+    ignore: Vec<String>,
+    fix: bool,
+    diff: bool,
+    html: bool,
+    paths: Vec<String>
+}
+
+
+/// Runs command line given without program name and returns exit code, 0 for success, 1 when
+/// syntax errors are found and 2 for wrong usage or files that cannot be read.
+pub fn run(args: &[String], stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let ( command, rest ) = match args.split_first() {
+        Some( x ) => x,
+        None => {
+            let _ = write!(err, "{}", USAGE);
+            return 2
+        }
+    };
+    let options = match parse_options(rest) {
+        Ok( o ) => o,
+        Err( e ) => {
+            let _ = write!(err, "error: {}\n\n{}", e, USAGE);
+            return 2
+        }
+    };
+    match command.as_str() {
+        "tokens" => command_tokens(&options, stdin, out, err),
+        "parse" => command_parse(&options, stdin, out, err),
+        "check" => command_check(&options, stdin, err),
+        "cfg" => command_cfg(&options, stdin, out, err),
+        "run" => command_run(&options, stdin, out, err),
+        "dis" => command_dis(&options, stdin, out, err),
+        "optimize" => command_optimize(&options, stdin, out, err),
+        "format" => command_format(&options, stdin, out, err),
+        "lint" => command_lint(&options, stdin, out, err),
+        "repl" => command_repl(&options, stdin, out, err),
+        "lsp" => command_lsp(&options, stdin, out, err),
+        "highlight" => command_highlight(&options, stdin, out, err),
+        "help" | "-h" | "--help" => {
+            let _ = write!(out, "{}", USAGE);
+            0
+        },
+        _ => {
+            let _ = write!(err, "error: unknown command '{}'\n\n{}", command, USAGE);
+            2
+        }
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options { mode: Mode::Exec, trivia: false, ast: false, attributes: false, function: None, warnings: false, vm: false, optimize: None, history: None, line_length: None, check: false, select: Vec::new(), ignore: Vec::new(), fix: false, diff: false, html: false, paths: Vec::new() };
+    let mut iter = args.iter();
+    while let Some( arg ) = iter.next() {
+        match arg.as_str() {
+            "--mode" => {
+                options.mode = match iter.next().map( |m| m.as_str() ) {
+                    Some( "exec" ) => Mode::Exec,
+                    Some( "eval" ) => Mode::Eval,
+                    Some( "single" ) => Mode::Single,
+                    Some( "func_type" ) => Mode::FuncType,
+                    Some( m ) => return Err(format!("unknown mode '{}'", m)),
+                    None => return Err("missing mode after '--mode'".to_string())
+                }
+            },
+            "--trivia" => options.trivia = true,
+            "--ast" => options.ast = true,
+            "--attributes" => options.attributes = true,
+            "--warnings" => options.warnings = true,
+            "--vm" => options.vm = true,
+            "--optimize" => {
+                options.optimize = match iter.next().map( |l| l.as_str() ) {
+                    Some( l @ ( "0" | "1" | "2" ) ) => l.parse().ok(),
+                    Some( l ) => return Err(format!("unknown optimization level '{}'", l)),
+                    None => return Err("missing level after '--optimize'".to_string())
+                }
+            },
+            "--history" => {
+                match iter.next() {
+                    Some( h ) => options.history = Some( h.clone() ),
+                    None => return Err("missing file after '--history'".to_string())
+                }
+            },
+            "--line-length" => {
+                options.line_length = match iter.next() {
+                    Some( n ) => match n.parse() {
+                        Ok( n ) if n > 0 => Some( n ),
+                        _ => return Err(format!("invalid line length '{}'", n))
+                    },
+                    None => return Err("missing width after '--line-length'".to_string())
+                }
+            },
+            "--check" => options.check = true,
+            "--fix" => options.fix = true,
+            "--diff" => options.diff = true,
+            "--html" => options.html = true,
+            "--select" | "--ignore" => {
+                let codes = match iter.next() {
+                    Some( c ) => c.split(',').map( |c| c.trim().to_ascii_uppercase() ).filter( |c| !c.is_empty() ).collect(),
+                    None => return Err(format!("missing codes after '{}'", arg))
+                };
+                match arg.as_str() {
+                    "--select" => options.select = codes,
+                    _ => options.ignore = codes
+                }
+            },
+            "--function" => {
+                match iter.next() {
+                    Some( f ) => options.function = Some( f.clone() ),
+                    None => return Err("missing name after '--function'".to_string())
+                }
+            },
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'", arg)),
+            _ => options.paths.push( arg.clone() )
+        }
+    }
+    Ok(options)
+}
+
+/// Name and text of file or standard input with line endings normalized, a missing newline at end
+/// of source added and the UTF-8 byte order mark that CPython skips at start of source left out.
+fn read_source(path: &str, stdin: &mut dyn Read) -> Result<( String, String ), String> {
+    let ( name, text ) = read_text(path, stdin)?;
+    Ok( ( name, normalize_source(text.strip_prefix('\u{feff}').unwrap_or(&text)) ) )
+}
+
+/// Name and text of file or standard input as they are.
+fn read_text(path: &str, stdin: &mut dyn Read) -> Result<( String, String ), String> {
+    let mut text = String::new();
+    let name = match path {
+        "-" => {
+            stdin.read_to_string(&mut text).map_err( |e| format!("cannot read standard input: {}", e) )?;
+            "<stdin>".to_string()
+        },
+        _ => {
+            text = std::fs::read_to_string(path).map_err( |e| format!("cannot read '{}': {}", path, e) )?;
+            path.to_string()
+        }
+    };
+    Ok( ( name, text ) )
+}
+
+fn single_path(options: &Options) -> Result<&str, String> {
+    match options.paths.len() {
+        0 => Ok( "-" ),
+        1 => Ok( options.paths[0].as_str() ),
+        _ => Err("command takes a single file".to_string())
+    }
+}
+
+/// Parses, lowers and validates source, which reports the same syntax errors as CPython compile does.
+pub fn check_source(source: &str, mode: Mode) -> Result<(), Diagnostic> {
+    match check(source, mode).into_iter().next() {
+        Some( d ) => Err( d ),
+        None => Ok(())
+    }
+}
+
+/// Formats syntax error the way CPython reports it, with source line and a caret below the error.
+pub fn format_syntax_error(name: &str, source: &str, error: &Diagnostic) -> String {
+    let ( line, col ) = LineIndex::new(source).line_char_col(error.start);
+    let text = source.lines().nth(line as usize - 1).unwrap_or("");
+    let stripped = text.trim_start();
+    let indent = ( text.chars().count() - stripped.chars().count() ) as u32;
+    format!("  File \"{}\", line {}\n    {}\n    {}^\nSyntaxError: {}\n",
+            name, line, stripped.trim_end(), " ".repeat(col.saturating_sub(indent) as usize), error.message)
+}
+
+fn command_tokens(options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let ( name, source ) = match single_path(options).and_then( |p| read_source(p, stdin) ) {
+        Ok( x ) => x,
+        Err( e ) => {
+            let _ = writeln!(err, "error: {}", e);
+            return 2
+        }
+    };
+    let index = LineIndex::new(&source);
+    let mut lexer = PythonCoreTokenizer::new(source.clone());
+    loop {
+        match lexer.get_symbol() {
+            Ok( tok ) => {
+                let position = lexer.get_position();
+                let ( start, end ) = tok.get_span().unwrap_or( ( position, position ) );
+                let ( start_line, start_col ) = index.line_char_col(start);
+                let ( end_line, end_col ) = index.line_char_col(end);
+                let range = format!("{},{}-{},{}:", start_line, start_col, end_line, end_col);
+                let text = tok.text().map( |t| format!("{:?}", t) ).unwrap_or_default();
+                let line = format!("{:<20}{:<20}{}", range, tok.kind_name(), text);
+                let _ = writeln!(out, "{}", line.trim_end());
+                if let Token::EOF(..) = &*tok {
+                    return 0
+                }
+            },
+            Err( e ) => {
+                let error = Diagnostic::from_error_text(&e, lexer.get_position());
+                let _ = write!(err, "{}", format_syntax_error(&name, &source, &error));
+                return 1
+            }
+        }
+    }
+}
+
+fn command_parse(options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let ( name, source ) = match single_path(options).and_then( |p| read_source(p, stdin) ) {
+        Ok( x ) => x,
+        Err( e ) => {
+            let _ = writeln!(err, "error: {}", e);
+            return 2
+        }
+    };
+    let res = parse(&source, options.mode);
+    let tree = match res.tree {
+        Some( t ) => t,
+        None => {
+            let _ = write!(err, "{}", format_syntax_error(&name, &source, &res.diagnostics[0]));
+            return 1
+        }
+    };
+    if !options.ast {
+        let _ = writeln!(out, "{}", tree.to_sexpr(options.trivia));
+        return 0
+    }
+    match PythonCoreLowering::new(&source).lower_mod(&tree) {
+        Ok( mut m ) => {
+            if let Some( level ) = options.optimize {
+                PythonCoreOptimizer::new(&source, level).optimize_mod(&mut m);
+            }
+            let _ = writeln!(out, "{}", m.dump(options.attributes));
+            0
+        },
+        Err( e ) => {
+            let _ = write!(err, "{}", format_syntax_error(&name, &source, &Diagnostic::from_error_text(&e, 0)));
+            1
+        }
+    }
+}
+
+/// Collects '.py' files below directory in sorted order, hidden directories are skipped.
+fn collect_python_files(dir: &Path, res: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir).map_err( |e| format!("cannot read '{}': {}", dir.display(), e) )?;
+    let mut paths : Vec<PathBuf> = entries.filter_map( |e| e.ok().map( |e| e.path() ) ).collect();
+    paths.sort();
+    for path in paths {
+        let hidden = path.file_name().map( |n| n.to_string_lossy().starts_with('.') ).unwrap_or(false);
+        if path.is_dir() && !hidden {
+            collect_python_files(&path, res)?
+        }
+        else if path.is_file() && path.extension().map( |e| e == "py" ).unwrap_or(false) {
+            res.push(path)
+        }
+    }
+    Ok(())
+}
+
+/// Paths of options with directories replaced by the '.py' files below them, and exit code 2 when a
+/// directory cannot be read. No paths at all means standard input.
+fn expand_paths(options: &Options, err: &mut dyn Write) -> ( Vec<String>, i32 ) {
+    let mut files : Vec<String> = Vec::new();
+    let mut code = 0;
+    let paths = match options.paths.is_empty() {
+        true => vec![ "-".to_string() ],
+        _ => options.paths.clone()
+    };
+    for path in paths {
+        let p = Path::new(&path);
+        if path != "-" && p.is_dir() {
+            let mut found = Vec::new();
+            match collect_python_files(p, &mut found) {
+                Ok( _ ) => files.extend( found.iter().map( |f| f.display().to_string() ) ),
+                Err( e ) => {
+                    let _ = writeln!(err, "error: {}", e);
+                    code = 2
+                }
+            }
+        }
+        else {
+            files.push(path)
+        }
+    }
+    ( files, code )
+}
+
+fn command_check(options: &Options, stdin: &mut dyn Read, err: &mut dyn Write) -> i32 {
+    let ( files, mut code ) = expand_paths(options, err);
+    for file in files {
+        match read_source(&file, stdin) {
+            Ok( ( name, source ) ) => {
+                if let Err( e ) = check_source(&source, options.mode) {
+                    let _ = write!(err, "{}", format_syntax_error(&name, &source, &e));
+                    code = code.max(1)
+                }
+                else if options.warnings {
+                    let index = LineIndex::new(&source);
+                    for w in analyze(&source, options.mode) {
+                        let ( line, col ) = index.line_char_col(w.start);
+                        let _ = writeln!(err, "{}:{}:{}: warning: {}", name, line, col + 1, w.message);
+                    }
+                }
+            },
+            Err( e ) => {
+                let _ = writeln!(err, "error: {}", e);
+                code = 2
+            }
+        }
+    }
+    code
+}
+
+/// Prints the formatted source of one file, or with '--check' reports every file that formatting
+/// would change and exits with 1 when there is one.
+fn command_format(options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let settings = FormatOptions { line_length: options.line_length.unwrap_or(FormatOptions::default().line_length) };
+    if !options.check {
+        let ( name, source ) = match single_path(options).and_then( |p| read_source(p, stdin) ) {
+            Ok( x ) => x,
+            Err( e ) => {
+                let _ = writeln!(err, "error: {}", e);
+                return 2
+            }
+        };
+        return match format_source(&source, &settings) {
+            Ok( text ) => {
+                let _ = write!(out, "{}", text);
+                0
+            },
+            Err( e ) => {
+                let _ = write!(err, "{}", format_syntax_error(&name, &source, &e));
+                1
+            }
+        }
+    }
+    let ( files, mut code ) = expand_paths(options, err);
+    for file in files {
+        match read_source(&file, stdin) {
+            Ok( ( name, source ) ) => match format_source(&source, &settings) {
+                Ok( text ) if text != source => {
+                    let _ = writeln!(err, "would reformat {}", name);
+                    code = code.max(1)
+                },
+                Ok( _ ) => { },
+                Err( e ) => {
+                    let _ = write!(err, "{}", format_syntax_error(&name, &source, &e));
+                    code = code.max(1)
+                }
+            },
+            Err( e ) => {
+                let _ = writeln!(err, "error: {}", e);
+                code = 2
+            }
+        }
+    }
+    code
+}
+
+/// Prints the diagnostics of the lint rules as 'file:line:column: CODE message', exits with 1 when
+/// any are found. With '--fix' files are rewritten with the fixes applied, keeping their line
+/// endings and byte order mark, and the diagnostics left are printed, source from standard input is fixed to standard output and its diagnostics go to
+/// standard error. With '--diff' the changes of the fixes are printed instead, exit code is 1 when
+/// there are any.
+fn command_lint(options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let settings = LintOptions { select: options.select.clone(), ignore: options.ignore.clone() };
+    let ( files, mut code ) = expand_paths(options, err);
+    for file in files {
+        let ( name, original ) = match read_text(&file, stdin) {
+            Ok( x ) => x,
+            Err( e ) => {
+                let _ = writeln!(err, "error: {}", e);
+                code = 2;
+                continue
+            }
+        };
+        let source = normalize_source(original.strip_prefix('\u{feff}').unwrap_or(&original));
+        let found = match options.fix || options.diff {
+            true => fix_source(&source, &settings).map( |r| ( r.source, r.remaining ) ),
+            _ => lint_source(&source, &settings).map( |found| ( source.clone(), found ) )
+        };
+        let ( text, found ) = match found {
+            Ok( x ) => x,
+            Err( e ) => {
+                let _ = write!(err, "{}", format_syntax_error(&name, &source, &e));
+                code = code.max(1);
+                continue
+            }
+        };
+        if options.diff {
+            let diff = unified_diff(&source, &text, &name);
+            let _ = write!(out, "{}", diff);
+            if !diff.is_empty() {
+                code = code.max(1)
+            }
+            continue
+        }
+        let report: &mut dyn Write = match options.fix && file == "-" {
+            true => {
+                let _ = write!(out, "{}", restore_source(&original, &source, &text));
+                &mut *err
+            },
+            _ => &mut *out
+        };
+        if options.fix && file != "-" && text != source {
+            if let Err( e ) = std::fs::write(&file, restore_source(&original, &source, &text)) {
+                let _ = writeln!(err, "error: cannot write '{}': {}", file, e);
+                code = 2;
+                continue
+            }
+        }
+        let index = LineIndex::new(&text);
+        for d in &found {
+            let ( line, col ) = index.line_char_col(d.start);
+            let _ = writeln!(report, "{}:{}:{}: {} {}", name, line, col + 1, d.code.as_deref().unwrap_or(""), d.message);
+        }
+        if !found.is_empty() {
+            code = code.max(1)
+        }
+    }
+    code
+}
+
+fn command_cfg(options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let ( name, source ) = match single_path(options).and_then( |p| read_source(p, stdin) ) {
+        Ok( x ) => x,
+        Err( e ) => {
+            let _ = writeln!(err, "error: {}", e);
+            return 2
+        }
+    };
+    if let Err( e ) = check_source(&source, Mode::Exec) {
+        let _ = write!(err, "{}", format_syntax_error(&name, &source, &e));
+        return 1
+    }
+    let module = match parse(&source, Mode::Exec).tree.map( |t| PythonCoreLowering::new(&source).lower_mod(&t) ) {
+        Some( Ok( m ) ) => m,
+        _ => return 1
+    };
+    let graphs : Vec<ControlFlowGraph> = build_graphs(&module).into_iter()
+        .filter( |g| options.function.as_ref().map( |f| *f == g.name ).unwrap_or(true) ).collect();
+    if graphs.is_empty() {
+        let _ = writeln!(err, "error: no function '{}'", options.function.as_deref().unwrap_or(""));
+        return 2
+    }
+    for g in graphs {
+        let _ = write!(out, "{}", g.to_dot(&source));
+    }
+    0
+}
+
+fn command_run(options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let ( name, source ) = match single_path(options).and_then( |p| read_source(p, stdin) ) {
+        Ok( x ) => x,
+        Err( e ) => {
+            let _ = writeln!(err, "error: {}", e);
+            return 2
+        }
+    };
+    if let Err( e ) = check_source(&source, Mode::Exec) {
+        let _ = write!(err, "{}", format_syntax_error(&name, &source, &e));
+        return 1
+    }
+    let ( output, res ) = if options.vm { run_bytecode(&source, &name, options.optimize.unwrap_or(0)) } else { run_source(&source, &name) };
+    let _ = write!(out, "{}", output);
+    match res {
+        Ok(()) => 0,
+        Err( traceback ) => {
+            let _ = write!(err, "{}", traceback);
+            1
+        }
+    }
+}
+
+fn command_dis(options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let ( name, source ) = match single_path(options).and_then( |p| read_source(p, stdin) ) {
+        Ok( x ) => x,
+        Err( e ) => {
+            let _ = writeln!(err, "error: {}", e);
+            return 2
+        }
+    };
+    if let Err( e ) = check_source(&source, Mode::Exec) {
+        let _ = write!(err, "{}", format_syntax_error(&name, &source, &e));
+        return 1
+    }
+    match compile_source(&source, &name, options.optimize.unwrap_or(0)) {
+        Ok( code ) => {
+            let _ = write!(out, "{}", disassemble(&code));
+            0
+        },
+        Err( e ) => {
+            let _ = writeln!(err, "{}", e);
+            1
+        }
+    }
+}
+
+fn command_optimize(options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let ( name, source ) = match single_path(options).and_then( |p| read_source(p, stdin) ) {
+        Ok( x ) => x,
+        Err( e ) => {
+            let _ = writeln!(err, "error: {}", e);
+            return 2
+        }
+    };
+    if let Err( e ) = check_source(&source, Mode::Exec) {
+        let _ = write!(err, "{}", format_syntax_error(&name, &source, &e));
+        return 1
+    }
+    let mut module = match parse(&source, Mode::Exec).tree.map( |t| PythonCoreLowering::new(&source).lower_mod(&t) ) {
+        Some( Ok( m ) ) => m,
+        _ => return 1
+    };
+    let index = LineIndex::new(&source);
+    for change in PythonCoreOptimizer::new(&source, options.optimize.unwrap_or(0)).optimize_mod(&mut module) {
+        let ( line, col ) = index.line_char_col(change.start);
+        let _ = writeln!(out, "{}:{}:{}: {}", name, line, col + 1, change.message);
+    }
+    0
+}
+
+/// Interactive session on standard input. With '--ast' statements are printed as abstract trees
+/// instead of being run on the virtual machine.
+fn command_repl(options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    if !options.paths.is_empty() {
+        let _ = writeln!(err, "error: command takes no paths");
+        return 2
+    }
+    let settings = ReplOptions {
+        ast: options.ast,
+        optimize: options.optimize.unwrap_or(0),
+        history: Some( PathBuf::from(options.history.as_deref().unwrap_or(HISTORY_FILE)) )
+    };
+    Repl::new(settings, out, err).run(&mut BufReader::new(stdin))
+}
+
+/// Runs the language server until the client sends 'exit' or closes standard input.
+fn command_lsp(options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    if !options.paths.is_empty() {
+        let _ = writeln!(err, "error: command takes no paths");
+        return 2
+    }
+    PythonCoreLanguageServer::new(out).run(&mut BufReader::new(stdin))
+}
+
+/// Prints source with highlighted tokens and comments, which also works for source with syntax errors.
+fn command_highlight(options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let ( name, source ) = match single_path(options).and_then( |p| read_source(p, stdin) ) {
+        Ok( x ) => x,
+        Err( e ) => {
+            let _ = writeln!(err, "error: {}", e);
+            return 2
+        }
+    };
+    let text = match options.html {
+        true => highlight_html(&source, &name),
+        _ => highlight_ansi(&source)
+    };
+    let _ = write!(out, "{}", text);
+    0
+}
+
+
+// UnitTests for command line driver ///////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::run;
+
+
+    fn run_with_stdin(args: &[&str], input: &str) -> ( i32, String, String ) {
+        let args : Vec<String> = args.iter().map( |a| a.to_string() ).collect();
+        let mut stdin = input.as_bytes();
+        let mut out : Vec<u8> = Vec::new();
+        let mut err : Vec<u8> = Vec::new();
+        let code = run(&args, &mut stdin, &mut out, &mut err);
+        ( code, String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap() )
+    }
+
+    #[test]
+    fn cli_tokens() {
+        let ( code, out, _ ) = run_with_stdin(&["tokens"], "x = 1\n");
+        assert_eq!(code, 0);
+        assert_eq!(out, concat!(
+            "1,0-1,1:            AtomName            \"x\"\n",
+            "1,2-1,3:            PyAssign            \"=\"\n",
+            "1,4-1,5:            AtomNumber          \"1\"\n",
+            "1,5-2,0:            Newline\n",
+            "2,0-2,0:            EOF\n"));
+    }
+
+    #[test]
+    fn cli_parse_tree() {
+        let ( code, out, _ ) = run_with_stdin(&["parse", "-"], "pass\n");
+        assert_eq!(code, 0);
+        assert!(out.starts_with("(FileInput 0..5\n  (SimpleStmtList 0..5\n    (PassStmt 0..4"));
+    }
+
+    #[test]
+    fn cli_parse_ast() {
+        let ( code, out, _ ) = run_with_stdin(&["parse", "--ast"], "x = 1\n");
+        assert_eq!(code, 0);
+        assert_eq!(out, "Module(body=[Assign(targets=[Name(id='x', ctx=Store())], value=Constant(value=1))], type_ignores=[])\n");
+    }
+
+    #[test]
+    fn cli_parse_eval_mode() {
+        let ( code, out, _ ) = run_with_stdin(&["parse", "--mode", "eval", "--ast", "--attributes"], "a\n");
+        assert_eq!(code, 0);
+        assert_eq!(out, "Expression(body=Name(id='a', ctx=Load(), lineno=1, col_offset=0, end_lineno=1, end_col_offset=1))\n");
+    }
+
+    #[test]
+    fn cli_check_reports_error_in_cpython_format() {
+        let ( code, _, err ) = run_with_stdin(&["check"], "x = 1\nif x\n    pass\n");
+        assert_eq!(code, 1);
+        assert_eq!(err, "  File \"<stdin>\", line 2\n    if x\n        ^\nSyntaxError: Expecting ':' in if statement!\n");
+    }
+
+    #[test]
+    fn cli_check_reports_lowering_error() {
+        let ( code, _, err ) = run_with_stdin(&["check"], "x = b'a' 'b'\n");
+        assert_eq!(code, 1);
+        assert_eq!(err, "  File \"<stdin>\", line 1\n    x = b'a' 'b'\n        ^\nSyntaxError: cannot mix bytes and nonbytes literals\n");
+    }
+
+    #[test]
+    fn cli_check_valid_source() {
+        let ( code, out, err ) = run_with_stdin(&["check", "-"], "def f():\n    return 1\n");
+        assert_eq!(( code, out.as_str(), err.as_str() ), ( 0, "", "" ));
+    }
+
+    #[test]
+    fn cli_check_directory() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+        let ( code, _, err ) = run_with_stdin(&["check", dir], "");
+        assert_eq!(code, 1);
+        assert_eq!(err.matches("SyntaxError").count(), 5);
+        assert!(err.contains("missing_colon.py\", line 1"));
+        assert!(err.contains("match_statement.py\", line 1"));
+        assert!(err.contains("containers_and_slices.py\", line 8"));
+        assert!(err.contains("trailers.py\", line 5"));
+    }
+
+    #[test]
+    fn cli_byte_order_mark() {
+        let path = std::env::temp_dir().join(format!("python_core_cli_bom_{}.py", std::process::id()));
+        std::fs::write(&path, "\u{feff}x = 1\nif x pass\n").unwrap();
+        let path = path.to_string_lossy().to_string();
+        let ( code, _, err ) = run_with_stdin(&["check", &path], "");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(code, 1);
+        assert!(err.contains("line 2\n    if x pass\n         ^\n"));
+        let ( code, out, _ ) = run_with_stdin(&["tokens"], "\u{feff}x = 1\n");
+        assert_eq!(code, 0);
+        assert!(out.starts_with("1,0-1,1:            AtomName            \"x\"\n"));
+    }
+
+    #[test]
+    fn cli_check_warnings() {
+        let ( code, _, err ) = run_with_stdin(&["check", "--warnings"], "def f():\n    return 1\n    g()\n");
+        assert_eq!(code, 0);
+        assert_eq!(err, "<stdin>:3:5: warning: unreachable code\n");
+    }
+
+    #[test]
+    fn cli_cfg() {
+        let ( code, out, _ ) = run_with_stdin(&["cfg", "--function", "f"], "def f():\n    pass\n");
+        assert_eq!(code, 0);
+        assert!(out.starts_with("digraph \"f\" {\n"));
+        assert!(out.contains("b2 [label=\"b2\\n2: pass\\l\"];\n"));
+        let ( code, out, _ ) = run_with_stdin(&["cfg"], "def f():\n    pass\n");
+        assert_eq!(( code, out.matches("digraph").count() ), ( 0, 2 ));
+        assert_eq!(run_with_stdin(&["cfg", "--function", "g"], "pass\n").0, 2);
+        assert_eq!(run_with_stdin(&["cfg"], "return\n").0, 1);
+    }
+
+    #[test]
+    fn cli_run() {
+        let ( code, out, _ ) = run_with_stdin(&["run"], "def f(n):\n    return n * 2\nprint(f(21), 2 ** 100)\n");
+        assert_eq!(( code, out.as_str() ), ( 0, "42 1267650600228229401496703205376\n" ));
+        let ( code, out, err ) = run_with_stdin(&["run"], "print('a')\n1 / 0\n");
+        assert_eq!(( code, out.as_str() ), ( 1, "a\n" ));
+        assert_eq!(err, "Traceback (most recent call last):\n  File \"<stdin>\", line 2, in <module>\n    1 / 0\nZeroDivisionError: division by zero\n");
+        assert_eq!(run_with_stdin(&["run"], "return\n").0, 1);
+    }
+
+    #[test]
+    fn cli_optimize() {
+        let ( code, out, _ ) = run_with_stdin(&["optimize", "--optimize", "1"], "x = 2 ** 8\nassert x\n");
+        assert_eq!(code, 0);
+        assert_eq!(out, "<stdin>:1:5: folded '2 ** 8' to 256\n<stdin>:2:1: removed assert statement\n");
+        let ( _, out, _ ) = run_with_stdin(&["parse", "--ast", "--optimize", "0"], "x = -1\n");
+        assert!(out.contains("value=Constant(value=-1)"));
+        let ( _, out, _ ) = run_with_stdin(&["dis"], "x = (1, 'a')\n");
+        assert!(out.contains("LOAD_CONST               0 ((1, 'a'))"));
+        assert_eq!(run_with_stdin(&["optimize", "--optimize", "3"], "").0, 2);
+    }
+
+    #[test]
+    fn cli_format() {
+        let ( code, out, _ ) = run_with_stdin(&["format"], "x=f(a,b)\n");
+        assert_eq!(( code, out.as_str() ), ( 0, "x = f(a, b)\n" ));
+        let ( code, out, _ ) = run_with_stdin(&["format", "--line-length", "10"], "x=f(a,b)\n");
+        assert_eq!(( code, out.as_str() ), ( 0, "x = f(\n    a, b\n)\n" ));
+        let ( code, _, err ) = run_with_stdin(&["format", "--check"], "x=1\n");
+        assert_eq!(( code, err.as_str() ), ( 1, "would reformat <stdin>\n" ));
+        assert_eq!(run_with_stdin(&["format", "--check"], "x = 1\n").0, 0);
+        assert_eq!(run_with_stdin(&["format"], "x = (\n").0, 1);
+        assert_eq!(run_with_stdin(&["format", "--line-length", "0"], "").0, 2);
+    }
+
+    #[test]
+    fn cli_lint() {
+        let ( code, out, _ ) = run_with_stdin(&["lint"], "import os\nif x == None:  # noqa: E711\n    pass\n");
+        assert_eq!(code, 1);
+        assert_eq!(out, "<stdin>:1:8: F401 'os' imported but unused\n<stdin>:2:4: F821 undefined name 'x'\n");
+        let ( code, out, _ ) = run_with_stdin(&["lint", "--select", "e7,W", "--ignore", "E711"], "x = None\nif x == None:\n    pass\n");
+        assert_eq!(( code, out.as_str() ), ( 0, "" ));
+        assert_eq!(run_with_stdin(&["lint"], "x = (\n").0, 1);
+        assert_eq!(run_with_stdin(&["lint", "--select"], "").0, 2);
+    }
+
+    #[test]
+    fn cli_lint_fix() {
+        let source = "from m import a, b\nif b == None:\n    pass\n";
+        let ( code, out, err ) = run_with_stdin(&["lint", "--fix"], source);
+        assert_eq!(( code, out.as_str(), err.as_str() ), ( 0, "from m import b\nif b is None:\n    pass\n", "" ));
+        let ( code, out, _ ) = run_with_stdin(&["lint", "--diff", "--select", "E711"], source);
+        assert_eq!(code, 1);
+        assert_eq!(out, "--- <stdin>\n+++ <stdin>\n@@ -1,3 +1,3 @@\n from m import a, b\n-if b == None:\n+if b is None:\n     pass\n");
+        let ( code, out, err ) = run_with_stdin(&["lint", "--fix"], "import os\nx\n");
+        assert_eq!(( code, out.as_str(), err.as_str() ), ( 1, "x\n", "<stdin>:1:1: F821 undefined name 'x'\n" ));
+    }
+
+    #[test]
+    fn cli_lint_fix_keeps_line_endings() {
+        let path = std::env::temp_dir().join(format!("python_core_cli_crlf_{}.py", std::process::id()));
+        std::fs::write(&path, "\u{feff}x = None\r\nif x == None:\r\n    pass").unwrap();
+        let name = path.to_string_lossy().to_string();
+        let ( code, out, err ) = run_with_stdin(&["lint", "--fix", &name], "");
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(( code, out.as_str(), err.as_str() ), ( 0, "", "" ));
+        assert_eq!(text, "\u{feff}x = None\r\nif x is None:\r\n    pass");
+        let ( _, out, _ ) = run_with_stdin(&["lint", "--fix"], "import os\r\nx = 1\r\n");
+        assert_eq!(out, "x = 1\r\n");
+    }
+
+    #[test]
+    fn cli_repl() {
+        let history = std::env::temp_dir().join(format!("python_core_cli_history_{}", std::process::id()));
+        let history = history.to_string_lossy().to_string();
+        let ( code, out, _ ) = run_with_stdin(&["repl", "--history", &history], "x = 6\nx * 7\n");
+        assert_eq!(( code, out.as_str() ), ( 0, ">>> >>> 42\n>>> \n" ));
+        assert_eq!(std::fs::read_to_string(&history).unwrap(), "x = 6\nx * 7\n");
+        let _ = std::fs::remove_file(&history);
+        assert_eq!(run_with_stdin(&["repl", "a.py"], "").0, 2);
+    }
+
+    #[test]
+    fn cli_lsp() {
+        let messages = [ r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#, r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","method":"exit"}"# ];
+        let input: String = messages.iter().map( |m| format!("Content-Length: {}\r\n\r\n{}", m.len(), m) ).collect();
+        let ( code, out, _ ) = run_with_stdin(&["lsp"], &input);
+        assert_eq!(code, 0);
+        assert!(out.starts_with("Content-Length: "));
+        assert!(out.ends_with("{\"jsonrpc\":\"2.0\",\"id\":2,\"result\":null}"));
+        assert_eq!(run_with_stdin(&["lsp"], "").0, 1);
+    }
+
+    #[test]
+    fn cli_highlight() {
+        assert_eq!(run_with_stdin(&["highlight"], "x = (1\n"), ( 0, "x \x1b[31m=\x1b[0m (\x1b[33m1\x1b[0m\n".to_string(), String::new() ));
+        let ( code, out, _ ) = run_with_stdin(&["highlight", "--html"], "type T = int\n");
+        assert_eq!(code, 0);
+        assert!(out.contains("<title>&lt;stdin&gt;</title>"));
+        assert!(out.contains("<span class=\"soft-keyword\">type</span> T <span class=\"operator\">=</span> <span class=\"builtin\">int</span>\n</pre>"));
+    }
+
+    #[test]
+    fn cli_run_vm() {
+        let ( code, out, _ ) = run_with_stdin(&["run", "--vm"], "def f(n):\n    yield n * 2\nprint(*f(21))\n");
+        assert_eq!(( code, out.as_str() ), ( 0, "42\n" ));
+        let ( code, _, err ) = run_with_stdin(&["run", "--vm"], "def f():\n    raise ValueError('x')\nf()\n");
+        assert_eq!(code, 1);
+        assert!(err.ends_with("  File \"<stdin>\", line 2, in f\n    raise ValueError('x')\nValueError: x\n"));
+    }
+
+    #[test]
+    fn cli_dis() {
+        let ( code, out, _ ) = run_with_stdin(&["dis"], "x = 1\n");
+        assert_eq!(code, 0);
+        assert_eq!(out, "  1           0 LOAD_CONST               0 (1)\n              1 STORE_NAME               0 (x)\n              2 LOAD_CONST               1 (None)\n              3 RETURN_VALUE\n");
+        assert_eq!(run_with_stdin(&["dis"], "x = (\n").0, 1);
+    }
+
+    #[test]
+    fn cli_usage_errors() {
+        assert_eq!(run_with_stdin(&[], "").0, 2);
+        assert_eq!(run_with_stdin(&["unknown"], "").0, 2);
+        assert_eq!(run_with_stdin(&["parse", "--mode", "bad"], "").0, 2);
+        assert_eq!(run_with_stdin(&["check", "/does/not/exist.py"], "").0, 2);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::edits::TextEdit;
+
+
+/// Severity of a diagnostic, errors make source invalid while warnings are only reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning
+}
+
+/// Message about a span of source, positions are character offsets like spans of the tree. Lint
+/// rules set 'code' to the code of the rule, like 'F401', and 'fix' to the edits that correct the
+/// problem when they can be applied without asking.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub start: u32,
+    pub end: u32,
+    pub severity: Severity,
+    pub message: String,
+    pub code: Option<String>,
+    pub fix: Vec<TextEdit>
+}
+
+
+impl Diagnostic {
+    pub fn error(start: u32, end: u32, message: &str) -> Diagnostic {
+        Diagnostic { start, end, severity: Severity::Error, message: message.to_string(), code: None, fix: Vec::new() }
+    }
+
+    pub fn warning(start: u32, end: u32, message: &str) -> Diagnostic {
+        Diagnostic { start, end, severity: Severity::Warning, message: message.to_string(), code: None, fix: Vec::new() }
+    }
+
+    /// Error from text of the form 'SyntaxError at N: message' used by parser and lowering.
+    /// Position in text is used when present, otherwise the given position.
+    pub fn from_error_text(text: &str, position: u32) -> Diagnostic {
+        let ( start, message ) = split_error_text(text);
+        let start = start.unwrap_or(position);
+        Diagnostic::error(start, start, &message)
+    }
+
+    pub fn with_code(mut self, code: &str) -> Diagnostic {
+        self.code = Some( code.to_string() );
+        self
+    }
+
+    pub fn with_fix(mut self, edits: Vec<TextEdit>) -> Diagnostic {
+        self.fix = edits;
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// Splits 'SyntaxError at N: message' into position and message.
+pub fn split_error_text(text: &str) -> ( Option<u32>, String ) {
+    if let Some( rest ) = text.strip_prefix("SyntaxError at ") {
+        if let Some( ( pos, message ) ) = rest.split_once(": ") {
+            if let Ok( p ) = pos.parse::<u32>() {
+                return ( Some( p ), message.to_string() )
+            }
+        }
+    }
+    ( None, text.trim_start_matches("SyntaxError: ").to_string() )
+}
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+
+/// Replacement of the text between character offsets 'start' and 'end' of source by 'text'.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextEdit {
+    pub start: u32,
+    pub end: u32,
+    pub text: String
+}
+
+
+impl TextEdit {
+    pub fn new(start: u32, end: u32, text: &str) -> TextEdit {
+        TextEdit { start, end, text: text.to_string() }
+    }
+
+    pub fn overlaps(&self, other: &TextEdit) -> bool {
+        self.start < other.end && other.start < self.end || self.start == other.start
+    }
+}
+
+/// Applies edits that do not overlap, in any order, to source.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key( |e| ( e.start, e.end ) );
+    let mut res = String::new();
+    let mut pos = 0usize;
+    for edit in edits {
+        let start = ( edit.start as usize ).clamp(pos, chars.len());
+        res.extend(&chars[pos .. start]);
+        res.push_str(&edit.text);
+        pos = ( edit.end as usize ).clamp(start, chars.len())
+    }
+    res.extend(&chars[pos ..]);
+    res
+}
+
+/// Smallest edits turning 'old' into 'new', one for every group of changed lines with the text both
+/// have in common at its ends left out. Offsets count from 'offset', the position of 'old' in source.
+pub fn diff_edits(old: &str, new: &str, offset: u32) -> Vec<TextEdit> {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let mut res = Vec::new();
+    let ( mut i, mut j ) = ( 0, 0 );
+    let mut position = offset;
+    for ( ci, cj ) in common_lines(&a, &b).into_iter().chain([ ( a.len(), b.len() ) ]) {
+        if ci > i || cj > j {
+            let removed: String = a[i .. ci].concat();
+            let added: String = b[j .. cj].concat();
+            res.push(trimmed_edit(&removed, &added, position));
+            position += removed.chars().count() as u32
+        }
+        if ci < a.len() {
+            position += a[ci].chars().count() as u32
+        }
+        ( i, j ) = ( ci + 1, cj + 1 )
+    }
+    res
+}
+
+/// Unified diff of the lines of 'old' and 'new' with three lines of context, like 'diff -u', empty
+/// when both are the same.
+pub fn unified_diff(old: &str, new: &str, name: &str) -> String {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    // Lines of both sides with ' ', '-' or '+', and the line numbers of old and new in front of them.
+    let mut lines: Vec<( char, &str, usize, usize )> = Vec::new();
+    let ( mut i, mut j ) = ( 0, 0 );
+    for ( ci, cj ) in common_lines(&a, &b).into_iter().chain([ ( a.len(), b.len() ) ]) {
+        while i < ci {
+            lines.push( ( '-', a[i], i, j ) );
+            i += 1
+        }
+        while j < cj {
+            lines.push( ( '+', b[j], i, j ) );
+            j += 1
+        }
+        if ci < a.len() {
+            lines.push( ( ' ', a[i], i, j ) );
+            i += 1;
+            j += 1
+        }
+    }
+    let changed: Vec<usize> = ( 0 .. lines.len() ).filter( |k| lines[*k].0 != ' ' ).collect();
+    if changed.is_empty() {
+        return String::new()
+    }
+    let mut res = format!("--- {}\n+++ {}\n", name, name);
+    let mut k = 0;
+    while k < changed.len() {
+        let first = changed[k].saturating_sub(3);
+        let mut last = changed[k];
+        while k < changed.len() && changed[k] <= last + 7 {
+            last = changed[k];
+            k += 1
+        }
+        let last = ( last + 3 ).min(lines.len() - 1);
+        let hunk = &lines[first ..= last];
+        let old_count = hunk.iter().filter( |l| l.0 != '+' ).count();
+        let new_count = hunk.iter().filter( |l| l.0 != '-' ).count();
+        let old_start = hunk[0].2 + if old_count > 0 { 1 } else { 0 };
+        let new_start = hunk[0].3 + if new_count > 0 { 1 } else { 0 };
+        res.push_str(&format!("@@ -{},{} +{},{} @@\n", old_start, old_count, new_start, new_count));
+        for ( kind, text, _, _ ) in hunk {
+            res.push(*kind);
+            res.push_str(text);
+            if !text.ends_with('\n') {
+                res.push_str("\n\\ No newline at end of file\n")
+            }
+        }
+    }
+    res
+}
+
+/// Indexes of the lines of 'a' and 'b' paired in a longest common subsequence, in order. Lines are
+/// numbered by their text, and the lines between common prefix and suffix are compared with the
+/// linear space variant of Myers' algorithm, in time growing with the number of changed lines.
+fn common_lines<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<( usize, usize )> {
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut number = |lines: &[&'a str]| -> Vec<usize> {
+        lines.iter().map( |l| {
+            let next = ids.len();
+            *ids.entry(l).or_insert(next)
+        }).collect()
+    };
+    let ( x, y ) = ( number(a), number(b) );
+    let mut res = Vec::new();
+    common_ranges(&x, &y, 0, 0, &mut res);
+    res
+}
+
+/// Appends the pairs of common lines of 'a' and 'b', which start at lines 'i' and 'j', by splitting
+/// them where a shortest edit script has done half of its edits.
+fn common_ranges(a: &[usize], b: &[usize], i: usize, j: usize, res: &mut Vec<( usize, usize )>) {
+    let prefix = a.iter().zip(b).take_while( |( x, y )| x == y ).count();
+    let suffix = a[prefix ..].iter().rev().zip(b[prefix ..].iter().rev()).take_while( |( x, y )| x == y ).count();
+    res.extend( ( 0 .. prefix ).map( |k| ( i + k, j + k ) ) );
+    let ( a_mid, b_mid ) = ( &a[prefix .. a.len() - suffix], &b[prefix .. b.len() - suffix] );
+    if !a_mid.is_empty() && !b_mid.is_empty() {
+        let ( x, y ) = middle_snake(a_mid, b_mid);
+        common_ranges(&a_mid[.. x], &b_mid[.. y], i + prefix, j + prefix, res);
+        common_ranges(&a_mid[x ..], &b_mid[y ..], i + prefix + x, j + prefix + y, res)
+    }
+    res.extend( ( 0 .. suffix ).map( |k| ( i + a.len() - suffix + k, j + b.len() - suffix + k ) ) )
+}
+
+/// Start of the middle snake of Myers, a point on a shortest edit script from 'a' to 'b' with half
+/// of its edits on each side. Searches forward from the start and backward from the end at once,
+/// keeping for every diagonal only the furthest line of 'a' reached.
+fn middle_snake(a: &[usize], b: &[usize]) -> ( usize, usize ) {
+    let ( n, m ) = ( a.len() as isize, b.len() as isize );
+    let max = ( n + m + 1 ) / 2 + 1;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let mut forward = vec![ 0isize; 2 * max as usize + 1 ];
+    let mut backward = vec![ 0isize; 2 * max as usize + 1 ];
+    let at = |k: isize| ( k + max ) as usize;
+    for d in 0 .. max {
+        for k in ( -d ..= d ).rev().step_by(2) {
+            let mut x = match k == -d || ( k != d && forward[at(k - 1)] < forward[at(k + 1)] ) {
+                true => forward[at(k + 1)],
+                _ => forward[at(k - 1)] + 1
+            };
+            let ( x0, y0 ) = ( x, x - k );
+            while x < n && x - k < m && x - k >= 0 && a[x as usize] == b[( x - k ) as usize] {
+                x += 1
+            }
+            forward[at(k)] = x;
+            if odd && ( k - delta ).abs() < d && x + backward[at(delta - k)] >= n {
+                return ( x0 as usize, y0 as usize )
+            }
+        }
+        for k in ( -d ..= d ).rev().step_by(2) {
+            let mut x = match k == -d || ( k != d && backward[at(k - 1)] < backward[at(k + 1)] ) {
+                true => backward[at(k + 1)],
+                _ => backward[at(k - 1)] + 1
+            };
+            while x < n && x - k < m && x - k >= 0 && a[( n - x - 1 ) as usize] == b[( m - x + k - 1 ) as usize] {
+                x += 1
+            }
+            backward[at(k)] = x;
+            if !odd && ( k - delta ).abs() <= d && x + forward[at(delta - k)] >= n {
+                return ( ( n - x ) as usize, ( m - x + k ) as usize )
+            }
+        }
+    }
+    unreachable!("edit script longer than both sequences")
+}
+
+/// Edit replacing 'old' at 'position' by 'new' without their common prefix and suffix.
+fn trimmed_edit(old: &str, new: &str, position: u32) -> TextEdit {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+    let prefix = old.iter().zip(new.iter()).take_while( |( x, y )| x == y ).count();
+    let suffix = old[prefix ..].iter().rev().zip(new[prefix ..].iter().rev()).take_while( |( x, y )| x == y ).count();
+    TextEdit {
+        start: position + prefix as u32,
+        end: position + ( old.len() - suffix ) as u32,
+        text: new[prefix .. new.len() - suffix].iter().collect()
+    }
+}
+
+
+// UnitTests for text edits ///////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::edits::{ TextEdit, apply_edits, diff_edits, unified_diff, common_lines };
+
+
+    #[test]
+    fn edits_are_applied_in_order_of_position() {
+        let edits = vec![ TextEdit::new(4, 5, "2"), TextEdit::new(0, 1, "y") ];
+        assert_eq!(apply_edits("x = 1\n", &edits), "y = 2\n");
+        assert_eq!(apply_edits("ab", &[ TextEdit::new(1, 1, "é") ]), "aéb");
+    }
+
+    #[test]
+    fn diff_gives_small_edits() {
+        let old = "a = 1\nb=2\nc = 3\nd=4\n";
+        let new = "a = 1\nb = 2\nc = 3\nd = 4\n";
+        let edits = diff_edits(old, new, 10);
+        assert_eq!(edits, vec![ TextEdit::new(17, 18, " = "), TextEdit::new(27, 28, " = ") ]);
+        assert_eq!(apply_edits(old, &diff_edits(old, new, 0)), new);
+        assert!(diff_edits(old, old, 0).is_empty());
+        assert_eq!(apply_edits("x\n", &diff_edits("x\n", "x\ny\n", 0)), "x\ny\n");
+    }
+
+    #[test]
+    fn unified_diff_has_hunks_with_context() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\n";
+        assert_eq!(unified_diff(old, new, "x.py"), concat!(
+            "--- x.py\n+++ x.py\n",
+            "@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n",
+            "@@ -11,3 +11,4 @@\n k\n l\n m\n+n\n"
+        ));
+        assert_eq!(unified_diff(old, old, "x.py"), "");
+        assert_eq!(unified_diff("x\n", "", "x.py"), "--- x.py\n+++ x.py\n@@ -1,1 +0,0 @@\n-x\n");
+    }
+
+    #[test]
+    fn common_lines_are_a_longest_common_subsequence() {
+        let mut seed = 7u32;
+        let mut lines = |count: usize| -> Vec<&str> {
+            ( 0 .. count ).map( |_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                [ "a\n", "b\n", "c\n", "d\n" ][( seed >> 16 ) as usize % 4]
+            }).collect()
+        };
+        for round in 0 .. 200 {
+            let ( a, b ) = ( lines(round % 23), lines(round % 17) );
+            let mut table = vec![ vec![ 0; b.len() + 1 ]; a.len() + 1 ];
+            for i in ( 0 .. a.len() ).rev() {
+                for j in ( 0 .. b.len() ).rev() {
+                    table[i][j] = if a[i] == b[j] { table[i + 1][j + 1] + 1 } else { table[i + 1][j].max(table[i][j + 1]) }
+                }
+            }
+            let common = common_lines(&a, &b);
+            assert_eq!(common.len(), table[0][0]);
+            assert!(common.iter().all( |( i, j )| a[*i] == b[*j] ));
+            assert!(common.windows(2).all( |w| w[0].0 < w[1].0 && w[0].1 < w[1].1 ));
+        }
+        let long: Vec<String> = ( 0 .. 20000 ).map( |i| format!("{}\n", i) ).collect();
+        let mut changed = long.clone();
+        changed[10] = "x\n".to_string();
+        changed.insert(15000, "y\n".to_string());
+        let edits = diff_edits(&long.concat(), &changed.concat(), 0);
+        assert_eq!(edits.len(), 2);
+        assert_eq!(apply_edits(&long.concat(), &edits), changed.concat());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod lines;
+pub mod split;
+pub mod printer;
+pub mod range;
@@ -0,0 +1,817 @@
+use crate::Token;
+use crate::parser::trivias::Trivia;
+
+
+/// Kind of a leaf, the tokens reduced to what spacing and splitting of lines need.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeafKind {
+    Name,
+    Keyword,
+    Number,
+    String,
+    Operator,
+    Open,
+    Close,
+    Comma,
+    Colon,
+    Dot,
+    /// Comment on a line of its own between brackets, it forces the line to be split.
+    StandaloneComment
+}
+
+/// Token of a logical line with the comments following it on the same source line. Invisible
+/// parentheses are added around expressions that may be wrapped in parentheses when split.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Leaf {
+    pub kind: LeafKind,
+    pub text: String,
+    pub space: bool,
+    pub invisible: bool,
+    /// Operator in front of its operand: unary operators, star arguments and decorators.
+    pub unary: bool,
+    /// Bracket of a call or subscription, or parameters of a function.
+    pub trailer: bool,
+    pub comments: Vec<String>
+}
+
+/// Logical line of output, 'depth' counts the indentation levels. Lines inside brackets are the
+/// parts of a split line.
+#[derive(Clone, Debug, Default)]
+pub struct Line {
+    pub depth: usize,
+    pub leaves: Vec<Leaf>,
+    pub inside_brackets: bool,
+    /// Part of an import statement, so a dot never separates attributes.
+    pub import: bool,
+    /// Body of brackets that is split at every comma even when it fits.
+    pub should_split: bool,
+    /// Character offsets of the line in source, from its first token to its last token.
+    pub start: u32,
+    pub end: u32,
+    /// Indentation in source of the statements of the block holding the line.
+    pub indent: String
+}
+
+
+impl Leaf {
+    pub fn new(kind: LeafKind, text: &str) -> Leaf {
+        Leaf { kind, text: text.to_string(), space: false, invisible: false, unary: false, trailer: false, comments: Vec::new() }
+    }
+
+    fn invisible(kind: LeafKind) -> Leaf {
+        let mut leaf = Leaf::new(kind, if kind == LeafKind::Open { "(" } else { ")" });
+        leaf.invisible = true;
+        leaf
+    }
+
+    pub fn is(&self, kind: LeafKind, text: &str) -> bool {
+        self.kind == kind && self.text == text
+    }
+
+    /// Leaf that can end an operand, so a bracket after it is a trailer and an operator after it
+    /// is binary.
+    pub fn ends_operand(&self) -> bool {
+        matches!(self.kind, LeafKind::Name | LeafKind::Number | LeafKind::String | LeafKind::Close)
+    }
+}
+
+impl Line {
+    pub fn new(depth: usize, inside_brackets: bool, import: bool) -> Line {
+        Line { depth, leaves: Vec::new(), inside_brackets, import, should_split: false, start: 0, end: 0, indent: String::new() }
+    }
+
+    fn first(&self) -> &str {
+        self.leaves.first().map( |l| l.text.as_str() ).unwrap_or("")
+    }
+
+    fn second(&self) -> &str {
+        self.leaves.get(1).map( |l| l.text.as_str() ).unwrap_or("")
+    }
+
+    pub fn is_comment(&self) -> bool {
+        self.leaves.len() == 1 && self.leaves[0].kind == LeafKind::StandaloneComment
+    }
+
+    pub fn is_decorator(&self) -> bool {
+        !self.inside_brackets && self.leaves.first().is_some_and( |l| l.is(LeafKind::Operator, "@") )
+    }
+
+    pub fn is_def(&self) -> bool {
+        !self.inside_brackets && ( self.first() == "def" || ( self.first() == "async" && self.second() == "def" ) )
+    }
+
+    pub fn is_class(&self) -> bool {
+        !self.inside_brackets && self.first() == "class"
+    }
+
+    pub fn is_import(&self) -> bool {
+        !self.inside_brackets && matches!(self.first(), "import" | "from")
+    }
+
+    /// Header of a compound statement with its body on the following lines.
+    pub fn opens_block(&self) -> bool {
+        !self.is_comment() && self.leaves.last().is_some_and( |l| l.kind == LeafKind::Colon )
+    }
+
+    pub fn has_standalone_comments(&self) -> bool {
+        self.leaves.iter().any( |l| l.kind == LeafKind::StandaloneComment )
+    }
+}
+
+/// Index of the matching bracket of every bracket of leaves.
+pub fn matching_brackets(leaves: &[Leaf]) -> Vec<Option<usize>> {
+    let mut res = vec![ None; leaves.len() ];
+    let mut stack = Vec::new();
+    for ( i, leaf ) in leaves.iter().enumerate() {
+        match leaf.kind {
+            LeafKind::Open => stack.push(i),
+            LeafKind::Close => if let Some( o ) = stack.pop() {
+                res[o] = Some( i );
+                res[i] = Some( o )
+            },
+            _ => { }
+        }
+    }
+    res
+}
+
+/// Bracket depth in front of every leaf, a closing bracket has the depth of its content.
+pub fn bracket_depths(leaves: &[Leaf]) -> Vec<usize> {
+    let mut depth = 0usize;
+    leaves.iter().map( |l| {
+        let res = depth;
+        match l.kind {
+            LeafKind::Open => depth += 1,
+            LeafKind::Close => depth = depth.saturating_sub(1),
+            _ => { }
+        }
+        res
+    }).collect()
+}
+
+
+// Logical lines //////////////////////////////////////////////////////////////////////////////////
+
+/// Splits tokens into logical lines, one for each simple statement or header of a compound
+/// statement and one for each comment on a line of its own. Every line comes with the number of
+/// empty lines in front of it in source.
+pub fn logical_lines(source: &str, tokens: &[Box<Token>]) -> Vec<( Line, usize )> {
+    let mut gen = LineGenerator {
+        chars: source.chars().collect(),
+        items: Vec::new(),
+        depth: 0,
+        columns: vec![ 0 ],
+        indents: vec![ String::new() ],
+        pending_column: false,
+        current: Vec::new(),
+        current_depth: 0,
+        current_span: ( 0, 0 ),
+        brackets: 0,
+        lambdas: 0,
+        inline_body: false,
+        at_line_start: true,
+        blank: 0,
+        before: 0,
+        comments: Vec::new()
+    };
+    for ( i, token ) in tokens.iter().enumerate() {
+        gen.visit(token, tokens.get(i + 1).map( |t| &**t ))
+    }
+    gen.end_statement();
+    gen.flush_comments();
+    gen.items
+}
+
+struct LineGenerator {
+    chars: Vec<char>,
+    items: Vec<( Line, usize )>,
+    depth: usize,
+    /// Column and indentation of statements of every open block.
+    columns: Vec<usize>,
+    indents: Vec<String>,
+    pending_column: bool,
+    current: Vec<Leaf>,
+    current_depth: usize,
+    current_span: ( u32, u32 ),
+    brackets: usize,
+    lambdas: usize,
+    /// Body of a compound statement on the line of its header.
+    inline_body: bool,
+    at_line_start: bool,
+    blank: usize,
+    before: usize,
+    /// Comments on lines of their own waiting for the next statement.
+    comments: Vec<PendingComment>
+}
+
+struct PendingComment {
+    leaf: Leaf,
+    column: usize,
+    before: usize,
+    span: ( u32, u32 )
+}
+
+impl LineGenerator {
+    fn visit(&mut self, token: &Token, next: Option<&Token>) {
+        if let Some( trivia ) = token.get_trivia() {
+            let mut trivia: Vec<&Trivia> = trivia.iter().map( |t| &**t ).collect();
+            trivia.sort_by_key( |t| match t {
+                Trivia::WhiteSpace(s, ..) | Trivia::Newline(s, ..) | Trivia::LineContinuation(s, ..) | Trivia::Comment(s, ..) => *s
+            });
+            for t in trivia {
+                match t {
+                    Trivia::Newline(..) if self.at_line_start => self.blank += 1,
+                    Trivia::Newline(..) => self.at_line_start = true,
+                    Trivia::Comment(s, e, text) => self.comment(*s, *e, text),
+                    _ => { }
+                }
+            }
+        }
+        match token {
+            Token::Newline(..) => {
+                self.end_statement();
+                if self.inline_body {
+                    self.inline_body = false;
+                    self.depth -= 1;
+                    self.indents.pop();
+                }
+                self.at_line_start = true;
+                self.blank = 0
+            },
+            Token::Indent(..) => {
+                self.depth += 1;
+                self.columns.push(0);
+                self.indents.push(String::new());
+                self.pending_column = true
+            },
+            Token::Dedent(..) => self.dedent(),
+            Token::EOF(..) => self.end_statement(),
+            Token::TypeComment(s, e, _, text) => {
+                let at_line_start = self.at_line_start;
+                self.at_line_start = false;
+                self.comment(*s, *e, text);
+                self.at_line_start = at_line_start
+            },
+            _ => self.leaf(token, next)
+        }
+    }
+
+    fn line_prefix(&self, pos: u32) -> &[char] {
+        let mut start = pos as usize;
+        while start > 0 && self.chars[start - 1] != '\n' {
+            start -= 1
+        }
+        &self.chars[start .. pos as usize]
+    }
+
+    fn column(&self, pos: u32) -> usize {
+        self.line_prefix(pos).iter().fold(0, |col, c| if *c == '\t' { ( col / 8 + 1 ) * 8 } else { col + 1 })
+    }
+
+    fn comment(&mut self, start: u32, end: u32, text: &str) {
+        let text = match text.starts_with("# type:") {
+            true => text.trim_end().to_string(),
+            _ => normalize_comment(text)
+        };
+        if !self.at_line_start {
+            if let Some( leaf ) = self.current.last_mut() {
+                leaf.comments.push(text)
+            } else if let Some( leaf ) = self.items.last_mut().and_then( |( l, _ )| l.leaves.last_mut() ) {
+                leaf.comments.push(text)
+            }
+            return
+        }
+        self.at_line_start = false;
+        let leaf = Leaf::new(LeafKind::StandaloneComment, &text);
+        if !self.current.is_empty() {
+            self.current.push(leaf);
+            return
+        }
+        let column = self.column(start);
+        self.comments.push(PendingComment { leaf, column, before: self.blank, span: ( start, end ) });
+        self.blank = 0
+    }
+
+    fn flush_comments(&mut self) {
+        for comment in std::mem::take(&mut self.comments) {
+            let mut line = Line::new(self.depth, false, false);
+            line.leaves.push(comment.leaf);
+            ( line.start, line.end ) = comment.span;
+            line.indent = self.indents[self.depth].clone();
+            self.items.push( ( line, comment.before ) )
+        }
+    }
+
+    /// Comments indented as deep as the statements of the closed block stay at the end of it.
+    fn dedent(&mut self) {
+        let column = self.columns[self.depth];
+        let count = self.comments.iter().take_while( |c| c.column >= column ).count();
+        let rest = self.comments.split_off(count);
+        self.flush_comments();
+        self.comments = rest;
+        self.depth -= 1;
+        self.columns.pop();
+        self.indents.pop();
+    }
+
+    fn leaf(&mut self, token: &Token, next: Option<&Token>) {
+        let Some( mut leaf ) = leaf_of(token) else { return };
+        let ( start, end ) = token.get_span().unwrap_or_default();
+        if self.current.is_empty() {
+            if self.pending_column {
+                self.columns[self.depth] = self.column(start);
+                self.indents[self.depth] = self.line_prefix(start).iter().collect();
+                self.pending_column = false
+            }
+            self.current_span.0 = start;
+            self.flush_comments();
+            self.before = self.blank;
+            self.current_depth = self.depth;
+            self.brackets = 0;
+            self.lambdas = 0
+        }
+        self.blank = 0;
+        self.at_line_start = false;
+        self.current_span.1 = end;
+        match leaf.kind {
+            LeafKind::Open => self.brackets += 1,
+            LeafKind::Close => self.brackets = self.brackets.saturating_sub(1),
+            _ => { }
+        }
+        if self.brackets == 0 && leaf.is(LeafKind::Keyword, "lambda") {
+            self.lambdas += 1
+        }
+        if self.brackets == 0 && leaf.is(LeafKind::Operator, ";") {
+            self.end_statement();
+            return
+        }
+        let header = self.brackets == 0 && leaf.kind == LeafKind::Colon && match self.lambdas {
+            0 => self.is_compound(),
+            _ => {
+                self.lambdas -= 1;
+                false
+            }
+        };
+        leaf.space = true;
+        self.current.push(leaf);
+        if header && !matches!(next, Some( Token::Newline(..) ) | Some( Token::TypeComment(..) )) {
+            self.end_statement();
+            self.depth += 1;
+            self.indents.push(format!("{}    ", self.indents[self.depth - 1]));
+            self.inline_body = true
+        }
+    }
+
+    fn is_compound(&self) -> bool {
+        let first = self.current.first().map( |l| l.text.as_str() ).unwrap_or("");
+        let second = self.current.get(1).map( |l| l.text.as_str() ).unwrap_or("");
+        matches!(first, "if" | "elif" | "else" | "while" | "for" | "try" | "except" | "finally" | "with" | "def" | "class")
+            || ( first == "async" && matches!(second, "def" | "for" | "with") )
+    }
+
+    fn end_statement(&mut self) {
+        if self.current.is_empty() {
+            return
+        }
+        let mut line = Line::new(self.current_depth, false, false);
+        line.leaves = std::mem::take(&mut self.current);
+        line.import = line.is_import();
+        ( line.start, line.end ) = self.current_span;
+        line.indent = self.indents[self.current_depth].clone();
+        self.items.push( ( line, self.before ) );
+        self.before = 0
+    }
+}
+
+fn leaf_of(token: &Token) -> Option<Leaf> {
+    let text = token.text()?;
+    let kind = match token {
+        Token::AtomName(..) | Token::PyTrue(..) | Token::PyFalse(..) | Token::PyNone(..) | Token::PyElipsis(..) => LeafKind::Name,
+        Token::AtomNumber(..) => LeafKind::Number,
+        Token::AtomString(..) => LeafKind::String,
+        Token::PyLeftParen(..) | Token::PyLeftBracket(..) | Token::PyLeftCurly(..) => LeafKind::Open,
+        Token::PyRightParen(..) | Token::PyRightBracket(..) | Token::PyRightCurly(..) => LeafKind::Close,
+        Token::PyComa(..) => LeafKind::Comma,
+        Token::PyColon(..) => LeafKind::Colon,
+        Token::PyDot(..) => LeafKind::Dot,
+        _ if text.chars().all( |c| c.is_ascii_alphabetic() ) => LeafKind::Keyword,
+        _ => LeafKind::Operator
+    };
+    let text = match kind {
+        LeafKind::Number => normalize_number(&text),
+        LeafKind::String => normalize_string(&text),
+        _ => text
+    };
+    Some( Leaf::new(kind, &text) )
+}
+
+
+// Normalization of leaves ////////////////////////////////////////////////////////////////////////
+
+/// Comment with a space after the hash unless it starts a shebang, a type comment or other marker.
+pub fn normalize_comment(text: &str) -> String {
+    let content = &text.trim_end()[1 ..];
+    match content.chars().next() {
+        None => "#".to_string(),
+        Some( ' ' | '!' | ':' | '#' | '\'' ) => format!("#{}", content),
+        _ => format!("# {}", content)
+    }
+}
+
+/// Number literal with lower case prefixes and exponents, upper case hexadecimal digits and a
+/// digit on both sides of a decimal point.
+pub fn normalize_number(text: &str) -> String {
+    let text = text.to_lowercase();
+    if let Some( digits ) = text.strip_prefix("0x") {
+        return format!("0x{}", digits.to_uppercase())
+    }
+    if text.starts_with("0b") || text.starts_with("0o") {
+        return text
+    }
+    if let Some( ( before, after ) ) = text.split_once('e') {
+        let after = after.strip_prefix('+').unwrap_or(after);
+        return format!("{}e{}", float_or_int(before), after)
+    }
+    if let Some( number ) = text.strip_suffix('j') {
+        return format!("{}j", float_or_int(number))
+    }
+    float_or_int(&text)
+}
+
+fn float_or_int(text: &str) -> String {
+    match text.split_once('.') {
+        Some( ( before, after ) ) => format!("{}.{}", if before.is_empty() { "0" } else { before }, if after.is_empty() { "0" } else { after }),
+        None => text.to_string()
+    }
+}
+
+/// String literal with lower case prefix and double quotes, unless that needs more escapes.
+pub fn normalize_string(text: &str) -> String {
+    let split = text.find(['\'', '"']).unwrap_or(0);
+    let ( prefix, value ) = text.split_at(split);
+    let prefix = prefix.replace('F', "f").replace('B', "b").replace('U', "u");
+    if value.starts_with("\"\"\"") {
+        return format!("{}{}", prefix, value)
+    }
+    if value.starts_with("'''") {
+        let body = &value[3 .. value.len() - 3];
+        return match body.contains('"') || body.ends_with('\\') {
+            true => format!("{}{}", prefix, value),
+            _ => format!("{}\"\"\"{}\"\"\"", prefix, body)
+        }
+    }
+    let ( orig, new ) = match value.starts_with('"') {
+        true => ( '"', '\'' ),
+        _ => ( '\'', '"' )
+    };
+    let body = &value[1 .. value.len() - 1];
+    let raw = prefix.contains(['r', 'R']);
+    let mut kept = String::new();
+    let mut changed = String::new();
+    match raw {
+        true => {
+            if body.contains(new) {
+                return format!("{}{}", prefix, value)
+            }
+            kept.push_str(body);
+            changed.push_str(body)
+        },
+        _ => {
+            let mut chars = body.chars();
+            while let Some( c ) = chars.next() {
+                match c {
+                    '\\' => match chars.next() {
+                        Some( n ) if n == new => {
+                            kept.push(n);
+                            changed.push('\\');
+                            changed.push(n)
+                        },
+                        Some( n ) if n == orig => {
+                            kept.push('\\');
+                            kept.push(n);
+                            changed.push(n)
+                        },
+                        Some( n ) => {
+                            kept.push('\\');
+                            kept.push(n);
+                            changed.push('\\');
+                            changed.push(n)
+                        },
+                        None => {
+                            kept.push('\\');
+                            changed.push('\\')
+                        }
+                    },
+                    _ if c == new => {
+                        kept.push(c);
+                        changed.push('\\');
+                        changed.push(c)
+                    },
+                    _ => {
+                        kept.push(c);
+                        changed.push(c)
+                    }
+                }
+            }
+        }
+    }
+    let keep = format!("{}{}{}{}", prefix, orig, kept, orig);
+    if prefix.contains('f') && body.contains(new) {
+        return keep
+    }
+    let kept_escapes = kept.matches('\\').count();
+    let changed_escapes = changed.matches('\\').count();
+    match changed_escapes > kept_escapes || ( changed_escapes == kept_escapes && orig == '"' ) {
+        true => keep,
+        _ => format!("{}{}{}{}", prefix, new, changed, new)
+    }
+}
+
+
+// Finishing lines ////////////////////////////////////////////////////////////////////////////////
+
+/// Marks unary operators and trailers, puts invisible parentheses around the expressions that are
+/// wrapped in parentheses when split, and decides the spaces between leaves.
+pub fn finish_line(line: &mut Line) {
+    if line.is_comment() {
+        return
+    }
+    for i in 0 .. line.leaves.len() {
+        let after_operand = i > 0 && line.leaves[i - 1].ends_operand();
+        let leaf = &mut line.leaves[i];
+        leaf.unary = leaf.kind == LeafKind::Operator && matches!(leaf.text.as_str(), "-" | "+" | "~" | "*" | "**" | "@") && !after_operand;
+        leaf.trailer = leaf.kind == LeafKind::Open && leaf.text != "{" && after_operand
+    }
+    if line.is_class() && line.leaves.len() > 3 && line.leaves[2].is(LeafKind::Open, "(") && line.leaves[3].is(LeafKind::Close, ")") && line.leaves[2].comments.is_empty() {
+        line.leaves.drain(2 .. 4);
+    }
+    let mut ranges = parenthesized_ranges(&line.leaves);
+    ranges.sort_by_key( |r| std::cmp::Reverse(r.0) );
+    for ( start, end, tuple ) in ranges {
+        wrap_in_invisible_parens(&mut line.leaves, start, end, tuple)
+    }
+    set_spaces(&mut line.leaves)
+}
+
+/// Ranges of the expressions that get invisible parentheses, with whether a tuple may lose them.
+fn parenthesized_ranges(leaves: &[Leaf]) -> Vec<( usize, usize, bool )> {
+    let n = leaves.len();
+    let depths = bracket_depths(leaves);
+    let find = |text: &str, from: usize| ( from .. n ).find( |i| depths[*i] == 0 && leaves[*i].kind == LeafKind::Keyword && leaves[*i].text == text );
+    let offset = usize::from(leaves[0].text == "async");
+    let ends_with_colon = leaves[n - 1].kind == LeafKind::Colon;
+    let mut res = Vec::new();
+    match leaves[offset].text.as_str() {
+        _ if leaves[offset].kind != LeafKind::Keyword => { },
+        "return" => res.push( ( 1, n, false ) ),
+        "if" | "elif" | "while" if ends_with_colon => res.push( ( 1, n - 1, false ) ),
+        "for" if ends_with_colon => if let Some( k ) = find("in", offset + 1) {
+            res.push( ( offset + 1, k, false ) );
+            res.push( ( k + 1, n - 1, false ) )
+        },
+        "del" if !( 1 .. n ).any( |i| depths[i] == 0 && leaves[i].kind == LeafKind::Comma ) => res.push( ( 1, n, false ) ),
+        "assert" => match ( 1 .. n ).find( |i| depths[*i] == 0 && leaves[*i].kind == LeafKind::Comma ) {
+            Some( k ) => {
+                res.push( ( 1, k, false ) );
+                res.push( ( k + 1, n, false ) )
+            },
+            None => res.push( ( 1, n, false ) )
+        },
+        "except" if ends_with_colon => res.push( ( 1, find("as", 1).unwrap_or(n - 1), false ) ),
+        "from" => if let Some( k ) = find("import", 1) {
+            if leaves.get(k + 1).is_some_and( |l| l.text != "*" ) {
+                res.push( ( k + 1, n, true ) )
+            }
+        },
+        _ => { }
+    }
+    if leaves[0].kind != LeafKind::Keyword || leaves[0].text == "await" {
+        let mut lambdas = 0;
+        let mut assign = None;
+        for i in 0 .. n {
+            if depths[i] != 0 {
+                continue
+            }
+            match leaves[i].kind {
+                LeafKind::Keyword if leaves[i].text == "lambda" => lambdas += 1,
+                LeafKind::Colon if lambdas > 0 => lambdas -= 1,
+                LeafKind::Operator if lambdas == 0 && is_assign(&leaves[i].text) => assign = Some( i ),
+                _ => { }
+            }
+        }
+        if let Some( k ) = assign {
+            res.push( ( k + 1, n, false ) )
+        }
+    }
+    res.retain( |( s, e, _ )| s < e );
+    res
+}
+
+fn is_assign(text: &str) -> bool {
+    matches!(text, "=" | "+=" | "-=" | "*=" | "/=" | "//=" | "%=" | "@=" | "&=" | "|=" | "^=" | "<<=" | ">>=" | "**=")
+}
+
+fn wrap_in_invisible_parens(leaves: &mut Vec<Leaf>, mut start: usize, mut end: usize, tuple: bool) {
+    let matching = matching_brackets(leaves);
+    let redundant = |leaves: &[Leaf], s: usize, e: usize| leaves[s].is(LeafKind::Open, "(") && matching[s] == Some( e - 1 )
+        && !leaves[s].trailer && removable(&leaves[s + 1 .. e - 1], tuple);
+    if !redundant(leaves, start, end) {
+        leaves.insert(end, Leaf::invisible(LeafKind::Close));
+        leaves.insert(start, Leaf::invisible(LeafKind::Open));
+        return
+    }
+    while redundant(leaves, start, end) {
+        leaves[start].invisible = true;
+        leaves[end - 1].invisible = true;
+        start += 1;
+        end -= 1
+    }
+}
+
+/// Content of parentheses that means the same without them.
+fn removable(content: &[Leaf], tuple: bool) -> bool {
+    if content.is_empty() || content[0].is(LeafKind::Keyword, "yield") || content.iter().any( |l| l.kind == LeafKind::StandaloneComment ) {
+        return false
+    }
+    let depths = bracket_depths(content);
+    !content.iter().zip(depths).any( |( l, d )| d == 0 && ( ( l.kind == LeafKind::Comma && !tuple )
+        || l.is(LeafKind::Keyword, "for") || l.is(LeafKind::Operator, ":=") ) )
+}
+
+/// Bracket being inside while deciding spaces.
+struct Context {
+    bracket: char,
+    trailer: bool,
+    params: bool,
+    annotated: bool,
+    lambdas: usize
+}
+
+fn set_spaces(leaves: &mut [Leaf]) {
+    let n = leaves.len();
+    let matching = matching_brackets(leaves);
+    let mut kwarg = vec![ false; n ];
+    let mut slice = vec![ None; n ];
+    let mut hug = vec![ false; n ];
+    let mut stack = vec![ Context { bracket: ' ', trailer: false, params: false, annotated: false, lambdas: 0 } ];
+    for i in 0 .. n {
+        let leaf = &leaves[i];
+        if leaf.invisible {
+            continue
+        }
+        let ctx = stack.last_mut().unwrap();
+        match leaf.kind {
+            LeafKind::Operator if leaf.text == "=" => kwarg[i] = ctx.lambdas > 0 || ( ctx.bracket == '(' && !( ctx.params && ctx.annotated ) ),
+            LeafKind::Operator if leaf.text == "**" && !leaf.unary => hug[i] = simple_power_operands(leaves, i),
+            LeafKind::Colon if ctx.lambdas > 0 => ctx.lambdas -= 1,
+            LeafKind::Colon if ctx.params => ctx.annotated = true,
+            LeafKind::Colon if ctx.bracket == '[' && ctx.trailer => slice[i] = Some( complex_subscript(leaves, &matching, i) ),
+            LeafKind::Comma => ctx.annotated = false,
+            LeafKind::Keyword if leaf.text == "lambda" => ctx.lambdas += 1,
+            LeafKind::Open => {
+                let params = i >= 2 && leaves[i - 2].is(LeafKind::Keyword, "def");
+                stack.push( Context { bracket: leaf.text.chars().next().unwrap(), trailer: leaf.trailer, params, annotated: false, lambdas: 0 } )
+            },
+            LeafKind::Close if stack.len() > 1 => { stack.pop(); },
+            _ => { }
+        }
+    }
+    let mut prev: Option<usize> = None;
+    for i in 0 .. n {
+        if leaves[i].invisible {
+            leaves[i].space = false;
+            continue
+        }
+        let space = match prev {
+            None => false,
+            Some( p ) => {
+                let ( p, leaf ) = ( &leaves[p], &leaves[i] );
+                let p_index = prev.unwrap();
+                if matches!(leaf.kind, LeafKind::Comma | LeafKind::Close) || p.kind == LeafKind::Open || p.unary
+                    || kwarg[i] || kwarg[p_index] || hug[i] || hug[p_index] {
+                    false
+                } else if leaf.kind == LeafKind::Colon {
+                    match slice[i] {
+                        Some( true ) => p.kind != LeafKind::Colon,
+                        Some( false ) => p.kind == LeafKind::Comma,
+                        None => false
+                    }
+                } else if let Some( complex ) = slice[p_index] {
+                    complex
+                } else if leaf.kind == LeafKind::Dot {
+                    p.kind == LeafKind::Keyword || ( p.kind == LeafKind::Number && p.text.chars().all( |c| c.is_ascii_digit() || c == '_' ) )
+                } else if p.kind == LeafKind::Dot {
+                    leaf.kind == LeafKind::Keyword
+                } else if leaf.kind == LeafKind::Open {
+                    !leaf.trailer
+                } else {
+                    true
+                }
+            }
+        };
+        leaves[i].space = space;
+        prev = Some( i )
+    }
+}
+
+/// Whether both operands of a power operator are names, numbers or attribute chains of names.
+fn simple_power_operands(leaves: &[Leaf], i: usize) -> bool {
+    let operand = |l: &Leaf| matches!(l.kind, LeafKind::Name | LeafKind::Number);
+    let mut j = i - 1;
+    if !operand(&leaves[j]) {
+        return false
+    }
+    while j >= 2 && leaves[j - 1].kind == LeafKind::Dot && leaves[j - 2].kind == LeafKind::Name {
+        j -= 2
+    }
+    if j >= 1 && leaves[j - 1].kind == LeafKind::Dot {
+        return false
+    }
+    let mut k = i + 1;
+    while k < leaves.len() && leaves[k].unary && matches!(leaves[k].text.as_str(), "-" | "+" | "~") {
+        k += 1
+    }
+    if k >= leaves.len() || !operand(&leaves[k]) {
+        return false
+    }
+    while k + 2 < leaves.len() && leaves[k + 1].kind == LeafKind::Dot && leaves[k + 2].kind == LeafKind::Name {
+        k += 2
+    }
+    !leaves.get(k + 1).is_some_and( |l| matches!(l.kind, LeafKind::Open | LeafKind::Dot) || l.is(LeafKind::Operator, "**") )
+}
+
+/// Whether the element of a subscription holding the colon at 'i' has non trivial expressions.
+fn complex_subscript(leaves: &[Leaf], matching: &[Option<usize>], i: usize) -> bool {
+    let mut start = i;
+    let mut depth = 0;
+    while start > 0 {
+        match leaves[start - 1].kind {
+            LeafKind::Close => depth += 1,
+            LeafKind::Open if depth == 0 => break,
+            LeafKind::Open => depth -= 1,
+            LeafKind::Comma if depth == 0 => break,
+            _ => { }
+        }
+        start -= 1
+    }
+    let close = matching[start - 1].unwrap_or(leaves.len());
+    let mut end = i;
+    depth = 0;
+    while end < close {
+        match leaves[end].kind {
+            LeafKind::Open => depth += 1,
+            LeafKind::Close => depth -= 1,
+            LeafKind::Comma if depth == 0 => break,
+            _ => { }
+        }
+        end += 1
+    }
+    leaves[start .. end].iter().any( |l| match l.kind {
+        LeafKind::Operator => !matches!(l.text.as_str(), "-" | "+" | "~") || !l.unary,
+        LeafKind::Keyword => matches!(l.text.as_str(), "and" | "or" | "not" | "in" | "is" | "if" | "else" | "lambda" | "await"),
+        LeafKind::Dot => true,
+        LeafKind::Open => l.trailer,
+        _ => false
+    })
+}
+
+
+// UnitTests for logical lines ////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::format::lines::{ normalize_string, normalize_number, normalize_comment };
+
+
+    #[test]
+    fn strings_prefer_double_quotes() {
+        assert_eq!(normalize_string("'abc'"), "\"abc\"");
+        assert_eq!(normalize_string("'a\"b'"), "'a\"b'");
+        assert_eq!(normalize_string("'a\\'b'"), "\"a'b\"");
+        assert_eq!(normalize_string("\"a\\\"b\""), "'a\"b'");
+        assert_eq!(normalize_string("F'{x}'"), "f\"{x}\"");
+        assert_eq!(normalize_string("f'{x[\"a\"]}'"), "f'{x[\"a\"]}'");
+        assert_eq!(normalize_string("R'\\d'"), "R\"\\d\"");
+        assert_eq!(normalize_string("'''doc'''"), "\"\"\"doc\"\"\"");
+        assert_eq!(normalize_string("B''"), "b\"\"");
+    }
+
+    #[test]
+    fn numbers_are_normalized() {
+        assert_eq!(normalize_number("0XABCDEF"), "0xABCDEF");
+        assert_eq!(normalize_number("0O17"), "0o17");
+        assert_eq!(normalize_number("1E+5"), "1e5");
+        assert_eq!(normalize_number("1.E-5"), "1.0e-5");
+        assert_eq!(normalize_number(".5"), "0.5");
+        assert_eq!(normalize_number("1_000"), "1_000");
+        assert_eq!(normalize_number("2.J"), "2.0j");
+    }
+
+    #[test]
+    fn comments_get_a_space() {
+        assert_eq!(normalize_comment("#x  "), "# x");
+        assert_eq!(normalize_comment("#!shebang"), "#!shebang");
+        assert_eq!(normalize_comment("#: marker"), "#: marker");
+        assert_eq!(normalize_comment("#"), "#");
+    }
+}
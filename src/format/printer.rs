@@ -0,0 +1,288 @@
+use crate::api::{ Mode, check, parse, normalize_source };
+use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+use crate::ast::dump::Dumper;
+use crate::ast::nodes::Mod;
+use crate::diagnostics::Diagnostic;
+use crate::format::lines::{ Line, logical_lines, finish_line };
+use crate::format::split::{ render, transform_line };
+
+
+/// Options of the formatter, lines longer than 'line_length' are split where brackets allow it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub line_length: usize
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions { line_length: 88 }
+    }
+}
+
+/// Formats a module in the style of Black: indentation of four spaces, normalized spaces around
+/// operators, strings with double quotes, and lines split at brackets to fit in the line length.
+/// Comments are kept. Source with syntax errors is rejected with the first error, and the result is
+/// checked to give the same abstract tree as source.
+pub fn format_source(source: &str, options: &FormatOptions) -> Result<String, Diagnostic> {
+    let source = normalize_source(source);
+    if let Some( error ) = check(&source, Mode::Exec).into_iter().find( |d| d.is_error() ) {
+        return Err( error )
+    }
+    let res = parse(&source, Mode::Exec);
+    let mut items = logical_lines(&source, &res.tokens);
+    for ( line, _ ) in items.iter_mut() {
+        finish_line(line)
+    }
+    let blank_lines = empty_lines(&items);
+    let mut out = String::new();
+    for ( ( line, _ ), before ) in items.into_iter().zip(blank_lines) {
+        for _ in 0 .. before {
+            out.push('\n')
+        }
+        let mut lines = Vec::new();
+        transform_line(line, options.line_length, false, &mut lines);
+        for l in lines {
+            out.push_str(&render(&l));
+            out.push('\n')
+        }
+    }
+    match same_tree(&source, &out) {
+        true => Ok( out ),
+        _ => Err( Diagnostic::error(0, 0, "INTERNAL ERROR: formatted source is not equivalent to the original") )
+    }
+}
+
+/// Whether both sources lower to the same abstract tree. Lines of type ignore comments may move.
+pub fn same_tree(a: &str, b: &str) -> bool {
+    let dump = |source: &str| parse(source, Mode::Exec).tree.and_then( |t| PythonCoreLowering::new(source).lower_mod(&t).ok() )
+        .map( |mut m| {
+            if let Mod::Module { type_ignores, .. } = &mut m {
+                type_ignores.iter_mut().for_each( |t| t.lineno = 0 )
+            }
+            m.dump(false)
+        });
+    match ( dump(a), dump(b) ) {
+        ( Some( x ), Some( y ) ) => x == y,
+        _ => false
+    }
+}
+
+/// Empty lines in front of every logical line, from the empty lines in source limited to two at
+/// module level and one in blocks. Definitions get two empty lines around them at module level
+/// and one in blocks, except right after a decorator or the header of a block.
+pub fn empty_lines(items: &[( Line, usize )]) -> Vec<usize> {
+    let mut res: Vec<usize> = Vec::new();
+    let mut previous_defs: Vec<usize> = Vec::new();
+    let mut prev: Option<&Line> = None;
+    // Comment block in front of the current line, with the line before the block.
+    let mut leading_comment: Option<( usize, Option<&Line> )> = None;
+    for ( i, ( line, user ) ) in items.iter().enumerate() {
+        let depth = line.depth;
+        let mut before = match prev {
+            None => 0,
+            _ => ( *user ).min(if depth == 0 { 2 } else { 1 })
+        };
+        while previous_defs.last().is_some_and( |d| *d >= depth ) {
+            let def_depth = previous_defs.pop().unwrap();
+            before = match depth {
+                0 if def_depth > 0 && line.opens_block() && !matches!(line.leaves[0].text.as_str(), "with" | "try" | "for" | "while" | "if") => 1,
+                0 => 2,
+                _ => 1
+            }
+        }
+        let value = if line.is_decorator() || line.is_def() || line.is_class() {
+            if !line.is_decorator() {
+                previous_defs.push(depth)
+            }
+            match prev {
+                None => 0,
+                Some( p ) if p.is_decorator() => 0,
+                Some( p ) if p.depth < depth && ( p.is_class() || p.is_def() ) => 0,
+                Some( p ) if p.is_comment() && p.depth == depth && before == 0 => {
+                    if let Some( ( c, Some( q ) ) ) = leading_comment {
+                        if !q.is_class() && !q.opens_block() && res[c] <= 1 {
+                            res[c] = res[c].max(if depth > 0 { 1 } else { 2 })
+                        }
+                    }
+                    0
+                },
+                _ if depth > 0 => 1,
+                _ => 2
+            }
+        } else {
+            match prev {
+                Some( p ) if p.is_decorator() || p.opens_block() => 0,
+                Some( p ) if p.is_import() && !line.is_import() && p.depth == depth => before.max(1),
+                _ => before
+            }
+        };
+        if line.is_comment() {
+            if prev.is_none() || ( !prev.unwrap().is_decorator() && ( leading_comment.is_none() || value > 0 ) ) {
+                leading_comment = Some( ( i, prev ) )
+            }
+        } else if !line.is_decorator() {
+            leading_comment = None
+        }
+        res.push(value);
+        prev = Some( line )
+    }
+    res
+}
+
+
+// UnitTests for formatter ////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::path::{ Path, PathBuf };
+    use crate::api::{ Mode, check };
+    use crate::format::printer::{ FormatOptions, format_source, same_tree };
+
+
+    fn format(source: &str) -> String {
+        format_source(source, &FormatOptions::default()).unwrap()
+    }
+
+    fn collect(dir: &Path, res: &mut Vec<PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                collect(&path, res)
+            } else if path.extension().is_some_and( |e| e == "py" ) {
+                res.push(path)
+            }
+        }
+    }
+
+    const SAMPLE: &str = concat!(
+        "#!/usr/bin/env python\n",
+        "import os,sys\n",
+        "from collections import (OrderedDict)\n",
+        "x=[1,2 ,3]\n",
+        "def f(a,b=1,*args,c:int=2,**kwargs)->int :\n",
+        "  '''Doc.'''\n",
+        "  return (a+b)\n",
+        "class A(  ):\n",
+        "    #comment\n",
+        "    def m(self): return self.x [1:2], self.y[a+1 :], -x**2\n",
+        "    @property\n",
+        "\n",
+        "    def p(self):\n",
+        "        if x: pass\n",
+        "        elif (y): x = lambda a=1: a ; z = not y\n",
+        "        # trailing\n",
+        "y = {'a': 1, **b}  # done\n",
+        "print(* args, ** kwargs, sep = '')\n",
+        "for (i, j) in ((1, 2),):\n    del (i), j\n",
+        "try:\n    pass\nexcept (ValueError) as e:\n    raise\nfinally:\n    x @= 0XFF + 1E5 + .5j\n",
+        "while (a if b else c): break\n",
+        "with open('f') as f, open(\"g\") as g: print(f'{f!r}', r'\\d', b'\\'')\n",
+        "assert (x), ('message')\n",
+        "async def g():\n    async for x in y:\n        await z\n    return [i async for i in aiter() if i]\n",
+        "very_long_function_name(first_argument_value, second_argument_value, third_argument_value)\n",
+        "value = some.object.method(argument).other_method(another_argument_here).final_method_call()\n",
+    );
+
+    #[test]
+    fn sample_is_formatted() {
+        assert_eq!(format(SAMPLE), concat!(
+            "#!/usr/bin/env python\n",
+            "import os, sys\n",
+            "from collections import OrderedDict\n",
+            "\n",
+            "x = [1, 2, 3]\n",
+            "\n",
+            "\n",
+            "def f(a, b=1, *args, c: int = 2, **kwargs) -> int:\n",
+            "    \"\"\"Doc.\"\"\"\n",
+            "    return a + b\n",
+            "\n",
+            "\n",
+            "class A:\n",
+            "    # comment\n",
+            "    def m(self):\n",
+            "        return self.x[1:2], self.y[a + 1 :], -x**2\n",
+            "\n",
+            "    @property\n",
+            "    def p(self):\n",
+            "        if x:\n",
+            "            pass\n",
+            "        elif y:\n",
+            "            x = lambda a=1: a\n",
+            "            z = not y\n",
+            "        # trailing\n",
+            "\n",
+            "\n",
+            "y = {\"a\": 1, **b}  # done\n",
+            "print(*args, **kwargs, sep=\"\")\n",
+            "for (i, j) in ((1, 2),):\n",
+            "    del (i), j\n",
+            "try:\n",
+            "    pass\n",
+            "except ValueError as e:\n",
+            "    raise\n",
+            "finally:\n",
+            "    x @= 0xFF + 1e5 + 0.5j\n",
+            "while a if b else c:\n",
+            "    break\n",
+            "with open(\"f\") as f, open(\"g\") as g:\n",
+            "    print(f\"{f!r}\", r\"\\d\", b\"'\")\n",
+            "assert x, \"message\"\n",
+            "\n",
+            "\n",
+            "async def g():\n",
+            "    async for x in y:\n",
+            "        await z\n",
+            "    return [i async for i in aiter() if i]\n",
+            "\n",
+            "\n",
+            "very_long_function_name(\n",
+            "    first_argument_value, second_argument_value, third_argument_value\n",
+            ")\n",
+            "value = (\n",
+            "    some.object.method(argument).other_method(another_argument_here).final_method_call()\n",
+            ")\n",
+        ));
+    }
+
+    #[test]
+    fn formatting_is_idempotent_and_keeps_tree() {
+        let mut paths = Vec::new();
+        collect(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests"), &mut paths);
+        paths.sort();
+        let mut sources: Vec<( String, String )> = paths.iter()
+            .map( |p| ( p.display().to_string(), std::fs::read_to_string(p).unwrap() ) ).collect();
+        sources.push( ( "sample".to_string(), SAMPLE.to_string() ) );
+        for width in [ 88, 40, 20 ] {
+            for ( name, source ) in &sources {
+                let options = FormatOptions { line_length: width };
+                let Ok( once ) = format_source(source, &options) else {
+                    assert!(check(source, Mode::Exec).iter().any( |d| d.is_error() ), "{} does not format", name);
+                    continue
+                };
+                assert!(same_tree(source, &once), "{} changes meaning at width {}", name, width);
+                let twice = format_source(&once, &options).unwrap();
+                assert_eq!(once, twice, "{} is not stable at width {}", name, width)
+            }
+        }
+    }
+
+    #[test]
+    fn empty_lines_around_definitions() {
+        assert_eq!(format("import a\nx = 1\n\n\n\n\ny = 2\ndef f():\n\n    pass\nclass C: pass\n"),
+                   "import a\n\nx = 1\n\n\ny = 2\n\n\ndef f():\n    pass\n\n\nclass C:\n    pass\n");
+        assert_eq!(format("x = 1\n# about f\ndef f(): pass\n"), "x = 1\n\n\n# about f\ndef f():\n    pass\n");
+    }
+
+    #[test]
+    fn comments_are_kept() {
+        assert_eq!(format("if x:  #why\n    y = 1 # set\n    # end of block\n# module\nz = 2\n"),
+                   "if x:  # why\n    y = 1  # set\n    # end of block\n# module\nz = 2\n");
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        let error = format_source("x = (\n", &FormatOptions::default()).unwrap_err();
+        assert!(error.is_error())
+    }
+}
@@ -0,0 +1,132 @@
+use crate::api::{ Mode, check, parse };
+use crate::diagnostics::Diagnostic;
+use crate::edits::{ TextEdit, apply_edits, diff_edits };
+use crate::format::lines::{ Line, logical_lines, finish_line };
+use crate::format::split::{ render, transform_line };
+use crate::format::printer::{ FormatOptions, empty_lines, same_tree };
+
+
+/// Formats the statements and comments of source on the lines of the characters 'start' to 'end'
+/// and returns the edits that change them, for the range formatting of editors. A statement over
+/// several lines is formatted as a whole. Code and empty lines around the statements stay as they
+/// are, and formatted statements keep the indentation of their block. Source must use '\n' line
+/// endings, positions are character offsets.
+pub fn format_range(source: &str, start: u32, end: u32, options: &FormatOptions) -> Result<Vec<TextEdit>, Diagnostic> {
+    if let Some( error ) = check(source, Mode::Exec).into_iter().find( |d| d.is_error() ) {
+        return Err( error )
+    }
+    let chars: Vec<char> = source.chars().collect();
+    let mut line_starts = vec![ 0u32 ];
+    line_starts.extend( chars.iter().enumerate().filter( |( _, c )| **c == '\n' ).map( |( i, _ )| i as u32 + 1 ) );
+    let line_of = |pos: u32| line_starts.partition_point( |s| *s <= pos ) - 1;
+
+    let res = parse(source, Mode::Exec);
+    let mut items = logical_lines(source, &res.tokens);
+    for ( line, _ ) in items.iter_mut() {
+        finish_line(line)
+    }
+    let spans: Vec<( usize, usize )> = items.iter()
+        .map( |( l, _ )| ( line_of(l.start), line_of(l.end.saturating_sub(1).max(l.start)) ) ).collect();
+    let ( mut first, mut last ) = ( line_of(start.min(end)), line_of(end.max(start).saturating_sub(1).max(start.min(end))) );
+    // Statements over several lines widen the range, until no more statements intersect it.
+    let selected: Vec<usize> = loop {
+        let selected: Vec<usize> = ( 0 .. items.len() ).filter( |i| spans[*i].0 <= last && spans[*i].1 >= first ).collect();
+        let lines = selected.iter().fold( ( first, last ), |( f, l ), i| ( f.min(spans[*i].0), l.max(spans[*i].1) ) );
+        if lines == ( first, last ) {
+            break selected
+        }
+        ( first, last ) = lines
+    };
+    if selected.is_empty() {
+        return Ok( Vec::new() )
+    }
+
+    let blank_lines = empty_lines(&items);
+    let mut text = String::new();
+    for ( n, i ) in selected.iter().enumerate() {
+        if n > 0 {
+            text.push_str(&"\n".repeat(blank_lines[*i]))
+        }
+        let ( line, _ ) = &items[*i];
+        let mut lines = Vec::new();
+        transform_line(line.clone(), options.line_length, false, &mut lines);
+        for l in lines {
+            text.push_str(&reindent(line, &l));
+            text.push('\n')
+        }
+    }
+    let region_start = line_starts[first];
+    let region_end = line_starts.get(last + 1).copied().unwrap_or(chars.len() as u32);
+    let old: String = chars[region_start as usize .. region_end as usize].iter().collect();
+    if !old.ends_with('\n') {
+        text.pop();
+    }
+    let edits = diff_edits(&old, &text, region_start);
+    match same_tree(source, &apply_edits(source, &edits)) {
+        true => Ok( edits ),
+        _ => Err( Diagnostic::error(0, 0, "INTERNAL ERROR: formatted source is not equivalent to the original") )
+    }
+}
+
+/// Formats the statement ending in front of 'position', for formatting on type after a newline or
+/// a colon was typed.
+pub fn format_on_type(source: &str, position: u32, options: &FormatOptions) -> Result<Vec<TextEdit>, Diagnostic> {
+    let before = source.chars().take(position as usize).collect::<String>();
+    let trimmed = before.trim_end().chars().count() as u32;
+    format_range(source, trimmed.saturating_sub(1), trimmed, options)
+}
+
+/// Text of a part of statement, with the indentation of the block of the statement in source.
+fn reindent(statement: &Line, part: &Line) -> String {
+    let text = render(part);
+    format!("{}{}", statement.indent, &text[4 * statement.depth ..])
+}
+
+
+// UnitTests for range formatting /////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::edits::apply_edits;
+    use crate::format::printer::FormatOptions;
+    use crate::format::range::{ format_range, format_on_type };
+
+
+    fn format(source: &str, start: u32, end: u32) -> String {
+        apply_edits(source, &format_range(source, start, end, &FormatOptions::default()).unwrap())
+    }
+
+    #[test]
+    fn only_selected_statements_change() {
+        let source = "a=1\nb=2\nc=3\n";
+        assert_eq!(format(source, 4, 7), "a=1\nb = 2\nc=3\n");
+        assert_eq!(format(source, 1, 5), "a = 1\nb = 2\nc=3\n");
+        assert_eq!(format(source, 0, 0), "a = 1\nb=2\nc=3\n");
+        let edits = format_range(source, 4, 7, &FormatOptions::default()).unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(( edits[0].start, edits[0].end, edits[0].text.as_str() ), ( 5, 6, " = " ));
+    }
+
+    #[test]
+    fn statements_over_several_lines_are_formatted_whole() {
+        let source = "x = f(a,\n      b)  # keep\ny  =  2\n";
+        assert_eq!(format(source, 12, 13), "x = f(a, b)  # keep\ny  =  2\n");
+    }
+
+    #[test]
+    fn block_indentation_and_trivia_are_kept() {
+        let source = "if x:\n  a=1\n\n\n\n  b=[1,2]\n  # note\n  c=3\n";
+        assert_eq!(format(source, 9, 20), "if x:\n  a = 1\n\n  b = [1, 2]\n  # note\n  c=3\n");
+        assert_eq!(format(source, 0, 5), "if x:\n  a=1\n\n\n\n  b=[1,2]\n  # note\n  c=3\n");
+        let source = "def f(): return  1\n";
+        assert_eq!(format(source, 0, 3), "def f():\n    return 1\n");
+    }
+
+    #[test]
+    fn statement_before_cursor_is_formatted_on_type() {
+        let source = "a=1\nb=2\n\n";
+        let edits = format_on_type(source, 8, &FormatOptions::default()).unwrap();
+        assert_eq!(apply_edits(source, &edits), "a=1\nb = 2\n\n");
+        assert!(format_range("x = (\n", 0, 1, &FormatOptions::default()).is_err());
+    }
+}
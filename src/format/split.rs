@@ -0,0 +1,578 @@
+use std::collections::HashSet;
+use crate::format::lines::{ Leaf, LeafKind, Line, matching_brackets, bracket_depths };
+
+
+/// Priorities of delimiters, a line in brackets is split at all delimiters of highest priority.
+pub const COMPREHENSION_PRIORITY: u8 = 20;
+pub const COMMA_PRIORITY: u8 = 18;
+pub const TERNARY_PRIORITY: u8 = 16;
+pub const LOGIC_PRIORITY: u8 = 14;
+pub const STRING_PRIORITY: u8 = 12;
+pub const COMPARATOR_PRIORITY: u8 = 10;
+pub const DOT_PRIORITY: u8 = 1;
+
+/// Text of a line with indentation of four spaces per level and comments at the end. A comment on
+/// a line of its own in brackets keeps its own line.
+pub fn render(line: &Line) -> String {
+    let indent = "    ".repeat(line.depth);
+    let mut res = indent.clone();
+    let mut first = true;
+    let mut after_comment = false;
+    let mut comments = Vec::new();
+    for leaf in &line.leaves {
+        if !leaf.invisible {
+            if after_comment || ( leaf.kind == LeafKind::StandaloneComment && !first ) {
+                res.push('\n');
+                res.push_str(&indent)
+            } else if !first && leaf.space {
+                res.push(' ')
+            }
+            res.push_str(&leaf.text);
+            after_comment = leaf.kind == LeafKind::StandaloneComment;
+            first = false
+        }
+        comments.extend(leaf.comments.iter())
+    }
+    for comment in comments {
+        res.push_str("  ");
+        res.push_str(comment)
+    }
+    res
+}
+
+/// Line fits in width on one line of output.
+pub fn is_short(line: &Line, width: usize) -> bool {
+    let text = render(line);
+    !text.contains('\n') && text.chars().count() <= width
+}
+
+/// Splits a line that does not fit in width, or holds a magic trailing comma, at its brackets and
+/// delimiters. The parts are split again until they fit or cannot be split.
+pub fn transform_line(line: Line, width: usize, force: bool, out: &mut Vec<Line>) {
+    let text = render(&line);
+    let forced = line.should_split || has_magic_trailing_comma(&line) || line.has_standalone_comments();
+    if line.is_comment() || ( !forced && !text.contains('\n') && text.chars().count() <= width ) {
+        out.push(line);
+        return
+    }
+    let transforms: &[Transform] = match ( line.is_def(), line.inside_brackets ) {
+        ( true, _ ) => &[ Transform::LeftHand ],
+        ( _, true ) => &[ Transform::Delimiter, Transform::RightHand ],
+        _ => &[ Transform::RightHand ]
+    };
+    for transform in transforms {
+        let lines = match transform {
+            Transform::LeftHand => left_hand_split(&line),
+            Transform::Delimiter => delimiter_split(&line),
+            Transform::RightHand => rhs(&line, width, force)
+        };
+        let Some( lines ) = lines else { continue };
+        if lines.iter().any( |l| render(l) == text ) {
+            continue
+        }
+        let mut result = Vec::new();
+        for l in lines {
+            transform_line(l, width, force, &mut result)
+        }
+        if *transform == Transform::RightHand && !force && !is_short(&result[0], width) && line.leaves.iter().any( |l| l.invisible )
+            && !line.leaves.iter().any( |l| l.kind == LeafKind::String && l.text.contains('\n') ) {
+            if let Some( second ) = rhs(&line, width, true) {
+                let mut opinion = Vec::new();
+                for l in second {
+                    transform_line(l, width, true, &mut opinion)
+                }
+                if opinion.iter().all( |l| is_short(l, width) ) {
+                    result = opinion
+                }
+            }
+        }
+        out.extend(result);
+        return
+    }
+    out.push(line)
+}
+
+#[derive(PartialEq)]
+enum Transform {
+    LeftHand,
+    Delimiter,
+    RightHand
+}
+
+
+// Delimiters /////////////////////////////////////////////////////////////////////////////////////
+
+/// Priority of the delimiter after every leaf at depth zero of the line, zero when the line cannot
+/// be split after the leaf. The leaf before an operator carries the priority of the operator.
+pub fn delimiters(leaves: &[Leaf], import: bool) -> Vec<u8> {
+    let mut res = vec![ 0; leaves.len() ];
+    let mut depth = 0usize;
+    let mut for_depths = Vec::new();
+    let mut lambda_depths = Vec::new();
+    let mut comprehension = false;
+    for i in 0 .. leaves.len() {
+        let leaf = &leaves[i];
+        if leaf.kind == LeafKind::Close {
+            depth = depth.saturating_sub(1)
+        }
+        let mut ends_for = false;
+        if leaf.is(LeafKind::Keyword, "in") && for_depths.last() == Some( &depth ) {
+            for_depths.pop();
+            depth -= 1;
+            ends_for = true
+        }
+        if leaf.kind == LeafKind::Colon && lambda_depths.last() == Some( &depth ) {
+            lambda_depths.pop();
+            depth -= 1
+        }
+        if depth == 0 {
+            if i > 0 {
+                let priority = split_before_priority(leaves, i, ends_for, comprehension, import);
+                res[i - 1] = res[i - 1].max(priority)
+            }
+            if leaf.kind == LeafKind::Comma {
+                res[i] = res[i].max(COMMA_PRIORITY)
+            }
+            if leaf.is(LeafKind::Keyword, "for") {
+                comprehension = true
+            }
+        }
+        match leaf.kind {
+            LeafKind::Open => depth += 1,
+            LeafKind::Keyword if leaf.text == "for" => {
+                depth += 1;
+                for_depths.push(depth)
+            },
+            LeafKind::Keyword if leaf.text == "lambda" => {
+                depth += 1;
+                lambda_depths.push(depth)
+            },
+            _ => { }
+        }
+    }
+    res
+}
+
+fn split_before_priority(leaves: &[Leaf], i: usize, ends_for: bool, comprehension: bool, import: bool) -> u8 {
+    let ( leaf, prev ) = ( &leaves[i], &leaves[i - 1] );
+    let next = leaves.get(i + 1).map( |l| l.text.as_str() ).unwrap_or("");
+    if leaf.unary {
+        return 0
+    }
+    match leaf.kind {
+        LeafKind::Dot if !import && prev.kind == LeafKind::Close => DOT_PRIORITY,
+        LeafKind::Operator => match leaf.text.as_str() {
+            "|" => 9,
+            "^" => 8,
+            "&" => 7,
+            "<<" | ">>" => 6,
+            "+" | "-" => 5,
+            "*" | "/" | "//" | "%" | "@" => 4,
+            "**" => 1,
+            "<" | ">" | "==" | "!=" | "<=" | ">=" => COMPARATOR_PRIORITY,
+            _ => 0
+        },
+        LeafKind::Keyword => match leaf.text.as_str() {
+            "and" | "or" => LOGIC_PRIORITY,
+            "not" if next == "in" => COMPARATOR_PRIORITY,
+            "is" => COMPARATOR_PRIORITY,
+            "in" if !ends_for && prev.text != "not" => COMPARATOR_PRIORITY,
+            "if" if comprehension => COMPREHENSION_PRIORITY,
+            "if" | "else" => TERNARY_PRIORITY,
+            "for" if prev.text != "async" => COMPREHENSION_PRIORITY,
+            "async" if next == "for" => COMPREHENSION_PRIORITY,
+            _ => 0
+        },
+        LeafKind::String if prev.kind == LeafKind::String => STRING_PRIORITY,
+        _ => 0
+    }
+}
+
+/// Splits a line in brackets at its delimiters of highest priority, and around comments on lines
+/// of their own. A trailing comma is added when splitting at commas.
+pub fn delimiter_split(line: &Line) -> Option<Vec<Line>> {
+    let leaves = &line.leaves;
+    let n = leaves.len();
+    let priorities = delimiters(leaves, line.import);
+    let mut max = priorities[.. n - 1].iter().copied().max().unwrap_or(0);
+    if max == DOT_PRIORITY && priorities.iter().filter( |p| **p == DOT_PRIORITY ).count() == 1 {
+        max = 0
+    }
+    if max == 0 && !line.has_standalone_comments() {
+        return None
+    }
+    let mut res = Vec::new();
+    let mut current = Line::new(line.depth, true, line.import);
+    let mut depth = 0usize;
+    for ( i, leaf ) in leaves.iter().enumerate() {
+        if leaf.kind == LeafKind::StandaloneComment && depth == 0 {
+            if !current.leaves.is_empty() {
+                res.push(std::mem::replace(&mut current, Line::new(line.depth, true, line.import)))
+            }
+            current.leaves.push(leaf.clone());
+            res.push(std::mem::replace(&mut current, Line::new(line.depth, true, line.import)));
+            continue
+        }
+        match leaf.kind {
+            LeafKind::Open => depth += 1,
+            LeafKind::Close => depth = depth.saturating_sub(1),
+            _ => { }
+        }
+        current.leaves.push(leaf.clone());
+        if max > 0 && priorities[i] == max && depth == 0 {
+            res.push(std::mem::replace(&mut current, Line::new(line.depth, true, line.import)))
+        }
+    }
+    if !current.leaves.is_empty() {
+        let last = &leaves[n - 1];
+        if max == COMMA_PRIORITY && last.kind != LeafKind::Comma && last.kind != LeafKind::StandaloneComment {
+            current.leaves.push(Leaf::new(LeafKind::Comma, ","))
+        }
+        res.push(current)
+    }
+    for l in res.iter_mut() {
+        if let Some( first ) = l.leaves.first_mut() {
+            first.space = false
+        }
+    }
+    Some( res )
+}
+
+
+// Splits at brackets /////////////////////////////////////////////////////////////////////////////
+
+/// Closing bracket after a comma that asks for one element per line. One element tuples and
+/// subscriptions keep their comma on one line.
+pub fn has_magic_trailing_comma(line: &Line) -> bool {
+    let leaves = &line.leaves;
+    let matching = matching_brackets(leaves);
+    ( 1 .. leaves.len() ).any( |i| {
+        let leaf = &leaves[i];
+        if leaf.kind != LeafKind::Close || leaves[i - 1].kind != LeafKind::Comma {
+            return false
+        }
+        let Some( open ) = matching[i] else { return false };
+        let commas = count_commas(&leaves[open + 1 .. i]);
+        match leaf.text.as_str() {
+            ")" => line.import || leaves[open].trailer || commas > 1,
+            "]" => !leaves[open].trailer || commas > 1,
+            _ => true
+        }
+    })
+}
+
+fn count_commas(leaves: &[Leaf]) -> usize {
+    let mut depth = 0usize;
+    leaves.iter().filter( |l| {
+        match l.kind {
+            LeafKind::Open => depth += 1,
+            LeafKind::Close => depth = depth.saturating_sub(1),
+            _ => { }
+        }
+        depth == 0 && l.kind == LeafKind::Comma
+    }).count()
+}
+
+/// Builds head, body and tail of a split at the brackets 'open' and 'close'. Invisible brackets
+/// become visible.
+fn bracket_split(line: &Line, open: usize, close: usize) -> Vec<Line> {
+    let leaves = &line.leaves;
+    let mut head = Line::new(line.depth, false, line.import);
+    let mut body = Line::new(line.depth + 1, true, line.import);
+    let mut tail = Line::new(line.depth, false, line.import);
+    head.leaves = leaves[..= open].to_vec();
+    body.leaves = leaves[open + 1 .. close].to_vec();
+    tail.leaves = leaves[close ..].to_vec();
+    if head.leaves[open].invisible {
+        head.leaves[open].invisible = false;
+        head.leaves[open].space = body.leaves.first().is_some_and( |l| l.space ) || open > 0 && !head.leaves[open - 1].invisible
+    }
+    if let Some( first ) = body.leaves.first_mut() {
+        first.space = false
+    }
+    tail.leaves[0].invisible = false;
+    tail.leaves[0].space = false;
+    let def_params = open >= 2 && leaves[open - 2].is(LeafKind::Keyword, "def") && leaves[open].text == "(";
+    if ( line.import && leaves[open].text == "(" ) || ( def_params && count_commas(&body.leaves) == 0 ) {
+        if let Some( i ) = body.leaves.iter().rposition( |l| l.kind != LeafKind::StandaloneComment ) {
+            if body.leaves[i].kind != LeafKind::Comma {
+                body.leaves.insert(i + 1, Leaf::new(LeafKind::Comma, ","))
+            }
+        }
+    }
+    if !body.leaves.is_empty() {
+        let priorities = delimiters(&body.leaves, body.import);
+        let max = priorities[.. body.leaves.len() - 1].iter().copied().max().unwrap_or(0);
+        let trailing_comma = body.leaves.last().is_some_and( |l| l.kind == LeafKind::Comma );
+        body.should_split = max == COMMA_PRIORITY && ( trailing_comma || !leaves[open].trailer || leaves[open].text == "{" )
+    }
+    let mut res = vec![ head ];
+    if !body.leaves.is_empty() {
+        res.push(body)
+    }
+    res.push(tail);
+    res
+}
+
+/// Splits at the first opening bracket with a non empty body, used for function definitions.
+fn left_hand_split(line: &Line) -> Option<Vec<Line>> {
+    let matching = matching_brackets(&line.leaves);
+    let mut open = None;
+    for ( i, leaf ) in line.leaves.iter().enumerate() {
+        match open {
+            Some( o ) if matching[i] == Some( o ) && leaf.kind == LeafKind::Close => {
+                if i > o + 1 {
+                    return Some( bracket_split(line, o, i) )
+                }
+                open = None
+            },
+            None if leaf.kind == LeafKind::Open => open = Some( i ),
+            _ => { }
+        }
+    }
+    None
+}
+
+/// Last pair of brackets with a non empty body, skipping the closing brackets in 'omit'.
+fn last_brackets(line: &Line, omit: &HashSet<usize>, matching: &[Option<usize>]) -> Option<( usize, usize )> {
+    let mut body: Option<( usize, usize )> = None;
+    for i in ( 0 .. line.leaves.len() ).rev() {
+        if let Some( ( open, close ) ) = body {
+            if i == open {
+                if close > open + 1 {
+                    return Some( ( open, close ) )
+                }
+                body = None
+            }
+            continue
+        }
+        if line.leaves[i].kind == LeafKind::Close && !omit.contains(&i) {
+            body = matching[i].map( |o| ( o, i ) )
+        }
+    }
+    None
+}
+
+fn right_hand_split(line: &Line, omit: &HashSet<usize>, force: bool, width: usize) -> Option<( Vec<Line>, usize )> {
+    let matching = matching_brackets(&line.leaves);
+    let ( open, close ) = last_brackets(line, omit, &matching)?;
+    let body = &line.leaves[open + 1 .. close];
+    if line.leaves[open].invisible {
+        let comments = body.iter().zip(bracket_depths(body)).any( |( l, d )| d == 0 && l.kind == LeafKind::StandaloneComment );
+        if !force && !line.import && !comments && can_omit_invisible_parens(body, line.depth + 1, width) {
+            let mut inner = omit.clone();
+            inner.insert(close);
+            if let Some( ( lines, inner_open ) ) = right_hand_split(line, &inner, force, width) {
+                if inner_open > open {
+                    return Some( ( lines, inner_open ) )
+                }
+            }
+        }
+        if body.iter().filter( |l| !l.invisible ).count() < 2 {
+            return None
+        }
+    }
+    Some( ( bracket_split(line, open, close), open ) )
+}
+
+/// Right hand split that tries to leave trailers on the first line when that makes the first line
+/// fit.
+fn rhs(line: &Line, width: usize, force: bool) -> Option<Vec<Line>> {
+    for omit in trailers_to_omit(line, width) {
+        if let Some( ( lines, _ ) ) = right_hand_split(line, &omit, force, width) {
+            if is_short(&lines[0], width) {
+                return Some( lines )
+            }
+        }
+    }
+    right_hand_split(line, &HashSet::new(), force, width).map( |( lines, _ )| lines )
+}
+
+fn leaf_length(leaf: &Leaf) -> usize {
+    match leaf.invisible {
+        true => 0,
+        _ => usize::from(leaf.space) + leaf.text.chars().count()
+    }
+}
+
+fn comments_length(leaf: &Leaf) -> usize {
+    leaf.comments.iter().map( |c| 2 + c.chars().count() ).sum()
+}
+
+/// Sets of closing brackets to skip, growing from the end of the line while the skipped trailers
+/// fit in width.
+fn trailers_to_omit(line: &Line, width: usize) -> Vec<HashSet<usize>> {
+    let leaves = &line.leaves;
+    let matching = matching_brackets(leaves);
+    let mut res = Vec::new();
+    let mut omit = HashSet::new();
+    if !has_magic_trailing_comma(line) {
+        res.push(omit.clone())
+    }
+    let mut length = 4 * line.depth;
+    let mut opening: Option<usize> = None;
+    let mut closing: Option<usize> = None;
+    let mut inner = Vec::new();
+    for i in ( 0 .. leaves.len() ).rev() {
+        let leaf = &leaves[i];
+        length += leaf_length(leaf) + comments_length(leaf);
+        if length > width {
+            break
+        }
+        if let Some( o ) = opening {
+            if i == o {
+                opening = None
+            } else if leaf.kind == LeafKind::Close {
+                inner.push(i)
+            }
+        } else if leaf.kind == LeafKind::Close {
+            if i > 0 && leaves[i - 1].kind == LeafKind::Open && matching[i] == Some( i - 1 ) {
+                inner.push(i);
+                continue
+            }
+            if let Some( c ) = closing {
+                omit.insert(c);
+                omit.extend(inner.drain(..));
+                res.push(omit.clone())
+            }
+            if i > 0 && leaves[i - 1].kind == LeafKind::Comma {
+                break
+            }
+            if !leaf.invisible {
+                opening = matching[i];
+                closing = Some( i )
+            }
+        }
+    }
+    res
+}
+
+/// Whether a split inside the expression in invisible parentheses looks better than making the
+/// parentheses visible.
+fn can_omit_invisible_parens(body: &[Leaf], depth: usize, width: usize) -> bool {
+    let leaves: Vec<Leaf> = body.iter().filter( |l| !l.invisible ).cloned().collect();
+    let priorities = delimiters(&leaves, false);
+    let max = priorities.iter().copied().max().unwrap_or(0);
+    if max == 0 {
+        return true
+    }
+    if priorities.iter().filter( |p| **p == max ).count() > 1 {
+        return false
+    }
+    if max == DOT_PRIORITY {
+        return true
+    }
+    if leaves.len() < 2 {
+        return false
+    }
+    let matching = matching_brackets(&leaves);
+    let ( first, second ) = ( &leaves[0], &leaves[1] );
+    if first.kind == LeafKind::Open && second.kind != LeafKind::Close && can_omit_opening_paren(&leaves, &matching, depth, width) {
+        return true
+    }
+    let n = leaves.len();
+    let ( penultimate, last ) = ( &leaves[n - 2], &leaves[n - 1] );
+    let Some( last_open ) = matching[n - 1] else { return false };
+    if last.text == ")" || last.text == "}" || ( last.text == "]" && !leaves[last_open].trailer ) {
+        if penultimate.kind == LeafKind::Open {
+            return false
+        }
+        if first.kind == LeafKind::String && first.text.contains('\n') {
+            return true
+        }
+        let mut length = 4 * depth;
+        let mut seen_other_brackets = false;
+        for ( i, leaf ) in leaves.iter().enumerate() {
+            length += leaf_length(leaf) + comments_length(leaf);
+            if i == last_open {
+                if seen_other_brackets || length <= width {
+                    return true
+                }
+            } else if leaf.kind == LeafKind::Open {
+                seen_other_brackets = true
+            }
+        }
+    }
+    false
+}
+
+/// Whether what follows the brackets at the start of the expression fits, or can be split again.
+fn can_omit_opening_paren(leaves: &[Leaf], matching: &[Option<usize>], depth: usize, width: usize) -> bool {
+    let mut remainder = false;
+    let mut length = 4 * depth;
+    for ( i, leaf ) in leaves.iter().enumerate() {
+        if leaf.kind == LeafKind::Close && matching[i] == Some( 0 ) {
+            remainder = true
+        }
+        if remainder {
+            length += leaf_length(leaf) + comments_length(leaf);
+            if length > width {
+                return false
+            }
+            if leaf.kind == LeafKind::Open {
+                remainder = false
+            }
+        }
+    }
+    true
+}
+
+
+// UnitTests for line splitting ///////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::format::printer::{ FormatOptions, format_source };
+
+
+    fn format(source: &str, width: usize) -> String {
+        format_source(source, &FormatOptions { line_length: width }).unwrap()
+    }
+
+    #[test]
+    fn call_is_split_at_its_brackets() {
+        assert_eq!(format("result = function(argument_one, argument_two)\n", 30),
+                   "result = function(\n    argument_one, argument_two\n)\n");
+    }
+
+    #[test]
+    fn body_is_split_at_commas_when_too_long() {
+        assert_eq!(format("result = function(argument_one, argument_two, argument_three)\n", 30),
+                   "result = function(\n    argument_one,\n    argument_two,\n    argument_three,\n)\n");
+    }
+
+    #[test]
+    fn magic_trailing_comma_explodes_collection() {
+        assert_eq!(format("x = [1, 2,]\n", 88), "x = [\n    1,\n    2,\n]\n");
+        assert_eq!(format("x = (1,)\n", 88), "x = (1,)\n");
+    }
+
+    #[test]
+    fn invisible_parentheses_wrap_right_hand_side() {
+        assert_eq!(format("value = first_operand + second_operand + third_operand\n", 50),
+                   "value = (\n    first_operand + second_operand + third_operand\n)\n");
+        assert_eq!(format("if first_condition and second_condition and third_condition:\n    pass\n", 40),
+                   "if (\n    first_condition\n    and second_condition\n    and third_condition\n):\n    pass\n");
+    }
+
+    #[test]
+    fn definitions_split_at_parameters() {
+        assert_eq!(format("def function(argument_one, argument_two):\n    pass\n", 30),
+                   "def function(\n    argument_one, argument_two\n):\n    pass\n");
+        assert_eq!(format("def function(argument_number_one_is_long):\n    pass\n", 30),
+                   "def function(\n    argument_number_one_is_long,\n):\n    pass\n");
+    }
+
+    #[test]
+    fn import_is_wrapped_in_parentheses() {
+        assert_eq!(format("from module import first_name, second_name\n", 30),
+                   "from module import (\n    first_name,\n    second_name,\n)\n");
+        assert_eq!(format("from module import (name)\n", 88), "from module import name\n");
+    }
+
+    #[test]
+    fn comments_in_brackets_keep_their_lines() {
+        assert_eq!(format("x = [\n    1,  # one\n    # two\n    2\n]\n", 88),
+                   "x = [\n    1,  # one\n    # two\n    2,\n]\n");
+    }
+}
@@ -0,0 +1,354 @@
+use crate::Token;
+use crate::api::tokenize;
+use crate::lint::rules::BUILTINS;
+use crate::lsp::semantic::{ TokenKind, semantic_tokens };
+
+
+/// Category of a highlighted span, named by the CSS class of its HTML span.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Category {
+    Keyword,
+    SoftKeyword,
+    Builtin,
+    String,
+    FStringExpression,
+    Number,
+    Comment,
+    Decorator,
+    Operator
+}
+
+/// Style sheet of standalone HTML output, one rule per category.
+const STYLE: &str = "pre.python { background: #fafafa; color: #24292e; padding: 1em; }
+.keyword { color: #d73a49; font-weight: bold; }
+.soft-keyword { color: #d73a49; font-style: italic; }
+.builtin { color: #005cc5; }
+.string { color: #032f62; }
+.fstring-expression { color: #24292e; background: #f1f8ff; }
+.number { color: #005cc5; }
+.comment { color: #6a737d; font-style: italic; }
+.decorator { color: #6f42c1; }
+.operator { color: #d73a49; }
+";
+
+
+impl Category {
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            Category::Keyword => "keyword",
+            Category::SoftKeyword => "soft-keyword",
+            Category::Builtin => "builtin",
+            Category::String => "string",
+            Category::FStringExpression => "fstring-expression",
+            Category::Number => "number",
+            Category::Comment => "comment",
+            Category::Decorator => "decorator",
+            Category::Operator => "operator"
+        }
+    }
+
+    /// Select Graphic Rendition parameters of the terminal color.
+    pub fn ansi_code(&self) -> &'static str {
+        match self {
+            Category::Keyword => "1;35",
+            Category::SoftKeyword => "3;35",
+            Category::Builtin => "36",
+            Category::String => "32",
+            Category::FStringExpression => "39",
+            Category::Number => "33",
+            Category::Comment => "2;37",
+            Category::Decorator => "34",
+            Category::Operator => "31"
+        }
+    }
+}
+
+/// Spans and categories of source in order and without overlaps, text outside of them is shown as
+/// it is. Tokens after a tokenizer error are left out. Replacement fields of f-strings split their
+/// string in parts, 'match', 'case', 'type' and the wildcard '_' are soft keywords only where the
+/// grammar reads them as such and names of builtins only when they are not attributes.
+pub fn highlight_spans(source: &str) -> Vec<( u32, u32, Category )> {
+    let ( tokens, _ ) = tokenize(source);
+    let text: Vec<char> = source.chars().collect();
+    let soft = soft_keywords(&tokens);
+    let mut previous: Option<&Token> = None;
+    let mut builtins = Vec::new();
+    for token in &tokens {
+        if let Token::AtomName(_, _, _, name) = &**token {
+            let attribute = matches!(previous, Some( Token::PyDot(..) | Token::PyDef(..) | Token::PyClass(..) ));
+            if !attribute && BUILTINS.contains(&name.as_str()) {
+                builtins.extend( token.get_span() )
+            }
+        }
+        if token.get_span().is_some() {
+            previous = Some( token )
+        }
+    }
+    let mut res = Vec::new();
+    for ( start, end, kind ) in semantic_tokens(&tokens) {
+        let end = end.min(text.len() as u32);
+        let category = match kind {
+            _ if soft.contains(&( start, end )) => Category::SoftKeyword,
+            _ if builtins.contains(&( start, end )) => Category::Builtin,
+            TokenKind::Keyword => Category::Keyword,
+            TokenKind::String => {
+                let mut at = start;
+                for ( s, e ) in fstring_expressions(&text[start as usize .. end as usize], start) {
+                    res.push( ( at, s, Category::String ) );
+                    res.push( ( s, e, Category::FStringExpression ) );
+                    at = e
+                }
+                res.push( ( at, end, Category::String ) );
+                continue
+            },
+            TokenKind::Number => Category::Number,
+            TokenKind::Comment => Category::Comment,
+            TokenKind::Operator => Category::Operator,
+            TokenKind::Decorator => Category::Decorator,
+            TokenKind::Variable | TokenKind::Function | TokenKind::Class => continue
+        };
+        res.push( ( start, end, category ) )
+    }
+    res.retain( |( s, e, _ )| s < e );
+    res
+}
+
+/// Spans of the names that start a 'match' statement, a 'case' block or a 'type' alias, and of
+/// the wildcards in 'case' patterns. The token stream is followed the way the parser reads it: a
+/// 'match' starts a statement whose line ends with a colon, a 'case' starts a statement directly in
+/// the block of a 'match', and a 'type' is followed by the name of the alias and '=' or '['.
+pub fn soft_keywords(tokens: &[Box<Token>]) -> Vec<( u32, u32 )> {
+    let mut res = Vec::new();
+    let mut blocks: Vec<bool> = Vec::new();
+    let mut match_header = false;
+    let mut pattern = false;
+    let mut statement_start = true;
+    let mut depth = 0;
+    for ( i, token ) in tokens.iter().enumerate() {
+        match &**token {
+            Token::Indent(..) => {
+                blocks.push(match_header);
+                match_header = false;
+                statement_start = true;
+                continue
+            },
+            Token::Dedent(..) => {
+                blocks.pop();
+                statement_start = true;
+                continue
+            },
+            Token::Newline(..) => {
+                pattern = false;
+                statement_start = true;
+                continue
+            },
+            _ => { }
+        }
+        let line: Vec<&Token> = tokens[i ..].iter().map( |t| &**t ).take_while( |t| !matches!(t, Token::Newline(..) | Token::EOF(..)) ).collect();
+        let span = token.get_span();
+        match &**token {
+            Token::AtomName(_, _, _, name) if statement_start => match ( name.as_str(), line.get(1) ) {
+                ( "match", Some( next ) ) if !matches!(next, Token::PyColon(..)) && matches!(line.last(), Some( Token::PyColon(..) )) => {
+                    res.extend(span);
+                    match_header = true
+                },
+                ( "case", Some( _ ) ) if blocks.last() == Some( &true ) && has_colon(&line) => {
+                    res.extend(span);
+                    pattern = true
+                },
+                ( "type", Some( Token::AtomName(..) ) ) if matches!(line.get(2), Some( Token::PyAssign(..) | Token::PyLeftBracket(..) )) => {
+                    res.extend(span)
+                },
+                _ => { }
+            },
+            Token::AtomName(_, _, _, name) if pattern && name.as_str() == "_" && !matches!(tokens[i - 1].as_ref(), Token::PyDot(..)) => {
+                res.extend(span)
+            },
+            Token::PyLeftParen(..) | Token::PyLeftBracket(..) | Token::PyLeftCurly(..) => depth += 1,
+            Token::PyRightParen(..) | Token::PyRightBracket(..) | Token::PyRightCurly(..) => depth -= 1,
+            Token::PyIf(..) if depth == 0 => pattern = false,
+            _ => { }
+        }
+        statement_start = depth == 0 && matches!(&**token, Token::PySemiColon(..) | Token::PyColon(..));
+        if statement_start {
+            pattern = false
+        }
+    }
+    res
+}
+
+fn has_colon(line: &[&Token]) -> bool {
+    let mut depth = 0;
+    for token in line {
+        match token {
+            Token::PyLeftParen(..) | Token::PyLeftBracket(..) | Token::PyLeftCurly(..) => depth += 1,
+            Token::PyRightParen(..) | Token::PyRightBracket(..) | Token::PyRightCurly(..) => depth -= 1,
+            Token::PyColon(..) if depth == 0 => return true,
+            _ => { }
+        }
+    }
+    false
+}
+
+/// Spans of the expressions in the replacement fields of a string literal with an 'f' prefix,
+/// 'start' is the offset of the literal. Fields nested in format specifications are included.
+pub fn fstring_expressions(literal: &[char], start: u32) -> Vec<( u32, u32 )> {
+    let prefix = literal.iter().take_while( |c| c.is_alphabetic() ).count();
+    if !literal[.. prefix].iter().any( |c| *c == 'f' || *c == 'F' ) || literal.len() < prefix + 2 {
+        return Vec::new()
+    }
+    let quote = match literal[prefix ..].starts_with(&[literal[prefix]; 3]) && literal.len() >= prefix + 6 {
+        true => 3,
+        _ => 1
+    };
+    let body = &literal[prefix + quote .. literal.len() - quote];
+    let mut res = Vec::new();
+    let mut i = 0;
+    while i < body.len() {
+        match ( body[i], body.get(i + 1) ) {
+            ( '{', Some( '{' ) ) | ( '}', Some( '}' ) ) => i += 2,
+            ( '{', _ ) => i = replacement_field(body, i, &mut res),
+            _ => i += 1
+        }
+    }
+    let offset = start + ( prefix + quote ) as u32;
+    res.into_iter().map( |( s, e )| ( offset + s as u32, offset + e as u32 ) ).collect()
+}
+
+/// Adds the expression of the field opened by '{' at index 'open' and of the fields in its format
+/// specification, and returns the index after the closing '}'.
+fn replacement_field(body: &[char], open: usize, res: &mut Vec<( usize, usize )>) -> usize {
+    let mut i = open + 1;
+    let mut depth = 0;
+    while let Some( c ) = body.get(i).copied() {
+        let next = body.get(i + 1).copied();
+        match c {
+            '\'' | '"' => {
+                i += 1;
+                while i < body.len() && body[i] != c { i += 1 }
+            },
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth > 0 => depth -= 1,
+            '=' | '!' | '<' | '>' if next == Some( '=' ) => i += 1,
+            '!' | ':' | '=' | '}' if depth == 0 => break,
+            _ => { }
+        }
+        i += 1
+    }
+    res.push( ( open + 1, i.min(body.len()) ) );
+    if body.get(i) == Some( &'=' ) {
+        i += 1
+    }
+    if body.get(i) == Some( &'!' ) {
+        i += 2
+    }
+    if body.get(i) == Some( &':' ) {
+        i += 1;
+        while let Some( c ) = body.get(i) {
+            match c {
+                '}' => break,
+                '{' => i = replacement_field(body, i, res),
+                _ => i += 1
+            }
+        }
+    }
+    while i < body.len() && body[i] != '}' {
+        i += 1
+    }
+    i + 1
+}
+
+/// Source with ANSI escape sequences around highlighted spans, closed at every line end so that
+/// pagers showing single lines keep the colors right.
+pub fn highlight_ansi(source: &str) -> String {
+    let mut res = String::new();
+    render(source, &mut |text, category| match category {
+        Some( c ) => {
+            let parts: Vec<String> = text.split('\n').map( |l| match l.is_empty() {
+                true => String::new(),
+                _ => format!("\x1b[{}m{}\x1b[0m", c.ansi_code(), l)
+            }).collect();
+            res.push_str(&parts.join("\n"))
+        },
+        None => res.push_str(text)
+    });
+    res
+}
+
+/// Standalone HTML document showing source in a 'pre' element with a 'span' of the class of its
+/// category around every highlighted span.
+pub fn highlight_html(source: &str, title: &str) -> String {
+    let mut code = String::new();
+    render(source, &mut |text, category| match category {
+        Some( c ) => code.push_str(&format!("<span class=\"{}\">{}</span>", c.class_name(), escape_html(text))),
+        None => code.push_str(&escape_html(text))
+    });
+    format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<pre class=\"python\">{}</pre>\n</body>\n</html>\n",
+        escape_html(title), STYLE, code)
+}
+
+/// Calls 'write' for the pieces of source in order, with the category of highlighted pieces.
+fn render(source: &str, write: &mut dyn FnMut(&str, Option<Category>)) {
+    let chars: Vec<char> = source.chars().collect();
+    let piece = |s: u32, e: u32| chars[s as usize .. e as usize].iter().collect::<String>();
+    let mut at = 0;
+    for ( start, end, category ) in highlight_spans(source) {
+        if start > at {
+            write(&piece(at, start), None)
+        }
+        write(&piece(start, end), Some( category ));
+        at = end
+    }
+    write(&piece(at, chars.len() as u32), None)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+
+// UnitTests for highlight /////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::highlight::{ Category, highlight_spans, highlight_ansi, highlight_html };
+
+
+    fn categories(source: &str) -> Vec<String> {
+        highlight_spans(source).iter().map( |( s, e, c )| {
+            format!("{} {}", c.class_name(), source.chars().skip(*s as usize).take(( e - s ) as usize).collect::<String>())
+        }).collect()
+    }
+
+    #[test]
+    fn tokens_and_trivia_are_categorized() {
+        assert_eq!(categories("@cache\ndef f(x=None):  # note\n    return len(x.len) + 1.5 if x else print\n"), vec![
+            "decorator @", "decorator cache", "keyword def", "operator =", "keyword None", "comment # note", "keyword return",
+            "builtin len", "operator +", "number 1.5", "keyword if", "keyword else", "builtin print" ]);
+    }
+
+    #[test]
+    fn fstring_expressions_split_strings() {
+        assert_eq!(categories("s = f'a{b!r:>{w}}c{{d}}' + rf\"\"\"{x['}']}\"\"\"\n"), vec![
+            "operator =", "string f'a{", "fstring-expression b", "string !r:>{", "fstring-expression w", "string }}c{{d}}'",
+            "operator +", "string rf\"\"\"{", "fstring-expression x['}']", "string }\"\"\"" ]);
+    }
+
+    #[test]
+    fn soft_keywords_follow_the_grammar() {
+        let source = "match x:\n    case [1, _] if y:\n        match = type\n    case _: pass\ntype T[U] = list\ncase = match(1)\nx._\n";
+        let soft: Vec<String> = categories(source).into_iter().filter( |c| c.starts_with("soft") ).collect();
+        assert_eq!(soft, vec![ "soft-keyword match", "soft-keyword case", "soft-keyword _", "soft-keyword case",
+            "soft-keyword _", "soft-keyword type" ]);
+    }
+
+    #[test]
+    fn output_is_ansi_or_html() {
+        assert_eq!(highlight_ansi("x = '''a\n'''  # <c>\n"), "x \x1b[31m=\x1b[0m \x1b[32m'''a\x1b[0m\n\x1b[32m'''\x1b[0m  \x1b[2;37m# <c>\x1b[0m\n");
+        let html = highlight_html("if a < 1: pass\n", "<t>");
+        assert!(html.starts_with("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>&lt;t&gt;</title>"));
+        assert!(html.contains(".soft-keyword {"));
+        assert!(html.contains("<pre class=\"python\"><span class=\"keyword\">if</span> a <span class=\"operator\">&lt;</span> <span class=\"number\">1</span>: <span class=\"keyword\">pass</span>\n</pre>"));
+        assert_eq!(Category::FStringExpression.class_name(), "fstring-expression");
+    }
+}
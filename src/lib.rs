@@ -0,0 +1,28 @@
+extern crate core;
+
+pub mod parser;
+pub mod ast;
+pub mod diagnostics;
+pub mod edits;
+pub mod api;
+pub mod semantic;
+pub mod runtime;
+pub mod bytecode;
+pub mod cli;
+pub mod repl;
+pub mod format;
+pub mod lint;
+pub mod lsp;
+pub mod highlight;
+
+pub use parser::nodes::ASTNode;
+pub use parser::tokens::Token;
+pub use parser::expressions::Expressions;
+pub use parser::statements::Statements;
+pub use parser::tokenizer::{ PythonCoreTokenizer, Tokenizer };
+pub use parser::parser::{ Parser, PythonCoreParser };
+pub use diagnostics::{ Diagnostic, Severity };
+pub use edits::TextEdit;
+pub use api::{ Mode, ParseResult, ExpectedToken, parse, parse_module, parse_expression, parse_interactive, parse_func_type, tokenize, expected_tokens, check, analyze };
+pub use format::printer::{ FormatOptions, format_source };
+pub use lint::linter::{ LintOptions, lint_source };
@@ -0,0 +1,3 @@
+pub mod linter;
+pub mod rules;
+pub mod fix;
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use crate::ASTNode;
+use crate::edits::TextEdit;
+use crate::lint::linter::{ walk_nodes, token_span };
+
+
+/// Edits deleting the statement of tree that starts at 'start', and with 'rest' also the statements
+/// after it in its block. Lines left without statements are removed, and a block left empty gets
+/// 'pass'. None when no statement starts there.
+pub fn delete_statements(source: &str, tree: &ASTNode, start: u32, rest: bool) -> Option<Vec<TextEdit>> {
+    StatementDeleter::new(source, tree).delete(start, rest)
+}
+
+/// Blocks and lines of statements of a tree indexed by where their statements start, so that
+/// deleting many statements walks the tree once.
+pub struct StatementDeleter<'t> {
+    chars: Vec<char>,
+    blocks: Vec<( Vec<&'t ASTNode>, bool )>,
+    lists: Vec<Vec<&'t ASTNode>>,
+    /// Start of every statement of a line to its list and place in it.
+    in_lists: HashMap<u32, ( usize, usize )>,
+    /// Start of every item of a block, and of the definition of decorated ones, to its block and place in it.
+    in_blocks: HashMap<u32, ( usize, usize )>
+}
+
+impl<'t> StatementDeleter<'t> {
+    pub fn new(source: &str, tree: &'t ASTNode) -> StatementDeleter<'t> {
+        let mut blocks: Vec<( Vec<&ASTNode>, bool )> = Vec::new();
+        let mut lists: Vec<Vec<&ASTNode>> = Vec::new();
+        walk_nodes(tree, &mut |node| {
+            match node {
+                ASTNode::FileInput(_, _, items, ..) => blocks.push( ( sorted(items), true ) ),
+                ASTNode::SuiteStmt(_, _, _, _, items, _) |
+                ASTNode::FuncBodySuite(_, _, _, _, _, _, items, _) => blocks.push( ( sorted(items), false ) ),
+                ASTNode::SimpleStmtList(_, _, statements, ..) => lists.push( sorted(statements) ),
+                _ => { }
+            }
+        });
+        let mut in_lists = HashMap::new();
+        for ( l, statements ) in lists.iter().enumerate() {
+            for ( k, s ) in statements.iter().enumerate() {
+                in_lists.entry(token_span(s).0).or_insert( ( l, k ) );
+            }
+        }
+        let mut in_blocks = HashMap::new();
+        for ( b, ( items, _ ) ) in blocks.iter().enumerate() {
+            for ( i, item ) in items.iter().enumerate() {
+                in_blocks.entry(token_span(item).0).or_insert( ( b, i ) );
+                if let ASTNode::Decorated(_, _, _, definition) = item {
+                    in_blocks.entry(token_span(definition).0).or_insert( ( b, i ) );
+                }
+            }
+        }
+        StatementDeleter { chars: source.chars().collect(), blocks, lists, in_lists, in_blocks }
+    }
+
+    /// Edits deleting the statement starting at 'start', the same as 'delete_statements'.
+    pub fn delete(&self, start: u32, rest: bool) -> Option<Vec<TextEdit>> {
+        if let Some( &( l, k ) ) = self.in_lists.get(&start) {
+            let statements = &self.lists[l];
+            let last = if rest { statements.len() - 1 } else { k };
+            let line = self.in_blocks.get(&token_span(statements[0]).0).copied();
+            let mut edits = Vec::new();
+            if k > 0 || last + 1 < statements.len() {
+                edits.push( match last + 1 < statements.len() {
+                    true => TextEdit::new(token_span(statements[k]).0, token_span(statements[last + 1]).0, ""),
+                    _ => TextEdit::new(token_span(statements[k - 1]).1, token_span(statements[last]).1, "")
+                });
+                if let ( true, Some( ( b, i ) ) ) = ( rest, line ) {
+                    let ( block, module ) = &self.blocks[b];
+                    if i + 1 < block.len() {
+                        edits.push( delete_lines(&self.chars, block, i + 1, block.len() - 1, *module) )
+                    }
+                }
+                return Some( edits )
+            }
+            return match line {
+                Some( ( b, i ) ) => Some( vec![ self.delete_block_lines(b, i, rest) ] ),
+                None => Some( vec![ TextEdit::new(token_span(statements[0]).0, token_span(statements[last]).1, "pass") ] )
+            }
+        }
+        self.in_blocks.get(&start).map( |&( b, i )| vec![ self.delete_block_lines(b, i, rest) ] )
+    }
+
+    fn delete_block_lines(&self, b: usize, i: usize, rest: bool) -> TextEdit {
+        let ( block, module ) = &self.blocks[b];
+        delete_lines(&self.chars, block, i, if rest { block.len() - 1 } else { i }, *module)
+    }
+}
+
+/// Edit deleting the lines of the statements 'first' to 'last' of block, or replacing them by 'pass'
+/// when they are all statements of a block that is not the module.
+fn delete_lines(chars: &[char], block: &[&ASTNode], first: usize, last: usize, module: bool) -> TextEdit {
+    let start = token_span(block[first]).0 as usize;
+    let line_start = chars[.. start].iter().rposition( |c| *c == '\n' ).map( |p| p + 1 ).unwrap_or(0);
+    let mut end = ( token_span(block[last]).1 as usize ).min(chars.len());
+    if end > 0 && chars[end - 1] != '\n' {
+        end = chars[end ..].iter().position( |c| *c == '\n' ).map( |p| end + p + 1 ).unwrap_or(chars.len())
+    }
+    match first == 0 && last + 1 == block.len() && !module {
+        true => {
+            let indent: String = chars[line_start .. start].iter().collect();
+            TextEdit::new(line_start as u32, end as u32, &format!("{}pass\n", indent))
+        },
+        _ => TextEdit::new(line_start as u32, end as u32, "")
+    }
+}
+
+fn sorted(items: &[Box<ASTNode>]) -> Vec<&ASTNode> {
+    let mut res: Vec<&ASTNode> = items.iter().map( |i| &**i ).collect();
+    res.sort_by_key( |i| i.get_span().0 );
+    res
+}
+
+/// Edits removing the item at 'index' of a list of items separated by commas, with the comma after
+/// it, or the comma in front of it for the last item. Items are spans and there must be two at least.
+pub fn delete_list_item(items: &[( u32, u32 )], index: usize) -> TextEdit {
+    match index + 1 < items.len() {
+        true => TextEdit::new(items[index].0, items[index + 1].0, ""),
+        _ => TextEdit::new(items[index - 1].1, items[index].1, "")
+    }
+}
+
+
+// UnitTests for fixes ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::api::parse_module;
+    use crate::edits::apply_edits;
+    use crate::lint::fix::{ StatementDeleter, delete_statements, delete_list_item };
+
+
+    fn delete(source: &str, start: u32, rest: bool) -> String {
+        let tree = parse_module(source).tree.unwrap();
+        apply_edits(source, &delete_statements(source, &tree, start, rest).unwrap())
+    }
+
+    #[test]
+    fn statements_are_deleted_with_their_lines() {
+        let source = "def f():\n    return 1\n    x = 2  # c\n    # about y\n    y = 3\nz = 4\n";
+        assert_eq!(delete(source, 26, true), "def f():\n    return 1\nz = 4\n");
+        assert_eq!(delete(source, 26, false), "def f():\n    return 1\n    # about y\n    y = 3\nz = 4\n");
+        assert_eq!(delete(source, 13, true), "def f():\n    pass\nz = 4\n");
+        assert_eq!(delete(source, 0, false), "z = 4\n");
+        assert_eq!(delete("import os\n", 0, false), "");
+    }
+
+    #[test]
+    fn simple_statements_keep_their_line() {
+        assert_eq!(delete("a = 1; b = 2; c = 3\n", 7, false), "a = 1; c = 3\n");
+        assert_eq!(delete("a = 1; b = 2; c = 3\n", 7, true), "a = 1\n");
+        assert_eq!(delete("a = 1; b = 2\nc = 3\n", 7, true), "a = 1\n");
+        assert_eq!(delete("if x: return; y()\n", 6, false), "if x: y()\n");
+        assert_eq!(delete("if x: y()\n", 6, false), "if x: pass\n");
+        assert!(delete_statements("x\n", &parse_module("x\n").tree.unwrap(), 1, false).is_none());
+    }
+
+    #[test]
+    fn deleter_is_reused_for_several_statements() {
+        let source = "import a\nimport b\ndef f():\n    import c\n    @d\n    def g(): pass\n";
+        let tree = parse_module(source).tree.unwrap();
+        let deleter = StatementDeleter::new(source, &tree);
+        let mut edits = deleter.delete(0, false).unwrap();
+        edits.extend( deleter.delete(51, false).unwrap() );
+        assert_eq!(apply_edits(source, &edits), "import b\ndef f():\n    import c\n");
+        assert_eq!(apply_edits(source, &deleter.delete(31, true).unwrap()), "import a\nimport b\ndef f():\n    pass\n");
+    }
+
+    #[test]
+    fn list_items_keep_commas_valid() {
+        let source = "from m import (a, b, c,)\n";
+        let items = [ ( 15, 16 ), ( 18, 19 ), ( 21, 22 ) ];
+        assert_eq!(apply_edits(source, &[ delete_list_item(&items, 0) ]), "from m import (b, c,)\n");
+        assert_eq!(apply_edits(source, &[ delete_list_item(&items, 2) ]), "from m import (a, b,)\n");
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use crate::{ ASTNode, Token };
+use crate::api::{ Mode, check, parse, normalize_source };
+use crate::ast::location::LineIndex;
+use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+use crate::ast::nodes::Mod;
+use crate::diagnostics::{ Diagnostic, Severity };
+use crate::edits::{ TextEdit, apply_edits };
+use crate::parser::nodes::NodeChild;
+use crate::parser::sexpr::ordered_children;
+use crate::parser::trivias::Trivia;
+use crate::semantic::symbols::SymbolTable;
+use crate::lint::rules::default_rules;
+
+
+/// Rounds of fixing after which 'fix' stops even when fixes are left.
+const MAX_FIX_ROUNDS: usize = 100;
+
+/// What a lint rule sees of a module without syntax errors: the source, the concrete syntax tree,
+/// every token with its trivia, the lowered module and its symbol table.
+pub struct LintContext<'a> {
+    pub source: &'a str,
+    pub tree: &'a ASTNode,
+    pub tokens: &'a [Box<Token>],
+    pub module: &'a Mod,
+    pub symbols: &'a SymbolTable
+}
+
+/// Check run by the linter. Diagnostics pushed by 'check' are reported as warnings with the code
+/// of the rule, and may carry the edits that fix them.
+pub trait LintRule {
+    /// Code of the rule in the style of pyflakes and pycodestyle, like 'F401'.
+    fn code(&self) -> &'static str;
+    /// Name of the rule, like 'unused-import'.
+    fn name(&self) -> &'static str;
+    fn check(&self, context: &LintContext, res: &mut Vec<Diagnostic>);
+}
+
+/// Codes of the rules to run, or prefixes of codes like 'F'. No 'select' at all selects every rule,
+/// and 'ignore' wins over 'select'.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LintOptions {
+    pub select: Vec<String>,
+    pub ignore: Vec<String>
+}
+
+/// Registry of lint rules, created with the rules of 'rules' and open for more.
+pub struct PythonCoreLinter {
+    rules: Vec<Box<dyn LintRule>>
+}
+
+/// Outcome of fixing source: the fixed source, the number of fixes applied and the diagnostics of
+/// the fixed source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixResult {
+    pub source: String,
+    pub fixed: usize,
+    pub remaining: Vec<Diagnostic>
+}
+
+
+impl LintOptions {
+    pub fn is_enabled(&self, code: &str) -> bool {
+        ( self.select.is_empty() || self.select.iter().any( |s| code.starts_with(s.as_str()) ) )
+            && !self.ignore.iter().any( |s| code.starts_with(s.as_str()) )
+    }
+}
+
+impl Default for PythonCoreLinter {
+    fn default() -> PythonCoreLinter {
+        PythonCoreLinter::new()
+    }
+}
+
+impl PythonCoreLinter {
+    pub fn new() -> PythonCoreLinter {
+        PythonCoreLinter { rules: default_rules() }
+    }
+
+    pub fn with_rules(rules: Vec<Box<dyn LintRule>>) -> PythonCoreLinter {
+        PythonCoreLinter { rules }
+    }
+
+    pub fn register(&mut self, rule: Box<dyn LintRule>) {
+        self.rules.push(rule)
+    }
+
+    pub fn rules(&self) -> &[Box<dyn LintRule>] {
+        &self.rules
+    }
+
+    pub fn rule(&self, code: &str) -> Option<&dyn LintRule> {
+        self.rules.iter().find( |r| r.code() == code ).map( |r| r.as_ref() )
+    }
+
+    /// Runs the rules enabled by options on source and returns their diagnostics sorted by position,
+    /// without those on a line with a '# noqa' comment for their code. Source with syntax errors is
+    /// rejected with the first error.
+    pub fn lint(&self, source: &str, options: &LintOptions) -> Result<Vec<Diagnostic>, Diagnostic> {
+        let source = normalize_source(source);
+        if let Some( error ) = check(&source, Mode::Exec).into_iter().find( |d| d.is_error() ) {
+            return Err( error )
+        }
+        let res = parse(&source, Mode::Exec);
+        let tree = res.tree.ok_or_else( || Diagnostic::error(0, 0, "source has no tree") )?;
+        let module = PythonCoreLowering::new(&source).lower_mod(&tree).map_err( |e| Diagnostic::from_error_text(&e, 0) )?;
+        let symbols = SymbolTable::build(&module, &source);
+        let context = LintContext { source: &source, tree: &tree, tokens: &res.tokens, module: &module, symbols: &symbols };
+        let mut diagnostics = Vec::new();
+        for rule in self.rules.iter().filter( |r| options.is_enabled(r.code()) ) {
+            let mut found = Vec::new();
+            rule.check(&context, &mut found);
+            diagnostics.extend( found.into_iter().map( |d| Diagnostic { severity: Severity::Warning, ..d }.with_code(rule.code()) ) )
+        }
+        let index = LineIndex::new(&source);
+        let noqa = noqa_lines(&res.tokens, &index);
+        diagnostics.retain( |d| {
+            let code = d.code.as_deref().unwrap_or("");
+            match noqa.get(&index.line_char_col(d.start).0) {
+                Some( codes ) => !codes.is_empty() && !codes.iter().any( |c| code.starts_with(c.as_str()) ),
+                None => true
+            }
+        });
+        diagnostics.sort_by( |a, b| ( a.start, a.end, &a.code ).cmp(&( b.start, b.end, &b.code )) );
+        Ok( diagnostics )
+    }
+
+    /// Applies the fixes of the diagnostics of 'lint' and lints again until no fix is left. Every
+    /// round applies the fixes that do not overlap an earlier one, and when the result has syntax
+    /// errors only the first fix that keeps source valid.
+    pub fn fix(&self, source: &str, options: &LintOptions) -> Result<FixResult, Diagnostic> {
+        let mut text = normalize_source(source);
+        let mut fixed = 0;
+        for _ in 0 .. MAX_FIX_ROUNDS {
+            let found = self.lint(&text, options)?;
+            let mut fixes: Vec<&Vec<TextEdit>> = Vec::new();
+            for d in found.iter().filter( |d| !d.fix.is_empty() ) {
+                if !d.fix.iter().any( |e| fixes.iter().any( |f| f.iter().any( |g| g.overlaps(e) ) ) ) {
+                    fixes.push(&d.fix)
+                }
+            }
+            if fixes.is_empty() {
+                break
+            }
+            let all: Vec<TextEdit> = fixes.iter().flat_map( |f| f.iter().cloned() ).collect();
+            let candidate = apply_edits(&text, &all);
+            if is_valid(&candidate) {
+                text = candidate;
+                fixed += fixes.len()
+            }
+            else if let Some( next ) = fixes.iter().map( |f| apply_edits(&text, f) ).find( |t| is_valid(t) ) {
+                text = next;
+                fixed += 1
+            }
+            else {
+                break
+            }
+        }
+        let remaining = self.lint(&text, options)?;
+        Ok( FixResult { source: text, fixed, remaining } )
+    }
+}
+
+fn is_valid(source: &str) -> bool {
+    !check(source, Mode::Exec).iter().any( |d| d.is_error() )
+}
+
+/// Lints source with the default rules.
+pub fn lint_source(source: &str, options: &LintOptions) -> Result<Vec<Diagnostic>, Diagnostic> {
+    PythonCoreLinter::new().lint(source, options)
+}
+
+/// Fixes source with the default rules.
+pub fn fix_source(source: &str, options: &LintOptions) -> Result<FixResult, Diagnostic> {
+    PythonCoreLinter::new().fix(source, options)
+}
+
+/// Calls f on node and every node below it, parents before children and children in source order.
+pub fn walk_nodes<'a>(node: &'a ASTNode, f: &mut dyn FnMut(&'a ASTNode)) {
+    f(node);
+    for child in ordered_children(node) {
+        if let NodeChild::Node( n ) = child {
+            walk_nodes(n, f)
+        }
+    }
+}
+
+/// Tokens of node in source order.
+pub fn node_tokens(node: &ASTNode) -> Vec<&Token> {
+    let mut res = Vec::new();
+    for child in ordered_children(node) {
+        match child {
+            NodeChild::Node( n ) => res.extend( node_tokens(n) ),
+            NodeChild::Token( t ) => res.push(t)
+        }
+    }
+    res
+}
+
+/// Span from the first to the last token of node, without the trivia that follows the node.
+pub fn token_span(node: &ASTNode) -> ( u32, u32 ) {
+    let spans: Vec<( u32, u32 )> = node_tokens(node).iter().filter_map( |t| t.get_span() ).collect();
+    match ( spans.first(), spans.last() ) {
+        ( Some( first ), Some( last ) ) => ( first.0, last.1 ),
+        _ => node.get_span()
+    }
+}
+
+/// Lines, counted from 1, with a '# noqa' comment and the codes it names. An empty list is a
+/// comment without codes, which suppresses every diagnostic of the line.
+fn noqa_lines(tokens: &[Box<Token>], index: &LineIndex) -> HashMap<u32, Vec<String>> {
+    let mut res = HashMap::new();
+    for trivia in tokens.iter().filter_map( |t| t.get_trivia() ).flatten() {
+        if let Trivia::Comment( start, _, text ) = &**trivia {
+            if let Some( codes ) = noqa_codes(text) {
+                res.insert(index.line_char_col(*start).0, codes);
+            }
+        }
+    }
+    res
+}
+
+/// Codes of a comment containing '# noqa' or '# noqa: CODE, CODE', in any case.
+fn noqa_codes(comment: &str) -> Option<Vec<String>> {
+    let lower = comment.to_ascii_lowercase();
+    let at = lower.match_indices('#').map( |( i, _ )| i ).find( |i| lower[i + 1 ..].trim_start().starts_with("noqa") )?;
+    let rest = lower[at + 1 ..].trim_start()[4 ..].trim_start();
+    let is_code = |c: &&str| {
+        let digits = c.trim_start_matches( |x: char| x.is_ascii_alphabetic() );
+        digits.len() < c.len() && !digits.is_empty() && digits.chars().all( |x| x.is_ascii_digit() )
+    };
+    Some( match rest.strip_prefix(':') {
+        Some( codes ) => codes.split( |c: char| c == ',' || c.is_whitespace() ).filter( |c| !c.is_empty() )
+            .take_while(is_code).map( |c| c.to_ascii_uppercase() ).collect(),
+        None => Vec::new()
+    })
+}
+
+
+// UnitTests for linter ///////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::Diagnostic;
+    use crate::lint::linter::{ LintContext, LintOptions, LintRule, PythonCoreLinter, lint_source, fix_source, noqa_codes };
+
+
+    struct EveryName;
+
+    impl LintRule for EveryName {
+        fn code(&self) -> &'static str { "X100" }
+        fn name(&self) -> &'static str { "every-name" }
+        fn check(&self, context: &LintContext, res: &mut Vec<Diagnostic>) {
+            for scope in &context.symbols.scopes {
+                for symbol in &scope.symbols {
+                    res.extend( symbol.bindings.iter().map( |( s, e )| Diagnostic::warning(*s, *e, &symbol.name) ) )
+                }
+            }
+        }
+    }
+
+    fn codes(source: &str, options: &LintOptions) -> Vec<String> {
+        lint_source(source, options).unwrap().iter().map( |d| d.code.clone().unwrap() ).collect()
+    }
+
+    #[test]
+    fn rules_are_registered_and_selected() {
+        let mut linter = PythonCoreLinter::with_rules(Vec::new());
+        linter.register( Box::new(EveryName) );
+        let found = linter.lint("x = 1\n", &LintOptions::default()).unwrap();
+        assert_eq!(found, vec![ Diagnostic::warning(0, 1, "x").with_code("X100") ]);
+        assert_eq!(linter.rule("X100").map( |r| r.name() ), Some( "every-name" ));
+        let source = "import os\nif x == None:\n    pass\n";
+        assert_eq!(codes(source, &LintOptions::default()), vec![ "F401", "F821", "E711" ]);
+        let options = LintOptions { select: vec![ "F".to_string() ], ignore: vec![ "F821".to_string() ] };
+        assert_eq!(codes(source, &options), vec![ "F401" ]);
+        assert!(lint_source("x = (\n", &LintOptions::default()).is_err());
+    }
+
+    #[test]
+    fn noqa_comments_suppress_diagnostics() {
+        assert_eq!(codes("import os  # noqa\nimport sys  # NOQA: E711\n", &LintOptions::default()), vec![ "F401" ]);
+        assert!(codes("import os, sys  # noqa: F401\n", &LintOptions::default()).is_empty());
+        assert_eq!(noqa_codes("# noqa:F401,E711 because"), Some( vec![ "F401".to_string(), "E711".to_string() ] ));
+        assert_eq!(noqa_codes("# type: int  # noqa"), Some( Vec::new() ));
+        assert_eq!(noqa_codes("# not noqa"), None);
+    }
+
+    #[test]
+    fn fixes_are_applied_until_stable() {
+        let source = "from m import a, b, c
+import os
+print(c)
+if c==None:
+    pass
+";
+        let res = fix_source(source, &LintOptions::default()).unwrap();
+        assert_eq!(res.source, "from m import c
+print(c)
+if c is None:
+    pass
+");
+        assert_eq!(res.fixed, 4);
+        assert!(res.remaining.is_empty());
+        let source = "def f():
+    return 1
+    x = 2
+    y = x
+import os
+";
+        let res = fix_source(source, &LintOptions { select: vec![ "W0101".to_string() ], ignore: Vec::new() }).unwrap();
+        assert_eq!(res.source, "def f():
+    return 1
+import os
+");
+        let res = fix_source("x = undefined_name
+", &LintOptions::default()).unwrap();
+        assert_eq!(( res.fixed, res.remaining.len() ), ( 0, 1 ));
+    }
+}
@@ -0,0 +1,440 @@
+use std::collections::{ HashMap, HashSet };
+use crate::{ ASTNode, Token };
+use crate::diagnostics::Diagnostic;
+use crate::edits::TextEdit;
+use crate::parser::trivias::Trivia;
+use crate::semantic::symbols::{ ScopeId, ScopeKind, SymbolTable };
+use crate::semantic::dataflow;
+use crate::lint::linter::{ LintContext, LintRule, walk_nodes, node_tokens, token_span };
+use crate::lint::fix::{ StatementDeleter, delete_list_item };
+
+
+/// Names of module 'builtins' of CPython 3.11.
+pub const BUILTINS: &[&str] = &[
+    "ArithmeticError", "AssertionError", "AttributeError", "BaseException", "BaseExceptionGroup", "BlockingIOError",
+    "BrokenPipeError", "BufferError", "BytesWarning", "ChildProcessError", "ConnectionAbortedError", "ConnectionError",
+    "ConnectionRefusedError", "ConnectionResetError", "DeprecationWarning", "EOFError", "Ellipsis", "EncodingWarning",
+    "EnvironmentError", "Exception", "ExceptionGroup", "False", "FileExistsError", "FileNotFoundError",
+    "FloatingPointError", "FutureWarning", "GeneratorExit", "IOError", "ImportError", "ImportWarning",
+    "IndentationError", "IndexError", "InterruptedError", "IsADirectoryError", "KeyError", "KeyboardInterrupt",
+    "LookupError", "MemoryError", "ModuleNotFoundError", "NameError", "None", "NotADirectoryError", "NotImplemented",
+    "NotImplementedError", "OSError", "OverflowError", "PendingDeprecationWarning", "PermissionError",
+    "ProcessLookupError", "RecursionError", "ReferenceError", "ResourceWarning", "RuntimeError", "RuntimeWarning",
+    "StopAsyncIteration", "StopIteration", "SyntaxError", "SyntaxWarning", "SystemError", "SystemExit", "TabError",
+    "TimeoutError", "True", "TypeError", "UnboundLocalError", "UnicodeDecodeError", "UnicodeEncodeError",
+    "UnicodeError", "UnicodeTranslateError", "UnicodeWarning", "UserWarning", "ValueError", "Warning",
+    "ZeroDivisionError", "__build_class__", "__debug__", "__doc__", "__import__", "__loader__", "__name__",
+    "__package__", "__spec__", "abs", "aiter", "all", "anext", "any", "ascii", "bin", "bool", "breakpoint", "bytearray",
+    "bytes", "callable", "chr", "classmethod", "compile", "complex", "copyright", "credits", "delattr", "dict", "dir",
+    "divmod", "enumerate", "eval", "exec", "exit", "filter", "float", "format", "frozenset", "getattr", "globals",
+    "hasattr", "hash", "help", "hex", "id", "input", "int", "isinstance", "issubclass", "iter", "len", "license", "list",
+    "locals", "map", "max", "memoryview", "min", "next", "object", "oct", "open", "ord", "pow", "print", "property",
+    "quit", "range", "repr", "reversed", "round", "set", "setattr", "slice", "sorted", "staticmethod", "str", "sum",
+    "super", "tuple", "type", "vars", "zip"
+];
+
+/// Names every module has besides the builtins.
+const MODULE_NAMES: &[&str] = &[ "__file__", "__builtins__", "__annotations__", "__cached__", "__path__" ];
+
+/// Name bound by an import statement. 'alias' spans the imported name with its 'as' part, and
+/// 'binding' the name that gets bound.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportedName {
+    pub name: String,
+    pub qualified: String,
+    pub aliased: bool,
+    pub alias: ( u32, u32 ),
+    pub binding: ( u32, u32 ),
+    pub statement: ( u32, u32 )
+}
+
+/// F401, imported names never used in their scope or scopes below it, or listed in '__all__'.
+pub struct UnusedImport;
+
+/// F821, names read that no scope binds and that are not builtins.
+pub struct UndefinedName;
+
+/// E711, comparison to None with '==' or '!='.
+pub struct NoneComparison;
+
+/// E722, 'except' without exception type.
+pub struct BareExcept;
+
+/// B006, lists, dictionaries and sets as default values of function parameters.
+pub struct MutableDefault;
+
+/// W291, spaces and tabs at end of line, from the whitespace trivia in front of line ends.
+pub struct TrailingWhitespace;
+
+/// W0101, statements that are never executed, found by the control flow graphs of 'dataflow'.
+pub struct UnreachableCode;
+
+
+/// Rules run by the linter unless others are registered.
+pub fn default_rules() -> Vec<Box<dyn LintRule>> {
+    vec![ Box::new(UnusedImport), Box::new(UndefinedName), Box::new(NoneComparison), Box::new(BareExcept),
+          Box::new(MutableDefault), Box::new(TrailingWhitespace), Box::new(UnreachableCode) ]
+}
+
+/// Names bound by the import statements of tree in source order, star imports and imports from
+/// '__future__' are left out.
+pub fn imported_names(tree: &ASTNode) -> Vec<ImportedName> {
+    let mut res = Vec::new();
+    walk_nodes(tree, &mut |node| {
+        match node {
+            ASTNode::ImportNameStmt(..) => {
+                let statement = token_span(node);
+                walk_nodes(node, &mut |n| {
+                    if let ASTNode::DottedAsNameStmt(_, _, dotted, alias) = n {
+                        let names: Vec<&Token> = node_tokens(dotted).into_iter().filter( |t| matches!(t, Token::AtomName(..)) ).collect();
+                        let qualified = names.iter().filter_map( |t| t.text() ).collect::<Vec<String>>().join(".");
+                        let bound = match alias {
+                            Some( ( _, name ) ) => &**name,
+                            None => names[0]
+                        };
+                        res.push( ImportedName {
+                            name: bound.text().unwrap_or_default(),
+                            qualified,
+                            aliased: alias.is_some(),
+                            alias: token_span(n),
+                            binding: bound.get_span().unwrap_or_default(),
+                            statement
+                        })
+                    }
+                })
+            },
+            ASTNode::ImportFromStmt(_, _, _, dots, module, ..) => {
+                let module_name = module.as_ref().map( |m| node_tokens(m).iter().filter_map( |t| t.text() ).collect::<String>() ).unwrap_or_default();
+                if module_name == "__future__" {
+                    return
+                }
+                let prefix = format!("{}{}", dots.iter().filter_map( |t| t.text() ).collect::<String>(), module_name);
+                let statement = token_span(node);
+                walk_nodes(node, &mut |n| {
+                    if let ASTNode::ImportAsName(_, _, name, alias) = n {
+                        let bound = match alias {
+                            Some( ( _, a ) ) => a,
+                            None => name
+                        };
+                        let text = name.text().unwrap_or_default();
+                        res.push( ImportedName {
+                            name: bound.text().unwrap_or_default(),
+                            qualified: match prefix.ends_with('.') {
+                                true => format!("{}{}", prefix, text),
+                                _ => format!("{}.{}", prefix, text)
+                            },
+                            aliased: alias.is_some(),
+                            alias: token_span(n),
+                            binding: bound.get_span().unwrap_or_default(),
+                            statement
+                        })
+                    }
+                })
+            },
+            _ => { }
+        }
+    });
+    res
+}
+
+/// Innermost module, class or function scope that holds position.
+pub fn scope_at(symbols: &SymbolTable, position: u32) -> ScopeId {
+    symbols.scopes.iter().enumerate()
+        .filter( |( _, s )| matches!(s.kind, ScopeKind::Module | ScopeKind::Class | ScopeKind::Function) )
+        .filter( |( _, s )| s.span.0 <= position && position < s.span.1 )
+        .min_by_key( |( _, s )| s.span.1 - s.span.0 )
+        .map( |( id, _ )| id ).unwrap_or(0)
+}
+
+/// Strings assigned or added to '__all__' at module level.
+pub fn all_names(tokens: &[Box<Token>]) -> Vec<String> {
+    all_strings(tokens).into_iter().map( |( text, _ )| text ).collect()
+}
+
+/// Strings assigned or added to '__all__' at module level with the span of their text between the
+/// quotes.
+pub fn all_strings(tokens: &[Box<Token>]) -> Vec<( String, ( u32, u32 ) )> {
+    let mut res = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        match &*tokens[i] {
+            Token::Indent(..) => depth += 1,
+            Token::Dedent(..) => depth -= 1,
+            Token::AtomName(_, _, _, name) if depth == 0 && name.as_str() == "__all__"
+                && matches!(tokens.get(i + 1).map( |t| &**t ), Some( Token::PyAssign(..) ) | Some( Token::PyPlusAssign(..) )) => {
+                while i < tokens.len() && !matches!(&*tokens[i], Token::Newline(..)) {
+                    if let Token::AtomString(start, end, _, text, prefix) = &*tokens[i] {
+                        let quote = ( text.chars().count() as u32 - string_text(text).chars().count() as u32 ) / 2;
+                        let start = start + prefix.as_ref().map( |p| p.chars().count() as u32 ).unwrap_or(0) + quote;
+                        res.push( ( string_text(text), ( start, end - quote ) ) )
+                    }
+                    i += 1
+                }
+            },
+            _ => { }
+        }
+        i += 1
+    }
+    res
+}
+
+/// Text of a string literal without prefix and quotes, escapes are kept.
+fn string_text(literal: &str) -> String {
+    let body = literal.trim_start_matches( |c: char| c.is_ascii_alphabetic() );
+    let quote = if body.starts_with("\"\"\"") || body.starts_with("'''") { 3 } else { 1 };
+    body.get(quote .. body.len().saturating_sub(quote).max(quote)).unwrap_or("").to_string()
+}
+
+impl LintRule for UnusedImport {
+    fn code(&self) -> &'static str { "F401" }
+    fn name(&self) -> &'static str { "unused-import" }
+
+    fn check(&self, context: &LintContext, res: &mut Vec<Diagnostic>) {
+        let symbols = context.symbols;
+        let mut used: HashSet<( ScopeId, &str )> = HashSet::new();
+        let mut imported_in: HashMap<u32, ( ScopeId, bool )> = HashMap::new();
+        for ( id, scope ) in symbols.scopes.iter().enumerate() {
+            for symbol in &scope.symbols {
+                if !symbol.references.is_empty() {
+                    if let Some( ( bound, _ ) ) = symbols.resolve(id, &symbol.name) {
+                        used.insert( ( bound, symbol.name.as_str() ) );
+                    }
+                }
+                if symbol.is_imported {
+                    for ( start, _ ) in &symbol.bindings {
+                        imported_in.insert(*start, ( id, symbol.is_declared_global ));
+                    }
+                }
+            }
+        }
+        let exported: HashSet<String> = all_names(context.tokens).into_iter().collect();
+        let imports = imported_names(context.tree);
+        let mut siblings: HashMap<( u32, u32 ), Vec<( u32, u32 )>> = HashMap::new();
+        for import in &imports {
+            siblings.entry(import.statement).or_default().push(import.alias)
+        }
+        let mut deleter = None;
+        for import in &imports {
+            let ( scope, declared_global ) = imported_in.get(&import.binding.0).copied().unwrap_or_else( || {
+                let scope = scope_at(symbols, import.binding.0);
+                ( scope, symbols.lookup(scope, &import.name).is_some_and( |s| s.is_declared_global ) )
+            });
+            if used.contains(&( scope, import.name.as_str() )) || declared_global || ( scope == 0 && exported.contains(&import.name) ) {
+                continue
+            }
+            let message = match import.aliased {
+                true => format!("'{}' imported as '{}' but unused", import.qualified, import.name),
+                _ => format!("'{}' imported but unused", import.qualified)
+            };
+            let siblings = &siblings[&import.statement];
+            let fix = match siblings.len() {
+                1 => deleter.get_or_insert_with( || StatementDeleter::new(context.source, context.tree) )
+                    .delete(import.statement.0, false).unwrap_or_default(),
+                _ => vec![ delete_list_item(siblings, siblings.iter().position( |s| *s == import.alias ).unwrap_or(0)) ]
+            };
+            res.push( Diagnostic::warning(import.alias.0, import.alias.1, &message).with_fix(fix) )
+        }
+    }
+}
+
+impl LintRule for UndefinedName {
+    fn code(&self) -> &'static str { "F821" }
+    fn name(&self) -> &'static str { "undefined-name" }
+
+    fn check(&self, context: &LintContext, res: &mut Vec<Diagnostic>) {
+        let symbols = context.symbols;
+        if symbols.root().has_import_star {
+            return
+        }
+        let declared: HashSet<&str> = symbols.scopes.iter().flat_map( |s| s.symbols.iter() )
+            .filter( |s| s.is_declared_global && s.is_bound() ).map( |s| s.name.as_str() ).collect();
+        for ( id, scope ) in symbols.scopes.iter().enumerate() {
+            for symbol in scope.symbols.iter().filter( |s| !s.references.is_empty() ) {
+                let name = symbol.name.as_str();
+                if symbols.resolve(id, name).is_some() || BUILTINS.contains(&name) || MODULE_NAMES.contains(&name) || declared.contains(name)
+                    || ( scope.kind == ScopeKind::Class && matches!(name, "__module__" | "__qualname__") ) {
+                    continue
+                }
+                for ( start, end ) in &symbol.references {
+                    res.push( Diagnostic::warning(*start, *end, &format!("undefined name '{}'", name)) )
+                }
+            }
+        }
+    }
+}
+
+impl LintRule for NoneComparison {
+    fn code(&self) -> &'static str { "E711" }
+    fn name(&self) -> &'static str { "none-comparison" }
+
+    fn check(&self, context: &LintContext, res: &mut Vec<Diagnostic>) {
+        let chars: Vec<char> = context.source.chars().collect();
+        let spaced = |pos: usize| chars.get(pos).is_none_or( |c| c.is_whitespace() );
+        walk_nodes(context.tree, &mut |node| {
+            let ( left, op, right, negated ) = match node {
+                ASTNode::EqualComparison(_, _, l, op, r) => ( l, op, r, false ),
+                ASTNode::NotEqualComparison(_, _, l, op, r) => ( l, op, r, true ),
+                _ => return
+            };
+            if matches!(&**left, ASTNode::AtomNone(..)) || matches!(&**right, ASTNode::AtomNone(..)) {
+                let ( start, end ) = op.get_span().unwrap_or_default();
+                let ( message, operator ) = match negated {
+                    true => ( "comparison to None should be 'if cond is not None:'", "is not" ),
+                    _ => ( "comparison to None should be 'if cond is None:'", "is" )
+                };
+                let text = format!("{}{}{}", if start == 0 || spaced(start as usize - 1) { "" } else { " " }, operator, if spaced(end as usize) { "" } else { " " });
+                res.push( Diagnostic::warning(start, end, message).with_fix(vec![ TextEdit::new(start, end, &text) ]) )
+            }
+        })
+    }
+}
+
+impl LintRule for BareExcept {
+    fn code(&self) -> &'static str { "E722" }
+    fn name(&self) -> &'static str { "bare-except" }
+
+    fn check(&self, context: &LintContext, res: &mut Vec<Diagnostic>) {
+        walk_nodes(context.tree, &mut |node| {
+            if let ASTNode::ExceptClauseStmt(_, _, keyword, _, None) = node {
+                let ( start, end ) = keyword.get_span().unwrap_or_default();
+                res.push( Diagnostic::warning(start, end, "do not use bare 'except'") )
+            }
+        })
+    }
+}
+
+impl LintRule for MutableDefault {
+    fn code(&self) -> &'static str { "B006" }
+    fn name(&self) -> &'static str { "mutable-argument-default" }
+
+    fn check(&self, context: &LintContext, res: &mut Vec<Diagnostic>) {
+        walk_nodes(context.tree, &mut |node| {
+            if let ASTNode::TFPAssign(_, _, _, _, default) = node {
+                let mutable = match &**default {
+                    ASTNode::AtomList(..) | ASTNode::AtomDictionary(..) | ASTNode::AtomSet(..) => true,
+                    ASTNode::AtomExpr(_, _, None, atom, trailers) => {
+                        matches!(&**atom, ASTNode::AtomName(_, _, name) if matches!(name.text().as_deref(), Some( "list" | "dict" | "set" )))
+                            && trailers.len() == 1 && matches!(&*trailers[0], ASTNode::CallTrailer(..))
+                    },
+                    _ => false
+                };
+                if mutable {
+                    let ( start, end ) = token_span(default);
+                    res.push( Diagnostic::warning(start, end, "do not use mutable data structures for argument defaults") )
+                }
+            }
+        })
+    }
+}
+
+impl LintRule for TrailingWhitespace {
+    fn code(&self) -> &'static str { "W291" }
+    fn name(&self) -> &'static str { "trailing-whitespace" }
+
+    fn check(&self, context: &LintContext, res: &mut Vec<Diagnostic>) {
+        let chars: Vec<char> = context.source.chars().collect();
+        for trivia in context.tokens.iter().filter_map( |t| t.get_trivia() ).flatten() {
+            match &**trivia {
+                Trivia::WhiteSpace(start, end, _) if matches!(chars.get(*end as usize), None | Some( '\n' )) => {
+                    res.push( Diagnostic::warning(*start, *end, "trailing whitespace") )
+                },
+                Trivia::Comment(start, end, text) => {
+                    let trimmed = text.trim_end_matches( [ ' ', '\t', '\x0c' ] );
+                    if trimmed.len() < text.len() {
+                        let start = start + trimmed.chars().count() as u32;
+                        res.push( Diagnostic::warning(start, *end, "trailing whitespace") )
+                    }
+                },
+                _ => { }
+            }
+        }
+    }
+}
+
+impl LintRule for UnreachableCode {
+    fn code(&self) -> &'static str { "W0101" }
+    fn name(&self) -> &'static str { "unreachable-code" }
+
+    fn check(&self, context: &LintContext, res: &mut Vec<Diagnostic>) {
+        let deleter = StatementDeleter::new(context.source, context.tree);
+        for d in dataflow::analyze(context.module, context.symbols).into_iter().filter( |d| d.message == "unreachable code" ) {
+            let fix = deleter.delete(d.start, true).unwrap_or_default();
+            res.push( d.with_fix(fix) )
+        }
+    }
+}
+
+
+// UnitTests for lint rules ///////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::lint::linter::{ LintOptions, lint_source };
+    use crate::api::parse_module;
+    use crate::lint::rules::{ imported_names, all_names };
+
+
+    /// Diagnostics of the rule with code as 'start-end: message'.
+    fn lint(source: &str, code: &str) -> Vec<String> {
+        let options = LintOptions { select: vec![ code.to_string() ], ignore: Vec::new() };
+        lint_source(source, &options).unwrap().iter().map( |d| format!("{}-{}: {}", d.start, d.end, d.message) ).collect()
+    }
+
+    #[test]
+    fn unused_imports() {
+        let source = "from __future__ import annotations\nimport os, sys as system, a.b\nfrom . import x\nfrom m import (y, z as w)\n\
+                      def f():\n    import json\n    return os, w\n";
+        assert_eq!(lint(source, "F401"), vec![
+            "46-59: 'sys' imported as 'system' but unused",
+            "61-64: 'a.b' imported but unused",
+            "79-80: '.x' imported but unused",
+            "96-97: 'm.y' imported but unused",
+            "127-131: 'json' imported but unused"
+        ]);
+        assert!(lint("import os\n__all__ = ['os']\n", "F401").is_empty());
+        assert!(lint("import os\nclass A:\n    def m(self):\n        return os.sep\n", "F401").is_empty());
+        let tree = parse_module("from .. import a as b\n").tree.unwrap();
+        let names = imported_names(&tree);
+        assert_eq!(( names[0].name.as_str(), names[0].qualified.as_str(), names[0].alias, names[0].statement ), ( "b", "..a", ( 15, 21 ), ( 0, 21 ) ));
+        assert_eq!(all_names(&parse_module("__all__ = ['a', \"b\"]\n__all__ += ('c',)\n").tokens), vec![ "a", "b", "c" ]);
+    }
+
+    #[test]
+    fn undefined_names() {
+        let source = "def f(a):\n    global g\n    g = 1\n    return a + b + len(a) + g\nclass C:\n    x = __qualname__\n    print(x, y)\n";
+        assert_eq!(lint(source, "F821"), vec![ "48-49: undefined name 'b'", "106-107: undefined name 'y'" ]);
+        assert!(lint("from m import *\nprint(x)\n", "F821").is_empty());
+        assert!(lint("def f():\n    x = 1\n    class A:\n        y = x\n    return [z for z in A.y], __name__\n", "F821").is_empty());
+    }
+
+    #[test]
+    fn none_comparisons() {
+        assert_eq!(lint("if x == None or None != y:\n    pass\n", "E711"), vec![
+            "5-7: comparison to None should be 'if cond is None:'",
+            "21-23: comparison to None should be 'if cond is not None:'"
+        ]);
+        assert!(lint("if x is None or x == 0:\n    pass\n", "E711").is_empty());
+    }
+
+    #[test]
+    fn bare_excepts() {
+        assert_eq!(lint("try:\n    pass\nexcept:\n    pass\n", "E722"), vec![ "14-20: do not use bare 'except'" ]);
+        assert!(lint("try:\n    pass\nexcept Exception:\n    pass\n", "E722").is_empty());
+    }
+
+    #[test]
+    fn mutable_defaults() {
+        assert_eq!(lint("def f(a=[], *, b={1: 2}, c=set(), d=(), e=None):\n    pass\n", "B006"), vec![
+            "8-10: do not use mutable data structures for argument defaults",
+            "17-23: do not use mutable data structures for argument defaults",
+            "27-32: do not use mutable data structures for argument defaults"
+        ]);
+        assert!(lint("f = lambda a=[]: a\n", "B006").is_empty());
+    }
+
+    #[test]
+    fn trailing_whitespace() {
+        assert_eq!(lint("x = 1  \ny = 2 # c \t\nz = '''a  \n'''\n", "W291"), vec![ "5-7: trailing whitespace", "17-19: trailing whitespace" ]);
+    }
+}
@@ -0,0 +1,8 @@
+pub mod json;
+pub mod document;
+pub mod outline;
+pub mod semantic;
+pub mod server;
+pub mod navigation;
+pub mod rename;
+pub mod completion;
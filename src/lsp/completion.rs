@@ -0,0 +1,249 @@
+use crate::Token;
+use crate::api::{ ExpectedToken, expected_tokens, tokenize };
+use crate::parser::trivias::Trivia;
+
+
+/// What may be typed at an offset. Span is the part of a name in front of the offset that a
+/// completion replaces, 'expected' the tokens the parser accepts there. The other fields describe
+/// where the offset is: after the '.' of an attribute, in the module or names of an import, in the
+/// arguments of a call or on the line of a decorator.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompletionContext {
+    pub span: ( u32, u32 ),
+    pub expected: Vec<ExpectedToken>,
+    pub attribute: Option<( u32, u32 )>,
+    pub import: Option<ImportContext>,
+    pub call: Option<CallContext>,
+    pub decorator: bool
+}
+
+/// Module of an import whose submodules may follow, like 'os' in 'import os.', or whose names may
+/// follow when 'names' is set, like in 'from os import '. Module is empty for top level modules and
+/// starts with dots for relative imports.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportContext {
+    pub module: String,
+    pub names: bool
+}
+
+/// Span of the called expression, index of the argument at offset and the name of the keyword
+/// when offset is in the value of a keyword argument.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallContext {
+    pub callee: ( u32, u32 ),
+    pub argument: usize,
+    pub keyword: Option<String>
+}
+
+
+/// Context of a completion at offset of source, which need not be valid past the offset. Inside
+/// strings and comments nothing is expected. The expected tokens come from parsing the top level
+/// statement around offset, the rest from the tokens of its logical line.
+pub fn completion_context(source: &str, offset: u32) -> CompletionContext {
+    let chars: Vec<char> = source.chars().collect();
+    let offset = ( offset as usize ).min(chars.len());
+    let mut start = offset;
+    while start > 0 && ( chars[start - 1].is_alphanumeric() || chars[start - 1] == '_' ) {
+        start -= 1
+    }
+    let mut res = CompletionContext { span: ( start as u32, offset as u32 ), ..Default::default() };
+    let prefix: String = chars[.. start].iter().collect();
+    let ( tokens, error ) = tokenize(&prefix);
+    let line_start = prefix.rfind('\n').map_or(0, |i| prefix[.. i].chars().count() + 1) as u32;
+    let comment = tokens.iter().flat_map( |t| t.get_trivia().into_iter().flatten() )
+        .any( |t| matches!(&**t, Trivia::Comment(s, ..) if *s >= line_start) );
+    if error.is_some() || comment {
+        return res
+    }
+    let tokens: Vec<&Token> = tokens.iter().map( |t| &**t ).take_while( |t| t.get_span().is_none_or( |( s, _ )| s < start as u32 ) ).collect();
+    let statement = top_level_statement(&tokens);
+    res.expected = expected_tokens(&chars[statement as usize .. start].iter().collect::<String>());
+
+    let line: Vec<&Token> = match tokens.iter().rposition( |t| matches!(t, Token::Newline(..) | Token::Indent(..) | Token::Dedent(..)) ) {
+        Some( i ) => tokens[i + 1 ..].to_vec(),
+        None => tokens.clone()
+    };
+    res.decorator = matches!(line.first(), Some( Token::PyMatrice(..) ));
+    res.import = import_context(&line);
+    if res.import.is_none() && matches!(line.last(), Some( Token::PyDot(..) )) {
+        res.attribute = primary(&line[.. line.len() - 1])
+    }
+    res.call = call_context(&line);
+    res
+}
+
+/// Start of the statement at indentation zero that the end of tokens is in, where parsing starts.
+/// Clauses like 'else' belong to the statement in front of them.
+fn top_level_statement(tokens: &[&Token]) -> u32 {
+    let mut res = 0;
+    let mut level = 0;
+    let mut line_start = true;
+    for token in tokens {
+        match token {
+            Token::Indent(..) => level += 1,
+            Token::Dedent(..) => level -= 1,
+            Token::Newline(..) => line_start = true,
+            Token::EOF(..) => { },
+            _ => {
+                let clause = matches!(token, Token::PyElse(..) | Token::PyElif(..) | Token::PyExcept(..) | Token::PyFinally(..));
+                if line_start && level == 0 && !clause {
+                    res = token.get_span().map_or(res, |( s, _ )| s)
+                }
+                line_start = false
+            }
+        }
+    }
+    res
+}
+
+fn import_context(line: &[&Token]) -> Option<ImportContext> {
+    let dotted = |tokens: &[&Token]| -> String {
+        let text: String = tokens.iter().filter_map( |t| t.text() ).collect();
+        match ( tokens.last(), tokens.len() > 1 && !text.chars().all( |c| c == '.' ) ) {
+            ( Some( Token::PyDot(..) ), true ) => text[.. text.len() - 1].to_string(),
+            ( Some( Token::PyDot(..) | Token::PyElipsis(..) ), false ) => text,
+            _ => String::new()
+        }
+    };
+    match line.first() {
+        Some( Token::PyImport(..) ) => {
+            let item = line.iter().rposition( |t| matches!(t, Token::PyImport(..) | Token::PyComa(..)) ).unwrap_or(0);
+            Some( ImportContext { module: dotted(&line[item + 1 ..]), names: false } )
+        },
+        Some( Token::PyFrom(..) ) => match line.iter().position( |t| matches!(t, Token::PyImport(..)) ) {
+            Some( i ) => Some( ImportContext { module: line[1 .. i].iter().filter_map( |t| t.text() ).collect(), names: true } ),
+            None => Some( ImportContext { module: dotted(&line[1 ..]), names: false } )
+        },
+        _ => None
+    }
+}
+
+/// Span of the primary expression that ends with tokens, a chain of names, atoms, attributes,
+/// calls and subscripts.
+fn primary(tokens: &[&Token]) -> Option<( u32, u32 )> {
+    let end = tokens.last()?.get_span()?.1;
+    let mut i = tokens.len();
+    let mut start = None;
+    while i > 0 {
+        i -= 1;
+        match tokens[i] {
+            Token::PyRightParen(..) | Token::PyRightBracket(..) | Token::PyRightCurly(..) => {
+                let mut depth = 0;
+                loop {
+                    match tokens[i] {
+                        Token::PyRightParen(..) | Token::PyRightBracket(..) | Token::PyRightCurly(..) => depth += 1,
+                        Token::PyLeftParen(..) | Token::PyLeftBracket(..) | Token::PyLeftCurly(..) => depth -= 1,
+                        _ => { }
+                    }
+                    if depth == 0 || i == 0 {
+                        break
+                    }
+                    i -= 1
+                }
+                start = tokens[i].get_span().map( |s| s.0 );
+                if i > 0 && matches!(tokens[i], Token::PyLeftParen(..) | Token::PyLeftBracket(..))
+                    && matches!(tokens[i - 1], Token::AtomName(..) | Token::PyRightParen(..) | Token::PyRightBracket(..) | Token::AtomString(..)) {
+                    continue
+                }
+            },
+            Token::AtomName(..) | Token::AtomString(..) | Token::AtomNumber(..) | Token::PyNone(..) | Token::PyTrue(..) | Token::PyFalse(..) => {
+                start = tokens[i].get_span().map( |s| s.0 )
+            },
+            _ => break
+        }
+        match i.checked_sub(1).map( |p| tokens[p] ) {
+            Some( Token::PyDot(..) ) => i -= 1,
+            Some( Token::AtomString(..) ) if matches!(tokens[i], Token::AtomString(..)) => { },
+            _ => break
+        }
+    }
+    start.map( |s| ( s, end ) )
+}
+
+fn call_context(line: &[&Token]) -> Option<CallContext> {
+    let mut open = Vec::new();
+    for ( i, token ) in line.iter().enumerate() {
+        match token {
+            Token::PyLeftParen(..) | Token::PyLeftBracket(..) | Token::PyLeftCurly(..) => open.push(i),
+            Token::PyRightParen(..) | Token::PyRightBracket(..) | Token::PyRightCurly(..) => { open.pop(); },
+            _ => { }
+        }
+    }
+    let paren = *open.last()?;
+    let definition = paren >= 2 && matches!(line[paren - 2], Token::PyDef(..) | Token::PyClass(..));
+    if !matches!(line[paren], Token::PyLeftParen(..)) || definition {
+        return None
+    }
+    let callee = match paren.checked_sub(1).map( |p| line[p] ) {
+        Some( Token::AtomName(..) | Token::PyRightParen(..) | Token::PyRightBracket(..) | Token::AtomString(..) ) => primary(&line[.. paren])?,
+        _ => return None
+    };
+    let mut argument = 0;
+    let mut current = paren + 1;
+    let mut depth = 0;
+    for ( i, token ) in line.iter().enumerate().skip(paren + 1) {
+        match token {
+            Token::PyLeftParen(..) | Token::PyLeftBracket(..) | Token::PyLeftCurly(..) => depth += 1,
+            Token::PyRightParen(..) | Token::PyRightBracket(..) | Token::PyRightCurly(..) => depth -= 1,
+            Token::PyComa(..) if depth == 0 => {
+                argument += 1;
+                current = i + 1
+            },
+            _ => { }
+        }
+    }
+    let keyword = match line.get(current .. current + 2) {
+        Some( [ Token::AtomName(..), Token::PyAssign(..) ] ) => line[current].text(),
+        _ => None
+    };
+    Some( CallContext { callee, argument, keyword } )
+}
+
+
+// UnitTests for completion context ////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::api::ExpectedToken;
+    use crate::lsp::completion::{ CallContext, ImportContext, completion_context };
+
+
+    fn import(module: &str, names: bool) -> Option<ImportContext> {
+        Some( ImportContext { module: module.to_string(), names } )
+    }
+
+    #[test]
+    fn expected_tokens_come_from_the_parser() {
+        let context = completion_context("if x:\n    pass\nel", 17);
+        assert_eq!(context.span, ( 15, 17 ));
+        assert!(context.expected.contains(&ExpectedToken::Keyword("elif")) && context.expected.contains(&ExpectedToken::Keyword("else")));
+        assert!(!context.expected.contains(&ExpectedToken::Keyword("except")));
+        let context = completion_context("def f():\n    x = 1\nfor a ", 26);
+        assert_eq!(context.expected[.. 2], [ ExpectedToken::Keyword("in"), ExpectedToken::Operator("+") ]);
+        assert_eq!(completion_context("from os ", 8).expected, vec![ ExpectedToken::Keyword("import"), ExpectedToken::Operator(".") ]);
+        assert_eq!(completion_context("f(a, ", 5).expected.last(), Some( &ExpectedToken::String ));
+        assert!(!completion_context("f(a, ", 5).expected.contains(&ExpectedToken::Operator(",")));
+        assert!(completion_context("x = 'ab", 7).expected.is_empty());
+        assert!(completion_context("x = 1  # ab", 11).expected.is_empty());
+    }
+
+    #[test]
+    fn attributes_imports_calls_and_decorators() {
+        let source = "x = a.b(1)[0].c";
+        let context = completion_context(source, 15);
+        assert_eq!(( context.span, context.attribute, context.expected ), ( ( 14, 15 ), Some( ( 4, 13 ) ), vec![ ExpectedToken::Name ] ));
+        assert_eq!(completion_context("1 + 'a' 'b'.", 12).attribute, Some( ( 4, 11 ) ));
+        assert_eq!(completion_context("import sys, os.pa", 17).import, import("os", false));
+        assert_eq!(completion_context("import ", 7).import, import("", false));
+        assert_eq!(completion_context("from ..pkg.", 11).import, import("..pkg", false));
+        assert_eq!(completion_context("from .", 6).import, import(".", false));
+        assert_eq!(completion_context("from .m import (a,\n    b", 24).import, import(".m", true));
+        assert_eq!(completion_context("print(f(x), sep=", 16).call, Some( CallContext { callee: ( 0, 5 ), argument: 1, keyword: Some( "sep".to_string() ) } ));
+        assert_eq!(completion_context("o.m(a)(", 7).call, Some( CallContext { callee: ( 0, 6 ), argument: 0, keyword: None } ));
+        assert_eq!(completion_context("f([", 3).call, None);
+        assert_eq!(completion_context("def f(", 6).call, None);
+        let context = completion_context("@app.ro\ndef f(): pass\n", 7);
+        assert!(context.decorator);
+        assert_eq!(context.attribute, Some( ( 1, 4 ) ));
+    }
+}
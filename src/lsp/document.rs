@@ -0,0 +1,100 @@
+/// Open document of the language server. Text has '\n' line endings only, so that character offsets
+/// of the parser and positions of the protocol, a line and a column in UTF-16 code units, map into
+/// each other the same way for client and server.
+pub struct Document {
+    pub uri: String,
+    pub version: i64,
+    text: String,
+    chars: Vec<char>,
+    line_starts: Vec<u32>
+}
+
+
+impl Document {
+    pub fn new(uri: &str, version: i64, text: &str) -> Document {
+        let mut document = Document { uri: uri.to_string(), version, text: String::new(), chars: Vec::new(), line_starts: Vec::new() };
+        document.set_text(text);
+        document
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Line and UTF-16 column of a character offset, offsets past the end give the end of text.
+    pub fn position(&self, offset: u32) -> ( u32, u32 ) {
+        let offset = offset.min(self.chars.len() as u32);
+        let line = self.line_starts.partition_point( |s| *s <= offset ) - 1;
+        let start = self.line_starts[line];
+        let column = self.chars[start as usize .. offset as usize].iter().map( |c| c.len_utf16() as u32 ).sum();
+        ( line as u32, column )
+    }
+
+    /// Character offset of a line and UTF-16 column, columns past the end of line give the end of
+    /// line and lines past the end give the end of text.
+    pub fn offset(&self, line: u32, column: u32) -> u32 {
+        let Some( start ) = self.line_starts.get(line as usize).copied() else { return self.chars.len() as u32 };
+        let end = self.line_starts.get(line as usize + 1).map( |e| e - 1 ).unwrap_or(self.chars.len() as u32);
+        let mut units = 0;
+        for pos in start .. end {
+            if units >= column {
+                return pos
+            }
+            units += self.chars[pos as usize].len_utf16() as u32
+        }
+        end
+    }
+
+    /// Replaces the characters from 'start' to 'end' by text, or the whole text when there is no range.
+    pub fn change(&mut self, range: Option<( u32, u32 )>, text: &str) {
+        let text = match range {
+            Some( ( start, end ) ) => {
+                let mut res: String = self.chars[.. start as usize].iter().collect();
+                res.push_str(text);
+                res.extend( &self.chars[end.max(start) as usize ..] );
+                res
+            },
+            None => text.to_string()
+        };
+        self.set_text(&text)
+    }
+
+    fn set_text(&mut self, text: &str) {
+        self.text = text.replace("\r\n", "\n").replace('\r', "\n");
+        self.chars = self.text.chars().collect();
+        self.line_starts = vec![ 0 ];
+        self.line_starts.extend( self.chars.iter().enumerate().filter( |( _, c )| **c == '\n' ).map( |( i, _ )| i as u32 + 1 ) );
+    }
+}
+
+
+// UnitTests for documents /////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::lsp::document::Document;
+
+
+    #[test]
+    fn positions_count_utf16_units() {
+        let document = Document::new("file:///a.py", 1, "x = '😀é'\r\ny\n");
+        assert_eq!(document.text(), "x = '😀é'\ny\n");
+        assert_eq!(document.position(6), ( 0, 7 ));
+        assert_eq!(document.position(9), ( 1, 0 ));
+        assert_eq!(document.position(99), ( 2, 0 ));
+        assert_eq!(document.offset(0, 7), 6);
+        assert_eq!(document.offset(0, 50), 8);
+        assert_eq!(document.offset(1, 1), 10);
+        assert_eq!(document.offset(7, 0), 11);
+    }
+
+    #[test]
+    fn changes_replace_ranges() {
+        let mut document = Document::new("file:///a.py", 1, "a = 1\nb = 2\n");
+        let ( start, end ) = ( document.offset(1, 4), document.offset(1, 5) );
+        document.change(Some( ( start, end ) ), "22\r\nc = 3");
+        assert_eq!(document.text(), "a = 1\nb = 22\nc = 3\n");
+        document.change(None, "z");
+        assert_eq!(document.text(), "z");
+    }
+}
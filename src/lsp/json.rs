@@ -0,0 +1,335 @@
+use std::fmt;
+
+
+/// JSON value of the messages of the language server protocol. Objects keep their fields in order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<( String, Json )>)
+}
+
+static NULL: Json = Json::Null;
+
+/// Deepest nesting of arrays and objects read, so a hostile message can't overflow the stack.
+const MAX_DEPTH: usize = 512;
+
+
+impl Json {
+    /// Parses a whole JSON text, with nothing but whitespace after the value.
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut reader = JsonReader { chars: text.chars().collect(), pos: 0, depth: 0 };
+        let value = reader.value()?;
+        reader.skip_whitespace();
+        match reader.pos < reader.chars.len() {
+            true => Err( format!("unexpected character at {}", reader.pos) ),
+            _ => Ok( value )
+        }
+    }
+
+    /// Object with fields in the order given.
+    pub fn object(fields: Vec<( &str, Json )>) -> Json {
+        Json::Object( fields.into_iter().map( |( k, v )| ( k.to_string(), v ) ).collect() )
+    }
+
+    /// Field of an object, Null when missing or when value is no object.
+    pub fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object( fields ) => fields.iter().find( |( k, _ )| k == key ).map( |( _, v )| v ).unwrap_or(&NULL),
+            _ => &NULL
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Json::Null)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String( s ) => Some( s ),
+            _ => None
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Number( n ) if n.fract() == 0.0 => Some( *n as i64 ),
+            _ => None
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        self.as_i64().and_then( |n| u32::try_from(n).ok() )
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array( items ) => Some( items ),
+            _ => None
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Json {
+        Json::Bool(value)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(value: u32) -> Json {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(value: i64) -> Json {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Json {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Json {
+        Json::String(value)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(value: Vec<Json>) -> Json {
+        Json::Array(value)
+    }
+}
+
+/// Compact JSON text, numbers without fraction are written as integers.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool( b ) => write!(f, "{}", b),
+            Json::Number( n ) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number( n ) if n.is_finite() => write!(f, "{}", n),
+            Json::Number( _ ) => write!(f, "null"),
+            Json::String( s ) => write_string(f, s),
+            Json::Array( items ) => {
+                write!(f, "[")?;
+                for ( i, item ) in items.iter().enumerate() {
+                    write!(f, "{}{}", if i > 0 { "," } else { "" }, item)?
+                }
+                write!(f, "]")
+            },
+            Json::Object( fields ) => {
+                write!(f, "{{")?;
+                for ( i, ( key, value ) ) in fields.iter().enumerate() {
+                    write!(f, "{}", if i > 0 { "," } else { "" })?;
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, text: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in text.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if ( c as u32 ) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?
+        }
+    }
+    write!(f, "\"")
+}
+
+struct JsonReader {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize
+}
+
+impl JsonReader {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && matches!(self.chars[self.pos], ' ' | '\t' | '\n' | '\r') {
+            self.pos += 1
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.get(self.pos) {
+            Some( x ) if *x == c => {
+                self.pos += 1;
+                Ok( () )
+            },
+            _ => Err( format!("expected '{}' at {}", c, self.pos) )
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        if self.depth == MAX_DEPTH {
+            return Err( format!("nesting deeper than {} at {}", MAX_DEPTH, self.pos) )
+        }
+        self.depth += 1;
+        let res = self.item();
+        self.depth -= 1;
+        res
+    }
+
+    fn item(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.chars.get(self.pos) {
+            Some( '{' ) => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.chars.get(self.pos) == Some( &'}' ) {
+                    self.pos += 1;
+                    return Ok( Json::Object(fields) )
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    fields.push( ( key, self.value()? ) );
+                    self.skip_whitespace();
+                    match self.chars.get(self.pos) {
+                        Some( ',' ) => self.pos += 1,
+                        Some( '}' ) => {
+                            self.pos += 1;
+                            return Ok( Json::Object(fields) )
+                        },
+                        _ => return Err( format!("expected ',' or '}}' at {}", self.pos) )
+                    }
+                }
+            },
+            Some( '[' ) => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.chars.get(self.pos) == Some( &']' ) {
+                    self.pos += 1;
+                    return Ok( Json::Array(items) )
+                }
+                loop {
+                    items.push( self.value()? );
+                    self.skip_whitespace();
+                    match self.chars.get(self.pos) {
+                        Some( ',' ) => self.pos += 1,
+                        Some( ']' ) => {
+                            self.pos += 1;
+                            return Ok( Json::Array(items) )
+                        },
+                        _ => return Err( format!("expected ',' or ']' at {}", self.pos) )
+                    }
+                }
+            },
+            Some( '"' ) => Ok( Json::String(self.string()?) ),
+            Some( c ) if *c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while self.pos < self.chars.len() && matches!(self.chars[self.pos], '0' ..= '9' | '-' | '+' | '.' | 'e' | 'E') {
+                    self.pos += 1
+                }
+                let text: String = self.chars[start .. self.pos].iter().collect();
+                text.parse::<f64>().map( Json::Number ).map_err( |_| format!("invalid number at {}", start) )
+            },
+            _ => {
+                for ( word, value ) in [ ( "null", Json::Null ), ( "true", Json::Bool(true) ), ( "false", Json::Bool(false) ) ] {
+                    if self.chars[self.pos ..].iter().take(word.len()).copied().eq(word.chars()) {
+                        self.pos += word.len();
+                        return Ok( value )
+                    }
+                }
+                Err( format!("unexpected character at {}", self.pos) )
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.chars.get(self.pos) != Some( &'"' ) {
+            return Err( format!("expected string at {}", self.pos) )
+        }
+        self.pos += 1;
+        let mut res = String::new();
+        loop {
+            let c = *self.chars.get(self.pos).ok_or("unterminated string")?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok( res ),
+                '\\' => {
+                    let e = *self.chars.get(self.pos).ok_or("unterminated string")?;
+                    self.pos += 1;
+                    match e {
+                        'n' => res.push('\n'),
+                        'r' => res.push('\r'),
+                        't' => res.push('\t'),
+                        'b' => res.push('\x08'),
+                        'f' => res.push('\x0c'),
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            if ( 0xd800 .. 0xdc00 ).contains(&code) && self.chars[self.pos ..].starts_with(&[ '\\', 'u' ]) {
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                code = 0x10000 + ( ( code - 0xd800 ) << 10 ) + ( low.wrapping_sub(0xdc00) & 0x3ff )
+                            }
+                            res.push( char::from_u32(code).unwrap_or('\u{fffd}') )
+                        },
+                        c => res.push(c)
+                    }
+                },
+                c => res.push(c)
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let text: String = self.chars.iter().skip(self.pos).take(4).collect();
+        self.pos += 4;
+        u32::from_str_radix(&text, 16).map_err( |_| format!("invalid escape at {}", self.pos - 4) )
+    }
+}
+
+
+// UnitTests for JSON //////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::lsp::json::Json;
+
+
+    #[test]
+    fn values_are_parsed_and_written_back() {
+        let text = r#"{"id":1,"method":"a/b","params":{"x":[true,false,null,-2.5,"q\"\né😀"]}}"#;
+        let value = Json::parse(text).unwrap();
+        assert_eq!(value.get("id").as_i64(), Some( 1 ));
+        assert_eq!(value.get("method").as_str(), Some( "a/b" ));
+        assert_eq!(value.get("params").get("x").as_array().unwrap()[4].as_str(), Some( "q\"\né😀" ));
+        assert!(value.get("missing").get("x").is_null());
+        assert_eq!(value.to_string(), r#"{"id":1,"method":"a/b","params":{"x":[true,false,null,-2.5,"q\"\né😀"]}}"#);
+        assert_eq!(Json::parse(r#""\ud83d\ude00\u00e9""#).unwrap().as_str(), Some( "😀é" ));
+        assert_eq!(Json::parse(" [ ] ").unwrap(), Json::Array(Vec::new()));
+        assert!(Json::parse("{\"a\" 1}").is_err());
+        assert!(Json::parse("[1] x").is_err());
+    }
+
+    #[test]
+    fn nesting_is_capped() {
+        let nested = |n: usize| format!("{}{}", "[".repeat(n), "]".repeat(n));
+        assert!(Json::parse(&nested(512)).is_ok());
+        assert_eq!(Json::parse(&nested(513)), Err( "nesting deeper than 512 at 512".to_string() ));
+        assert!(Json::parse(&"[{\"a\":".repeat(200000)).is_err());
+    }
+}
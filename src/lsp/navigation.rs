@@ -0,0 +1,248 @@
+use crate::{ ASTNode, Token };
+use crate::api::{ Mode, parse, normalize_source };
+use crate::ast::nodes::{ Mod, Stmt, StmtKind, ExprKind, Constant };
+use crate::ast::visitor::{ Visitor, walk_stmt };
+use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+use crate::diagnostics::Diagnostic;
+use crate::lint::linter::{ walk_nodes, node_tokens };
+use crate::lint::rules::BUILTINS;
+use crate::semantic::symbols::{ ScopeId, Symbol, SymbolTable };
+
+
+/// Text shown when hovering a name, in markdown, and the span of the name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hover {
+    pub span: ( u32, u32 ),
+    pub text: String
+}
+
+/// Answers the queries of editors about the names of one module, using the scopes of its symbol
+/// table. Positions are character offsets, a name is found when the offset is in it or at its end.
+pub struct PythonCoreNavigator {
+    source: String,
+    names: Vec<( u32, u32 )>,
+    tree: Box<ASTNode>,
+    module: Mod,
+    symbols: SymbolTable
+}
+
+
+impl PythonCoreNavigator {
+    /// Parses and analyzes source, or returns the first syntax error.
+    pub fn new(source: &str) -> Result<PythonCoreNavigator, Diagnostic> {
+        let source = normalize_source(source);
+        let res = parse(&source, Mode::Exec);
+        let tree = match res.tree {
+            Some( t ) => t,
+            None => return Err( res.diagnostics.into_iter().next().unwrap_or_else( || Diagnostic::error(0, 0, "invalid syntax") ) )
+        };
+        let module = PythonCoreLowering::new(&source).lower_mod(&tree).map_err( |e| Diagnostic::from_error_text(&e, 0) )?;
+        let symbols = SymbolTable::build(&module, &source);
+        let names = res.tokens.iter().filter( |t| matches!(***t, Token::AtomName(..)) ).filter_map( |t| t.get_span() ).collect();
+        Ok( PythonCoreNavigator { source, names, tree, module, symbols } )
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn tree(&self) -> &ASTNode {
+        &self.tree
+    }
+
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Span of the name token at offset.
+    pub fn name_at(&self, offset: u32) -> Option<( u32, u32 )> {
+        self.names.iter().find( |( s, e )| *s <= offset && offset < *e )
+            .or_else( || self.names.iter().find( |( _, e )| *e == offset ) ).copied()
+    }
+
+    /// Scope and symbol where the name at offset is bound, none for attributes, keywords of calls,
+    /// builtins and undefined names.
+    pub fn binding_at(&self, offset: u32) -> Option<( ScopeId, &Symbol )> {
+        let ( start, _ ) = self.name_at(offset)?;
+        let ( scope, symbol ) = self.symbols.symbol_at(start)?;
+        self.symbols.resolve(scope, &symbol.name)
+    }
+
+    /// Spans where the name at offset is bound: assignments, parameters, imports, definitions and
+    /// targets of loops and comprehensions, also in functions that declare it 'global' or 'nonlocal'.
+    pub fn definition(&self, offset: u32) -> Vec<( u32, u32 )> {
+        let mut res: Vec<( u32, u32 )> = self.same_binding(offset).iter().flat_map( |( _, s )| s.bindings.iter().copied() ).collect();
+        res.sort();
+        res
+    }
+
+    /// Spans of every occurrence of the name at offset that resolves to the same binding, in source
+    /// order, bindings only when 'include_declaration' is set.
+    pub fn references(&self, offset: u32, include_declaration: bool) -> Vec<( u32, u32 )> {
+        let symbols = self.same_binding(offset);
+        let mut res: Vec<( u32, u32 )> = symbols.iter().flat_map( |( _, s )| s.occurrences() ).collect();
+        if !include_declaration {
+            res.retain( |span| !symbols.iter().any( |( _, s )| s.bindings.contains(span) ) )
+        }
+        res.sort();
+        res.dedup();
+        res
+    }
+
+    /// Scopes and symbols of every scope where the name at offset resolves to the same binding.
+    pub fn same_binding(&self, offset: u32) -> Vec<( ScopeId, &Symbol )> {
+        let Some( ( target, symbol ) ) = self.binding_at(offset) else { return Vec::new() };
+        self.symbols.scopes.iter().enumerate()
+            .filter( |( id, _ )| self.symbols.resolve(*id, &symbol.name).is_some_and( |( r, _ )| r == target ) )
+            .filter_map( |( id, scope )| scope.lookup(&symbol.name).map( |s| ( id, s ) ) ).collect()
+    }
+
+    /// Hover of the name at offset: the header and docstring of a function or class, or the kind
+    /// of other names.
+    pub fn hover(&self, offset: u32) -> Option<Hover> {
+        let span = self.name_at(offset)?;
+        let name: String = self.source.chars().skip(span.0 as usize).take(( span.1 - span.0 ) as usize).collect();
+        let Some( ( _, symbol ) ) = self.binding_at(offset) else {
+            return match self.symbols.symbol_at(span.0).is_some() && BUILTINS.contains(&name.as_str()) {
+                true => Some( Hover { span, text: format!("```python\n(builtin) {}\n```", name) } ),
+                _ => None
+            }
+        };
+        for binding in &symbol.bindings {
+            if let Some( header ) = self.definition_header(*binding) {
+                let mut text = format!("```python\n{}\n```", header);
+                if let Some( doc ) = self.docstring(&symbol.name, *binding) {
+                    text.push_str(&format!("\n\n---\n\n{}", doc))
+                }
+                return Some( Hover { span, text } )
+            }
+        }
+        let kind = match symbol {
+            s if s.is_parameter => "parameter",
+            s if s.is_imported => "import",
+            _ => "variable"
+        };
+        Some( Hover { span, text: format!("```python\n({}) {}\n```", kind, symbol.name) } )
+    }
+
+    /// Header of the function or class whose name is at span, from 'def' or 'class' to the colon,
+    /// with the whitespace between tokens reduced to single spaces and comments left out.
+    fn definition_header(&self, span: ( u32, u32 )) -> Option<String> {
+        let mut res = None;
+        walk_nodes(&self.tree, &mut |node| {
+            let ( name, body ) = match node {
+                ASTNode::FuncDef(_, _, _, name, .., body) |
+                ASTNode::ClassDef(_, _, _, name, .., body) => ( name, body ),
+                _ => return
+            };
+            if name.get_span() != Some( span ) {
+                return
+            }
+            let body_start = body.get_span().0;
+            let mut text = String::new();
+            let mut last = None;
+            for token in node_tokens(node) {
+                let Some( ( start, end ) ) = token.get_span() else { continue };
+                if start >= body_start || matches!(token, Token::Newline(..)) {
+                    break
+                }
+                if last.is_some_and( |l| l < start ) {
+                    text.push(' ')
+                }
+                text.push_str(&token.text().unwrap_or_default());
+                last = Some( end )
+            }
+            res = Some( text.trim_end_matches(':').to_string() )
+        });
+        res
+    }
+
+    /// Docstring of the function or class named 'name' that binds it at span, cleaned like
+    /// CPython 'inspect.cleandoc'.
+    fn docstring(&self, name: &str, span: ( u32, u32 )) -> Option<String> {
+        let mut finder = DocstringFinder { name, span, found: None };
+        finder.visit_mod(&self.module);
+        finder.found.map( |d| clean_docstring(&d) )
+    }
+}
+
+struct DocstringFinder<'a> {
+    name: &'a str,
+    span: ( u32, u32 ),
+    found: Option<String>
+}
+
+impl Visitor for DocstringFinder<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        let ( name, body ) = match &stmt.node {
+            StmtKind::FunctionDef { name, body, .. } |
+            StmtKind::AsyncFunctionDef { name, body, .. } |
+            StmtKind::ClassDef { name, body, .. } => ( name, body ),
+            _ => return walk_stmt(self, stmt)
+        };
+        let header = stmt.location.start <= self.span.0 && body.first().is_some_and( |b| self.span.1 <= b.location.start );
+        if name == self.name && header {
+            if let Some( StmtKind::Expr { value } ) = body.first().map( |s| &s.node ) {
+                if let ExprKind::Constant { value: Constant::Str( doc ), .. } = &value.node {
+                    self.found = Some( doc.clone() )
+                }
+            }
+            return
+        }
+        walk_stmt(self, stmt)
+    }
+}
+
+/// Docstring with tabs expanded, the indentation common to the lines after the first removed and
+/// leading and trailing empty lines dropped.
+pub fn clean_docstring(doc: &str) -> String {
+    let lines: Vec<String> = doc.replace('\t', "        ").lines().map( |l| l.trim_end().to_string() ).collect();
+    let margin = lines.iter().skip(1).filter( |l| !l.is_empty() ).map( |l| l.len() - l.trim_start().len() ).min().unwrap_or(0);
+    let mut res: Vec<&str> = lines.iter().enumerate()
+        .map( |( i, l )| if i == 0 { l.trim_start() } else { l.get(margin ..).unwrap_or("") } ).collect();
+    while res.last().is_some_and( |l| l.is_empty() ) {
+        res.pop();
+    }
+    let first = res.iter().position( |l| !l.is_empty() ).unwrap_or(res.len());
+    res[first ..].join("\n")
+}
+
+
+// UnitTests for navigation ////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::lsp::navigation::{ PythonCoreNavigator, clean_docstring };
+
+
+    fn spans(source: &str, spans: Vec<( u32, u32 )>) -> Vec<String> {
+        spans.iter().map( |( s, e )| format!("{} {}", s, source.chars().skip(*s as usize).take(( e - s ) as usize).collect::<String>()) ).collect()
+    }
+
+    #[test]
+    fn names_resolve_to_their_bindings() {
+        let source = "import os.path as p, sys\nx = 1\ndef f(a, *b, k=x, **kw):\n    global x\n    x = [y for y in a]\n    return lambda z: z + p\nclass C:\n    x = 2\n    def m(self): return x\n";
+        let navigator = PythonCoreNavigator::new(source).unwrap();
+        assert_eq!(spans(source, navigator.definition(117)), vec![ "18 p" ]);
+        assert_eq!(spans(source, navigator.definition(163)), vec![ "25 x", "73 x" ]);
+        assert_eq!(spans(source, navigator.definition(78)), vec![ "84 y" ]);
+        assert_eq!(spans(source, navigator.references(25, true)), vec![ "25 x", "46 x", "67 x", "73 x", "162 x" ]);
+        assert_eq!(spans(source, navigator.references(132, true)), vec![ "132 x" ]);
+        assert_eq!(spans(source, navigator.references(37, false)), vec![ "89 a" ]);
+        assert!(navigator.definition(10).is_empty());
+        assert!(navigator.references(3, true).is_empty());
+    }
+
+    #[test]
+    fn hover_shows_headers_and_docstrings() {
+        let source = "def g(a,\n      b=1) -> int:  # note\n    \"\"\"Doc.\n\n    More.\n    \"\"\"\nclass C(g):\n    pass\nx = g(1) + len(C.y)\n";
+        let navigator = PythonCoreNavigator::new(source).unwrap();
+        let hover = navigator.hover(93).unwrap();
+        assert_eq!(( hover.span, hover.text.as_str() ), ( ( 92, 93 ), "```python\ndef g(a, b=1) -> int\n```\n\n---\n\nDoc.\n\nMore." ));
+        assert_eq!(navigator.hover(103).unwrap().text, "```python\nclass C(g)\n```");
+        assert_eq!(navigator.hover(88).unwrap().text, "```python\n(variable) x\n```");
+        assert_eq!(navigator.hover(99).unwrap().text, "```python\n(builtin) len\n```");
+        assert!(navigator.hover(105).is_none());
+        assert_eq!(clean_docstring("  First.\n\t  Second.\n      Third.\n\n"), "First.\n    Second.\nThird.");
+    }
+}
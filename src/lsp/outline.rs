@@ -0,0 +1,318 @@
+use crate::ASTNode;
+use crate::parser::tokens::Token;
+use crate::lint::linter::{ walk_nodes, node_tokens, token_span };
+use crate::parser::nodes::NodeChild;
+use crate::parser::sexpr::ordered_children;
+use crate::parser::trivias::Trivia;
+
+
+/// Kind of a symbol of the outline, mapped to the symbol kinds of the protocol by the server.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymbolKind {
+    Class,
+    Method,
+    AsyncMethod,
+    Function,
+    AsyncFunction,
+    Variable,
+    MainBlock
+}
+
+/// Definition of a module with the span of its whole statement, decorators included, and the span
+/// of its name. Children are the definitions in the body of classes and functions, and in the body
+/// of an 'if __name__ == "__main__"' block, whose selection is its condition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutlineSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub span: ( u32, u32 ),
+    pub selection: ( u32, u32 ),
+    pub children: Vec<OutlineSymbol>
+}
+
+
+/// Classes, functions and the variables assigned at module and class level, and the blocks run
+/// as main module, in source order.
+pub fn document_symbols(tree: &ASTNode) -> Vec<OutlineSymbol> {
+    let mut res = Vec::new();
+    collect_symbols(tree, None, &mut res);
+    res
+}
+
+/// Collects the symbols of the statements below node, 'container' is the kind of the innermost
+/// class or function around them.
+fn collect_symbols(node: &ASTNode, container: Option<SymbolKind>, res: &mut Vec<OutlineSymbol>) {
+    let span = code_span(node);
+    let mut definition = node;
+    let mut is_async = false;
+    while let ASTNode::Decorated(_, _, _, d) | ASTNode::AsyncStmt(_, _, _, d) = definition {
+        is_async |= matches!(definition, ASTNode::AsyncStmt(..));
+        definition = d
+    }
+    let ( name, kind, body ) = match definition {
+        ASTNode::FuncDef(_, _, _, name, .., body) if container == Some( SymbolKind::Class ) => match is_async {
+            true => ( name, SymbolKind::AsyncMethod, body ),
+            _ => ( name, SymbolKind::Method, body )
+        },
+        ASTNode::FuncDef(_, _, _, name, .., body) => match is_async {
+            true => ( name, SymbolKind::AsyncFunction, body ),
+            _ => ( name, SymbolKind::Function, body )
+        },
+        ASTNode::ClassDef(_, _, _, name, .., body) => ( name, SymbolKind::Class, body ),
+        ASTNode::IfStmt(_, _, _, test, _, body, elifs, orelse) if container.is_none() && is_main_test(test) => {
+            let mut children = Vec::new();
+            collect_symbols(body, None, &mut children);
+            let name = "__main__".to_string();
+            res.push( OutlineSymbol { name, kind: SymbolKind::MainBlock, span, selection: code_span(test), children } );
+            for clause in elifs.iter().chain(orelse) {
+                collect_symbols(clause, container, res)
+            }
+            return
+        },
+        ASTNode::AssignmentStmt(_, _, first, rest, _) if !is_function(container) => {
+            let targets = std::iter::once( &**first ).chain( rest.iter().map( |r| &*r.1 ) ).take(rest.len());
+            for target in targets {
+                res.extend( target_names(target).into_iter().map( |t| variable(t, span) ) )
+            }
+            return
+        },
+        ASTNode::AnnAssignStmt(_, _, target, ..) if !is_function(container) => {
+            res.extend( target_names(target).into_iter().map( |t| variable(t, span) ) );
+            return
+        },
+        _ => {
+            for child in ordered_children(node) {
+                if let NodeChild::Node( n ) = child {
+                    collect_symbols(n, container, res)
+                }
+            }
+            return
+        }
+    };
+    let mut children = Vec::new();
+    collect_symbols(body, Some( kind ), &mut children);
+    res.push( OutlineSymbol { name: name_text(name), kind, span, selection: name.get_span().unwrap_or(span), children } )
+}
+
+/// Span from the first to the last token of node that is no line end.
+fn code_span(node: &ASTNode) -> ( u32, u32 ) {
+    let spans: Vec<( u32, u32 )> = node_tokens(node).into_iter()
+        .filter( |t| !matches!(t, Token::Newline(..)) ).filter_map( |t| t.get_span() ).collect();
+    match ( spans.first(), spans.last() ) {
+        ( Some( first ), Some( last ) ) => ( first.0, last.1 ),
+        _ => token_span(node)
+    }
+}
+
+fn is_function(container: Option<SymbolKind>) -> bool {
+    matches!(container, Some( SymbolKind::Function | SymbolKind::AsyncFunction | SymbolKind::Method | SymbolKind::AsyncMethod ))
+}
+
+/// Whether test is '__name__ == "__main__"', in either order.
+fn is_main_test(test: &ASTNode) -> bool {
+    let ASTNode::EqualComparison(_, _, left, _, right) = test else { return false };
+    let name = |node: &ASTNode| matches!(node, ASTNode::AtomName(_, _, t) if t.text().as_deref() == Some( "__name__" ));
+    let main = |node: &ASTNode| match node {
+        ASTNode::AtomString(_, _, parts) => matches!(parts.as_slice(), [ t ] if matches!(&**t, Token::AtomString(_, _, _, text, prefix)
+            if prefix.is_none() && matches!(text.as_str(), "'__main__'" | "\"__main__\"" ))),
+        _ => false
+    };
+    ( name(left) && main(right) ) || ( name(right) && main(left) )
+}
+
+/// Names bound by an assignment target, alone or in tuples and lists, but not attributes or items.
+fn target_names(target: &ASTNode) -> Vec<&Token> {
+    match target {
+        ASTNode::AtomName(_, _, name) => vec![ &**name ],
+        ASTNode::TestListStarExpr(..) | ASTNode::TestListComp(..) | ASTNode::ExprList(..) | ASTNode::TestList(..) |
+        ASTNode::AtomTuple(..) | ASTNode::AtomList(..) | ASTNode::StarExpr(..) => {
+            ordered_children(target).into_iter().flat_map( |c| match c {
+                NodeChild::Node( n ) => target_names(n),
+                _ => Vec::new()
+            }).collect()
+        },
+        _ => Vec::new()
+    }
+}
+
+fn variable(name: &Token, span: ( u32, u32 )) -> OutlineSymbol {
+    let selection = name.get_span().unwrap_or(span);
+    OutlineSymbol { name: name_text(name), kind: SymbolKind::Variable, span, selection, children: Vec::new() }
+}
+
+fn name_text(name: &Token) -> String {
+    name.text().unwrap_or_default()
+}
+
+/// What a folding range folds, the server tells comments and imports to the client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FoldingKind {
+    Block,
+    Brackets,
+    Comment,
+    Imports,
+    String
+}
+
+/// Span of source that an editor can fold, over several lines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FoldingRange {
+    pub start: u32,
+    pub end: u32,
+    pub kind: FoldingKind
+}
+
+
+/// Folding ranges of a module sorted by start: the indented blocks of compound statements from the
+/// end of the line of their header, brackets and triple-quoted strings over several lines, groups
+/// of comments on consecutive lines of their own and groups of import statements next to each other.
+pub fn folding_ranges(tree: &ASTNode) -> Vec<FoldingRange> {
+    let mut res = Vec::new();
+    let range = |( start, end ): ( u32, u32 ), kind| FoldingRange { start, end, kind };
+    walk_nodes(tree, &mut |node| {
+        if let ASTNode::SuiteStmt(..) | ASTNode::FuncBodySuite(..) = node {
+            if let Some( start ) = node_tokens(node).first().and_then( |t| t.get_span() ) {
+                res.push( range(( start.0, code_span(node).1 ), FoldingKind::Block) )
+            }
+        }
+        let statements = match node {
+            ASTNode::FileInput(_, _, statements, ..) | ASTNode::SuiteStmt(_, _, _, _, statements, _) |
+            ASTNode::FuncBodySuite(_, _, _, _, _, _, statements, _) => statements,
+            _ => return
+        };
+        let mut statements: Vec<&ASTNode> = statements.iter().map( |s| &**s ).collect();
+        statements.sort_by_key( |s| s.get_span().0 );
+        for group in statements.split( |s| !is_import(s) ) {
+            if let [ first, .., last ] = group {
+                res.push( range(( code_span(first).0, code_span(last).1 ), FoldingKind::Imports) )
+            }
+        }
+    });
+    let mut open: Vec<( u32, bool )> = Vec::new();
+    let mut comments: Vec<( u32, u32 )> = Vec::new();
+    let mut lines = 0;
+    let mut code = false;
+    for token in node_tokens(tree) {
+        let mut newline = matches!(token, Token::AtomString(_, _, _, text, _) if text.contains('\n'));
+        let mut trivias: Vec<&Trivia> = token.get_trivia().into_iter().flatten().map( |t| &**t ).collect();
+        trivias.sort_by_key( |t| match t {
+            Trivia::WhiteSpace(start, ..) | Trivia::Newline(start, ..) | Trivia::LineContinuation(start, ..) | Trivia::Comment(start, ..) => *start
+        });
+        for trivia in trivias {
+            match trivia {
+                Trivia::Newline(..) => {
+                    newline = true;
+                    lines += 1;
+                    code = false
+                },
+                Trivia::Comment(start, end, _) if !code => {
+                    if comments.is_empty() || lines != 1 {
+                        push_comments(&comments, &mut res);
+                        comments.clear()
+                    }
+                    comments.push( ( *start, *end ) );
+                    lines = 0
+                },
+                _ => { }
+            }
+        }
+        if newline {
+            open.iter_mut().for_each( |o| o.1 = true )
+        }
+        let Some( span ) = token.get_span() else { continue };
+        match token {
+            Token::Newline(..) => {
+                lines += 1;
+                code = false;
+                continue
+            },
+            Token::EOF(..) => continue,
+            Token::PyLeftParen(..) | Token::PyLeftBracket(..) | Token::PyLeftCurly(..) => open.push( ( span.0, false ) ),
+            Token::PyRightParen(..) | Token::PyRightBracket(..) | Token::PyRightCurly(..) => {
+                if let Some( ( start, true ) ) = open.pop() {
+                    res.push( range(( start, span.1 ), FoldingKind::Brackets) )
+                }
+            },
+            Token::AtomString(_, _, _, text, _) if text.contains('\n') => res.push( range(span, FoldingKind::String) ),
+            _ => { }
+        }
+        push_comments(&comments, &mut res);
+        comments.clear();
+        code = true
+    }
+    push_comments(&comments, &mut res);
+    res.sort_by_key( |r| ( r.start, std::cmp::Reverse( r.end ) ) );
+    res
+}
+
+fn push_comments(comments: &[( u32, u32 )], res: &mut Vec<FoldingRange>) {
+    if let [ first, _, .. ] = comments {
+        res.push( FoldingRange { start: first.0, end: comments[comments.len() - 1].1, kind: FoldingKind::Comment } )
+    }
+}
+
+/// Whether statement is a line of imports only.
+fn is_import(statement: &ASTNode) -> bool {
+    match statement {
+        ASTNode::SimpleStmtList(_, _, statements, ..) => {
+            statements.iter().all( |s| matches!(**s, ASTNode::ImportNameStmt(..) | ASTNode::ImportFromStmt(..)) )
+        },
+        _ => false
+    }
+}
+
+// UnitTests for outline ///////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::api::parse_module;
+    use crate::lsp::outline::{ FoldingKind, SymbolKind, document_symbols, folding_ranges };
+
+
+    #[test]
+    fn symbols_nest_in_classes_and_functions() {
+        let source = "x = y = 1\na, (b, c.d) = t\nclass A:\n    q: int = 1\n    @staticmethod\n    async def f():\n        z = 1\n        def g(): pass\n";
+        let symbols = document_symbols(&parse_module(source).tree.unwrap());
+        let names: Vec<( &str, SymbolKind )> = symbols.iter().map( |s| ( s.name.as_str(), s.kind ) ).collect();
+        assert_eq!(names, vec![ ( "x", SymbolKind::Variable ), ( "y", SymbolKind::Variable ), ( "a", SymbolKind::Variable ),
+            ( "b", SymbolKind::Variable ), ( "A", SymbolKind::Class ) ]);
+        assert_eq!(( symbols[0].span, symbols[1].selection ), ( ( 0, 9 ), ( 4, 5 ) ));
+        let class = &symbols[4];
+        assert_eq!(( class.span, class.selection ), ( ( 26, 122 ), ( 32, 33 ) ));
+        assert_eq!(class.children.iter().map( |s| ( s.name.as_str(), s.kind ) ).collect::<Vec<_>>(),
+            vec![ ( "q", SymbolKind::Variable ), ( "f", SymbolKind::AsyncMethod ) ]);
+        let method = &class.children[1];
+        assert_eq!(method.span, ( 54, 122 ));
+        assert_eq!(method.children.iter().map( |s| ( s.name.as_str(), s.kind ) ).collect::<Vec<_>>(), vec![ ( "g", SymbolKind::Function ) ]);
+    }
+
+    #[test]
+    fn main_blocks_and_async_functions() {
+        let source = "async def f(): pass\nif __name__ == '__main__':\n    x = 1\n    def g(): pass\nelse:\n    y = 2\nif \"__main__\" == __name__: pass\n";
+        let symbols = document_symbols(&parse_module(source).tree.unwrap());
+        let names: Vec<( &str, SymbolKind )> = symbols.iter().map( |s| ( s.name.as_str(), s.kind ) ).collect();
+        assert_eq!(names, vec![ ( "f", SymbolKind::AsyncFunction ), ( "__main__", SymbolKind::MainBlock ), ( "y", SymbolKind::Variable ),
+            ( "__main__", SymbolKind::MainBlock ) ]);
+        assert_eq!(( symbols[1].span, symbols[1].selection ), ( ( 20, 90 ), ( 23, 45 ) ));
+        assert_eq!(symbols[1].children.iter().map( |s| ( s.name.as_str(), s.kind ) ).collect::<Vec<_>>(),
+            vec![ ( "x", SymbolKind::Variable ), ( "g", SymbolKind::Function ) ]);
+    }
+
+    #[test]
+    fn blocks_fold_from_their_header() {
+        let source = "if x:\n    a = 1\n    b = 2\n\nwhile y: pass\ndef f():\n    return 1\n";
+        let ranges: Vec<( u32, u32, FoldingKind )> = folding_ranges(&parse_module(source).tree.unwrap()).iter().map( |r| ( r.start, r.end, r.kind ) ).collect();
+        assert_eq!(ranges, vec![ ( 5, 25, FoldingKind::Block ), ( 49, 62, FoldingKind::Block ) ]);
+    }
+
+    #[test]
+    fn brackets_comments_imports_and_strings_fold() {
+        let source = "import a\nfrom b import (c,\n    d)\n# one\n  # two\nx = [1, (2, 3),  # three\n    \"\"\"s\n\"\"\"]\n# four\nimport e\n";
+        let ranges: Vec<( String, FoldingKind )> = folding_ranges(&parse_module(source).tree.unwrap()).iter()
+            .map( |r| ( source.chars().skip(r.start as usize).take(( r.end - r.start ) as usize).collect(), r.kind ) ).collect();
+        assert_eq!(ranges, vec![
+            ( "import a\nfrom b import (c,\n    d)".to_string(), FoldingKind::Imports ), ( "(c,\n    d)".to_string(), FoldingKind::Brackets ),
+            ( "# one\n  # two".to_string(), FoldingKind::Comment ), ( "[1, (2, 3),  # three\n    \"\"\"s\n\"\"\"]".to_string(), FoldingKind::Brackets ),
+            ( "\"\"\"s\n\"\"\"".to_string(), FoldingKind::String ) ]);
+    }
+}
@@ -0,0 +1,304 @@
+use crate::{ ASTNode, Token };
+use crate::api::tokenize;
+use crate::diagnostics::Diagnostic;
+use crate::edits::TextEdit;
+use crate::lint::linter::walk_nodes;
+use crate::lint::rules::all_strings;
+use crate::lsp::navigation::PythonCoreNavigator;
+use crate::parser::nodes::NodeChild;
+use crate::parser::sexpr::ordered_children;
+use crate::semantic::symbols::{ ScopeId, ScopeKind, SymbolTable };
+
+
+/// Names with their spans, parameters of a function or keyword arguments of a call.
+type Names = Vec<( String, ( u32, u32 ) )>;
+
+/// Edits renaming the name at offset to 'new_name' everywhere it resolves to the same binding:
+/// in scopes declaring it 'global' or 'nonlocal', as keyword argument of calls to the function
+/// whose parameter it is, and in the strings of '__all__' for module names. Names imported without
+/// 'as' get an alias instead. Only name tokens change, so comments and whitespace stay as they are.
+/// Fails when there is no name bound in the module at offset, when the name is bound in a class,
+/// since attribute references such as 'self.name' are not resolved, when the new name is no
+/// identifier, or when it is already used where the renamed name would then be found or hide another one.
+pub fn rename(navigator: &PythonCoreNavigator, offset: u32, new_name: &str) -> Result<Vec<TextEdit>, Diagnostic> {
+    let source: Vec<char> = navigator.source().chars().collect();
+    let span = navigator.name_at(offset).ok_or_else( || Diagnostic::error(offset, offset, "no name to rename at position") )?;
+    let old: String = source[span.0 as usize .. span.1 as usize].iter().collect();
+    let at = keyword_parameter(navigator, span, &old).unwrap_or(span.0);
+    let Some( ( target, _ ) ) = navigator.binding_at(at) else {
+        return Err( Diagnostic::error(span.0, span.1, &format!("cannot rename '{}', it is not bound in this module", old)) )
+    };
+    if navigator.symbols().scope(target).kind == ScopeKind::Class {
+        let message = format!("cannot rename '{}', it is bound in {} and its attribute references are not known", old, describe(navigator.symbols(), target));
+        return Err( Diagnostic::error(span.0, span.1, &message) )
+    }
+    if !is_identifier(new_name) {
+        return Err( Diagnostic::error(span.0, span.1, &format!("'{}' is not a valid name", new_name)) )
+    }
+    if new_name == old {
+        return Ok( Vec::new() )
+    }
+    let symbols = navigator.same_binding(at);
+    let scopes: Vec<ScopeId> = symbols.iter().map( |( id, _ )| *id ).collect();
+    if let Some( message ) = collision(navigator.symbols(), target, &scopes, new_name) {
+        return Err( Diagnostic::error(span.0, span.1, &format!("cannot rename '{}' to '{}', {}", old, new_name, message)) )
+    }
+
+    let mut edits = Vec::new();
+    let mut parameters = Vec::new();
+    for ( _, symbol ) in &symbols {
+        for occurrence in symbol.occurrences() {
+            if symbol.is_parameter && symbol.bindings.contains(&occurrence) {
+                parameters.push(occurrence)
+            }
+            match import_binding(navigator.tree(), occurrence) {
+                Some( true ) => edits.push( TextEdit::new(occurrence.0, occurrence.1, &format!("{} as {}", old, new_name)) ),
+                Some( false ) => {
+                    let message = format!("cannot rename '{}', it is bound by the import of a dotted module name", old);
+                    return Err( Diagnostic::error(occurrence.0, occurrence.1, &message) )
+                },
+                None => edits.push( TextEdit::new(occurrence.0, occurrence.1, new_name) )
+            }
+        }
+    }
+    for parameter in parameters {
+        edits.extend( keyword_arguments(navigator, parameter, &old).into_iter().map( |( s, e )| TextEdit::new(s, e, new_name) ) )
+    }
+    if target == 0 {
+        let ( tokens, _ ) = tokenize(navigator.source());
+        edits.extend( all_strings(&tokens).into_iter().filter( |( text, _ )| *text == old ).map( |( _, ( s, e ) )| TextEdit::new(s, e, new_name) ) )
+    }
+    edits.sort_by_key( |e| ( e.start, e.end ) );
+    edits.dedup();
+    Ok( edits )
+}
+
+/// Name is one identifier token, which leaves out keywords but not soft keywords.
+fn is_identifier(name: &str) -> bool {
+    let ( tokens, error ) = tokenize(name);
+    error.is_none() && matches!(tokens.first().map( |t| &**t ), Some( Token::AtomName(.., text) ) if text.as_str() == name)
+        && tokens.iter().skip(1).all( |t| matches!(**t, Token::Newline(..) | Token::EOF(..)) )
+}
+
+/// Reason why 'name' cannot replace the name bound in scope 'target' and used in 'scopes'.
+fn collision(table: &SymbolTable, target: ScopeId, scopes: &[ScopeId], name: &str) -> Option<String> {
+    for id in scopes {
+        if table.lookup(*id, name).is_some() {
+            return Some( format!("'{}' is already used in {}", name, describe(table, *id)) )
+        }
+        let mut current = table.scope(*id).parent.filter( |_| *id != target );
+        while let Some( parent ) = current.filter( |p| *p != target ) {
+            let scope = table.scope(parent);
+            if scope.kind != ScopeKind::Class && scope.lookup(name).is_some_and( |s| s.is_bound() ) {
+                return Some( format!("'{}' would be bound by {}", name, describe(table, parent)) )
+            }
+            current = scope.parent
+        }
+    }
+    for id in ( 0 .. table.scopes.len() ).filter( |id| *id != target && is_inside(table, *id, target) ) {
+        if table.lookup(id, name).is_some() {
+            match table.resolve(id, name) {
+                Some( ( bound, _ ) ) if bound != target && is_inside(table, bound, target) => { },
+                _ => return Some( format!("it would hide '{}' used in {}", name, describe(table, id)) )
+            }
+        }
+    }
+    None
+}
+
+fn is_inside(table: &SymbolTable, scope: ScopeId, ancestor: ScopeId) -> bool {
+    let mut current = Some( scope );
+    while let Some( id ) = current {
+        if id == ancestor {
+            return true
+        }
+        current = table.scope(id).parent
+    }
+    false
+}
+
+fn describe(table: &SymbolTable, id: ScopeId) -> String {
+    let scope = table.scope(id);
+    match scope.kind {
+        ScopeKind::Module => "the module".to_string(),
+        ScopeKind::Class => format!("class '{}'", scope.name),
+        ScopeKind::Function => format!("function '{}'", scope.name),
+        ScopeKind::Lambda => "a lambda".to_string(),
+        ScopeKind::Comprehension => "a comprehension".to_string()
+    }
+}
+
+/// For a name bound by an import without 'as', whether it can get an alias, which is not the case
+/// for the first name of a dotted module name. None for other names.
+fn import_binding(tree: &ASTNode, span: ( u32, u32 )) -> Option<bool> {
+    let mut res = None;
+    walk_nodes(tree, &mut |node| {
+        match node {
+            ASTNode::ImportAsName(_, _, name, None) if name.get_span() == Some( span ) => res = Some( true ),
+            ASTNode::DottedAsNameStmt(_, _, dotted, None) => {
+                let names: Vec<( u32, u32 )> = ordered_children(dotted).iter().filter_map( |c| match c {
+                    NodeChild::Token( t @ Token::AtomName(..) ) => t.get_span(),
+                    _ => None
+                }).collect();
+                if names.first() == Some( &span ) {
+                    res = Some( names.len() == 1 )
+                }
+            },
+            _ => { }
+        }
+    });
+    res
+}
+
+/// Names and spans of the parameters of the function whose name is at 'definition', or of the
+/// '__init__' method of the class whose name is there.
+fn parameters(tree: &ASTNode, definition: ( u32, u32 )) -> Names {
+    let mut res = Vec::new();
+    walk_nodes(tree, &mut |node| {
+        let function = match node {
+            ASTNode::FuncDef(_, _, _, name, ..) if name.get_span() == Some( definition ) => node,
+            ASTNode::ClassDef(_, _, _, name, .., body) if name.get_span() == Some( definition ) => {
+                let init = ordered_children(body).into_iter().find_map( |c| match c {
+                    NodeChild::Node( n ) => init_method(n),
+                    _ => None
+                });
+                match init {
+                    Some( f ) => f,
+                    None => return
+                }
+            },
+            _ => return
+        };
+        if let ASTNode::FuncDef(_, _, _, _, Some( params ), ..) = function {
+            walk_nodes(params, &mut |p| {
+                if let ASTNode::TFPDef(_, _, name, _) = p {
+                    if let ( Some( text ), Some( span ) ) = ( name.text(), name.get_span() ) {
+                        res.push( ( text, span ) )
+                    }
+                }
+            })
+        }
+    });
+    res
+}
+
+fn init_method(node: &ASTNode) -> Option<&ASTNode> {
+    match node {
+        ASTNode::Decorated(_, _, _, d) => init_method(d),
+        ASTNode::FuncDef(_, _, _, name, ..) if name.text().as_deref() == Some( "__init__" ) => Some( node ),
+        _ => None
+    }
+}
+
+/// Calls of a name with the spans of the definitions it resolves to and the keyword arguments of
+/// the call, names and spans.
+fn calls(navigator: &PythonCoreNavigator) -> Vec<( Vec<( u32, u32 )>, Names )> {
+    let mut res = Vec::new();
+    walk_nodes(navigator.tree(), &mut |node| {
+        let ASTNode::AtomExpr(_, _, _, atom, _) = node else { return };
+        let ASTNode::AtomName(_, _, callee) = &**atom else { return };
+        let children: Vec<&ASTNode> = ordered_children(node).into_iter().filter_map( |c| match c {
+            NodeChild::Node( n ) => Some( n ),
+            _ => None
+        }).collect();
+        let Some( ASTNode::CallTrailer(..) ) = children.get(1) else { return };
+        let mut keywords = Vec::new();
+        for child in ordered_children(children[1]) {
+            let NodeChild::Node( ASTNode::ArgList(..) ) = child else { continue };
+            let NodeChild::Node( list ) = child else { continue };
+            for argument in ordered_children(list) {
+                if let NodeChild::Node( ASTNode::Argument(_, _, Some( name ), Some( assign ), Some( _ )) ) = argument {
+                    if let ( ASTNode::AtomName(_, _, keyword), Token::PyAssign(..) ) = ( &**name, &**assign ) {
+                        if let ( Some( text ), Some( span ) ) = ( keyword.text(), keyword.get_span() ) {
+                            keywords.push( ( text, span ) )
+                        }
+                    }
+                }
+            }
+        }
+        if let Some( ( start, _ ) ) = callee.get_span() {
+            res.push( ( navigator.definition(start), keywords ) )
+        }
+    });
+    res
+}
+
+/// Spans of the keyword arguments named 'name' of calls to the function with the parameter at span.
+fn keyword_arguments(navigator: &PythonCoreNavigator, parameter: ( u32, u32 ), name: &str) -> Vec<( u32, u32 )> {
+    let mut res = Vec::new();
+    for ( definitions, keywords ) in calls(navigator) {
+        if definitions.iter().any( |d| parameters(navigator.tree(), *d).iter().any( |( _, p )| *p == parameter ) ) {
+            res.extend( keywords.iter().filter( |( k, _ )| k == name ).map( |( _, span )| *span ) )
+        }
+    }
+    res
+}
+
+/// Position of the parameter that the keyword argument at span of a call is passed to.
+fn keyword_parameter(navigator: &PythonCoreNavigator, span: ( u32, u32 ), name: &str) -> Option<u32> {
+    let ( definitions, _ ) = calls(navigator).into_iter().find( |( _, keywords )| keywords.iter().any( |( _, k )| *k == span ) )?;
+    definitions.iter().flat_map( |d| parameters(navigator.tree(), *d) ).find( |( p, _ )| p == name ).map( |( _, ( start, _ ) )| start )
+}
+
+
+// UnitTests for rename ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::edits::apply_edits;
+    use crate::lsp::navigation::PythonCoreNavigator;
+    use crate::lsp::rename::rename;
+
+
+    fn renamed(source: &str, offset: u32, name: &str) -> Result<String, String> {
+        let navigator = PythonCoreNavigator::new(source).unwrap();
+        rename(&navigator, offset, name).map( |edits| apply_edits(source, &edits) ).map_err( |d| d.message )
+    }
+
+    #[test]
+    fn every_occurrence_of_the_binding_is_renamed() {
+        let source = "x = 1  # x\ndef f():\n    global x\n    x = x + 1\nclass C:\n    x = 2\n    def m(self): return x\n__all__ = ['x', \"f\"]\n";
+        assert_eq!(renamed(source, 0, "y").unwrap(),
+            "y = 1  # x\ndef f():\n    global y\n    y = y + 1\nclass C:\n    x = 2\n    def m(self): return y\n__all__ = ['y', \"f\"]\n");
+        assert_eq!(renamed(source, 60, "z").unwrap_err(), "cannot rename 'x', it is bound in class 'C' and its attribute references are not known");
+        let source = "def f():\n    a = 1\n    def g():\n        nonlocal a\n        a += 1\n    return a\n";
+        assert_eq!(renamed(source, 59, "b").unwrap(), source.replace("a = 1", "b = 1").replace(" a", " b"));
+    }
+
+    #[test]
+    fn class_bindings_are_not_renamed() {
+        let source = "class A:\n    def m(self):\n        return self.m()\nA().m()\n";
+        assert_eq!(renamed(source, 17, "n").unwrap_err(), "cannot rename 'm', it is bound in class 'A' and its attribute references are not known");
+        assert_eq!(renamed(source, 6, "B").unwrap(), "class B:\n    def m(self):\n        return self.m()\nB().m()\n");
+    }
+
+    #[test]
+    fn keyword_arguments_follow_parameters() {
+        let source = "def f(a, b=1):\n    return a + b\nclass C:\n    def __init__(self, b): pass\nf(1, b=2)\nC(b=3)\ng(b=4)\n";
+        assert_eq!(renamed(source, 9, "c").unwrap(),
+            "def f(a, c=1):\n    return a + c\nclass C:\n    def __init__(self, b): pass\nf(1, c=2)\nC(b=3)\ng(b=4)\n");
+        assert_eq!(renamed(source, 64, "d").unwrap(),
+            "def f(a, b=1):\n    return a + b\nclass C:\n    def __init__(self, d): pass\nf(1, b=2)\nC(d=3)\ng(b=4)\n");
+    }
+
+    #[test]
+    fn imports_get_aliases() {
+        let source = "import os, os.path\nfrom m import (a,  # keep\n    b as c)\nprint(a, c, os)\n";
+        assert_eq!(renamed(source, 34, "z").unwrap(), "import os, os.path\nfrom m import (a as z,  # keep\n    b as c)\nprint(z, c, os)\n");
+        assert_eq!(renamed(source, 54, "d").unwrap(), "import os, os.path\nfrom m import (a,  # keep\n    b as d)\nprint(a, d, os)\n");
+        assert!(renamed(source, 7, "o").unwrap_err().contains("dotted module name"));
+    }
+
+    #[test]
+    fn collisions_are_rejected() {
+        let source = "def f(a, b):\n    return [a for c in b] + len(a)\nlen(0)\n";
+        assert_eq!(renamed(source, 6, "b").unwrap_err(), "cannot rename 'a' to 'b', 'b' is already used in function 'f'");
+        assert_eq!(renamed(source, 6, "c").unwrap_err(), "cannot rename 'a' to 'c', 'c' is already used in a comprehension");
+        assert_eq!(renamed(source, 6, "len").unwrap_err(), "cannot rename 'a' to 'len', 'len' is already used in function 'f'");
+        assert_eq!(renamed(source, 4, "len").unwrap_err(), "cannot rename 'f' to 'len', 'len' is already used in the module");
+        assert_eq!(renamed("def f():\n    return g(x)\n", 4, "x").unwrap_err(), "cannot rename 'f' to 'x', it would hide 'x' used in function 'f'");
+        assert!(renamed(source, 6, "class").unwrap_err().contains("not a valid name"));
+        assert!(renamed(source, 48, "x").unwrap_err().contains("not bound"));
+        assert_eq!(renamed(source, 6, "match").unwrap(), "def f(match, b):\n    return [match for c in b] + len(match)\nlen(0)\n");
+    }
+}
@@ -0,0 +1,107 @@
+use crate::parser::tokens::Token;
+use crate::parser::trivias::Trivia;
+
+
+/// Kinds of semantic tokens, the server announces their names in this order as its legend.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Number,
+    Comment,
+    Operator,
+    Variable,
+    Function,
+    Class,
+    Decorator
+}
+
+pub const TOKEN_KINDS: [TokenKind; 9] = [
+    TokenKind::Keyword, TokenKind::String, TokenKind::Number, TokenKind::Comment, TokenKind::Operator,
+    TokenKind::Variable, TokenKind::Function, TokenKind::Class, TokenKind::Decorator
+];
+
+
+impl TokenKind {
+    /// Name of the standard token type of the protocol.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenKind::Keyword => "keyword",
+            TokenKind::String => "string",
+            TokenKind::Number => "number",
+            TokenKind::Comment => "comment",
+            TokenKind::Operator => "operator",
+            TokenKind::Variable => "variable",
+            TokenKind::Function => "function",
+            TokenKind::Class => "class",
+            TokenKind::Decorator => "decorator"
+        }
+    }
+
+    pub fn index(&self) -> u32 {
+        TOKEN_KINDS.iter().position( |k| k == self ).unwrap_or(0) as u32
+    }
+}
+
+/// Spans and kinds of the tokens and comments of a token stream, in source order. Names after 'def'
+/// and 'class' are functions and classes, a name after '@' at start of a line is a decorator, and
+/// brackets and delimiters are left out.
+pub fn semantic_tokens(tokens: &[Box<Token>]) -> Vec<( u32, u32, TokenKind )> {
+    let mut res = Vec::new();
+    let mut previous: Vec<&Token> = Vec::new();
+    for token in tokens {
+        for trivia in token.get_trivia().into_iter().flatten() {
+            if let Trivia::Comment( start, _, text ) = &**trivia {
+                res.push( ( *start, *start + text.trim_end().chars().count() as u32, TokenKind::Comment ) )
+            }
+        }
+        let Some( ( start, end ) ) = token.get_span() else { continue };
+        let kind = match &**token {
+            Token::AtomName(..) => match previous.last() {
+                Some( Token::PyDef(..) ) => Some( TokenKind::Function ),
+                Some( Token::PyClass(..) ) => Some( TokenKind::Class ),
+                Some( Token::PyMatrice(..) ) if is_line_start(&previous[.. previous.len() - 1]) => Some( TokenKind::Decorator ),
+                _ => Some( TokenKind::Variable )
+            },
+            Token::AtomString(..) => Some( TokenKind::String ),
+            Token::AtomNumber(..) => Some( TokenKind::Number ),
+            Token::PyMatrice(..) if is_line_start(&previous) => Some( TokenKind::Decorator ),
+            t => match t.text() {
+                Some( text ) if text.starts_with( |c: char| c.is_alphabetic() ) => Some( TokenKind::Keyword ),
+                Some( text ) if !matches!(text.as_str(), "(" | ")" | "[" | "]" | "{" | "}" | "," | ":" | "." | ";" | "...") => Some( TokenKind::Operator ),
+                _ => None
+            }
+        };
+        if let Some( kind ) = kind {
+            res.push( ( start, end, kind ) )
+        }
+        previous.push(token)
+    }
+    res.sort_by_key( |t| t.0 );
+    res
+}
+
+fn is_line_start(previous: &[&Token]) -> bool {
+    matches!(previous.last(), None | Some( Token::Newline(..) | Token::Indent(..) | Token::Dedent(..) ))
+}
+
+
+// UnitTests for semantic tokens ///////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::api::tokenize;
+    use crate::lsp::semantic::{ TokenKind, semantic_tokens };
+
+
+    #[test]
+    fn tokens_are_classified() {
+        let ( tokens, _ ) = tokenize("@d\ndef f(x):  # c\n    return x @ 2 + 'a'\n");
+        let kinds: Vec<( u32, u32, TokenKind )> = semantic_tokens(&tokens);
+        assert_eq!(kinds, vec![
+            ( 0, 1, TokenKind::Decorator ), ( 1, 2, TokenKind::Decorator ), ( 3, 6, TokenKind::Keyword ), ( 7, 8, TokenKind::Function ),
+            ( 9, 10, TokenKind::Variable ), ( 14, 17, TokenKind::Comment ), ( 22, 28, TokenKind::Keyword ), ( 29, 30, TokenKind::Variable ),
+            ( 31, 32, TokenKind::Operator ), ( 33, 34, TokenKind::Number ), ( 35, 36, TokenKind::Operator ), ( 37, 40, TokenKind::String )
+        ]);
+    }
+}
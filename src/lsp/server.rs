@@ -0,0 +1,450 @@
+use std::collections::HashMap;
+use std::io::{ BufRead, Write };
+use crate::api::{ Mode, analyze, parse, tokenize, normalize_source };
+use crate::diagnostics::{ Diagnostic, Severity };
+use crate::lsp::json::Json;
+use crate::lsp::document::Document;
+use crate::lsp::outline::{ FoldingKind, OutlineSymbol, SymbolKind, document_symbols, folding_ranges };
+use crate::lsp::semantic::{ TOKEN_KINDS, semantic_tokens };
+use crate::lsp::navigation::PythonCoreNavigator;
+use crate::lsp::rename::rename;
+
+
+/// Error codes of JSON-RPC and the protocol.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+const REQUEST_FAILED: i64 = -32803;
+
+type Response = Result<Json, ( i64, String )>;
+
+/// Language server answering JSON-RPC messages framed by a 'Content-Length' header. Documents are
+/// kept in memory as the client opens and changes them, and after every change the syntax errors
+/// and warnings of 'analyze' are published.
+pub struct PythonCoreLanguageServer<'o> {
+    out: &'o mut dyn Write,
+    documents: HashMap<String, Document>,
+    initialized: bool,
+    shutdown: bool,
+    exited: bool
+}
+
+
+impl<'o> PythonCoreLanguageServer<'o> {
+    pub fn new(out: &'o mut dyn Write) -> PythonCoreLanguageServer<'o> {
+        PythonCoreLanguageServer { out, documents: HashMap::new(), initialized: false, shutdown: false, exited: false }
+    }
+
+    /// Serves messages until 'exit' or end of input. Returns exit code 0 when 'shutdown' was
+    /// requested before, 1 otherwise, as the protocol asks.
+    pub fn run(&mut self, input: &mut dyn BufRead) -> i32 {
+        while !self.exited {
+            match read_message(input) {
+                Ok( Some( text ) ) => match Json::parse(&text) {
+                    Ok( message ) => self.handle(&message),
+                    Err( e ) => self.send_error(Json::Null, PARSE_ERROR, &e)
+                },
+                Ok( None ) => break,
+                Err( e ) => {
+                    self.send_error(Json::Null, PARSE_ERROR, &e);
+                    break
+                }
+            }
+        }
+        match self.shutdown {
+            true => 0,
+            _ => 1
+        }
+    }
+
+    /// Handles one request or notification, only requests, which have an id, get a response.
+    pub fn handle(&mut self, message: &Json) {
+        let Some( method ) = message.get("method").as_str() else { return };
+        let id = message.get("id").clone();
+        let response = match method {
+            "initialize" => {
+                self.initialized = true;
+                Ok( capabilities() )
+            },
+            "exit" => {
+                self.exited = true;
+                return
+            },
+            _ if !self.initialized => Err( ( SERVER_NOT_INITIALIZED, "server is not initialized".to_string() ) ),
+            _ => self.dispatch(method, message.get("params"))
+        };
+        if id.is_null() {
+            return
+        }
+        match response {
+            Ok( result ) => self.send(Json::object(vec![ ( "jsonrpc", Json::from("2.0") ), ( "id", id ), ( "result", result ) ])),
+            Err( ( code, text ) ) => self.send_error(id, code, &text)
+        }
+    }
+
+    fn dispatch(&mut self, method: &str, params: &Json) -> Response {
+        let uri = params.get("textDocument").get("uri").as_str().unwrap_or_default().to_string();
+        match method {
+            "initialized" | "$/cancelRequest" | "$/setTrace" => Ok( Json::Null ),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok( Json::Null )
+            },
+            "textDocument/didOpen" => {
+                let item = params.get("textDocument");
+                let text = item.get("text").as_str().ok_or_else( || invalid_params("missing text") )?;
+                self.documents.insert(uri.clone(), Document::new(&uri, item.get("version").as_i64().unwrap_or(0), text));
+                self.publish_diagnostics(&uri);
+                Ok( Json::Null )
+            },
+            "textDocument/didChange" => {
+                let document = self.documents.get_mut(&uri).ok_or_else( || invalid_params("unknown document") )?;
+                for change in params.get("contentChanges").as_array().unwrap_or_default() {
+                    let range = change.get("range");
+                    let span = match range.is_null() {
+                        true => None,
+                        _ => Some( ( offset(document, range.get("start")), offset(document, range.get("end")) ) )
+                    };
+                    document.change(span, change.get("text").as_str().unwrap_or_default())
+                }
+                document.version = params.get("textDocument").get("version").as_i64().unwrap_or(document.version);
+                self.publish_diagnostics(&uri);
+                Ok( Json::Null )
+            },
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.send_notification("textDocument/publishDiagnostics", Json::object(vec![
+                    ( "uri", Json::from(uri) ), ( "diagnostics", Json::Array(Vec::new()) ) ]));
+                Ok( Json::Null )
+            },
+            "textDocument/documentSymbol" => {
+                let document = self.document(&uri)?;
+                let symbols = parse(&normalize_source(document.text()), Mode::Exec).tree.map( |t| document_symbols(&t) ).unwrap_or_default();
+                Ok( Json::Array( symbols.iter().map( |s| symbol_json(document, s) ).collect() ) )
+            },
+            "textDocument/foldingRange" => {
+                let document = self.document(&uri)?;
+                let ranges = parse(&normalize_source(document.text()), Mode::Exec).tree.map( |t| folding_ranges(&t) ).unwrap_or_default();
+                Ok( Json::Array( ranges.into_iter()
+                    .map( |r| ( document.position(r.start).0, document.position(r.end).0, r.kind ) )
+                    .filter( |( start, end, _ )| end > start )
+                    .map( |( start, end, kind )| {
+                        let mut range = vec![ ( "startLine", Json::from(start) ), ( "endLine", Json::from(end) ) ];
+                        match kind {
+                            FoldingKind::Comment => range.push( ( "kind", Json::from("comment") ) ),
+                            FoldingKind::Imports => range.push( ( "kind", Json::from("imports") ) ),
+                            _ => { }
+                        }
+                        Json::object(range)
+                    })
+                    .collect() ) )
+            },
+            "textDocument/semanticTokens/full" => {
+                let document = self.document(&uri)?;
+                let ( tokens, _ ) = tokenize(&normalize_source(document.text()));
+                Ok( Json::object(vec![ ( "data", encode_tokens(document, &semantic_tokens(&tokens)) ) ]) )
+            },
+            "textDocument/definition" | "textDocument/references" => {
+                let document = self.document(&uri)?;
+                let Ok( navigator ) = PythonCoreNavigator::new(document.text()) else { return Ok( Json::Array(Vec::new()) ) };
+                let at = offset(document, params.get("position"));
+                let spans = match method {
+                    "textDocument/definition" => navigator.definition(at),
+                    _ => navigator.references(at, params.get("context").get("includeDeclaration") != &Json::Bool(false))
+                };
+                Ok( Json::Array( spans.iter().map( |( start, end )| Json::object(vec![
+                    ( "uri", Json::from(uri.as_str()) ), ( "range", range_json(document, *start, *end) ) ]) ).collect() ) )
+            },
+            "textDocument/hover" => {
+                let document = self.document(&uri)?;
+                let hover = PythonCoreNavigator::new(document.text()).ok().and_then( |n| n.hover(offset(document, params.get("position"))) );
+                Ok( match hover {
+                    Some( h ) => Json::object(vec![
+                        ( "contents", Json::object(vec![ ( "kind", Json::from("markdown") ), ( "value", Json::from(h.text) ) ]) ),
+                        ( "range", range_json(document, h.span.0, h.span.1) ) ]),
+                    None => Json::Null
+                })
+            },
+            "textDocument/prepareRename" => {
+                let document = self.document(&uri)?;
+                let span = PythonCoreNavigator::new(document.text()).ok().and_then( |n| n.name_at(offset(document, params.get("position"))) );
+                Ok( span.map( |( start, end )| range_json(document, start, end) ).unwrap_or(Json::Null) )
+            },
+            "textDocument/rename" => {
+                let document = self.document(&uri)?;
+                let new_name = params.get("newName").as_str().ok_or_else( || invalid_params("missing newName") )?;
+                let navigator = PythonCoreNavigator::new(document.text()).map_err( |d| ( REQUEST_FAILED, d.message ) )?;
+                let edits = rename(&navigator, offset(document, params.get("position")), new_name).map_err( |d| ( REQUEST_FAILED, d.message ) )?;
+                let edits = edits.iter().map( |e| Json::object(vec![
+                    ( "range", range_json(document, e.start, e.end) ), ( "newText", Json::from(e.text.as_str()) ) ]) ).collect();
+                Ok( Json::object(vec![ ( "changes", Json::Object(vec![ ( uri.clone(), Json::Array(edits) ) ]) ) ]) )
+            },
+            _ => Err( ( METHOD_NOT_FOUND, format!("unknown method '{}'", method) ) )
+        }
+    }
+
+    fn document(&self, uri: &str) -> Result<&Document, ( i64, String )> {
+        self.documents.get(uri).ok_or_else( || invalid_params(&format!("unknown document '{}'", uri)) )
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str) {
+        let Some( document ) = self.documents.get(uri) else { return };
+        let diagnostics: Vec<Json> = analyze(document.text(), Mode::Exec).iter().map( |d| diagnostic_json(document, d) ).collect();
+        let params = Json::object(vec![
+            ( "uri", Json::from(uri) ), ( "version", Json::from(document.version) ), ( "diagnostics", Json::Array(diagnostics) ) ]);
+        self.send_notification("textDocument/publishDiagnostics", params)
+    }
+
+    fn send_notification(&mut self, method: &str, params: Json) {
+        self.send(Json::object(vec![ ( "jsonrpc", Json::from("2.0") ), ( "method", Json::from(method) ), ( "params", params ) ]))
+    }
+
+    fn send_error(&mut self, id: Json, code: i64, message: &str) {
+        let error = Json::object(vec![ ( "code", Json::from(code) ), ( "message", Json::from(message) ) ]);
+        self.send(Json::object(vec![ ( "jsonrpc", Json::from("2.0") ), ( "id", id ), ( "error", error ) ]))
+    }
+
+    fn send(&mut self, message: Json) {
+        let text = message.to_string();
+        let _ = write!(self.out, "Content-Length: {}\r\n\r\n{}", text.len(), text);
+        let _ = self.out.flush();
+    }
+}
+
+/// Body of the next message, none at end of input.
+pub fn read_message(input: &mut dyn BufRead) -> Result<Option<String>, String> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).map_err( |e| format!("cannot read message: {}", e) )? == 0 {
+            return Ok( None )
+        }
+        let line = line.trim_end_matches([ '\r', '\n' ]);
+        if line.is_empty() && length.is_some() {
+            break
+        }
+        if let Some( ( name, value ) ) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = Some( value.trim().parse::<usize>().map_err( |_| format!("invalid header '{}'", line) )? )
+            }
+        }
+    }
+    let mut body = vec![ 0; length.unwrap_or(0) ];
+    input.read_exact(&mut body).map_err( |e| format!("cannot read message: {}", e) )?;
+    String::from_utf8(body).map( Some ).map_err( |_| "message is not UTF-8".to_string() )
+}
+
+fn capabilities() -> Json {
+    let legend = Json::object(vec![
+        ( "tokenTypes", Json::Array( TOKEN_KINDS.iter().map( |k| Json::from(k.name()) ).collect() ) ),
+        ( "tokenModifiers", Json::Array(Vec::new()) )
+    ]);
+    Json::object(vec![
+        ( "capabilities", Json::object(vec![
+            ( "positionEncoding", Json::from("utf-16") ),
+            ( "textDocumentSync", Json::object(vec![ ( "openClose", Json::from(true) ), ( "change", Json::from(2u32) ) ]) ),
+            ( "documentSymbolProvider", Json::from(true) ),
+            ( "foldingRangeProvider", Json::from(true) ),
+            ( "definitionProvider", Json::from(true) ),
+            ( "referencesProvider", Json::from(true) ),
+            ( "hoverProvider", Json::from(true) ),
+            ( "renameProvider", Json::object(vec![ ( "prepareProvider", Json::from(true) ) ]) ),
+            ( "semanticTokensProvider", Json::object(vec![ ( "legend", legend ), ( "full", Json::from(true) ) ]) )
+        ]) ),
+        ( "serverInfo", Json::object(vec![ ( "name", Json::from("python_core_rust") ), ( "version", Json::from(env!("CARGO_PKG_VERSION")) ) ]) )
+    ])
+}
+
+fn invalid_params(message: &str) -> ( i64, String ) {
+    ( INVALID_PARAMS, message.to_string() )
+}
+
+fn offset(document: &Document, position: &Json) -> u32 {
+    document.offset(position.get("line").as_u32().unwrap_or(0), position.get("character").as_u32().unwrap_or(0))
+}
+
+fn position_json(document: &Document, offset: u32) -> Json {
+    let ( line, character ) = document.position(offset);
+    Json::object(vec![ ( "line", Json::from(line) ), ( "character", Json::from(character) ) ])
+}
+
+pub fn range_json(document: &Document, start: u32, end: u32) -> Json {
+    Json::object(vec![ ( "start", position_json(document, start) ), ( "end", position_json(document, end) ) ])
+}
+
+fn diagnostic_json(document: &Document, diagnostic: &Diagnostic) -> Json {
+    let severity = match diagnostic.severity {
+        Severity::Error => 1u32,
+        Severity::Warning => 2
+    };
+    let mut fields = vec![
+        ( "range", range_json(document, diagnostic.start, diagnostic.end) ),
+        ( "severity", Json::from(severity) ),
+        ( "source", Json::from("python_core_rust") ),
+        ( "message", Json::from(diagnostic.message.as_str()) )
+    ];
+    if let Some( code ) = &diagnostic.code {
+        fields.push( ( "code", Json::from(code.as_str()) ) )
+    }
+    Json::object(fields)
+}
+
+fn symbol_json(document: &Document, symbol: &OutlineSymbol) -> Json {
+    let kind = match symbol.kind {
+        SymbolKind::MainBlock => 2u32,
+        SymbolKind::Class => 5,
+        SymbolKind::Method | SymbolKind::AsyncMethod => 6,
+        SymbolKind::Function | SymbolKind::AsyncFunction => 12,
+        SymbolKind::Variable => 13
+    };
+    Json::object(vec![
+        ( "name", Json::from(symbol.name.as_str()) ),
+        ( "kind", Json::from(kind) ),
+        ( "range", range_json(document, symbol.span.0, symbol.span.1) ),
+        ( "selectionRange", range_json(document, symbol.selection.0, symbol.selection.1) ),
+        ( "children", Json::Array( symbol.children.iter().map( |c| symbol_json(document, c) ).collect() ) )
+    ])
+}
+
+/// Semantic tokens in the relative encoding of the protocol, five numbers for each token. Tokens
+/// over several lines, like triple-quoted strings, are split at line ends.
+fn encode_tokens(document: &Document, tokens: &[( u32, u32, crate::lsp::semantic::TokenKind )]) -> Json {
+    let mut data = Vec::new();
+    let ( mut last_line, mut last_column ) = ( 0, 0 );
+    for ( start, end, kind ) in tokens {
+        let ( mut line, mut column ) = document.position(*start);
+        let ( end_line, end_column ) = document.position(*end);
+        while line <= end_line {
+            let line_end = match line == end_line {
+                true => end_column,
+                _ => document.position(document.offset(line, u32::MAX)).1
+            };
+            if line_end > column {
+                let delta_column = if line == last_line { column - last_column } else { column };
+                data.extend( [ line - last_line, delta_column, line_end - column, kind.index(), 0 ].map( Json::from ) );
+                ( last_line, last_column ) = ( line, column )
+            }
+            ( line, column ) = ( line + 1, 0 )
+        }
+    }
+    Json::Array(data)
+}
+
+
+// UnitTests for language server ///////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use crate::lsp::json::Json;
+    use crate::lsp::server::{ PythonCoreLanguageServer, read_message };
+
+
+    /// Runs the server on messages given as JSON texts and returns its exit code and messages.
+    fn session(messages: &[&str]) -> ( i32, Vec<Json> ) {
+        let mut input = String::new();
+        for m in messages {
+            let text = Json::parse(m).unwrap().to_string();
+            input.push_str(&format!("Content-Length: {}\r\n\r\n{}", text.len(), text))
+        }
+        let mut out = Vec::new();
+        let code = PythonCoreLanguageServer::new(&mut out).run(&mut Cursor::new(input.into_bytes()));
+        let mut reader = Cursor::new(out);
+        let mut res = Vec::new();
+        while let Some( text ) = read_message(&mut reader).unwrap() {
+            res.push( Json::parse(&text).unwrap() )
+        }
+        ( code, res )
+    }
+
+    const INITIALIZE: &str = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#;
+    const OPEN: &str = r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///a.py","languageId":"python","version":1,"text":"class A:\n    def f(self):\n        return 1\nx = (\n"}}}"#;
+
+    #[test]
+    fn diagnostics_follow_changes() {
+        let change = r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":{"uri":"file:///a.py","version":2},
+            "contentChanges":[{"range":{"start":{"line":3,"character":5},"end":{"line":3,"character":5}},"text":")"}]}}"#;
+        let ( code, messages ) = session(&[ INITIALIZE, r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#, OPEN, change,
+            r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#, r#"{"jsonrpc":"2.0","method":"exit"}"# ]);
+        assert_eq!(code, 0);
+        assert_eq!(messages.len(), 4);
+        let capabilities = messages[0].get("result").get("capabilities");
+        assert_eq!(capabilities.get("textDocumentSync").get("change").as_i64(), Some( 2 ));
+        assert_eq!(capabilities.get("semanticTokensProvider").get("legend").get("tokenTypes").as_array().unwrap()[0].as_str(), Some( "keyword" ));
+        assert_eq!(messages[1].get("method").as_str(), Some( "textDocument/publishDiagnostics" ));
+        let diagnostic = &messages[1].get("params").get("diagnostics").as_array().unwrap()[0];
+        assert_eq!(diagnostic.get("severity").as_i64(), Some( 1 ));
+        assert_eq!(diagnostic.get("range").get("start").to_string(), r#"{"line":4,"character":0}"#);
+        assert_eq!(messages[2].get("params").to_string(), r#"{"uri":"file:///a.py","version":2,"diagnostics":[]}"#);
+        assert_eq!(messages[3].to_string(), r#"{"jsonrpc":"2.0","id":2,"result":null}"#);
+    }
+
+    #[test]
+    fn symbols_folding_and_semantic_tokens() {
+        let ( code, messages ) = session(&[ INITIALIZE, OPEN,
+            r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":{"uri":"file:///a.py","version":2},"contentChanges":[{"text":"class A:\n    def f(self):\n        return 1\nx = '''\n'''\n"}]}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/documentSymbol","params":{"textDocument":{"uri":"file:///a.py"}}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"textDocument/foldingRange","params":{"textDocument":{"uri":"file:///a.py"}}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"textDocument/semanticTokens/full","params":{"textDocument":{"uri":"file:///a.py"}}}"#,
+            r#"{"jsonrpc":"2.0","id":5,"method":"textDocument/codeLens","params":{}}"# ]);
+        assert_eq!(code, 1);
+        let symbols = messages[3].get("result").as_array().unwrap();
+        assert_eq!(symbols.iter().map( |s| ( s.get("name").as_str().unwrap(), s.get("kind").as_i64().unwrap() ) ).collect::<Vec<_>>(), vec![ ( "A", 5 ), ( "x", 13 ) ]);
+        let method = &symbols[0].get("children").as_array().unwrap()[0];
+        assert_eq!(method.get("range").to_string(), r#"{"start":{"line":1,"character":4},"end":{"line":2,"character":16}}"#);
+        assert_eq!(method.get("selectionRange").get("start").to_string(), r#"{"line":1,"character":8}"#);
+        assert_eq!(messages[4].get("result").to_string(), r#"[{"startLine":0,"endLine":2},{"startLine":1,"endLine":2},{"startLine":3,"endLine":4}]"#);
+        assert_eq!(messages[5].get("result").get("data").to_string(),
+            "[0,0,5,0,0,0,6,1,7,0,1,4,3,0,0,0,4,1,6,0,0,2,4,5,0,1,8,6,0,0,0,7,1,2,0,1,0,1,5,0,0,2,1,4,0,0,2,3,1,0,1,0,3,1,0]");
+        assert_eq!(messages[6].get("error").get("code").as_i64(), Some( -32601 ));
+    }
+
+    #[test]
+    fn definition_references_and_hover() {
+        let open = r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///b.py","version":1,
+            "text":"def f():\n    'Doc.'\nf()\n"}}}"#;
+        let request = |id: u32, method: &str, line: u32, character: u32| format!(r#"{{"jsonrpc":"2.0","id":{},"method":"textDocument/{}",
+            "params":{{"textDocument":{{"uri":"file:///b.py"}},"position":{{"line":{},"character":{}}},"context":{{"includeDeclaration":false}}}}}}"#, id, method, line, character);
+        let ( definition, references, hover ) = ( request(2, "definition", 2, 1), request(3, "references", 0, 4), request(4, "hover", 2, 0) );
+        let ( _, messages ) = session(&[ INITIALIZE, open, &definition, &references, &hover, &request(5, "hover", 1, 0) ]);
+        assert_eq!(messages[2].get("result").to_string(), r#"[{"uri":"file:///b.py","range":{"start":{"line":0,"character":4},"end":{"line":0,"character":5}}}]"#);
+        assert_eq!(messages[3].get("result").to_string(), r#"[{"uri":"file:///b.py","range":{"start":{"line":2,"character":0},"end":{"line":2,"character":1}}}]"#);
+        assert_eq!(messages[4].get("result").get("contents").get("value").as_str(), Some( "```python\ndef f()\n```\n\n---\n\nDoc." ));
+        assert!(messages[5].get("result").is_null());
+    }
+
+    #[test]
+    fn rename_returns_workspace_edits() {
+        let open = r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///c.py","version":1,
+            "text":"def f(a):\n    return a\nf(a=1)\n"}}}"#;
+        let request = |id: u32, method: &str, line: u32, character: u32, name: &str| format!(r#"{{"jsonrpc":"2.0","id":{},"method":"textDocument/{}",
+            "params":{{"textDocument":{{"uri":"file:///c.py"}},"position":{{"line":{},"character":{}}},"newName":"{}"}}}}"#, id, method, line, character, name);
+        let ( prepare, rename, failed ) = ( request(2, "prepareRename", 1, 11, ""), request(3, "rename", 2, 2, "b"), request(4, "rename", 0, 4, "def") );
+        let ( _, messages ) = session(&[ INITIALIZE, open, &prepare, &rename, &failed ]);
+        assert_eq!(messages[2].get("result").to_string(), r#"{"start":{"line":1,"character":11},"end":{"line":1,"character":12}}"#);
+        let edits = messages[3].get("result").get("changes").get("file:///c.py").as_array().unwrap();
+        let edits: Vec<String> = edits.iter().map( |e| format!("{}:{} {}", e.get("range").get("start").get("line").as_i64().unwrap(),
+            e.get("range").get("start").get("character").as_i64().unwrap(), e.get("newText").as_str().unwrap()) ).collect();
+        assert_eq!(edits, vec![ "0:6 b", "1:11 b", "2:2 b" ]);
+        assert_eq!(messages[4].get("error").get("code").as_i64(), Some( -32803 ));
+    }
+
+    #[test]
+    fn requests_wait_for_initialize() {
+        let ( code, messages ) = session(&[ r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"# ]);
+        assert_eq!(code, 1);
+        assert_eq!(messages[0].get("error").get("code").as_i64(), Some( -32002 ));
+    }
+
+    #[test]
+    fn deep_nesting_is_a_parse_error() {
+        let text = "[".repeat(300000);
+        let input = format!("Content-Length: {}\r\n\r\n{}", text.len(), text);
+        let mut out = Vec::new();
+        let code = PythonCoreLanguageServer::new(&mut out).run(&mut Cursor::new(input.into_bytes()));
+        let message = Json::parse(&read_message(&mut Cursor::new(out)).unwrap().unwrap()).unwrap();
+        assert_eq!(code, 1);
+        assert_eq!(message.get("error").get("code").as_i64(), Some( -32700 ));
+    }
+}
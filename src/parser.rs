@@ -9,3 +9,4 @@ pub mod tokens;
 pub mod trivias;
 pub mod blocks;
 pub mod functions;
+pub mod sexpr;
@@ -1,5 +1,6 @@
 
 use crate::{ ASTNode, Statements, Token, Parser, PythonCoreParser, Expressions, Tokenizer };
+use crate::parser::parser::kinds;
 use crate::parser::functions::Functions;
 use crate::parser::patterns::Patterns;
 
@@ -29,7 +30,7 @@ impl Blocks for PythonCoreParser {
         let right_node = self.parse_expressions_testlist()?;
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
         while
-            match self.symbol.clone() {
+            match self.expecting(kinds![ Newline ]).clone() {
                 Ok(s) => {
                     match &*s {
                         Token::Newline(..) => {
@@ -43,7 +44,7 @@ impl Blocks for PythonCoreParser {
                 _ => return Err(format!("SyntaxError at {}: Expecting symbol in eval expression!", start_pos))
             } { };
         separators_list.reverse();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ EOF ]).clone() {
             Ok(s2) => {
                 match &*s2 {
                     Token::EOF(..) => {
@@ -63,7 +64,7 @@ impl Blocks for PythonCoreParser {
         let mut nodes_list : Box<Vec<Box<ASTNode>>> = Box::new(Vec::new());
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
         while
-            match self.symbol.clone() {
+            match self.expecting(kinds![ EOF, Newline ]).clone() {
                 Ok(s) => {
                     match &*s {
                         Token::EOF(..) => false,
@@ -82,7 +83,7 @@ impl Blocks for PythonCoreParser {
             } { };
         separators_list.reverse();
         nodes_list.reverse();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ EOF ]).clone() {
             Ok(s2) => {
                 match &*s2 {
                     Token::EOF(..) => {
@@ -99,7 +100,7 @@ impl Blocks for PythonCoreParser {
     fn parse_blocks_single_input(&mut self) -> Result<Box<ASTNode>, String> {
         let _ = self.advance();
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ Newline, PyIf, PyWhile, PyFor, PyTry, PyWith, PyDef, PyClass, PyAsync, PyMatrice, AtomName; "match" ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::Newline( .. ) => {
@@ -117,7 +118,7 @@ impl Blocks for PythonCoreParser {
                     Token::PyAsync( .. ) |
                     Token::PyMatrice( .. ) => {
                         let right_node = Some(self.parse_statements_compound_stmt()?);
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ Newline ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::Newline(..) => {
@@ -132,9 +133,9 @@ impl Blocks for PythonCoreParser {
                     },
                     Token::AtomName( _ , _ , _ , txt) => {
                         match &*txt.as_str() {
-                            "match" => {
+                            "match" if self.is_match_statement() => {
                                 let right_node = Some( self.parse_patterns_match()? );
-                                match self.symbol.clone() {
+                                match self.expecting(kinds![ Newline ]).clone() {
                                     Ok(s3) => {
                                         match &*s3 {
                                             Token::Newline(..) => {
@@ -169,7 +170,7 @@ impl Blocks for PythonCoreParser {
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
         let right_node = self.parse_functions_func_type()?;
         while
-            match self.symbol.clone() {
+            match self.expecting(kinds![ Newline ]).clone() {
                 Ok(s) => {
                     match &*s {
                         Token::Newline(..) => {
@@ -183,7 +184,7 @@ impl Blocks for PythonCoreParser {
                 _ => return Err(format!("SyntaxError at {}: Expecting symbol in functional input!", start_pos))
             } { };
         separators_list.reverse();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ EOF ]).clone() {
             Ok(s2) => {
                 match &*s2 {
                     Token::EOF(..) => {
@@ -199,7 +200,7 @@ impl Blocks for PythonCoreParser {
 
     fn parse_blocks_decorator(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyMatrice ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyMatrice(..) => {
@@ -209,13 +210,13 @@ impl Blocks for PythonCoreParser {
                         let mut symbol2 : Option<Box<Token>> = None;
                         let mut right_node : Option<Box<ASTNode>> = None;
                         let mut symbol3 : Option<Box<Token>> = None;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyLeftParen ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyLeftParen(..) => {
                                         symbol2 = Some( s2 );
                                         let _ = self.advance();
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyRightParen ]).clone() {
                                             Ok(s3) => {
                                                 match &*s3 {
                                                     Token::PyRightParen(..) => {},
@@ -226,7 +227,7 @@ impl Blocks for PythonCoreParser {
                                             },
                                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in functional input!", start_pos))
                                         }
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyRightParen ]).clone() {
                                             Ok(s4) => {
                                                 match &*s4 {
                                                     Token::PyRightParen(..) => {
@@ -245,11 +246,12 @@ impl Blocks for PythonCoreParser {
                             },
                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in functional input!", start_pos))
                         }
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ Newline ]).clone() {
                             Ok(s5) => {
                                 match &*s5 {
                                     Token::Newline(..) => {
                                         let symbol4 = s5;
+                                        let _ = self.advance();
                                         Ok(Box::new( ASTNode::Decorator(start_pos, self.lexer.get_position(), symbol1, left_node,  symbol2, right_node, symbol3, symbol4) ))
                                     },
                                     _ => Err(format!("SyntaxError at {}: Expecting end of file in function input!", start_pos))
@@ -270,7 +272,7 @@ impl Blocks for PythonCoreParser {
         let mut nodes_list : Box<Vec<Box<ASTNode>>> = Box::new(Vec::new());
         nodes_list.push( self.parse_blocks_decorator()? );
         while
-            match self.symbol.clone() {
+            match self.expecting(kinds![ PyMatrice ]).clone() {
                 Ok(s) => {
                     match &*s {
                         Token::PyMatrice(..) => {
@@ -288,12 +290,12 @@ impl Blocks for PythonCoreParser {
 
     fn parse_blocks_decorated(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyMatrice ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyMatrice(..) => {
                         let left_node = self.parse_blocks_decorators()?;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyClass, PyDef, PyAsync ]).clone() {
                             Ok(s) => {
                                 match &*s {
                                     Token::PyClass(..) => {
@@ -323,7 +325,7 @@ impl Blocks for PythonCoreParser {
 
     fn parse_blocks_async_func_def(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyAsync ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyAsync(..) => {
@@ -341,19 +343,19 @@ impl Blocks for PythonCoreParser {
 
     fn parse_blocks_func_def(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyDef ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyDef(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ AtomName ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::AtomName(..) => {
                                         let symbol2 = s2;
                                         let _ = self.advance();
-                                        let left_node : Option<Box<ASTNode>> = match self.symbol.clone() {
+                                        let left_node : Option<Box<ASTNode>> = match self.expecting(kinds![ PyLeftParen ]).clone() {
                                             Ok(s3) => {
                                                 match &*s3 {
                                                     Token::PyLeftParen(..) => {
@@ -364,7 +366,7 @@ impl Blocks for PythonCoreParser {
                                             },
                                             _ => return Err(format!("SyntaxError at {}: Expecting 'def' in function statement!", start_pos))
                                         };
-                                        let ret_node : Option<Box<( Box<Token>, Box<ASTNode> )>> = match self.symbol.clone() {
+                                        let ret_node : Option<Box<( Box<Token>, Box<ASTNode> )>> = match self.expecting(kinds![ PyArrow ]).clone() {
                                             Ok(s4) => {
                                                 match &*s4 {
                                                     Token::PyArrow(..) => {
@@ -378,13 +380,13 @@ impl Blocks for PythonCoreParser {
                                             },
                                             _ => return Err(format!("SyntaxError at {}: Expecting 'def' in function statement!", start_pos))
                                         };
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyColon ]).clone() {
                                             Ok(s5) => {
                                                 match &*s5 {
                                                     Token::PyColon(..) => {
                                                         let symbol4 = s5;
                                                         let _ = self.advance();
-                                                        let tc_symbol : Option<Box<Token>> = match self.symbol.clone() {
+                                                        let tc_symbol : Option<Box<Token>> = match self.expecting(kinds![ TypeComment ]).clone() {
                                                             Ok(s6) => {
                                                                 match &*s6 {
                                                                     Token::TypeComment(..) => {
@@ -421,13 +423,13 @@ impl Blocks for PythonCoreParser {
 
     fn parse_blocks_parameters(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyLeftParen ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyLeftParen(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
-                        let right_node : Option<Box<ASTNode>> = match self.symbol.clone() {
+                        let right_node : Option<Box<ASTNode>> = match self.expecting(kinds![ PyRightParen ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyRightParen(..) => None,
@@ -436,7 +438,7 @@ impl Blocks for PythonCoreParser {
                             },
                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in parameters of function statement!", start_pos))
                         };
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyRightParen ]).clone() {
                             Ok(s3) => {
                                 match &*s3 {
                                     Token::PyRightParen(..) => {
@@ -459,19 +461,90 @@ impl Blocks for PythonCoreParser {
 
     fn parse_blocks_typed_args_list(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        let a : Box<Vec<Box<ASTNode>>> = Box::new(Vec::new());
-        let b : Box<Vec<Box<Token>>> = Box::new(Vec::new());
-        let c : Box<Vec<Box<Token>>> = Box::new(Vec::new());
-
-        // Missing rule here!
-
-        Ok(Box::new(ASTNode::TypedArgsList(start_pos, self.lexer.get_position(), a, b, c, None, None )))
+        let mut nodes_list : Box<Vec<Box<ASTNode>>> = Box::new(Vec::new());
+        let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
+        let mut markers_list : Box<Vec<Box<Token>>> = Box::new(Vec::new()); /* '/' and bare '*' */
+        let mut mul_node : Option<(Box<Token>, Box<ASTNode>)> = None;
+        let mut power_node : Option<(Box<Token>, Box<ASTNode>)> = None;
+        while
+            match self.expecting(kinds![ PyRightParen, PyDiv, PyMul, PyPower ]).clone() {
+                Ok(s) => {
+                    match &*s {
+                        Token::PyRightParen(..) => false,
+                        Token::PyDiv(..) => {
+                            match ( markers_list.len(), &mul_node, &power_node ) {
+                                ( 0, None, None ) => { },
+                                _ => return Err(format!("SyntaxError at {}: Unexpected '/' in parameters of function statement!", start_pos))
+                            }
+                            markers_list.push( s );
+                            let _ = self.advance();
+                            true
+                        },
+                        Token::PyMul(..) => {
+                            match ( &mul_node, &power_node ) {
+                                ( None, None ) => { },
+                                _ => return Err(format!("SyntaxError at {}: Unexpected '*' in parameters of function statement!", start_pos))
+                            }
+                            let _ = self.advance();
+                            match self.expecting(kinds![ PyComa, PyRightParen ]).clone() {
+                                Ok(s2) => {
+                                    match &*s2 {
+                                        Token::PyComa(..) |
+                                        Token::PyRightParen(..) => {
+                                            markers_list.push( s );
+                                        },
+                                        _ => {
+                                            mul_node = Some( ( s, self.parse_blocks_tfp_def()? ) );
+                                        }
+                                    }
+                                },
+                                _ => return Err(format!("SyntaxError at {}: Expecting symbol in parameters of function statement!", start_pos))
+                            }
+                            true
+                        },
+                        Token::PyPower(..) => {
+                            match &power_node {
+                                None => { },
+                                _ => return Err(format!("SyntaxError at {}: Unexpected '**' in parameters of function statement!", start_pos))
+                            }
+                            let _ = self.advance();
+                            power_node = Some( ( s, self.parse_blocks_tfp_def()? ) );
+                            true
+                        },
+                        _ => {
+                            match &power_node {
+                                None => { },
+                                _ => return Err(format!("SyntaxError at {}: Parameter after '**' in parameters of function statement!", start_pos))
+                            }
+                            nodes_list.push( self.parse_blocks_tfp_def_assign()? );
+                            true
+                        }
+                    }
+                },
+                _ => return Err(format!("SyntaxError at {}: Expecting symbol in parameters of function statement!", start_pos))
+            } && match self.expecting(kinds![ PyComa ]).clone() {
+                Ok(s3) => {
+                    match &*s3 {
+                        Token::PyComa(..) => {
+                            separators_list.push( s3 );
+                            let _ = self.advance();
+                            true
+                        },
+                        _ => false
+                    }
+                },
+                _ => return Err(format!("SyntaxError at {}: Expecting symbol in parameters of function statement!", start_pos))
+            } { };
+        nodes_list.reverse();
+        separators_list.reverse();
+        markers_list.reverse();
+        Ok(Box::new(ASTNode::TypedArgsList(start_pos, self.lexer.get_position(), nodes_list, separators_list, markers_list, mul_node, power_node )))
     }
 
     fn parse_blocks_tfp_def_assign(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let left_node = self.parse_blocks_tfp_def()?;
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyAssign ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyAssign(..) => {
@@ -489,13 +562,13 @@ impl Blocks for PythonCoreParser {
 
     fn parse_blocks_tfp_def(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ AtomName ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::AtomName(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyColon ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyColon(..) => {
@@ -522,7 +595,7 @@ impl Blocks for PythonCoreParser {
     fn parse_blocks_func_body_suite(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let mut nodes_list : Box<Vec<Box<ASTNode>>> = Box::new(Vec::new());
-        match self.symbol.clone() {
+        match self.expecting(kinds![ Newline ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::Newline(..) => {
@@ -530,13 +603,13 @@ impl Blocks for PythonCoreParser {
                         let _ = self.advance();
                         let mut tc_symbol : Option<Box<Token>> = None;
                         let mut tc_newline : Option<Box<Token>> = None;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ TypeComment ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::TypeComment(..) => {
                                         tc_symbol = Some( s2 );
                                         let _ = self.advance();
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ Newline ]).clone() {
                                             Ok(s3) => {
                                                 match &*s3 {
                                                     Token::Newline(..) => {
@@ -554,7 +627,7 @@ impl Blocks for PythonCoreParser {
                             },
                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in function body!", start_pos))
                         }
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ Indent ]).clone() {
                             Ok(s4) => {
                                 match &*s4{
                                     Token::Indent(..) => {
@@ -562,7 +635,7 @@ impl Blocks for PythonCoreParser {
                                         let _ = self.advance();
                                         nodes_list.push(self.parse_statements_stmt()?);
                                         while
-                                            match self.symbol.clone() {
+                                            match self.expecting(kinds![ Dedent ]).clone() {
                                                 Ok(s5) => {
                                                     match &*s5 {
                                                         Token::Dedent(..) => false,
@@ -575,7 +648,7 @@ impl Blocks for PythonCoreParser {
                                                 _ => return Err(format!("SyntaxError at {}: Expecting symbol in function body!", start_pos))
                                             } { };
                                         nodes_list.reverse();
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ Dedent ]).clone() {
                                             Ok(s6) => {
                                                 match &*s6 {
                                                     Token::Dedent(..) => {
@@ -604,13 +677,13 @@ impl Blocks for PythonCoreParser {
 
     fn parse_blocks_class_def(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyClass ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyClass(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ AtomName ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::AtomName(..) => {
@@ -619,22 +692,22 @@ impl Blocks for PythonCoreParser {
                                         let mut symbol3 : Option<Box<Token>> = None;
                                         let mut symbol4 : Option<Box<Token>> = None;
                                         let mut left_node : Option<Box<ASTNode>> = None;
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyLeftParen ]).clone() {
                                             Ok(s3) => {
                                                 match &*s3 {
                                                     Token::PyLeftParen(..) => {
                                                         symbol3 = Some( s3 );
                                                         let _ = self.advance();
-                                                        left_node = match self.symbol.clone() {
+                                                        left_node = match self.expecting(kinds![ PyRightParen ]).clone() {
                                                             Ok(s5) => {
                                                                 match &*s5 {
                                                                     Token::PyRightParen(..) => None,
-                                                                    _ => Some(self.parse_expressions_var_args_list()?)
+                                                                    _ => Some(self.parse_expressions_arglist()?)
                                                                 }
                                                             },
                                                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in class statement!", start_pos))
                                                         };
-                                                        match self.symbol.clone() {
+                                                        match self.expecting(kinds![ PyRightParen ]).clone() {
                                                             Ok(s6) => {
                                                                 match &*s6 {
                                                                     Token::PyRightParen(..) => {
@@ -652,7 +725,7 @@ impl Blocks for PythonCoreParser {
                                             },
                                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in class statement!", start_pos))
                                         }
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyColon ]).clone() {
                                             Ok(s4) => {
                                                 match &*s4 {
                                                     Token::PyColon(..) => {
@@ -1,5 +1,6 @@
 
 use crate::{ ASTNode, Token, Parser, PythonCoreParser, Tokenizer };
+use crate::parser::parser::kinds;
 
 
 pub trait Expressions {
@@ -46,7 +47,7 @@ pub trait Expressions {
 impl Expressions for PythonCoreParser {
     fn parse_expressions_atom(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyElipsis, PyFalse, PyNone, PyTrue, AtomName, AtomNumber, AtomString, PyLeftParen, PyLeftBracket, PyLeftCurly ]).clone() {
             Ok(s) => {
                 let symbol1 = (*s).clone();
                 match symbol1 {
@@ -78,7 +79,7 @@ impl Expressions for PythonCoreParser {
                         let mut lst: Vec<Box<Token>> = Vec::new();
                         lst.push(Box::new(symbol1));
                         let _ = &self.advance();
-                        while   match self.symbol.clone() {
+                        while   match self.expecting(kinds![ AtomString ]).clone() {
                                 Ok(s) => {
                                     let symbol1 = (*s).clone();
                                     match symbol1 {
@@ -97,7 +98,7 @@ impl Expressions for PythonCoreParser {
                     Token::PyLeftParen(..) => {
                         let _ = self.advance();
                         let mut right : Option<Box<ASTNode>> = None;
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyYield, PyRightParen ]) {
                             Ok(s) => {
                                 match **s {
                                     Token::PyYield(..) => {
@@ -111,7 +112,7 @@ impl Expressions for PythonCoreParser {
                             },
                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in atom expression!", start_pos))
                         }
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyRightParen ]) {
                             Ok(s2) => {
                                 match **s2 {
                                     Token::PyRightParen(..) => {
@@ -128,7 +129,7 @@ impl Expressions for PythonCoreParser {
                     Token::PyLeftBracket(..) => {
                         let _ = self.advance();
                         let mut right : Option<Box<ASTNode>> = None;
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyRightBracket ]) {
                             Ok(s) => {
                                 match **s {
                                     Token::PyRightBracket(..) => { },
@@ -139,7 +140,7 @@ impl Expressions for PythonCoreParser {
                             },
                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in atom expression!", start_pos))
                         }
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyRightBracket ]) {
                             Ok(s2) => {
                                 match **s2 {
                                     Token::PyRightBracket(..) => {
@@ -156,7 +157,7 @@ impl Expressions for PythonCoreParser {
                     Token::PyLeftCurly(..) => {
                         let _ = self.advance();
                         let mut right : Option<Box<ASTNode>> = None;
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyRightCurly ]) {
                             Ok(s) => {
                                 match **s {
                                     Token::PyRightCurly(..) => { },
@@ -167,7 +168,7 @@ impl Expressions for PythonCoreParser {
                             },
                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in atom expression!", start_pos))
                         }
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyRightCurly ]) {
                             Ok(s2) => {
                                 match **s2 {
                                     Token::PyRightCurly(..) => {
@@ -185,7 +186,7 @@ impl Expressions for PythonCoreParser {
                                                     _ => Ok(Box::new(ASTNode::AtomDictionary(start_pos, self.lexer.get_position(), Box::new(symbol1), None, Box::new(symbol2))))
                                                 }
                                             },
-                                            None => Err(format!("SyntaxError at {}: Expecting dictionary/set in atom expression!", start_pos))
+                                            None => Ok(Box::new(ASTNode::AtomDictionary(start_pos, self.lexer.get_position(), Box::new(symbol1), None, Box::new(symbol2))))
                                         }
                                     },
                                     _ => Err(format!("SyntaxError at {}: Expecting end marker in dictionary/set atom expression!", start_pos))
@@ -206,7 +207,7 @@ impl Expressions for PythonCoreParser {
 
         /* Optional 'await' prefix */
         let mut await_symbol: Option<Box<Token>> = None;
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyAwait ]).clone() {
             Ok(s) => {
                 let symbol1 = (*s).clone();
                 match symbol1 {
@@ -228,7 +229,7 @@ impl Expressions for PythonCoreParser {
 
                 /* Optional trailers */
                 let mut lst : Vec<Box<ASTNode>> = Vec::new();
-                while   match self.symbol.clone() {
+                while   match self.expecting(kinds![ PyLeftParen, PyLeftBracket, PyDot ]).clone() {
                             Ok(s) => {
                                 match *s {
                                     Token::PyLeftParen(..) |
@@ -264,7 +265,7 @@ impl Expressions for PythonCoreParser {
     fn parse_expressions_power(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let left_node = self.parse_expressions_atom_expr()?;
-        match &self.symbol {
+        match self.expecting(kinds![ PyPower ]) {
             Ok(s) => {
                 match **s {
                     Token::PyPower(..) => {
@@ -282,7 +283,7 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_factor(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyPlus, PyMinus, PyBitInvert ]) {
             Ok(s) => {
                 match &**s {
                     Token::PyPlus(..) => {
@@ -313,7 +314,7 @@ impl Expressions for PythonCoreParser {
     fn parse_expressions_term(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let mut left_node = self.parse_expressions_factor()?;
-        while   match &self.symbol {
+        while   match self.expecting(kinds![ PyMul, PyDiv, PyFloorDiv, PyModulo, PyMatrice ]) {
                     Ok(symbol_x) => {
                         let symbol = (**symbol_x).clone();
                         match &symbol {
@@ -358,7 +359,7 @@ impl Expressions for PythonCoreParser {
     fn parse_expressions_arith(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let mut left_node = self.parse_expressions_term()?;
-        while   match &self.symbol {
+        while   match self.expecting(kinds![ PyPlus, PyMinus ]) {
             Ok(symbol_x) => {
                 let symbol = (**symbol_x).clone();
                 match &symbol {
@@ -385,7 +386,7 @@ impl Expressions for PythonCoreParser {
     fn parse_expressions_shift(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let mut left_node = self.parse_expressions_arith()?;
-        while   match &self.symbol {
+        while   match self.expecting(kinds![ PyShiftLeft, PyShiftRight ]) {
             Ok(symbol_x) => {
                 let symbol = (**symbol_x).clone();
                 match &symbol {
@@ -412,7 +413,7 @@ impl Expressions for PythonCoreParser {
     fn parse_expressions_and_expr(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let mut left_node = self.parse_expressions_shift()?;
-        while   match &self.symbol {
+        while   match self.expecting(kinds![ PyBitAnd ]) {
             Ok(symbol_x) => {
                 let symbol = (**symbol_x).clone();
                 match &symbol {
@@ -433,7 +434,7 @@ impl Expressions for PythonCoreParser {
     fn parse_expressions_xor_expr(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let mut left_node = self.parse_expressions_and_expr()?;
-        while   match &self.symbol {
+        while   match self.expecting(kinds![ PyBitXor ]) {
             Ok(symbol_x) => {
                 let symbol = (**symbol_x).clone();
                 match &symbol {
@@ -454,7 +455,7 @@ impl Expressions for PythonCoreParser {
     fn parse_expressions_expr(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let mut left_node = self.parse_expressions_xor_expr()?;
-        while   match &self.symbol {
+        while   match self.expecting(kinds![ PyBitOr ]) {
             Ok(symbol_x) => {
                 let symbol = (**symbol_x).clone();
                 match &symbol {
@@ -474,7 +475,7 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_star_expr(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyMul ]) {
             Ok(s) => {
                 match &**s {
                     Token::PyMul(..) => {
@@ -493,7 +494,7 @@ impl Expressions for PythonCoreParser {
     fn parse_expressions_comparison(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let mut left_node = self.parse_expressions_expr()?;
-        while   match &self.symbol {
+        while   match self.expecting(kinds![ PyLess, PyLessEqual, PyEqual, PyGreaterEqual, PyGreater, PyNotEqual, PyIn, PyIs, PyNot ]) {
             Ok(symbol_x) => {
                 let symbol = (**symbol_x).clone();
                 match &symbol {
@@ -541,7 +542,7 @@ impl Expressions for PythonCoreParser {
                     },
                     Token::PyIs(..) => {
                         let _ = self.advance();
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyNot ]) {
                             Ok(symbol_x) => {
                                 let symbol2 = (**symbol_x).clone();
                                 match &symbol2 {
@@ -564,7 +565,7 @@ impl Expressions for PythonCoreParser {
                     },
                     Token::PyNot(..) => {
                         let _ = self.advance();
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyIn ]) {
                             Ok(symbol_x) => {
                                 let symbol2 = (**symbol_x).clone();
                                 match &symbol2 {
@@ -594,7 +595,7 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_not_test(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyNot ]) {
             Ok(s) => {
                 match &**s {
                     Token::PyNot(..) => {
@@ -613,7 +614,7 @@ impl Expressions for PythonCoreParser {
     fn parse_expressions_and_test(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let mut left_node = self.parse_expressions_not_test()?;
-        while   match &self.symbol {
+        while   match self.expecting(kinds![ PyAnd ]) {
             Ok(symbol_x) => {
                 let symbol = (**symbol_x).clone();
                 match &symbol {
@@ -634,7 +635,7 @@ impl Expressions for PythonCoreParser {
     fn parse_expressions_or_test(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let mut left_node = self.parse_expressions_and_test()?;
-        while   match &self.symbol {
+        while   match self.expecting(kinds![ PyOr ]) {
             Ok(symbol_x) => {
                 let symbol = (**symbol_x).clone();
                 match &symbol {
@@ -654,12 +655,12 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_lambda_def(&mut self, cond: bool) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyLambda ]) {
             Ok(s1) => {
                 let symbol1 = (*s1).clone();
                 let _ = self.advance();
                 let mut left : Option<Box<ASTNode>> = None;
-                match &self.symbol {
+                match self.expecting(kinds![ PyColon ]) {
                     Ok(s2) => {
                         match &**s2 {
                             Token::PyColon(..) => { },
@@ -668,7 +669,7 @@ impl Expressions for PythonCoreParser {
                     },
                     _=> return Err(format!("SyntaxError at {}: Expecting symbol in 'lambda' expression!", self.lexer.get_position()))
                 }
-                match &self.symbol {
+                match self.expecting(kinds![ PyColon ]) {
                     Ok(s2) => {
                         match &**s2 {
                             Token::PyColon(..) => {
@@ -696,7 +697,7 @@ impl Expressions for PythonCoreParser {
     }
 
     fn parse_expressions_no_cond_test(&mut self) -> Result<Box<ASTNode>, String> {
-        match &self.symbol {
+        match self.expecting(kinds![ PyLambda ]) {
             Ok(symbol_x) => {
                 let symbol = (**symbol_x).clone();
                 match &symbol {
@@ -710,20 +711,20 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_test(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyLambda ]) {
             Ok(s) => {
                 match (**s).clone() {
                     Token::PyLambda(..) => self.parse_expressions_lambda_def(true),
                     _ => {
                         let left = self.parse_expressions_or_test()?;
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyIf ]) {
                             Ok(s2) => {
                                 let symbol1 = (**s2).clone();
                                 match &symbol1 {
                                     Token::PyIf(..) => {
                                         let _ = self.advance();
                                         let right = self.parse_expressions_or_test()?;
-                                        match &self.symbol {
+                                        match self.expecting(kinds![ PyElse ]) {
                                             Ok(s3) => {
                                                 let symbol2 = (**s3).clone();
                                                 match &symbol2 {
@@ -753,7 +754,7 @@ impl Expressions for PythonCoreParser {
     fn parse_expressions_named_expression(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let left = self.parse_expressions_test()?;
-        match &self.symbol {
+        match self.expecting(kinds![ PyColonAssign ]) {
             Ok(s) => {
                 let symbol = (**s).clone();
                 match &symbol {
@@ -773,7 +774,7 @@ impl Expressions for PythonCoreParser {
         let start_pos = self.lexer.get_position();
         let mut nodes_list : Box<Vec<Box<ASTNode>>> = Box::new(Vec::new());
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
-        match &self.symbol {
+        match self.expecting(kinds![ PyMul ]) {
             Ok(s) => {
                 match &(**s) {
                     Token::PyMul(..) => {
@@ -784,7 +785,7 @@ impl Expressions for PythonCoreParser {
             },
             _ => return Err(format!("SyntaxError at {}: Expecting symbol in list expression!", self.lexer.get_position()))
         }
-        match &self.symbol {
+        match self.expecting(kinds![ PyFor, PyAsync, PyComa ]) {
             Ok(s2) => {
                 match &(**s2) {
                     Token::PyFor( .. ) |
@@ -792,16 +793,18 @@ impl Expressions for PythonCoreParser {
                         nodes_list.push( self.parse_expressions_comp_for()? );
                     },
                     Token::PyComa( .. ) => {
-                        while match &self.symbol {
+                        while match self.expecting(kinds![ PyComa ]) {
                             Ok(s3) => {
                                 match &(**s3) {
                                     Token::PyComa( .. ) => {
                                         separators_list.push(Box::new((**s3).clone()));
                                         let _ = self.advance();
 
-                                        match &self.symbol {
+                                        match self.expecting(kinds![ PyRightParen, PyRightBracket, PyMul ]) {
                                             Ok(s4) => {
                                                 match &(**s4) {
+                                                    Token::PyRightParen(..) |
+                                                    Token::PyRightBracket(..) => return Ok(Box::new(ASTNode::TestListComp(start_pos, self.lexer.get_position(), { nodes_list.reverse(); nodes_list }, { separators_list.reverse(); separators_list }))),
                                                     Token::PyMul(..) => {
                                                         nodes_list.push(self.parse_expressions_star_expr()?)
                                                     },
@@ -830,23 +833,23 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_trailer(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyLeftParen, PyLeftBracket, PyDot ]) {
             Ok(s) => {
                 let symbol1 = (**s).clone();
                 match &symbol1 {
                     Token::PyLeftParen(..) => {
                         let mut right : Option<Box<ASTNode>> = None;
                         let _ = self.advance();
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyRightParen ]) {
                             Ok(s2) => {
                                 match &(**s2) {
                                     Token::PyRightParen(..) => { },
-                                    _ => right = Some(self.parse_expressions_subscript_list()?)
+                                    _ => right = Some(self.parse_expressions_arglist()?)
                                 }
                             },
                             _ => return Err(format!("Syntax Error at {} - Expecting symbol in trailer expression!", self.lexer.get_position()))
                         }
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyRightParen ]) {
                             Ok(s3) => {
                                 let symbol2 = (**s3).clone();
                                 match &symbol2 {
@@ -863,7 +866,7 @@ impl Expressions for PythonCoreParser {
                     Token::PyLeftBracket(..) => {
                         let _ = self.advance();
                         let right = self.parse_expressions_subscript_list()?;
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyRightBracket ]) {
                             Ok(s3) => {
                                 let symbol2 = (**s3).clone();
                                 match &symbol2 {
@@ -879,7 +882,7 @@ impl Expressions for PythonCoreParser {
                     },
                     Token::PyDot(..) => {
                         let _ = self.advance();
-                        match &self.symbol {
+                        match self.expecting(kinds![ AtomName ]) {
                             Ok(s2) => {
                                 let symbol2 = (**s2).clone();
                                 match &symbol2 {
@@ -906,15 +909,20 @@ impl Expressions for PythonCoreParser {
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
         nodes_list.push( self.parse_expressions_subscript()? );
         while
-            match &self.symbol {
+            match self.expecting(kinds![ PyComa ]) {
                 Ok(s) => {
                     match &**s {
                         Token::PyComa(..) => {
                             let symbol1 = (**s).clone();
                             separators_list.push( Box::new(symbol1) );
                             let _ = self.advance();
-                            nodes_list.push( self.parse_expressions_subscript()? );
-                            true
+                            match self.expecting(kinds![ PyRightBracket ]) {
+                                Ok(s2) if matches!(&**s2, Token::PyRightBracket(..)) => false,
+                                _ => {
+                                    nodes_list.push( self.parse_expressions_subscript()? );
+                                    true
+                                }
+                            }
                         },
                         _ => false
                     }
@@ -933,7 +941,7 @@ impl Expressions for PythonCoreParser {
         let mut symbol1 : Option<Box<Token>> = None;
         let mut symbol2 : Option<Box<Token>> = None;
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyColon ]) {
             Ok(s) => {
                 match &**s {
                     Token::PyColon(..) => { },
@@ -942,33 +950,34 @@ impl Expressions for PythonCoreParser {
             },
             _ => return Err(format!("Syntax Error at {} - Expecting symbol in subscript expression!", self.lexer.get_position()))
         };
-        match &self.symbol.clone() {
+        match self.expecting(kinds![ PyColon ]) {
             Ok(s2) => {
                 match &**s2{
                     Token::PyColon(..) => {
                         symbol1 = Some(Box::new((**s2).clone()));
                         let _ = self.advance();
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyRightBracket, PyComa, PyColon ]) {
                             Ok(s3) => {
                                 match &**s3 {
                                     Token::PyRightBracket(..) |
+                                    Token::PyComa(..) |
                                     Token::PyColon(..) => { },
                                     _ => second_node = Some(self.parse_expressions_test()?)
                                 }
                             },
                             _ => return Err(format!("Syntax Error at {} - Expecting symbol in subscript expression!", self.lexer.get_position()))
                         };
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyColon ]) {
                             Ok(s3) => {
-                                match &**s2{
+                                match &**s3 {
                                     Token::PyColon(..) => {
                                         symbol2 = Some(Box::new((**s3).clone()));
                                         let _ = self.advance();
-                                        match &self.symbol {
+                                        match self.expecting(kinds![ PyRightBracket, PyComa ]) {
                                             Ok(s4) => {
                                                 match &**s4 {
                                                     Token::PyRightBracket(..) |
-                                                    Token::PyColon(..) => { },
+                                                    Token::PyComa(..) => { },
                                                     _ => third_node = Some(self.parse_expressions_test()?)
                                                 }
                                             },
@@ -993,26 +1002,26 @@ impl Expressions for PythonCoreParser {
         let start_pos = self.lexer.get_position();
         let mut nodes_list : Box<Vec<Box<ASTNode>>> = Box::new(Vec::new());
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
-        match &self.symbol {
+        match self.expecting(kinds![ PyMul ]) {
             Ok(s) => {
                 match &(**s) {
                     Token::PyMul(..) => {
                         nodes_list.push(self.parse_expressions_star_expr()?)
                     },
-                    _ => nodes_list.push(self.parse_expressions_named_expression()?)
+                    _ => nodes_list.push(self.parse_expressions_expr()?)
                 }
             },
             _ => return Err(format!("SyntaxError at {}: Expecting symbol in list expression!", self.lexer.get_position()))
         };
         while
-            match &self.symbol {
+            match self.expecting(kinds![ PyComa ]) {
                 Ok(s) => {
                     match &**s {
                         Token::PyComa(..) => {
                             let symbol1 = (**s).clone();
                             separators_list.push( Box::new(symbol1) );
                             let _ = self.advance();
-                            match &self.symbol {
+                            match self.expecting(kinds![ PyIn, PyMul ]) {
                                 Ok(s2) => {
                                     match &(**s2) {
                                         Token::PyIn(..) => false,
@@ -1022,7 +1031,7 @@ impl Expressions for PythonCoreParser {
                                             true
                                         },
                                         _ => {
-                                            nodes_list.push(self.parse_expressions_named_expression()?);
+                                            nodes_list.push(self.parse_expressions_expr()?);
                                             true
                                         }
                                     }
@@ -1050,14 +1059,14 @@ impl Expressions for PythonCoreParser {
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
         nodes_list.push(self.parse_expressions_test()?);
         while
-            match &self.symbol {
+            match self.expecting(kinds![ PyComa ]) {
                 Ok(s) => {
                     match &**s {
                         Token::PyComa(..) => {
                             let symbol1 = (**s).clone();
                             separators_list.push( Box::new(symbol1) );
                             let _ = self.advance();
-                            match &self.symbol {
+                            match self.expecting(kinds![ Newline, PySemiColon, EOF ]) {
                                 Ok(s2) => {
                                     match &(**s2) {
                                         Token::Newline(..) |
@@ -1092,7 +1101,7 @@ impl Expressions for PythonCoreParser {
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
         let mut is_dictionary = true;
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyMul, PyPower ]) {
             Ok(s) => {
                 match &**s {
                     Token::PyMul(..) => {
@@ -1110,7 +1119,7 @@ impl Expressions for PythonCoreParser {
                     },
                     _ => {
                         let left_node = self.parse_expressions_test()?;
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyColon ]) {
                             Ok(s2) => {
                                 match &**s2 {
                                     Token::PyColon(..) => {
@@ -1132,38 +1141,56 @@ impl Expressions for PythonCoreParser {
             },
             _ => return Err(format!("Syntax Error at {} - Expecting symbol in dictionary/set expression!", self.lexer.get_position()))
         }
+        match self.expecting(kinds![ PyFor, PyAsync ]) {
+            Ok(s) => {
+                match &**s {
+                    Token::PyFor(..) |
+                    Token::PyAsync(..) => {
+                        nodes_list.push( self.parse_expressions_comp_for()? );
+                        return match is_dictionary {
+                            true => Ok(Box::new( ASTNode::DictionaryContainer(start_pos, self.lexer.get_position(), nodes_list, separators_list) )),
+                            _ => Ok(Box::new( ASTNode::SetContainer(start_pos, self.lexer.get_position(), nodes_list, separators_list) ))
+                        }
+                    },
+                    _ => { }
+                }
+            },
+            _ => return Err(format!("Syntax Error at {} - Expecting symbol in dictionary/set expression!", self.lexer.get_position()))
+        }
         match is_dictionary {
             true => {
                 while
-                    match &self.symbol {
+                    match self.expecting(kinds![ PyComa ]) {
                         Ok(s) => {
                             match &**s {
                                 Token::PyComa(..) => {
                                     let symbol1 = (**s).clone();
                                     separators_list.push( Box::new(symbol1) );
                                     let _ = self.advance();
-                                    match &self.symbol {
+                                    match self.expecting(kinds![ PyRightCurly, PyPower ]) {
                                         Ok(s2) => {
                                             match &(**s2) {
                                                 Token::PyRightCurly(..) => false,
                                                 Token::PyComa(..) => return Err(format!("SyntaxError at {}: Missing elements between two ',' in set list expression!", self.lexer.get_position())),
                                                 Token::PyPower(..) => {
+                                                    let entry_pos = self.lexer.get_position();
                                                     let symbol2 = (**s2).clone();
                                                     let _ = self.advance();
                                                     let right_node = self.parse_expressions_expr()?;
-                                                    nodes_list.push( Box::new( ASTNode::PowerDictionary(start_pos, self.lexer.get_position(), Box::new(symbol2), right_node) ) );
+                                                    nodes_list.push( Box::new( ASTNode::PowerDictionary(entry_pos, self.lexer.get_position(), Box::new(symbol2), right_node) ) );
                                                     true
                                                 },
                                                 _ => {
+                                                    let entry_pos = self.lexer.get_position();
                                                     let left_node = self.parse_expressions_test()?;
-                                                    match &self.symbol {
+                                                    match self.expecting(kinds![ PyColon ]) {
                                                         Ok(s3) => {
                                                             match &(**s3) {
                                                                 Token::PyColon(..) => {
                                                                     let symbol3 = (**s3).clone();
                                                                     let _ = self.advance();
                                                                     let right_node = self.parse_expressions_test()?;
-                                                                    nodes_list.push( Box::new( ASTNode::DictionaryEntry(start_pos, self.lexer.get_position(), left_node, Box::new(symbol3), right_node) ) );
+                                                                    nodes_list.push( Box::new( ASTNode::DictionaryEntry(entry_pos, self.lexer.get_position(), left_node, Box::new(symbol3), right_node) ) );
                                                                     true
                                                                 },
                                                                 _ => return Err(format!("SyntaxError at {}: Expecting ':' in dictionary entry expression!", self.lexer.get_position()))
@@ -1176,7 +1203,7 @@ impl Expressions for PythonCoreParser {
                                         },
                                         _ => return Err(format!("SyntaxError at {}: Expecting symbol in argument list expression!", self.lexer.get_position()))
                                     };
-                                    false
+                                    true
                                 },
                                 _ => false
                             }
@@ -1186,23 +1213,24 @@ impl Expressions for PythonCoreParser {
             },
             false => {
                 while
-                    match &self.symbol {
+                    match self.expecting(kinds![ PyComa ]) {
                         Ok(s) => {
                             match &**s {
                                 Token::PyComa(..) => {
                                     let symbol1 = (**s).clone();
                                     separators_list.push( Box::new(symbol1) );
                                     let _ = self.advance();
-                                    match &self.symbol {
+                                    match self.expecting(kinds![ PyRightCurly, PyMul ]) {
                                         Ok(s2) => {
                                             match &(**s2) {
                                                 Token::PyRightCurly(..) => false,
                                                 Token::PyComa(..) => return Err(format!("SyntaxError at {}: Missing elements between two ',' in set list expression!", self.lexer.get_position())),
                                                 Token::PyMul(..) => {
+                                                    let entry_pos = self.lexer.get_position();
                                                     let symbol2 = (**s2).clone();
                                                     let _ = self.advance();
                                                     let right_node = self.parse_expressions_expr()?;
-                                                    nodes_list.push( Box::new( ASTNode::MulSet(start_pos, self.lexer.get_position(), Box::new(symbol2), right_node) ) );
+                                                    nodes_list.push( Box::new( ASTNode::MulSet(entry_pos, self.lexer.get_position(), Box::new(symbol2), right_node) ) );
                                                     true
                                                 },
                                                 _ => {
@@ -1240,14 +1268,14 @@ impl Expressions for PythonCoreParser {
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
         nodes_list.push(self.parse_expressions_argument()?);
         while
-            match &self.symbol {
+            match self.expecting(kinds![ PyComa ]) {
                 Ok(s) => {
                     match &**s {
                         Token::PyComa(..) => {
                             let symbol1 = (**s).clone();
                             separators_list.push( Box::new(symbol1) );
                             let _ = self.advance();
-                            match &self.symbol {
+                            match self.expecting(kinds![ PyRightParen ]) {
                                 Ok(s2) => {
                                     match &(**s2) {
                                         Token::PyRightParen(..) => false,
@@ -1274,7 +1302,7 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_argument(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyMul, PyPower ]) {
             Ok(s) => {
                 match &**s {
                     Token::PyMul(..) |
@@ -1286,7 +1314,7 @@ impl Expressions for PythonCoreParser {
                     },
                     _ => {
                         let left_node = Some( self.parse_expressions_test()? );
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyFor, PyAsync, PyColonAssign, PyAssign ]) {
                             Ok(s2) => {
                                 match &**s2 {
                                     Token::PyFor(..) |
@@ -1316,7 +1344,7 @@ impl Expressions for PythonCoreParser {
     }
 
     fn parse_expressions_comp_iter(&mut self) -> Result<Box<ASTNode>, String> {
-        match &self.symbol {
+        match self.expecting(kinds![ PyFor, PyAsync, PyIf ]) {
             Ok(s) => {
                 match &**s {
                     Token::PyFor(..) |
@@ -1335,21 +1363,21 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_sync_comp_for(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyFor ]) {
             Ok(s1) => {
                 match &(**s1) {
                     Token::PyFor(..) => {
                         let symbol1 = Box::new((**s1).clone());
                         let _ = self.advance();
                         let left_node = self.parse_expressions_exprlist()?;
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyIn ]) {
                             Ok(s2) => {
                                 match &(**s2) {
                                     Token::PyIn(..) => {
                                         let symbol2 = Box::new((**s2).clone());
                                         let _ = self.advance();
                                         let right_node = self.parse_expressions_or_test()?;
-                                        match &self.symbol {
+                                        match self.expecting(kinds![ PyAsync, PyFor, PyIf ]) {
                                             Ok(s3) => {
                                                 match &(**s3) {
                                                     Token::PyAsync(..) |
@@ -1381,7 +1409,7 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_comp_for(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyAsync ]) {
             Ok(s1) => {
                 match &(**s1) {
                     Token::PyAsync(..) => {
@@ -1401,14 +1429,14 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_comp_if(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyIf ]) {
             Ok(s1) => {
                 match &(**s1) {
                     Token::PyIf(..) => {
                         let symbol1 = Box::new((**s1).clone());
                         let _ = self.advance();
                         let right_node = self.parse_expressions_no_cond_test()?;
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyAsync, PyFor, PyIf ]) {
                             Ok(s2) => {
                                 match &(**s2) {
                                     Token::PyAsync( .. ) |
@@ -1434,13 +1462,13 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_yield_expr(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ PyYield ]) {
             Ok(s1) => {
                 match &(**s1) {
                     Token::PyYield(..) => {
                         let symbol1 = Box::new((**s1).clone());
                         let _ = self.advance();
-                        match &self.symbol {
+                        match self.expecting(kinds![ PyFrom, PyRightParen, PyRightBracket, PyRightCurly, PyAssign, PySemiColon, Newline, EOF ]) {
                             Ok(s2) => {
                                 match &(**s2) {
                                     Token::PyFrom(..) => {
@@ -1449,9 +1477,18 @@ impl Expressions for PythonCoreParser {
                                         let right_node = self.parse_expressions_test()?;
                                         Ok(Box::new(ASTNode::YieldFromExpr(start_pos, self.lexer.get_position(), symbol1, symbol2, right_node)))
                                     },
+                                    Token::PyRightParen(..) |
+                                    Token::PyRightBracket(..) |
+                                    Token::PyRightCurly(..) |
+                                    Token::PyAssign(..) |
+                                    Token::PySemiColon(..) |
+                                    Token::Newline(..) |
+                                    Token::EOF(..) => {
+                                        Ok(Box::new(ASTNode::YieldExpr(start_pos, self.lexer.get_position(), symbol1, None)))
+                                    },
                                     _ => {
                                         let right_node = self.parse_expressions_testlist_star_expr()?;
-                                        Ok(Box::new(ASTNode::YieldExpr(start_pos, self.lexer.get_position(), symbol1, right_node)))
+                                        Ok(Box::new(ASTNode::YieldExpr(start_pos, self.lexer.get_position(), symbol1, Some(right_node))))
                                     }
                                 }
                             },
@@ -1469,7 +1506,7 @@ impl Expressions for PythonCoreParser {
         let start_pos = self.lexer.get_position();
         let mut nodes_list : Box<Vec<Box<ASTNode>>> = Box::new(Vec::new());
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
-        match &self.symbol {
+        match self.expecting(kinds![ PyMul ]) {
             Ok(s) => {
                 match &(**s) {
                     Token::PyMul(..) => {
@@ -1481,14 +1518,14 @@ impl Expressions for PythonCoreParser {
             _ => return Err(format!("SyntaxError at {}: Expecting symbol in list expression!", self.lexer.get_position()))
         };
         while
-            match &self.symbol {
+            match self.expecting(kinds![ PyComa ]) {
                 Ok(s) => {
                     match &**s {
                         Token::PyComa(..) => {
                             let symbol1 = (**s).clone();
                             separators_list.push( Box::new(symbol1) );
                             let _ = self.advance();
-                            match &self.symbol {
+                            match self.expecting(kinds![ PyPlusAssign, PyMinusAssign, PyMulAssign, PyPowerAssign, PyModuloAssign, PyMatriceAssign, PyFloorDivAssign, PyDivAssign, PyShiftLeftAssign, PyShiftRightAssign, PyBitAndAssign, PyBitOrAssign, PyBitXorAssign, PyAssign, PySemiColon, Newline, EOF, PyColon, PyMul ]) {
                                 Ok(s2) => {
                                     match &(**s2) {
                                         Token::PyPlusAssign( .. ) |
@@ -1543,295 +1580,88 @@ impl Expressions for PythonCoreParser {
         let start_pos = self.lexer.get_position();
         let mut nodes_list : Box<Vec<Box<ASTNode>>> = Box::new(Vec::new());
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
-        let mut mul_symbol : Option<Box<Token>> = None;
-        let mut mul_node : Option<Box<ASTNode>> = None;
-        let mut power_symbol : Option<Box<Token>> = None;
-        let mut power_node : Option<Box<ASTNode>> = None;
-        let mut div_symbol : Option<Box<Token>> = None;
-        let mut coma_found : bool = false;
-        match &self.symbol {
-            Ok(s) => {
-                match &**s {
-                    Token::PyMul(..) => {
-                        mul_symbol = Some( s.clone() );
-                        let _ = self.advance();
-                        mul_node = Some( self.parse_expressions_var_args_assignments()?  );
-                        while
-                            match &self.symbol {
+        let mut markers_list : Box<Vec<Box<Token>>> = Box::new(Vec::new()); /* '/' and bare '*' */
+        let mut mul_node : Option<(Box<Token>, Box<ASTNode>)> = None;
+        let mut power_node : Option<(Box<Token>, Box<ASTNode>)> = None;
+        while
+            match self.expecting(kinds![ PyColon, PyDiv, PyMul, PyPower ]).clone() {
+                Ok(s) => {
+                    match &*s {
+                        Token::PyColon(..) => false,
+                        Token::PyDiv(..) => {
+                            match ( markers_list.len(), &mul_node, &power_node ) {
+                                ( 0, None, None ) => { },
+                                _ => return Err(format!("Syntax Error at {} - Unexpected '/' in variable arguments list expression!", self.lexer.get_position()))
+                            }
+                            markers_list.push( s );
+                            let _ = self.advance();
+                            true
+                        },
+                        Token::PyMul(..) => {
+                            match ( &mul_node, &power_node ) {
+                                ( None, None ) => { },
+                                _ => return Err(format!("Syntax Error at {} - Unexpected '*' in variable arguments list expression!", self.lexer.get_position()))
+                            }
+                            let _ = self.advance();
+                            match self.expecting(kinds![ PyComa, PyColon ]).clone() {
                                 Ok(s2) => {
-                                    match &**s2 {
-                                        Token::PyComa(..) => {
-                                            separators_list.push( s2.clone() );
-                                            let _ = self.advance();
-                                            match &self.symbol {
-                                                Ok(s3) => {
-                                                    match &**s3 {
-                                                        Token::PyPower(..) => {
-                                                            power_symbol = Some( s3.clone() );
-                                                            let _ = self.advance();
-                                                            power_node = Some( self.parse_expressions_var_args_assignments()? );
-                                                            match &self.symbol {
-                                                                Ok(s4) => {
-                                                                    match &**s4 {
-                                                                        Token::PyComa(..) => {
-                                                                            separators_list.push( s4.clone());
-                                                                            let _ = self.advance();
-                                                                        },
-                                                                        _ => { }
-                                                                    }
-                                                                },
-                                                                _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                            }
-                                                            false
-                                                        },
-                                                        _ => {
-                                                            nodes_list.push( self.parse_expressions_var_args_assignments()? );
-                                                            true
-                                                        }
-                                                    }
-                                                },
-                                                _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                            }
+                                    match &*s2 {
+                                        Token::PyComa(..) |
+                                        Token::PyColon(..) => {
+                                            markers_list.push( s );
                                         },
-                                        _ => false
+                                        _ => {
+                                            mul_node = Some( ( s, self.parse_expressions_vfp_def()? ) );
+                                        }
                                     }
                                 },
                                 _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                            } {};
-                    },
-                    Token::PyPower(..) => {
-                        power_symbol = Some( s.clone() );
-                        let _ = self.advance();
-                        power_node = Some( self.parse_expressions_var_args_assignments()? );
-                        match &self.symbol {
-                            Ok(s2) => {
-                                match &**s2 {
-                                    Token::PyComa(..) => {
-                                        separators_list.push( s2.clone());
-                                        let _ = self.advance();
-                                    },
-                                    _ => { }
-                                }
-                            },
-                            _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
+                            }
+                            true
+                        },
+                        Token::PyPower(..) => {
+                            match &power_node {
+                                None => { },
+                                _ => return Err(format!("Syntax Error at {} - Unexpected '**' in variable arguments list expression!", self.lexer.get_position()))
+                            }
+                            let _ = self.advance();
+                            power_node = Some( ( s, self.parse_expressions_vfp_def()? ) );
+                            true
+                        },
+                        _ => {
+                            match &power_node {
+                                None => { },
+                                _ => return Err(format!("Syntax Error at {} - Parameter after '**' in variable arguments list expression!", self.lexer.get_position()))
+                            }
+                            nodes_list.push( self.parse_expressions_var_args_assignments()? );
+                            true
                         }
-                    },
-                    _ => {
-                        nodes_list.push( (self.parse_expressions_var_args_assignments()?).clone() );
-                        while
-                            match &self.symbol {
-                                Ok(s5) => {
-                                    match &**s5 {
-                                        Token::PyComa(..) => {
-                                            separators_list.push(s5.clone());
-                                            let _ = self.advance();
-                                            match &self.symbol {
-                                                Ok(s6) => {
-                                                    match &**s6 {
-                                                        Token::PyDiv( .. ) => {
-                                                            div_symbol = Some(s6.clone());
-                                                            let _ = self.advance();
-                                                            while
-                                                                match &self.symbol {
-                                                                    Ok(s11) => {
-                                                                        match &**s11 {
-                                                                            Token::PyComa(..) => {
-                                                                                separators_list.push(s11.clone());
-                                                                                let _ = self.advance();
-                                                                                match &self.symbol {
-                                                                                    Ok(s12) => {
-                                                                                        match &**s12 {
-                                                                                            Token::PyMul( .. ) |
-                                                                                            Token::PyPower( .. ) => {
-                                                                                                coma_found = true;
-                                                                                                false
-                                                                                            },
-                                                                                            _ => {
-                                                                                                nodes_list.push( self.parse_expressions_var_args_assignments()? );
-                                                                                                true
-                                                                                            }
-                                                                                        }
-                                                                                    },
-                                                                                    _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                                                }
-                                                                            },
-                                                                            _ => false
-                                                                        }
-                                                                    },
-                                                                    _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                                } {};
-
-                                                            match &self.symbol {
-                                                                Ok(s20) => {
-                                                                    match (coma_found, &**s20) {
-                                                                        (true, Token::PyMul(..)) => {
-                                                                            mul_symbol = Some( s20.clone() );
-                                                                            let _ = self.advance();
-                                                                            mul_node = Some( self.parse_expressions_var_args_assignments()?  );
-                                                                            while
-                                                                                match &self.symbol {
-                                                                                    Ok(s30) => {
-                                                                                        match &**s30 {
-                                                                                            Token::PyComa(..) => {
-                                                                                                separators_list.push( s30.clone() );
-                                                                                                let _ = self.advance();
-                                                                                                match &self.symbol {
-                                                                                                    Ok(s31) => {
-                                                                                                        match &**s31 {
-                                                                                                            Token::PyPower(..) => {
-                                                                                                                power_symbol = Some( s31.clone() );
-                                                                                                                let _ = self.advance();
-                                                                                                                power_node = Some( self.parse_expressions_var_args_assignments()? );
-                                                                                                                match &self.symbol {
-                                                                                                                    Ok(s32) => {
-                                                                                                                        match &**s32 {
-                                                                                                                            Token::PyComa(..) => {
-                                                                                                                                separators_list.push( s32.clone());
-                                                                                                                                let _ = self.advance();
-                                                                                                                            },
-                                                                                                                            _ => { }
-                                                                                                                        }
-                                                                                                                    },
-                                                                                                                    _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                                                                                }
-                                                                                                                false
-                                                                                                            },
-                                                                                                            _ => {
-                                                                                                                nodes_list.push( self.parse_expressions_var_args_assignments()? );
-                                                                                                                true
-                                                                                                            }
-                                                                                                        }
-                                                                                                    },
-                                                                                                    _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                                                                }
-                                                                                            },
-                                                                                            _ => false
-                                                                                        }
-                                                                                    },
-                                                                                    _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                                                } {};
-                                                                            false
-                                                                        },
-                                                                        (true, Token::PyPower(..)) => {
-                                                                            power_symbol = Some( s20.clone() );
-                                                                            let _ = self.advance();
-                                                                            power_node = Some( self.parse_expressions_var_args_assignments()? );
-                                                                            match &self.symbol {
-                                                                                Ok(s21) => {
-                                                                                    match &**s21 {
-                                                                                        Token::PyComa(..) => {
-                                                                                            separators_list.push( s21.clone());
-                                                                                            let _ = self.advance();
-                                                                                        },
-                                                                                        _ => { }
-                                                                                    }
-                                                                                },
-                                                                                _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                                            }
-                                                                            false
-                                                                        },
-                                                                        _ => {
-                                                                            false
-                                                                        }
-                                                                    }
-                                                                },
-                                                                _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                            }
-                                                        },
-                                                        Token::PyMul(..) => {
-                                                            mul_symbol = Some( s6.clone() );
-                                                            let _ = self.advance();
-                                                            mul_node = Some( self.parse_expressions_var_args_assignments()?  );
-                                                            while
-                                                                match &self.symbol {
-                                                                    Ok(s8) => {
-                                                                        match &**s8 {
-                                                                            Token::PyComa(..) => {
-                                                                                separators_list.push( s8.clone() );
-                                                                                let _ = self.advance();
-                                                                                match &self.symbol {
-                                                                                    Ok(s9) => {
-                                                                                        match &**s9 {
-                                                                                            Token::PyPower(..) => {
-                                                                                                power_symbol = Some( s9.clone() );
-                                                                                                let _ = self.advance();
-                                                                                                power_node = Some( self.parse_expressions_var_args_assignments()? );
-                                                                                                match &self.symbol {
-                                                                                                    Ok(s10) => {
-                                                                                                        match &**s10 {
-                                                                                                            Token::PyComa(..) => {
-                                                                                                                separators_list.push( s10.clone());
-                                                                                                                let _ = self.advance();
-                                                                                                            },
-                                                                                                            _ => { }
-                                                                                                        }
-                                                                                                    },
-                                                                                                    _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                                                                }
-                                                                                                false
-                                                                                            },
-                                                                                            _ => {
-                                                                                                nodes_list.push( self.parse_expressions_var_args_assignments()? );
-                                                                                                true
-                                                                                            }
-                                                                                        }
-                                                                                    },
-                                                                                    _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                                                }
-                                                                            },
-                                                                            _ => false
-                                                                        }
-                                                                    },
-                                                                    _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                                } {};
-                                                            false
-                                                        },
-                                                        Token::PyPower(..) => {
-                                                            power_symbol = Some( s6.clone() );
-                                                            let _ = self.advance();
-                                                            power_node = Some( self.parse_expressions_var_args_assignments()? );
-                                                            match &self.symbol {
-                                                                Ok(s7) => {
-                                                                    match &**s7 {
-                                                                        Token::PyComa(..) => {
-                                                                            separators_list.push( s7.clone());
-                                                                            let _ = self.advance();
-                                                                        },
-                                                                        _ => { }
-                                                                    }
-                                                                },
-                                                                _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                                            }
-                                                            false
-                                                        },
-                                                        _ => {
-                                                            nodes_list.push( self.parse_expressions_var_args_assignments()? );
-                                                            true
-                                                        }
-                                                    }
-                                                },
-                                                _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                                            }
-                                        },
-                                        _ => false
-                                    }
-                                },
-                                _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-                            } {};
                     }
-                }
-            },
-            _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
-        }
+                },
+                _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
+            } && match self.expecting(kinds![ PyComa ]).clone() {
+                Ok(s3) => {
+                    match &*s3 {
+                        Token::PyComa(..) => {
+                            separators_list.push( s3 );
+                            let _ = self.advance();
+                            true
+                        },
+                        _ => false
+                    }
+                },
+                _ => return Err(format!("Syntax Error at {} - Expecting symbol in variable arguments list expression!", self.lexer.get_position()))
+            } { };
         nodes_list.reverse();
         separators_list.reverse();
-        Ok(Box::new( ASTNode::VarArgsList(start_pos, self.lexer.get_position(), nodes_list, separators_list, mul_symbol, mul_node, power_symbol, power_node, div_symbol) ))
+        markers_list.reverse();
+        Ok(Box::new( ASTNode::VarArgsList(start_pos, self.lexer.get_position(), nodes_list, separators_list, markers_list, mul_node, power_node) ))
     }
 
     fn parse_expressions_var_args_assignments(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let left_node = self.parse_expressions_vfp_def()?;
-        match &self.symbol {
+        match self.expecting(kinds![ PyAssign ]) {
             Ok(s) => {
                 match &**s {
                    Token::PyAssign(..) => {
@@ -1851,7 +1681,7 @@ impl Expressions for PythonCoreParser {
 
     fn parse_expressions_vfp_def(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match &self.symbol {
+        match self.expecting(kinds![ AtomName ]) {
             Ok(s) => {
                 match &**s {
                     Token::AtomName(..) => {
@@ -1,5 +1,6 @@
 
 use crate::{ Token, Parser, PythonCoreParser, Expressions, Tokenizer, ASTNode };
+use crate::parser::parser::kinds;
 
 
 pub trait Functions {
@@ -11,14 +12,14 @@ pub trait Functions {
 impl Functions for PythonCoreParser {
     fn parse_functions_func_type(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyLeftParen ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyLeftParen(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
                         let mut left_node : Option<Box<ASTNode>> = None;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyRightParen ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyRightParen(..) => { },
@@ -29,13 +30,13 @@ impl Functions for PythonCoreParser {
                             },
                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in functional type!", start_pos))
                         }
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyRightParen ]).clone() {
                             Ok(s3) => {
                                 match &*s3 {
                                     Token::PyRightParen(..) => {
                                         let symbol2 = s3;
                                         let _ = self.advance();
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyArrow ]).clone() {
                                             Ok(s4) => {
                                                 match &*s4 {
                                                     Token::PyArrow(..) => {
@@ -72,7 +73,7 @@ impl Functions for PythonCoreParser {
         let mut power_symbol : Option<Box<Token>> = None;
         let mut power_node : Option<Box<ASTNode>> = None;
 
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyMul, PyPower ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyMul(..) => {
@@ -80,13 +81,13 @@ impl Functions for PythonCoreParser {
                         let _ = self.advance();
                         mul_node = Some( self.parse_expressions_test()? );
                         while
-                            match self.symbol.clone() {
+                            match self.expecting(kinds![ PyComa ]).clone() {
                                     Ok(s2) => {
                                         match &*s2 {
                                             Token::PyComa(..) => {
                                                 separators_list.push( s2 );
                                                 let _ = self.advance();
-                                                match self.symbol.clone() {
+                                                match self.expecting(kinds![ PyPower ]).clone() {
                                                     Ok(s) => {
                                                         match &*s {
                                                             Token::PyPower(..) => {
@@ -118,13 +119,13 @@ impl Functions for PythonCoreParser {
                     _ => {
                         nodes_list.push( self.parse_expressions_test()? );
                         while
-                            match self.symbol.clone() {
+                            match self.expecting(kinds![ PyComa ]).clone() {
                                 Ok(s2) => {
                                     match &*s2 {
                                         Token::PyComa(..) => {
                                             separators_list.push( s2 );
                                             let _ = self.advance();
-                                            match self.symbol.clone() {
+                                            match self.expecting(kinds![ PyMul, PyPower ]).clone() {
                                                 Ok(s) => {
                                                     match &*s {
                                                         Token::PyMul(..) => {
@@ -132,13 +133,13 @@ impl Functions for PythonCoreParser {
                                                             let _ = self.advance();
                                                             mul_node = Some( self.parse_expressions_test()? );
                                                             while
-                                                                match self.symbol.clone() {
+                                                                match self.expecting(kinds![ PyComa ]).clone() {
                                                                     Ok(s2) => {
                                                                         match &*s2 {
                                                                             Token::PyComa(..) => {
                                                                                 separators_list.push( s2 );
                                                                                 let _ = self.advance();
-                                                                                match self.symbol.clone() {
+                                                                                match self.expecting(kinds![ PyPower ]).clone() {
                                                                                     Ok(s) => {
                                                                                         match &*s {
                                                                                             Token::PyPower(..) => {
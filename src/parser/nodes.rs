@@ -1,7 +1,8 @@
 
+use std::hash::{ Hash, Hasher };
 use crate::parser::tokens::{ Token };
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ASTNode {
     NamedExpr(u32, u32, Box<ASTNode>, Box<Token>, Box<ASTNode>),
     Test(u32, u32, Box<ASTNode>, Box<Token>, Box<ASTNode>, Box<Token>, Box<ASTNode>),
@@ -67,7 +68,7 @@ pub enum ASTNode {
     SyncCompForComprehension(u32, u32, Box<Token>, Box<ASTNode>, Box<Token>, Box<ASTNode>, Option<Box<ASTNode>>),
     CompForComprehension(u32, u32,  Box<Token>, Box<ASTNode>),
     CompIfComprehension(u32, u32, Box<Token>, Box<ASTNode>, Option<Box<ASTNode>>),
-    YieldExpr(u32, u32, Box<Token>, Box<ASTNode>),
+    YieldExpr(u32, u32, Box<Token>, Option<Box<ASTNode>>),
     YieldFromExpr(u32, u32, Box<Token>, Box<Token>, Box<ASTNode>),
     FuncBodySuite(u32, u32, Box<Token>, Option<Box<Token>>, Option<Box<Token>>, Box<Token>, Box<Vec<Box<ASTNode>>>, Box<Token>),
     FuncTypeInput(u32, u32, Box<ASTNode>, Box<Vec<Box<Token>>>, Box<Token>),
@@ -127,7 +128,7 @@ pub enum ASTNode {
     TypedArgsList(u32, u32, Box<Vec<Box<ASTNode>>>, Box<Vec<Box<Token>>>, Box<Vec<Box<Token>>>, Option<(Box<Token>, Box<ASTNode>)>, Option<(Box<Token>, Box<ASTNode>)>),
     TFPAssign(u32, u32, Box<ASTNode>, Box<Token>, Box<ASTNode>),
     TFPDef(u32, u32, Box<Token>, Option<Box<(Box<Token>, Box<ASTNode>)>>),
-    VarArgsList(u32, u32, Box<Vec<Box<ASTNode>>>, Box<Vec<Box<Token>>>, Option<Box<Token>>, Option<Box<ASTNode>>, Option<Box<Token>>, Option<Box<ASTNode>>, Option<Box<Token>>),
+    VarArgsList(u32, u32, Box<Vec<Box<ASTNode>>>, Box<Vec<Box<Token>>>, Box<Vec<Box<Token>>>, Option<(Box<Token>, Box<ASTNode>)>, Option<(Box<Token>, Box<ASTNode>)>),
     VFPAssign(u32, u32, Box<ASTNode>, Box<Token>, Box<ASTNode>),
     VFPDef(u32, u32, Box<Token>),
     SingleInput(u32, u32, Option<Box<ASTNode>>, Option<Box<Token>>),
@@ -135,3 +136,590 @@ pub enum ASTNode {
     EvalInput(u32, u32, Box<ASTNode>, Box<Vec<Box<Token>>>, Box<Token>)
 }
 
+
+impl ASTNode {
+    /// Returns start and end position of node, where end is the start of the symbol following the node.
+    pub fn get_span(&self) -> ( u32, u32 ) {
+        match self {
+            ASTNode::NamedExpr(start, end, ..) |
+            ASTNode::Test(start, end, ..) |
+            ASTNode::Lambda(start, end, ..) |
+            ASTNode::OrTest(start, end, ..) |
+            ASTNode::AndTest(start, end, ..) |
+            ASTNode::NotTest(start, end, ..) |
+            ASTNode::LessComparison(start, end, ..) |
+            ASTNode::LessEqualComparison(start, end, ..) |
+            ASTNode::EqualComparison(start, end, ..) |
+            ASTNode::GreaterComparison(start, end, ..) |
+            ASTNode::GreaterEqualComparison(start, end, ..) |
+            ASTNode::NotEqualComparison(start, end, ..) |
+            ASTNode::InComparison(start, end, ..) |
+            ASTNode::IsComparison(start, end, ..) |
+            ASTNode::NotInComparison(start, end, ..) |
+            ASTNode::IsNotComparison(start, end, ..) |
+            ASTNode::StarExpr(start, end, ..) |
+            ASTNode::Expr(start, end, ..) |
+            ASTNode::XorExpr(start, end, ..) |
+            ASTNode::AndExpr(start, end, ..) |
+            ASTNode::ShiftLeftExpr(start, end, ..) |
+            ASTNode::ShiftRightExpr(start, end, ..) |
+            ASTNode::PlusArithExpr(start, end, ..) |
+            ASTNode::MinusArithExpr(start, end, ..) |
+            ASTNode::MulTerm(start, end, ..) |
+            ASTNode::DivTerm(start, end, ..) |
+            ASTNode::FloorDivTerm(start, end, ..) |
+            ASTNode::ModuloTerm(start, end, ..) |
+            ASTNode::MatriceTerm(start, end, ..) |
+            ASTNode::UnaryPlus(start, end, ..) |
+            ASTNode::UnaryMinus(start, end, ..) |
+            ASTNode::UnaryInvert(start, end, ..) |
+            ASTNode::PowerExpr(start, end, ..) |
+            ASTNode::AtomExpr(start, end, ..) |
+            ASTNode::AtomName(start, end, ..) |
+            ASTNode::AtomNumber(start, end, ..) |
+            ASTNode::AtomString(start, end, ..) |
+            ASTNode::AtomElipsis(start, end, ..) |
+            ASTNode::AtomNone(start, end, ..) |
+            ASTNode::AtomTrue(start, end, ..) |
+            ASTNode::AtomFalse(start, end, ..) |
+            ASTNode::AtomTuple(start, end, ..) |
+            ASTNode::AtomList(start, end, ..) |
+            ASTNode::AtomDictionary(start, end, ..) |
+            ASTNode::AtomSet(start, end, ..) |
+            ASTNode::TestListComp(start, end, ..) |
+            ASTNode::CallTrailer(start, end, ..) |
+            ASTNode::IndexTrailer(start, end, ..) |
+            ASTNode::DotNameTrailer(start, end, ..) |
+            ASTNode::SubscriptList(start, end, ..) |
+            ASTNode::Subscript(start, end, ..) |
+            ASTNode::ExprList(start, end, ..) |
+            ASTNode::TestList(start, end, ..) |
+            ASTNode::DictionaryContainer(start, end, ..) |
+            ASTNode::DictionaryEntry(start, end, ..) |
+            ASTNode::SetContainer(start, end, ..) |
+            ASTNode::MulSet(start, end, ..) |
+            ASTNode::PowerDictionary(start, end, ..) |
+            ASTNode::ClassDef(start, end, ..) |
+            ASTNode::ArgList(start, end, ..) |
+            ASTNode::Argument(start, end, ..) |
+            ASTNode::SyncCompForComprehension(start, end, ..) |
+            ASTNode::CompForComprehension(start, end, ..) |
+            ASTNode::CompIfComprehension(start, end, ..) |
+            ASTNode::YieldExpr(start, end, ..) |
+            ASTNode::YieldFromExpr(start, end, ..) |
+            ASTNode::FuncBodySuite(start, end, ..) |
+            ASTNode::FuncTypeInput(start, end, ..) |
+            ASTNode::FuncType(start, end, ..) |
+            ASTNode::TypeList(start, end, ..) |
+            ASTNode::TestListStarExpr(start, end, ..) |
+            ASTNode::SimpleStmtList(start, end, ..) |
+            ASTNode::PlusAssignStmt(start, end, ..) |
+            ASTNode::MinusAssignStmt(start, end, ..) |
+            ASTNode::MulAssignStmt(start, end, ..) |
+            ASTNode::DivAssignStmt(start, end, ..) |
+            ASTNode::FloorDivAssignStmt(start, end, ..) |
+            ASTNode::ModuloAssignStmt(start, end, ..) |
+            ASTNode::MatriceAssignStmt(start, end, ..) |
+            ASTNode::PowerAssignStmt(start, end, ..) |
+            ASTNode::ShiftLeftAssignStmt(start, end, ..) |
+            ASTNode::ShiftRightAssignStmt(start, end, ..) |
+            ASTNode::BitOrAssignStmt(start, end, ..) |
+            ASTNode::BitXorAssignStmt(start, end, ..) |
+            ASTNode::BitAndAssignStmt(start, end, ..) |
+            ASTNode::AnnAssignStmt(start, end, ..) |
+            ASTNode::AssignmentStmt(start, end, ..) |
+            ASTNode::DelStmt(start, end, ..) |
+            ASTNode::PassStmt(start, end, ..) |
+            ASTNode::BreakStmt(start, end, ..) |
+            ASTNode::ContinueStmt(start, end, ..) |
+            ASTNode::ReturnStmt(start, end, ..) |
+            ASTNode::RaiseStmt(start, end, ..) |
+            ASTNode::ImportNameStmt(start, end, ..) |
+            ASTNode::ImportFromStmt(start, end, ..) |
+            ASTNode::ImportAsName(start, end, ..) |
+            ASTNode::DottedAsNameStmt(start, end, ..) |
+            ASTNode::ImportAsNamesStmt(start, end, ..) |
+            ASTNode::DottedAsNamesStmt(start, end, ..) |
+            ASTNode::DottedNameStmt(start, end, ..) |
+            ASTNode::GlobalStmt(start, end, ..) |
+            ASTNode::NonLocalStmt(start, end, ..) |
+            ASTNode::AssertStmt(start, end, ..) |
+            ASTNode::AsyncStmt(start, end, ..) |
+            ASTNode::IfStmt(start, end, ..) |
+            ASTNode::ElifStmt(start, end, ..) |
+            ASTNode::ElseStmt(start, end, ..) |
+            ASTNode::WhileStmt(start, end, ..) |
+            ASTNode::ForStmt(start, end, ..) |
+            ASTNode::TryStmt(start, end, ..) |
+            ASTNode::FinallyStmt(start, end, ..) |
+            ASTNode::WithStmt(start, end, ..) |
+            ASTNode::WithItem(start, end, ..) |
+            ASTNode::ExceptClauseStmt(start, end, ..) |
+            ASTNode::ExceptStmt(start, end, ..) |
+            ASTNode::SuiteStmt(start, end, ..) |
+            ASTNode::Decorator(start, end, ..) |
+            ASTNode::Decorators(start, end, ..) |
+            ASTNode::Decorated(start, end, ..) |
+            ASTNode::FuncDef(start, end, ..) |
+            ASTNode::Parameter(start, end, ..) |
+            ASTNode::TypedArgsList(start, end, ..) |
+            ASTNode::TFPAssign(start, end, ..) |
+            ASTNode::TFPDef(start, end, ..) |
+            ASTNode::VarArgsList(start, end, ..) |
+            ASTNode::VFPAssign(start, end, ..) |
+            ASTNode::VFPDef(start, end, ..) |
+            ASTNode::SingleInput(start, end, ..) |
+            ASTNode::FileInput(start, end, ..) |
+            ASTNode::EvalInput(start, end, ..) => ( *start, *end )
+        }
+    }
+
+    /// Returns name of node kind, the same as name of the enum variant.
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            ASTNode::NamedExpr(..) => "NamedExpr",
+            ASTNode::Test(..) => "Test",
+            ASTNode::Lambda(..) => "Lambda",
+            ASTNode::OrTest(..) => "OrTest",
+            ASTNode::AndTest(..) => "AndTest",
+            ASTNode::NotTest(..) => "NotTest",
+            ASTNode::LessComparison(..) => "LessComparison",
+            ASTNode::LessEqualComparison(..) => "LessEqualComparison",
+            ASTNode::EqualComparison(..) => "EqualComparison",
+            ASTNode::GreaterComparison(..) => "GreaterComparison",
+            ASTNode::GreaterEqualComparison(..) => "GreaterEqualComparison",
+            ASTNode::NotEqualComparison(..) => "NotEqualComparison",
+            ASTNode::InComparison(..) => "InComparison",
+            ASTNode::IsComparison(..) => "IsComparison",
+            ASTNode::NotInComparison(..) => "NotInComparison",
+            ASTNode::IsNotComparison(..) => "IsNotComparison",
+            ASTNode::StarExpr(..) => "StarExpr",
+            ASTNode::Expr(..) => "Expr",
+            ASTNode::XorExpr(..) => "XorExpr",
+            ASTNode::AndExpr(..) => "AndExpr",
+            ASTNode::ShiftLeftExpr(..) => "ShiftLeftExpr",
+            ASTNode::ShiftRightExpr(..) => "ShiftRightExpr",
+            ASTNode::PlusArithExpr(..) => "PlusArithExpr",
+            ASTNode::MinusArithExpr(..) => "MinusArithExpr",
+            ASTNode::MulTerm(..) => "MulTerm",
+            ASTNode::DivTerm(..) => "DivTerm",
+            ASTNode::FloorDivTerm(..) => "FloorDivTerm",
+            ASTNode::ModuloTerm(..) => "ModuloTerm",
+            ASTNode::MatriceTerm(..) => "MatriceTerm",
+            ASTNode::UnaryPlus(..) => "UnaryPlus",
+            ASTNode::UnaryMinus(..) => "UnaryMinus",
+            ASTNode::UnaryInvert(..) => "UnaryInvert",
+            ASTNode::PowerExpr(..) => "PowerExpr",
+            ASTNode::AtomExpr(..) => "AtomExpr",
+            ASTNode::AtomName(..) => "AtomName",
+            ASTNode::AtomNumber(..) => "AtomNumber",
+            ASTNode::AtomString(..) => "AtomString",
+            ASTNode::AtomElipsis(..) => "AtomElipsis",
+            ASTNode::AtomNone(..) => "AtomNone",
+            ASTNode::AtomTrue(..) => "AtomTrue",
+            ASTNode::AtomFalse(..) => "AtomFalse",
+            ASTNode::AtomTuple(..) => "AtomTuple",
+            ASTNode::AtomList(..) => "AtomList",
+            ASTNode::AtomDictionary(..) => "AtomDictionary",
+            ASTNode::AtomSet(..) => "AtomSet",
+            ASTNode::TestListComp(..) => "TestListComp",
+            ASTNode::CallTrailer(..) => "CallTrailer",
+            ASTNode::IndexTrailer(..) => "IndexTrailer",
+            ASTNode::DotNameTrailer(..) => "DotNameTrailer",
+            ASTNode::SubscriptList(..) => "SubscriptList",
+            ASTNode::Subscript(..) => "Subscript",
+            ASTNode::ExprList(..) => "ExprList",
+            ASTNode::TestList(..) => "TestList",
+            ASTNode::DictionaryContainer(..) => "DictionaryContainer",
+            ASTNode::DictionaryEntry(..) => "DictionaryEntry",
+            ASTNode::SetContainer(..) => "SetContainer",
+            ASTNode::MulSet(..) => "MulSet",
+            ASTNode::PowerDictionary(..) => "PowerDictionary",
+            ASTNode::ClassDef(..) => "ClassDef",
+            ASTNode::ArgList(..) => "ArgList",
+            ASTNode::Argument(..) => "Argument",
+            ASTNode::SyncCompForComprehension(..) => "SyncCompForComprehension",
+            ASTNode::CompForComprehension(..) => "CompForComprehension",
+            ASTNode::CompIfComprehension(..) => "CompIfComprehension",
+            ASTNode::YieldExpr(..) => "YieldExpr",
+            ASTNode::YieldFromExpr(..) => "YieldFromExpr",
+            ASTNode::FuncBodySuite(..) => "FuncBodySuite",
+            ASTNode::FuncTypeInput(..) => "FuncTypeInput",
+            ASTNode::FuncType(..) => "FuncType",
+            ASTNode::TypeList(..) => "TypeList",
+            ASTNode::TestListStarExpr(..) => "TestListStarExpr",
+            ASTNode::SimpleStmtList(..) => "SimpleStmtList",
+            ASTNode::PlusAssignStmt(..) => "PlusAssignStmt",
+            ASTNode::MinusAssignStmt(..) => "MinusAssignStmt",
+            ASTNode::MulAssignStmt(..) => "MulAssignStmt",
+            ASTNode::DivAssignStmt(..) => "DivAssignStmt",
+            ASTNode::FloorDivAssignStmt(..) => "FloorDivAssignStmt",
+            ASTNode::ModuloAssignStmt(..) => "ModuloAssignStmt",
+            ASTNode::MatriceAssignStmt(..) => "MatriceAssignStmt",
+            ASTNode::PowerAssignStmt(..) => "PowerAssignStmt",
+            ASTNode::ShiftLeftAssignStmt(..) => "ShiftLeftAssignStmt",
+            ASTNode::ShiftRightAssignStmt(..) => "ShiftRightAssignStmt",
+            ASTNode::BitOrAssignStmt(..) => "BitOrAssignStmt",
+            ASTNode::BitXorAssignStmt(..) => "BitXorAssignStmt",
+            ASTNode::BitAndAssignStmt(..) => "BitAndAssignStmt",
+            ASTNode::AnnAssignStmt(..) => "AnnAssignStmt",
+            ASTNode::AssignmentStmt(..) => "AssignmentStmt",
+            ASTNode::DelStmt(..) => "DelStmt",
+            ASTNode::PassStmt(..) => "PassStmt",
+            ASTNode::BreakStmt(..) => "BreakStmt",
+            ASTNode::ContinueStmt(..) => "ContinueStmt",
+            ASTNode::ReturnStmt(..) => "ReturnStmt",
+            ASTNode::RaiseStmt(..) => "RaiseStmt",
+            ASTNode::ImportNameStmt(..) => "ImportNameStmt",
+            ASTNode::ImportFromStmt(..) => "ImportFromStmt",
+            ASTNode::ImportAsName(..) => "ImportAsName",
+            ASTNode::DottedAsNameStmt(..) => "DottedAsNameStmt",
+            ASTNode::ImportAsNamesStmt(..) => "ImportAsNamesStmt",
+            ASTNode::DottedAsNamesStmt(..) => "DottedAsNamesStmt",
+            ASTNode::DottedNameStmt(..) => "DottedNameStmt",
+            ASTNode::GlobalStmt(..) => "GlobalStmt",
+            ASTNode::NonLocalStmt(..) => "NonLocalStmt",
+            ASTNode::AssertStmt(..) => "AssertStmt",
+            ASTNode::AsyncStmt(..) => "AsyncStmt",
+            ASTNode::IfStmt(..) => "IfStmt",
+            ASTNode::ElifStmt(..) => "ElifStmt",
+            ASTNode::ElseStmt(..) => "ElseStmt",
+            ASTNode::WhileStmt(..) => "WhileStmt",
+            ASTNode::ForStmt(..) => "ForStmt",
+            ASTNode::TryStmt(..) => "TryStmt",
+            ASTNode::FinallyStmt(..) => "FinallyStmt",
+            ASTNode::WithStmt(..) => "WithStmt",
+            ASTNode::WithItem(..) => "WithItem",
+            ASTNode::ExceptClauseStmt(..) => "ExceptClauseStmt",
+            ASTNode::ExceptStmt(..) => "ExceptStmt",
+            ASTNode::SuiteStmt(..) => "SuiteStmt",
+            ASTNode::Decorator(..) => "Decorator",
+            ASTNode::Decorators(..) => "Decorators",
+            ASTNode::Decorated(..) => "Decorated",
+            ASTNode::FuncDef(..) => "FuncDef",
+            ASTNode::Parameter(..) => "Parameter",
+            ASTNode::TypedArgsList(..) => "TypedArgsList",
+            ASTNode::TFPAssign(..) => "TFPAssign",
+            ASTNode::TFPDef(..) => "TFPDef",
+            ASTNode::VarArgsList(..) => "VarArgsList",
+            ASTNode::VFPAssign(..) => "VFPAssign",
+            ASTNode::VFPDef(..) => "VFPDef",
+            ASTNode::SingleInput(..) => "SingleInput",
+            ASTNode::FileInput(..) => "FileInput",
+            ASTNode::EvalInput(..) => "EvalInput"
+        }
+    }
+
+    /// Returns child nodes and tokens in the order of the fields, absent optional children are left out.
+    pub fn children(&self) -> Vec<NodeChild<'_>> {
+        let mut res : Vec<NodeChild<'_>> = Vec::new();
+        match self {
+            ASTNode::NamedExpr(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::Test(_, _, a, b, c, d, e) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); },
+            ASTNode::Lambda(_, _, a, b, c, d) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); },
+            ASTNode::OrTest(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::AndTest(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::NotTest(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::LessComparison(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::LessEqualComparison(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::EqualComparison(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::GreaterComparison(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::GreaterEqualComparison(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::NotEqualComparison(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::InComparison(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::IsComparison(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::NotInComparison(_, _, a, b, c, d) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); },
+            ASTNode::IsNotComparison(_, _, a, b, c, d) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); },
+            ASTNode::StarExpr(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::Expr(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::XorExpr(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::AndExpr(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::ShiftLeftExpr(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::ShiftRightExpr(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::PlusArithExpr(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::MinusArithExpr(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::MulTerm(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::DivTerm(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::FloorDivTerm(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::ModuloTerm(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::MatriceTerm(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::UnaryPlus(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::UnaryMinus(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::UnaryInvert(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::PowerExpr(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::AtomExpr(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::AtomName(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::AtomNumber(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::AtomString(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::AtomElipsis(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::AtomNone(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::AtomTrue(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::AtomFalse(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::AtomTuple(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::AtomList(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::AtomDictionary(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::AtomSet(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::TestListComp(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::CallTrailer(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::IndexTrailer(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::DotNameTrailer(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::SubscriptList(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::Subscript(_, _, a, b, c, d, e) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); },
+            ASTNode::ExprList(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::TestList(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::DictionaryContainer(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::DictionaryEntry(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::SetContainer(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::MulSet(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::PowerDictionary(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::ClassDef(_, _, a, b, c, d, e, f, g) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); f.collect_children(&mut res); g.collect_children(&mut res); },
+            ASTNode::ArgList(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::Argument(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::SyncCompForComprehension(_, _, a, b, c, d, e) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); },
+            ASTNode::CompForComprehension(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::CompIfComprehension(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::YieldExpr(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::YieldFromExpr(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::FuncBodySuite(_, _, a, b, c, d, e, f) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); f.collect_children(&mut res); },
+            ASTNode::FuncTypeInput(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::FuncType(_, _, a, b, c, d, e) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); },
+            ASTNode::TypeList(_, _, a, b, c, d, e, f) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); f.collect_children(&mut res); },
+            ASTNode::TestListStarExpr(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::SimpleStmtList(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::PlusAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::MinusAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::MulAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::DivAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::FloorDivAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::ModuloAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::MatriceAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::PowerAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::ShiftLeftAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::ShiftRightAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::BitOrAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::BitXorAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::BitAndAssignStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::AnnAssignStmt(_, _, a, b, c, d) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); },
+            ASTNode::AssignmentStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::DelStmt(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::PassStmt(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::BreakStmt(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::ContinueStmt(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::ReturnStmt(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::RaiseStmt(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::ImportNameStmt(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::ImportFromStmt(_, _, a, b, c, d, e, f, g) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); f.collect_children(&mut res); g.collect_children(&mut res); },
+            ASTNode::ImportAsName(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::DottedAsNameStmt(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::ImportAsNamesStmt(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::DottedAsNamesStmt(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::DottedNameStmt(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::GlobalStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::NonLocalStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::AssertStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::AsyncStmt(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::IfStmt(_, _, a, b, c, d, e, f) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); f.collect_children(&mut res); },
+            ASTNode::ElifStmt(_, _, a, b, c, d) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); },
+            ASTNode::ElseStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::WhileStmt(_, _, a, b, c, d, e) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); },
+            ASTNode::ForStmt(_, _, a, b, c, d, e, f, g, h) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); f.collect_children(&mut res); g.collect_children(&mut res); h.collect_children(&mut res); },
+            ASTNode::TryStmt(_, _, a, b, c, d, e, f) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); f.collect_children(&mut res); },
+            ASTNode::FinallyStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::WithStmt(_, _, a, b, c, d, e, f, g) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); f.collect_children(&mut res); g.collect_children(&mut res); },
+            ASTNode::WithItem(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::ExceptClauseStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::ExceptStmt(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::SuiteStmt(_, _, a, b, c, d) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); },
+            ASTNode::Decorator(_, _, a, b, c, d, e, f) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); f.collect_children(&mut res); },
+            ASTNode::Decorators(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::Decorated(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::FuncDef(_, _, a, b, c, d, e, f, g) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); f.collect_children(&mut res); g.collect_children(&mut res); },
+            ASTNode::Parameter(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::TypedArgsList(_, _, a, b, c, d, e) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); },
+            ASTNode::TFPAssign(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::TFPDef(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::VarArgsList(_, _, a, b, c, d, e) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); d.collect_children(&mut res); e.collect_children(&mut res); },
+            ASTNode::VFPAssign(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::VFPDef(_, _, a) => { a.collect_children(&mut res); },
+            ASTNode::SingleInput(_, _, a, b) => { a.collect_children(&mut res); b.collect_children(&mut res); },
+            ASTNode::FileInput(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); },
+            ASTNode::EvalInput(_, _, a, b, c) => { a.collect_children(&mut res); b.collect_children(&mut res); c.collect_children(&mut res); }
+        }
+        res
+    }
+
+    /// Compares two trees by kind of nodes, kind of tokens and text of names, numbers, strings and
+    /// type comments, while positions and trivia are ignored.
+    pub fn structural_eq(&self, other: &ASTNode) -> bool {
+        if std::mem::discriminant(self) != std::mem::discriminant(other) {
+            return false
+        }
+        let ( left, right ) = ( self.children(), other.children() );
+        left.len() == right.len() && left.iter().zip(right.iter()).all( |( a, b )| a.structural_eq(b) )
+    }
+
+    /// Hash consistent with 'structural_eq', positions and trivia are ignored.
+    pub fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        for child in self.children() {
+            match child {
+                NodeChild::Node( n ) => n.structural_hash(state),
+                NodeChild::Token( t ) => t.structural_hash(state)
+            }
+        }
+    }
+}
+
+
+/// Child of a node, either another node or a token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeChild<'a> {
+    Node(&'a ASTNode),
+    Token(&'a Token)
+}
+
+impl NodeChild<'_> {
+    pub fn structural_eq(&self, other: &NodeChild<'_>) -> bool {
+        match ( self, other ) {
+            ( NodeChild::Node( a ), NodeChild::Node( b ) ) => a.structural_eq(b),
+            ( NodeChild::Token( a ), NodeChild::Token( b ) ) => a.structural_eq(b),
+            _ => false
+        }
+    }
+}
+
+
+/// Wrapper comparing and hashing a tree structurally, for use as key in sets and maps when
+/// deduplicating trees parsed from different sources.
+#[derive(Clone, Copy, Debug)]
+pub struct Structural<'a>(pub &'a ASTNode);
+
+impl PartialEq for Structural<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.structural_eq(other.0)
+    }
+}
+
+impl Eq for Structural<'_> {}
+
+impl Hash for Structural<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.structural_hash(state)
+    }
+}
+
+
+trait CollectChildren {
+    fn collect_children<'a>(&'a self, res: &mut Vec<NodeChild<'a>>);
+}
+
+impl CollectChildren for Box<ASTNode> {
+    fn collect_children<'a>(&'a self, res: &mut Vec<NodeChild<'a>>) {
+        res.push( NodeChild::Node(self) )
+    }
+}
+
+impl CollectChildren for Box<Token> {
+    fn collect_children<'a>(&'a self, res: &mut Vec<NodeChild<'a>>) {
+        res.push( NodeChild::Token(self) )
+    }
+}
+
+impl<T: CollectChildren> CollectChildren for Option<T> {
+    fn collect_children<'a>(&'a self, res: &mut Vec<NodeChild<'a>>) {
+        if let Some( x ) = self {
+            x.collect_children(res)
+        }
+    }
+}
+
+impl<T: CollectChildren> CollectChildren for Box<Vec<T>> {
+    fn collect_children<'a>(&'a self, res: &mut Vec<NodeChild<'a>>) {
+        for x in self.iter() {
+            x.collect_children(res)
+        }
+    }
+}
+
+impl<A: CollectChildren, B: CollectChildren> CollectChildren for ( A, B ) {
+    fn collect_children<'a>(&'a self, res: &mut Vec<NodeChild<'a>>) {
+        self.0.collect_children(res);
+        self.1.collect_children(res)
+    }
+}
+
+impl<A: CollectChildren, B: CollectChildren> CollectChildren for Box<( A, B )> {
+    fn collect_children<'a>(&'a self, res: &mut Vec<NodeChild<'a>>) {
+        self.0.collect_children(res);
+        self.1.collect_children(res)
+    }
+}
+
+
+// UnitTests for tree equality /////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::{ASTNode, Token};
+    use crate::parser::nodes::{NodeChild, Structural};
+    use crate::parser::blocks::Blocks;
+    use crate::parser::tokenizer::{PythonCoreTokenizer, Tokenizer};
+    use crate::parser::parser::{Parser, PythonCoreParser};
+
+
+    fn parse(source: &str) -> Box<ASTNode> {
+        let lexer = Box::new( PythonCoreTokenizer::new(source.to_string()) );
+        let mut parser = PythonCoreParser::new(lexer);
+        parser.parse_blocks_file_input().unwrap()
+    }
+
+    #[test]
+    fn nodes_derived_equality_includes_positions() {
+        let tree = parse("a + b\n");
+        assert_eq!(tree, tree.clone());
+        assert_ne!(parse("a + b\n"), parse("a+b\n"));
+    }
+
+    #[test]
+    fn nodes_structural_equality_ignores_positions_and_trivia() {
+        assert!(parse("a + b\n").structural_eq(&parse("a+b  # comment\n")));
+        assert!(parse("x = [1, 2]\n").structural_eq(&parse("\nx=[ 1,2 ]\n")));
+    }
+
+    #[test]
+    fn nodes_structural_equality_compares_kinds_and_text() {
+        assert!(!parse("a + b\n").structural_eq(&parse("a - b\n")));
+        assert!(!parse("a + b\n").structural_eq(&parse("a + c\n")));
+        assert!(!parse("'a'\n").structural_eq(&parse("b'a'\n")));
+        assert!(!parse("x[a:]\n").structural_eq(&parse("x[:a]\n")));
+    }
+
+    #[test]
+    fn nodes_structural_hash_deduplicates() {
+        let trees = [ parse("f(x)\n"), parse("f( x )\n"), parse("f(y)\n") ];
+        let set : HashSet<Structural> = trees.iter().map( |t| Structural(t) ).collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn nodes_children_in_field_order() {
+        match &*parse("a.b\n") {
+            ASTNode::FileInput(_, _, nodes, _, _) => {
+                let children = nodes[0].children();
+                assert_eq!(children.len(), 2);
+                match children[1] {
+                    NodeChild::Token( t ) => assert!(matches!(t, Token::Newline(..))),
+                    _ => panic!("expected newline token")
+                }
+            },
+            _ => panic!("expected file input")
+        }
+    }
+
+    #[test]
+    fn nodes_debug_output() {
+        assert_eq!(format!("{:?}", parse("x\n")),
+                   "FileInput(0, 2, [SimpleStmtList(0, 2, [AtomName(0, 1, AtomName(0, 1, None, \"x\"))], [], Newline(1, 2, None, '\\n', ' '))], [], EOF(2, None))");
+    }
+}
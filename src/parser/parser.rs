@@ -1,3 +1,4 @@
+use std::cell::{ Cell, RefCell };
 use crate::parser::parser::ET::ExceptNone;
 use crate::parser::tokens::{ Token };
 use crate::parser::tokenizer::{PythonCoreTokenizer, Tokenizer};
@@ -7,7 +8,10 @@ pub enum ET { ExceptNone, ExceptMul, Except }
 pub struct PythonCoreParser {
     pub lexer: Box<PythonCoreTokenizer>,
     pub(crate) symbol: Result<Box<Token>, String>,
-    pub except_status: ET
+    pub except_status: ET,
+    /// Kinds of tokens tested at the furthest position, see 'expecting'.
+    pub(crate) expected: RefCell<Vec<&'static str>>,
+    pub(crate) furthest: Cell<u32>
 }
 
 pub trait Parser {
@@ -21,7 +25,9 @@ impl Parser for PythonCoreParser {
         PythonCoreParser {
             lexer,
             symbol: Err("Token not advanced yet! ".to_string()),
-            except_status: ExceptNone
+            except_status: ExceptNone,
+            expected: RefCell::new(Vec::new()),
+            furthest: Cell::new(0)
         }
     }
 
@@ -29,4 +35,53 @@ impl Parser for PythonCoreParser {
         self.symbol = self.lexer.get_symbol()
     }
 
-}
\ No newline at end of file
+}
+
+impl PythonCoreParser {
+    /// Current symbol, recording that the parser tests it for the token kinds given with 'kinds!' by
+    /// the names of their variants, or by the text of soft keywords. Kinds tested at the furthest position are
+    /// kept, which at a syntax error are the tokens the parser would have accepted instead. Kinds
+    /// tested only to report an error of their own are left out.
+    pub(crate) fn expecting(&self, kinds: &[&'static str]) -> &Result<Box<Token>, String> {
+        let position = match &self.symbol {
+            Ok( t ) => t.get_span().map_or( self.lexer.get_position(), |( s, _ )| s ),
+            Err( _ ) => self.lexer.get_position()
+        };
+        if position > self.furthest.get() {
+            self.furthest.set(position);
+            self.expected.borrow_mut().clear()
+        }
+        if position == self.furthest.get() {
+            self.expected.borrow_mut().extend_from_slice(kinds)
+        }
+        &self.symbol
+    }
+
+    /// Whether the name 'match' at the current symbol starts a match statement rather than an
+    /// expression, which is the case when its logical line ends with a colon that does not follow it
+    /// directly. Reads ahead with a copy of the lexer.
+    pub(crate) fn is_match_statement(&self) -> bool {
+        let mut lexer = self.lexer.clone();
+        let mut last = match lexer.get_symbol() {
+            Ok( t ) if !matches!(*t, Token::PyColon(..) | Token::Newline(..) | Token::EOF(..)) => t,
+            _ => return false
+        };
+        loop {
+            match lexer.get_symbol() {
+                Ok( t ) if matches!(*t, Token::Newline(..) | Token::EOF(..)) => return matches!(*last, Token::PyColon(..)),
+                Ok( t ) => last = t,
+                Err( _ ) => return false
+            }
+        }
+    }
+}
+
+/// Token kinds for 'expecting' by the names of their variants, each checked to be a variant of
+/// Token, followed by the text of soft keywords after a semicolon.
+macro_rules! kinds {
+    ( $( $kind:ident ),* $( ; $( $soft:literal ),* )? ) => {
+        &[ $( { let _ = |t: &Token| matches!(t, Token::$kind(..)); stringify!($kind) }, )* $( $( $soft, )* )? ]
+    }
+}
+
+pub(crate) use kinds;
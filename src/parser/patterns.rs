@@ -1,5 +1,5 @@
 
-use crate::{ ASTNode, PythonCoreParser };
+use crate::{ ASTNode, PythonCoreParser, Tokenizer };
 
 
 pub trait Patterns {
@@ -9,7 +9,7 @@ pub trait Patterns {
 
 impl Patterns for PythonCoreParser {
     fn parse_patterns_match(&self) -> Result<Box<ASTNode>, String> {
-        todo!()
+        Err(format!("SyntaxError at {}: 'match' statement is not supported yet!", self.lexer.get_position()))
     }
 }
 
@@ -0,0 +1,218 @@
+use crate::{ ASTNode, Token };
+use crate::parser::nodes::NodeChild;
+use crate::parser::trivias::Trivia;
+
+
+/// Compact and stable text dump of the concrete tree as S-expressions. Nodes are written as
+/// '(Kind start..end' followed by their children in source order, one per line, and tokens as
+/// '(Kind start..end "text")'. Trivia in front of tokens is added when requested.
+pub trait SExpression {
+    fn to_sexpr(&self, with_trivia: bool) -> String;
+}
+
+
+impl SExpression for ASTNode {
+    fn to_sexpr(&self, with_trivia: bool) -> String {
+        let mut out = String::new();
+        write_node(self, 0, with_trivia, &mut out);
+        out
+    }
+}
+
+impl SExpression for Token {
+    fn to_sexpr(&self, with_trivia: bool) -> String {
+        let mut out = String::new();
+        write_token(self, 0, with_trivia, &mut out);
+        out
+    }
+}
+
+
+fn write_node(node: &ASTNode, depth: usize, with_trivia: bool, out: &mut String) {
+    let ( start, end ) = node.get_span();
+    out.push_str( &"  ".repeat(depth) );
+    out.push_str( &format!("({} {}..{}", node.kind_name(), start, end) );
+    for child in ordered_children(node) {
+        out.push('\n');
+        match child {
+            NodeChild::Node( n ) => write_node(n, depth + 1, with_trivia, out),
+            NodeChild::Token( t ) => write_token(t, depth + 1, with_trivia, out)
+        }
+    }
+    out.push(')')
+}
+
+fn write_token(token: &Token, depth: usize, with_trivia: bool, out: &mut String) {
+    out.push_str( &"  ".repeat(depth) );
+    out.push('(');
+    out.push_str( token.kind_name() );
+    if let Some( ( start, end ) ) = token.get_span() {
+        out.push_str( &format!(" {}..{}", start, end) )
+    }
+    if let Some( text ) = token.text() {
+        out.push_str( &format!(" {:?}", text) )
+    }
+    if with_trivia {
+        if let Some( trivia ) = token.get_trivia() {
+            let mut sorted : Vec<&Trivia> = trivia.iter().map( |t| &**t ).collect();
+            sorted.sort_by_key( |t| trivia_span(t).0 );
+            for t in sorted {
+                out.push(' ');
+                out.push_str( &trivia_sexpr(t) )
+            }
+        }
+    }
+    out.push(')')
+}
+
+/// Children sorted by start position, as several lists are kept in reverse order by the parser.
+/// Indent follows the child in front of it and Dedent is placed last, since neither has a position.
+pub fn ordered_children(node: &ASTNode) -> Vec<NodeChild<'_>> {
+    let ( start, end ) = node.get_span();
+    let mut previous = start;
+    let mut keyed : Vec<( u32, usize, NodeChild<'_> )> = Vec::new();
+    for ( index, child ) in node.children().into_iter().enumerate() {
+        let key = match child {
+            NodeChild::Node( n ) => n.get_span().0,
+            NodeChild::Token( Token::Dedent(..) ) => end,
+            NodeChild::Token( t ) => t.get_span().map( |s| s.0 ).unwrap_or(previous)
+        };
+        previous = key;
+        keyed.push( ( key, index, child ) )
+    }
+    keyed.sort_by_key( |k| ( k.0, k.1 ) );
+    keyed.into_iter().map( |k| k.2 ).collect()
+}
+
+fn trivia_span(trivia: &Trivia) -> ( u32, u32 ) {
+    match trivia {
+        Trivia::WhiteSpace(start, end, ..) |
+        Trivia::Newline(start, end, ..) |
+        Trivia::LineContinuation(start, end, ..) |
+        Trivia::Comment(start, end, ..) => ( *start, *end )
+    }
+}
+
+fn trivia_sexpr(trivia: &Trivia) -> String {
+    match trivia {
+        Trivia::WhiteSpace(start, end, ..) => format!("(WhiteSpace {}..{})", start, end),
+        Trivia::Newline(start, end, ..) => format!("(Newline {}..{})", start, end),
+        Trivia::LineContinuation(start, end, ..) => format!("(LineContinuation {}..{})", start, end),
+        Trivia::Comment(start, end, txt) => format!("(Comment {}..{} {:?})", start, end, txt)
+    }
+}
+
+
+// UnitTests for S-expression dump and snapshot fixtures ///////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use crate::parser::blocks::Blocks;
+    use crate::parser::sexpr::SExpression;
+    use crate::parser::tokenizer::{PythonCoreTokenizer, Tokenizer};
+    use crate::parser::parser::{Parser, PythonCoreParser};
+
+
+    fn dump_source(source: &str, with_trivia: bool) -> String {
+        let lexer = Box::new( PythonCoreTokenizer::new(source.to_string()) );
+        let mut parser = PythonCoreParser::new(lexer);
+        match parser.parse_blocks_file_input() {
+            Ok( tree ) => format!("{}\n", tree.to_sexpr(with_trivia)),
+            Err( e ) => format!("(Error {:?})\n", e)
+        }
+    }
+
+    fn collect_fixtures(dir: &Path, res: &mut Vec<PathBuf>) {
+        let mut entries : Vec<PathBuf> = std::fs::read_dir(dir).unwrap().map( |e| e.unwrap().path() ).collect();
+        entries.sort();
+        for path in entries {
+            if path.is_dir() {
+                collect_fixtures(&path, res)
+            }
+            else if path.extension().map( |e| e == "py" ).unwrap_or(false) {
+                res.push(path)
+            }
+        }
+    }
+
+    /// Compares dump of every '.py' file below directory with the '.tree' file next to it. With
+    /// 'update' set, snapshots that are missing or different are written instead.
+    fn check_snapshots(dir: &Path, update: bool) -> Vec<String> {
+        let mut fixtures = Vec::new();
+        collect_fixtures(dir, &mut fixtures);
+        let mut failures = Vec::new();
+        for path in fixtures {
+            let source = std::fs::read_to_string(&path).unwrap();
+            let actual = dump_source(&source, false);
+            let snapshot = path.with_extension("tree");
+            let expected = std::fs::read_to_string(&snapshot).ok();
+            if expected.as_deref() == Some( actual.as_str() ) {
+                continue
+            }
+            if update {
+                std::fs::write(&snapshot, &actual).unwrap();
+                continue
+            }
+            match expected {
+                None => failures.push( format!("{}: snapshot is missing", snapshot.display()) ),
+                Some( text ) => {
+                    let line = text.lines().zip(actual.lines()).position( |( a, b )| a != b )
+                        .unwrap_or( text.lines().count().min( actual.lines().count() ) );
+                    failures.push( format!("{}:{}: expected {:?}, found {:?}", snapshot.display(), line + 1,
+                                           text.lines().nth(line).unwrap_or(""), actual.lines().nth(line).unwrap_or("")) )
+                }
+            }
+        }
+        failures
+    }
+
+    /// Run with UPDATE_SNAPSHOTS=1 set to rewrite the '.tree' files after a deliberate change.
+    #[test]
+    fn sexpr_snapshot_fixtures() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+        let update = std::env::var("UPDATE_SNAPSHOTS").is_ok();
+        let failures = check_snapshots(&dir, update);
+        assert!(failures.is_empty(), "Snapshots differ:\n{}", failures.join("\n"))
+    }
+
+    #[test]
+    fn sexpr_simple_expression() {
+        assert_eq!(dump_source("a + 1\n", false), concat!(
+            "(FileInput 0..6\n",
+            "  (SimpleStmtList 0..6\n",
+            "    (PlusArithExpr 0..5\n",
+            "      (AtomName 0..2\n",
+            "        (AtomName 0..1 \"a\"))\n",
+            "      (PyPlus 2..3 \"+\")\n",
+            "      (AtomNumber 4..5\n",
+            "        (AtomNumber 4..5 \"1\")))\n",
+            "    (Newline 5..6))\n",
+            "  (EOF 6..6))\n"));
+    }
+
+    #[test]
+    fn sexpr_children_in_source_order() {
+        let dump = dump_source("f(a, b)\n", false);
+        assert!(dump.find("\"a\"").unwrap() < dump.find("\"b\"").unwrap());
+    }
+
+    #[test]
+    fn sexpr_with_trivia() {
+        assert_eq!(dump_source("x  # c\n", true), concat!(
+            "(FileInput 0..7\n",
+            "  (SimpleStmtList 0..7\n",
+            "    (AtomName 0..6\n",
+            "      (AtomName 0..1 \"x\"))\n",
+            "    (Newline 6..7 (WhiteSpace 1..3) (Comment 3..6 \"# c\")))\n",
+            "  (EOF 7..7))\n"));
+    }
+
+    #[test]
+    fn sexpr_indentation_tokens() {
+        let dump = dump_source("if a:\n    pass\n", false);
+        let indent = dump.find("(Indent)").unwrap();
+        let dedent = dump.find("(Dedent)").unwrap();
+        assert!(indent < dump.find("PyPass").unwrap() && dump.find("PyPass").unwrap() < dedent);
+    }
+}
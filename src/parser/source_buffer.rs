@@ -1,6 +1,10 @@
 
+use std::rc::Rc;
+
+/// Characters of source shared between clones, which only differ in their position.
+#[derive(Clone)]
 pub struct SourceBuffer {
-    source_buffer: Box<Vec<char>>,
+    source_buffer: Rc<Vec<char>>,
     index: u32
 }
 
@@ -18,7 +22,7 @@ pub trait SourceBufferFunctionality {
 impl SourceBufferFunctionality for SourceBuffer {
     fn new(buffer: String ) -> SourceBuffer {
         SourceBuffer {
-            source_buffer: Box::new(buffer.chars().collect()),
+            source_buffer: Rc::new(buffer.chars().collect()),
             index: 0u32
         }
     }
@@ -1,5 +1,6 @@
 
 use crate::{ Parser, ASTNode, PythonCoreParser, Tokenizer, Token, Expressions };
+use crate::parser::parser::kinds;
 use crate::parser::patterns::Patterns;
 use crate::parser::blocks::Blocks;
 use crate::parser::parser::ET;
@@ -50,7 +51,7 @@ pub trait Statements {
 impl Statements for PythonCoreParser {
     fn parse_statements_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyIf, PyFor, PyWhile, PyWith, PyTry, PyAsync, PyMatrice, PyDef, PyClass, AtomName; "match" ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyIf(..) |
@@ -66,7 +67,7 @@ impl Statements for PythonCoreParser {
                     },
                     Token::AtomName(_, _, _, txt) => {
                         match &*txt.as_str() {
-                            "match" => {
+                            "match" if self.is_match_statement() => {
                                 self.parse_patterns_match()
                             },
                             _ => {
@@ -89,13 +90,13 @@ impl Statements for PythonCoreParser {
         let mut separators_list: Box<Vec<Box<Token>>> = Box::new(Vec::new());
         nodes_list.push(self.parse_statements_small_stmt()?);
         while
-            match self.symbol.clone() {
+            match self.expecting(kinds![ PySemiColon ]).clone() {
                 Ok(s) => {
                     match &*s {
                         Token::PySemiColon(..) => {
                             separators_list.push(s);
                             let _ = self.advance();
-                            match self.symbol.clone() {
+                            match self.expecting(kinds![ Newline, EOF ]).clone() {
                                 Ok(s2) => {
                                     match &*s2 {
                                         Token::Newline(..) |
@@ -118,7 +119,14 @@ impl Statements for PythonCoreParser {
                 },
                 _ => return Err(format!("SyntaxError at {}: Expecting symbol in statement list!", start_pos))
             } {};
-        match self.symbol.clone() {
+        /* Type comment like '# type: ignore' after statement list is kept together with separators */
+        if let Ok(s4) = self.expecting(kinds![ TypeComment ]).clone() {
+            if let Token::TypeComment( .. ) = &*s4 {
+                separators_list.push(s4);
+                let _ = self.advance();
+            }
+        }
+        match self.expecting(kinds![ Newline ]).clone() {
             Ok(s3) => {
                 match &*s3 {
                     Token::Newline( .. ) => {
@@ -136,7 +144,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_small_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyDel, PyPass, PyBreak, PyContinue, PyReturn, PyRaise, PyYield, PyImport, PyFrom, PyGlobal, PyNonLocal, PyAssert ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyDel(..) => {
@@ -177,7 +185,7 @@ impl Statements for PythonCoreParser {
     fn parse_statements_expr_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let left_node = self.parse_expressions_testlist_star_expr()?;
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyColon, PyAssign, PyPlusAssign, PyMinusAssign, PyMulAssign, PyPowerAssign, PyDivAssign, PyFloorDivAssign, PyModuloAssign, PyMatriceAssign, PyBitAndAssign, PyBitOrAssign, PyBitXorAssign, PyShiftLeftAssign, PyShiftRightAssign ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyColon(..) => {
@@ -186,13 +194,13 @@ impl Statements for PythonCoreParser {
                     Token::PyAssign(..) => {
                         let mut nodes_list : Box<Vec<Box< ( Box<Token>, Box<ASTNode> ) >>> = Box::new(Vec::new());
                         while
-                            match self.symbol.clone() {
+                            match self.expecting(kinds![ PyAssign ]).clone() {
                                 Ok(s2) => {
                                     match &*s2 {
                                         Token::PyAssign( .. ) => {
                                             let ass_symbol = s2;
                                             let _ = self.advance();
-                                            match self.symbol.clone() {
+                                            match self.expecting(kinds![ PyYield ]).clone() {
                                                 Ok(s3) => {
                                                     match &*s3 {
                                                         Token::PyYield( .. ) => {
@@ -214,7 +222,7 @@ impl Statements for PythonCoreParser {
                                 },
                                 _ => return Err(format!("SyntaxError at {}: Expecting symbol in expression statement!", start_pos))
                             } {};
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ TypeComment ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::TypeComment(..) => {
@@ -233,7 +241,7 @@ impl Statements for PythonCoreParser {
                     Token::PyPlusAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -252,7 +260,7 @@ impl Statements for PythonCoreParser {
                     Token::PyMinusAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -271,7 +279,7 @@ impl Statements for PythonCoreParser {
                     Token::PyMulAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -290,7 +298,7 @@ impl Statements for PythonCoreParser {
                     Token::PyPowerAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -309,7 +317,7 @@ impl Statements for PythonCoreParser {
                     Token::PyDivAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -328,7 +336,7 @@ impl Statements for PythonCoreParser {
                     Token::PyFloorDivAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -347,7 +355,7 @@ impl Statements for PythonCoreParser {
                     Token::PyModuloAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -366,7 +374,7 @@ impl Statements for PythonCoreParser {
                     Token::PyMatriceAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -385,7 +393,7 @@ impl Statements for PythonCoreParser {
                     Token::PyBitAndAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -404,7 +412,7 @@ impl Statements for PythonCoreParser {
                     Token::PyBitOrAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -423,7 +431,7 @@ impl Statements for PythonCoreParser {
                     Token::PyBitXorAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -442,7 +450,7 @@ impl Statements for PythonCoreParser {
                     Token::PyShiftLeftAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -461,7 +469,7 @@ impl Statements for PythonCoreParser {
                     Token::PyShiftRightAssign( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyYield ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyYield( .. ) => {
@@ -487,20 +495,20 @@ impl Statements for PythonCoreParser {
     }
 
     fn parse_statements_ann_assign(&mut self, start_pos: u32, left_node: Box<ASTNode>) -> Result<Box<ASTNode>, String> {
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyColon ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyColon( .. ) => {
                         let symbol = s;
                         let _ = self.advance();
                         let right_node = self.parse_expressions_test()?;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyAssign ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyAssign( .. ) => {
                                         let symbol2 = s2;
                                         let _ = self.advance();
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyYield ]).clone() {
                                             Ok(s3) => {
                                                 match &*s3 {
                                                     Token::PyYield( .. ) => {
@@ -533,7 +541,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_del_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyDel ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyDel(..) => {
@@ -551,7 +559,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_pass_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyPass ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyPass(..) => {
@@ -568,7 +576,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_flow_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyBreak, PyContinue, PyReturn, PyRaise, PyYield ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyBreak( .. ) => {
@@ -595,7 +603,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_break_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyBreak ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyBreak(..) => {
@@ -612,7 +620,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_continue_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyContinue ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyContinue(..) => {
@@ -629,13 +637,13 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_return_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyReturn ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyReturn(..) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PySemiColon, Newline, EOF ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PySemiColon( .. ) |
@@ -665,13 +673,13 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_raise_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyRaise ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyRaise(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PySemiColon, Newline, EOF ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PySemiColon( .. ) |
@@ -681,7 +689,7 @@ impl Statements for PythonCoreParser {
                                     },
                                     _ => {
                                         let left_node = self.parse_expressions_test()?;
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyFrom ]).clone() {
                                             Ok(s2) => {
                                                 match &*s2 {
                                                     Token::PyFrom(..) => {
@@ -712,11 +720,11 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_import_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyImport, PyFrom ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyImport(..) => {
-                        self.parse_statements_import_stmt()
+                        self.parse_statements_import_name()
                     },
                     Token::PyFrom(..) => {
                         self.parse_statements_import_from()
@@ -730,7 +738,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_import_name(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyImport ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyImport(..) => {
@@ -748,7 +756,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_import_from(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyFrom ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyFrom(..) => {
@@ -756,7 +764,7 @@ impl Statements for PythonCoreParser {
                         let _ = self.advance();
                         let mut nodes_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
                         while
-                            match self.symbol.clone() {
+                            match self.expecting(kinds![ PyDot, PyElipsis ]).clone() {
                                 Ok(s2) => {
                                     match &*s2 {
                                         Token::PyDot(..) |
@@ -774,7 +782,7 @@ impl Statements for PythonCoreParser {
                             } { };
                         nodes_list.reverse();
                         let mut left_node : Option<Box<ASTNode>> = None;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyImport ]).clone() {
                             Ok(s3) => {
                                 match &*s3 {
                                     Token::PyImport(..) => {
@@ -790,13 +798,13 @@ impl Statements for PythonCoreParser {
                             },
                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in 'from' statement!", start_pos))
                         }
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyImport ]).clone() {
                             Ok(s4) => {
                                 match &*s4 {
                                     Token::PyImport(..) => {
                                         let symbol2 = s4;
                                         let _ = self.advance();
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyMul, PyLeftParen ]).clone() {
                                             Ok(s5) => {
                                                 match &*s5 {
                                                     Token::PyMul(..) => {
@@ -808,7 +816,7 @@ impl Statements for PythonCoreParser {
                                                         let symbol3 = s5;
                                                         let _ = self.advance();
                                                         let right_node = Some( self.parse_statements_import_as_names()? );
-                                                        match self.symbol.clone() {
+                                                        match self.expecting(kinds![ PyRightParen ]).clone() {
                                                             Ok(s6) => {
                                                                 match &*s6 {
                                                                     Token::PyRightParen(..) => {
@@ -846,19 +854,19 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_import_as_name(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ AtomName ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::AtomName(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyAs ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyAs(..) => {
                                         let symbol2 = s2;
                                         let _ = self.advance();
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ AtomName ]).clone() {
                                             Ok(s3) => {
                                                 match &*s3 {
                                                     Token::AtomName(..) => {
@@ -889,18 +897,18 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_dotted_as_name(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ AtomName ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::AtomName(..) => {
                         let left_node = self.parse_statements_dotted_name()?;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyAs ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyAs(..) => {
                                         let symbol2 = s2;
                                         let _ = self.advance();
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ AtomName ]).clone() {
                                             Ok(s3) => {
                                                 match &*s3 {
                                                     Token::AtomName(..) => {
@@ -935,14 +943,19 @@ impl Statements for PythonCoreParser {
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
         nodes_list.push( self.parse_statements_import_as_name()? );
         while
-            match self.symbol.clone() {
+            match self.expecting(kinds![ PyComa ]).clone() {
                 Ok(s) => {
                     match &*s {
                         Token::PyComa(..) => {
                             separators_list.push( s );
                             let _ = self.advance();
-                            nodes_list.push( self.parse_statements_import_as_name()? );
-                            true
+                            match self.expecting(kinds![ PyRightParen ]) {
+                                Ok(s2) if matches!(&**s2, Token::PyRightParen(..)) => false,
+                                _ => {
+                                    nodes_list.push( self.parse_statements_import_as_name()? );
+                                    true
+                                }
+                            }
                         },
                         _ => false
                     }
@@ -960,7 +973,7 @@ impl Statements for PythonCoreParser {
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
         nodes_list.push( self.parse_statements_dotted_as_name()? );
         while
-            match self.symbol.clone() {
+            match self.expecting(kinds![ PyComa ]).clone() {
                 Ok(s) => {
                     match &*s {
                         Token::PyComa(..) => {
@@ -983,20 +996,20 @@ impl Statements for PythonCoreParser {
         let start_pos = self.lexer.get_position();
         let mut nodes_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
-        match self.symbol.clone() {
+        match self.expecting(kinds![ AtomName ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::AtomName(..) => {
                         nodes_list.push( s );
                         let _ = self.advance();
                         while
-                            match self.symbol.clone() {
+                            match self.expecting(kinds![ PyDot ]).clone() {
                                 Ok(s2) => {
                                     match &*s2 {
                                         Token::PyDot(..) => {
                                             separators_list.push( s2 );
                                             let _ = self.advance();
-                                            match self.symbol.clone() {
+                                            match self.expecting(kinds![ AtomName ]).clone() {
                                                 Ok(s3) => {
                                                     match &*s3 {
                                                         Token::AtomName(..) => {
@@ -1028,7 +1041,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_global_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyGlobal ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyGlobal(..) => {
@@ -1036,7 +1049,7 @@ impl Statements for PythonCoreParser {
                         let _ = self.advance();
                         let mut nodes_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
                         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ AtomName ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::AtomName(..) => {
@@ -1049,13 +1062,13 @@ impl Statements for PythonCoreParser {
                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in 'global' statement!", start_pos))
                         }
                         while
-                            match self.symbol.clone() {
+                            match self.expecting(kinds![ PyComa ]).clone() {
                                 Ok(s3) => {
                                     match &*s3 {
                                         Token::PyComa(..) => {
                                             separators_list.push( s3 );
                                             let _ = self.advance();
-                                            match self.symbol.clone() {
+                                            match self.expecting(kinds![ AtomName ]).clone() {
                                                 Ok(s4) => {
                                                     match &*s4 {
                                                         Token::AtomName(..) => {
@@ -1087,7 +1100,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_nonlocal_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyNonLocal ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyNonLocal(..) => {
@@ -1095,7 +1108,7 @@ impl Statements for PythonCoreParser {
                         let _ = self.advance();
                         let mut nodes_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
                         let mut separators_list : Box<Vec<Box<Token>>> = Box::new(Vec::new());
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ AtomName ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::AtomName(..) => {
@@ -1108,13 +1121,13 @@ impl Statements for PythonCoreParser {
                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in 'nonlocal' statement!", start_pos))
                         }
                         while
-                            match self.symbol.clone() {
+                            match self.expecting(kinds![ PyComa ]).clone() {
                                 Ok(s3) => {
                                     match &*s3 {
                                         Token::PyComa(..) => {
                                             separators_list.push( s3 );
                                             let _ = self.advance();
-                                            match self.symbol.clone() {
+                                            match self.expecting(kinds![ AtomName ]).clone() {
                                                 Ok(s4) => {
                                                     match &*s4 {
                                                         Token::AtomName(..) => {
@@ -1146,17 +1159,17 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_assert_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyAssert ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyAssert(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
                         let left_node = self.parse_expressions_test()?;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyComa ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
-                                    Token::PyFrom(..) => {
+                                    Token::PyComa(..) => {
                                         let symbol2 = s2;
                                         let _ = self.advance();
                                         let right_node = self.parse_expressions_test()?;
@@ -1179,7 +1192,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_compound_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyIf, PyWhile, PyFor, PyTry, PyWith, PyDef, PyClass, PyMatrice, PyAsync ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyIf( .. ) => {
@@ -1218,17 +1231,17 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_async_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyAsync ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyAsync(..) => {
                         let symbol = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyDef, PyWith, PyFor ]).clone() {
                             Ok(s) => {
                                 match &*s {
                                     Token::PyDef(..) => {
-                                        let right_node = self.parse_blocks_class_def()?;
+                                        let right_node = self.parse_blocks_func_def()?;
                                         Ok(Box::new( ASTNode::AsyncStmt(start_pos, self.lexer.get_position(), symbol, right_node) ))
                                     },
                                     Token::PyWith(..) => {
@@ -1254,14 +1267,14 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_if_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyIf ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyIf(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
                         let left_node = self.parse_expressions_test()?;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyColon ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyColon(..) => {
@@ -1270,7 +1283,7 @@ impl Statements for PythonCoreParser {
                                         let right_node = self.parse_statements_suite()?;
                                         let mut nodes_list : Box<Vec<Box<ASTNode>>> = Box::new(Vec::new());
                                         while
-                                            match self.symbol.clone() {
+                                            match self.expecting(kinds![ PyElif ]).clone() {
                                                 Ok(s3) => {
                                                     match &*s3 {
                                                         Token::PyElif(..) => {
@@ -1283,7 +1296,7 @@ impl Statements for PythonCoreParser {
                                                 _ => return Err(format!("SyntaxError at {}: Expecting symbol in if statement!", start_pos))
                                             } { };
                                         nodes_list.reverse();
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyElse ]).clone() {
                                             Ok(s) => {
                                                 match &*s {
                                                     Token::PyElse(..) => {
@@ -1313,14 +1326,14 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_elif_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyElif ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyElif(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
                         let left_node = self.parse_expressions_test()?;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyColon ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyColon(..) => {
@@ -1344,14 +1357,14 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_else_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyElse ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyElse(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
 
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyColon ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyColon(..) => {
@@ -1375,21 +1388,21 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_while_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyWhile ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyWhile(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
                         let left_node = self.parse_expressions_test()?;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyColon ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyColon(..) => {
                                         let symbol2 = s2;
                                         let _ = self.advance();
                                         let right_node = self.parse_statements_suite()?;
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyElse ]).clone() {
                                             Ok(s3) => {
                                                 match &*s3 {
                                                     Token::PyElse(..) => {
@@ -1419,28 +1432,28 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_for_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyFor ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyFor(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
                         let left_node = self.parse_expressions_exprlist()?;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyIn ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyIn(..) => {
                                         let symbol2 = s2;
                                         let _ = self.advance();
                                         let right_node = self.parse_expressions_testlist()?;
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyColon ]).clone() {
                                             Ok(s3) => {
                                                 match &*s3 {
                                                     Token::PyColon(..) => {
                                                         let symbol3 = s3;
                                                         let _ = self.advance();
                                                         let mut tc_symbol: Option<Box<Token>> = None;
-                                                        match self.symbol.clone() {
+                                                        match self.expecting(kinds![ TypeComment ]).clone() {
                                                             Ok(s4) => {
                                                                 match &*s4 {
                                                                     Token::TypeComment(..) => {
@@ -1453,7 +1466,7 @@ impl Statements for PythonCoreParser {
                                                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in for statement!", start_pos))
                                                         }
                                                         let next_node = self.parse_statements_suite()?;
-                                                        match self.symbol.clone() {
+                                                        match self.expecting(kinds![ PyElse ]).clone() {
                                                             Ok(s5) => {
                                                                 match &*s5 {
                                                                     Token::PyElse(..) => {
@@ -1489,21 +1502,21 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_try_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyTry ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyTry(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
                         self.except_status = ET::ExceptNone;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyColon ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyColon(..) => {
                                         let symbol2 = s2;
                                         let _ = self.advance();
                                         let left_node = self.parse_statements_suite()?;
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyFinally ]).clone() {
                                             Ok(s3) => {
                                                 match &*s3 {
                                                     Token::PyFinally(..) => {
@@ -1516,7 +1529,7 @@ impl Statements for PythonCoreParser {
                                                         let mut right_node : Option<Box<ASTNode>> = None;
                                                         nodes_list.push( self.parse_statements_except_stmt()? );
                                                         while
-                                                            match self.symbol.clone() {
+                                                            match self.expecting(kinds![ PyExcept ]).clone() {
                                                                 Ok(s4) => {
                                                                     match &*s4 {
                                                                         Token::PyExcept(..) => {
@@ -1528,7 +1541,7 @@ impl Statements for PythonCoreParser {
                                                                 },
                                                                 _ => return Err(format!("SyntaxError at {}: Expecting symbol in try statement!", start_pos))
                                                             } { };
-                                                        match self.symbol.clone() {
+                                                        match self.expecting(kinds![ PyElse ]).clone() {
                                                             Ok(s5) => {
                                                                 match &*s5 {
                                                                     Token::PyElse(..) => {
@@ -1539,7 +1552,7 @@ impl Statements for PythonCoreParser {
                                                             },
                                                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in try statement!", start_pos))
                                                         }
-                                                        match self.symbol.clone() {
+                                                        match self.expecting(kinds![ PyFinally ]).clone() {
                                                             Ok(s6) => {
                                                                 match &*s6 {
                                                                     Token::PyFinally(..) => {
@@ -1573,13 +1586,13 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_finally_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyFinally ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyFinally(..) => {
                         let symbol1 = s;
                         let _ = self.advance();
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyColon ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyColon(..) => {
@@ -1603,7 +1616,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_with_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyWith ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyWith(..) => {
@@ -1614,7 +1627,7 @@ impl Statements for PythonCoreParser {
                         let mut left_symbol : Option<Box<Token>> = None;
                         let mut right_symbol : Option<Box<Token>> = None;
                         let symbol2;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyLeftParen ]).clone() {
                             Ok(s3) => {
                                 match &*s3 {
                                     Token::PyLeftParen(..) => {
@@ -1622,13 +1635,13 @@ impl Statements for PythonCoreParser {
                                         let _ = self.advance();
                                         nodes_list.push( self.parse_statements_with_item()? );
                                         while
-                                            match self.symbol.clone() {
+                                            match self.expecting(kinds![ PyComa ]).clone() {
                                                 Ok(s5) => {
                                                     match &*s5 {
                                                         Token::PyComa(..) => {
                                                             separators_list.push( s5 );
                                                             let _ = self.advance();
-                                                            match self.symbol.clone() {
+                                                            match self.expecting(kinds![ PyRightParen ]).clone() {
                                                                 Ok(s6) => {
                                                                     match &*s6 {
                                                                         Token::PyRightParen(..) => false,
@@ -1646,7 +1659,7 @@ impl Statements for PythonCoreParser {
                                                 },
                                                 _ => return Err(format!("SyntaxError at {}: Expecting symbol in with statement!", start_pos))
                                             } { };
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyRightParen ]).clone() {
                                             Ok(s7) => {
                                                 match &*s7 {
                                                     Token::PyRightParen(..) => {
@@ -1662,7 +1675,7 @@ impl Statements for PythonCoreParser {
                                     _ => {
                                         nodes_list.push( self.parse_statements_with_item()? );
                                         while
-                                            match self.symbol.clone() {
+                                            match self.expecting(kinds![ PyComa ]).clone() {
                                                 Ok(s4) => {
                                                     match &*s4 {
                                                         Token::PyComa(..) => {
@@ -1681,7 +1694,7 @@ impl Statements for PythonCoreParser {
                             },
                             _ => return Err(format!("SyntaxError at {}: Expecting symbol in with statement!", start_pos))
                         }
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyColon ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyColon(..) => {
@@ -1708,14 +1721,14 @@ impl Statements for PythonCoreParser {
     fn parse_statements_with_item(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let left_node = self.parse_expressions_test()?;
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyAs ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyAs(..) => {
                         let symbol = s;
                         let _ = self.advance();
                         let right_node = self.parse_expressions_expr()?;
-                        Ok(Box::new( ASTNode::ExceptStmt(start_pos, self.lexer.get_position(), left_node, symbol, right_node) ))
+                        Ok(Box::new( ASTNode::WithItem(start_pos, self.lexer.get_position(), left_node, Some( ( symbol, right_node ) )) ))
                     },
                     _ => {
                         Ok(Box::new( ASTNode::WithItem(start_pos, self.lexer.get_position(), left_node, None) ))
@@ -1729,7 +1742,7 @@ impl Statements for PythonCoreParser {
     fn parse_statements_except_stmt(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let left_node = self.parse_statements_except_clause()?;
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyColon ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyColon(..) => {
@@ -1748,7 +1761,7 @@ impl Statements for PythonCoreParser {
     fn parse_statements_except_clause(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
         let mut symbol_mul : Option<Box<Token>> = None;
-        match self.symbol.clone() {
+        match self.expecting(kinds![ PyExcept ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::PyExcept(..) => {
@@ -1756,7 +1769,7 @@ impl Statements for PythonCoreParser {
                         let _ = self.advance();
 
                         /* Handle 'except*' or 'except' e.g. Python 3.11 */
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyMul ]).clone() {
                             Ok(s10) => {
                                 match &*s10 {
                                     Token::PyMul(..)=> {
@@ -1782,7 +1795,7 @@ impl Statements for PythonCoreParser {
                         }
 
 
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ PyColon ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::PyColon(..) => {
@@ -1792,13 +1805,13 @@ impl Statements for PythonCoreParser {
                                         //let symbol2 = s2;  // BUG!
                                         //let _ = self.advance();
                                         let left_node = self.parse_expressions_test()?;
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ PyAs ]).clone() {
                                             Ok(s3) => {
                                                 match &*s3 {
                                                     Token::PyAs(..) => {
                                                         let symbol2 = s3;
                                                         let _ = self.advance();
-                                                        match self.symbol.clone() {
+                                                        match self.expecting(kinds![ AtomName ]).clone() {
                                                             Ok(s4) => {
                                                                 match &*s4 {
                                                                     Token::AtomName(..) => {
@@ -1834,7 +1847,7 @@ impl Statements for PythonCoreParser {
 
     fn parse_statements_suite(&mut self) -> Result<Box<ASTNode>, String> {
         let start_pos = self.lexer.get_position();
-        match self.symbol.clone() {
+        match self.expecting(kinds![ Newline ]).clone() {
             Ok(s) => {
                 match &*s {
                     Token::Newline(..) => {
@@ -1843,7 +1856,7 @@ impl Statements for PythonCoreParser {
                         let mut nodes_list : Box<Vec<Box<ASTNode>>> = Box::new(Vec::new());
                         let symbol2;
                         let symbol3;
-                        match self.symbol.clone() {
+                        match self.expecting(kinds![ Indent ]).clone() {
                             Ok(s2) => {
                                 match &*s2 {
                                     Token::Indent(..) => {
@@ -1851,7 +1864,7 @@ impl Statements for PythonCoreParser {
                                         let _ = self.advance();
                                         nodes_list.push(self.parse_statements_stmt()?);
                                         while
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ Dedent ]).clone() {
                                                 Ok(s3) => {
                                                     match &*s3 {
                                                         Token::Dedent(..) => false,
@@ -1863,7 +1876,7 @@ impl Statements for PythonCoreParser {
                                                 },
                                                 _ => return Err(format!("SyntaxError at {}: Expecting symbol in suite statement!", start_pos))
                                             } { };
-                                        match self.symbol.clone() {
+                                        match self.expecting(kinds![ Dedent ]).clone() {
                                             Ok(s4) => {
                                                 match &*s4 {
                                                     Token::Dedent(..) => {
@@ -3211,6 +3224,17 @@ mod tests {
         }
     }
 
-
+    #[test]
+    fn statements_expected_tokens_at_error() {
+        let lexer = Box::new(PythonCoreTokenizer::new("class A\n".to_string()));
+        let mut parser = PythonCoreParser::new(lexer);
+        parser.advance();
+        assert!(parser.parse_statements_stmt().is_err());
+        assert_eq!(( parser.furthest.get(), parser.expected.into_inner() ), ( 7, vec![ "PyLeftParen", "PyColon" ] ));
+        let lexer = Box::new(PythonCoreTokenizer::new("for x in y:\n    pass\nelse pass\n".to_string()));
+        let mut parser = PythonCoreParser::new(lexer);
+        parser.advance();
+        assert!(parser.parse_statements_stmt().is_err());
+        assert_eq!(parser.expected.into_inner(), vec![ "PyColon" ]);
+    }
 }
-
@@ -3,6 +3,7 @@ use crate::parser::source_buffer::SourceBuffer;
 use crate::parser::source_buffer::SourceBufferFunctionality;
 use crate::Token;
 
+#[derive(Clone)]
 pub struct PythonCoreTokenizer {
     source_buffer: Box<SourceBuffer>,
     token_start_position: u32,
@@ -11,7 +12,8 @@ pub struct PythonCoreTokenizer {
     tab_size: u32,
     is_interactive: bool,
     pending: i32,
-    indent_stack: Vec<u32>
+    indent_stack: Vec<u32>,
+    is_probe: bool
 }
 
 
@@ -20,6 +22,8 @@ pub trait Tokenizer {
     fn get_symbol(&mut self) -> Result<Box<Token>, String>;
     fn handle_string(&mut self, start: u32, triple: bool, prefix: Option<String>, trivia: Box<Vec<Box<Trivia>>>) -> Result<Box<Token>, String>;
     fn get_position(&self) -> u32;
+    fn set_interactive(&mut self, interactive: bool);
+    fn set_probe(&mut self, probe: bool);
 }
 
 
@@ -33,10 +37,22 @@ impl Tokenizer for PythonCoreTokenizer {
             tab_size: 4,
             is_interactive: false,
             pending: 0,
-            indent_stack: { let mut stack : Vec<u32> = Vec::new(); stack.push(0); stack }
+            indent_stack: { let mut stack : Vec<u32> = Vec::new(); stack.push(0); stack },
+            is_probe: false
         }
     }
 
+    /// Interactive mode ends blocks at an empty line with a Newline token, as the REPL does.
+    fn set_interactive(&mut self, interactive: bool) {
+        self.is_interactive = interactive
+    }
+
+    /// Probe mode ends source with ProbeEnd instead of EOF, repeated on every further read, so that
+    /// the parser tests there every token that may follow the source.
+    fn set_probe(&mut self, probe: bool) {
+        self.is_probe = probe
+    }
+
     fn get_symbol(&mut self) -> Result<Box<Token>, String> {
 
         let mut trivia_collector : Box<Vec<Box<Trivia>>> = Box::new( Vec::new() );
@@ -50,6 +66,7 @@ impl Tokenizer for PythonCoreTokenizer {
             match &self.is_at_beginning_of_line {
                 true => {
                     self.is_at_beginning_of_line = false;
+                    is_blank_line = false;
                     let mut col: u32 = 0;
                     while   match self.source_buffer.get_char() {
                             ' ' => {
@@ -110,11 +127,11 @@ impl Tokenizer for PythonCoreTokenizer {
             match self.pending {
                 _ if self.pending < 0 => {
                     self.pending += 1;
-                    return Ok( Box::new( Token::Dedent(None) ) )
+                    return Ok( Box::new( Token::Dedent(match trivia_collector.len() { 0 => None, _ => Some( { trivia_collector.reverse(); trivia_collector } ) }) ) )
                 },
                 _ if self.pending > 0 => {
                     self.pending -= 1;
-                    return Ok( Box::new( Token::Indent(None) ) )
+                    return Ok( Box::new( Token::Indent(match trivia_collector.len() { 0 => None, _ => Some( { trivia_collector.reverse(); trivia_collector } ) }) ) )
                 },
                 _ => { }
             }
@@ -172,7 +189,11 @@ impl Tokenizer for PythonCoreTokenizer {
                 /* Handle End Of File */
                 match self.source_buffer.get_char() {
                     '\0' => {
-                        return Ok(Box::new(Token::EOF(self.source_buffer.get_position(), match trivia_collector.len() { 0 => None, _ => Some( { trivia_collector.reverse(); trivia_collector } ) } )))
+                        let trivia = match trivia_collector.len() { 0 => None, _ => Some( { trivia_collector.reverse(); trivia_collector } ) };
+                        return Ok(Box::new(match self.is_probe {
+                            true => Token::ProbeEnd(self.source_buffer.get_position(), trivia),
+                            _ => Token::EOF(self.source_buffer.get_position(), trivia)
+                        }))
                     },
                     _ => {}
                 }
@@ -626,7 +647,17 @@ impl Tokenizer for PythonCoreTokenizer {
                     "rf" |
                     "rF" |
                     "Rf" |
-                    "RF" if self.source_buffer.get_char() == '\'' || self.source_buffer.get_char() == '"' => {
+                    "RF" |
+                    "b" |
+                    "B" |
+                    "br" |
+                    "Br" |
+                    "bR" |
+                    "BR" |
+                    "rb" |
+                    "rB" |
+                    "Rb" |
+                    "RB" if self.source_buffer.get_char() == '\'' || self.source_buffer.get_char() == '"' => {
                         match self.source_buffer.peek_three_chars() {
                             ( '\'', '\'', '\'' ) |
                             ( '"', '"', '"' ) => {
@@ -741,9 +772,15 @@ impl Tokenizer for PythonCoreTokenizer {
                     },
                     _ => {
                         let mut non_zero = false;
-                        buffer.push( self.source_buffer.get_char() );
-                        let _ = self.source_buffer.advance();
                         while   match self.source_buffer.get_char() {
+                            '_' => {
+                                buffer.push( self.source_buffer.get_char() );
+                                let _ = self.source_buffer.advance();
+                                match self.source_buffer.get_char() {
+                                    '0'..='9' => true,
+                                    _ => return Err( format!("Syntax Error at {} - Expected digit after '_' in number!", &self.get_position()) )
+                                }
+                            },
                             '0' => {
                                 buffer.push( self.source_buffer.get_char() );
                                 let _ = self.source_buffer.advance();
@@ -1020,6 +1057,10 @@ impl Tokenizer for PythonCoreTokenizer {
                         let _ = self.source_buffer.advance();
                         true
                     },
+                    ( '\\', x, _ ) if x != '\0' => {
+                        for _i in 1 ..= 2 { buffer.push(self.source_buffer.get_char()); let _ = self.source_buffer.advance(); }
+                        true
+                    },
                     ( a, b, c) if quote == a && quote == b && quote == c => {
                         for _i in 1 ..= 3 { buffer.push(self.source_buffer.get_char()); let _ = self.source_buffer.advance(); }
                         false
@@ -1038,6 +1079,22 @@ impl Tokenizer for PythonCoreTokenizer {
                 let _ = self.source_buffer.advance();
                 while match self.source_buffer.get_char() {
                     '\0' | '\r' | '\n' => return Err(format!("Unterminated single quote string at {}!", self.source_buffer.get_position()).to_string()),
+                    '\\' => {
+                        /* Escaped character, including escaped quote and line continuation inside string */
+                        buffer.push(self.source_buffer.get_char());
+                        let _ = self.source_buffer.advance();
+                        match self.source_buffer.peek_three_chars() {
+                            ( '\0', _ , _ ) => { },
+                            ( '\r', '\n', _ ) => {
+                                for _i in 1 ..= 2 { buffer.push(self.source_buffer.get_char()); let _ = self.source_buffer.advance(); }
+                            },
+                            _ => {
+                                buffer.push(self.source_buffer.get_char());
+                                let _ = self.source_buffer.advance();
+                            }
+                        }
+                        true
+                    },
                     _ => {
                         if self.source_buffer.get_char() == quote {
                             buffer.push(self.source_buffer.get_char());
@@ -3229,4 +3286,38 @@ mod tests {
             Err( _e ) => assert!(false)
         }
     }
+
+    #[test]
+    fn tokenizer_literal_number_with_underscores() {
+        let mut tokenizer = Box::new( PythonCoreTokenizer::new( "1_000_000".to_string() ) );
+        let s = tokenizer.get_symbol().unwrap();
+        assert!(matches!(&*s, Token::AtomNumber( 0u32, 9u32, None, txt ) if txt.as_str() == "1_000_000"));
+    }
+
+    #[test]
+    fn tokenizer_literal_number_with_double_underscore() {
+        let mut tokenizer = Box::new( PythonCoreTokenizer::new( "0__0".to_string() ) );
+        let e = tokenizer.get_symbol().err().unwrap();
+        assert!(e.contains("Expected digit after '_' in number!"));
+    }
+
+    #[test]
+    fn tokenizer_literal_bytes_prefixes() {
+        for prefix in [ "b", "B", "br", "Br", "bR", "BR", "rb", "rB", "Rb", "RB" ] {
+            let mut tokenizer = Box::new( PythonCoreTokenizer::new( format!("{}'x'", prefix) ) );
+            let s = tokenizer.get_symbol().unwrap();
+            let end = ( prefix.len() + 3 ) as u32;
+            assert!(matches!(&*s, Token::AtomString( 0u32, e, None, txt, Some( p ) )
+                if *e == end && txt.as_str() == "'x'" && p.as_str() == prefix));
+        }
+    }
+
+    #[test]
+    fn tokenizer_literal_string_escaped_quote_and_line_continuation() {
+        let mut tokenizer = Box::new( PythonCoreTokenizer::new( "'it\\'s' 'a\\\r\nb'".to_string() ) );
+        let s = tokenizer.get_symbol().unwrap();
+        assert!(matches!(&*s, Token::AtomString( 0u32, 7u32, None, txt, None ) if txt.as_str() == "'it\\'s'"));
+        let s = tokenizer.get_symbol().unwrap();
+        assert!(matches!(&*s, Token::AtomString( 8u32, 15u32, _, txt, None ) if txt.as_str() == "'a\\\r\nb'"));
+    }
 }
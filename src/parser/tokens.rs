@@ -1,7 +1,8 @@
 
+use std::hash::{ Hash, Hasher };
 use crate::parser::trivias::{ Trivia };
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Token {
     PyFalse(u32, u32, Option<Box<Vec<Box<Trivia>>>>),
     PyNone(u32, u32, Option<Box<Vec<Box<Trivia>>>>),
@@ -92,5 +93,425 @@ pub enum Token {
     Indent(Option<Box<Vec<Box<Trivia>>>>),
     Dedent(Option<Box<Vec<Box<Trivia>>>>),
     TypeComment(u32, u32, Option<Box<Vec<Box<Trivia>>>>, Box<String>),
-    EOF(u32, Option<Box<Vec<Box<Trivia>>>>)
-} 
\ No newline at end of file
+    EOF(u32, Option<Box<Vec<Box<Trivia>>>>),
+    /// End of source in probe mode of the tokenizer, a token no rule accepts.
+    ProbeEnd(u32, Option<Box<Vec<Box<Trivia>>>>)
+}
+
+
+impl Token {
+    /// Returns start and end position of token. Indent and Dedent tokens carries no position.
+    pub fn get_span(&self) -> Option<( u32, u32 )> {
+        match self {
+            Token::PyFalse(start, end, ..) |
+            Token::PyNone(start, end, ..) |
+            Token::PyTrue(start, end, ..) |
+            Token::PyAnd(start, end, ..) |
+            Token::PyAs(start, end, ..) |
+            Token::PyAssert(start, end, ..) |
+            Token::PyAsync(start, end, ..) |
+            Token::PyAwait(start, end, ..) |
+            Token::PyBreak(start, end, ..) |
+            Token::PyClass(start, end, ..) |
+            Token::PyContinue(start, end, ..) |
+            Token::PyDef(start, end, ..) |
+            Token::PyDel(start, end, ..) |
+            Token::PyElif(start, end, ..) |
+            Token::PyElse(start, end, ..) |
+            Token::PyExcept(start, end, ..) |
+            Token::PyFinally(start, end, ..) |
+            Token::PyFor(start, end, ..) |
+            Token::PyFrom(start, end, ..) |
+            Token::PyGlobal(start, end, ..) |
+            Token::PyIf(start, end, ..) |
+            Token::PyImport(start, end, ..) |
+            Token::PyIn(start, end, ..) |
+            Token::PyIs(start, end, ..) |
+            Token::PyLambda(start, end, ..) |
+            Token::PyNonLocal(start, end, ..) |
+            Token::PyNot(start, end, ..) |
+            Token::PyOr(start, end, ..) |
+            Token::PyPass(start, end, ..) |
+            Token::PyRaise(start, end, ..) |
+            Token::PyReturn(start, end, ..) |
+            Token::PyTry(start, end, ..) |
+            Token::PyWhile(start, end, ..) |
+            Token::PyWith(start, end, ..) |
+            Token::PyYield(start, end, ..) |
+            Token::PyPlus(start, end, ..) |
+            Token::PyMinus(start, end, ..) |
+            Token::PyMul(start, end, ..) |
+            Token::PyPower(start, end, ..) |
+            Token::PyDiv(start, end, ..) |
+            Token::PyFloorDiv(start, end, ..) |
+            Token::PyModulo(start, end, ..) |
+            Token::PyMatrice(start, end, ..) |
+            Token::PyShiftLeft(start, end, ..) |
+            Token::PyShiftRight(start, end, ..) |
+            Token::PyBitAnd(start, end, ..) |
+            Token::PyBitOr(start, end, ..) |
+            Token::PyBitXor(start, end, ..) |
+            Token::PyBitInvert(start, end, ..) |
+            Token::PyColonAssign(start, end, ..) |
+            Token::PyLess(start, end, ..) |
+            Token::PyGreater(start, end, ..) |
+            Token::PyLessEqual(start, end, ..) |
+            Token::PyGreaterEqual(start, end, ..) |
+            Token::PyEqual(start, end, ..) |
+            Token::PyNotEqual(start, end, ..) |
+            Token::PyLeftParen(start, end, ..) |
+            Token::PyLeftBracket(start, end, ..) |
+            Token::PyLeftCurly(start, end, ..) |
+            Token::PyRightParen(start, end, ..) |
+            Token::PyRightBracket(start, end, ..) |
+            Token::PyRightCurly(start, end, ..) |
+            Token::PyComa(start, end, ..) |
+            Token::PyColon(start, end, ..) |
+            Token::PyDot(start, end, ..) |
+            Token::PySemiColon(start, end, ..) |
+            Token::PyAssign(start, end, ..) |
+            Token::PyArrow(start, end, ..) |
+            Token::PyPlusAssign(start, end, ..) |
+            Token::PyMinusAssign(start, end, ..) |
+            Token::PyMulAssign(start, end, ..) |
+            Token::PyDivAssign(start, end, ..) |
+            Token::PyFloorDivAssign(start, end, ..) |
+            Token::PyModuloAssign(start, end, ..) |
+            Token::PyMatriceAssign(start, end, ..) |
+            Token::PyBitAndAssign(start, end, ..) |
+            Token::PyBitOrAssign(start, end, ..) |
+            Token::PyBitXorAssign(start, end, ..) |
+            Token::PyShiftLeftAssign(start, end, ..) |
+            Token::PyShiftRightAssign(start, end, ..) |
+            Token::PyPowerAssign(start, end, ..) |
+            Token::PyElipsis(start, end, ..) |
+            Token::AtomName(start, end, ..) |
+            Token::AtomNumber(start, end, ..) |
+            Token::AtomString(start, end, ..) |
+            Token::Newline(start, end, ..) |
+            Token::TypeComment(start, end, ..) => Some( ( *start, *end ) ),
+            Token::EOF(pos, _ ) |
+            Token::ProbeEnd(pos, _ ) => Some( ( *pos, *pos ) ),
+            Token::Indent(..) |
+            Token::Dedent(..) => None
+        }
+    }
+
+    /// Returns trivia in front of token, that is whitespace, comments and line continuations.
+    pub fn get_trivia(&self) -> Option<&Vec<Box<Trivia>>> {
+        match self {
+            Token::PyFalse(_, _, trivia, ..) |
+            Token::PyNone(_, _, trivia, ..) |
+            Token::PyTrue(_, _, trivia, ..) |
+            Token::PyAnd(_, _, trivia, ..) |
+            Token::PyAs(_, _, trivia, ..) |
+            Token::PyAssert(_, _, trivia, ..) |
+            Token::PyAsync(_, _, trivia, ..) |
+            Token::PyAwait(_, _, trivia, ..) |
+            Token::PyBreak(_, _, trivia, ..) |
+            Token::PyClass(_, _, trivia, ..) |
+            Token::PyContinue(_, _, trivia, ..) |
+            Token::PyDef(_, _, trivia, ..) |
+            Token::PyDel(_, _, trivia, ..) |
+            Token::PyElif(_, _, trivia, ..) |
+            Token::PyElse(_, _, trivia, ..) |
+            Token::PyExcept(_, _, trivia, ..) |
+            Token::PyFinally(_, _, trivia, ..) |
+            Token::PyFor(_, _, trivia, ..) |
+            Token::PyFrom(_, _, trivia, ..) |
+            Token::PyGlobal(_, _, trivia, ..) |
+            Token::PyIf(_, _, trivia, ..) |
+            Token::PyImport(_, _, trivia, ..) |
+            Token::PyIn(_, _, trivia, ..) |
+            Token::PyIs(_, _, trivia, ..) |
+            Token::PyLambda(_, _, trivia, ..) |
+            Token::PyNonLocal(_, _, trivia, ..) |
+            Token::PyNot(_, _, trivia, ..) |
+            Token::PyOr(_, _, trivia, ..) |
+            Token::PyPass(_, _, trivia, ..) |
+            Token::PyRaise(_, _, trivia, ..) |
+            Token::PyReturn(_, _, trivia, ..) |
+            Token::PyTry(_, _, trivia, ..) |
+            Token::PyWhile(_, _, trivia, ..) |
+            Token::PyWith(_, _, trivia, ..) |
+            Token::PyYield(_, _, trivia, ..) |
+            Token::PyPlus(_, _, trivia, ..) |
+            Token::PyMinus(_, _, trivia, ..) |
+            Token::PyMul(_, _, trivia, ..) |
+            Token::PyPower(_, _, trivia, ..) |
+            Token::PyDiv(_, _, trivia, ..) |
+            Token::PyFloorDiv(_, _, trivia, ..) |
+            Token::PyModulo(_, _, trivia, ..) |
+            Token::PyMatrice(_, _, trivia, ..) |
+            Token::PyShiftLeft(_, _, trivia, ..) |
+            Token::PyShiftRight(_, _, trivia, ..) |
+            Token::PyBitAnd(_, _, trivia, ..) |
+            Token::PyBitOr(_, _, trivia, ..) |
+            Token::PyBitXor(_, _, trivia, ..) |
+            Token::PyBitInvert(_, _, trivia, ..) |
+            Token::PyColonAssign(_, _, trivia, ..) |
+            Token::PyLess(_, _, trivia, ..) |
+            Token::PyGreater(_, _, trivia, ..) |
+            Token::PyLessEqual(_, _, trivia, ..) |
+            Token::PyGreaterEqual(_, _, trivia, ..) |
+            Token::PyEqual(_, _, trivia, ..) |
+            Token::PyNotEqual(_, _, trivia, ..) |
+            Token::PyLeftParen(_, _, trivia, ..) |
+            Token::PyLeftBracket(_, _, trivia, ..) |
+            Token::PyLeftCurly(_, _, trivia, ..) |
+            Token::PyRightParen(_, _, trivia, ..) |
+            Token::PyRightBracket(_, _, trivia, ..) |
+            Token::PyRightCurly(_, _, trivia, ..) |
+            Token::PyComa(_, _, trivia, ..) |
+            Token::PyColon(_, _, trivia, ..) |
+            Token::PyDot(_, _, trivia, ..) |
+            Token::PySemiColon(_, _, trivia, ..) |
+            Token::PyAssign(_, _, trivia, ..) |
+            Token::PyArrow(_, _, trivia, ..) |
+            Token::PyPlusAssign(_, _, trivia, ..) |
+            Token::PyMinusAssign(_, _, trivia, ..) |
+            Token::PyMulAssign(_, _, trivia, ..) |
+            Token::PyDivAssign(_, _, trivia, ..) |
+            Token::PyFloorDivAssign(_, _, trivia, ..) |
+            Token::PyModuloAssign(_, _, trivia, ..) |
+            Token::PyMatriceAssign(_, _, trivia, ..) |
+            Token::PyBitAndAssign(_, _, trivia, ..) |
+            Token::PyBitOrAssign(_, _, trivia, ..) |
+            Token::PyBitXorAssign(_, _, trivia, ..) |
+            Token::PyShiftLeftAssign(_, _, trivia, ..) |
+            Token::PyShiftRightAssign(_, _, trivia, ..) |
+            Token::PyPowerAssign(_, _, trivia, ..) |
+            Token::PyElipsis(_, _, trivia, ..) |
+            Token::AtomName(_, _, trivia, ..) |
+            Token::AtomNumber(_, _, trivia, ..) |
+            Token::AtomString(_, _, trivia, ..) |
+            Token::Newline(_, _, trivia, ..) |
+            Token::TypeComment(_, _, trivia, ..) |
+            Token::EOF(_, trivia) |
+            Token::ProbeEnd(_, trivia) |
+            Token::Indent(trivia) |
+            Token::Dedent(trivia) => trivia.as_deref()
+        }
+    }
+
+    /// Returns name of token kind, the same as name of the enum variant.
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Token::PyFalse(..) => "PyFalse",
+            Token::PyNone(..) => "PyNone",
+            Token::PyTrue(..) => "PyTrue",
+            Token::PyAnd(..) => "PyAnd",
+            Token::PyAs(..) => "PyAs",
+            Token::PyAssert(..) => "PyAssert",
+            Token::PyAsync(..) => "PyAsync",
+            Token::PyAwait(..) => "PyAwait",
+            Token::PyBreak(..) => "PyBreak",
+            Token::PyClass(..) => "PyClass",
+            Token::PyContinue(..) => "PyContinue",
+            Token::PyDef(..) => "PyDef",
+            Token::PyDel(..) => "PyDel",
+            Token::PyElif(..) => "PyElif",
+            Token::PyElse(..) => "PyElse",
+            Token::PyExcept(..) => "PyExcept",
+            Token::PyFinally(..) => "PyFinally",
+            Token::PyFor(..) => "PyFor",
+            Token::PyFrom(..) => "PyFrom",
+            Token::PyGlobal(..) => "PyGlobal",
+            Token::PyIf(..) => "PyIf",
+            Token::PyImport(..) => "PyImport",
+            Token::PyIn(..) => "PyIn",
+            Token::PyIs(..) => "PyIs",
+            Token::PyLambda(..) => "PyLambda",
+            Token::PyNonLocal(..) => "PyNonLocal",
+            Token::PyNot(..) => "PyNot",
+            Token::PyOr(..) => "PyOr",
+            Token::PyPass(..) => "PyPass",
+            Token::PyRaise(..) => "PyRaise",
+            Token::PyReturn(..) => "PyReturn",
+            Token::PyTry(..) => "PyTry",
+            Token::PyWhile(..) => "PyWhile",
+            Token::PyWith(..) => "PyWith",
+            Token::PyYield(..) => "PyYield",
+            Token::PyPlus(..) => "PyPlus",
+            Token::PyMinus(..) => "PyMinus",
+            Token::PyMul(..) => "PyMul",
+            Token::PyPower(..) => "PyPower",
+            Token::PyDiv(..) => "PyDiv",
+            Token::PyFloorDiv(..) => "PyFloorDiv",
+            Token::PyModulo(..) => "PyModulo",
+            Token::PyMatrice(..) => "PyMatrice",
+            Token::PyShiftLeft(..) => "PyShiftLeft",
+            Token::PyShiftRight(..) => "PyShiftRight",
+            Token::PyBitAnd(..) => "PyBitAnd",
+            Token::PyBitOr(..) => "PyBitOr",
+            Token::PyBitXor(..) => "PyBitXor",
+            Token::PyBitInvert(..) => "PyBitInvert",
+            Token::PyColonAssign(..) => "PyColonAssign",
+            Token::PyLess(..) => "PyLess",
+            Token::PyGreater(..) => "PyGreater",
+            Token::PyLessEqual(..) => "PyLessEqual",
+            Token::PyGreaterEqual(..) => "PyGreaterEqual",
+            Token::PyEqual(..) => "PyEqual",
+            Token::PyNotEqual(..) => "PyNotEqual",
+            Token::PyLeftParen(..) => "PyLeftParen",
+            Token::PyLeftBracket(..) => "PyLeftBracket",
+            Token::PyLeftCurly(..) => "PyLeftCurly",
+            Token::PyRightParen(..) => "PyRightParen",
+            Token::PyRightBracket(..) => "PyRightBracket",
+            Token::PyRightCurly(..) => "PyRightCurly",
+            Token::PyComa(..) => "PyComa",
+            Token::PyColon(..) => "PyColon",
+            Token::PyDot(..) => "PyDot",
+            Token::PySemiColon(..) => "PySemiColon",
+            Token::PyAssign(..) => "PyAssign",
+            Token::PyArrow(..) => "PyArrow",
+            Token::PyPlusAssign(..) => "PyPlusAssign",
+            Token::PyMinusAssign(..) => "PyMinusAssign",
+            Token::PyMulAssign(..) => "PyMulAssign",
+            Token::PyDivAssign(..) => "PyDivAssign",
+            Token::PyFloorDivAssign(..) => "PyFloorDivAssign",
+            Token::PyModuloAssign(..) => "PyModuloAssign",
+            Token::PyMatriceAssign(..) => "PyMatriceAssign",
+            Token::PyBitAndAssign(..) => "PyBitAndAssign",
+            Token::PyBitOrAssign(..) => "PyBitOrAssign",
+            Token::PyBitXorAssign(..) => "PyBitXorAssign",
+            Token::PyShiftLeftAssign(..) => "PyShiftLeftAssign",
+            Token::PyShiftRightAssign(..) => "PyShiftRightAssign",
+            Token::PyPowerAssign(..) => "PyPowerAssign",
+            Token::PyElipsis(..) => "PyElipsis",
+            Token::AtomName(..) => "AtomName",
+            Token::AtomNumber(..) => "AtomNumber",
+            Token::AtomString(..) => "AtomString",
+            Token::Newline(..) => "Newline",
+            Token::Indent(..) => "Indent",
+            Token::Dedent(..) => "Dedent",
+            Token::TypeComment(..) => "TypeComment",
+            Token::EOF(..) => "EOF",
+            Token::ProbeEnd(..) => "ProbeEnd"
+        }
+    }
+
+    /// Returns source text of token, while Newline, Indent, Dedent and EOF has no text.
+    pub fn text(&self) -> Option<String> {
+        match self {
+            Token::PyFalse(..) => Some( "False".to_string() ),
+            Token::PyNone(..) => Some( "None".to_string() ),
+            Token::PyTrue(..) => Some( "True".to_string() ),
+            Token::PyAnd(..) => Some( "and".to_string() ),
+            Token::PyAs(..) => Some( "as".to_string() ),
+            Token::PyAssert(..) => Some( "assert".to_string() ),
+            Token::PyAsync(..) => Some( "async".to_string() ),
+            Token::PyAwait(..) => Some( "await".to_string() ),
+            Token::PyBreak(..) => Some( "break".to_string() ),
+            Token::PyClass(..) => Some( "class".to_string() ),
+            Token::PyContinue(..) => Some( "continue".to_string() ),
+            Token::PyDef(..) => Some( "def".to_string() ),
+            Token::PyDel(..) => Some( "del".to_string() ),
+            Token::PyElif(..) => Some( "elif".to_string() ),
+            Token::PyElse(..) => Some( "else".to_string() ),
+            Token::PyExcept(..) => Some( "except".to_string() ),
+            Token::PyFinally(..) => Some( "finally".to_string() ),
+            Token::PyFor(..) => Some( "for".to_string() ),
+            Token::PyFrom(..) => Some( "from".to_string() ),
+            Token::PyGlobal(..) => Some( "global".to_string() ),
+            Token::PyIf(..) => Some( "if".to_string() ),
+            Token::PyImport(..) => Some( "import".to_string() ),
+            Token::PyIn(..) => Some( "in".to_string() ),
+            Token::PyIs(..) => Some( "is".to_string() ),
+            Token::PyLambda(..) => Some( "lambda".to_string() ),
+            Token::PyNonLocal(..) => Some( "nonlocal".to_string() ),
+            Token::PyNot(..) => Some( "not".to_string() ),
+            Token::PyOr(..) => Some( "or".to_string() ),
+            Token::PyPass(..) => Some( "pass".to_string() ),
+            Token::PyRaise(..) => Some( "raise".to_string() ),
+            Token::PyReturn(..) => Some( "return".to_string() ),
+            Token::PyTry(..) => Some( "try".to_string() ),
+            Token::PyWhile(..) => Some( "while".to_string() ),
+            Token::PyWith(..) => Some( "with".to_string() ),
+            Token::PyYield(..) => Some( "yield".to_string() ),
+            Token::PyPlus(..) => Some( "+".to_string() ),
+            Token::PyMinus(..) => Some( "-".to_string() ),
+            Token::PyMul(..) => Some( "*".to_string() ),
+            Token::PyPower(..) => Some( "**".to_string() ),
+            Token::PyDiv(..) => Some( "/".to_string() ),
+            Token::PyFloorDiv(..) => Some( "//".to_string() ),
+            Token::PyModulo(..) => Some( "%".to_string() ),
+            Token::PyMatrice(..) => Some( "@".to_string() ),
+            Token::PyShiftLeft(..) => Some( "<<".to_string() ),
+            Token::PyShiftRight(..) => Some( ">>".to_string() ),
+            Token::PyBitAnd(..) => Some( "&".to_string() ),
+            Token::PyBitOr(..) => Some( "|".to_string() ),
+            Token::PyBitXor(..) => Some( "^".to_string() ),
+            Token::PyBitInvert(..) => Some( "~".to_string() ),
+            Token::PyColonAssign(..) => Some( ":=".to_string() ),
+            Token::PyLess(..) => Some( "<".to_string() ),
+            Token::PyGreater(..) => Some( ">".to_string() ),
+            Token::PyLessEqual(..) => Some( "<=".to_string() ),
+            Token::PyGreaterEqual(..) => Some( ">=".to_string() ),
+            Token::PyEqual(..) => Some( "==".to_string() ),
+            Token::PyNotEqual(..) => Some( "!=".to_string() ),
+            Token::PyLeftParen(..) => Some( "(".to_string() ),
+            Token::PyLeftBracket(..) => Some( "[".to_string() ),
+            Token::PyLeftCurly(..) => Some( "{".to_string() ),
+            Token::PyRightParen(..) => Some( ")".to_string() ),
+            Token::PyRightBracket(..) => Some( "]".to_string() ),
+            Token::PyRightCurly(..) => Some( "}".to_string() ),
+            Token::PyComa(..) => Some( ",".to_string() ),
+            Token::PyColon(..) => Some( ":".to_string() ),
+            Token::PyDot(..) => Some( ".".to_string() ),
+            Token::PySemiColon(..) => Some( ";".to_string() ),
+            Token::PyAssign(..) => Some( "=".to_string() ),
+            Token::PyArrow(..) => Some( "->".to_string() ),
+            Token::PyPlusAssign(..) => Some( "+=".to_string() ),
+            Token::PyMinusAssign(..) => Some( "-=".to_string() ),
+            Token::PyMulAssign(..) => Some( "*=".to_string() ),
+            Token::PyDivAssign(..) => Some( "/=".to_string() ),
+            Token::PyFloorDivAssign(..) => Some( "//=".to_string() ),
+            Token::PyModuloAssign(..) => Some( "%=".to_string() ),
+            Token::PyMatriceAssign(..) => Some( "@=".to_string() ),
+            Token::PyBitAndAssign(..) => Some( "&=".to_string() ),
+            Token::PyBitOrAssign(..) => Some( "|=".to_string() ),
+            Token::PyBitXorAssign(..) => Some( "^=".to_string() ),
+            Token::PyShiftLeftAssign(..) => Some( "<<=".to_string() ),
+            Token::PyShiftRightAssign(..) => Some( ">>=".to_string() ),
+            Token::PyPowerAssign(..) => Some( "**=".to_string() ),
+            Token::PyElipsis(..) => Some( "...".to_string() ),
+            Token::AtomName(.., txt) |
+            Token::AtomNumber(.., txt) |
+            Token::TypeComment(.., txt) => Some( txt.to_string() ),
+            Token::AtomString(.., txt, prefix) => Some( format!("{}{}", prefix.clone().unwrap_or_default(), txt) ),
+            Token::Newline(..) |
+            Token::Indent(..) |
+            Token::Dedent(..) |
+            Token::EOF(..) |
+            Token::ProbeEnd(..) => None
+        }
+    }
+
+    /// Compares kind of tokens and text of names, numbers, strings and type comments, while
+    /// positions and trivia are ignored.
+    pub fn structural_eq(&self, other: &Token) -> bool {
+        match ( self, other ) {
+            ( Token::AtomName(.., a), Token::AtomName(.., b) ) |
+            ( Token::AtomNumber(.., a), Token::AtomNumber(.., b) ) |
+            ( Token::TypeComment(.., a), Token::TypeComment(.., b) ) => a == b,
+            ( Token::AtomString(.., a, pa), Token::AtomString(.., b, pb) ) => a == b && pa == pb,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other)
+        }
+    }
+
+    /// Hash consistent with 'structural_eq', positions and trivia are ignored.
+    pub fn structural_hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Token::AtomName(.., txt) |
+            Token::AtomNumber(.., txt) |
+            Token::TypeComment(.., txt) => txt.hash(state),
+            Token::AtomString(.., txt, prefix) => {
+                txt.hash(state);
+                prefix.hash(state)
+            },
+            _ => { }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Trivia {
     WhiteSpace(u32, u32, char),
     Newline(u32, u32, char, char),
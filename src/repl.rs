@@ -0,0 +1,200 @@
+use std::io::{ BufRead, Write };
+use std::path::PathBuf;
+use crate::api::{ Mode, parse, check, tokenize_with };
+use crate::diagnostics::Diagnostic;
+use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+use crate::ast::dump::Dumper;
+use crate::bytecode::vm::Session;
+use crate::cli::format_syntax_error;
+
+
+/// History file in the current directory used when no other file is given.
+pub const HISTORY_FILE: &str = ".python_core_history";
+
+/// What the lines typed so far hold, as decided by CPython 'codeop' before compiling them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    Complete,
+    /// More lines are needed to end a block, close brackets or end a triple-quoted string.
+    Incomplete,
+    Invalid(Diagnostic)
+}
+
+/// Settings of an interactive session.
+pub struct ReplOptions {
+    /// Print abstract tree of every statement instead of running it.
+    pub ast: bool,
+    pub optimize: u32,
+    /// File that every complete statement is appended to, none keeps no history.
+    pub history: Option<PathBuf>
+}
+
+/// Read, evaluate and print loop over lines of input.
+pub struct Repl<'o> {
+    options: ReplOptions,
+    session: Option<Session>,
+    out: &'o mut dyn Write,
+    err: &'o mut dyn Write
+}
+
+
+/// Classifies interactive input with the tokenizer in interactive mode and the 'single' start rule.
+/// A syntax error at end of input only means that the statement goes on, a block ends at an empty line.
+pub fn classify(source: &str) -> Input {
+    if let ( _, Some( e ) ) = tokenize_with(source, true) {
+        if e.message.starts_with("MORE DATA") {
+            return Input::Incomplete
+        }
+    }
+    let res = parse(source, Mode::Single);
+    if let Some( d ) = res.diagnostics.into_iter().find( |d| d.is_error() ) {
+        return match d.start as usize >= source.chars().count() {
+            true => Input::Incomplete,
+            _ => Input::Invalid(d)
+        }
+    }
+    match check(source, Mode::Single).into_iter().next() {
+        Some( d ) => Input::Invalid(d),
+        None => Input::Complete
+    }
+}
+
+impl<'o> Repl<'o> {
+    pub fn new(options: ReplOptions, out: &'o mut dyn Write, err: &'o mut dyn Write) -> Repl<'o> {
+        let session = match options.ast {
+            true => None,
+            _ => Some( Session::new("<stdin>", options.optimize) )
+        };
+        Repl { options, session, out, err }
+    }
+
+    /// Reads statements until end of input with '>>> ' and '... ' prompts, returns exit code 0.
+    pub fn run(&mut self, input: &mut dyn BufRead) -> i32 {
+        let mut buffer = String::new();
+        loop {
+            let _ = write!(self.out, "{}", if buffer.is_empty() { ">>> " } else { "... " });
+            let _ = self.out.flush();
+            let mut line = String::new();
+            match input.read_line(&mut line) {
+                Ok( 0 ) | Err( _ ) => break,
+                Ok( _ ) => ()
+            }
+            buffer.push_str(line.trim_end_matches(['\r', '\n']));
+            buffer.push('\n');
+            match classify(&buffer) {
+                Input::Incomplete => continue,
+                Input::Complete => self.execute(&buffer),
+                Input::Invalid(d) => {
+                    let _ = write!(self.err, "{}", format_syntax_error("<stdin>", &buffer, &d));
+                }
+            }
+            buffer.clear()
+        }
+        if !buffer.is_empty() {
+            buffer.push('\n');
+            match classify(&buffer) {
+                Input::Complete => self.execute(&buffer),
+                Input::Invalid(d) => {
+                    let _ = write!(self.err, "{}", format_syntax_error("<stdin>", &buffer, &d));
+                },
+                Input::Incomplete => {
+                    let d = Diagnostic::error(0, 0, "unexpected EOF while parsing");
+                    let _ = write!(self.err, "{}", format_syntax_error("<stdin>", &buffer, &d));
+                }
+            }
+        }
+        let _ = writeln!(self.out);
+        0
+    }
+
+    /// Runs or prints complete statement and adds it to history.
+    fn execute(&mut self, source: &str) {
+        if source.trim().is_empty() {
+            return
+        }
+        self.remember(source);
+        match &self.session {
+            Some( session ) => {
+                let ( output, res ) = session.run(source);
+                let _ = write!(self.out, "{}", output);
+                if let Err( traceback ) = res {
+                    let _ = write!(self.err, "{}", traceback);
+                }
+            },
+            None => {
+                let module = parse(source, Mode::Single).tree.map( |t| PythonCoreLowering::new(source).lower_mod(&t) );
+                if let Some( Ok( m ) ) = module {
+                    let _ = writeln!(self.out, "{}", m.dump(false));
+                }
+            }
+        }
+    }
+
+    fn remember(&mut self, source: &str) {
+        let path = match &self.options.history {
+            Some( p ) => p,
+            None => return
+        };
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path);
+        if let Err( e ) = file.and_then( |mut f| f.write_all(source.trim_end().as_bytes()).and_then( |_| f.write_all(b"\n") ) ) {
+            let _ = writeln!(self.err, "warning: cannot write history to '{}': {}", path.display(), e);
+            self.options.history = None
+        }
+    }
+}
+
+
+// UnitTests for interactive loop //////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::repl::{ Repl, ReplOptions, Input, classify };
+
+
+    fn session(input: &str, ast: bool, history: Option<std::path::PathBuf>) -> ( String, String ) {
+        let ( mut out, mut err ) = ( Vec::new(), Vec::new() );
+        let code = Repl::new(ReplOptions { ast, optimize: 0, history }, &mut out, &mut err).run(&mut input.as_bytes());
+        assert_eq!(code, 0);
+        ( String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap() )
+    }
+
+    #[test]
+    fn repl_classify() {
+        assert_eq!(classify("x = 1\n"), Input::Complete);
+        assert_eq!(classify("\n"), Input::Complete);
+        for source in [ "if a:\n", "if a:\n    pass\n", "(1,\n", "'''abc\n", "x = 1 + \\\n", "def f():\n    return 1\n" ] {
+            assert_eq!(classify(source), Input::Incomplete, "{:?}", source)
+        }
+        assert_eq!(classify("if a:\n    pass\n\n"), Input::Complete);
+        assert!(matches!(classify("x = \n"), Input::Invalid(_)));
+        assert!(matches!(classify("f() = 1\n"), Input::Invalid(_)));
+    }
+
+    #[test]
+    fn repl_evaluates() {
+        let ( out, err ) = session("x = 20\nx + 1\ndef f(n):\n    return n * x\n\nf(2)\n'a' * 2\nNone\n", false, None);
+        assert_eq!(out, ">>> >>> 21\n>>> ... ... >>> 40\n>>> 'aa'\n>>> >>> \n");
+        assert_eq!(err, "");
+        let ( out, err ) = session("1 / 0\nfor i in range(2):\n    i\n\n", false, None);
+        assert_eq!(out, ">>> >>> ... ... 0\n1\n>>> \n");
+        assert_eq!(err, "Traceback (most recent call last):\n  File \"<stdin>\", line 1, in <module>\nZeroDivisionError: division by zero\n");
+    }
+
+    #[test]
+    fn repl_errors_and_tree() {
+        let ( out, err ) = session("1 +\n(1,\n2)\n", false, None);
+        assert_eq!(out, ">>> >>> ... (1, 2)\n>>> \n");
+        assert!(err.starts_with("  File \"<stdin>\", line 1\n    1 +\n"));
+        let ( out, _ ) = session("x = 1\n", true, None);
+        assert!(out.contains("Interactive(body=[Assign(targets=[Name(id='x', ctx=Store())], value=Constant(value=1))])"));
+    }
+
+    #[test]
+    fn repl_history() {
+        let path = std::env::temp_dir().join(format!("python_core_history_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        session("x = 1\n\nif x:\n    pass\n\n", true, Some( path.clone() ));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "x = 1\nif x:\n    pass\n");
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod bigint;
+pub mod value;
+pub mod format;
+pub mod ops;
+pub mod builtins;
+pub mod interpreter;
@@ -0,0 +1,626 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{ Hash, Hasher };
+use std::rc::Rc;
+
+
+/// Integer without size limit. Values that fit in 64 bits are kept inline, the big form is only
+/// used for values outside that range, which keeps equal values equal in representation.
+#[derive(Clone, Debug)]
+pub enum Int {
+    Small(i64),
+    Big(Rc<BigInt>)
+}
+
+/// Sign and magnitude, the magnitude is kept in base 2^32 with least significant digit first and
+/// without leading zero digits.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>
+}
+
+
+fn trim(mut v: Vec<u32>) -> Vec<u32> {
+    while v.last() == Some( &0 ) {
+        v.pop();
+    }
+    v
+}
+
+fn mag_cmp(a: &[u32], b: &[u32]) -> Ordering {
+    if a.len() != b.len() {
+        return a.len().cmp(&b.len())
+    }
+    for i in ( 0 .. a.len() ).rev() {
+        if a[i] != b[i] {
+            return a[i].cmp(&b[i])
+        }
+    }
+    Ordering::Equal
+}
+
+fn mag_add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let ( a, b ) = if a.len() >= b.len() { ( a, b ) } else { ( b, a ) };
+    let mut res = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0u64;
+    for ( i, x ) in a.iter().enumerate() {
+        let s = *x as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        res.push(s as u32);
+        carry = s >> 32
+    }
+    res.push(carry as u32);
+    trim(res)
+}
+
+/// Difference of magnitudes where 'a' is not smaller than 'b'.
+fn mag_sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for ( i, x ) in a.iter().enumerate() {
+        let mut d = *x as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if d < 0 {
+            d += 1 << 32;
+            borrow = 1
+        }
+        res.push(d as u32)
+    }
+    trim(res)
+}
+
+fn mag_mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new()
+    }
+    let mut res = vec![ 0u32; a.len() + b.len() ];
+    for ( i, x ) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for ( j, y ) in b.iter().enumerate() {
+            let t = *x as u64 * *y as u64 + res[i + j] as u64 + carry;
+            res[i + j] = t as u32;
+            carry = t >> 32
+        }
+        res[i + b.len()] = carry as u32
+    }
+    trim(res)
+}
+
+/// Shifts magnitude left by less than 32 bits, result has one more digit than input.
+fn shl_bits(v: &[u32], s: u32) -> Vec<u32> {
+    let mut res = Vec::with_capacity(v.len() + 1);
+    let mut carry = 0u32;
+    for d in v {
+        res.push(( d << s ) | carry);
+        carry = if s == 0 { 0 } else { d >> ( 32 - s ) }
+    }
+    res.push(carry);
+    res
+}
+
+fn shr_bits(v: &[u32], s: u32) -> Vec<u32> {
+    let mut res = vec![ 0u32; v.len() ];
+    for i in 0 .. v.len() {
+        let high = if s == 0 || i + 1 == v.len() { 0 } else { v[i + 1] << ( 32 - s ) };
+        res[i] = ( v[i] >> s ) | high
+    }
+    trim(res)
+}
+
+fn mag_divmod_small(a: &[u32], d: u32) -> ( Vec<u32>, u32 ) {
+    let mut q = vec![ 0u32; a.len() ];
+    let mut r = 0u64;
+    for i in ( 0 .. a.len() ).rev() {
+        let cur = ( r << 32 ) | a[i] as u64;
+        q[i] = ( cur / d as u64 ) as u32;
+        r = cur % d as u64
+    }
+    ( trim(q), r as u32 )
+}
+
+/// Quotient and remainder of magnitudes by Knuth's algorithm D, divisor is not zero.
+fn mag_divmod(a: &[u32], b: &[u32]) -> ( Vec<u32>, Vec<u32> ) {
+    if mag_cmp(a, b) == Ordering::Less {
+        return ( Vec::new(), a.to_vec() )
+    }
+    if b.len() == 1 {
+        let ( q, r ) = mag_divmod_small(a, b[0]);
+        return ( q, trim(vec![ r ]) )
+    }
+    let s = b[b.len() - 1].leading_zeros();
+    let n = b.len();
+    let b = &shl_bits(b, s)[.. n];
+    let mut a = shl_bits(a, s);
+    let m = a.len() - n;
+    let mut q = vec![ 0u32; m ];
+    let base = 1u64 << 32;
+    for j in ( 0 .. m ).rev() {
+        let num = ( ( a[j + n] as u64 ) << 32 ) | a[j + n - 1] as u64;
+        let mut qhat = num / b[n - 1] as u64;
+        let mut rhat = num % b[n - 1] as u64;
+        while qhat >= base || qhat * b[n - 2] as u64 > ( ( rhat << 32 ) | a[j + n - 2] as u64 ) {
+            qhat -= 1;
+            rhat += b[n - 1] as u64;
+            if rhat >= base {
+                break
+            }
+        }
+        let mut borrow = 0i64;
+        let mut carry = 0u64;
+        for i in 0 .. n {
+            let p = qhat * b[i] as u64 + carry;
+            carry = p >> 32;
+            let t = a[i + j] as i64 - borrow - ( p & 0xffff_ffff ) as i64;
+            a[i + j] = t as u32;
+            borrow = if t < 0 { 1 } else { 0 }
+        }
+        let t = a[j + n] as i64 - borrow - carry as i64;
+        a[j + n] = t as u32;
+        if t < 0 {
+            qhat -= 1;
+            let mut c = 0u64;
+            for i in 0 .. n {
+                let s = a[i + j] as u64 + b[i] as u64 + c;
+                a[i + j] = s as u32;
+                c = s >> 32
+            }
+            a[j + n] = a[j + n].wrapping_add(c as u32)
+        }
+        q[j] = qhat as u32
+    }
+    ( trim(q), shr_bits(&a[.. n], s) )
+}
+
+/// Two's complement form of signed magnitude in 'len' digits, enough to hold the sign bit.
+fn to_twos(negative: bool, digits: &[u32], len: usize) -> Vec<u32> {
+    let mut res : Vec<u32> = ( 0 .. len ).map( |i| *digits.get(i).unwrap_or(&0) ).collect();
+    if negative {
+        let mut carry = 1u64;
+        for d in res.iter_mut() {
+            let s = ( !*d ) as u64 + carry;
+            *d = s as u32;
+            carry = s >> 32
+        }
+    }
+    res
+}
+
+fn from_twos(mut v: Vec<u32>) -> Int {
+    let negative = v.last().map( |d| d >> 31 == 1 ).unwrap_or(false);
+    if negative {
+        let mut carry = 1u64;
+        for d in v.iter_mut() {
+            let s = ( !*d ) as u64 + carry;
+            *d = s as u32;
+            carry = s >> 32
+        }
+    }
+    Int::from_parts(negative, trim(v))
+}
+
+
+impl Int {
+    pub fn zero() -> Int {
+        Int::Small(0)
+    }
+
+    fn from_parts(negative: bool, digits: Vec<u32>) -> Int {
+        let digits = trim(digits);
+        if digits.len() <= 2 {
+            let mag = digits.first().cloned().unwrap_or(0) as u64 | ( *digits.get(1).unwrap_or(&0) as u64 ) << 32;
+            let value = if negative { -( mag as i128 ) } else { mag as i128 };
+            if let Ok( v ) = i64::try_from(value) {
+                return Int::Small(v)
+            }
+        }
+        Int::Big( Rc::new( BigInt { negative: negative && !digits.is_empty(), digits } ) )
+    }
+
+    fn from_i128(v: i128) -> Int {
+        match i64::try_from(v) {
+            Ok( v ) => Int::Small(v),
+            Err( _ ) => {
+                let mag = v.unsigned_abs();
+                Int::from_parts(v < 0, vec![ mag as u32, ( mag >> 32 ) as u32, ( mag >> 64 ) as u32, ( mag >> 96 ) as u32 ])
+            }
+        }
+    }
+
+    /// Sign and magnitude digits of any integer.
+    fn parts(&self) -> ( bool, Vec<u32> ) {
+        match self {
+            Int::Small(v) => {
+                let mag = v.unsigned_abs();
+                ( *v < 0, trim(vec![ mag as u32, ( mag >> 32 ) as u32 ]) )
+            },
+            Int::Big(b) => ( b.negative, b.digits.clone() )
+        }
+    }
+
+    /// Parses digits in radix from 2 to 36 with optional sign, underscores are not accepted here.
+    pub fn parse(text: &str, radix: u32) -> Option<Int> {
+        let ( negative, digits ) = match text.strip_prefix('-') {
+            Some( rest ) => ( true, rest ),
+            None => ( false, text.strip_prefix('+').unwrap_or(text) )
+        };
+        if digits.is_empty() || digits.starts_with(['+', '-']) {
+            return None
+        }
+        if let Ok( v ) = i64::from_str_radix(digits, radix) {
+            return Some( Int::Small(if negative { -v } else { v }) )
+        }
+        let mut mag : Vec<u32> = Vec::new();
+        for c in digits.chars() {
+            let d = c.to_digit(radix)?;
+            let mut carry = d as u64;
+            for x in mag.iter_mut() {
+                let t = *x as u64 * radix as u64 + carry;
+                *x = t as u32;
+                carry = t >> 32
+            }
+            if carry > 0 {
+                mag.push(carry as u32)
+            }
+        }
+        Some( Int::from_parts(negative, mag) )
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        match self {
+            Int::Small(v) => Some( *v ),
+            Int::Big(_) => None
+        }
+    }
+
+    /// Nearest float, infinite when the integer is out of range of floats.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Int::Small(v) => *v as f64,
+            Int::Big(b) => {
+                let mut res = 0.0f64;
+                for d in b.digits.iter().rev() {
+                    res = res * 4294967296.0 + *d as f64
+                }
+                if b.negative { -res } else { res }
+            }
+        }
+    }
+
+    /// Integer part of finite float.
+    pub fn from_f64(v: f64) -> Int {
+        let t = v.trunc();
+        if t.abs() < 9.0e18 {
+            return Int::Small(t as i64)
+        }
+        let bits = t.abs().to_bits();
+        let exponent = ( ( bits >> 52 ) & 0x7ff ) as i64 - 1075;
+        let mantissa = ( bits & ( ( 1u64 << 52 ) - 1 ) ) | ( 1u64 << 52 );
+        let res = Int::Small(mantissa as i64).shl(exponent as u64);
+        if t < 0.0 { res.neg() } else { res }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        matches!(self, Int::Small(0))
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Int::Small(v) => *v < 0,
+            Int::Big(b) => b.negative
+        }
+    }
+
+    pub fn add(&self, other: &Int) -> Int {
+        if let ( Int::Small(a), Int::Small(b) ) = ( self, other ) {
+            return Int::from_i128(*a as i128 + *b as i128)
+        }
+        let ( an, a ) = self.parts();
+        let ( bn, b ) = other.parts();
+        if an == bn {
+            return Int::from_parts(an, mag_add(&a, &b))
+        }
+        match mag_cmp(&a, &b) {
+            Ordering::Less => Int::from_parts(bn, mag_sub(&b, &a)),
+            _ => Int::from_parts(an, mag_sub(&a, &b))
+        }
+    }
+
+    pub fn sub(&self, other: &Int) -> Int {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Int) -> Int {
+        if let ( Int::Small(a), Int::Small(b) ) = ( self, other ) {
+            return Int::from_i128(*a as i128 * *b as i128)
+        }
+        let ( an, a ) = self.parts();
+        let ( bn, b ) = other.parts();
+        Int::from_parts(an != bn, mag_mul(&a, &b))
+    }
+
+    /// Floor division and modulo with the sign of the divisor, None when dividing by zero.
+    pub fn divmod(&self, other: &Int) -> Option<( Int, Int )> {
+        if other.is_zero() {
+            return None
+        }
+        if let ( Int::Small(a), Int::Small(b) ) = ( self, other ) {
+            let ( a, b ) = ( *a as i128, *b as i128 );
+            let ( q, r ) = ( a / b, a % b );
+            if r != 0 && ( r < 0 ) != ( b < 0 ) {
+                return Some( ( Int::from_i128(q - 1), Int::from_i128(r + b) ) )
+            }
+            return Some( ( Int::from_i128(q), Int::from_i128(r) ) )
+        }
+        let ( an, a ) = self.parts();
+        let ( bn, b ) = other.parts();
+        let ( q, r ) = mag_divmod(&a, &b);
+        let q = Int::from_parts(an != bn, q);
+        let r = Int::from_parts(an, r);
+        if an != bn && !r.is_zero() {
+            Some( ( q.sub(&Int::Small(1)), r.add(other) ) )
+        } else {
+            Some( ( q, r ) )
+        }
+    }
+
+    pub fn pow(&self, mut exponent: u64) -> Int {
+        let mut base = self.clone();
+        let mut res = Int::Small(1);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                res = res.mul(&base)
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.mul(&base)
+            }
+        }
+        res
+    }
+
+    pub fn neg(&self) -> Int {
+        match self {
+            Int::Small(v) => Int::from_i128(-( *v as i128 )),
+            Int::Big(b) => Int::from_parts(!b.negative, b.digits.clone())
+        }
+    }
+
+    pub fn abs(&self) -> Int {
+        if self.is_negative() { self.neg() } else { self.clone() }
+    }
+
+    pub fn invert(&self) -> Int {
+        self.neg().sub(&Int::Small(1))
+    }
+
+    pub fn shl(&self, n: u64) -> Int {
+        if let Int::Small(v) = self {
+            if n < 64 {
+                return Int::from_i128(( *v as i128 ) << n)
+            }
+        }
+        let ( negative, digits ) = self.parts();
+        let mut res = vec![ 0u32; ( n / 32 ) as usize ];
+        res.extend(shl_bits(&digits, ( n % 32 ) as u32));
+        Int::from_parts(negative, res)
+    }
+
+    /// Arithmetic right shift, rounding towards negative infinity.
+    pub fn shr(&self, n: u64) -> Int {
+        if let Int::Small(v) = self {
+            return Int::Small(if n >= 64 { if *v < 0 { -1 } else { 0 } } else { v >> n })
+        }
+        if self.is_negative() {
+            return self.neg().sub(&Int::Small(1)).shr(n).add(&Int::Small(1)).neg()
+        }
+        let ( _, digits ) = self.parts();
+        let skip = ( n / 32 ) as usize;
+        if skip >= digits.len() {
+            return Int::zero()
+        }
+        Int::from_parts(false, shr_bits(&digits[skip ..], ( n % 32 ) as u32))
+    }
+
+    fn bitwise(&self, other: &Int, op: fn(u32, u32) -> u32) -> Int {
+        let ( an, a ) = self.parts();
+        let ( bn, b ) = other.parts();
+        let len = a.len().max(b.len()) + 1;
+        let x = to_twos(an, &a, len);
+        let y = to_twos(bn, &b, len);
+        from_twos(x.iter().zip(y.iter()).map( |( p, q )| op(*p, *q) ).collect())
+    }
+
+    pub fn bitand(&self, other: &Int) -> Int {
+        match ( self, other ) {
+            ( Int::Small(a), Int::Small(b) ) => Int::Small(a & b),
+            _ => self.bitwise(other, |a, b| a & b)
+        }
+    }
+
+    pub fn bitor(&self, other: &Int) -> Int {
+        match ( self, other ) {
+            ( Int::Small(a), Int::Small(b) ) => Int::Small(a | b),
+            _ => self.bitwise(other, |a, b| a | b)
+        }
+    }
+
+    pub fn bitxor(&self, other: &Int) -> Int {
+        match ( self, other ) {
+            ( Int::Small(a), Int::Small(b) ) => Int::Small(a ^ b),
+            _ => self.bitwise(other, |a, b| a ^ b)
+        }
+    }
+
+    /// Digits in radix from 2 to 36 with a leading '-' for negative numbers and no prefix.
+    pub fn to_string_radix(&self, radix: u32) -> String {
+        let ( negative, mut digits ) = self.parts();
+        if digits.is_empty() {
+            return "0".to_string()
+        }
+        let mut chars = Vec::new();
+        while !digits.is_empty() {
+            let ( q, r ) = mag_divmod_small(&digits, radix);
+            chars.push(std::char::from_digit(r, radix).unwrap());
+            digits = q
+        }
+        if negative {
+            chars.push('-')
+        }
+        chars.iter().rev().collect()
+    }
+
+    pub fn bit_length(&self) -> u64 {
+        let ( _, digits ) = self.parts();
+        match digits.last() {
+            Some( d ) => ( digits.len() as u64 - 1 ) * 32 + ( 32 - d.leading_zeros() ) as u64,
+            None => 0
+        }
+    }
+}
+
+impl From<i64> for Int {
+    fn from(v: i64) -> Int {
+        Int::Small(v)
+    }
+}
+
+impl fmt::Display for Int {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Int::Small(v) => write!(f, "{}", v),
+            Int::Big(b) => {
+                let mut parts = Vec::new();
+                let mut digits = b.digits.clone();
+                while !digits.is_empty() {
+                    let ( q, r ) = mag_divmod_small(&digits, 1_000_000_000);
+                    parts.push(r);
+                    digits = q
+                }
+                let mut res = String::new();
+                if b.negative {
+                    res.push('-')
+                }
+                res.push_str(&parts.last().unwrap().to_string());
+                for p in parts.iter().rev().skip(1) {
+                    res.push_str(&format!("{:09}", p))
+                }
+                f.pad(&res)
+            }
+        }
+    }
+}
+
+impl PartialEq for Int {
+    fn eq(&self, other: &Int) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Int {}
+
+impl PartialOrd for Int {
+    fn partial_cmp(&self, other: &Int) -> Option<Ordering> {
+        Some( self.cmp(other) )
+    }
+}
+
+impl Ord for Int {
+    fn cmp(&self, other: &Int) -> Ordering {
+        match ( self, other ) {
+            ( Int::Small(a), Int::Small(b) ) => a.cmp(b),
+            _ => {
+                let ( an, a ) = self.parts();
+                let ( bn, b ) = other.parts();
+                match ( an, bn ) {
+                    ( false, true ) => Ordering::Greater,
+                    ( true, false ) => Ordering::Less,
+                    ( false, false ) => mag_cmp(&a, &b),
+                    ( true, true ) => mag_cmp(&b, &a)
+                }
+            }
+        }
+    }
+}
+
+impl Hash for Int {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Int::Small(v) => v.hash(state),
+            Int::Big(b) => b.hash(state)
+        }
+    }
+}
+
+
+// UnitTests for integers of any size //////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::bigint::Int;
+
+
+    fn int(text: &str) -> Int {
+        Int::parse(text, 10).unwrap()
+    }
+
+    #[test]
+    fn bigint_parse_and_display() {
+        assert_eq!(int("12345678901234567890123456789").to_string(), "12345678901234567890123456789");
+        assert_eq!(int("-98765432109876543210").to_string(), "-98765432109876543210");
+        assert_eq!(int("-9223372036854775808").to_i64(), Some( i64::MIN ));
+        assert!(int("9223372036854775808").to_i64().is_none());
+        assert_eq!(Int::parse("ff", 16).unwrap().to_string(), "255");
+        assert_eq!(int("255").to_string_radix(2), "11111111");
+        assert_eq!(int("-340282366920938463463374607431768211456").to_string_radix(16), "-100000000000000000000000000000000");
+        assert!(Int::parse("12a", 10).is_none())
+    }
+
+    #[test]
+    fn bigint_arithmetic() {
+        let a = int("123456789012345678901234567890");
+        let b = int("987654321098765432109876543210");
+        assert_eq!(a.add(&b).to_string(), "1111111110111111111011111111100");
+        assert_eq!(a.sub(&b).to_string(), "-864197532086419753208641975320");
+        assert_eq!(a.mul(&b).to_string(), "121932631137021795226185032733622923332237463801111263526900");
+        assert_eq!(int("2").pow(100).to_string(), "1267650600228229401496703205376");
+        assert_eq!(int("9223372036854775807").add(&int("1")).sub(&int("1")), int("9223372036854775807"));
+        assert!(matches!(int("9223372036854775807").add(&int("1")).sub(&int("1")), Int::Small(_)))
+    }
+
+    #[test]
+    fn bigint_division_rounds_down() {
+        let ( q, r ) = b_divmod("121932631137021795226185032733622923332237463801111263526901", "987654321098765432109876543210");
+        assert_eq!(( q.as_str(), r.as_str() ), ( "123456789012345678901234567890", "1" ));
+        let ( q, r ) = b_divmod("-100000000000000000000000", "7");
+        assert_eq!(( q.as_str(), r.as_str() ), ( "-14285714285714285714286", "2" ));
+        let ( q, r ) = b_divmod("100000000000000000000000", "-7");
+        assert_eq!(( q.as_str(), r.as_str() ), ( "-14285714285714285714286", "-2" ));
+        let ( q, r ) = b_divmod("-7", "2");
+        assert_eq!(( q.as_str(), r.as_str() ), ( "-4", "1" ));
+        assert!(int("5").divmod(&int("0")).is_none())
+    }
+
+    fn b_divmod(a: &str, b: &str) -> ( String, String ) {
+        let ( q, r ) = int(a).divmod(&int(b)).unwrap();
+        ( q.to_string(), r.to_string() )
+    }
+
+    #[test]
+    fn bigint_bitwise_and_shifts() {
+        let a = int("-340282366920938463463374607431768211457");
+        assert_eq!(a.bitand(&int("255")).to_string(), "255");
+        assert_eq!(a.bitor(&int("1")).to_string(), "-340282366920938463463374607431768211457");
+        assert_eq!(int("1").shl(100).shr(98).to_string(), "4");
+        assert_eq!(int("-1").shl(100).shr(200).to_string(), "-1");
+        assert_eq!(int("-5").shr(1).to_string(), "-3");
+        assert_eq!(int("12345").invert().to_string(), "-12346");
+        assert_eq!(int("1").shl(70).bit_length(), 71)
+    }
+
+    #[test]
+    fn bigint_floats() {
+        assert_eq!(int("1").shl(80).to_f64(), 2f64.powi(80));
+        assert_eq!(Int::from_f64(1e20).to_string(), "100000000000000000000");
+        assert_eq!(Int::from_f64(-2.5).to_string(), "-2")
+    }
+}
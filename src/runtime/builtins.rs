@@ -0,0 +1,2162 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::ast::nodes::Operator;
+use crate::runtime::bigint::Int;
+use crate::runtime::value::{ Value, RunResult, Runtime, Builtins, BuiltinFn, Class, TypeKind, Iter, ViewKind, Dict, Range, Resume, Step };
+use crate::runtime::ops::{ throw, error, new_exception, type_name, class_of, is_instance, exception_args, call, truthy, repr, to_str,
+                           binary_op, equal, less, hash_key, to_index, get_attr, set_attr, del_attr, get_item, iter, next,
+                           collect, len, dict_update, set_from, call_special, is_exception };
+use crate::runtime::format::{ format_value };
+
+
+type Kwargs<'a> = Vec<( String, Value<'a> )>;
+
+/// Builtin exceptions with their base, in an order where bases come first.
+const EXCEPTIONS: &[( &str, &str )] = &[
+    ( "BaseException", "" ),
+    ( "SystemExit", "BaseException" ),
+    ( "KeyboardInterrupt", "BaseException" ),
+    ( "GeneratorExit", "BaseException" ),
+    ( "Exception", "BaseException" ),
+    ( "StopIteration", "Exception" ),
+    ( "ArithmeticError", "Exception" ),
+    ( "OverflowError", "ArithmeticError" ),
+    ( "ZeroDivisionError", "ArithmeticError" ),
+    ( "AssertionError", "Exception" ),
+    ( "AttributeError", "Exception" ),
+    ( "ImportError", "Exception" ),
+    ( "ModuleNotFoundError", "ImportError" ),
+    ( "LookupError", "Exception" ),
+    ( "IndexError", "LookupError" ),
+    ( "KeyError", "LookupError" ),
+    ( "MemoryError", "Exception" ),
+    ( "NameError", "Exception" ),
+    ( "UnboundLocalError", "NameError" ),
+    ( "RuntimeError", "Exception" ),
+    ( "NotImplementedError", "RuntimeError" ),
+    ( "RecursionError", "RuntimeError" ),
+    ( "SyntaxError", "Exception" ),
+    ( "TypeError", "Exception" ),
+    ( "ValueError", "Exception" ),
+    ( "UnicodeError", "ValueError" )
+];
+
+
+impl<'a> Builtins<'a> {
+    pub fn new() -> Builtins<'a> {
+        let object = Rc::new( Class::new("object", None, TypeKind::Object, None) );
+        let mut types = HashMap::new();
+        types.insert(TypeKind::Object, object.clone());
+        let int = Rc::new( Class::new("int", Some( object.clone() ), TypeKind::Int, Some( builtin_int )) );
+        types.insert(TypeKind::Int, int.clone());
+        types.insert(TypeKind::Bool, Rc::new( Class::new("bool", Some( int ), TypeKind::Bool, Some( builtin_bool )) ));
+        let simple : &[( &str, TypeKind, Option<BuiltinFn<'a>> )] = &[
+            ( "type", TypeKind::Type, Some( builtin_type ) ),
+            ( "NoneType", TypeKind::NoneType, Some( builtin_none ) ),
+            ( "ellipsis", TypeKind::Ellipsis, None ),
+            ( "NotImplementedType", TypeKind::NotImplemented, None ),
+            ( "float", TypeKind::Float, Some( builtin_float ) ),
+            ( "str", TypeKind::Str, Some( builtin_str ) ),
+            ( "bytes", TypeKind::Bytes, Some( builtin_bytes ) ),
+            ( "list", TypeKind::List, Some( builtin_list ) ),
+            ( "tuple", TypeKind::Tuple, Some( builtin_tuple ) ),
+            ( "dict", TypeKind::Dict, Some( builtin_dict ) ),
+            ( "set", TypeKind::Set, Some( builtin_set ) ),
+            ( "dict_view", TypeKind::DictView, None ),
+            ( "range", TypeKind::Range, Some( builtin_range ) ),
+            ( "slice", TypeKind::Slice, Some( builtin_slice ) ),
+            ( "function", TypeKind::Function, None ),
+            ( "builtin_function_or_method", TypeKind::Builtin, None ),
+            ( "method", TypeKind::Method, None ),
+            ( "staticmethod", TypeKind::StaticMethod, Some( builtin_staticmethod ) ),
+            ( "classmethod", TypeKind::ClassMethod, Some( builtin_classmethod ) ),
+            ( "property", TypeKind::Property, Some( builtin_property ) ),
+            ( "super", TypeKind::Super, Some( builtin_super ) ),
+            ( "iterator", TypeKind::Iterator, None ),
+            ( "generator", TypeKind::Generator, None ),
+            ( "cell", TypeKind::Cell, None ),
+            ( "code", TypeKind::Code, None )
+        ];
+        for ( name, kind, new ) in simple {
+            types.insert(*kind, Rc::new( Class::new(name, Some( object.clone() ), *kind, *new) ));
+        }
+        let mut exceptions : HashMap<&'static str, Rc<Class<'a>>> = HashMap::new();
+        for ( name, base ) in EXCEPTIONS {
+            let base = if base.is_empty() { object.clone() } else { exceptions[base].clone() };
+            exceptions.insert(name, Rc::new( Class::new(name, Some( base ), TypeKind::Exception, None) ));
+        }
+        let mut namespace = HashMap::new();
+        for class in types.values().chain(exceptions.values()) {
+            if !matches!(class.kind, TypeKind::NoneType | TypeKind::Ellipsis | TypeKind::NotImplemented | TypeKind::DictView |
+                                     TypeKind::Function | TypeKind::Builtin | TypeKind::Method | TypeKind::Iterator | TypeKind::Generator |
+                                     TypeKind::Cell | TypeKind::Code) {
+                namespace.insert(class.name.clone(), Value::Class( class.clone() ));
+            }
+        }
+        let functions : &[( &'static str, BuiltinFn<'a> )] = &[
+            ( "print", builtin_print ),
+            ( "len", builtin_len ),
+            ( "isinstance", builtin_isinstance ),
+            ( "issubclass", builtin_issubclass ),
+            ( "repr", builtin_repr ),
+            ( "abs", builtin_abs ),
+            ( "min", builtin_min ),
+            ( "max", builtin_max ),
+            ( "sum", builtin_sum ),
+            ( "iter", builtin_iter ),
+            ( "next", builtin_next ),
+            ( "enumerate", builtin_enumerate ),
+            ( "zip", builtin_zip ),
+            ( "map", builtin_map ),
+            ( "filter", builtin_filter ),
+            ( "reversed", builtin_reversed ),
+            ( "sorted", builtin_sorted ),
+            ( "any", builtin_any ),
+            ( "all", builtin_all ),
+            ( "hash", builtin_hash ),
+            ( "id", builtin_id ),
+            ( "chr", builtin_chr ),
+            ( "ord", builtin_ord ),
+            ( "round", builtin_round ),
+            ( "divmod", builtin_divmod ),
+            ( "pow", builtin_pow ),
+            ( "hex", builtin_hex ),
+            ( "oct", builtin_oct ),
+            ( "bin", builtin_bin ),
+            ( "getattr", builtin_getattr ),
+            ( "setattr", builtin_setattr ),
+            ( "hasattr", builtin_hasattr ),
+            ( "delattr", builtin_delattr ),
+            ( "callable", builtin_callable ),
+            ( "format", builtin_format )
+        ];
+        for ( name, func ) in functions {
+            namespace.insert(name.to_string(), Value::builtin(name, *func));
+        }
+        namespace.insert("None".to_string(), Value::None);
+        namespace.insert("Ellipsis".to_string(), Value::Ellipsis);
+        namespace.insert("NotImplemented".to_string(), Value::NotImplemented);
+        Builtins { types, exceptions, namespace }
+    }
+}
+
+impl Default for Builtins<'_> {
+    fn default() -> Self {
+        Builtins::new()
+    }
+}
+
+/// Checks the number of positional arguments of builtin.
+fn arity<'a>(rt: &dyn Runtime<'a>, name: &str, args: &[Value<'a>], min: usize, max: usize) -> Result<(), Value<'a>> {
+    let n = args.len();
+    if n >= min && n <= max {
+        return Ok(())
+    }
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    let message = if min == max {
+        match min {
+            0 => format!("{}() takes no arguments ({} given)", name, n),
+            1 => format!("{}() takes exactly one argument ({} given)", name, n),
+            _ => format!("{} expected {} arguments, got {}", name, min, n)
+        }
+    } else if n < min {
+        format!("{} expected at least {} argument{}, got {}", name, min, plural(min), n)
+    } else {
+        format!("{} expected at most {} argument{}, got {}", name, max, plural(max), n)
+    };
+    throw(rt, "TypeError", message)
+}
+
+fn no_kwargs<'a>(rt: &dyn Runtime<'a>, name: &str, kwargs: &Kwargs<'a>) -> Result<(), Value<'a>> {
+    if kwargs.is_empty() {
+        Ok(())
+    } else {
+        throw(rt, "TypeError", format!("{}() takes no keyword arguments", name))
+    }
+}
+
+/// Values of the keyword arguments builtin accepts, in the order of 'names'.
+fn keywords<'a>(rt: &dyn Runtime<'a>, name: &str, kwargs: Kwargs<'a>, names: &[&str]) -> Result<Vec<Option<Value<'a>>>, Value<'a>> {
+    let mut res = vec![ None; names.len() ];
+    for ( key, value ) in kwargs {
+        match names.iter().position( |n| *n == key ) {
+            Some( i ) => res[i] = Some( value ),
+            None => return throw(rt, "TypeError", format!("'{}' is an invalid keyword argument for {}()", key, name))
+        }
+    }
+    Ok( res )
+}
+
+fn receiver_error<'a, T>(rt: &dyn Runtime<'a>, kind: &str, args: &[Value<'a>]) -> Result<T, Value<'a>> {
+    match args.first() {
+        Some( v ) => throw(rt, "TypeError", format!("descriptor requires a '{}' object but received a '{}'", kind, type_name(rt, v))),
+        None => throw(rt, "TypeError", format!("unbound method of '{}' objects needs an argument", kind))
+    }
+}
+
+fn recv_str<'a>(rt: &dyn Runtime<'a>, args: &[Value<'a>]) -> Result<Rc<str>, Value<'a>> {
+    match args.first() {
+        Some( Value::Str(s) ) => Ok( s.clone() ),
+        _ => receiver_error(rt, "str", args)
+    }
+}
+
+fn recv_list<'a>(rt: &dyn Runtime<'a>, args: &[Value<'a>]) -> Result<Rc<RefCell<Vec<Value<'a>>>>, Value<'a>> {
+    match args.first() {
+        Some( Value::List(l) ) => Ok( l.clone() ),
+        _ => receiver_error(rt, "list", args)
+    }
+}
+
+fn recv_dict<'a>(rt: &dyn Runtime<'a>, args: &[Value<'a>]) -> Result<Rc<RefCell<Dict<'a>>>, Value<'a>> {
+    match args.first() {
+        Some( Value::Dict(d) ) => Ok( d.clone() ),
+        _ => receiver_error(rt, "dict", args)
+    }
+}
+
+fn recv_set<'a>(rt: &dyn Runtime<'a>, args: &[Value<'a>]) -> Result<Rc<RefCell<Dict<'a>>>, Value<'a>> {
+    match args.first() {
+        Some( Value::Set(d) ) => Ok( d.clone() ),
+        _ => receiver_error(rt, "set", args)
+    }
+}
+
+fn expect_str<'a>(rt: &dyn Runtime<'a>, what: &str, value: &Value<'a>) -> Result<Rc<str>, Value<'a>> {
+    match value {
+        Value::Str(s) => Ok( s.clone() ),
+        _ => throw(rt, "TypeError", format!("{} must be str, not {}", what, type_name(rt, value)))
+    }
+}
+
+fn int_value<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>) -> Result<Int, Value<'a>> {
+    match value {
+        Value::Int(i) => Ok( i.clone() ),
+        Value::Bool(b) => Ok( Int::from(*b as i64) ),
+        _ => Ok( Int::from(to_index(rt, value)?) )
+    }
+}
+
+
+// Types ///////////////////////////////////////////////////////////////////////////////////////////
+
+fn builtin_type<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "type", &kwargs)?;
+    match args.len() {
+        1 => Ok( Value::Class( class_of(rt, &args[0]) ) ),
+        3 => {
+            let name = expect_str(rt, "type.__new__() argument 1", &args[0])?;
+            let bases = match &args[1] {
+                Value::Tuple(t) => t.to_vec(),
+                _ => return throw(rt, "TypeError", "type.__new__() argument 2 must be tuple")
+            };
+            let base = match bases.first() {
+                Some( Value::Class(c) ) => c.clone(),
+                Some( _ ) => return throw(rt, "TypeError", "bases must be types"),
+                None => rt.builtins().class(TypeKind::Object)
+            };
+            let namespace = match &args[2] {
+                Value::Dict(d) => d.borrow().items(),
+                _ => return throw(rt, "TypeError", "type.__new__() argument 3 must be dict")
+            };
+            let mut dict = HashMap::new();
+            dict.insert("__module__".to_string(), Value::str("__main__"));
+            for ( k, v ) in namespace {
+                dict.insert(to_str(rt, &k)?, v);
+            }
+            new_class(rt, &name, base, dict)
+        },
+        _ => throw(rt, "TypeError", "type() takes 1 or 3 arguments")
+    }
+}
+
+/// Creates class deriving from base, which must have a layout user classes can share.
+pub fn new_class<'a>(rt: &mut dyn Runtime<'a>, name: &str, base: Rc<Class<'a>>, dict: HashMap<String, Value<'a>>) -> RunResult<'a> {
+    if !matches!(base.kind, TypeKind::Object | TypeKind::Exception) {
+        return throw(rt, "TypeError", format!("deriving from builtin type '{}' is not supported", base.name))
+    }
+    let class = Class::new(name, Some( base.clone() ), base.kind, None);
+    *class.dict.borrow_mut() = dict;
+    Ok( Value::Class( Rc::new(class) ) )
+}
+
+fn builtin_none<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "NoneType", &kwargs)?;
+    arity(rt, "NoneType", &args, 0, 0)?;
+    Ok( Value::None )
+}
+
+fn builtin_bool<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "bool", &kwargs)?;
+    arity(rt, "bool", &args, 0, 1)?;
+    match args.first() {
+        Some( v ) => Ok( Value::Bool(truthy(rt, v)?) ),
+        None => Ok( Value::Bool(false) )
+    }
+}
+
+/// Parses integer literal as 'int' accepts it, with optional sign, underscores between digits and
+/// a prefix matching base, where base 0 takes the base from the prefix.
+pub fn parse_int(text: &str, base: u32) -> Option<Int> {
+    let text = text.trim();
+    let ( negative, rest ) = match text.strip_prefix('-') {
+        Some( r ) => ( true, r ),
+        None => ( false, text.strip_prefix('+').unwrap_or(text) )
+    };
+    let lower = rest.to_ascii_lowercase();
+    let ( base, digits ) = match ( base, lower.get(.. 2) ) {
+        ( 0 | 16, Some( "0x" ) ) => ( 16, &rest[2 ..] ),
+        ( 0 | 8, Some( "0o" ) ) => ( 8, &rest[2 ..] ),
+        ( 0 | 2, Some( "0b" ) ) => ( 2, &rest[2 ..] ),
+        ( 0, _ ) => {
+            if rest.len() > 1 && rest.starts_with('0') && rest.chars().any( |c| c != '0' && c != '_' ) {
+                return None
+            }
+            ( 10, rest )
+        },
+        ( b, _ ) => ( b, rest )
+    };
+    let digits = digits.strip_prefix('_').filter( |_| digits.len() != rest.len() ).unwrap_or(digits);
+    if digits.is_empty() || digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+        return None
+    }
+    let clean : String = digits.chars().filter( |c| *c != '_' ).collect();
+    if clean.starts_with(['+', '-']) {
+        return None
+    }
+    let value = Int::parse(&clean, base)?;
+    Some( if negative { value.neg() } else { value } )
+}
+
+fn builtin_int<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let named = keywords(rt, "int", kwargs, &[ "base" ])?;
+    arity(rt, "int", &args, 0, 2)?;
+    let base = match args.get(1).or(named[0].as_ref()) {
+        Some( b ) => {
+            let b = to_index(rt, b)?;
+            if b != 0 && !( 2 ..= 36 ).contains(&b) {
+                return throw(rt, "ValueError", "int() base must be >= 2 and <= 36, or 0")
+            }
+            Some( b as u32 )
+        },
+        None => None
+    };
+    let value = match args.first() {
+        Some( v ) => v,
+        None => return Ok( Value::int(0) )
+    };
+    match ( value, base ) {
+        ( Value::Str(s), _ ) => match parse_int(s, base.unwrap_or(10)) {
+            Some( i ) => Ok( Value::Int(i) ),
+            None => throw(rt, "ValueError", format!("invalid literal for int() with base {}: {}", base.unwrap_or(10), crate::runtime::format::str_repr(s)))
+        },
+        ( _, Some( _ ) ) => throw(rt, "TypeError", "int() can't convert non-string with explicit base"),
+        ( Value::Int(i), _ ) => Ok( Value::Int(i.clone()) ),
+        ( Value::Bool(b), _ ) => Ok( Value::int(*b as i64) ),
+        ( Value::Float(f), _ ) => {
+            if f.is_nan() {
+                throw(rt, "ValueError", "cannot convert float NaN to integer")
+            } else if f.is_infinite() {
+                throw(rt, "OverflowError", "cannot convert float infinity to integer")
+            } else {
+                Ok( Value::Int(Int::from_f64(*f)) )
+            }
+        },
+        _ => {
+            for name in [ "__int__", "__index__" ] {
+                if let Some( v ) = call_special(rt, value, name, Vec::new())? {
+                    return Ok( v )
+                }
+            }
+            throw(rt, "TypeError", format!("int() argument must be a string, a bytes-like object or a real number, not '{}'", type_name(rt, value)))
+        }
+    }
+}
+
+/// Parses float literal as 'float' accepts it, including 'inf' and 'nan'.
+pub fn parse_float(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let lower = text.to_ascii_lowercase();
+    let unsigned = lower.trim_start_matches(['+', '-']);
+    if matches!(unsigned, "inf" | "infinity" | "nan") {
+        return lower.parse().ok()
+    }
+    if text.is_empty() || text.starts_with('_') || text.ends_with('_') || text.contains("__") || !text.chars().all( |c| c.is_ascii_digit() || "+-.eE_".contains(c) ) {
+        return None
+    }
+    text.replace('_', "").parse().ok()
+}
+
+fn builtin_float<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "float", &kwargs)?;
+    arity(rt, "float", &args, 0, 1)?;
+    let value = match args.first() {
+        Some( v ) => v,
+        None => return Ok( Value::Float(0.0) )
+    };
+    match value {
+        Value::Float(f) => Ok( Value::Float(*f) ),
+        Value::Bool(b) => Ok( Value::Float(*b as i64 as f64) ),
+        Value::Int(i) => {
+            let f = i.to_f64();
+            if f.is_infinite() {
+                return throw(rt, "OverflowError", "int too large to convert to float")
+            }
+            Ok( Value::Float(f) )
+        },
+        Value::Str(s) => match parse_float(s) {
+            Some( f ) => Ok( Value::Float(f) ),
+            None => throw(rt, "ValueError", format!("could not convert string to float: {}", crate::runtime::format::str_repr(s)))
+        },
+        _ => match call_special(rt, value, "__float__", Vec::new())? {
+            Some( v ) => Ok( v ),
+            None => throw(rt, "TypeError", format!("float() argument must be a string or a real number, not '{}'", type_name(rt, value)))
+        }
+    }
+}
+
+fn builtin_str<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "str", &kwargs)?;
+    arity(rt, "str", &args, 0, 3)?;
+    match ( args.first(), args.len() ) {
+        ( None, _ ) => Ok( Value::str("") ),
+        ( Some( Value::Bytes(b) ), 2 | 3 ) => match std::str::from_utf8(b) {
+            Ok( s ) => Ok( Value::str(s) ),
+            Err( _ ) => throw(rt, "UnicodeError", "'utf-8' codec can't decode bytes")
+        },
+        ( Some( v ), 1 ) => Ok( Value::str(&to_str(rt, v)?) ),
+        ( Some( v ), _ ) => throw(rt, "TypeError", format!("decoding to str: need a bytes-like object, {} found", type_name(rt, v)))
+    }
+}
+
+fn builtin_bytes<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "bytes", &kwargs)?;
+    arity(rt, "bytes", &args, 0, 2)?;
+    match args.first() {
+        None => Ok( Value::Bytes( Rc::from(Vec::new()) ) ),
+        Some( Value::Str(s) ) => {
+            if args.len() < 2 {
+                return throw(rt, "TypeError", "string argument without an encoding")
+            }
+            Ok( Value::Bytes( Rc::from(s.as_bytes()) ) )
+        },
+        Some( Value::Int(i) ) => match i.to_i64() {
+            Some( n ) if n >= 0 => Ok( Value::Bytes( Rc::from(vec![ 0u8; n as usize ]) ) ),
+            _ => throw(rt, "ValueError", "negative count")
+        },
+        Some( v ) => {
+            let mut res = Vec::new();
+            for item in collect(rt, v)? {
+                match to_index(rt, &item)? {
+                    b @ 0 ..= 255 => res.push(b as u8),
+                    _ => return throw(rt, "ValueError", "bytes must be in range(0, 256)")
+                }
+            }
+            Ok( Value::Bytes( Rc::from(res) ) )
+        }
+    }
+}
+
+fn builtin_list<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "list", &kwargs)?;
+    arity(rt, "list", &args, 0, 1)?;
+    match args.first() {
+        Some( v ) => Ok( Value::list(collect(rt, v)?) ),
+        None => Ok( Value::list(Vec::new()) )
+    }
+}
+
+fn builtin_tuple<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "tuple", &kwargs)?;
+    arity(rt, "tuple", &args, 0, 1)?;
+    match args.first() {
+        Some( Value::Tuple(t) ) => Ok( Value::Tuple(t.clone()) ),
+        Some( v ) => Ok( Value::tuple(collect(rt, v)?) ),
+        None => Ok( Value::tuple(Vec::new()) )
+    }
+}
+
+fn builtin_dict<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    arity(rt, "dict", &args, 0, 1)?;
+    let dict = Rc::new( RefCell::new( Dict::new() ) );
+    if let Some( v ) = args.first() {
+        dict_update(rt, &dict, v)?
+    }
+    for ( k, v ) in kwargs {
+        let key = Value::str(&k);
+        let h = hash_key(rt, &key)?;
+        dict.borrow_mut().insert(h, key, v)
+    }
+    Ok( Value::Dict(dict) )
+}
+
+fn builtin_set<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "set", &kwargs)?;
+    arity(rt, "set", &args, 0, 1)?;
+    let values = match args.first() {
+        Some( v ) => collect(rt, v)?,
+        None => Vec::new()
+    };
+    Ok( Value::set(set_from(rt, values)?) )
+}
+
+fn range_bound<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>) -> Result<i64, Value<'a>> {
+    match value {
+        Value::Int(i) if i.to_i64().is_none() => throw(rt, "OverflowError", "range() arguments out of supported range"),
+        _ => to_index(rt, value)
+    }
+}
+
+fn builtin_range<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "range", &kwargs)?;
+    arity(rt, "range", &args, 1, 3)?;
+    let mut bounds = Vec::new();
+    for a in &args {
+        bounds.push(range_bound(rt, a)?)
+    }
+    let range = match bounds.as_slice() {
+        [ stop ] => Range { start: 0, stop: *stop, step: 1 },
+        [ start, stop ] => Range { start: *start, stop: *stop, step: 1 },
+        [ start, stop, step ] => {
+            if *step == 0 {
+                return throw(rt, "ValueError", "range() arg 3 must not be zero")
+            }
+            Range { start: *start, stop: *stop, step: *step }
+        },
+        _ => unreachable!()
+    };
+    Ok( Value::Range( Rc::new(range) ) )
+}
+
+fn builtin_slice<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "slice", &kwargs)?;
+    arity(rt, "slice", &args, 1, 3)?;
+    let parts = match args.len() {
+        1 => [ Value::None, args[0].clone(), Value::None ],
+        2 => [ args[0].clone(), args[1].clone(), Value::None ],
+        _ => [ args[0].clone(), args[1].clone(), args[2].clone() ]
+    };
+    Ok( Value::Slice( Rc::new(parts) ) )
+}
+
+fn builtin_staticmethod<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "staticmethod", &kwargs)?;
+    arity(rt, "staticmethod", &args, 1, 1)?;
+    Ok( Value::StaticMethod( Rc::new( args[0].clone() ) ) )
+}
+
+fn builtin_classmethod<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "classmethod", &kwargs)?;
+    arity(rt, "classmethod", &args, 1, 1)?;
+    Ok( Value::ClassMethod( Rc::new( args[0].clone() ) ) )
+}
+
+fn builtin_property<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let named = keywords(rt, "property", kwargs, &[ "fget", "fset", "fdel", "doc" ])?;
+    arity(rt, "property", &args, 0, 4)?;
+    let fget = args.first().cloned().or(named[0].clone()).unwrap_or(Value::None);
+    let fset = args.get(1).cloned().or(named[1].clone()).unwrap_or(Value::None);
+    Ok( Value::Property( Rc::new( ( fget, fset ) ) ) )
+}
+
+fn builtin_super<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "super", &kwargs)?;
+    if args.len() != 2 {
+        return throw(rt, "RuntimeError", "super(): no arguments")
+    }
+    match &args[0] {
+        Value::Class(c) => {
+            let owner = match &args[1] {
+                Value::Class(o) => o.clone(),
+                v => class_of(rt, v)
+            };
+            if !owner.is_subclass(c) {
+                return throw(rt, "TypeError", "super(type, obj): obj must be an instance or subtype of type")
+            }
+            Ok( Value::Super( Rc::new( ( c.clone(), args[1].clone() ) ) ) )
+        },
+        v => throw(rt, "TypeError", format!("super() argument 1 must be a type, not {}", type_name(rt, v)))
+    }
+}
+
+
+// Functions ///////////////////////////////////////////////////////////////////////////////////////
+
+fn builtin_print<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let named = keywords(rt, "print", kwargs, &[ "sep", "end", "file", "flush" ])?;
+    let text_of = |rt: &mut dyn Runtime<'a>, v: &Option<Value<'a>>, name: &str, default: &str| -> Result<String, Value<'a>> {
+        match v {
+            None | Some( Value::None ) => Ok( default.to_string() ),
+            Some( Value::Str(s) ) => Ok( s.to_string() ),
+            Some( v ) => throw(rt, "TypeError", format!("{} must be None or a string, not {}", name, type_name(rt, v)))
+        }
+    };
+    let sep = text_of(rt, &named[0], "sep", " ")?;
+    let end = text_of(rt, &named[1], "end", "\n")?;
+    let mut parts = Vec::with_capacity(args.len());
+    for a in &args {
+        parts.push(to_str(rt, a)?)
+    }
+    let text = format!("{}{}", parts.join(&sep), end);
+    rt.write(&text);
+    Ok( Value::None )
+}
+
+fn builtin_len<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "len", &kwargs)?;
+    arity(rt, "len", &args, 1, 1)?;
+    Ok( Value::int(len(rt, &args[0])? as i64) )
+}
+
+/// Classes of the second argument of 'isinstance' and 'issubclass'.
+fn class_info<'a>(rt: &dyn Runtime<'a>, name: &str, value: &Value<'a>) -> Result<Vec<Rc<Class<'a>>>, Value<'a>> {
+    match value {
+        Value::Class(c) => Ok( vec![ c.clone() ] ),
+        Value::Tuple(t) => {
+            let mut res = Vec::new();
+            for v in t.iter() {
+                res.extend(class_info(rt, name, v)?)
+            }
+            Ok( res )
+        },
+        _ => throw(rt, "TypeError", format!("{}() arg 2 must be a type, a tuple of types, or a union", name))
+    }
+}
+
+fn builtin_isinstance<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "isinstance", &kwargs)?;
+    arity(rt, "isinstance", &args, 2, 2)?;
+    let classes = class_info(rt, "isinstance", &args[1])?;
+    Ok( Value::Bool(classes.iter().any( |c| is_instance(rt, &args[0], c) )) )
+}
+
+fn builtin_issubclass<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "issubclass", &kwargs)?;
+    arity(rt, "issubclass", &args, 2, 2)?;
+    let classes = class_info(rt, "issubclass", &args[1])?;
+    match &args[0] {
+        Value::Class(c) => Ok( Value::Bool(classes.iter().any( |k| c.is_subclass(k) )) ),
+        _ => throw(rt, "TypeError", "issubclass() arg 1 must be a class")
+    }
+}
+
+fn builtin_repr<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "repr", &kwargs)?;
+    arity(rt, "repr", &args, 1, 1)?;
+    Ok( Value::str(&repr(rt, &args[0])?) )
+}
+
+fn builtin_abs<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "abs", &kwargs)?;
+    arity(rt, "abs", &args, 1, 1)?;
+    match &args[0] {
+        Value::Int(i) => Ok( Value::Int(i.abs()) ),
+        Value::Bool(b) => Ok( Value::int(*b as i64) ),
+        Value::Float(f) => Ok( Value::Float(f.abs()) ),
+        v => match call_special(rt, v, "__abs__", Vec::new())? {
+            Some( r ) => Ok( r ),
+            None => throw(rt, "TypeError", format!("bad operand type for abs(): '{}'", type_name(rt, v)))
+        }
+    }
+}
+
+fn min_max<'a>(rt: &mut dyn Runtime<'a>, name: &str, args: Vec<Value<'a>>, kwargs: Kwargs<'a>, maximum: bool) -> RunResult<'a> {
+    let named = keywords(rt, name, kwargs, &[ "key", "default" ])?;
+    arity(rt, name, &args, 1, usize::MAX)?;
+    let values = if args.len() == 1 {
+        collect(rt, &args[0])?
+    } else {
+        if named[1].is_some() {
+            return throw(rt, "TypeError", format!("Cannot specify a default for {}() with multiple positional arguments", name))
+        }
+        args
+    };
+    let key = named[0].clone().unwrap_or(Value::None);
+    let mut best : Option<( Value<'a>, Value<'a> )> = None;
+    for v in values {
+        let k = if key.is_none() { v.clone() } else { call(rt, &key, vec![ v.clone() ], Vec::new())? };
+        let better = match &best {
+            None => true,
+            Some( ( bk, _ ) ) => if maximum { less(rt, bk, &k)? } else { less(rt, &k, bk)? }
+        };
+        if better {
+            best = Some( ( k, v ) )
+        }
+    }
+    match ( best, &named[1] ) {
+        ( Some( ( _, v ) ), _ ) => Ok( v ),
+        ( None, Some( d ) ) => Ok( d.clone() ),
+        ( None, None ) => throw(rt, "ValueError", format!("{}() arg is an empty sequence", name))
+    }
+}
+
+fn builtin_min<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    min_max(rt, "min", args, kwargs, false)
+}
+
+fn builtin_max<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    min_max(rt, "max", args, kwargs, true)
+}
+
+fn builtin_sum<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let named = keywords(rt, "sum", kwargs, &[ "start" ])?;
+    arity(rt, "sum", &args, 1, 2)?;
+    let mut total = args.get(1).cloned().or(named[0].clone()).unwrap_or(Value::int(0));
+    if let Value::Str(_) = total {
+        return throw(rt, "TypeError", "sum() can't sum strings [use ''.join(seq) instead]")
+    }
+    let it = iter(rt, &args[0])?;
+    while let Some( v ) = next(rt, &it)? {
+        total = binary_op(rt, Operator::Add, &total, &v)?
+    }
+    Ok( total )
+}
+
+fn builtin_iter<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "iter", &kwargs)?;
+    arity(rt, "iter", &args, 1, 1)?;
+    iter(rt, &args[0])
+}
+
+fn builtin_next<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "next", &kwargs)?;
+    arity(rt, "next", &args, 1, 2)?;
+    if let Value::Generator(g) = &args[0] {
+        return match rt.resume(g, Resume::Send(Value::None))? {
+            Step::Yield(v) => Ok( v ),
+            Step::Return(v) => match args.get(1) {
+                Some( d ) => Ok( d.clone() ),
+                None => Err( new_exception(rt.builtins().exception("StopIteration"), if v.is_none() { Vec::new() } else { vec![ v ] }) )
+            }
+        }
+    }
+    match next(rt, &args[0])? {
+        Some( v ) => Ok( v ),
+        None => match args.get(1) {
+            Some( d ) => Ok( d.clone() ),
+            None => Err( new_exception(rt.builtins().exception("StopIteration"), Vec::new()) )
+        }
+    }
+}
+
+fn builtin_enumerate<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let named = keywords(rt, "enumerate", kwargs, &[ "start" ])?;
+    arity(rt, "enumerate", &args, 1, 2)?;
+    let start = match args.get(1).or(named[0].as_ref()) {
+        Some( s ) => int_value(rt, s)?,
+        None => Int::zero()
+    };
+    let it = iter(rt, &args[0])?;
+    Ok( Value::iterator( Iter::Enumerate(it, start) ) )
+}
+
+fn builtin_zip<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    keywords(rt, "zip", kwargs, &[ "strict" ])?;
+    let mut iters = Vec::new();
+    for a in &args {
+        iters.push(iter(rt, a)?)
+    }
+    Ok( Value::iterator( Iter::Zip(iters) ) )
+}
+
+fn builtin_map<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "map", &kwargs)?;
+    arity(rt, "map", &args, 2, usize::MAX)?;
+    let mut iters = Vec::new();
+    for a in &args[1 ..] {
+        iters.push(iter(rt, a)?)
+    }
+    Ok( Value::iterator( Iter::Map(args[0].clone(), iters) ) )
+}
+
+fn builtin_filter<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "filter", &kwargs)?;
+    arity(rt, "filter", &args, 2, 2)?;
+    let it = iter(rt, &args[1])?;
+    Ok( Value::iterator( Iter::Filter(args[0].clone(), it) ) )
+}
+
+fn builtin_reversed<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "reversed", &kwargs)?;
+    arity(rt, "reversed", &args, 1, 1)?;
+    match &args[0] {
+        Value::List(_) | Value::Tuple(_) | Value::Str(_) | Value::Range(_) | Value::Bytes(_) | Value::Dict(_) | Value::DictView(..) => {
+            let mut values = collect(rt, &args[0])?;
+            values.reverse();
+            Ok( Value::iterator( Iter::Values(Rc::from(values), 0) ) )
+        },
+        v => match call_special(rt, v, "__reversed__", Vec::new())? {
+            Some( r ) => Ok( r ),
+            None => throw(rt, "TypeError", format!("'{}' object is not reversible", type_name(rt, v)))
+        }
+    }
+}
+
+/// Stable merge sort of values by keys, comparisons may raise.
+fn merge_sort<'a>(rt: &mut dyn Runtime<'a>, keys: &[Value<'a>], mut order: Vec<usize>) -> Result<Vec<usize>, Value<'a>> {
+    if order.len() <= 1 {
+        return Ok( order )
+    }
+    let right = order.split_off(order.len() / 2);
+    let left = merge_sort(rt, keys, order)?;
+    let right = merge_sort(rt, keys, right)?;
+    let mut res = Vec::with_capacity(left.len() + right.len());
+    let ( mut i, mut j ) = ( 0, 0 );
+    while i < left.len() && j < right.len() {
+        if less(rt, &keys[right[j]], &keys[left[i]])? {
+            res.push(right[j]);
+            j += 1
+        } else {
+            res.push(left[i]);
+            i += 1
+        }
+    }
+    res.extend_from_slice(&left[i ..]);
+    res.extend_from_slice(&right[j ..]);
+    Ok( res )
+}
+
+/// Values sorted the way 'list.sort' does, stable also when reversed.
+fn sort_values<'a>(rt: &mut dyn Runtime<'a>, mut values: Vec<Value<'a>>, key: &Value<'a>, reverse: bool) -> Result<Vec<Value<'a>>, Value<'a>> {
+    if reverse {
+        values.reverse()
+    }
+    let keys = if key.is_none() {
+        values.clone()
+    } else {
+        let mut keys = Vec::with_capacity(values.len());
+        for v in &values {
+            keys.push(call(rt, key, vec![ v.clone() ], Vec::new())?)
+        }
+        keys
+    };
+    let order = merge_sort(rt, &keys, ( 0 .. values.len() ).collect())?;
+    let mut res : Vec<Value<'a>> = order.into_iter().map( |i| values[i].clone() ).collect();
+    if reverse {
+        res.reverse()
+    }
+    Ok( res )
+}
+
+fn builtin_sorted<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let named = keywords(rt, "sorted", kwargs, &[ "key", "reverse" ])?;
+    arity(rt, "sorted", &args, 1, 1)?;
+    let values = collect(rt, &args[0])?;
+    let reverse = match &named[1] {
+        Some( r ) => truthy(rt, r)?,
+        None => false
+    };
+    Ok( Value::list(sort_values(rt, values, named[0].as_ref().unwrap_or(&Value::None), reverse)?) )
+}
+
+fn builtin_any<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "any", &kwargs)?;
+    arity(rt, "any", &args, 1, 1)?;
+    let it = iter(rt, &args[0])?;
+    while let Some( v ) = next(rt, &it)? {
+        if truthy(rt, &v)? {
+            return Ok( Value::Bool(true) )
+        }
+    }
+    Ok( Value::Bool(false) )
+}
+
+fn builtin_all<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "all", &kwargs)?;
+    arity(rt, "all", &args, 1, 1)?;
+    let it = iter(rt, &args[0])?;
+    while let Some( v ) = next(rt, &it)? {
+        if !truthy(rt, &v)? {
+            return Ok( Value::Bool(false) )
+        }
+    }
+    Ok( Value::Bool(true) )
+}
+
+fn builtin_hash<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "hash", &kwargs)?;
+    arity(rt, "hash", &args, 1, 1)?;
+    Ok( Value::int(hash_key(rt, &args[0])?.hash_value()) )
+}
+
+fn builtin_id<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "id", &kwargs)?;
+    arity(rt, "id", &args, 1, 1)?;
+    match args[0].identity() {
+        Some( id ) => Ok( Value::int(id as i64) ),
+        None => Ok( Value::int(hash_key(rt, &args[0]).map( |h| h.hash_value() ).unwrap_or(0)) )
+    }
+}
+
+fn builtin_chr<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "chr", &kwargs)?;
+    arity(rt, "chr", &args, 1, 1)?;
+    let i = to_index(rt, &args[0])?;
+    match u32::try_from(i).ok().and_then(char::from_u32) {
+        Some( c ) => Ok( Value::str(&c.to_string()) ),
+        None => throw(rt, "ValueError", "chr() arg not in range(0x110000)")
+    }
+}
+
+fn builtin_ord<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "ord", &kwargs)?;
+    arity(rt, "ord", &args, 1, 1)?;
+    match &args[0] {
+        Value::Str(s) if s.chars().count() == 1 => Ok( Value::int(s.chars().next().unwrap() as i64) ),
+        Value::Str(s) => throw(rt, "TypeError", format!("ord() expected a character, but string of length {} found", s.chars().count())),
+        Value::Bytes(b) if b.len() == 1 => Ok( Value::int(b[0] as i64) ),
+        v => throw(rt, "TypeError", format!("ord() expected string of length 1, but {} found", type_name(rt, v)))
+    }
+}
+
+/// Integer rounded to multiple of 10^digits, ties go to the even multiple.
+fn round_int(value: &Int, digits: i64) -> Int {
+    if digits >= 0 {
+        return value.clone()
+    }
+    let unit = Int::from(10).pow(( -digits ) as u64);
+    let ( q, r ) = value.divmod(&unit).unwrap();
+    let twice = r.add(&r);
+    let up = match twice.cmp(&unit) {
+        std::cmp::Ordering::Greater => true,
+        std::cmp::Ordering::Equal => !q.bitand(&Int::from(1)).is_zero(),
+        std::cmp::Ordering::Less => false
+    };
+    let q = if up { q.add(&Int::from(1)) } else { q };
+    q.mul(&unit)
+}
+
+fn builtin_round<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let named = keywords(rt, "round", kwargs, &[ "number", "ndigits" ])?;
+    let number = match args.first().or(named[0].as_ref()) {
+        Some( n ) => n.clone(),
+        None => return throw(rt, "TypeError", "round() missing required argument 'number' (pos 1)")
+    };
+    let digits = match args.get(1).or(named[1].as_ref()) {
+        None | Some( Value::None ) => None,
+        Some( d ) => Some( to_index(rt, d)? )
+    };
+    match number {
+        Value::Int(_) | Value::Bool(_) => {
+            let i = int_value(rt, &number)?;
+            Ok( Value::Int(round_int(&i, digits.unwrap_or(0))) )
+        },
+        Value::Float(f) => match digits {
+            None => {
+                if !f.is_finite() {
+                    return throw(rt, if f.is_nan() { "ValueError" } else { "OverflowError" },
+                                 if f.is_nan() { "cannot convert float NaN to integer" } else { "cannot convert float infinity to integer" })
+                }
+                let r = f.round();
+                let r = if ( f - f.trunc() ).abs() == 0.5 { 2.0 * ( f / 2.0 ).round() } else { r };
+                Ok( Value::Int(Int::from_f64(r)) )
+            },
+            Some( d ) if !f.is_finite() || d > 308 => Ok( Value::Float(f) ),
+            Some( d ) if d >= 0 => Ok( Value::Float(format!("{:.*}", d as usize, f).parse().unwrap_or(f)) ),
+            Some( d ) => {
+                let unit = 10f64.powi(( -d ) as i32);
+                let q = f / unit;
+                let r = if ( q - q.trunc() ).abs() == 0.5 { 2.0 * ( q / 2.0 ).round() } else { q.round() };
+                Ok( Value::Float(r * unit) )
+            }
+        },
+        v => match call_special(rt, &v, "__round__", digits.map( Value::int ).into_iter().collect())? {
+            Some( r ) => Ok( r ),
+            None => throw(rt, "TypeError", format!("type {} doesn't define __round__ method", type_name(rt, &v)))
+        }
+    }
+}
+
+fn builtin_divmod<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "divmod", &kwargs)?;
+    arity(rt, "divmod", &args, 2, 2)?;
+    let q = binary_op(rt, Operator::FloorDiv, &args[0], &args[1])?;
+    let r = binary_op(rt, Operator::Mod, &args[0], &args[1])?;
+    Ok( Value::tuple(vec![ q, r ]) )
+}
+
+fn builtin_pow<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let named = keywords(rt, "pow", kwargs, &[ "base", "exp", "mod" ])?;
+    let get = |i: usize| args.get(i).cloned().or(named[i].clone());
+    let ( base, exp ) = match ( get(0), get(1) ) {
+        ( Some( b ), Some( e ) ) => ( b, e ),
+        _ => return throw(rt, "TypeError", "pow() missing required argument")
+    };
+    match get(2) {
+        None | Some( Value::None ) => binary_op(rt, Operator::Pow, &base, &exp),
+        Some( m ) => {
+            let ( mut b, mut e, m ) = match ( &base, &exp, &m ) {
+                ( Value::Int(_) | Value::Bool(_), Value::Int(_) | Value::Bool(_), Value::Int(_) | Value::Bool(_) ) =>
+                    ( int_value(rt, &base)?, int_value(rt, &exp)?, int_value(rt, &m)? ),
+                _ => return throw(rt, "TypeError", "pow() 3rd argument not allowed unless all arguments are integers")
+            };
+            if m.is_zero() {
+                return throw(rt, "ValueError", "pow() 3rd argument cannot be 0")
+            }
+            if e.is_negative() {
+                return throw(rt, "ValueError", "base is not invertible for the given modulus")
+            }
+            let mut res = Int::from(1).divmod(&m).unwrap().1;
+            b = b.divmod(&m).unwrap().1;
+            while !e.is_zero() {
+                if !e.bitand(&Int::from(1)).is_zero() {
+                    res = res.mul(&b).divmod(&m).unwrap().1
+                }
+                e = e.shr(1);
+                b = b.mul(&b).divmod(&m).unwrap().1
+            }
+            Ok( Value::Int(res) )
+        }
+    }
+}
+
+fn radix_text<'a>(rt: &mut dyn Runtime<'a>, name: &str, args: Vec<Value<'a>>, kwargs: Kwargs<'a>, radix: u32, prefix: &str) -> RunResult<'a> {
+    no_kwargs(rt, name, &kwargs)?;
+    arity(rt, name, &args, 1, 1)?;
+    let i = int_value(rt, &args[0])?;
+    let digits = i.abs().to_string_radix(radix);
+    Ok( Value::str(&format!("{}{}{}", if i.is_negative() { "-" } else { "" }, prefix, digits)) )
+}
+
+fn builtin_hex<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    radix_text(rt, "hex", args, kwargs, 16, "0x")
+}
+
+fn builtin_oct<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    radix_text(rt, "oct", args, kwargs, 8, "0o")
+}
+
+fn builtin_bin<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    radix_text(rt, "bin", args, kwargs, 2, "0b")
+}
+
+fn builtin_getattr<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "getattr", &kwargs)?;
+    arity(rt, "getattr", &args, 2, 3)?;
+    let name = expect_str(rt, "attribute name", &args[1])?;
+    match get_attr(rt, &args[0], &name) {
+        Err( e ) if args.len() == 3 && is_exception(rt, &e, "AttributeError") => Ok( args[2].clone() ),
+        res => res
+    }
+}
+
+fn builtin_setattr<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "setattr", &kwargs)?;
+    arity(rt, "setattr", &args, 3, 3)?;
+    let name = expect_str(rt, "attribute name", &args[1])?;
+    set_attr(rt, &args[0], &name, args[2].clone())?;
+    Ok( Value::None )
+}
+
+fn builtin_hasattr<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "hasattr", &kwargs)?;
+    arity(rt, "hasattr", &args, 2, 2)?;
+    let name = expect_str(rt, "attribute name", &args[1])?;
+    match get_attr(rt, &args[0], &name) {
+        Ok( _ ) => Ok( Value::Bool(true) ),
+        Err( e ) if is_exception(rt, &e, "AttributeError") => Ok( Value::Bool(false) ),
+        Err( e ) => Err( e )
+    }
+}
+
+fn builtin_delattr<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "delattr", &kwargs)?;
+    arity(rt, "delattr", &args, 2, 2)?;
+    let name = expect_str(rt, "attribute name", &args[1])?;
+    del_attr(rt, &args[0], &name)?;
+    Ok( Value::None )
+}
+
+fn builtin_callable<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "callable", &kwargs)?;
+    arity(rt, "callable", &args, 1, 1)?;
+    Ok( Value::Bool(match &args[0] {
+        Value::Function(_) | Value::Builtin(_) | Value::BoundMethod(_) | Value::Class(_) | Value::StaticMethod(_) => true,
+        Value::Instance(i) => i.class.lookup("__call__").is_some(),
+        _ => false
+    }) )
+}
+
+fn builtin_format<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    no_kwargs(rt, "format", &kwargs)?;
+    arity(rt, "format", &args, 1, 2)?;
+    let spec = match args.get(1) {
+        Some( s ) => expect_str(rt, "format() argument 2", s)?,
+        None => Rc::from("")
+    };
+    Ok( Value::str(&format_value(rt, &args[0], &spec)?) )
+}
+
+
+// Methods of builtin types ////////////////////////////////////////////////////////////////////////
+
+/// Method of builtin type by name, called with the receiver as first argument.
+pub fn method<'a>(kind: TypeKind, name: &str) -> Option<( &'static str, BuiltinFn<'a> )> {
+    let table : &[( &'static str, BuiltinFn<'a> )] = match kind {
+        TypeKind::Object => &[ ( "__init__", object_init ), ( "__repr__", object_repr ), ( "__str__", object_str ) ],
+        TypeKind::Exception => &[ ( "__init__", exception_init ), ( "with_traceback", exception_with_traceback ),
+                                  ( "__repr__", object_repr ), ( "__str__", object_str ) ],
+        TypeKind::Str => &[
+            ( "upper", str_upper ), ( "lower", str_lower ), ( "casefold", str_lower ), ( "swapcase", str_swapcase ),
+            ( "title", str_title ), ( "capitalize", str_capitalize ), ( "strip", str_strip ), ( "lstrip", str_lstrip ),
+            ( "rstrip", str_rstrip ), ( "split", str_split ), ( "rsplit", str_rsplit ), ( "splitlines", str_splitlines ),
+            ( "join", str_join ), ( "replace", str_replace ), ( "startswith", str_startswith ), ( "endswith", str_endswith ),
+            ( "find", str_find ), ( "rfind", str_rfind ), ( "index", str_index ), ( "rindex", str_rindex ),
+            ( "count", str_count ), ( "format", str_format ), ( "isdigit", str_isdigit ), ( "isdecimal", str_isdigit ),
+            ( "isnumeric", str_isnumeric ), ( "isalpha", str_isalpha ), ( "isalnum", str_isalnum ), ( "isspace", str_isspace ),
+            ( "isupper", str_isupper ), ( "islower", str_islower ), ( "isidentifier", str_isidentifier ),
+            ( "center", str_center ), ( "ljust", str_ljust ), ( "rjust", str_rjust ), ( "zfill", str_zfill ),
+            ( "partition", str_partition ), ( "rpartition", str_rpartition ), ( "encode", str_encode ),
+            ( "removeprefix", str_removeprefix ), ( "removesuffix", str_removesuffix )
+        ],
+        TypeKind::Bytes => &[ ( "decode", bytes_decode ) ],
+        TypeKind::List => &[
+            ( "append", list_append ), ( "extend", list_extend ), ( "insert", list_insert ), ( "pop", list_pop ),
+            ( "remove", list_remove ), ( "index", seq_index ), ( "count", seq_count ), ( "sort", list_sort ),
+            ( "reverse", list_reverse ), ( "copy", list_copy ), ( "clear", list_clear )
+        ],
+        TypeKind::Tuple => &[ ( "index", seq_index ), ( "count", seq_count ) ],
+        TypeKind::Dict => &[
+            ( "get", dict_get ), ( "keys", dict_keys ), ( "values", dict_values ), ( "items", dict_items ),
+            ( "pop", dict_pop ), ( "popitem", dict_popitem ), ( "setdefault", dict_setdefault ), ( "update", dict_update_method ),
+            ( "copy", dict_copy ), ( "clear", dict_clear ), ( "fromkeys", dict_fromkeys )
+        ],
+        TypeKind::Set => &[
+            ( "add", set_add ), ( "remove", set_remove ), ( "discard", set_discard ), ( "pop", set_pop ),
+            ( "clear", set_clear ), ( "copy", set_copy ), ( "update", set_update ), ( "union", set_union ),
+            ( "intersection", set_intersection ), ( "difference", set_difference ),
+            ( "symmetric_difference", set_symmetric_difference ), ( "issubset", set_issubset ),
+            ( "issuperset", set_issuperset ), ( "isdisjoint", set_isdisjoint )
+        ],
+        TypeKind::Int | TypeKind::Bool => &[ ( "bit_length", int_bit_length ) ],
+        TypeKind::Float => &[ ( "is_integer", float_is_integer ) ],
+        TypeKind::Property => &[ ( "setter", property_setter ), ( "getter", property_getter ) ],
+        TypeKind::Generator => &[ ( "send", generator_send ), ( "throw", generator_throw ), ( "close", generator_close ) ],
+        _ => &[]
+    };
+    table.iter().find( |( n, _ )| *n == name ).cloned()
+}
+
+fn object_init<'a>(_rt: &mut dyn Runtime<'a>, _args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    Ok( Value::None )
+}
+
+fn object_repr<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    arity(rt, "__repr__", &args, 1, 1)?;
+    match &args[0] {
+        Value::Instance(i) if i.class.kind == TypeKind::Exception => {
+            let values = exception_args(&args[0]);
+            let mut parts = Vec::new();
+            for v in &values {
+                parts.push(repr(rt, v)?)
+            }
+            Ok( Value::str(&format!("{}({})", i.class.name, parts.join(", "))) )
+        },
+        Value::Instance(i) => Ok( Value::str(&format!("<__main__.{} object at 0x{:012x}>", i.class.name, args[0].identity().unwrap_or(0))) ),
+        v => Ok( Value::str(&repr(rt, v)?) )
+    }
+}
+
+fn object_str<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    arity(rt, "__str__", &args, 1, 1)?;
+    match &args[0] {
+        Value::Instance(i) if i.class.kind == TypeKind::Exception => {
+            let values = exception_args(&args[0]);
+            match values.len() {
+                0 => Ok( Value::str("") ),
+                1 => Ok( Value::str(&to_str(rt, &values[0])?) ),
+                _ => Ok( Value::str(&repr(rt, &Value::tuple(values))?) )
+            }
+        },
+        Value::Instance(i) if i.class.lookup("__repr__").is_some() => {
+            let f = get_attr(rt, &args[0], "__repr__")?;
+            call(rt, &f, Vec::new(), Vec::new())
+        },
+        _ => object_repr(rt, args, kwargs)
+    }
+}
+
+fn exception_init<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    match args.first() {
+        Some( Value::Instance(i) ) => {
+            i.dict.borrow_mut().insert("args".to_string(), Value::tuple(args[1 ..].to_vec()));
+            Ok( Value::None )
+        },
+        _ => receiver_error(rt, "BaseException", &args)
+    }
+}
+
+fn exception_with_traceback<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    arity(rt, "with_traceback", &args, 2, 2)?;
+    Ok( args[0].clone() )
+}
+
+fn str_map<'a>(rt: &mut dyn Runtime<'a>, name: &str, args: &[Value<'a>], f: fn(&str) -> String) -> RunResult<'a> {
+    let s = recv_str(rt, args)?;
+    arity(rt, name, &args[1 ..], 0, 0)?;
+    Ok( Value::str(&f(&s)) )
+}
+
+fn str_upper<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_map(rt, "str.upper", &args, str::to_uppercase)
+}
+
+fn str_lower<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_map(rt, "str.lower", &args, str::to_lowercase)
+}
+
+fn str_swapcase<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_map(rt, "str.swapcase", &args, |s| s.chars().map( |c| if c.is_uppercase() { c.to_lowercase().to_string() } else { c.to_uppercase().to_string() } ).collect())
+}
+
+fn str_title<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_map(rt, "str.title", &args, |s| {
+        let mut res = String::new();
+        let mut previous_cased = false;
+        for c in s.chars() {
+            if previous_cased { res.extend(c.to_lowercase()) } else { res.extend(c.to_uppercase()) }
+            previous_cased = c.is_alphabetic()
+        }
+        res
+    })
+}
+
+fn str_capitalize<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_map(rt, "str.capitalize", &args, |s| {
+        let mut chars = s.chars();
+        match chars.next() {
+            Some( c ) => c.to_uppercase().chain(chars.flat_map( |c| c.to_lowercase() )).collect(),
+            None => String::new()
+        }
+    })
+}
+
+fn strip_chars<'a>(rt: &mut dyn Runtime<'a>, name: &str, args: &[Value<'a>], left: bool, right: bool) -> RunResult<'a> {
+    let s = recv_str(rt, args)?;
+    arity(rt, name, &args[1 ..], 0, 1)?;
+    let chars : Option<Vec<char>> = match args.get(1) {
+        None | Some( Value::None ) => None,
+        Some( Value::Str(c) ) => Some( c.chars().collect() ),
+        Some( v ) => return throw(rt, "TypeError", format!("{} arg must be None or str, not {}", name.trim_start_matches("str."), type_name(rt, v)))
+    };
+    let matches = |c: char| match &chars { Some( set ) => set.contains(&c), None => c.is_whitespace() };
+    let mut res : &str = &s;
+    if left {
+        res = res.trim_start_matches(matches)
+    }
+    if right {
+        res = res.trim_end_matches(matches)
+    }
+    Ok( Value::str(res) )
+}
+
+fn str_strip<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    strip_chars(rt, "str.strip", &args, true, true)
+}
+
+fn str_lstrip<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    strip_chars(rt, "str.lstrip", &args, true, false)
+}
+
+fn str_rstrip<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    strip_chars(rt, "str.rstrip", &args, false, true)
+}
+
+fn split<'a>(rt: &mut dyn Runtime<'a>, name: &str, args: Vec<Value<'a>>, kwargs: Kwargs<'a>, reverse: bool) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    let named = keywords(rt, name, kwargs, &[ "sep", "maxsplit" ])?;
+    arity(rt, name, &args[1 ..], 0, 2)?;
+    let sep = match args.get(1).or(named[0].as_ref()) {
+        None | Some( Value::None ) => None,
+        Some( v ) => {
+            let sep = expect_str(rt, "sep", v)?;
+            if sep.is_empty() {
+                return throw(rt, "ValueError", "empty separator")
+            }
+            Some( sep )
+        }
+    };
+    let maxsplit = match args.get(2).or(named[1].as_ref()) {
+        Some( m ) => to_index(rt, m)?,
+        None => -1
+    };
+    let limit = if maxsplit < 0 { usize::MAX } else { maxsplit as usize + 1 };
+    let mut parts : Vec<String> = match ( &sep, reverse ) {
+        ( Some( sep ), false ) => s.splitn(limit, &**sep).map( String::from ).collect(),
+        ( Some( sep ), true ) => s.rsplitn(limit, &**sep).map( String::from ).collect(),
+        ( None, _ ) => {
+            let mut parts = Vec::new();
+            let mut rest : &str = if reverse { s.trim_end() } else { s.trim_start() };
+            while !rest.is_empty() {
+                if parts.len() + 1 == limit {
+                    parts.push(rest.to_string());
+                    break
+                }
+                if reverse {
+                    match rest.rfind(char::is_whitespace) {
+                        Some( i ) => {
+                            let c = rest[i ..].chars().next().unwrap();
+                            parts.push(rest[i + c.len_utf8() ..].to_string());
+                            rest = rest[.. i].trim_end()
+                        },
+                        None => {
+                            parts.push(rest.to_string());
+                            rest = ""
+                        }
+                    }
+                } else {
+                    match rest.find(char::is_whitespace) {
+                        Some( i ) => {
+                            parts.push(rest[.. i].to_string());
+                            rest = rest[i ..].trim_start()
+                        },
+                        None => {
+                            parts.push(rest.to_string());
+                            rest = ""
+                        }
+                    }
+                }
+            }
+            parts
+        }
+    };
+    if reverse {
+        parts.reverse()
+    }
+    Ok( Value::list(parts.iter().map( |p| Value::str(p) ).collect()) )
+}
+
+fn str_split<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    split(rt, "split", args, kwargs, false)
+}
+
+fn str_rsplit<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    split(rt, "rsplit", args, kwargs, true)
+}
+
+fn str_splitlines<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    let named = keywords(rt, "splitlines", kwargs, &[ "keepends" ])?;
+    let keep = match args.get(1).or(named[0].as_ref()) {
+        Some( k ) => truthy(rt, k)?,
+        None => false
+    };
+    let mut res = Vec::new();
+    let mut rest : &str = &s;
+    while !rest.is_empty() {
+        let ( line, end ) = match rest.find(['\n', '\r']) {
+            Some( i ) if rest[i ..].starts_with("\r\n") => ( &rest[.. i], i + 2 ),
+            Some( i ) => ( &rest[.. i], i + 1 ),
+            None => ( rest, rest.len() )
+        };
+        res.push(Value::str(if keep { &rest[.. end] } else { line }));
+        rest = &rest[end ..]
+    }
+    Ok( Value::list(res) )
+}
+
+fn str_join<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    arity(rt, "str.join", &args[1 ..], 1, 1)?;
+    let mut parts = Vec::new();
+    for ( i, v ) in collect(rt, &args[1])?.into_iter().enumerate() {
+        match v {
+            Value::Str(p) => parts.push(p),
+            _ => return throw(rt, "TypeError", format!("sequence item {}: expected str instance, {} found", i, type_name(rt, &v)))
+        }
+    }
+    let parts : Vec<&str> = parts.iter().map( |p| &**p ).collect();
+    Ok( Value::str(&parts.join(&s)) )
+}
+
+fn str_replace<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    arity(rt, "replace", &args[1 ..], 2, 3)?;
+    let old = expect_str(rt, "replace() argument 1", &args[1])?;
+    let new = expect_str(rt, "replace() argument 2", &args[2])?;
+    let count = match args.get(3) {
+        Some( c ) => to_index(rt, c)?,
+        None => -1
+    };
+    if count < 0 {
+        Ok( Value::str(&s.replace(&*old, &new)) )
+    } else {
+        Ok( Value::str(&s.replacen(&*old, &new, count as usize)) )
+    }
+}
+
+/// Prefixes or suffixes to test, a single string or a tuple of them.
+fn affixes<'a>(rt: &dyn Runtime<'a>, name: &str, value: &Value<'a>) -> Result<Vec<Rc<str>>, Value<'a>> {
+    match value {
+        Value::Str(s) => Ok( vec![ s.clone() ] ),
+        Value::Tuple(t) => {
+            let mut res = Vec::new();
+            for v in t.iter() {
+                res.push(expect_str(rt, "tuple for startswith must only contain str, not", v).map_err( |_| error(rt, "TypeError",
+                    format!("tuple for {} must only contain str, not {}", name, type_name(rt, v))) )?)
+            }
+            Ok( res )
+        },
+        _ => throw(rt, "TypeError", format!("{} first arg must be str or a tuple of str, not {}", name, type_name(rt, value)))
+    }
+}
+
+/// Characters of receiver between optional start and end arguments at 'args[i]' and 'args[i + 1]',
+/// with the offset of the first one.
+fn char_range<'a>(rt: &mut dyn Runtime<'a>, s: &str, args: &[Value<'a>], i: usize) -> Result<( String, usize ), Value<'a>> {
+    let chars : Vec<char> = s.chars().collect();
+    let n = chars.len() as i64;
+    let mut bound = |v: Option<&Value<'a>>, default: i64| -> Result<i64, Value<'a>> {
+        Ok( match v {
+            None | Some( Value::None ) => default,
+            Some( v ) => {
+                let b = to_index(rt, v)?;
+                if b < 0 { ( b + n ).max(0) } else { b.min(n) }
+            }
+        } )
+    };
+    let start = bound(args.get(i), 0)?;
+    let end = bound(args.get(i + 1), n)?;
+    if start > end {
+        return Ok( ( String::new(), start as usize ) )
+    }
+    Ok( ( chars[start as usize .. end as usize].iter().collect(), start as usize ) )
+}
+
+fn str_startswith<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    arity(rt, "startswith", &args[1 ..], 1, 3)?;
+    let prefixes = affixes(rt, "startswith", &args[1])?;
+    let ( text, _ ) = char_range(rt, &s, &args, 2)?;
+    Ok( Value::Bool(prefixes.iter().any( |p| text.starts_with(&**p) )) )
+}
+
+fn str_endswith<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    arity(rt, "endswith", &args[1 ..], 1, 3)?;
+    let suffixes = affixes(rt, "endswith", &args[1])?;
+    let ( text, _ ) = char_range(rt, &s, &args, 2)?;
+    Ok( Value::Bool(suffixes.iter().any( |p| text.ends_with(&**p) )) )
+}
+
+/// Character position of substring, searched from the end when reverse.
+fn find<'a>(rt: &mut dyn Runtime<'a>, name: &str, args: &[Value<'a>], reverse: bool) -> Result<Option<usize>, Value<'a>> {
+    let s = recv_str(rt, args)?;
+    arity(rt, name, &args[1 ..], 1, 3)?;
+    let sub = expect_str(rt, &format!("{}() argument 1", name), &args[1])?;
+    let ( text, offset ) = char_range(rt, &s, args, 2)?;
+    let found = if reverse { text.rfind(&*sub) } else { text.find(&*sub) };
+    Ok( found.map( |b| text[.. b].chars().count() + offset ) )
+}
+
+fn str_find<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    Ok( Value::int(find(rt, "find", &args, false)?.map( |i| i as i64 ).unwrap_or(-1)) )
+}
+
+fn str_rfind<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    Ok( Value::int(find(rt, "rfind", &args, true)?.map( |i| i as i64 ).unwrap_or(-1)) )
+}
+
+fn str_index<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    match find(rt, "index", &args, false)? {
+        Some( i ) => Ok( Value::int(i as i64) ),
+        None => throw(rt, "ValueError", "substring not found")
+    }
+}
+
+fn str_rindex<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    match find(rt, "rindex", &args, true)? {
+        Some( i ) => Ok( Value::int(i as i64) ),
+        None => throw(rt, "ValueError", "substring not found")
+    }
+}
+
+fn str_count<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    arity(rt, "count", &args[1 ..], 1, 3)?;
+    let sub = expect_str(rt, "count() argument 1", &args[1])?;
+    let ( text, _ ) = char_range(rt, &s, &args, 2)?;
+    let n = if sub.is_empty() { text.chars().count() + 1 } else { text.matches(&*sub).count() };
+    Ok( Value::int(n as i64) )
+}
+
+/// Value of replacement field name of 'str.format', like '0', 'name', '0.attr' or 'name[key]'.
+fn format_field<'a>(rt: &mut dyn Runtime<'a>, field: &str, args: &[Value<'a>], kwargs: &Kwargs<'a>, auto: &mut usize) -> RunResult<'a> {
+    let end = field.find(['.', '[']).unwrap_or(field.len());
+    let ( first, mut rest ) = field.split_at(end);
+    let mut value = if first.is_empty() {
+        let v = args.get(*auto).cloned();
+        *auto += 1;
+        match v {
+            Some( v ) => v,
+            None => return throw(rt, "IndexError", format!("Replacement index {} out of range for positional args tuple", *auto - 1))
+        }
+    } else if let Ok( i ) = first.parse::<usize>() {
+        match args.get(i) {
+            Some( v ) => v.clone(),
+            None => return throw(rt, "IndexError", format!("Replacement index {} out of range for positional args tuple", i))
+        }
+    } else {
+        match kwargs.iter().find( |( k, _ )| k == first ) {
+            Some( ( _, v ) ) => v.clone(),
+            None => return Err( new_exception(rt.builtins().exception("KeyError"), vec![ Value::str(first) ]) )
+        }
+    };
+    while !rest.is_empty() {
+        if let Some( r ) = rest.strip_prefix('.') {
+            let end = r.find(['.', '[']).unwrap_or(r.len());
+            value = get_attr(rt, &value, &r[.. end])?;
+            rest = &r[end ..]
+        } else if let Some( r ) = rest.strip_prefix('[') {
+            let end = match r.find(']') {
+                Some( e ) => e,
+                None => return throw(rt, "ValueError", "Missing ']' in format string")
+            };
+            let key = &r[.. end];
+            let key = match key.parse::<i64>() {
+                Ok( i ) => Value::int(i),
+                Err( _ ) => Value::str(key)
+            };
+            value = get_item(rt, &value, &key)?;
+            rest = &r[end + 1 ..]
+        } else {
+            return throw(rt, "ValueError", "Only '.' or '[' may follow ']' in format field specifier")
+        }
+    }
+    Ok( value )
+}
+
+/// Applies 'str.format' to template.
+pub fn format_template<'a>(rt: &mut dyn Runtime<'a>, template: &str, args: &[Value<'a>], kwargs: &Kwargs<'a>, auto: &mut usize) -> Result<String, Value<'a>> {
+    let chars : Vec<char> = template.chars().collect();
+    let mut res = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '{' if chars.get(i + 1) == Some( &'{' ) => {
+                res.push('{');
+                i += 2
+            },
+            '}' if chars.get(i + 1) == Some( &'}' ) => {
+                res.push('}');
+                i += 2
+            },
+            '}' => return throw(rt, "ValueError", "Single '}' encountered in format string"),
+            '{' => {
+                let mut depth = 1;
+                let mut j = i + 1;
+                while j < chars.len() && depth > 0 {
+                    match chars[j] {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => ()
+                    }
+                    j += 1
+                }
+                if depth > 0 {
+                    return throw(rt, "ValueError", "expected '}' before end of string")
+                }
+                let field : String = chars[i + 1 .. j - 1].iter().collect();
+                let ( field, spec ) = match field.find(':') {
+                    Some( k ) => ( field[.. k].to_string(), field[k + 1 ..].to_string() ),
+                    None => ( field, String::new() )
+                };
+                let ( name, conversion ) = match field.find('!') {
+                    Some( k ) => ( field[.. k].to_string(), field[k + 1 ..].chars().next() ),
+                    None => ( field, None )
+                };
+                let mut value = format_field(rt, &name, args, kwargs, auto)?;
+                value = match conversion {
+                    None => value,
+                    Some( 's' ) => Value::str(&to_str(rt, &value)?),
+                    Some( 'r' ) | Some( 'a' ) => Value::str(&repr(rt, &value)?),
+                    Some( c ) => return throw(rt, "ValueError", format!("Unknown conversion specifier {}", c))
+                };
+                let spec = if spec.contains('{') { format_template(rt, &spec, args, kwargs, auto)? } else { spec };
+                res.push_str(&format_value(rt, &value, &spec)?);
+                i = j
+            },
+            c => {
+                res.push(c);
+                i += 1
+            }
+        }
+    }
+    Ok( res )
+}
+
+fn str_format<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    let mut auto = 0;
+    Ok( Value::str(&format_template(rt, &s, &args[1 ..], &kwargs, &mut auto)?) )
+}
+
+fn str_test<'a>(rt: &mut dyn Runtime<'a>, args: &[Value<'a>], test: fn(&str) -> bool) -> RunResult<'a> {
+    let s = recv_str(rt, args)?;
+    Ok( Value::Bool(!s.is_empty() && test(&s)) )
+}
+
+fn str_isdigit<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_test(rt, &args, |s| s.chars().all( |c| c.is_ascii_digit() ))
+}
+
+fn str_isnumeric<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_test(rt, &args, |s| s.chars().all(char::is_numeric))
+}
+
+fn str_isalpha<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_test(rt, &args, |s| s.chars().all(char::is_alphabetic))
+}
+
+fn str_isalnum<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_test(rt, &args, |s| s.chars().all(char::is_alphanumeric))
+}
+
+fn str_isspace<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_test(rt, &args, |s| s.chars().all(char::is_whitespace))
+}
+
+fn str_isupper<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_test(rt, &args, |s| s.chars().any(char::is_uppercase) && !s.chars().any(char::is_lowercase))
+}
+
+fn str_islower<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_test(rt, &args, |s| s.chars().any(char::is_lowercase) && !s.chars().any(char::is_uppercase))
+}
+
+fn str_isidentifier<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    str_test(rt, &args, |s| {
+        let mut chars = s.chars();
+        chars.next().map( |c| c == '_' || c.is_alphabetic() ).unwrap_or(false) && chars.all( |c| c == '_' || c.is_alphanumeric() )
+    })
+}
+
+/// Receiver padded to width with fill character, 'align' is '<', '>' or '^'.
+fn justify<'a>(rt: &mut dyn Runtime<'a>, name: &str, args: &[Value<'a>], align: char) -> RunResult<'a> {
+    let s = recv_str(rt, args)?;
+    arity(rt, name, &args[1 ..], 1, 2)?;
+    let width = to_index(rt, &args[1])?.max(0) as usize;
+    let fill = match args.get(2) {
+        Some( Value::Str(f) ) if f.chars().count() == 1 => f.chars().next().unwrap(),
+        Some( _ ) => return throw(rt, "TypeError", "The fill character must be exactly one character long"),
+        None => ' '
+    };
+    let n = s.chars().count();
+    if n >= width {
+        return Ok( Value::Str(s) )
+    }
+    let total = width - n;
+    let left = match align {
+        '<' => 0,
+        '>' => total,
+        _ => total / 2 + ( total & width & 1 )
+    };
+    let fill = |k: usize| fill.to_string().repeat(k);
+    Ok( Value::str(&format!("{}{}{}", fill(left), s, fill(total - left))) )
+}
+
+fn str_center<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    justify(rt, "center", &args, '^')
+}
+
+fn str_ljust<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    justify(rt, "ljust", &args, '<')
+}
+
+fn str_rjust<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    justify(rt, "rjust", &args, '>')
+}
+
+fn str_zfill<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    arity(rt, "zfill", &args[1 ..], 1, 1)?;
+    let width = to_index(rt, &args[1])?.max(0) as usize;
+    let n = s.chars().count();
+    if n >= width {
+        return Ok( Value::Str(s) )
+    }
+    let ( sign, digits ) = match s.chars().next() {
+        Some( c @ ( '+' | '-' ) ) => ( c.to_string(), &s[1 ..] ),
+        _ => ( String::new(), &s[..] )
+    };
+    Ok( Value::str(&format!("{}{}{}", sign, "0".repeat(width - n), digits)) )
+}
+
+fn partition<'a>(rt: &mut dyn Runtime<'a>, name: &str, args: &[Value<'a>], reverse: bool) -> RunResult<'a> {
+    let s = recv_str(rt, args)?;
+    arity(rt, name, &args[1 ..], 1, 1)?;
+    let sep = expect_str(rt, "must be str, not", &args[1])?;
+    if sep.is_empty() {
+        return throw(rt, "ValueError", "empty separator")
+    }
+    let found = if reverse { s.rfind(&*sep) } else { s.find(&*sep) };
+    let parts = match ( found, reverse ) {
+        ( Some( i ), _ ) => [ &s[.. i], &*sep, &s[i + sep.len() ..] ],
+        ( None, false ) => [ &s[..], "", "" ],
+        ( None, true ) => [ "", "", &s[..] ]
+    };
+    Ok( Value::tuple(parts.iter().map( |p| Value::str(p) ).collect()) )
+}
+
+fn str_partition<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    partition(rt, "partition", &args, false)
+}
+
+fn str_rpartition<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    partition(rt, "rpartition", &args, true)
+}
+
+fn str_encode<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    Ok( Value::Bytes( Rc::from(s.as_bytes()) ) )
+}
+
+fn str_removeprefix<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    arity(rt, "removeprefix", &args[1 ..], 1, 1)?;
+    let p = expect_str(rt, "removeprefix() argument", &args[1])?;
+    Ok( Value::str(s.strip_prefix(&*p).unwrap_or(&s)) )
+}
+
+fn str_removesuffix<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_str(rt, &args)?;
+    arity(rt, "removesuffix", &args[1 ..], 1, 1)?;
+    let p = expect_str(rt, "removesuffix() argument", &args[1])?;
+    Ok( Value::str(s.strip_suffix(&*p).unwrap_or(&s)) )
+}
+
+fn bytes_decode<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    match args.first() {
+        Some( Value::Bytes(b) ) => match std::str::from_utf8(b) {
+            Ok( s ) => Ok( Value::str(s) ),
+            Err( e ) => throw(rt, "UnicodeError", format!("'utf-8' codec can't decode byte 0x{:02x} in position {}: invalid start byte", b[e.valid_up_to()], e.valid_up_to()))
+        },
+        _ => receiver_error(rt, "bytes", &args)
+    }
+}
+
+fn list_append<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let l = recv_list(rt, &args)?;
+    arity(rt, "list.append", &args[1 ..], 1, 1)?;
+    l.borrow_mut().push(args[1].clone());
+    Ok( Value::None )
+}
+
+fn list_extend<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let l = recv_list(rt, &args)?;
+    arity(rt, "list.extend", &args[1 ..], 1, 1)?;
+    let values = collect(rt, &args[1])?;
+    l.borrow_mut().extend(values);
+    Ok( Value::None )
+}
+
+fn list_insert<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let l = recv_list(rt, &args)?;
+    arity(rt, "insert", &args[1 ..], 2, 2)?;
+    let n = l.borrow().len() as i64;
+    let i = to_index(rt, &args[1])?;
+    let i = if i < 0 { ( i + n ).max(0) } else { i.min(n) };
+    l.borrow_mut().insert(i as usize, args[2].clone());
+    Ok( Value::None )
+}
+
+fn list_pop<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let l = recv_list(rt, &args)?;
+    arity(rt, "pop", &args[1 ..], 0, 1)?;
+    let n = l.borrow().len() as i64;
+    if n == 0 {
+        return throw(rt, "IndexError", "pop from empty list")
+    }
+    let i = match args.get(1) {
+        Some( i ) => to_index(rt, i)?,
+        None => -1
+    };
+    let i = if i < 0 { i + n } else { i };
+    if i < 0 || i >= n {
+        return throw(rt, "IndexError", "pop index out of range")
+    }
+    let removed = l.borrow_mut().remove(i as usize);
+    Ok( removed )
+}
+
+fn list_remove<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let l = recv_list(rt, &args)?;
+    arity(rt, "list.remove", &args[1 ..], 1, 1)?;
+    let items = l.borrow().clone();
+    for ( i, v ) in items.iter().enumerate() {
+        if equal(rt, v, &args[1])? {
+            l.borrow_mut().remove(i);
+            return Ok( Value::None )
+        }
+    }
+    throw(rt, "ValueError", "list.remove(x): x not in list")
+}
+
+fn receiver_values<'a>(rt: &dyn Runtime<'a>, args: &[Value<'a>]) -> Result<Vec<Value<'a>>, Value<'a>> {
+    match args.first() {
+        Some( Value::List(l) ) => Ok( l.borrow().clone() ),
+        Some( Value::Tuple(t) ) => Ok( t.to_vec() ),
+        _ => receiver_error(rt, "list", args)
+    }
+}
+
+fn seq_index<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let items = receiver_values(rt, &args)?;
+    arity(rt, "index", &args[1 ..], 1, 3)?;
+    let n = items.len() as i64;
+    let mut bound = |v: Option<&Value<'a>>, default: i64| -> Result<i64, Value<'a>> {
+        Ok( match v {
+            Some( v ) => {
+                let b = to_index(rt, v)?;
+                if b < 0 { ( b + n ).max(0) } else { b.min(n) }
+            },
+            None => default
+        } )
+    };
+    let start = bound(args.get(2), 0)?;
+    let end = bound(args.get(3), n)?;
+    for i in start .. end {
+        if equal(rt, &items[i as usize], &args[1])? {
+            return Ok( Value::int(i) )
+        }
+    }
+    let what = if let Value::Tuple(_) = args[0] { "tuple.index(x): x not in tuple".to_string() } else { format!("{} is not in list", repr(rt, &args[1])?) };
+    throw(rt, "ValueError", what)
+}
+
+fn seq_count<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let items = receiver_values(rt, &args)?;
+    arity(rt, "count", &args[1 ..], 1, 1)?;
+    let mut n = 0;
+    for v in &items {
+        if equal(rt, v, &args[1])? {
+            n += 1
+        }
+    }
+    Ok( Value::int(n) )
+}
+
+fn list_sort<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let l = recv_list(rt, &args)?;
+    arity(rt, "sort", &args[1 ..], 0, 0)?;
+    let named = keywords(rt, "sort", kwargs, &[ "key", "reverse" ])?;
+    let reverse = match &named[1] {
+        Some( r ) => truthy(rt, r)?,
+        None => false
+    };
+    let values = std::mem::take(&mut *l.borrow_mut());
+    let res = sort_values(rt, values.clone(), named[0].as_ref().unwrap_or(&Value::None), reverse);
+    let modified = !l.borrow().is_empty();
+    match res {
+        Ok( sorted ) => *l.borrow_mut() = sorted,
+        Err( e ) => {
+            *l.borrow_mut() = values;
+            return Err( e )
+        }
+    }
+    if modified {
+        return throw(rt, "ValueError", "list modified during sort")
+    }
+    Ok( Value::None )
+}
+
+fn list_reverse<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let l = recv_list(rt, &args)?;
+    l.borrow_mut().reverse();
+    Ok( Value::None )
+}
+
+fn list_copy<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let l = recv_list(rt, &args)?;
+    let items = l.borrow().clone();
+    Ok( Value::list(items) )
+}
+
+fn list_clear<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let l = recv_list(rt, &args)?;
+    l.borrow_mut().clear();
+    Ok( Value::None )
+}
+
+fn dict_get<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let d = recv_dict(rt, &args)?;
+    arity(rt, "get", &args[1 ..], 1, 2)?;
+    let h = hash_key(rt, &args[1])?;
+    let found = d.borrow().get(&h).cloned();
+    Ok( found.unwrap_or_else( || args.get(2).cloned().unwrap_or(Value::None) ) )
+}
+
+fn dict_keys<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    Ok( Value::DictView(recv_dict(rt, &args)?, ViewKind::Keys) )
+}
+
+fn dict_values<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    Ok( Value::DictView(recv_dict(rt, &args)?, ViewKind::Values) )
+}
+
+fn dict_items<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    Ok( Value::DictView(recv_dict(rt, &args)?, ViewKind::Items) )
+}
+
+fn dict_pop<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let d = recv_dict(rt, &args)?;
+    arity(rt, "pop", &args[1 ..], 1, 2)?;
+    let h = hash_key(rt, &args[1])?;
+    let removed = d.borrow_mut().remove(&h);
+    match ( removed, args.get(2) ) {
+        ( Some( ( _, v ) ), _ ) => Ok( v ),
+        ( None, Some( default ) ) => Ok( default.clone() ),
+        ( None, None ) => Err( new_exception(rt.builtins().exception("KeyError"), vec![ args[1].clone() ]) )
+    }
+}
+
+fn dict_popitem<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let d = recv_dict(rt, &args)?;
+    let last = d.borrow_mut().pop_last();
+    match last {
+        Some( ( k, v ) ) => Ok( Value::tuple(vec![ k, v ]) ),
+        None => throw(rt, "KeyError", "popitem(): dictionary is empty")
+    }
+}
+
+fn dict_setdefault<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let d = recv_dict(rt, &args)?;
+    arity(rt, "setdefault", &args[1 ..], 1, 2)?;
+    let h = hash_key(rt, &args[1])?;
+    let found = d.borrow().get(&h).cloned();
+    match found {
+        Some( v ) => Ok( v ),
+        None => {
+            let v = args.get(2).cloned().unwrap_or(Value::None);
+            d.borrow_mut().insert(h, args[1].clone(), v.clone());
+            Ok( v )
+        }
+    }
+}
+
+fn dict_update_method<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let d = recv_dict(rt, &args)?;
+    arity(rt, "update", &args[1 ..], 0, 1)?;
+    if let Some( v ) = args.get(1) {
+        dict_update(rt, &d, v)?
+    }
+    for ( k, v ) in kwargs {
+        let key = Value::str(&k);
+        let h = hash_key(rt, &key)?;
+        d.borrow_mut().insert(h, key, v)
+    }
+    Ok( Value::None )
+}
+
+fn dict_copy<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let d = recv_dict(rt, &args)?;
+    let copy = d.borrow().clone();
+    Ok( Value::dict(copy) )
+}
+
+fn dict_clear<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let d = recv_dict(rt, &args)?;
+    d.borrow_mut().clear();
+    Ok( Value::None )
+}
+
+fn dict_fromkeys<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let args = if let Some( Value::Dict(_) ) = args.first() { &args[1 ..] } else { &args[..] };
+    arity(rt, "fromkeys", args, 1, 2)?;
+    let value = args.get(1).cloned().unwrap_or(Value::None);
+    let mut res = Dict::new();
+    for k in collect(rt, &args[0])? {
+        let h = hash_key(rt, &k)?;
+        res.insert(h, k, value.clone())
+    }
+    Ok( Value::dict(res) )
+}
+
+fn set_add<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_set(rt, &args)?;
+    arity(rt, "set.add", &args[1 ..], 1, 1)?;
+    let h = hash_key(rt, &args[1])?;
+    s.borrow_mut().insert(h, args[1].clone(), Value::None);
+    Ok( Value::None )
+}
+
+fn set_remove<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_set(rt, &args)?;
+    arity(rt, "set.remove", &args[1 ..], 1, 1)?;
+    let h = hash_key(rt, &args[1])?;
+    let removed = s.borrow_mut().remove(&h);
+    match removed {
+        Some( _ ) => Ok( Value::None ),
+        None => Err( new_exception(rt.builtins().exception("KeyError"), vec![ args[1].clone() ]) )
+    }
+}
+
+fn set_discard<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_set(rt, &args)?;
+    arity(rt, "set.discard", &args[1 ..], 1, 1)?;
+    let h = hash_key(rt, &args[1])?;
+    s.borrow_mut().remove(&h);
+    Ok( Value::None )
+}
+
+fn set_pop<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_set(rt, &args)?;
+    let first = s.borrow().iter().next().map( |( h, k, _ )| ( h.clone(), k.clone() ) );
+    match first {
+        Some( ( h, k ) ) => {
+            s.borrow_mut().remove(&h);
+            Ok( k )
+        },
+        None => throw(rt, "KeyError", "pop from an empty set")
+    }
+}
+
+fn set_clear<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    recv_set(rt, &args)?.borrow_mut().clear();
+    Ok( Value::None )
+}
+
+fn set_copy<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let copy = recv_set(rt, &args)?.borrow().clone();
+    Ok( Value::set(copy) )
+}
+
+fn set_update<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let s = recv_set(rt, &args)?;
+    for other in &args[1 ..] {
+        for v in collect(rt, other)? {
+            let h = hash_key(rt, &v)?;
+            s.borrow_mut().insert(h, v, Value::None)
+        }
+    }
+    Ok( Value::None )
+}
+
+/// Applies set operator to receiver and each argument, which may be any iterable.
+fn set_operation<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, op: Operator) -> RunResult<'a> {
+    let s = recv_set(rt, &args)?;
+    let mut res = Value::set(s.borrow().clone());
+    for other in &args[1 ..] {
+        let other = match other {
+            Value::Set(_) => other.clone(),
+            _ => {
+                let values = collect(rt, other)?;
+                Value::set(set_from(rt, values)?)
+            }
+        };
+        res = binary_op(rt, op, &res, &other)?
+    }
+    Ok( res )
+}
+
+fn set_union<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    set_operation(rt, args, Operator::BitOr)
+}
+
+fn set_intersection<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    set_operation(rt, args, Operator::BitAnd)
+}
+
+fn set_difference<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    set_operation(rt, args, Operator::Sub)
+}
+
+fn set_symmetric_difference<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    set_operation(rt, args, Operator::BitXor)
+}
+
+/// Receiver and the single argument as sets.
+fn set_pair<'a>(rt: &mut dyn Runtime<'a>, name: &str, args: &[Value<'a>]) -> Result<( Dict<'a>, Dict<'a> ), Value<'a>> {
+    let s = recv_set(rt, args)?;
+    arity(rt, name, &args[1 ..], 1, 1)?;
+    let values = collect(rt, &args[1])?;
+    let other = set_from(rt, values)?;
+    let s = s.borrow().clone();
+    Ok( ( s, other ) )
+}
+
+fn set_issubset<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let ( s, other ) = set_pair(rt, "issubset", &args)?;
+    let res = s.iter().all( |( h, _, _ )| other.contains(h) );
+    Ok( Value::Bool(res) )
+}
+
+fn set_issuperset<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let ( s, other ) = set_pair(rt, "issuperset", &args)?;
+    let res = other.iter().all( |( h, _, _ )| s.contains(h) );
+    Ok( Value::Bool(res) )
+}
+
+fn set_isdisjoint<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let ( s, other ) = set_pair(rt, "isdisjoint", &args)?;
+    let res = !s.iter().any( |( h, _, _ )| other.contains(h) );
+    Ok( Value::Bool(res) )
+}
+
+fn int_bit_length<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    match args.first() {
+        Some( v @ ( Value::Int(_) | Value::Bool(_) ) ) => Ok( Value::int(int_value(rt, v)?.abs().bit_length() as i64) ),
+        _ => receiver_error(rt, "int", &args)
+    }
+}
+
+fn float_is_integer<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    match args.first() {
+        Some( Value::Float(f) ) => Ok( Value::Bool(f.is_finite() && f.fract() == 0.0) ),
+        _ => receiver_error(rt, "float", &args)
+    }
+}
+
+fn property_setter<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    match args.as_slice() {
+        [ Value::Property(p), f ] => Ok( Value::Property( Rc::new( ( p.0.clone(), f.clone() ) ) ) ),
+        _ => receiver_error(rt, "property", &args)
+    }
+}
+
+fn property_getter<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    match args.as_slice() {
+        [ Value::Property(p), f ] => Ok( Value::Property( Rc::new( ( f.clone(), p.1.clone() ) ) ) ),
+        _ => receiver_error(rt, "property", &args)
+    }
+}
+
+/// Resumes generator and turns its return into 'StopIteration'.
+fn resume_generator<'a>(rt: &mut dyn Runtime<'a>, args: &[Value<'a>], input: Resume<'a>) -> RunResult<'a> {
+    let g = match args.first() {
+        Some( Value::Generator(g) ) => g.clone(),
+        _ => return receiver_error(rt, "generator", args)
+    };
+    match rt.resume(&g, input)? {
+        Step::Yield(v) => Ok( v ),
+        Step::Return(v) => Err( new_exception(rt.builtins().exception("StopIteration"), if v.is_none() { Vec::new() } else { vec![ v ] }) )
+    }
+}
+
+fn generator_send<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    arity(rt, "send", &args[1 ..], 1, 1)?;
+    resume_generator(rt, &args, Resume::Send(args[1].clone()))
+}
+
+fn generator_throw<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    arity(rt, "throw", &args[1 ..], 1, 3)?;
+    let exception = match &args[1] {
+        Value::Class(c) if c.kind == TypeKind::Exception => {
+            let params = args.get(2).map( |v| vec![ v.clone() ] ).unwrap_or_default();
+            call(rt, &args[1], params, Vec::new())?
+        },
+        v @ Value::Instance(i) if i.class.kind == TypeKind::Exception => v.clone(),
+        _ => return throw(rt, "TypeError", "exceptions must be classes or instances deriving from BaseException, not str")
+    };
+    resume_generator(rt, &args, Resume::Throw(exception))
+}
+
+fn generator_close<'a>(rt: &mut dyn Runtime<'a>, args: Vec<Value<'a>>, _kwargs: Kwargs<'a>) -> RunResult<'a> {
+    let exit = new_exception(rt.builtins().exception("GeneratorExit"), Vec::new());
+    match resume_generator(rt, &args, Resume::Throw(exit)) {
+        Ok( _ ) => throw(rt, "RuntimeError", "generator ignored GeneratorExit"),
+        Err( e ) if is_exception(rt, &e, "GeneratorExit") || is_exception(rt, &e, "StopIteration") => Ok( Value::None ),
+        Err( e ) => Err( e )
+    }
+}
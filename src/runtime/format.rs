@@ -0,0 +1,497 @@
+use crate::runtime::bigint::Int;
+use crate::runtime::value::{ Value, Runtime };
+use crate::runtime::ops::{ throw, repr, to_str, call_special, type_name };
+
+
+/// Parsed format specification of the mini language used by 'format' and f-strings,
+/// '[[fill]align][sign][#][0][width][grouping][.precision][type]'.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatSpec {
+    pub fill: char,
+    pub align: Option<char>,
+    pub sign: char,
+    pub alternate: bool,
+    pub width: usize,
+    pub grouping: Option<char>,
+    pub precision: Option<usize>,
+    pub kind: Option<char>
+}
+
+
+/// Shortest text that reads back as the same float, in the notation CPython 'repr' picks.
+pub fn float_repr(f: f64) -> String {
+    if f.is_nan() {
+        return "nan".to_string()
+    }
+    if f.is_infinite() {
+        return if f > 0.0 { "inf".to_string() } else { "-inf".to_string() }
+    }
+    if f == 0.0 {
+        return if f.is_sign_negative() { "-0.0".to_string() } else { "0.0".to_string() }
+    }
+    let sci = format!("{:e}", f);
+    let ( mantissa, exponent ) = sci.split_once('e').unwrap();
+    let exponent : i32 = exponent.parse().unwrap();
+    let sign = if mantissa.starts_with('-') { "-" } else { "" };
+    let digits : String = mantissa.chars().filter( |c| c.is_ascii_digit() ).collect();
+    if ( -4 .. 16 ).contains(&exponent) {
+        let ( int_part, frac ) = if exponent >= 0 {
+            let e = exponent as usize + 1;
+            if digits.len() <= e {
+                ( format!("{}{}", digits, "0".repeat(e - digits.len())), "0".to_string() )
+            } else {
+                ( digits[.. e].to_string(), digits[e ..].to_string() )
+            }
+        } else {
+            ( "0".to_string(), format!("{}{}", "0".repeat(( -exponent - 1 ) as usize), digits) )
+        };
+        format!("{}{}.{}", sign, int_part, frac)
+    } else {
+        let m = if digits.len() == 1 { digits } else { format!("{}.{}", &digits[.. 1], &digits[1 ..]) };
+        format!("{}{}e{}{:02}", sign, m, if exponent < 0 { '-' } else { '+' }, exponent.abs())
+    }
+}
+
+/// String literal for text, quoted and escaped like CPython 'repr'.
+pub fn str_repr(s: &str) -> String {
+    let quote = if s.contains('\'') && !s.contains('"') { '"' } else { '\'' };
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push(quote);
+    for c in s.chars() {
+        match c {
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            _ if c == quote => {
+                res.push('\\');
+                res.push(c)
+            },
+            _ if ( c as u32 ) < 0x20 || ( 0x7f ..= 0xa0 ).contains(&( c as u32 )) => res.push_str(&format!("\\x{:02x}", c as u32)),
+            _ => res.push(c)
+        }
+    }
+    res.push(quote);
+    res
+}
+
+pub fn bytes_repr(b: &[u8]) -> String {
+    let quote = if b.contains(&b'\'') && !b.contains(&b'"') { '"' } else { '\'' };
+    let mut res = String::from("b");
+    res.push(quote);
+    for c in b {
+        match *c {
+            b'\\' => res.push_str("\\\\"),
+            b'\n' => res.push_str("\\n"),
+            b'\r' => res.push_str("\\r"),
+            b'\t' => res.push_str("\\t"),
+            c if c as char == quote => {
+                res.push('\\');
+                res.push(c as char)
+            },
+            0x20 ..= 0x7e => res.push(*c as char),
+            _ => res.push_str(&format!("\\x{:02x}", c))
+        }
+    }
+    res.push(quote);
+    res
+}
+
+impl FormatSpec {
+    pub fn parse(spec: &str) -> Option<FormatSpec> {
+        let chars : Vec<char> = spec.chars().collect();
+        let mut res = FormatSpec { fill: ' ', align: None, sign: '-', alternate: false, width: 0, grouping: None, precision: None, kind: None };
+        let mut i = 0;
+        let is_align = |c: char| matches!(c, '<' | '>' | '^' | '=');
+        if chars.len() >= 2 && is_align(chars[1]) {
+            res.fill = chars[0];
+            res.align = Some( chars[1] );
+            i = 2
+        } else if !chars.is_empty() && is_align(chars[0]) {
+            res.align = Some( chars[0] );
+            i = 1
+        }
+        if i < chars.len() && matches!(chars[i], '+' | '-' | ' ') {
+            res.sign = chars[i];
+            i += 1
+        }
+        if i < chars.len() && chars[i] == '#' {
+            res.alternate = true;
+            i += 1
+        }
+        if i < chars.len() && chars[i] == '0' {
+            if res.align.is_none() {
+                res.fill = '0';
+                res.align = Some( '=' )
+            }
+            i += 1
+        }
+        let start = i;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1
+        }
+        if i > start {
+            res.width = chars[start .. i].iter().collect::<String>().parse().ok()?
+        }
+        if i < chars.len() && matches!(chars[i], ',' | '_') {
+            res.grouping = Some( chars[i] );
+            i += 1
+        }
+        if i < chars.len() && chars[i] == '.' {
+            i += 1;
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1
+            }
+            if i == start {
+                return None
+            }
+            res.precision = Some( chars[start .. i].iter().collect::<String>().parse().ok()? )
+        }
+        if i < chars.len() {
+            res.kind = Some( chars[i] );
+            i += 1
+        }
+        if i < chars.len() { None } else { Some( res ) }
+    }
+
+    /// Pads text to width, 'sign' is the sign and prefix which '=' alignment puts before the padding.
+    fn pad(&self, sign: &str, body: &str, default_align: char) -> String {
+        let len = sign.chars().count() + body.chars().count();
+        if len >= self.width {
+            return format!("{}{}", sign, body)
+        }
+        let fill = self.fill.to_string().repeat(self.width - len);
+        match self.align.unwrap_or(default_align) {
+            '<' => format!("{}{}{}", sign, body, fill),
+            '^' => {
+                let left = ( self.width - len ) / 2;
+                let fills : Vec<char> = fill.chars().collect();
+                format!("{}{}{}{}", fills[.. left].iter().collect::<String>(), sign, body, fills[left ..].iter().collect::<String>())
+            },
+            '=' => format!("{}{}{}", sign, fill, body),
+            _ => format!("{}{}{}", fill, sign, body)
+        }
+    }
+
+    fn sign_of(&self, negative: bool) -> &'static str {
+        match ( negative, self.sign ) {
+            ( true, _ ) => "-",
+            ( false, '+' ) => "+",
+            ( false, ' ' ) => " ",
+            _ => ""
+        }
+    }
+}
+
+fn group_digits(digits: &str, separator: char, size: usize) -> String {
+    let chars : Vec<char> = digits.chars().collect();
+    let mut res = String::new();
+    for ( i, c ) in chars.iter().enumerate() {
+        if i > 0 && ( chars.len() - i ).is_multiple_of(size) {
+            res.push(separator)
+        }
+        res.push(*c)
+    }
+    res
+}
+
+/// Integer formatted by spec, None when the presentation type does not apply to integers.
+fn format_int(value: &Int, spec: &FormatSpec) -> Option<String> {
+    let ( radix, prefix ) = match spec.kind {
+        None | Some( 'd' ) | Some( 'n' ) => ( 10, "" ),
+        Some( 'b' ) => ( 2, "0b" ),
+        Some( 'o' ) => ( 8, "0o" ),
+        Some( 'x' ) => ( 16, "0x" ),
+        Some( 'X' ) => ( 16, "0X" ),
+        Some( 'c' ) => {
+            let c = char::from_u32(value.to_i64()? as u32)?;
+            return Some( spec.pad("", &c.to_string(), '<') )
+        },
+        _ => return None
+    };
+    let mut digits = value.abs().to_string_radix(radix);
+    if spec.kind == Some( 'X' ) {
+        digits = digits.to_uppercase()
+    }
+    if let Some( g ) = spec.grouping {
+        digits = group_digits(&digits, g, if radix == 10 { 3 } else { 4 })
+    }
+    let sign = format!("{}{}", spec.sign_of(value.is_negative()), if spec.alternate { prefix } else { "" });
+    Some( spec.pad(&sign, &digits, '>') )
+}
+
+fn strip_zeros(text: &str) -> String {
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Float in exponent notation with at least two exponent digits.
+fn exponent_notation(f: f64, precision: usize, upper: bool) -> String {
+    let text = format!("{:.*e}", precision, f);
+    let ( mantissa, exponent ) = text.split_once('e').unwrap();
+    let exponent : i32 = exponent.parse().unwrap();
+    format!("{}{}{}{:02}", mantissa, if upper { 'E' } else { 'e' }, if exponent < 0 { '-' } else { '+' }, exponent.abs())
+}
+
+fn general_notation(f: f64, precision: usize, alternate: bool, upper: bool) -> String {
+    let p = precision.max(1);
+    let exponent : i32 = format!("{:.*e}", p - 1, f).split_once('e').unwrap().1.parse().unwrap();
+    if -4 <= exponent && exponent < p as i32 {
+        let text = format!("{:.*}", ( p as i32 - 1 - exponent ) as usize, f);
+        if alternate { text } else { strip_zeros(&text) }
+    } else {
+        let text = exponent_notation(f, p - 1, upper);
+        if alternate {
+            return text
+        }
+        let ( mantissa, exponent ) = text.split_at(text.find(['e', 'E']).unwrap());
+        format!("{}{}", strip_zeros(mantissa), exponent)
+    }
+}
+
+/// Float formatted by spec, None when the presentation type does not apply to floats.
+fn format_float(f: f64, spec: &FormatSpec) -> Option<String> {
+    let upper = matches!(spec.kind, Some( 'F' ) | Some( 'E' ) | Some( 'G' ));
+    let magnitude = f.abs();
+    let mut body = if !f.is_finite() {
+        let text = if f.is_nan() { "nan" } else { "inf" };
+        if upper { text.to_uppercase() } else { text.to_string() }
+    } else {
+        match spec.kind {
+            Some( 'f' ) | Some( 'F' ) => format!("{:.*}", spec.precision.unwrap_or(6), magnitude),
+            Some( 'e' ) | Some( 'E' ) => exponent_notation(magnitude, spec.precision.unwrap_or(6), upper),
+            Some( 'g' ) | Some( 'G' ) | Some( 'n' ) => general_notation(magnitude, spec.precision.unwrap_or(6), spec.alternate, upper),
+            Some( '%' ) => format!("{:.*}%", spec.precision.unwrap_or(6), magnitude * 100.0),
+            None => match spec.precision {
+                Some( p ) => {
+                    let text = general_notation(magnitude, p, spec.alternate, false);
+                    if text.contains(['.', 'e', 'n']) { text } else { format!("{}.0", text) }
+                },
+                None => float_repr(magnitude)
+            },
+            _ => return None
+        }
+    };
+    if let Some( g ) = spec.grouping {
+        let end = body.find(|c: char| !c.is_ascii_digit()).unwrap_or(body.len());
+        body = format!("{}{}", group_digits(&body[.. end], g, 3), &body[end ..])
+    }
+    Some( spec.pad(spec.sign_of(f.is_sign_negative() && !f.is_nan()), &body, '>') )
+}
+
+/// Value formatted by the format mini language, as builtin 'format' does.
+pub fn format_value<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>, spec: &str) -> Result<String, Value<'a>> {
+    if let Some( res ) = call_special(rt, value, "__format__", vec![ Value::str(spec) ])? {
+        return match res {
+            Value::Str(s) => Ok( s.to_string() ),
+            _ => throw(rt, "TypeError", format!("__format__ must return a str, not {}", type_name(rt, &res)))
+        }
+    }
+    if spec.is_empty() {
+        return to_str(rt, value)
+    }
+    let parsed = match FormatSpec::parse(spec) {
+        Some( p ) => p,
+        None => return throw(rt, "ValueError", "Invalid format specifier")
+    };
+    let res = match value {
+        Value::Int(_) | Value::Bool(_) if matches!(parsed.kind, Some( 'e' | 'E' | 'f' | 'F' | 'g' | 'G' | '%' )) => {
+            let f = match value { Value::Int(i) => i.to_f64(), Value::Bool(b) => *b as i64 as f64, _ => 0.0 };
+            format_float(f, &parsed)
+        },
+        Value::Int(i) => format_int(i, &parsed),
+        Value::Bool(b) if parsed.kind.is_some() => format_int(&Int::from(*b as i64), &parsed),
+        Value::Float(f) => format_float(*f, &parsed),
+        Value::Str(_) | Value::Bool(_) | Value::None if matches!(parsed.kind, None | Some( 's' )) && parsed.sign == '-' && parsed.grouping.is_none() => {
+            let text = to_str(rt, value)?;
+            let text : String = match parsed.precision {
+                Some( p ) => text.chars().take(p).collect(),
+                None => text
+            };
+            Some( parsed.pad("", &text, '<') )
+        },
+        _ => None
+    };
+    match res {
+        Some( s ) => Ok( s ),
+        None => match ( value, parsed.kind ) {
+            ( Value::Int(_) | Value::Float(_) | Value::Str(_), Some( k ) ) =>
+                throw(rt, "ValueError", format!("Unknown format code '{}' for object of type '{}'", k, type_name(rt, value))),
+            _ => throw(rt, "TypeError", format!("unsupported format string passed to {}.__format__", type_name(rt, value)))
+        }
+    }
+}
+
+/// Applies printf style formatting of 'text % args'.
+pub fn percent_format<'a>(rt: &mut dyn Runtime<'a>, text: &str, args: &Value<'a>) -> Result<String, Value<'a>> {
+    let ( values, mapping ) = match args {
+        Value::Tuple(t) => ( t.to_vec(), None ),
+        Value::Dict(_) => ( vec![ args.clone() ], Some( args.clone() ) ),
+        _ => ( vec![ args.clone() ], None )
+    };
+    let chars : Vec<char> = text.chars().collect();
+    let mut res = String::new();
+    let mut next = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '%' {
+            res.push(chars[i]);
+            i += 1;
+            continue
+        }
+        i += 1;
+        let mut value = None;
+        if i < chars.len() && chars[i] == '(' {
+            let start = i + 1;
+            while i < chars.len() && chars[i] != ')' {
+                i += 1
+            }
+            if i >= chars.len() {
+                return throw(rt, "ValueError", "incomplete format key")
+            }
+            let key : String = chars[start .. i].iter().collect();
+            i += 1;
+            value = match &mapping {
+                Some( m ) => Some( crate::runtime::ops::get_item(rt, m, &Value::str(&key))? ),
+                None => return throw(rt, "TypeError", "format requires a mapping")
+            }
+        }
+        let mut spec = FormatSpec { fill: ' ', align: Some( '>' ), sign: '-', alternate: false, width: 0, grouping: None, precision: None, kind: None };
+        let mut zero = false;
+        while i < chars.len() && matches!(chars[i], '-' | '+' | ' ' | '#' | '0') {
+            match chars[i] {
+                '-' => spec.align = Some( '<' ),
+                '+' => spec.sign = '+',
+                ' ' => if spec.sign != '+' { spec.sign = ' ' },
+                '#' => spec.alternate = true,
+                _ => zero = true
+            }
+            i += 1
+        }
+        let start = i;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1
+        }
+        spec.width = chars[start .. i].iter().collect::<String>().parse().unwrap_or(0);
+        if i < chars.len() && chars[i] == '.' {
+            i += 1;
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1
+            }
+            spec.precision = Some( chars[start .. i].iter().collect::<String>().parse().unwrap_or(0) )
+        }
+        let kind = match chars.get(i) {
+            Some( c ) => *c,
+            None => return throw(rt, "ValueError", "incomplete format")
+        };
+        i += 1;
+        if kind == '%' {
+            res.push('%');
+            continue
+        }
+        let value = match value {
+            Some( v ) => v,
+            None => match values.get(next) {
+                Some( v ) => {
+                    next += 1;
+                    v.clone()
+                },
+                None => return throw(rt, "TypeError", "not enough arguments for format string")
+            }
+        };
+        if zero && spec.align == Some( '>' ) && !matches!(kind, 's' | 'r' | 'a' | 'c') {
+            spec.fill = '0';
+            spec.align = Some( '=' )
+        }
+        let text = match kind {
+            's' | 'r' | 'a' => {
+                let text = if kind == 's' { to_str(rt, &value)? } else { repr(rt, &value)? };
+                let text : String = match spec.precision {
+                    Some( p ) => text.chars().take(p).collect(),
+                    None => text
+                };
+                spec.pad("", &text, '>')
+            },
+            'd' | 'i' | 'u' | 'x' | 'X' | 'o' | 'c' => {
+                let n = match &value {
+                    Value::Int(n) => n.clone(),
+                    Value::Bool(b) => Int::from(*b as i64),
+                    Value::Float(f) if kind != 'c' && f.is_finite() => Int::from_f64(*f),
+                    Value::Str(s) if kind == 'c' && s.chars().count() == 1 => Int::from(s.chars().next().unwrap() as i64),
+                    _ => {
+                        let message = if kind == 'c' { "%c requires int or char".to_string() } else {
+                            format!("%{} format: {} is required, not {}", kind, if matches!(kind, 'x' | 'X' | 'o') { "an integer" } else { "a real number" }, type_name(rt, &value))
+                        };
+                        return throw(rt, "TypeError", message)
+                    }
+                };
+                spec.kind = if matches!(kind, 'i' | 'u') { Some( 'd' ) } else { Some( kind ) };
+                spec.precision = None;
+                format_int(&n, &spec).unwrap_or_default()
+            },
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' => {
+                let f = match &value {
+                    Value::Int(n) => n.to_f64(),
+                    Value::Bool(b) => *b as i64 as f64,
+                    Value::Float(f) => *f,
+                    _ => return throw(rt, "TypeError", format!("must be real number, not {}", type_name(rt, &value)))
+                };
+                spec.kind = Some( kind );
+                if spec.precision.is_none() {
+                    spec.precision = Some( 6 )
+                }
+                format_float(f, &spec).unwrap_or_default()
+            },
+            _ => return throw(rt, "ValueError", format!("unsupported format character '{}' ({:#x}) at index {}", kind, kind as u32, i - 1))
+        };
+        res.push_str(&text)
+    }
+    if next < values.len() && mapping.is_none() {
+        return throw(rt, "TypeError", "not all arguments converted during string formatting")
+    }
+    Ok( res )
+}
+
+
+// UnitTests for formatting of values //////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::format::{ float_repr, str_repr, FormatSpec };
+
+
+    #[test]
+    fn format_float_repr() {
+        assert_eq!(float_repr(0.1), "0.1");
+        assert_eq!(float_repr(1.0), "1.0");
+        assert_eq!(float_repr(-2.5), "-2.5");
+        assert_eq!(float_repr(1e16), "1e+16");
+        assert_eq!(float_repr(1234567890123456.0), "1234567890123456.0");
+        assert_eq!(float_repr(0.0001), "0.0001");
+        assert_eq!(float_repr(0.00001), "1e-05");
+        assert_eq!(float_repr(1.5e-10), "1.5e-10");
+        assert_eq!(float_repr(f64::INFINITY), "inf")
+    }
+
+    #[test]
+    fn format_str_repr() {
+        assert_eq!(str_repr("abc"), "'abc'");
+        assert_eq!(str_repr("it's"), "\"it's\"");
+        assert_eq!(str_repr("a'\"b\n"), "'a\\'\"b\\n'")
+    }
+
+    #[test]
+    fn format_spec_parse() {
+        let spec = FormatSpec::parse("*^+#012,.3f").unwrap();
+        assert_eq!(( spec.fill, spec.align, spec.sign, spec.alternate, spec.width ), ( '*', Some( '^' ), '+', true, 12 ));
+        assert_eq!(( spec.grouping, spec.precision, spec.kind ), ( Some( ',' ), Some( 3 ), Some( 'f' ) ));
+        assert_eq!(FormatSpec::parse("08").unwrap().align, Some( '=' ));
+        assert!(FormatSpec::parse("10.").is_none());
+        assert!(FormatSpec::parse("dd").is_none())
+    }
+}
@@ -0,0 +1,1619 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::ast::nodes::{ Mod, Stmt, StmtKind, Expr, ExprKind, Constant, Comprehension, Arguments, Keyword, ExceptHandler, WithItem, BoolOperator, Operator };
+use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+use crate::api::{ Mode, parse };
+use crate::semantic::symbols::{ SymbolTable, ScopeId, ScopeKind, SymbolScope };
+use crate::runtime::bigint::Int;
+use crate::runtime::value::{ Value, RunResult, Runtime, Builtins, Cell, Globals, Code, Function, Signature, Generator, Suspension, Resume, Step, TypeKind, Dict, TracebackEntry };
+use crate::runtime::ops::{ throw, error, call, bind_arguments, truthy, repr, to_str, binary_op, inplace_op, unary_op, compare, hash_key, get_attr,
+                           set_attr, del_attr, get_item, set_item, del_item, iter, next, collect, dict_update, set_from, is_instance,
+                           is_exception, is_same, class_of, type_name };
+use crate::runtime::builtins::new_class;
+use crate::runtime::format::format_value;
+
+
+/// Depth of nested calls after which 'RecursionError' is raised, as in CPython.
+pub const RECURSION_LIMIT: usize = 1000;
+
+/// Stack of the thread running code, deep enough for the recursion limit in debug builds.
+pub const STACK_SIZE: usize = 512 * 1024 * 1024;
+
+/// Namespace and position of code being run.
+struct Frame<'a> {
+    scope: ScopeId,
+    kind: ScopeKind,
+    name: String,
+    /// Prefix of the qualified names of functions and classes defined in the frame.
+    prefix: String,
+    locals: HashMap<String, Value<'a>>,
+    cells: HashMap<String, Cell<'a>>,
+    globals: Globals<'a>,
+    /// First argument of the call, the receiver of zero argument 'super()'.
+    first_arg: Option<Value<'a>>,
+    line: u32
+}
+
+/// Construct in progress in a frame. Tasks form a stack instead of nested calls so that a
+/// generator can suspend at 'yield' and continue later from the same place.
+enum Task<'a> {
+    Block(&'a [Stmt], usize),
+    While(&'a Stmt),
+    For(&'a Stmt, Value<'a>),
+    Try(&'a Stmt, TryPhase<'a>),
+    /// Body of 'with' statement, holding the bound '__exit__' method.
+    With(Value<'a>),
+    /// Generator suspended at 'yield', waiting for the sent value.
+    Resume(Action<'a>),
+    YieldFrom(Value<'a>, Action<'a>),
+    /// Generator expression with an iterator for each entered 'for' clause.
+    Generate(&'a Expr, &'a [Comprehension], Vec<Value<'a>>)
+}
+
+enum TryPhase<'a> {
+    Body,
+    Else,
+    Handler(Option<&'a str>),
+    Finally(Option<Completion<'a>>)
+}
+
+/// Use of the value a suspended 'yield' or 'yield from' evaluates to.
+#[derive(Clone, Copy)]
+enum Action<'a> {
+    Discard,
+    Assign(&'a [Expr]),
+    Return
+}
+
+/// Abrupt completion of statements, unwound through the tasks that handle it.
+enum Completion<'a> {
+    Break,
+    Continue,
+    Return(Value<'a>),
+    Raise(Value<'a>)
+}
+
+enum Signal<'a> {
+    Complete(Completion<'a>),
+    Yield(Value<'a>)
+}
+
+enum Outcome<'a> {
+    Yield(Value<'a>),
+    Done(Option<Completion<'a>>)
+}
+
+/// Frame and tasks of a generator between resumptions, with the exceptions its 'except' clauses
+/// were handling.
+pub struct Suspended<'a> {
+    frame: Frame<'a>,
+    tasks: Vec<Task<'a>>,
+    handled: Vec<Value<'a>>,
+    started: bool
+}
+
+/// Tree walking interpreter of the lowered abstract tree. Names are resolved with the symbol
+/// table of the module, so locals, cells and globals behave as in CPython.
+pub struct Interpreter<'a> {
+    symbols: &'a SymbolTable,
+    builtins: Rc<Builtins<'a>>,
+    globals: Globals<'a>,
+    /// Exceptions being handled by 'except' clauses and 'finally' blocks, innermost last.
+    handled: Vec<Value<'a>>,
+    depth: usize,
+    output: String
+}
+
+
+/// Runs module on a thread with a stack deep enough for the recursion limit. Returns the printed
+/// output and the traceback of an uncaught exception.
+pub fn run_source(source: &str, file: &str) -> ( String, Result<(), String> ) {
+    let ( source, file ) = ( source.to_string(), file.to_string() );
+    let worker = std::thread::Builder::new().stack_size(STACK_SIZE).spawn(move || {
+        let tree = match parse(&source, Mode::Exec).tree {
+            Some( t ) => t,
+            None => return ( String::new(), Err( "SyntaxError: invalid syntax\n".to_string() ) )
+        };
+        let module = match PythonCoreLowering::new(&source).lower_mod(&tree) {
+            Ok( m ) => m,
+            Err( e ) => return ( String::new(), Err( format!("{}\n", e) ) )
+        };
+        let symbols = SymbolTable::build(&module, &source);
+        let mut interpreter = Interpreter::new(&symbols);
+        let res = interpreter.run_module(&module).map_err( |e| interpreter.traceback(&e, &file, &source) );
+        ( interpreter.take_output(), res )
+    });
+    match worker.map( |w| w.join() ) {
+        Ok( Ok( res ) ) => res,
+        _ => ( String::new(), Err( "Fatal Python error: interpreter thread failed\n".to_string() ) )
+    }
+}
+
+/// Traceback of uncaught exception as CPython prints it, chained causes and contexts first.
+pub fn format_traceback<'a>(rt: &mut dyn Runtime<'a>, exception: &Value<'a>, file: &str, source: &str) -> String {
+    let lines : Vec<&str> = source.lines().collect();
+    let mut chain = vec![ ( exception.clone(), "" ) ];
+    let mut current = exception.clone();
+    while let Value::Instance(i) = &current {
+        let dict = i.dict.borrow();
+        let suppress = matches!(dict.get("__suppress_context__"), Some( Value::Bool(true) ));
+        let next = match ( dict.get("__cause__"), dict.get("__context__") ) {
+            ( Some( c ), _ ) if !c.is_none() => ( c.clone(), "\nThe above exception was the direct cause of the following exception:\n\n" ),
+            ( _, Some( c ) ) if !suppress && !c.is_none() => ( c.clone(), "\nDuring handling of the above exception, another exception occurred:\n\n" ),
+            _ => break
+        };
+        drop(dict);
+        if chain.iter().any( |( e, _ )| is_same(e, &next.0) ) {
+            break
+        }
+        current = next.0.clone();
+        chain.push(next)
+    }
+    let mut res = String::new();
+    for ( e, separator ) in chain.iter().rev() {
+        res.push_str(&format_exception(rt, e, file, &lines));
+        res.push_str(separator)
+    }
+    res
+}
+
+fn format_exception<'a>(rt: &mut dyn Runtime<'a>, exception: &Value<'a>, file: &str, lines: &[&str]) -> String {
+    let mut res = String::new();
+    if let Value::Instance(i) = exception {
+        let entries = i.traceback.borrow().clone();
+        if !entries.is_empty() {
+            res.push_str("Traceback (most recent call last):\n");
+            for entry in entries.iter().rev() {
+                res.push_str(&format!("  File \"{}\", line {}, in {}\n", file, entry.line, entry.name));
+                if let Some( line ) = lines.get(( entry.line as usize ).wrapping_sub(1)) {
+                    if !line.trim().is_empty() {
+                        res.push_str(&format!("    {}\n", line.trim()))
+                    }
+                }
+            }
+        }
+    }
+    let name = class_of(rt, exception).name.clone();
+    let text = to_str(rt, exception).unwrap_or_else( |_| "<exception str() failed>".to_string() );
+    if text.is_empty() {
+        res.push_str(&format!("{}\n", name))
+    } else {
+        res.push_str(&format!("{}: {}\n", name, text))
+    }
+    res
+}
+
+/// Sets exception being handled as '__context__' of raised exception, unless it already has one or
+/// the link would close a cycle.
+pub fn link_context<'a>(handled: &Value<'a>, exception: &Value<'a>) {
+    if let Value::Instance(i) = exception {
+        if is_same(handled, exception) || handled.is_none() || i.dict.borrow().contains_key("__context__") {
+            return
+        }
+        let mut link = handled.clone();
+        loop {
+            let context = match &link {
+                Value::Instance(h) => h.dict.borrow().get("__context__").cloned(),
+                _ => None
+            };
+            match context {
+                Some( c ) if is_same(&c, exception) => return,
+                Some( c ) if !c.is_none() => link = c,
+                _ => break
+            }
+        }
+        i.dict.borrow_mut().insert("__context__".to_string(), handled.clone());
+    }
+}
+
+fn is_yield(expr: &Expr) -> bool {
+    matches!(expr.node, ExprKind::Yield { .. } | ExprKind::YieldFrom { .. })
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(symbols: &'a SymbolTable) -> Interpreter<'a> {
+        let mut globals = HashMap::new();
+        globals.insert("__name__".to_string(), Value::str("__main__"));
+        Interpreter {
+            symbols,
+            builtins: Rc::new( Builtins::new() ),
+            globals: Rc::new( RefCell::new(globals) ),
+            handled: Vec::new(),
+            depth: 0,
+            output: String::new()
+        }
+    }
+
+    /// Runs module body in the globals of the interpreter, returns the uncaught exception.
+    pub fn run_module(&mut self, module: &'a Mod) -> Result<(), Value<'a>> {
+        let body = match module {
+            Mod::Module { body, .. } | Mod::Interactive { body } => body,
+            _ => return Ok(())
+        };
+        let mut frame = Frame {
+            scope: 0,
+            kind: ScopeKind::Module,
+            name: "<module>".to_string(),
+            prefix: String::new(),
+            locals: HashMap::new(),
+            cells: HashMap::new(),
+            globals: self.globals.clone(),
+            first_arg: None,
+            line: 0
+        };
+        let mut tasks = vec![ Task::Block(body, 0) ];
+        match self.execute(&mut frame, &mut tasks, None) {
+            Outcome::Done(Some( Completion::Raise(e) )) => Err( e ),
+            _ => Ok(())
+        }
+    }
+
+    /// Output printed since last call.
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Traceback of uncaught exception as CPython prints it, chained causes and contexts first.
+    pub fn traceback(&mut self, exception: &Value<'a>, file: &str, source: &str) -> String {
+        format_traceback(self, exception, file, source)
+    }
+
+    /// Records the frame in the traceback of exception entering it.
+    fn record(&self, frame: &Frame<'a>, exception: &Value<'a>) {
+        if let Value::Instance(i) = exception {
+            i.traceback.borrow_mut().push(TracebackEntry { line: frame.line, name: frame.name.clone() })
+        }
+    }
+
+    /// Records where exception entered the frame and links it to the exception being handled.
+    fn raised(&mut self, frame: &mut Frame<'a>, exception: &Value<'a>) {
+        self.record(frame, exception);
+        if let Some( handled ) = self.handled.last() {
+            link_context(handled, exception)
+        }
+    }
+
+    fn scope_of(&self, location: ( u32, u32 ), frame: &Frame<'a>) -> ScopeId {
+        self.symbols.scope_of_node(location).unwrap_or(frame.scope)
+    }
+
+    /// Cells of the free variables of scope defined in frame.
+    fn closure(&self, frame: &Frame<'a>, scope: ScopeId) -> HashMap<String, Cell<'a>> {
+        self.symbols.scope(scope).symbols.iter()
+            .filter( |s| matches!(s.scope, SymbolScope::Free | SymbolScope::Nonlocal) )
+            .map( |s| ( s.name.clone(), frame.cells.get(&s.name).cloned().unwrap_or_default() ) )
+            .collect()
+    }
+
+    /// Frame of function like scope, with its free variables taken from the closure and new cells
+    /// for its own variables used by nested scopes.
+    fn new_frame(&self, scope: ScopeId, name: &str, prefix: String, closure: HashMap<String, Cell<'a>>, globals: Globals<'a>, line: u32) -> Frame<'a> {
+        let info = self.symbols.scope(scope);
+        let mut cells = closure;
+        for s in info.symbols.iter().filter( |s| s.scope == SymbolScope::Cell ) {
+            cells.insert(s.name.clone(), Rc::new( RefCell::new(None) ));
+        }
+        Frame { scope, kind: info.kind, name: name.to_string(), prefix, locals: HashMap::new(), cells, globals, first_arg: None, line }
+    }
+
+
+    // Names ///////////////////////////////////////////////////////////////////////////////////////
+
+    fn symbol_scope(&self, frame: &Frame<'a>, name: &str) -> ( Option<SymbolScope>, bool ) {
+        match self.symbols.lookup(frame.scope, name) {
+            Some( s ) => ( Some( s.scope ), s.is_declared_global ),
+            None => ( None, false )
+        }
+    }
+
+    fn load_global(&self, frame: &Frame<'a>, name: &str) -> RunResult<'a> {
+        if let Some( v ) = frame.globals.borrow().get(name) {
+            return Ok( v.clone() )
+        }
+        match self.builtins.namespace.get(name) {
+            Some( v ) => Ok( v.clone() ),
+            None => throw(self, "NameError", format!("name '{}' is not defined", name))
+        }
+    }
+
+    fn load_cell(&self, frame: &Frame<'a>, name: &str, kind: Option<SymbolScope>) -> RunResult<'a> {
+        if let Some( v ) = frame.cells.get(name).and_then( |c| c.borrow().clone() ) {
+            return Ok( v )
+        }
+        match kind {
+            Some( SymbolScope::Cell ) => throw(self, "UnboundLocalError", format!("cannot access local variable '{}' where it is not associated with a value", name)),
+            _ => throw(self, "NameError", format!("cannot access free variable '{}' where it is not associated with a value in enclosing scope", name))
+        }
+    }
+
+    fn load_name(&self, frame: &Frame<'a>, name: &str) -> RunResult<'a> {
+        let ( kind, declared_global ) = self.symbol_scope(frame, name);
+        match frame.kind {
+            ScopeKind::Module => self.load_global(frame, name),
+            ScopeKind::Class => {
+                if !declared_global {
+                    if let Some( v ) = frame.locals.get(name) {
+                        return Ok( v.clone() )
+                    }
+                    if kind == Some( SymbolScope::Free ) {
+                        return self.load_cell(frame, name, kind)
+                    }
+                }
+                self.load_global(frame, name)
+            },
+            _ => match kind {
+                Some( SymbolScope::Local ) => match frame.locals.get(name) {
+                    Some( v ) => Ok( v.clone() ),
+                    None => throw(self, "UnboundLocalError", format!("cannot access local variable '{}' where it is not associated with a value", name))
+                },
+                Some( SymbolScope::Cell | SymbolScope::Free | SymbolScope::Nonlocal ) => self.load_cell(frame, name, kind),
+                _ => self.load_global(frame, name)
+            }
+        }
+    }
+
+    fn store_name(&self, frame: &mut Frame<'a>, name: &str, value: Value<'a>) {
+        let ( kind, declared_global ) = self.symbol_scope(frame, name);
+        match frame.kind {
+            ScopeKind::Module => {
+                frame.globals.borrow_mut().insert(name.to_string(), value);
+            },
+            ScopeKind::Class if declared_global => {
+                frame.globals.borrow_mut().insert(name.to_string(), value);
+            },
+            ScopeKind::Class => {
+                frame.locals.insert(name.to_string(), value);
+            },
+            _ => match kind {
+                Some( SymbolScope::Cell | SymbolScope::Free | SymbolScope::Nonlocal ) => {
+                    *frame.cells.entry(name.to_string()).or_default().borrow_mut() = Some( value )
+                },
+                Some( SymbolScope::Global ) => {
+                    frame.globals.borrow_mut().insert(name.to_string(), value);
+                },
+                _ => {
+                    frame.locals.insert(name.to_string(), value);
+                }
+            }
+        }
+    }
+
+    fn delete_name(&self, frame: &mut Frame<'a>, name: &str) -> Result<(), Value<'a>> {
+        let ( kind, declared_global ) = self.symbol_scope(frame, name);
+        let global = match frame.kind {
+            ScopeKind::Module => true,
+            ScopeKind::Class => declared_global,
+            _ => kind == Some( SymbolScope::Global )
+        };
+        let found = if global {
+            frame.globals.borrow_mut().remove(name).is_some()
+        } else if matches!(kind, Some( SymbolScope::Cell | SymbolScope::Free | SymbolScope::Nonlocal )) && frame.kind != ScopeKind::Class {
+            frame.cells.get(name).and_then( |c| c.borrow_mut().take() ).is_some()
+        } else {
+            frame.locals.remove(name).is_some()
+        };
+        match ( found, global ) {
+            ( true, _ ) => Ok(()),
+            ( false, true ) => throw(self, "NameError", format!("name '{}' is not defined", name)),
+            ( false, false ) => throw(self, "UnboundLocalError", format!("cannot access local variable '{}' where it is not associated with a value", name))
+        }
+    }
+
+
+    // Statements //////////////////////////////////////////////////////////////////////////////////
+
+    /// Runs tasks of frame until they are done or a generator yields. Input is the value sent to
+    /// a resumed generator.
+    fn execute(&mut self, frame: &mut Frame<'a>, tasks: &mut Vec<Task<'a>>, input: Option<Resume<'a>>) -> Outcome<'a> {
+        let mut step = match input {
+            Some( input ) => self.receive(frame, tasks, input),
+            None => Ok( None )
+        };
+        loop {
+            let completion = match step {
+                Ok( None ) => None,
+                Ok( Some( Signal::Complete(c) ) ) => Some( c ),
+                Ok( Some( Signal::Yield(v) ) ) => return Outcome::Yield(v),
+                Err( e ) => {
+                    self.raised(frame, &e);
+                    Some( Completion::Raise(e) )
+                }
+            };
+            if let Some( c ) = completion {
+                if let Some( c ) = self.unwind(frame, tasks, c) {
+                    return Outcome::Done(Some( c ))
+                }
+            }
+            if tasks.is_empty() {
+                return Outcome::Done(None)
+            }
+            step = self.step(frame, tasks)
+        }
+    }
+
+    /// Delivers value sent to a resumed generator to the task it is suspended in.
+    fn receive(&mut self, frame: &mut Frame<'a>, tasks: &mut Vec<Task<'a>>, input: Resume<'a>) -> Result<Option<Signal<'a>>, Value<'a>> {
+        match ( tasks.last(), input ) {
+            ( Some( Task::Resume(action) ), Resume::Send(v) ) => {
+                let action = *action;
+                tasks.pop();
+                self.perform(frame, action, v)
+            },
+            ( Some( Task::Resume(_) ), Resume::Throw(e) ) => {
+                tasks.pop();
+                Err( e )
+            },
+            ( Some( Task::YieldFrom(..) ), input ) => self.delegate(frame, tasks, input),
+            ( _, Resume::Throw(e) ) => Err( e ),
+            ( _, Resume::Send(_) ) => Ok( None )
+        }
+    }
+
+    fn perform(&mut self, frame: &mut Frame<'a>, action: Action<'a>, value: Value<'a>) -> Result<Option<Signal<'a>>, Value<'a>> {
+        match action {
+            Action::Discard => Ok( None ),
+            Action::Assign(targets) => {
+                for t in targets {
+                    self.assign(frame, t, value.clone())?
+                }
+                Ok( None )
+            },
+            Action::Return => Ok( Some( Signal::Complete(Completion::Return(value)) ) )
+        }
+    }
+
+    /// Advances the task on top of the stack.
+    fn step(&mut self, frame: &mut Frame<'a>, tasks: &mut Vec<Task<'a>>) -> Result<Option<Signal<'a>>, Value<'a>> {
+        let top = tasks.len() - 1;
+        match &mut tasks[top] {
+            Task::Block(body, index) => {
+                let body : &'a [Stmt] = body;
+                match body.get(*index) {
+                    Some( stmt ) => {
+                        *index += 1;
+                        frame.line = stmt.location.lineno;
+                        self.exec(frame, stmt, tasks)
+                    },
+                    None => {
+                        tasks.pop();
+                        Ok( None )
+                    }
+                }
+            },
+            Task::While(stmt) => {
+                let stmt : &'a Stmt = stmt;
+                if let StmtKind::While { test, body, orelse } = &stmt.node {
+                    frame.line = stmt.location.lineno;
+                    let v = self.eval(frame, test)?;
+                    if truthy(self, &v)? {
+                        tasks.push(Task::Block(body, 0))
+                    } else {
+                        tasks.pop();
+                        tasks.push(Task::Block(orelse, 0))
+                    }
+                }
+                Ok( None )
+            },
+            Task::For(stmt, it) => {
+                let ( stmt, it ) : ( &'a Stmt, Value<'a> ) = ( stmt, it.clone() );
+                if let StmtKind::For { target, body, orelse, .. } = &stmt.node {
+                    frame.line = stmt.location.lineno;
+                    match next(self, &it)? {
+                        Some( v ) => {
+                            self.assign(frame, target, v)?;
+                            tasks.push(Task::Block(body, 0))
+                        },
+                        None => {
+                            tasks.pop();
+                            tasks.push(Task::Block(orelse, 0))
+                        }
+                    }
+                }
+                Ok( None )
+            },
+            Task::Try(stmt, phase) => {
+                let ( orelse, finalbody ) = match &stmt.node {
+                    StmtKind::Try { orelse, finalbody, .. } => ( orelse, finalbody ),
+                    _ => return Ok( None )
+                };
+                match phase {
+                    TryPhase::Body => {
+                        *phase = TryPhase::Else;
+                        tasks.push(Task::Block(orelse, 0))
+                    },
+                    TryPhase::Else => {
+                        *phase = TryPhase::Finally(None);
+                        tasks.push(Task::Block(finalbody, 0))
+                    },
+                    TryPhase::Handler(name) => {
+                        let name = *name;
+                        *phase = TryPhase::Finally(None);
+                        self.end_handler(frame, name);
+                        tasks.push(Task::Block(finalbody, 0))
+                    },
+                    TryPhase::Finally(_) => {
+                        if let Some( Task::Try(_, TryPhase::Finally(pending)) ) = tasks.pop() {
+                            if let Some( Completion::Raise(_) ) = pending {
+                                self.handled.pop();
+                            }
+                            return Ok( pending.map(Signal::Complete) )
+                        }
+                    }
+                }
+                Ok( None )
+            },
+            Task::With(exit) => {
+                let exit = exit.clone();
+                tasks.pop();
+                call(self, &exit, vec![ Value::None, Value::None, Value::None ], Vec::new())?;
+                Ok( None )
+            },
+            Task::Resume(_) => {
+                tasks.pop();
+                Ok( None )
+            },
+            Task::YieldFrom(..) => self.delegate(frame, tasks, Resume::Send(Value::None)),
+            Task::Generate(..) => self.generate(frame, tasks)
+        }
+    }
+
+    /// Pops tasks until one handles completion, returns completion when none does.
+    fn unwind(&mut self, frame: &mut Frame<'a>, tasks: &mut Vec<Task<'a>>, mut completion: Completion<'a>) -> Option<Completion<'a>> {
+        loop {
+            let top = match tasks.last_mut() {
+                Some( t ) => t,
+                None => return Some( completion )
+            };
+            match top {
+                Task::Block(..) | Task::Resume(_) | Task::YieldFrom(..) | Task::Generate(..) => {
+                    tasks.pop();
+                },
+                Task::While(_) | Task::For(..) => match completion {
+                    Completion::Break => {
+                        tasks.pop();
+                        return None
+                    },
+                    Completion::Continue => return None,
+                    _ => {
+                        tasks.pop();
+                    }
+                },
+                Task::Try(stmt, phase) => {
+                    let ( handlers, finalbody ) : ( &'a [ExceptHandler], &'a [Stmt] ) = match &stmt.node {
+                        StmtKind::Try { handlers, finalbody, .. } => ( handlers, finalbody ),
+                        _ => ( &[], &[] )
+                    };
+                    match phase {
+                        TryPhase::Body => {
+                            if let Completion::Raise(e) = &completion {
+                                let e = e.clone();
+                                match self.find_handler(frame, handlers, &e) {
+                                    Ok( Some( handler ) ) => {
+                                        let name = handler.name.as_deref();
+                                        if let Some( Task::Try(_, phase) ) = tasks.last_mut() {
+                                            *phase = TryPhase::Handler(name)
+                                        }
+                                        self.handled.push(e.clone());
+                                        if let Some( n ) = name {
+                                            self.store_name(frame, n, e)
+                                        }
+                                        tasks.push(Task::Block(&handler.body, 0));
+                                        return None
+                                    },
+                                    Ok( None ) => (),
+                                    Err( err ) => {
+                                        self.raised(frame, &err);
+                                        completion = Completion::Raise(err)
+                                    }
+                                }
+                            }
+                            self.enter_finally(tasks, finalbody, completion);
+                            return None
+                        },
+                        TryPhase::Else => {
+                            self.enter_finally(tasks, finalbody, completion);
+                            return None
+                        },
+                        TryPhase::Handler(name) => {
+                            let name = *name;
+                            self.end_handler(frame, name);
+                            self.enter_finally(tasks, finalbody, completion);
+                            return None
+                        },
+                        TryPhase::Finally(_) => {
+                            if let Some( Task::Try(_, TryPhase::Finally(Some( Completion::Raise(_) ))) ) = tasks.pop() {
+                                self.handled.pop();
+                            }
+                        }
+                    }
+                },
+                Task::With(exit) => {
+                    let exit = exit.clone();
+                    tasks.pop();
+                    let res = match &completion {
+                        Completion::Raise(e) => {
+                            let args = vec![ Value::Class( class_of(self, e) ), e.clone(), Value::None ];
+                            call(self, &exit, args, Vec::new()).and_then( |r| truthy(self, &r) )
+                        },
+                        _ => call(self, &exit, vec![ Value::None, Value::None, Value::None ], Vec::new()).map( |_| false )
+                    };
+                    match res {
+                        Ok( true ) => return None,
+                        Ok( false ) => (),
+                        Err( err ) => {
+                            self.raised(frame, &err);
+                            completion = Completion::Raise(err)
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Runs 'finally' block of the 'try' statement on top, completion continues after it.
+    fn enter_finally(&mut self, tasks: &mut Vec<Task<'a>>, finalbody: &'a [Stmt], completion: Completion<'a>) {
+        if let Completion::Raise(e) = &completion {
+            self.handled.push(e.clone())
+        }
+        if let Some( Task::Try(_, phase) ) = tasks.last_mut() {
+            *phase = TryPhase::Finally(Some( completion ))
+        }
+        tasks.push(Task::Block(finalbody, 0))
+    }
+
+    fn end_handler(&mut self, frame: &mut Frame<'a>, name: Option<&str>) {
+        self.handled.pop();
+        if let Some( n ) = name {
+            let _ = self.delete_name(frame, n);
+        }
+    }
+
+    fn find_handler(&mut self, frame: &mut Frame<'a>, handlers: &'a [ExceptHandler], exception: &Value<'a>) -> Result<Option<&'a ExceptHandler>, Value<'a>> {
+        for handler in handlers {
+            match &handler.type_ {
+                None => return Ok( Some( handler ) ),
+                Some( t ) => {
+                    frame.line = handler.location.lineno;
+                    let class = self.eval(frame, t)?;
+                    if self.matches(&class, exception)? {
+                        return Ok( Some( handler ) )
+                    }
+                }
+            }
+        }
+        Ok( None )
+    }
+
+    fn matches(&self, class: &Value<'a>, exception: &Value<'a>) -> Result<bool, Value<'a>> {
+        match class {
+            Value::Class(c) if c.kind == TypeKind::Exception => Ok( is_instance(self, exception, c) ),
+            Value::Tuple(t) => {
+                for c in t.iter() {
+                    if self.matches(c, exception)? {
+                        return Ok( true )
+                    }
+                }
+                Ok( false )
+            },
+            _ => throw(self, "TypeError", "catching classes that do not inherit from BaseException is not allowed")
+        }
+    }
+
+    /// Resumes the iterator a 'yield from' delegates to.
+    fn delegate(&mut self, frame: &mut Frame<'a>, tasks: &mut Vec<Task<'a>>, input: Resume<'a>) -> Result<Option<Signal<'a>>, Value<'a>> {
+        let ( it, action ) = match tasks.last() {
+            Some( Task::YieldFrom(it, action) ) => ( it.clone(), *action ),
+            _ => return Ok( None )
+        };
+        let step = match ( &it, input ) {
+            ( Value::Generator(g), input ) => self.resume(g, input),
+            ( _, Resume::Send(v) ) if v.is_none() => next(self, &it).map( |v| match v {
+                Some( v ) => Step::Yield(v),
+                None => Step::Return(Value::None)
+            } ),
+            ( _, Resume::Send(v) ) => match get_attr(self, &it, "send").and_then( |send| call(self, &send, vec![ v ], Vec::new()) ) {
+                Ok( v ) => Ok( Step::Yield(v) ),
+                Err( e ) if is_exception(self, &e, "StopIteration") => get_attr(self, &e, "value").map(Step::Return),
+                Err( e ) => Err( e )
+            },
+            ( _, Resume::Throw(e) ) => Err( e )
+        };
+        match step {
+            Ok( Step::Yield(v) ) => Ok( Some( Signal::Yield(v) ) ),
+            Ok( Step::Return(v) ) => {
+                tasks.pop();
+                self.perform(frame, action, v)
+            },
+            Err( e ) => {
+                tasks.pop();
+                Err( e )
+            }
+        }
+    }
+
+    /// Advances generator expression to its next value.
+    fn generate(&mut self, frame: &mut Frame<'a>, tasks: &mut Vec<Task<'a>>) -> Result<Option<Signal<'a>>, Value<'a>> {
+        loop {
+            let ( elt, generators, it, level ) = match tasks.last() {
+                Some( Task::Generate(elt, generators, iterators) ) => ( *elt, *generators, iterators[iterators.len() - 1].clone(), iterators.len() - 1 ),
+                _ => return Ok( None )
+            };
+            match next(self, &it)? {
+                None if level == 0 => {
+                    tasks.pop();
+                    return Ok( None )
+                },
+                None => {
+                    if let Some( Task::Generate(_, _, iterators) ) = tasks.last_mut() {
+                        iterators.pop();
+                    }
+                },
+                Some( v ) => {
+                    let clause = &generators[level];
+                    self.assign(frame, &clause.target, v)?;
+                    if !self.conditions(frame, &clause.ifs)? {
+                        continue
+                    }
+                    if level + 1 < generators.len() {
+                        let source = self.eval(frame, &generators[level + 1].iter)?;
+                        let inner = iter(self, &source)?;
+                        if let Some( Task::Generate(_, _, iterators) ) = tasks.last_mut() {
+                            iterators.push(inner)
+                        }
+                        continue
+                    }
+                    return Ok( Some( Signal::Yield(self.eval(frame, elt)?) ) )
+                }
+            }
+        }
+    }
+
+    /// Suspends generator at 'yield' or starts delegation of 'yield from'.
+    fn suspend(&mut self, frame: &mut Frame<'a>, expr: &'a Expr, action: Action<'a>, tasks: &mut Vec<Task<'a>>) -> Result<Option<Signal<'a>>, Value<'a>> {
+        match &expr.node {
+            ExprKind::Yield { value } => {
+                let v = match value {
+                    Some( v ) => self.eval(frame, v)?,
+                    None => Value::None
+                };
+                tasks.push(Task::Resume(action));
+                Ok( Some( Signal::Yield(v) ) )
+            },
+            ExprKind::YieldFrom { value } => {
+                let source = self.eval(frame, value)?;
+                let it = iter(self, &source)?;
+                tasks.push(Task::YieldFrom(it, action));
+                Ok( None )
+            },
+            _ => Ok( None )
+        }
+    }
+
+    fn exec(&mut self, frame: &mut Frame<'a>, stmt: &'a Stmt, tasks: &mut Vec<Task<'a>>) -> Result<Option<Signal<'a>>, Value<'a>> {
+        match &stmt.node {
+            StmtKind::FunctionDef { name, args, body, decorator_list, .. } => {
+                let decorators = self.eval_all(frame, decorator_list)?;
+                let scope = self.scope_of(( stmt.location.start, stmt.location.end ), frame);
+                let mut f = self.make_function(frame, name, args, Code::Body { scope, body })?;
+                for d in decorators.iter().rev() {
+                    f = call(self, d, vec![ f ], Vec::new())?
+                }
+                self.store_name(frame, name, f)
+            },
+            StmtKind::ClassDef { name, bases, keywords, body, decorator_list } => {
+                let decorators = self.eval_all(frame, decorator_list)?;
+                let mut class = self.make_class(frame, stmt, name, bases, keywords, body)?;
+                for d in decorators.iter().rev() {
+                    class = call(self, d, vec![ class ], Vec::new())?
+                }
+                self.store_name(frame, name, class)
+            },
+            StmtKind::AsyncFunctionDef { .. } | StmtKind::AsyncFor { .. } | StmtKind::AsyncWith { .. } =>
+                return throw(self, "NotImplementedError", "coroutines are not supported"),
+            StmtKind::Return { value: Some( value ) } if is_yield(value) => return self.suspend(frame, value, Action::Return, tasks),
+            StmtKind::Return { value } => {
+                let v = match value {
+                    Some( v ) => self.eval(frame, v)?,
+                    None => Value::None
+                };
+                return Ok( Some( Signal::Complete(Completion::Return(v)) ) )
+            },
+            StmtKind::Delete { targets } => {
+                for t in targets {
+                    self.delete(frame, t)?
+                }
+            },
+            StmtKind::Assign { targets, value, .. } if is_yield(value) => return self.suspend(frame, value, Action::Assign(targets), tasks),
+            StmtKind::Assign { targets, value, .. } => {
+                let v = self.eval(frame, value)?;
+                for t in targets {
+                    self.assign(frame, t, v.clone())?
+                }
+            },
+            StmtKind::AugAssign { target, op, value } => self.augmented(frame, target, *op, value)?,
+            StmtKind::AnnAssign { target, value: Some( value ), .. } if is_yield(value) =>
+                return self.suspend(frame, value, Action::Assign(std::slice::from_ref(&**target)), tasks),
+            StmtKind::AnnAssign { target, value, .. } => {
+                if let Some( value ) = value {
+                    let v = self.eval(frame, value)?;
+                    self.assign(frame, target, v)?
+                }
+            },
+            StmtKind::For { iter: source, .. } => {
+                let source = self.eval(frame, source)?;
+                let it = iter(self, &source)?;
+                tasks.push(Task::For(stmt, it))
+            },
+            StmtKind::While { .. } => tasks.push(Task::While(stmt)),
+            StmtKind::If { test, body, orelse } => {
+                let v = self.eval(frame, test)?;
+                let branch = if truthy(self, &v)? { body } else { orelse };
+                tasks.push(Task::Block(branch, 0))
+            },
+            StmtKind::With { items, body, .. } => {
+                for item in items {
+                    self.enter_context(frame, item, tasks)?
+                }
+                tasks.push(Task::Block(body, 0))
+            },
+            StmtKind::Raise { exc: None, .. } => return match self.handled.last() {
+                Some( e ) => Ok( Some( Signal::Complete(Completion::Raise(e.clone())) ) ),
+                None => throw(self, "RuntimeError", "No active exception to reraise")
+            },
+            StmtKind::Raise { exc: Some( exc ), cause } => return Err( self.raise(frame, exc, cause.as_deref())? ),
+            StmtKind::Try { body, .. } => {
+                tasks.push(Task::Try(stmt, TryPhase::Body));
+                tasks.push(Task::Block(body, 0))
+            },
+            StmtKind::TryStar { .. } => return throw(self, "NotImplementedError", "'except*' is not supported"),
+            StmtKind::Match { .. } => return throw(self, "NotImplementedError", "'match' is not supported"),
+            StmtKind::Assert { test, msg } => {
+                let v = self.eval(frame, test)?;
+                if !truthy(self, &v)? {
+                    let args = match msg {
+                        Some( m ) => vec![ self.eval(frame, m)? ],
+                        None => Vec::new()
+                    };
+                    return Err( crate::runtime::ops::new_exception(self.builtins.exception("AssertionError"), args) )
+                }
+            },
+            StmtKind::Import { names } => {
+                let name = names.first().map( |a| a.name.split('.').next().unwrap_or("") ).unwrap_or("");
+                return throw(self, "ModuleNotFoundError", format!("No module named '{}'", name))
+            },
+            StmtKind::ImportFrom { module, level, .. } => {
+                match ( module.as_deref(), *level ) {
+                    ( Some( "__future__" ), 0 ) => (),
+                    ( Some( m ), 0 ) => return throw(self, "ModuleNotFoundError", format!("No module named '{}'", m.split('.').next().unwrap_or(m))),
+                    _ => return throw(self, "ImportError", "attempted relative import with no known parent package")
+                }
+            },
+            StmtKind::Expr { value } if is_yield(value) => return self.suspend(frame, value, Action::Discard, tasks),
+            StmtKind::Expr { value } => {
+                self.eval(frame, value)?;
+            },
+            StmtKind::Global { .. } | StmtKind::Nonlocal { .. } | StmtKind::Pass => (),
+            StmtKind::Break => return Ok( Some( Signal::Complete(Completion::Break) ) ),
+            StmtKind::Continue => return Ok( Some( Signal::Complete(Completion::Continue) ) )
+        }
+        Ok( None )
+    }
+
+    fn enter_context(&mut self, frame: &mut Frame<'a>, item: &'a WithItem, tasks: &mut Vec<Task<'a>>) -> Result<(), Value<'a>> {
+        let manager = self.eval(frame, &item.context_expr)?;
+        let methods = match &manager {
+            Value::Instance(_) => get_attr(self, &manager, "__enter__").and_then( |enter| Ok( ( enter, get_attr(self, &manager, "__exit__")? ) ) ),
+            _ => Err( Value::None )
+        };
+        let ( enter, exit ) = match methods {
+            Ok( m ) => m,
+            Err( e ) if e.is_none() || is_exception(self, &e, "AttributeError") =>
+                return throw(self, "TypeError", format!("'{}' object does not support the context manager protocol", type_name(self, &manager))),
+            Err( e ) => return Err( e )
+        };
+        let value = call(self, &enter, Vec::new(), Vec::new())?;
+        tasks.push(Task::With(exit));
+        match &item.optional_vars {
+            Some( target ) => self.assign(frame, target, value),
+            None => Ok(())
+        }
+    }
+
+    /// Exception raised by 'raise' statement.
+    fn raise(&mut self, frame: &mut Frame<'a>, exc: &'a Expr, cause: Option<&'a Expr>) -> RunResult<'a> {
+        let value = self.eval(frame, exc)?;
+        let exception = self.exception_value(value)?;
+        if let Some( cause ) = cause {
+            let cause = self.eval(frame, cause)?;
+            let cause = if cause.is_none() { cause } else { self.exception_value(cause)? };
+            if let Value::Instance(i) = &exception {
+                let mut dict = i.dict.borrow_mut();
+                dict.insert("__cause__".to_string(), cause);
+                dict.insert("__suppress_context__".to_string(), Value::Bool(true));
+            }
+        }
+        Ok( exception )
+    }
+
+    fn exception_value(&mut self, value: Value<'a>) -> RunResult<'a> {
+        match &value {
+            Value::Class(c) if c.kind == TypeKind::Exception => {
+                let instance = call(self, &value, Vec::new(), Vec::new())?;
+                self.exception_value(instance)
+            },
+            Value::Instance(i) if i.class.kind == TypeKind::Exception => Ok( value ),
+            _ => throw(self, "TypeError", "exceptions must derive from BaseException")
+        }
+    }
+
+    fn make_function(&mut self, frame: &mut Frame<'a>, name: &str, args: &'a Arguments, code: Code<'a>) -> RunResult<'a> {
+        let defaults = self.eval_all(frame, &args.defaults)?;
+        let mut kw_defaults = HashMap::new();
+        for ( arg, default ) in args.kwonlyargs.iter().zip(&args.kw_defaults) {
+            if let Some( d ) = default {
+                let v = self.eval(frame, d)?;
+                kw_defaults.insert(arg.arg.clone(), v);
+            }
+        }
+        let signature = Signature {
+            positional: args.posonlyargs.iter().chain(&args.args).map( |a| a.arg.clone() ).collect(),
+            posonly: args.posonlyargs.len(),
+            vararg: args.vararg.as_ref().map( |a| a.arg.clone() ),
+            kwonly: args.kwonlyargs.iter().map( |a| a.arg.clone() ).collect(),
+            kwarg: args.kwarg.as_ref().map( |a| a.arg.clone() )
+        };
+        let ( scope, is_generator ) = match &code {
+            Code::Body { scope, .. } => ( *scope, self.symbols.scope(*scope).is_generator ),
+            Code::Lambda { scope, .. } => ( *scope, false ),
+            Code::Bytecode(c) => ( frame.scope, c.is_generator() )
+        };
+        Ok( Value::Function( Rc::new( Function {
+            name: name.to_string(),
+            qualname: format!("{}{}", frame.prefix, name),
+            signature,
+            defaults,
+            kw_defaults,
+            code,
+            is_generator,
+            closure: self.closure(frame, scope),
+            globals: frame.globals.clone(),
+            dict: RefCell::new( HashMap::new() )
+        } ) ) )
+    }
+
+    fn make_class(&mut self, frame: &mut Frame<'a>, stmt: &'a Stmt, name: &str, bases: &'a [Expr], keywords: &'a [Keyword], body: &'a [Stmt]) -> RunResult<'a> {
+        let bases = self.eval_elements(frame, bases)?;
+        if !keywords.is_empty() {
+            return throw(self, "NotImplementedError", "class keyword arguments are not supported")
+        }
+        let base = match bases.as_slice() {
+            [] => self.builtins.class(TypeKind::Object),
+            [ Value::Class(c) ] => c.clone(),
+            [ _ ] => return throw(self, "TypeError", "bases must be types"),
+            _ => return throw(self, "NotImplementedError", "multiple inheritance is not supported")
+        };
+        let scope = self.scope_of(( stmt.location.start, stmt.location.end ), frame);
+        let qualname = format!("{}{}", frame.prefix, name);
+        let mut class_frame = Frame {
+            scope,
+            kind: ScopeKind::Class,
+            name: name.to_string(),
+            prefix: format!("{}.", qualname),
+            locals: HashMap::new(),
+            cells: frame.cells.clone(),
+            globals: frame.globals.clone(),
+            first_arg: None,
+            line: frame.line
+        };
+        class_frame.locals.insert("__module__".to_string(), Value::str("__main__"));
+        class_frame.locals.insert("__qualname__".to_string(), Value::str(&qualname));
+        let class_cell : Cell<'a> = Rc::new( RefCell::new(None) );
+        if self.symbols.scope(scope).needs_class_closure {
+            class_frame.cells.insert("__class__".to_string(), class_cell.clone());
+        }
+        let mut tasks = vec![ Task::Block(body, 0) ];
+        if let Outcome::Done(Some( Completion::Raise(e) )) = self.execute(&mut class_frame, &mut tasks, None) {
+            return Err( e )
+        }
+        let class = new_class(self, name, base, class_frame.locals)?;
+        *class_cell.borrow_mut() = Some( class.clone() );
+        Ok( class )
+    }
+
+    fn assign(&mut self, frame: &mut Frame<'a>, target: &'a Expr, value: Value<'a>) -> Result<(), Value<'a>> {
+        match &target.node {
+            ExprKind::Name { id, .. } => {
+                self.store_name(frame, id, value);
+                Ok(())
+            },
+            ExprKind::Attribute { value: object, attr, .. } => {
+                let object = self.eval(frame, object)?;
+                set_attr(self, &object, attr, value)
+            },
+            ExprKind::Subscript { value: object, slice, .. } => {
+                let object = self.eval(frame, object)?;
+                let index = self.eval(frame, slice)?;
+                set_item(self, &object, &index, value)
+            },
+            ExprKind::Tuple { elts, .. } | ExprKind::List { elts, .. } => self.unpack(frame, elts, value),
+            _ => throw(self, "SyntaxError", "cannot assign to expression")
+        }
+    }
+
+    fn unpack(&mut self, frame: &mut Frame<'a>, targets: &'a [Expr], value: Value<'a>) -> Result<(), Value<'a>> {
+        let mut values = collect(self, &value)?;
+        let n = targets.len();
+        match targets.iter().position( |t| matches!(t.node, ExprKind::Starred { .. }) ) {
+            None => {
+                if values.len() < n {
+                    return throw(self, "ValueError", format!("not enough values to unpack (expected {}, got {})", n, values.len()))
+                }
+                if values.len() > n {
+                    return throw(self, "ValueError", format!("too many values to unpack (expected {})", n))
+                }
+                for ( t, v ) in targets.iter().zip(values) {
+                    self.assign(frame, t, v)?
+                }
+            },
+            Some( star ) => {
+                if values.len() < n - 1 {
+                    return throw(self, "ValueError", format!("not enough values to unpack (expected at least {}, got {})", n - 1, values.len()))
+                }
+                let after = values.split_off(values.len() - ( n - 1 - star ));
+                let middle = values.split_off(star);
+                for ( t, v ) in targets[.. star].iter().zip(values) {
+                    self.assign(frame, t, v)?
+                }
+                if let ExprKind::Starred { value: t, .. } = &targets[star].node {
+                    self.assign(frame, t, Value::list(middle))?
+                }
+                for ( t, v ) in targets[star + 1 ..].iter().zip(after) {
+                    self.assign(frame, t, v)?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, frame: &mut Frame<'a>, target: &'a Expr) -> Result<(), Value<'a>> {
+        match &target.node {
+            ExprKind::Name { id, .. } => self.delete_name(frame, id),
+            ExprKind::Attribute { value, attr, .. } => {
+                let object = self.eval(frame, value)?;
+                del_attr(self, &object, attr)
+            },
+            ExprKind::Subscript { value, slice, .. } => {
+                let object = self.eval(frame, value)?;
+                let index = self.eval(frame, slice)?;
+                del_item(self, &object, &index)
+            },
+            ExprKind::Tuple { elts, .. } | ExprKind::List { elts, .. } => {
+                for e in elts {
+                    self.delete(frame, e)?
+                }
+                Ok(())
+            },
+            _ => throw(self, "SyntaxError", "cannot delete expression")
+        }
+    }
+
+    fn augmented(&mut self, frame: &mut Frame<'a>, target: &'a Expr, op: Operator, value: &'a Expr) -> Result<(), Value<'a>> {
+        match &target.node {
+            ExprKind::Name { id, .. } => {
+                let current = self.load_name(frame, id)?;
+                let v = self.eval(frame, value)?;
+                let res = inplace_op(self, op, &current, &v)?;
+                self.store_name(frame, id, res);
+                Ok(())
+            },
+            ExprKind::Attribute { value: object, attr, .. } => {
+                let object = self.eval(frame, object)?;
+                let current = get_attr(self, &object, attr)?;
+                let v = self.eval(frame, value)?;
+                let res = inplace_op(self, op, &current, &v)?;
+                set_attr(self, &object, attr, res)
+            },
+            ExprKind::Subscript { value: object, slice, .. } => {
+                let object = self.eval(frame, object)?;
+                let index = self.eval(frame, slice)?;
+                let current = get_item(self, &object, &index)?;
+                let v = self.eval(frame, value)?;
+                let res = inplace_op(self, op, &current, &v)?;
+                set_item(self, &object, &index, res)
+            },
+            _ => throw(self, "SyntaxError", "illegal expression for augmented assignment")
+        }
+    }
+
+
+    // Expressions /////////////////////////////////////////////////////////////////////////////////
+
+    fn eval_all(&mut self, frame: &mut Frame<'a>, exprs: &'a [Expr]) -> Result<Vec<Value<'a>>, Value<'a>> {
+        let mut res = Vec::with_capacity(exprs.len());
+        for e in exprs {
+            res.push(self.eval(frame, e)?)
+        }
+        Ok( res )
+    }
+
+    /// Values of elements of a display or of call arguments, with starred elements unpacked.
+    fn eval_elements(&mut self, frame: &mut Frame<'a>, exprs: &'a [Expr]) -> Result<Vec<Value<'a>>, Value<'a>> {
+        let mut res = Vec::with_capacity(exprs.len());
+        for e in exprs {
+            match &e.node {
+                ExprKind::Starred { value, .. } => {
+                    let v = self.eval(frame, value)?;
+                    res.extend(collect(self, &v)?)
+                },
+                _ => res.push(self.eval(frame, e)?)
+            }
+        }
+        Ok( res )
+    }
+
+    fn conditions(&mut self, frame: &mut Frame<'a>, conditions: &'a [Expr]) -> Result<bool, Value<'a>> {
+        for c in conditions {
+            let v = self.eval(frame, c)?;
+            if !truthy(self, &v)? {
+                return Ok( false )
+            }
+        }
+        Ok( true )
+    }
+
+    fn constant(&self, value: &Constant) -> RunResult<'a> {
+        Ok( match value {
+            Constant::None => Value::None,
+            Constant::Bool(b) => Value::Bool(*b),
+            Constant::Str(s) => Value::str(s),
+            Constant::Bytes(b) => Value::Bytes( Rc::from(b.as_slice()) ),
+            Constant::Int(digits) => match Int::parse(digits, 10) {
+                Some( i ) => Value::Int(i),
+                None => return throw(self, "ValueError", format!("invalid integer literal '{}'", digits))
+            },
+            Constant::Float(f) => Value::Float(*f),
+            Constant::Complex(..) => return throw(self, "NotImplementedError", "complex numbers are not supported"),
+            Constant::Tuple(t) => Value::tuple(t.iter().map( |c| self.constant(c) ).collect::<Result<Vec<Value<'a>>, Value<'a>>>()?),
+            Constant::Ellipsis => Value::Ellipsis
+        } )
+    }
+
+    fn eval(&mut self, frame: &mut Frame<'a>, expr: &'a Expr) -> RunResult<'a> {
+        match &expr.node {
+            ExprKind::BoolOp { op, values } => {
+                let mut last = Value::None;
+                for ( i, v ) in values.iter().enumerate() {
+                    last = self.eval(frame, v)?;
+                    if i + 1 < values.len() && truthy(self, &last)? == ( *op == BoolOperator::Or ) {
+                        break
+                    }
+                }
+                Ok( last )
+            },
+            ExprKind::NamedExpr { target, value } => {
+                let v = self.eval(frame, value)?;
+                self.assign(frame, target, v.clone())?;
+                Ok( v )
+            },
+            ExprKind::BinOp { left, op, right } => {
+                let l = self.eval(frame, left)?;
+                let r = self.eval(frame, right)?;
+                binary_op(self, *op, &l, &r)
+            },
+            ExprKind::UnaryOp { op, operand } => {
+                let v = self.eval(frame, operand)?;
+                unary_op(self, *op, &v)
+            },
+            ExprKind::Lambda { args, body } => {
+                let scope = self.scope_of(( expr.location.start, expr.location.end ), frame);
+                self.make_function(frame, "<lambda>", args, Code::Lambda { scope, body })
+            },
+            ExprKind::IfExp { test, body, orelse } => {
+                let v = self.eval(frame, test)?;
+                if truthy(self, &v)? { self.eval(frame, body) } else { self.eval(frame, orelse) }
+            },
+            ExprKind::Dict { keys, values } => {
+                let dict = Rc::new( RefCell::new( Dict::new() ) );
+                for ( key, value ) in keys.iter().zip(values) {
+                    match key {
+                        Some( key ) => {
+                            let k = self.eval(frame, key)?;
+                            let v = self.eval(frame, value)?;
+                            let h = hash_key(self, &k)?;
+                            dict.borrow_mut().insert(h, k, v)
+                        },
+                        None => {
+                            let mapping = self.eval(frame, value)?;
+                            if !matches!(mapping, Value::Dict(_)) {
+                                return throw(self, "TypeError", format!("'{}' object is not a mapping", type_name(self, &mapping)))
+                            }
+                            dict_update(self, &dict, &mapping)?
+                        }
+                    }
+                }
+                Ok( Value::Dict(dict) )
+            },
+            ExprKind::Set { elts } => {
+                let values = self.eval_elements(frame, elts)?;
+                Ok( Value::set(set_from(self, values)?) )
+            },
+            ExprKind::ListComp { elt, generators } => Ok( Value::list(self.comprehension(frame, expr, generators, &[ elt ])?) ),
+            ExprKind::SetComp { elt, generators } => {
+                let values = self.comprehension(frame, expr, generators, &[ elt ])?;
+                Ok( Value::set(set_from(self, values)?) )
+            },
+            ExprKind::DictComp { key, value, generators } => {
+                let values = self.comprehension(frame, expr, generators, &[ key, value ])?;
+                let mut dict = Dict::new();
+                for pair in values.chunks(2) {
+                    let h = hash_key(self, &pair[0])?;
+                    dict.insert(h, pair[0].clone(), pair[1].clone())
+                }
+                Ok( Value::dict(dict) )
+            },
+            ExprKind::GeneratorExp { elt, generators } => self.generator_expression(frame, expr, elt, generators),
+            ExprKind::Await { .. } => throw(self, "NotImplementedError", "coroutines are not supported"),
+            ExprKind::Yield { .. } | ExprKind::YieldFrom { .. } =>
+                throw(self, "NotImplementedError", "'yield' is only supported as a statement, an assigned value or a returned value"),
+            ExprKind::Compare { left, ops, comparators } => {
+                let mut l = self.eval(frame, left)?;
+                for ( op, right ) in ops.iter().zip(comparators) {
+                    let r = self.eval(frame, right)?;
+                    if !compare(self, *op, &l, &r)? {
+                        return Ok( Value::Bool(false) )
+                    }
+                    l = r
+                }
+                Ok( Value::Bool(true) )
+            },
+            ExprKind::Call { func, args, keywords } => self.call_expr(frame, func, args, keywords),
+            ExprKind::FormattedValue { value, conversion, format_spec } => {
+                let v = self.eval(frame, value)?;
+                let v = match *conversion {
+                    115 => Value::str(&to_str(self, &v)?),
+                    114 | 97 => Value::str(&repr(self, &v)?),
+                    _ => v
+                };
+                let spec = match format_spec {
+                    Some( s ) => {
+                        let s = self.eval(frame, s)?;
+                        to_str(self, &s)?
+                    },
+                    None => String::new()
+                };
+                Ok( Value::str(&format_value(self, &v, &spec)?) )
+            },
+            ExprKind::JoinedStr { values } => {
+                let mut text = String::new();
+                for v in values {
+                    let part = self.eval(frame, v)?;
+                    text.push_str(&to_str(self, &part)?)
+                }
+                Ok( Value::str(&text) )
+            },
+            ExprKind::Constant { value, .. } => self.constant(value),
+            ExprKind::Attribute { value, attr, .. } => {
+                let object = self.eval(frame, value)?;
+                get_attr(self, &object, attr)
+            },
+            ExprKind::Subscript { value, slice, .. } => {
+                let object = self.eval(frame, value)?;
+                let index = self.eval(frame, slice)?;
+                get_item(self, &object, &index)
+            },
+            ExprKind::Starred { .. } => throw(self, "SyntaxError", "can't use starred expression here"),
+            ExprKind::Name { id, .. } => self.load_name(frame, id),
+            ExprKind::List { elts, .. } => Ok( Value::list(self.eval_elements(frame, elts)?) ),
+            ExprKind::Tuple { elts, .. } => Ok( Value::tuple(self.eval_elements(frame, elts)?) ),
+            ExprKind::Slice { lower, upper, step } => {
+                let mut parts = [ Value::None, Value::None, Value::None ];
+                for ( part, e ) in parts.iter_mut().zip([ lower, upper, step ]) {
+                    if let Some( e ) = e {
+                        *part = self.eval(frame, e)?
+                    }
+                }
+                Ok( Value::Slice( Rc::new(parts) ) )
+            }
+        }
+    }
+
+    fn call_expr(&mut self, frame: &mut Frame<'a>, func: &'a Expr, args: &'a [Expr], keywords: &'a [Keyword]) -> RunResult<'a> {
+        let f = self.eval(frame, func)?;
+        if let ( ExprKind::Name { id, .. }, Value::Class(c), true ) = ( &func.node, &f, args.is_empty() && keywords.is_empty() ) {
+            if id == "super" && c.kind == TypeKind::Super {
+                return self.zero_arg_super(frame)
+            }
+        }
+        let values = self.eval_elements(frame, args)?;
+        let mut kwargs : Vec<( String, Value<'a> )> = Vec::new();
+        for keyword in keywords {
+            let v = self.eval(frame, &keyword.value)?;
+            let items = match ( &keyword.arg, &v ) {
+                ( Some( name ), _ ) => vec![ ( Value::str(name), v.clone() ) ],
+                ( None, Value::Dict(d) ) => d.borrow().items(),
+                ( None, _ ) => return throw(self, "TypeError", format!("argument after ** must be a mapping, not {}", type_name(self, &v)))
+            };
+            for ( key, value ) in items {
+                let key = match key {
+                    Value::Str(s) => s.to_string(),
+                    _ => return throw(self, "TypeError", "keywords must be strings")
+                };
+                if kwargs.iter().any( |( k, _ )| *k == key ) {
+                    let name = match &f {
+                        Value::Function(f) => f.qualname.clone(),
+                        _ => type_name(self, &f)
+                    };
+                    return throw(self, "TypeError", format!("{}() got multiple values for keyword argument '{}'", name, key))
+                }
+                kwargs.push(( key, value ))
+            }
+        }
+        call(self, &f, values, kwargs)
+    }
+
+    fn zero_arg_super(&mut self, frame: &Frame<'a>) -> RunResult<'a> {
+        let class = match frame.cells.get("__class__").and_then( |c| c.borrow().clone() ) {
+            Some( Value::Class(c) ) => c,
+            _ => return throw(self, "RuntimeError", "super(): __class__ cell not found")
+        };
+        match &frame.first_arg {
+            Some( receiver ) => Ok( Value::Super( Rc::new( ( class, receiver.clone() ) ) ) ),
+            None => throw(self, "RuntimeError", "super(): no arguments")
+        }
+    }
+
+    /// Frame of comprehension or generator expression, the iterable of its first clause is
+    /// evaluated in the enclosing frame.
+    fn comprehension_frame(&mut self, frame: &mut Frame<'a>, expr: &'a Expr, generators: &'a [Comprehension], name: &str) -> Result<( Frame<'a>, Value<'a> ), Value<'a>> {
+        let source = self.eval(frame, &generators[0].iter)?;
+        let it = iter(self, &source)?;
+        let scope = self.scope_of(( expr.location.start, expr.location.end ), frame);
+        let prefix = format!("{}{}.<locals>.", frame.prefix, name);
+        let mut child = self.new_frame(scope, name, prefix, self.closure(frame, scope), frame.globals.clone(), frame.line);
+        child.first_arg = frame.first_arg.clone();
+        Ok( ( child, it ) )
+    }
+
+    fn comprehension(&mut self, frame: &mut Frame<'a>, expr: &'a Expr, generators: &'a [Comprehension], elts: &[&'a Expr]) -> Result<Vec<Value<'a>>, Value<'a>> {
+        let ( mut child, it ) = self.comprehension_frame(frame, expr, generators, "<listcomp>")?;
+        let mut res = Vec::new();
+        self.comprehension_level(&mut child, generators, 0, it, elts, &mut res)?;
+        Ok( res )
+    }
+
+    fn comprehension_level(&mut self, frame: &mut Frame<'a>, generators: &'a [Comprehension], level: usize, it: Value<'a>, elts: &[&'a Expr], res: &mut Vec<Value<'a>>) -> Result<(), Value<'a>> {
+        let clause = &generators[level];
+        while let Some( v ) = next(self, &it)? {
+            self.assign(frame, &clause.target, v)?;
+            if !self.conditions(frame, &clause.ifs)? {
+                continue
+            }
+            if level + 1 < generators.len() {
+                let source = self.eval(frame, &generators[level + 1].iter)?;
+                let inner = iter(self, &source)?;
+                self.comprehension_level(frame, generators, level + 1, inner, elts, res)?
+            } else {
+                for e in elts {
+                    res.push(self.eval(frame, e)?)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn generator_expression(&mut self, frame: &mut Frame<'a>, expr: &'a Expr, elt: &'a Expr, generators: &'a [Comprehension]) -> RunResult<'a> {
+        let ( child, it ) = self.comprehension_frame(frame, expr, generators, "<genexpr>")?;
+        let suspended = Suspended { frame: child, tasks: vec![ Task::Generate(elt, generators, vec![ it ]) ], handled: Vec::new(), started: false };
+        Ok( Value::Generator( Rc::new( RefCell::new( Generator {
+            name: format!("{}<genexpr>", frame.prefix),
+            frame: Some( Suspension::Tree(Box::new(suspended)) ),
+            running: false
+        } ) ) ) )
+    }
+}
+
+impl<'a> Runtime<'a> for Interpreter<'a> {
+    fn builtins(&self) -> Rc<Builtins<'a>> {
+        self.builtins.clone()
+    }
+
+    fn call_function(&mut self, function: &Rc<Function<'a>>, args: Vec<Value<'a>>, kwargs: Vec<( String, Value<'a> )>) -> RunResult<'a> {
+        let values = bind_arguments(self, function, args, kwargs)?;
+        let ( scope, line ) = match function.code {
+            Code::Body { scope, body } => ( scope, body.first().map( |s| s.location.lineno ).unwrap_or(0) ),
+            Code::Lambda { scope, body } => ( scope, body.location.lineno ),
+            Code::Bytecode(_) => return throw(self, "TypeError", "function was compiled for the bytecode virtual machine")
+        };
+        let prefix = format!("{}.<locals>.", function.qualname);
+        let mut frame = self.new_frame(scope, &function.name, prefix, function.closure.clone(), function.globals.clone(), line);
+        frame.first_arg = match ( values.first(), function.signature.positional.is_empty() ) {
+            ( Some( Value::Tuple(t) ), true ) => t.first().cloned(),
+            ( first, _ ) => first.cloned()
+        };
+        for ( name, value ) in function.signature.names().into_iter().zip(values) {
+            self.store_name(&mut frame, name, value)
+        }
+        if function.is_generator {
+            if let Code::Body { body, .. } = function.code {
+                let suspended = Suspended { frame, tasks: vec![ Task::Block(body, 0) ], handled: Vec::new(), started: false };
+                return Ok( Value::Generator( Rc::new( RefCell::new( Generator {
+                    name: function.qualname.clone(),
+                    frame: Some( Suspension::Tree(Box::new(suspended)) ),
+                    running: false
+                } ) ) ) )
+            }
+        }
+        if self.depth >= RECURSION_LIMIT {
+            return throw(self, "RecursionError", "maximum recursion depth exceeded")
+        }
+        self.depth += 1;
+        let base = self.handled.len();
+        let res = match function.code {
+            Code::Body { body, .. } => {
+                let mut tasks = vec![ Task::Block(body, 0) ];
+                match self.execute(&mut frame, &mut tasks, None) {
+                    Outcome::Done(Some( Completion::Return(v) )) => Ok( v ),
+                    Outcome::Done(Some( Completion::Raise(e) )) => Err( e ),
+                    _ => Ok( Value::None )
+                }
+            },
+            Code::Lambda { body, .. } => {
+                let res = self.eval(&mut frame, body);
+                if let Err( e ) = &res {
+                    self.raised(&mut frame, e)
+                }
+                res
+            },
+            Code::Bytecode(_) => Ok( Value::None )
+        };
+        self.depth -= 1;
+        self.handled.truncate(base);
+        res
+    }
+
+    fn resume(&mut self, generator: &Rc<RefCell<Generator<'a>>>, input: Resume<'a>) -> Result<Step<'a>, Value<'a>> {
+        let suspended = {
+            let mut g = generator.borrow_mut();
+            if g.running {
+                drop(g);
+                return throw(self, "ValueError", "generator already executing")
+            }
+            match g.frame.take() {
+                Some( Suspension::Tree(s) ) => s,
+                Some( other ) => {
+                    g.frame = Some( other );
+                    drop(g);
+                    return throw(self, "TypeError", "generator was created by another engine")
+                },
+                None => return match input {
+                    Resume::Send(_) => Ok( Step::Return(Value::None) ),
+                    Resume::Throw(e) => Err( e )
+                }
+            }
+        };
+        let mut s = *suspended;
+        let input = match ( s.started, input ) {
+            ( true, input ) => Some( input ),
+            ( false, Resume::Send(v) ) if !v.is_none() => {
+                generator.borrow_mut().frame = Some( Suspension::Tree(Box::new(s)) );
+                return throw(self, "TypeError", "can't send non-None value to a just-started generator")
+            },
+            ( false, Resume::Throw(e) ) => return Err( e ),
+            ( false, Resume::Send(_) ) => None
+        };
+        if self.depth >= RECURSION_LIMIT {
+            generator.borrow_mut().frame = Some( Suspension::Tree(Box::new(s)) );
+            return throw(self, "RecursionError", "maximum recursion depth exceeded")
+        }
+        s.started = true;
+        generator.borrow_mut().running = true;
+        self.depth += 1;
+        let base = self.handled.len();
+        self.handled.append(&mut s.handled);
+        let outcome = self.execute(&mut s.frame, &mut s.tasks, input);
+        self.depth -= 1;
+        let res = match outcome {
+            Outcome::Yield(v) => {
+                s.handled = self.handled.split_off(base);
+                generator.borrow_mut().frame = Some( Suspension::Tree(Box::new(s)) );
+                Ok( Step::Yield(v) )
+            },
+            Outcome::Done(completion) => {
+                self.handled.truncate(base);
+                match completion {
+                    Some( Completion::Return(v) ) => Ok( Step::Return(v) ),
+                    Some( Completion::Raise(e) ) => {
+                        if is_exception(self, &e, "StopIteration") {
+                            let replaced = error(self, "RuntimeError", "generator raised StopIteration");
+                            self.record(&s.frame, &replaced);
+                            if let Value::Instance(i) = &replaced {
+                                i.dict.borrow_mut().insert("__cause__".to_string(), e);
+                            }
+                            Err( replaced )
+                        } else {
+                            Err( e )
+                        }
+                    },
+                    _ => Ok( Step::Return(Value::None) )
+                }
+            }
+        };
+        generator.borrow_mut().running = false;
+        res
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text)
+    }
+}
+
+
+// UnitTests for tree walking interpreter //////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::interpreter::run_source;
+
+
+    fn output(source: &str) -> String {
+        let ( out, res ) = run_source(source, "test.py");
+        assert_eq!(res, Ok(()));
+        out
+    }
+
+    fn traceback(source: &str) -> String {
+        run_source(source, "test.py").1.unwrap_err()
+    }
+
+    #[test]
+    fn interpreter_values() {
+        assert_eq!(output("print(2 ** 100, -7 // 2, 7 / 2, 0.1 + 0.2)\n"), "1267650600228229401496703205376 -4 3.5 0.30000000000000004\n");
+        assert_eq!(output("print([1, (2, 'a')], {'k': None}, {3}, len('abc'))\n"), "[1, (2, 'a')] {'k': None} {3} 3\n");
+        assert_eq!(output("a, *b = range(4)\nprint(a, b, b[::-1])\n"), "0 [1, 2, 3] [3, 2, 1]\n");
+        assert_eq!(output("print(f'{3.14159:.2f}|{7:>3}|', '%s-%d' % ('x', 4))\n"), "3.14|  7| x-4\n");
+        assert_eq!(output("x = []\nx.append(x)\nd = {}\nd[1] = d\nprint(x, d, [d])\n"), "[[...]] {1: {...}} [{1: {...}}]\n");
+    }
+
+    #[test]
+    fn interpreter_closures() {
+        let source = "def counter():\n    n = 0\n    def inc():\n        nonlocal n\n        n += 1\n        return n\n    return inc\nc = counter()\nc()\nprint(c())\n";
+        assert_eq!(output(source), "2\n");
+        assert_eq!(output("fs = [lambda: i for i in range(3)]\nprint([f() for f in fs])\n"), "[2, 2, 2]\n");
+    }
+
+    #[test]
+    fn interpreter_classes() {
+        let source = "class A:\n    def __init__(self, v):\n        self.v = v\n    def get(self):\n        return self.v\n\
+                      class B(A):\n    def get(self):\n        return super().get() * 2\n\
+                      b = B(21)\nprint(b.get(), isinstance(b, A), type(b).__name__)\n";
+        assert_eq!(output(source), "42 True B\n");
+    }
+
+    #[test]
+    fn interpreter_exceptions() {
+        let source = "def f():\n    try:\n        raise ValueError('bad')\n    except ValueError as e:\n        print('caught', e)\n        return 1\n    finally:\n        print('finally')\nprint(f())\n";
+        assert_eq!(output(source), "caught bad\nfinally\n1\n");
+        assert_eq!(output("class E(Exception):\n    pass\ntry:\n    raise E\nexcept (TypeError, E):\n    print('E')\n"), "E\n");
+        assert_eq!(output("def f():\n    return f()\ntry:\n    f()\nexcept RecursionError as e:\n    print(e)\n"), "maximum recursion depth exceeded\n");
+    }
+
+    #[test]
+    fn interpreter_generators() {
+        let source = "def gen():\n    x = yield 1\n    print('got', x)\n    yield from range(2)\n    return 'end'\n\
+                      g = gen()\nprint(next(g), g.send('s'), next(g))\ntry:\n    next(g)\nexcept StopIteration as e:\n    print(e.value)\n";
+        assert_eq!(output(source), "got s\n1 0 1\nend\n");
+        assert_eq!(output("print(sum(x * x for x in range(4) if x))\n"), "14\n");
+    }
+
+    #[test]
+    fn interpreter_tracebacks() {
+        assert_eq!(traceback("def f():\n    return 1 / 0\nf()\n"),
+                   "Traceback (most recent call last):\n  File \"test.py\", line 3, in <module>\n    f()\n\
+                    \x20 File \"test.py\", line 2, in f\n    return 1 / 0\nZeroDivisionError: division by zero\n");
+        let chained = traceback("try:\n    x\nexcept NameError as e:\n    raise KeyError('k') from e\n");
+        assert!(chained.contains("NameError: name 'x' is not defined\n\nThe above exception was the direct cause of the following exception:\n\n"));
+        assert!(chained.ends_with("KeyError: 'k'\n"));
+        assert!(traceback("try:\n    x\nexcept NameError:\n    y\n").contains("During handling of the above exception, another exception occurred:"));
+    }
+}
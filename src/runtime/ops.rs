@@ -0,0 +1,1705 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+use crate::ast::nodes::{ Operator, UnaryOperator, CmpOperator };
+use crate::runtime::bigint::Int;
+use crate::runtime::value::{ Value, RunResult, Runtime, Class, Instance, Function, TypeKind, Iter, ViewKind, Dict, HashKey, Range, Resume, Step };
+use crate::runtime::format::{ float_repr, str_repr, bytes_repr, percent_format };
+use crate::runtime::builtins::method;
+
+
+/// Number operand of arithmetic, booleans count as integers.
+enum Num {
+    I(Int),
+    F(f64)
+}
+
+thread_local! {
+    /// Identities of the containers whose repr is being built, as CPython's 'Py_ReprEnter'.
+    /// Views share the identity of their dictionary and are told apart by the flag.
+    static REPRS: RefCell<Vec<( usize, bool )>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks a container as being repr'd until dropped, also when the repr of an element fails.
+struct ReprGuard(( usize, bool ));
+
+impl ReprGuard {
+    /// None when the repr of value is already being built, which then ends in a cycle.
+    fn enter(value: &Value) -> Option<ReprGuard> {
+        let id = ( value.identity()?, matches!(value, Value::DictView(..)) );
+        REPRS.with_borrow_mut( |reprs| {
+            if reprs.contains(&id) {
+                return None
+            }
+            reprs.push(id);
+            Some( ReprGuard(id) )
+        })
+    }
+}
+
+impl Drop for ReprGuard {
+    fn drop(&mut self) {
+        REPRS.with_borrow_mut( |reprs| reprs.retain( |id| *id != self.0 ) )
+    }
+}
+
+
+/// Instance of exception class with the given arguments.
+pub fn new_exception<'a>(class: Rc<Class<'a>>, args: Vec<Value<'a>>) -> Value<'a> {
+    let instance = Instance::new(class);
+    instance.dict.borrow_mut().insert("args".to_string(), Value::tuple(args));
+    Value::Instance( Rc::new(instance) )
+}
+
+/// Instance of builtin exception with message.
+pub fn error<'a>(rt: &dyn Runtime<'a>, name: &str, message: impl Into<String>) -> Value<'a> {
+    new_exception(rt.builtins().exception(name), vec![ Value::str(&message.into()) ])
+}
+
+pub fn throw<'a, T>(rt: &dyn Runtime<'a>, name: &str, message: impl Into<String>) -> Result<T, Value<'a>> {
+    Err( error(rt, name, message) )
+}
+
+pub fn class_of<'a>(rt: &dyn Runtime<'a>, value: &Value<'a>) -> Rc<Class<'a>> {
+    let kind = match value {
+        Value::Instance(i) => return i.class.clone(),
+        Value::None => TypeKind::NoneType,
+        Value::Ellipsis => TypeKind::Ellipsis,
+        Value::NotImplemented => TypeKind::NotImplemented,
+        Value::Bool(_) => TypeKind::Bool,
+        Value::Int(_) => TypeKind::Int,
+        Value::Float(_) => TypeKind::Float,
+        Value::Str(_) => TypeKind::Str,
+        Value::Bytes(_) => TypeKind::Bytes,
+        Value::List(_) => TypeKind::List,
+        Value::Tuple(_) => TypeKind::Tuple,
+        Value::Dict(_) => TypeKind::Dict,
+        Value::Set(_) => TypeKind::Set,
+        Value::DictView(..) => TypeKind::DictView,
+        Value::Range(_) => TypeKind::Range,
+        Value::Slice(_) => TypeKind::Slice,
+        Value::Function(_) => TypeKind::Function,
+        Value::Builtin(_) => TypeKind::Builtin,
+        Value::BoundMethod(_) => TypeKind::Method,
+        Value::StaticMethod(_) => TypeKind::StaticMethod,
+        Value::ClassMethod(_) => TypeKind::ClassMethod,
+        Value::Property(_) => TypeKind::Property,
+        Value::Super(_) => TypeKind::Super,
+        Value::Class(_) => TypeKind::Type,
+        Value::Iterator(_) => TypeKind::Iterator,
+        Value::Generator(_) => TypeKind::Generator,
+        Value::Cell(_) => TypeKind::Cell,
+        Value::Code(_) => TypeKind::Code
+    };
+    rt.builtins().class(kind)
+}
+
+pub fn type_name<'a>(rt: &dyn Runtime<'a>, value: &Value<'a>) -> String {
+    match value {
+        Value::Instance(i) => i.class.name.clone(),
+        _ => class_of(rt, value).name.clone()
+    }
+}
+
+pub fn is_instance<'a>(rt: &dyn Runtime<'a>, value: &Value<'a>, class: &Rc<Class<'a>>) -> bool {
+    class_of(rt, value).is_subclass(class)
+}
+
+/// Exception instance is of the builtin exception class with this name.
+pub fn is_exception<'a>(rt: &dyn Runtime<'a>, value: &Value<'a>, name: &str) -> bool {
+    is_instance(rt, value, &rt.builtins().exception(name))
+}
+
+/// Identity comparison of 'is'. Immutable values kept inline compare by value.
+pub fn is_same<'a>(a: &Value<'a>, b: &Value<'a>) -> bool {
+    match ( a, b ) {
+        ( Value::None, Value::None ) | ( Value::Ellipsis, Value::Ellipsis ) | ( Value::NotImplemented, Value::NotImplemented ) => true,
+        ( Value::Bool(x), Value::Bool(y) ) => x == y,
+        ( Value::Int(x), Value::Int(y) ) => x == y,
+        ( Value::Float(x), Value::Float(y) ) => x.to_bits() == y.to_bits(),
+        ( Value::Str(x), Value::Str(y) ) => Rc::ptr_eq(x, y) || ( x.is_empty() && y.is_empty() ),
+        ( Value::Tuple(x), Value::Tuple(y) ) => Rc::ptr_eq(x, y) || ( x.is_empty() && y.is_empty() ),
+        ( Value::DictView(x, p), Value::DictView(y, q) ) => Rc::ptr_eq(x, y) && p == q,
+        _ => match ( a.identity(), b.identity() ) {
+            ( Some( x ), Some( y ) ) => x == y && std::mem::discriminant(a) == std::mem::discriminant(b),
+            _ => false
+        }
+    }
+}
+
+/// Attribute found on class bound to the instance it was looked up for, as descriptors do.
+pub fn bind<'a>(rt: &mut dyn Runtime<'a>, attribute: Value<'a>, instance: &Value<'a>, class: &Rc<Class<'a>>) -> RunResult<'a> {
+    match attribute {
+        Value::Function(_) => Ok( Value::BoundMethod( Rc::new( ( instance.clone(), attribute ) ) ) ),
+        Value::StaticMethod(f) => Ok( ( *f ).clone() ),
+        Value::ClassMethod(f) => Ok( Value::BoundMethod( Rc::new( ( Value::Class( class.clone() ), ( *f ).clone() ) ) ) ),
+        Value::Property(p) => {
+            if p.0.is_none() {
+                return throw(rt, "AttributeError", "unreadable attribute")
+            }
+            call(rt, &p.0, vec![ instance.clone() ], Vec::new())
+        },
+        _ => Ok( attribute )
+    }
+}
+
+/// Calls special method defined by class of instance, None when class does not define it.
+pub fn call_special<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>, name: &str, args: Vec<Value<'a>>) -> Result<Option<Value<'a>>, Value<'a>> {
+    let class = match value {
+        Value::Instance(i) => i.class.clone(),
+        _ => return Ok( None )
+    };
+    match class.lookup(name) {
+        Some( Value::None ) => Ok( None ),
+        Some( f ) => {
+            let f = bind(rt, f, value, &class)?;
+            call(rt, &f, args, Vec::new()).map(Some)
+        },
+        None => Ok( None )
+    }
+}
+
+/// Calls any callable value.
+pub fn call<'a>(rt: &mut dyn Runtime<'a>, func: &Value<'a>, mut args: Vec<Value<'a>>, kwargs: Vec<( String, Value<'a> )>) -> RunResult<'a> {
+    match func {
+        Value::Function(f) => rt.call_function(f, args, kwargs),
+        Value::Builtin(b) => ( b.func )(rt, args, kwargs),
+        Value::BoundMethod(m) => {
+            args.insert(0, m.0.clone());
+            call(rt, &m.1, args, kwargs)
+        },
+        Value::StaticMethod(f) => call(rt, f, args, kwargs),
+        Value::Class(c) => instantiate(rt, c, args, kwargs),
+        Value::Instance(i) if i.class.lookup("__call__").is_some() => {
+            let f = bind(rt, i.class.lookup("__call__").unwrap(), func, &i.class)?;
+            call(rt, &f, args, kwargs)
+        },
+        _ => throw(rt, "TypeError", format!("'{}' object is not callable", type_name(rt, func)))
+    }
+}
+
+fn instantiate<'a>(rt: &mut dyn Runtime<'a>, class: &Rc<Class<'a>>, args: Vec<Value<'a>>, kwargs: Vec<( String, Value<'a> )>) -> RunResult<'a> {
+    if let Some( new ) = class.new {
+        return new(rt, args, kwargs)
+    }
+    let instance = match class.kind {
+        TypeKind::Exception => new_exception(class.clone(), args.clone()),
+        TypeKind::Object => Value::Instance( Rc::new( Instance::new(class.clone()) ) ),
+        _ => return throw(rt, "TypeError", format!("cannot create '{}' instances", class.name))
+    };
+    match class.lookup("__init__") {
+        Some( init ) => {
+            let init = bind(rt, init, &instance, class)?;
+            let res = call(rt, &init, args, kwargs)?;
+            if !res.is_none() {
+                return throw(rt, "TypeError", format!("__init__() should return None, not '{}'", type_name(rt, &res)))
+            }
+        },
+        None if class.kind == TypeKind::Object && ( !args.is_empty() || !kwargs.is_empty() ) =>
+            return throw(rt, "TypeError", format!("{}() takes no arguments", class.name)),
+        None if !kwargs.is_empty() => return throw(rt, "TypeError", format!("{}() takes no keyword arguments", class.name)),
+        None => ()
+    }
+    Ok( instance )
+}
+
+fn join_names(names: &[String]) -> String {
+    let quoted : Vec<String> = names.iter().map( |n| format!("'{}'", n) ).collect();
+    match quoted.len() {
+        1 => quoted[0].clone(),
+        2 => format!("{} and {}", quoted[0], quoted[1]),
+        n => format!("{}, and {}", quoted[.. n - 1].join(", "), quoted[n - 1])
+    }
+}
+
+/// Values of the parameters of function called with arguments, in slot order of its signature.
+pub fn bind_arguments<'a>(rt: &mut dyn Runtime<'a>, function: &Function<'a>, args: Vec<Value<'a>>, kwargs: Vec<( String, Value<'a> )>) -> Result<Vec<Value<'a>>, Value<'a>> {
+    let sig = &function.signature;
+    let name = &function.qualname;
+    let npos = sig.positional.len();
+    let given = args.len();
+    let mut slots : Vec<Option<Value<'a>>> = vec![ None; sig.slots() ];
+    let mut extra = Vec::new();
+    for ( i, a ) in args.into_iter().enumerate() {
+        if i < npos {
+            slots[i] = Some( a )
+        } else {
+            extra.push(a)
+        }
+    }
+    let kwonly_start = npos + sig.vararg.iter().count();
+    if sig.vararg.is_some() {
+        slots[npos] = Some( Value::tuple(extra) )
+    } else if !extra.is_empty() {
+        let required = npos - function.defaults.len();
+        let takes = if required == npos { format!("{}", npos) } else { format!("from {} to {}", required, npos) };
+        return throw(rt, "TypeError", format!("{}() takes {} positional argument{} but {} {} given",
+                                                name, takes, if npos == 1 { "" } else { "s" }, given, if given == 1 { "was" } else { "were" }))
+    }
+    let mut kwdict = Dict::new();
+    let mut posonly_passed = Vec::new();
+    for ( key, value ) in kwargs {
+        if let Some( i ) = sig.positional.iter().position( |p| *p == key ).filter( |i| *i >= sig.posonly ) {
+            if slots[i].is_some() {
+                return throw(rt, "TypeError", format!("{}() got multiple values for argument '{}'", name, key))
+            }
+            slots[i] = Some( value )
+        } else if let Some( i ) = sig.kwonly.iter().position( |p| *p == key ) {
+            slots[kwonly_start + i] = Some( value )
+        } else if sig.kwarg.is_some() {
+            kwdict.insert(HashKey::Str( Rc::from(key.as_str()) ), Value::str(&key), value)
+        } else if sig.positional[.. sig.posonly].contains(&key) {
+            posonly_passed.push(key)
+        } else {
+            return throw(rt, "TypeError", format!("{}() got an unexpected keyword argument '{}'", name, key))
+        }
+    }
+    if !posonly_passed.is_empty() {
+        return throw(rt, "TypeError", format!("{}() got some positional-only arguments passed as keyword arguments: '{}'", name, posonly_passed.join(", ")))
+    }
+    let first_default = npos - function.defaults.len();
+    let mut missing = Vec::new();
+    for ( i, slot ) in slots.iter_mut().enumerate().take(npos) {
+        if slot.is_none() {
+            if i >= first_default {
+                *slot = Some( function.defaults[i - first_default].clone() )
+            } else {
+                missing.push(sig.positional[i].clone())
+            }
+        }
+    }
+    if !missing.is_empty() {
+        return throw(rt, "TypeError", format!("{}() missing {} required positional argument{}: {}",
+                                                name, missing.len(), if missing.len() == 1 { "" } else { "s" }, join_names(&missing)))
+    }
+    for ( i, key ) in sig.kwonly.iter().enumerate() {
+        if slots[kwonly_start + i].is_none() {
+            match function.kw_defaults.get(key) {
+                Some( v ) => slots[kwonly_start + i] = Some( v.clone() ),
+                None => missing.push(key.clone())
+            }
+        }
+    }
+    if !missing.is_empty() {
+        return throw(rt, "TypeError", format!("{}() missing {} required keyword-only argument{}: {}",
+                                                name, missing.len(), if missing.len() == 1 { "" } else { "s" }, join_names(&missing)))
+    }
+    if sig.kwarg.is_some() {
+        let last = slots.len() - 1;
+        slots[last] = Some( Value::dict(kwdict) )
+    }
+    Ok( slots.into_iter().map( |s| s.unwrap_or(Value::None) ).collect() )
+}
+
+pub fn truthy<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>) -> Result<bool, Value<'a>> {
+    Ok( match value {
+        Value::None => false,
+        Value::Bool(b) => *b,
+        Value::Int(i) => !i.is_zero(),
+        Value::Float(f) => *f != 0.0,
+        Value::Str(s) => !s.is_empty(),
+        Value::Bytes(b) => !b.is_empty(),
+        Value::List(l) => !l.borrow().is_empty(),
+        Value::Tuple(t) => !t.is_empty(),
+        Value::Dict(d) | Value::Set(d) | Value::DictView(d, _) => !d.borrow().is_empty(),
+        Value::Range(r) => !r.is_empty(),
+        Value::Instance(_) => {
+            if let Some( res ) = call_special(rt, value, "__bool__", Vec::new())? {
+                return match res {
+                    Value::Bool(b) => Ok( b ),
+                    _ => throw(rt, "TypeError", format!("__bool__ should return bool, returned {}", type_name(rt, &res)))
+                }
+            }
+            match call_special(rt, value, "__len__", Vec::new())? {
+                Some( _ ) => len(rt, value)? != 0,
+                None => true
+            }
+        },
+        _ => true
+    } )
+}
+
+fn address(value: &Value) -> String {
+    format!("0x{:012x}", value.identity().unwrap_or(0))
+}
+
+fn class_repr(class: &Class) -> String {
+    match class.dict.borrow().get("__module__") {
+        Some( Value::Str(m) ) if &**m != "builtins" => format!("<class '{}.{}'>", m, class.name),
+        _ => format!("<class '{}'>", class.name)
+    }
+}
+
+fn join_reprs<'a>(rt: &mut dyn Runtime<'a>, values: &[Value<'a>]) -> Result<String, Value<'a>> {
+    let mut parts = Vec::with_capacity(values.len());
+    for v in values {
+        parts.push(repr(rt, v)?)
+    }
+    Ok( parts.join(", ") )
+}
+
+pub fn repr<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>) -> Result<String, Value<'a>> {
+    Ok( match value {
+        Value::None => "None".to_string(),
+        Value::Ellipsis => "Ellipsis".to_string(),
+        Value::NotImplemented => "NotImplemented".to_string(),
+        Value::Bool(b) => if *b { "True".to_string() } else { "False".to_string() },
+        Value::Int(i) => i.to_string(),
+        Value::Float(f) => float_repr(*f),
+        Value::Str(s) => str_repr(s),
+        Value::Bytes(b) => bytes_repr(b),
+        Value::List(l) => {
+            let Some( _guard ) = ReprGuard::enter(value) else { return Ok( "[...]".to_string() ) };
+            let items = l.borrow().clone();
+            format!("[{}]", join_reprs(rt, &items)?)
+        },
+        Value::Tuple(t) => {
+            if t.len() == 1 {
+                format!("({},)", repr(rt, &t[0])?)
+            } else {
+                format!("({})", join_reprs(rt, t)?)
+            }
+        },
+        Value::Dict(d) => {
+            let Some( _guard ) = ReprGuard::enter(value) else { return Ok( "{...}".to_string() ) };
+            let items = d.borrow().items();
+            let mut parts = Vec::new();
+            for ( k, v ) in items {
+                parts.push(format!("{}: {}", repr(rt, &k)?, repr(rt, &v)?))
+            }
+            format!("{{{}}}", parts.join(", "))
+        },
+        Value::Set(d) => {
+            let Some( _guard ) = ReprGuard::enter(value) else { return Ok( "set(...)".to_string() ) };
+            let keys = d.borrow().keys();
+            if keys.is_empty() { "set()".to_string() } else { format!("{{{}}}", join_reprs(rt, &keys)?) }
+        },
+        Value::DictView(d, kind) => {
+            let Some( _guard ) = ReprGuard::enter(value) else { return Ok( "...".to_string() ) };
+            let items : Vec<Value<'a>> = match kind {
+                ViewKind::Keys => d.borrow().keys(),
+                ViewKind::Values => d.borrow().values(),
+                ViewKind::Items => d.borrow().items().into_iter().map( |( k, v )| Value::tuple(vec![ k, v ]) ).collect()
+            };
+            let name = match kind { ViewKind::Keys => "dict_keys", ViewKind::Values => "dict_values", ViewKind::Items => "dict_items" };
+            format!("{}([{}])", name, join_reprs(rt, &items)?)
+        },
+        Value::Range(r) => {
+            if r.step == 1 { format!("range({}, {})", r.start, r.stop) } else { format!("range({}, {}, {})", r.start, r.stop, r.step) }
+        },
+        Value::Slice(s) => format!("slice({})", join_reprs(rt, &s[..])?),
+        Value::Function(f) => format!("<function {} at {}>", f.qualname, address(value)),
+        Value::Builtin(b) => format!("<built-in function {}>", b.name),
+        Value::BoundMethod(m) => {
+            let name = match &m.1 {
+                Value::Function(f) => f.qualname.clone(),
+                Value::Builtin(b) => b.name.to_string(),
+                _ => "?".to_string()
+            };
+            format!("<bound method {} of {}>", name, repr(rt, &m.0)?)
+        },
+        Value::Class(c) => class_repr(c),
+        Value::Instance(i) => {
+            if let Some( res ) = call_special(rt, value, "__repr__", Vec::new())? {
+                return match res {
+                    Value::Str(s) => Ok( s.to_string() ),
+                    _ => throw(rt, "TypeError", format!("__repr__ returned non-string (type {})", type_name(rt, &res)))
+                }
+            }
+            if i.class.kind == TypeKind::Exception {
+                let args = exception_args(value);
+                let inner = if args.len() == 1 { repr(rt, &args[0])? } else { join_reprs(rt, &args)? };
+                return Ok( format!("{}({})", i.class.name, inner) )
+            }
+            let module = match i.class.lookup("__module__") {
+                Some( Value::Str(m) ) => m.to_string(),
+                _ => "builtins".to_string()
+            };
+            format!("<{}.{} object at {}>", module, i.class.name, address(value))
+        },
+        Value::Generator(g) => format!("<generator object {} at {}>", g.borrow().name, address(value)),
+        Value::Code(c) => format!("<code object {} at {}, file \"{}\", line {}>", c.name, address(value), c.filename, c.first_line),
+        Value::Cell(c) => match c.borrow().as_ref() {
+            Some( v ) => format!("<cell at {}: {} object at {}>", address(value), type_name(rt, v), address(v)),
+            None => format!("<cell at {}: empty>", address(value))
+        },
+        Value::Super(s) => format!("<super: {}, <{} object>>", class_repr(&s.0), type_name(rt, &s.1)),
+        _ => format!("<{} object at {}>", type_name(rt, value), address(value))
+    } )
+}
+
+/// Arguments an exception was created with.
+pub fn exception_args<'a>(value: &Value<'a>) -> Vec<Value<'a>> {
+    match value {
+        Value::Instance(i) => match i.dict.borrow().get("args") {
+            Some( Value::Tuple(t) ) => t.to_vec(),
+            _ => Vec::new()
+        },
+        _ => Vec::new()
+    }
+}
+
+/// Text of builtin 'str'.
+pub fn to_str<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>) -> Result<String, Value<'a>> {
+    match value {
+        Value::Str(s) => Ok( s.to_string() ),
+        Value::Instance(i) => {
+            if let Some( res ) = call_special(rt, value, "__str__", Vec::new())? {
+                return match res {
+                    Value::Str(s) => Ok( s.to_string() ),
+                    _ => throw(rt, "TypeError", format!("__str__ returned non-string (type {})", type_name(rt, &res)))
+                }
+            }
+            if i.class.kind == TypeKind::Exception {
+                let args = exception_args(value);
+                return match args.len() {
+                    0 => Ok( String::new() ),
+                    1 if is_exception(rt, value, "KeyError") => repr(rt, &args[0]),
+                    1 => to_str(rt, &args[0]),
+                    _ => repr(rt, &Value::tuple(args))
+                }
+            }
+            repr(rt, value)
+        },
+        _ => repr(rt, value)
+    }
+}
+
+fn num(value: &Value) -> Option<Num> {
+    match value {
+        Value::Bool(b) => Some( Num::I(Int::from(*b as i64)) ),
+        Value::Int(i) => Some( Num::I(i.clone()) ),
+        Value::Float(f) => Some( Num::F(*f) ),
+        _ => None
+    }
+}
+
+fn operator_symbol(op: Operator) -> &'static str {
+    match op {
+        Operator::Add => "+",
+        Operator::Sub => "-",
+        Operator::Mult => "*",
+        Operator::MatMult => "@",
+        Operator::Div => "/",
+        Operator::Mod => "%",
+        Operator::Pow => "** or pow()",
+        Operator::LShift => "<<",
+        Operator::RShift => ">>",
+        Operator::BitOr => "|",
+        Operator::BitXor => "^",
+        Operator::BitAnd => "&",
+        Operator::FloorDiv => "//"
+    }
+}
+
+fn operator_method(op: Operator) -> &'static str {
+    match op {
+        Operator::Add => "add",
+        Operator::Sub => "sub",
+        Operator::Mult => "mul",
+        Operator::MatMult => "matmul",
+        Operator::Div => "truediv",
+        Operator::Mod => "mod",
+        Operator::Pow => "pow",
+        Operator::LShift => "lshift",
+        Operator::RShift => "rshift",
+        Operator::BitOr => "or",
+        Operator::BitXor => "xor",
+        Operator::BitAnd => "and",
+        Operator::FloorDiv => "floordiv"
+    }
+}
+
+fn int_true_divide<'a>(rt: &dyn Runtime<'a>, a: &Int, b: &Int) -> RunResult<'a> {
+    if b.is_zero() {
+        return throw(rt, "ZeroDivisionError", "division by zero")
+    }
+    let limit = 1i64 << 53;
+    if let ( Some( x ), Some( y ) ) = ( a.to_i64(), b.to_i64() ) {
+        if x.abs() <= limit && y.abs() <= limit {
+            return Ok( Value::Float(x as f64 / y as f64) )
+        }
+    }
+    let res = a.to_f64() / b.to_f64();
+    if res.is_finite() {
+        Ok( Value::Float(res) )
+    } else {
+        throw(rt, "OverflowError", "integer division result too large for a float")
+    }
+}
+
+fn float_divmod(x: f64, y: f64) -> ( f64, f64 ) {
+    let mut m = x % y;
+    let mut div = ( x - m ) / y;
+    if m != 0.0 {
+        if ( y < 0.0 ) != ( m < 0.0 ) {
+            m += y;
+            div -= 1.0
+        }
+    } else {
+        m = 0.0f64.copysign(y)
+    }
+    let floordiv = if div != 0.0 {
+        let f = div.floor();
+        if div - f > 0.5 { f + 1.0 } else { f }
+    } else {
+        0.0f64.copysign(x / y)
+    };
+    ( floordiv, m )
+}
+
+fn int_arith<'a>(rt: &dyn Runtime<'a>, op: Operator, a: &Int, b: &Int) -> Option<RunResult<'a>> {
+    Some( match op {
+        Operator::Add => Ok( Value::Int(a.add(b)) ),
+        Operator::Sub => Ok( Value::Int(a.sub(b)) ),
+        Operator::Mult => Ok( Value::Int(a.mul(b)) ),
+        Operator::Div => int_true_divide(rt, a, b),
+        Operator::FloorDiv => match a.divmod(b) {
+            Some( ( q, _ ) ) => Ok( Value::Int(q) ),
+            None => throw(rt, "ZeroDivisionError", "integer division or modulo by zero")
+        },
+        Operator::Mod => match a.divmod(b) {
+            Some( ( _, r ) ) => Ok( Value::Int(r) ),
+            None => throw(rt, "ZeroDivisionError", "integer modulo by zero")
+        },
+        Operator::Pow => {
+            if b.is_negative() {
+                return Some( float_arith(rt, op, a.to_f64(), b.to_f64()) )
+            }
+            match b.to_i64() {
+                Some( e ) => Ok( Value::Int(a.pow(e as u64)) ),
+                None if matches!(a.to_i64(), Some( 0 ) | Some( 1 )) => Ok( Value::Int(a.clone()) ),
+                None => throw(rt, "OverflowError", "exponent too large")
+            }
+        },
+        Operator::LShift | Operator::RShift => {
+            if b.is_negative() {
+                return Some( throw(rt, "ValueError", "negative shift count") )
+            }
+            match ( op, b.to_i64() ) {
+                ( Operator::LShift, Some( n ) ) => Ok( Value::Int(a.shl(n as u64)) ),
+                ( Operator::LShift, None ) => throw(rt, "OverflowError", "too many digits in integer"),
+                ( _, n ) => Ok( Value::Int(a.shr(n.unwrap_or(i64::MAX) as u64)) )
+            }
+        },
+        Operator::BitAnd => Ok( Value::Int(a.bitand(b)) ),
+        Operator::BitOr => Ok( Value::Int(a.bitor(b)) ),
+        Operator::BitXor => Ok( Value::Int(a.bitxor(b)) ),
+        Operator::MatMult => return None
+    } )
+}
+
+fn float_arith<'a>(rt: &dyn Runtime<'a>, op: Operator, x: f64, y: f64) -> RunResult<'a> {
+    Ok( Value::Float( match op {
+        Operator::Add => x + y,
+        Operator::Sub => x - y,
+        Operator::Mult => x * y,
+        Operator::Div => {
+            if y == 0.0 {
+                return throw(rt, "ZeroDivisionError", "float division by zero")
+            }
+            x / y
+        },
+        Operator::FloorDiv => {
+            if y == 0.0 {
+                return throw(rt, "ZeroDivisionError", "float floor division by zero")
+            }
+            float_divmod(x, y).0
+        },
+        Operator::Mod => {
+            if y == 0.0 {
+                return throw(rt, "ZeroDivisionError", "float modulo")
+            }
+            float_divmod(x, y).1
+        },
+        Operator::Pow => {
+            if x == 0.0 && y < 0.0 {
+                return throw(rt, "ZeroDivisionError", "0.0 cannot be raised to a negative power")
+            }
+            if x < 0.0 && y.fract() != 0.0 {
+                return throw(rt, "ValueError", "complex results are not supported")
+            }
+            let res = x.powf(y);
+            if res.is_infinite() && x.is_finite() && y.is_finite() {
+                return throw(rt, "OverflowError", "(34, 'Numerical result out of range')")
+            }
+            res
+        },
+        _ => return throw(rt, "TypeError", format!("unsupported operand type(s) for {}: 'float' and 'float'", operator_symbol(op)))
+    } ) )
+}
+
+fn repeat<'a>(rt: &dyn Runtime<'a>, count: &Value<'a>) -> Result<Option<usize>, Value<'a>> {
+    match count {
+        Value::Bool(b) => Ok( Some( *b as usize ) ),
+        Value::Int(i) => match i.to_i64() {
+            Some( n ) => Ok( Some( n.max(0) as usize ) ),
+            None if i.is_negative() => Ok( Some( 0 ) ),
+            None => throw(rt, "OverflowError", "cannot fit 'int' into an index-sized integer")
+        },
+        _ => Ok( None )
+    }
+}
+
+fn builtin_binary<'a>(rt: &mut dyn Runtime<'a>, op: Operator, a: &Value<'a>, b: &Value<'a>) -> Result<Option<Value<'a>>, Value<'a>> {
+    if let ( Value::Bool(x), Value::Bool(y) ) = ( a, b ) {
+        match op {
+            Operator::BitAnd => return Ok( Some( Value::Bool(*x && *y) ) ),
+            Operator::BitOr => return Ok( Some( Value::Bool(*x || *y) ) ),
+            Operator::BitXor => return Ok( Some( Value::Bool(x != y) ) ),
+            _ => ()
+        }
+    }
+    if let ( Some( x ), Some( y ) ) = ( num(a), num(b) ) {
+        return match ( x, y ) {
+            ( Num::I(x), Num::I(y) ) => int_arith(rt, op, &x, &y).transpose(),
+            ( Num::I(x), Num::F(y) ) if op != Operator::MatMult && !is_bitwise(op) => float_arith(rt, op, x.to_f64(), y).map(Some),
+            ( Num::F(x), Num::I(y) ) if op != Operator::MatMult && !is_bitwise(op) => float_arith(rt, op, x, y.to_f64()).map(Some),
+            ( Num::F(x), Num::F(y) ) if op != Operator::MatMult && !is_bitwise(op) => float_arith(rt, op, x, y).map(Some),
+            _ => Ok( None )
+        }
+    }
+    Ok( match ( op, a, b ) {
+        ( Operator::Add, Value::Str(x), Value::Str(y) ) => Some( Value::str(&format!("{}{}", x, y)) ),
+        ( Operator::Add, Value::Bytes(x), Value::Bytes(y) ) => Some( Value::Bytes( Rc::from([ &x[..], &y[..] ].concat()) ) ),
+        ( Operator::Add, Value::List(x), Value::List(y) ) => {
+            let mut items = x.borrow().clone();
+            items.extend(y.borrow().iter().cloned());
+            Some( Value::list(items) )
+        },
+        ( Operator::Add, Value::Tuple(x), Value::Tuple(y) ) => Some( Value::tuple(x.iter().chain(y.iter()).cloned().collect()) ),
+        ( Operator::Mult, Value::Str(_) | Value::Bytes(_) | Value::List(_) | Value::Tuple(_), _ ) |
+        ( Operator::Mult, _, Value::Str(_) | Value::Bytes(_) | Value::List(_) | Value::Tuple(_) ) => {
+            let ( seq, count ) = if matches!(a, Value::Str(_) | Value::Bytes(_) | Value::List(_) | Value::Tuple(_)) { ( a, b ) } else { ( b, a ) };
+            let n = match repeat(rt, count)? {
+                Some( n ) => n,
+                None => return Ok( None )
+            };
+            match seq {
+                Value::Str(s) => Some( Value::str(&s.repeat(n)) ),
+                Value::Bytes(s) => Some( Value::Bytes( Rc::from(s.repeat(n)) ) ),
+                Value::List(l) => {
+                    let items = l.borrow();
+                    Some( Value::list(( 0 .. n ).flat_map( |_| items.iter().cloned() ).collect()) )
+                },
+                Value::Tuple(t) => Some( Value::tuple(( 0 .. n ).flat_map( |_| t.iter().cloned() ).collect()) ),
+                _ => None
+            }
+        },
+        ( Operator::Mod, Value::Str(s), _ ) => Some( Value::str(&percent_format(rt, s, b)?) ),
+        ( Operator::BitOr | Operator::BitAnd | Operator::Sub | Operator::BitXor, Value::Set(x), Value::Set(y) ) => {
+            let ( x, y ) = ( x.borrow(), y.borrow() );
+            let mut res = Dict::new();
+            for ( h, k, _ ) in x.iter() {
+                let keep = match op {
+                    Operator::BitAnd => y.contains(h),
+                    Operator::BitOr => true,
+                    _ => !y.contains(h)
+                };
+                if keep {
+                    res.insert(h.clone(), k.clone(), Value::None)
+                }
+            }
+            if matches!(op, Operator::BitOr | Operator::BitXor) {
+                for ( h, k, _ ) in y.iter() {
+                    if op == Operator::BitOr || !x.contains(h) {
+                        res.insert(h.clone(), k.clone(), Value::None)
+                    }
+                }
+            }
+            Some( Value::set(res) )
+        },
+        ( Operator::BitOr, Value::Dict(x), Value::Dict(y) ) => {
+            let mut res = x.borrow().clone();
+            for ( h, k, v ) in y.borrow().iter() {
+                res.insert(h.clone(), k.clone(), v.clone())
+            }
+            Some( Value::dict(res) )
+        },
+        _ => None
+    } )
+}
+
+fn is_bitwise(op: Operator) -> bool {
+    matches!(op, Operator::LShift | Operator::RShift | Operator::BitAnd | Operator::BitOr | Operator::BitXor)
+}
+
+/// Result of binary operator, tries the builtin operation and then the special methods of instances.
+pub fn binary_op<'a>(rt: &mut dyn Runtime<'a>, op: Operator, a: &Value<'a>, b: &Value<'a>) -> RunResult<'a> {
+    if let Some( res ) = builtin_binary(rt, op, a, b)? {
+        return Ok( res )
+    }
+    let name = operator_method(op);
+    if let Some( res ) = call_special(rt, a, &format!("__{}__", name), vec![ b.clone() ])? {
+        if !matches!(res, Value::NotImplemented) {
+            return Ok( res )
+        }
+    }
+    if let Some( res ) = call_special(rt, b, &format!("__r{}__", name), vec![ a.clone() ])? {
+        if !matches!(res, Value::NotImplemented) {
+            return Ok( res )
+        }
+    }
+    throw(rt, "TypeError", format!("unsupported operand type(s) for {}: '{}' and '{}'", operator_symbol(op), type_name(rt, a), type_name(rt, b)))
+}
+
+/// Result of augmented assignment, which changes mutable containers in place.
+pub fn inplace_op<'a>(rt: &mut dyn Runtime<'a>, op: Operator, a: &Value<'a>, b: &Value<'a>) -> RunResult<'a> {
+    match ( op, a ) {
+        ( Operator::Add, Value::List(l) ) => {
+            let items = collect(rt, b)?;
+            l.borrow_mut().extend(items);
+            return Ok( a.clone() )
+        },
+        ( Operator::BitOr, Value::Dict(d) ) if matches!(b, Value::Dict(_)) => {
+            dict_update(rt, d, b)?;
+            return Ok( a.clone() )
+        },
+        ( Operator::BitOr | Operator::BitAnd | Operator::Sub | Operator::BitXor, Value::Set(s) ) if matches!(b, Value::Set(_)) => {
+            let res = binary_op(rt, op, a, b)?;
+            if let Value::Set(r) = res {
+                let r = r.borrow().clone();
+                *s.borrow_mut() = r
+            }
+            return Ok( a.clone() )
+        },
+        _ => ()
+    }
+    if let Some( res ) = call_special(rt, a, &format!("__i{}__", operator_method(op)), vec![ b.clone() ])? {
+        if !matches!(res, Value::NotImplemented) {
+            return Ok( res )
+        }
+    }
+    binary_op(rt, op, a, b).map_err( |e| {
+        match op {
+            Operator::Pow => e,
+            _ => {
+                let symbol = format!("{}=", operator_symbol(op));
+                let args = exception_args(&e);
+                match args.first() {
+                    Some( Value::Str(s) ) if s.starts_with("unsupported operand") => {
+                        error(rt, "TypeError", s.replacen(&format!(" {}:", operator_symbol(op)), &format!(" {}:", symbol), 1))
+                    },
+                    _ => e
+                }
+            }
+        }
+    } )
+}
+
+pub fn unary_op<'a>(rt: &mut dyn Runtime<'a>, op: UnaryOperator, value: &Value<'a>) -> RunResult<'a> {
+    if op == UnaryOperator::Not {
+        return Ok( Value::Bool(!truthy(rt, value)?) )
+    }
+    match ( op, num(value) ) {
+        ( UnaryOperator::USub, Some( Num::I(i) ) ) => return Ok( Value::Int(i.neg()) ),
+        ( UnaryOperator::USub, Some( Num::F(f) ) ) => return Ok( Value::Float(-f) ),
+        ( UnaryOperator::UAdd, Some( Num::I(i) ) ) => return Ok( Value::Int(i) ),
+        ( UnaryOperator::UAdd, Some( Num::F(f) ) ) => return Ok( Value::Float(f) ),
+        ( UnaryOperator::Invert, Some( Num::I(i) ) ) => return Ok( Value::Int(i.invert()) ),
+        _ => ()
+    }
+    let ( name, symbol ) = match op {
+        UnaryOperator::USub => ( "__neg__", "-" ),
+        UnaryOperator::UAdd => ( "__pos__", "+" ),
+        _ => ( "__invert__", "~" )
+    };
+    match call_special(rt, value, name, Vec::new())? {
+        Some( res ) => Ok( res ),
+        None => throw(rt, "TypeError", format!("bad operand type for unary {}: '{}'", symbol, type_name(rt, value)))
+    }
+}
+
+fn num_cmp(a: &Num, b: &Num) -> Option<Ordering> {
+    match ( a, b ) {
+        ( Num::I(x), Num::I(y) ) => Some( x.cmp(y) ),
+        ( Num::I(x), Num::F(y) ) => x.to_f64().partial_cmp(y),
+        ( Num::F(x), Num::I(y) ) => x.partial_cmp(&y.to_f64()),
+        ( Num::F(x), Num::F(y) ) => x.partial_cmp(y)
+    }
+}
+
+/// Equality of '==', falls back to identity for objects without '__eq__'.
+pub fn equal<'a>(rt: &mut dyn Runtime<'a>, a: &Value<'a>, b: &Value<'a>) -> Result<bool, Value<'a>> {
+    if let ( Some( x ), Some( y ) ) = ( num(a), num(b) ) {
+        return Ok( num_cmp(&x, &y) == Some( Ordering::Equal ) )
+    }
+    match ( a, b ) {
+        ( Value::Str(x), Value::Str(y) ) => Ok( x == y ),
+        ( Value::Bytes(x), Value::Bytes(y) ) => Ok( x == y ),
+        ( Value::List(x), Value::List(y) ) => {
+            if Rc::ptr_eq(x, y) {
+                return Ok( true )
+            }
+            let ( x, y ) = ( x.borrow().clone(), y.borrow().clone() );
+            sequence_equal(rt, &x, &y)
+        },
+        ( Value::Tuple(x), Value::Tuple(y) ) => sequence_equal(rt, x, y),
+        ( Value::Dict(x), Value::Dict(y) ) => {
+            let items = x.borrow().items();
+            if items.len() != y.borrow().len() {
+                return Ok( false )
+            }
+            for ( k, v ) in items {
+                let h = hash_key(rt, &k)?;
+                let other = y.borrow().get(&h).cloned();
+                match other {
+                    Some( w ) if equal(rt, &v, &w)? => (),
+                    _ => return Ok( false )
+                }
+            }
+            Ok( true )
+        },
+        ( Value::Set(x), Value::Set(y) ) => {
+            let ( x, y ) = ( x.borrow(), y.borrow() );
+            Ok( x.len() == y.len() && x.iter().all( |( h, _, _ )| y.contains(h) ) )
+        },
+        ( Value::Range(x), Value::Range(y) ) => {
+            let ( n, m ) = ( x.len(), y.len() );
+            Ok( n == m && ( n == 0 || ( x.start == y.start && ( n == 1 || x.step == y.step ) ) ) )
+        },
+        ( Value::Instance(_), _ ) | ( _, Value::Instance(_) ) => {
+            for ( x, y ) in [ ( a, b ), ( b, a ) ] {
+                if let Some( res ) = call_special(rt, x, "__eq__", vec![ y.clone() ])? {
+                    if !matches!(res, Value::NotImplemented) {
+                        return truthy(rt, &res)
+                    }
+                }
+            }
+            Ok( is_same(a, b) )
+        },
+        _ => Ok( is_same(a, b) )
+    }
+}
+
+fn sequence_equal<'a>(rt: &mut dyn Runtime<'a>, x: &[Value<'a>], y: &[Value<'a>]) -> Result<bool, Value<'a>> {
+    if x.len() != y.len() {
+        return Ok( false )
+    }
+    for ( p, q ) in x.iter().zip(y.iter()) {
+        if !is_same(p, q) && !equal(rt, p, q)? {
+            return Ok( false )
+        }
+    }
+    Ok( true )
+}
+
+fn cmp_symbol(op: CmpOperator) -> &'static str {
+    match op {
+        CmpOperator::Lt => "<",
+        CmpOperator::LtE => "<=",
+        CmpOperator::Gt => ">",
+        _ => ">="
+    }
+}
+
+fn ordering_holds(op: CmpOperator, ord: Ordering) -> bool {
+    match op {
+        CmpOperator::Lt => ord == Ordering::Less,
+        CmpOperator::LtE => ord != Ordering::Greater,
+        CmpOperator::Gt => ord == Ordering::Greater,
+        _ => ord != Ordering::Less
+    }
+}
+
+/// Result of '<', '<=', '>' or '>='.
+pub fn order<'a>(rt: &mut dyn Runtime<'a>, op: CmpOperator, a: &Value<'a>, b: &Value<'a>) -> Result<bool, Value<'a>> {
+    if let ( Some( x ), Some( y ) ) = ( num(a), num(b) ) {
+        return Ok( num_cmp(&x, &y).map( |o| ordering_holds(op, o) ).unwrap_or(false) )
+    }
+    match ( a, b ) {
+        ( Value::Str(x), Value::Str(y) ) => return Ok( ordering_holds(op, x.cmp(y)) ),
+        ( Value::Bytes(x), Value::Bytes(y) ) => return Ok( ordering_holds(op, x.cmp(y)) ),
+        ( Value::List(x), Value::List(y) ) => {
+            let ( x, y ) = ( x.borrow().clone(), y.borrow().clone() );
+            return sequence_order(rt, op, &x, &y)
+        },
+        ( Value::Tuple(x), Value::Tuple(y) ) => return sequence_order(rt, op, x, y),
+        ( Value::Set(x), Value::Set(y) ) => {
+            let ( x, y ) = ( x.borrow(), y.borrow() );
+            let subset = |p: &Dict<'a>, q: &Dict<'a>| p.iter().all( |( h, _, _ )| q.contains(h) );
+            return Ok( match op {
+                CmpOperator::Lt => x.len() < y.len() && subset(&x, &y),
+                CmpOperator::LtE => subset(&x, &y),
+                CmpOperator::Gt => x.len() > y.len() && subset(&y, &x),
+                _ => subset(&y, &x)
+            } )
+        },
+        _ => ()
+    }
+    let ( name, reflected ) = match op {
+        CmpOperator::Lt => ( "__lt__", "__gt__" ),
+        CmpOperator::LtE => ( "__le__", "__ge__" ),
+        CmpOperator::Gt => ( "__gt__", "__lt__" ),
+        _ => ( "__ge__", "__le__" )
+    };
+    for ( x, y, method ) in [ ( a, b, name ), ( b, a, reflected ) ] {
+        if let Some( res ) = call_special(rt, x, method, vec![ y.clone() ])? {
+            if !matches!(res, Value::NotImplemented) {
+                return truthy(rt, &res)
+            }
+        }
+    }
+    throw(rt, "TypeError", format!("'{}' not supported between instances of '{}' and '{}'", cmp_symbol(op), type_name(rt, a), type_name(rt, b)))
+}
+
+fn sequence_order<'a>(rt: &mut dyn Runtime<'a>, op: CmpOperator, x: &[Value<'a>], y: &[Value<'a>]) -> Result<bool, Value<'a>> {
+    for ( p, q ) in x.iter().zip(y.iter()) {
+        if !is_same(p, q) && !equal(rt, p, q)? {
+            return order(rt, op, p, q)
+        }
+    }
+    Ok( ordering_holds(op, x.len().cmp(&y.len())) )
+}
+
+/// Result of comparison operator.
+pub fn compare<'a>(rt: &mut dyn Runtime<'a>, op: CmpOperator, a: &Value<'a>, b: &Value<'a>) -> Result<bool, Value<'a>> {
+    match op {
+        CmpOperator::Eq => equal(rt, a, b),
+        CmpOperator::NotEq => {
+            if let Some( res ) = call_special(rt, a, "__ne__", vec![ b.clone() ])? {
+                if !matches!(res, Value::NotImplemented) {
+                    return truthy(rt, &res)
+                }
+            }
+            Ok( !equal(rt, a, b)? )
+        },
+        CmpOperator::Is => Ok( is_same(a, b) ),
+        CmpOperator::IsNot => Ok( !is_same(a, b) ),
+        CmpOperator::In => contains(rt, b, a),
+        CmpOperator::NotIn => Ok( !contains(rt, b, a)? ),
+        _ => order(rt, op, a, b)
+    }
+}
+
+/// Sort order used by 'sorted', 'min' and 'max'.
+pub fn less<'a>(rt: &mut dyn Runtime<'a>, a: &Value<'a>, b: &Value<'a>) -> Result<bool, Value<'a>> {
+    order(rt, CmpOperator::Lt, a, b)
+}
+
+/// Membership test of 'in'.
+pub fn contains<'a>(rt: &mut dyn Runtime<'a>, container: &Value<'a>, item: &Value<'a>) -> Result<bool, Value<'a>> {
+    match container {
+        Value::Str(s) => match item {
+            Value::Str(i) => Ok( s.contains(&**i) ),
+            _ => throw(rt, "TypeError", format!("'in <string>' requires string as left operand, not {}", type_name(rt, item)))
+        },
+        Value::Bytes(b) => match item {
+            Value::Int(i) => Ok( i.to_i64().map( |i| b.iter().any( |c| *c as i64 == i ) ).unwrap_or(false) ),
+            Value::Bytes(i) => Ok( i.is_empty() || b.windows(i.len()).any( |w| w == &i[..] ) ),
+            _ => throw(rt, "TypeError", format!("a bytes-like object is required, not '{}'", type_name(rt, item)))
+        },
+        Value::List(l) => {
+            let items = l.borrow().clone();
+            for v in items.iter() {
+                if is_same(v, item) || equal(rt, v, item)? {
+                    return Ok( true )
+                }
+            }
+            Ok( false )
+        },
+        Value::Tuple(t) => {
+            for v in t.iter() {
+                if is_same(v, item) || equal(rt, v, item)? {
+                    return Ok( true )
+                }
+            }
+            Ok( false )
+        },
+        Value::Dict(d) | Value::Set(d) | Value::DictView(d, ViewKind::Keys) => {
+            let h = hash_key(rt, item)?;
+            Ok( d.borrow().contains(&h) )
+        },
+        Value::DictView(d, ViewKind::Items) => {
+            if let Value::Tuple(t) = item {
+                if t.len() == 2 {
+                    let h = hash_key(rt, &t[0])?;
+                    let value = d.borrow().get(&h).cloned();
+                    if let Some( v ) = value {
+                        return equal(rt, &v, &t[1])
+                    }
+                }
+            }
+            Ok( false )
+        },
+        Value::Range(r) => {
+            let i = match item {
+                Value::Int(i) => i.to_i64(),
+                Value::Bool(b) => Some( *b as i64 ),
+                Value::Float(f) if f.fract() == 0.0 => Some( *f as i64 ),
+                _ => return Ok( false )
+            };
+            Ok( match i {
+                Some( i ) => ( r.step > 0 && r.start <= i && i < r.stop || r.step < 0 && r.stop < i && i <= r.start ) && ( i - r.start ) % r.step == 0,
+                None => false
+            } )
+        },
+        _ => {
+            if let Some( res ) = call_special(rt, container, "__contains__", vec![ item.clone() ])? {
+                return truthy(rt, &res)
+            }
+            let it = match iter(rt, container) {
+                Ok( it ) => it,
+                Err( _ ) => return throw(rt, "TypeError", format!("argument of type '{}' is not iterable", type_name(rt, container)))
+            };
+            while let Some( v ) = next(rt, &it)? {
+                if is_same(&v, item) || equal(rt, &v, item)? {
+                    return Ok( true )
+                }
+            }
+            Ok( false )
+        }
+    }
+}
+
+/// Key of value in dictionaries and sets, fails for unhashable values.
+pub fn hash_key<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>) -> Result<HashKey, Value<'a>> {
+    Ok( match value {
+        Value::None => HashKey::None,
+        Value::Ellipsis => HashKey::Ellipsis,
+        Value::Bool(b) => HashKey::Int( Int::from(*b as i64) ),
+        Value::Int(i) => HashKey::Int( i.clone() ),
+        Value::Float(f) => {
+            if f.is_finite() && f.fract() == 0.0 {
+                HashKey::Int( Int::from_f64(*f) )
+            } else {
+                HashKey::Float( f.to_bits() )
+            }
+        },
+        Value::Str(s) => HashKey::Str( s.clone() ),
+        Value::Bytes(b) => HashKey::Bytes( b.clone() ),
+        Value::Tuple(t) => {
+            let mut keys = Vec::with_capacity(t.len());
+            for v in t.iter() {
+                keys.push(hash_key(rt, v)?)
+            }
+            HashKey::Tuple(keys)
+        },
+        Value::List(_) | Value::Dict(_) | Value::Set(_) | Value::DictView(..) | Value::Slice(_) =>
+            return throw(rt, "TypeError", format!("unhashable type: '{}'", type_name(rt, value))),
+        Value::Instance(i) => {
+            match i.class.lookup("__hash__") {
+                Some( Value::None ) => return throw(rt, "TypeError", format!("unhashable type: '{}'", i.class.name)),
+                Some( _ ) => match call_special(rt, value, "__hash__", Vec::new())? {
+                    Some( Value::Int(h) ) => HashKey::Custom( h.to_i64().unwrap_or_else( || h.bitand(&Int::from(i64::MAX)).to_i64().unwrap() ) ),
+                    Some( Value::Bool(b) ) => HashKey::Custom(b as i64),
+                    _ => return throw(rt, "TypeError", "__hash__ method should return an integer")
+                },
+                None if i.class.lookup("__eq__").is_some() => return throw(rt, "TypeError", format!("unhashable type: '{}'", i.class.name)),
+                None => HashKey::Identity( value.identity().unwrap() )
+            }
+        },
+        _ => HashKey::Identity( value.identity().unwrap_or(0) )
+    } )
+}
+
+fn as_index(value: &Value) -> Option<Result<i64, ()>> {
+    match value {
+        Value::Bool(b) => Some( Ok( *b as i64 ) ),
+        Value::Int(i) => Some( i.to_i64().ok_or(()) ),
+        _ => None
+    }
+}
+
+/// Integer value usable as index or size, as with '__index__'.
+pub fn to_index<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>) -> Result<i64, Value<'a>> {
+    match as_index(value) {
+        Some( Ok( i ) ) => Ok( i ),
+        Some( Err( _ ) ) => throw(rt, "OverflowError", "cannot fit 'int' into an index-sized integer"),
+        None => match call_special(rt, value, "__index__", Vec::new())? {
+            Some( v ) if as_index(&v).is_some() => to_index(rt, &v),
+            _ => throw(rt, "TypeError", format!("'{}' object cannot be interpreted as an integer", type_name(rt, value)))
+        }
+    }
+}
+
+/// Start, stop and step of slice applied to sequence of length, clamped like 'slice.indices'.
+pub fn slice_indices<'a>(rt: &mut dyn Runtime<'a>, slice: &[Value<'a>; 3], len: i64) -> Result<( i64, i64, i64 ), Value<'a>> {
+    let mut bound = |v: &Value<'a>| -> Result<Option<i64>, Value<'a>> {
+        match v {
+            Value::None => Ok( None ),
+            Value::Int(i) if i.to_i64().is_none() => Ok( Some( if i.is_negative() { i64::MIN / 2 } else { i64::MAX / 2 } ) ),
+            _ => match to_index(rt, v) {
+                Ok( i ) => Ok( Some( i ) ),
+                Err( _ ) => throw(rt, "TypeError", "slice indices must be integers or None or have an __index__ method")
+            }
+        }
+    };
+    let step = bound(&slice[2])?.unwrap_or(1);
+    if step == 0 {
+        return throw(rt, "ValueError", "slice step cannot be zero")
+    }
+    let start = bound(&slice[0])?;
+    let stop = bound(&slice[1])?;
+    let clamp = |v: Option<i64>, default: i64| -> i64 {
+        match v {
+            None => default,
+            Some( i ) if i < 0 => {
+                let i = i + len;
+                if i < 0 { if step < 0 { -1 } else { 0 } } else { i }
+            },
+            Some( i ) if i >= len => if step < 0 { len - 1 } else { len },
+            Some( i ) => i
+        }
+    };
+    if step > 0 {
+        Ok( ( clamp(start, 0), clamp(stop, len), step ) )
+    } else {
+        Ok( ( clamp(start, len - 1), clamp(stop, -1), step ) )
+    }
+}
+
+/// Positions selected by slice from sequence of length.
+pub fn slice_positions<'a>(rt: &mut dyn Runtime<'a>, slice: &[Value<'a>; 3], len: usize) -> Result<Vec<usize>, Value<'a>> {
+    let ( start, stop, step ) = slice_indices(rt, slice, len as i64)?;
+    let mut res = Vec::new();
+    let mut i = start;
+    while ( step > 0 && i < stop ) || ( step < 0 && i > stop ) {
+        res.push(i as usize);
+        i += step
+    }
+    Ok( res )
+}
+
+fn sequence_index<'a>(rt: &mut dyn Runtime<'a>, index: &Value<'a>, len: usize, what: &str) -> Result<usize, Value<'a>> {
+    let i = match as_index(index) {
+        Some( Ok( i ) ) => i,
+        Some( Err( _ ) ) => return throw(rt, "IndexError", "cannot fit 'int' into an index-sized integer"),
+        None => match call_special(rt, index, "__index__", Vec::new())? {
+            Some( v ) => to_index(rt, &v)?,
+            None => return throw(rt, "TypeError", format!("{} indices must be integers or slices, not {}", what, type_name(rt, index)))
+        }
+    };
+    let j = if i < 0 { i + len as i64 } else { i };
+    if j < 0 || j >= len as i64 {
+        let kind = if what == "str" { "string" } else { what };
+        return throw(rt, "IndexError", format!("{} index out of range", kind))
+    }
+    Ok( j as usize )
+}
+
+pub fn get_item<'a>(rt: &mut dyn Runtime<'a>, container: &Value<'a>, index: &Value<'a>) -> RunResult<'a> {
+    match ( container, index ) {
+        ( Value::List(l), Value::Slice(s) ) => {
+            let items = l.borrow().clone();
+            let positions = slice_positions(rt, s, items.len())?;
+            Ok( Value::list(positions.into_iter().map( |i| items[i].clone() ).collect()) )
+        },
+        ( Value::List(l), _ ) => {
+            let len = l.borrow().len();
+            let i = sequence_index(rt, index, len, "list")?;
+            Ok( l.borrow()[i].clone() )
+        },
+        ( Value::Tuple(t), Value::Slice(s) ) => {
+            let positions = slice_positions(rt, s, t.len())?;
+            Ok( Value::tuple(positions.into_iter().map( |i| t[i].clone() ).collect()) )
+        },
+        ( Value::Tuple(t), _ ) => {
+            let i = sequence_index(rt, index, t.len(), "tuple")?;
+            Ok( t[i].clone() )
+        },
+        ( Value::Str(s), _ ) => {
+            let chars : Vec<char> = s.chars().collect();
+            match index {
+                Value::Slice(sl) => {
+                    let positions = slice_positions(rt, sl, chars.len())?;
+                    Ok( Value::str(&positions.into_iter().map( |i| chars[i] ).collect::<String>()) )
+                },
+                _ => {
+                    let i = sequence_index(rt, index, chars.len(), "str")?;
+                    Ok( Value::str(&chars[i].to_string()) )
+                }
+            }
+        },
+        ( Value::Bytes(b), Value::Slice(s) ) => {
+            let positions = slice_positions(rt, s, b.len())?;
+            Ok( Value::Bytes( positions.into_iter().map( |i| b[i] ).collect() ) )
+        },
+        ( Value::Bytes(b), _ ) => {
+            let i = sequence_index(rt, index, b.len(), "index")?;
+            Ok( Value::int(b[i] as i64) )
+        },
+        ( Value::Range(r), Value::Slice(s) ) => {
+            let ( start, stop, step ) = slice_indices(rt, s, r.len())?;
+            Ok( Value::Range( Rc::new( Range { start: r.get(start), stop: r.get(stop), step: r.step * step } ) ) )
+        },
+        ( Value::Range(r), _ ) => {
+            let i = sequence_index(rt, index, r.len() as usize, "range object")?;
+            Ok( Value::int(r.get(i as i64)) )
+        },
+        ( Value::Dict(d), _ ) => {
+            let h = hash_key(rt, index)?;
+            let found = d.borrow().get(&h).cloned();
+            match found {
+                Some( v ) => Ok( v ),
+                None => Err( new_exception(rt.builtins().exception("KeyError"), vec![ index.clone() ]) )
+            }
+        },
+        ( Value::Class(_), _ ) => Ok( container.clone() ),
+        _ => match call_special(rt, container, "__getitem__", vec![ index.clone() ])? {
+            Some( v ) => Ok( v ),
+            None => throw(rt, "TypeError", format!("'{}' object is not subscriptable", type_name(rt, container)))
+        }
+    }
+}
+
+pub fn set_item<'a>(rt: &mut dyn Runtime<'a>, container: &Value<'a>, index: &Value<'a>, value: Value<'a>) -> Result<(), Value<'a>> {
+    match ( container, index ) {
+        ( Value::List(l), Value::Slice(s) ) => {
+            let values = collect(rt, &value)?;
+            let len = l.borrow().len();
+            let ( start, stop, step ) = slice_indices(rt, s, len as i64)?;
+            if step == 1 {
+                let stop = stop.max(start);
+                l.borrow_mut().splice(start as usize .. stop as usize, values);
+                return Ok(())
+            }
+            let positions = slice_positions(rt, s, len)?;
+            if positions.len() != values.len() {
+                return throw(rt, "ValueError", format!("attempt to assign sequence of size {} to extended slice of size {}", values.len(), positions.len()))
+            }
+            let mut items = l.borrow_mut();
+            for ( i, v ) in positions.into_iter().zip(values) {
+                items[i] = v
+            }
+            Ok(())
+        },
+        ( Value::List(l), _ ) => {
+            let len = l.borrow().len();
+            let i = sequence_index(rt, index, len, "list").map_err( |e| {
+                if is_exception(rt, &e, "IndexError") { error(rt, "IndexError", "list assignment index out of range") } else { e }
+            } )?;
+            l.borrow_mut()[i] = value;
+            Ok(())
+        },
+        ( Value::Dict(d), _ ) => {
+            let h = hash_key(rt, index)?;
+            d.borrow_mut().insert(h, index.clone(), value);
+            Ok(())
+        },
+        _ => match call_special(rt, container, "__setitem__", vec![ index.clone(), value ])? {
+            Some( _ ) => Ok(()),
+            None => throw(rt, "TypeError", format!("'{}' object does not support item assignment", type_name(rt, container)))
+        }
+    }
+}
+
+pub fn del_item<'a>(rt: &mut dyn Runtime<'a>, container: &Value<'a>, index: &Value<'a>) -> Result<(), Value<'a>> {
+    match ( container, index ) {
+        ( Value::List(l), Value::Slice(s) ) => {
+            let len = l.borrow().len();
+            let mut positions = slice_positions(rt, s, len)?;
+            positions.sort_unstable();
+            let mut items = l.borrow_mut();
+            for i in positions.into_iter().rev() {
+                items.remove(i);
+            }
+            Ok(())
+        },
+        ( Value::List(l), _ ) => {
+            let len = l.borrow().len();
+            let i = sequence_index(rt, index, len, "list").map_err( |e| {
+                if is_exception(rt, &e, "IndexError") { error(rt, "IndexError", "list assignment index out of range") } else { e }
+            } )?;
+            l.borrow_mut().remove(i);
+            Ok(())
+        },
+        ( Value::Dict(d), _ ) => {
+            let h = hash_key(rt, index)?;
+            let removed = d.borrow_mut().remove(&h);
+            match removed {
+                Some( _ ) => Ok(()),
+                None => Err( new_exception(rt.builtins().exception("KeyError"), vec![ index.clone() ]) )
+            }
+        },
+        _ => match call_special(rt, container, "__delitem__", vec![ index.clone() ])? {
+            Some( _ ) => Ok(()),
+            None => throw(rt, "TypeError", format!("'{}' object doesn't support item deletion", type_name(rt, container)))
+        }
+    }
+}
+
+fn attribute_error<'a>(rt: &dyn Runtime<'a>, value: &Value<'a>, name: &str) -> Value<'a> {
+    match value {
+        Value::Class(c) => error(rt, "AttributeError", format!("type object '{}' has no attribute '{}'", c.name, name)),
+        _ => error(rt, "AttributeError", format!("'{}' object has no attribute '{}'", type_name(rt, value), name))
+    }
+}
+
+/// Attribute of builtin kind of value, the methods of builtin types bound to value.
+fn builtin_attribute<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>, kind: TypeKind, name: &str) -> Option<Value<'a>> {
+    if name == "__class__" {
+        return Some( Value::Class( class_of(rt, value) ) )
+    }
+    method(kind, name).map( |( n, f )| Value::BoundMethod( Rc::new( ( value.clone(), Value::builtin(n, f) ) ) ) )
+}
+
+pub fn get_attr<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>, name: &str) -> RunResult<'a> {
+    match value {
+        Value::Instance(i) => {
+            let class_attr = i.class.lookup(name);
+            if let Some( Value::Property(_) ) = class_attr {
+                return bind(rt, class_attr.unwrap(), value, &i.class)
+            }
+            let own = i.dict.borrow().get(name).cloned();
+            if let Some( v ) = own {
+                return Ok( v )
+            }
+            if let Some( attr ) = class_attr {
+                return bind(rt, attr, value, &i.class)
+            }
+            if i.class.kind == TypeKind::Exception && matches!(name, "__cause__" | "__context__" | "__traceback__") {
+                return Ok( Value::None )
+            }
+            if name == "value" && is_exception(rt, value, "StopIteration") {
+                return Ok( exception_args(value).into_iter().next().unwrap_or(Value::None) )
+            }
+            if let Some( v ) = builtin_attribute(rt, value, i.class.kind, name) {
+                return Ok( v )
+            }
+            if let Some( getattr ) = i.class.lookup("__getattr__") {
+                let getattr = bind(rt, getattr, value, &i.class)?;
+                return call(rt, &getattr, vec![ Value::str(name) ], Vec::new())
+            }
+            Err( attribute_error(rt, value, name) )
+        },
+        Value::Class(c) => {
+            match name {
+                "__name__" | "__qualname__" => return Ok( Value::str(&c.name) ),
+                "__base__" => return Ok( c.base.clone().map(Value::Class).unwrap_or(Value::None) ),
+                "__bases__" => return Ok( Value::tuple(c.base.iter().map( |b| Value::Class( b.clone() ) ).collect()) ),
+                "__mro__" => return Ok( Value::tuple(c.mro().into_iter().map(Value::Class).collect()) ),
+                _ => ()
+            }
+            match c.lookup(name) {
+                Some( Value::ClassMethod(f) ) => Ok( Value::BoundMethod( Rc::new( ( value.clone(), ( *f ).clone() ) ) ) ),
+                Some( Value::StaticMethod(f) ) => Ok( ( *f ).clone() ),
+                Some( v ) => Ok( v ),
+                None => {
+                    for class in c.mro() {
+                        if let Some( ( n, f ) ) = method(class.kind, name) {
+                            return Ok( Value::builtin(n, f) )
+                        }
+                    }
+                    if name == "__class__" {
+                        return Ok( Value::Class( class_of(rt, value) ) )
+                    }
+                    Err( attribute_error(rt, value, name) )
+                }
+            }
+        },
+        Value::Super(s) => {
+            let ( class, receiver ) = ( &s.0, &s.1 );
+            let start = class.base.clone();
+            let owner = match receiver {
+                Value::Class(c) => c.clone(),
+                _ => class_of(rt, receiver)
+            };
+            if let Some( base ) = start {
+                for c in base.mro() {
+                    let found = c.dict.borrow().get(name).cloned();
+                    if let Some( attr ) = found {
+                        return match ( receiver, attr ) {
+                            ( Value::Class(_), attr @ Value::Function(_) ) => Ok( attr ),
+                            ( _, attr ) => bind(rt, attr, receiver, &owner)
+                        }
+                    }
+                    if let Some( ( n, f ) ) = method(c.kind, name) {
+                        return Ok( Value::BoundMethod( Rc::new( ( receiver.clone(), Value::builtin(n, f) ) ) ) )
+                    }
+                }
+            }
+            throw(rt, "AttributeError", format!("'super' object has no attribute '{}'", name))
+        },
+        Value::Function(f) => {
+            match name {
+                "__name__" => Ok( Value::str(&f.name) ),
+                "__qualname__" => Ok( Value::str(&f.qualname) ),
+                _ => {
+                    let found = f.dict.borrow().get(name).cloned();
+                    match found {
+                        Some( v ) => Ok( v ),
+                        None if name == "__doc__" || name == "__module__" => Ok( Value::None ),
+                        None => builtin_attribute(rt, value, TypeKind::Function, name).ok_or_else( || attribute_error(rt, value, name) )
+                    }
+                }
+            }
+        },
+        Value::Builtin(b) if name == "__name__" || name == "__qualname__" => Ok( Value::str(b.name) ),
+        Value::BoundMethod(m) => match name {
+            "__self__" => Ok( m.0.clone() ),
+            "__func__" => Ok( m.1.clone() ),
+            _ => get_attr(rt, &m.1, name)
+        },
+        Value::StaticMethod(f) | Value::ClassMethod(f) if name == "__func__" => Ok( ( **f ).clone() ),
+        Value::Property(p) if name == "fget" || name == "fset" => Ok( if name == "fget" { p.0.clone() } else { p.1.clone() } ),
+        Value::Generator(g) if name == "__name__" => Ok( Value::str(&g.borrow().name) ),
+        Value::Slice(s) if matches!(name, "start" | "stop" | "step") => Ok( s[match name { "start" => 0, "stop" => 1, _ => 2 }].clone() ),
+        Value::Range(r) if matches!(name, "start" | "stop" | "step") => Ok( Value::int(match name { "start" => r.start, "stop" => r.stop, _ => r.step }) ),
+        _ => {
+            let kind = class_of(rt, value).kind;
+            builtin_attribute(rt, value, kind, name).ok_or_else( || attribute_error(rt, value, name) )
+        }
+    }
+}
+
+pub fn set_attr<'a>(rt: &mut dyn Runtime<'a>, target: &Value<'a>, name: &str, value: Value<'a>) -> Result<(), Value<'a>> {
+    match target {
+        Value::Instance(i) => {
+            if let Some( Value::Property(p) ) = i.class.lookup(name) {
+                if p.1.is_none() {
+                    return throw(rt, "AttributeError", "can't set attribute")
+                }
+                call(rt, &p.1, vec![ target.clone(), value ], Vec::new())?;
+                return Ok(())
+            }
+            i.dict.borrow_mut().insert(name.to_string(), value);
+            Ok(())
+        },
+        Value::Class(c) if c.dict.borrow().contains_key("__module__") => {
+            c.dict.borrow_mut().insert(name.to_string(), value);
+            Ok(())
+        },
+        Value::Class(c) => throw(rt, "TypeError", format!("cannot set '{}' attribute of immutable type '{}'", name, c.name)),
+        Value::Function(f) => {
+            f.dict.borrow_mut().insert(name.to_string(), value);
+            Ok(())
+        },
+        _ => throw(rt, "AttributeError", format!("'{}' object has no attribute '{}'", type_name(rt, target), name))
+    }
+}
+
+pub fn del_attr<'a>(rt: &mut dyn Runtime<'a>, target: &Value<'a>, name: &str) -> Result<(), Value<'a>> {
+    let removed = match target {
+        Value::Instance(i) => i.dict.borrow_mut().remove(name),
+        Value::Class(c) => c.dict.borrow_mut().remove(name),
+        Value::Function(f) => f.dict.borrow_mut().remove(name),
+        _ => None
+    };
+    match removed {
+        Some( _ ) => Ok(()),
+        None => Err( attribute_error(rt, target, name) )
+    }
+}
+
+/// Iterator over iterable value.
+pub fn iter<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>) -> RunResult<'a> {
+    Ok( match value {
+        Value::List(l) => Value::iterator( Iter::List(l.clone(), 0) ),
+        Value::Tuple(t) => Value::iterator( Iter::Values(t.clone(), 0) ),
+        Value::Str(s) => Value::iterator( Iter::Str(s.clone(), 0) ),
+        Value::Bytes(b) => Value::iterator( Iter::Values(b.iter().map( |c| Value::int(*c as i64) ).collect(), 0) ),
+        Value::Range(r) => Value::iterator( Iter::Range(0, **r) ),
+        Value::Dict(d) | Value::Set(d) => {
+            let len = d.borrow().len();
+            Value::iterator( Iter::Dict(d.clone(), ViewKind::Keys, 0, len) )
+        },
+        Value::DictView(d, kind) => {
+            let len = d.borrow().len();
+            Value::iterator( Iter::Dict(d.clone(), *kind, 0, len) )
+        },
+        Value::Iterator(_) | Value::Generator(_) => value.clone(),
+        _ => match call_special(rt, value, "__iter__", Vec::new())? {
+            Some( it ) => it,
+            None => return throw(rt, "TypeError", format!("'{}' object is not iterable", type_name(rt, value)))
+        }
+    } )
+}
+
+/// Next value of iterator, None when it is exhausted.
+pub fn next<'a>(rt: &mut dyn Runtime<'a>, iterator: &Value<'a>) -> Result<Option<Value<'a>>, Value<'a>> {
+    match iterator {
+        Value::Iterator(it) => iter_next(rt, it),
+        Value::Generator(g) => match rt.resume(g, Resume::Send(Value::None))? {
+            Step::Yield(v) => Ok( Some( v ) ),
+            Step::Return(_) => Ok( None )
+        },
+        Value::Instance(_) => match call_special(rt, iterator, "__next__", Vec::new()) {
+            Ok( Some( v ) ) => Ok( Some( v ) ),
+            Ok( None ) => throw(rt, "TypeError", format!("'{}' object is not an iterator", type_name(rt, iterator))),
+            Err( e ) if is_exception(rt, &e, "StopIteration") => Ok( None ),
+            Err( e ) => Err( e )
+        },
+        _ => throw(rt, "TypeError", format!("'{}' object is not an iterator", type_name(rt, iterator)))
+    }
+}
+
+fn iter_next<'a>(rt: &mut dyn Runtime<'a>, it: &Rc<RefCell<Iter<'a>>>) -> Result<Option<Value<'a>>, Value<'a>> {
+    let composite = match &*it.borrow() {
+        Iter::Enumerate(inner, n) => Some( Iter::Enumerate(inner.clone(), n.clone()) ),
+        Iter::Zip(iters) => Some( Iter::Zip(iters.clone()) ),
+        Iter::Map(func, iters) => Some( Iter::Map(func.clone(), iters.clone()) ),
+        Iter::Filter(func, inner) => Some( Iter::Filter(func.clone(), inner.clone()) ),
+        _ => None
+    };
+    if let Some( current ) = composite {
+        return composite_next(rt, it, current)
+    }
+    let mut state = it.borrow_mut();
+    let mut changed = false;
+    let res = match &mut *state {
+        Iter::List(l, i) => {
+            let v = l.borrow().get(*i).cloned();
+            *i += 1;
+            v
+        },
+        Iter::Values(t, i) => {
+            let v = t.get(*i).cloned();
+            *i += 1;
+            v
+        },
+        Iter::Str(s, i) => {
+            let c = s[*i ..].chars().next();
+            if let Some( c ) = c {
+                *i += c.len_utf8()
+            }
+            c.map( |c| Value::str(&c.to_string()) )
+        },
+        Iter::Range(i, r) => {
+            if *i < r.len() {
+                *i += 1;
+                Some( Value::int(r.get(*i - 1)) )
+            } else {
+                None
+            }
+        },
+        Iter::Dict(d, kind, slot, len) => {
+            let d = d.borrow();
+            let mut res = None;
+            if d.len() != *len {
+                changed = true
+            } else {
+                while *slot < d.slots() && res.is_none() {
+                    res = d.slot(*slot).map( |( k, v )| match kind {
+                        ViewKind::Keys => k.clone(),
+                        ViewKind::Values => v.clone(),
+                        ViewKind::Items => Value::tuple(vec![ k.clone(), v.clone() ])
+                    } );
+                    *slot += 1
+                }
+            }
+            res
+        },
+        _ => None
+    };
+    if res.is_none() {
+        *state = Iter::Exhausted
+    }
+    drop(state);
+    if changed {
+        return throw(rt, "RuntimeError", "dictionary changed size during iteration")
+    }
+    Ok( res )
+}
+
+/// Next value of iterators built from other iterators. These run code that may use the iterator
+/// again, so its state is not kept borrowed meanwhile.
+fn composite_next<'a>(rt: &mut dyn Runtime<'a>, it: &Rc<RefCell<Iter<'a>>>, current: Iter<'a>) -> Result<Option<Value<'a>>, Value<'a>> {
+    let res = match &current {
+        Iter::Enumerate(inner, n) => next(rt, inner)?.map( |v| Value::tuple(vec![ Value::Int(n.clone()), v ]) ),
+        Iter::Zip(iters) | Iter::Map(_, iters) => {
+            let mut values = Vec::with_capacity(iters.len());
+            for i in iters {
+                match next(rt, i)? {
+                    Some( v ) => values.push(v),
+                    None => break
+                }
+            }
+            match &current {
+                _ if values.len() < iters.len() || iters.is_empty() => None,
+                Iter::Map(func, _) => Some( call(rt, func, values, Vec::new())? ),
+                _ => Some( Value::tuple(values) )
+            }
+        },
+        Iter::Filter(func, inner) => {
+            let mut res = None;
+            while let Some( v ) = next(rt, inner)? {
+                let keep = if func.is_none() { v.clone() } else { call(rt, func, vec![ v.clone() ], Vec::new())? };
+                if truthy(rt, &keep)? {
+                    res = Some( v );
+                    break
+                }
+            }
+            res
+        },
+        _ => None
+    };
+    let mut state = it.borrow_mut();
+    match ( &mut *state, &res ) {
+        ( _, None ) => *state = Iter::Exhausted,
+        ( Iter::Enumerate(_, n), Some( _ ) ) => *n = n.add(&Int::from(1)),
+        _ => ()
+    }
+    Ok( res )
+}
+
+/// All values of iterable.
+pub fn collect<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>) -> Result<Vec<Value<'a>>, Value<'a>> {
+    match value {
+        Value::List(l) => Ok( l.borrow().clone() ),
+        Value::Tuple(t) => Ok( t.to_vec() ),
+        _ => {
+            let it = iter(rt, value)?;
+            let mut res = Vec::new();
+            while let Some( v ) = next(rt, &it)? {
+                res.push(v)
+            }
+            Ok( res )
+        }
+    }
+}
+
+pub fn len<'a>(rt: &mut dyn Runtime<'a>, value: &Value<'a>) -> Result<usize, Value<'a>> {
+    Ok( match value {
+        Value::Str(s) => s.chars().count(),
+        Value::Bytes(b) => b.len(),
+        Value::List(l) => l.borrow().len(),
+        Value::Tuple(t) => t.len(),
+        Value::Dict(d) | Value::Set(d) | Value::DictView(d, _) => d.borrow().len(),
+        Value::Range(r) => r.len() as usize,
+        _ => match call_special(rt, value, "__len__", Vec::new())? {
+            Some( n ) => {
+                let n = to_index(rt, &n)?;
+                if n < 0 {
+                    return throw(rt, "ValueError", "__len__() should return >= 0")
+                }
+                n as usize
+            },
+            None => return throw(rt, "TypeError", format!("object of type '{}' has no len()", type_name(rt, value)))
+        }
+    } )
+}
+
+/// Adds pairs of mapping or iterable of pairs to dictionary, as 'dict.update' does.
+pub fn dict_update<'a>(rt: &mut dyn Runtime<'a>, dict: &Rc<RefCell<Dict<'a>>>, source: &Value<'a>) -> Result<(), Value<'a>> {
+    let pairs = match source {
+        Value::Dict(d) => d.borrow().items(),
+        _ => {
+            let mut pairs = Vec::new();
+            for ( i, item ) in collect(rt, source)?.into_iter().enumerate() {
+                let kv = collect(rt, &item)?;
+                if kv.len() != 2 {
+                    return throw(rt, "ValueError", format!("dictionary update sequence element #{} has length {}; 2 is required", i, kv.len()))
+                }
+                let mut kv = kv.into_iter();
+                pairs.push(( kv.next().unwrap(), kv.next().unwrap() ))
+            }
+            pairs
+        }
+    };
+    for ( k, v ) in pairs {
+        let h = hash_key(rt, &k)?;
+        dict.borrow_mut().insert(h, k, v)
+    }
+    Ok(())
+}
+
+/// Set of the values of iterable.
+pub fn set_from<'a>(rt: &mut dyn Runtime<'a>, values: Vec<Value<'a>>) -> Result<Dict<'a>, Value<'a>> {
+    let mut res = Dict::new();
+    for v in values {
+        let h = hash_key(rt, &v)?;
+        res.insert(h, v, Value::None)
+    }
+    Ok( res )
+}
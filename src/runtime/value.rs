@@ -0,0 +1,521 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{ Hash, Hasher };
+use std::rc::Rc;
+use crate::ast::nodes::{ Stmt, Expr };
+use crate::semantic::symbols::ScopeId;
+use crate::runtime::bigint::Int;
+use crate::runtime::interpreter::Suspended;
+use crate::bytecode::code::CodeObject;
+use crate::bytecode::vm;
+
+
+/// Result of evaluation, the error is the raised exception instance.
+pub type RunResult<'a> = Result<Value<'a>, Value<'a>>;
+
+/// Storage of a variable shared between a function and the closures that use it, empty until bound.
+pub type Cell<'a> = Rc<RefCell<Option<Value<'a>>>>;
+
+/// Global namespace of a module, shared by every function defined in it.
+pub type Globals<'a> = Rc<RefCell<HashMap<String, Value<'a>>>>;
+
+/// Native function, called with positional and keyword arguments. Methods get their receiver as
+/// first positional argument.
+pub type BuiltinFn<'a> = fn(&mut dyn Runtime<'a>, Vec<Value<'a>>, Vec<( String, Value<'a> )>) -> RunResult<'a>;
+
+/// Python object. Immutable values are kept inline, mutable ones are shared by reference counting
+/// like CPython objects, so that aliasing behaves the same. Lifetime is the one of the abstract tree
+/// that function bodies point into.
+#[derive(Clone)]
+pub enum Value<'a> {
+    None,
+    Ellipsis,
+    NotImplemented,
+    Bool(bool),
+    Int(Int),
+    Float(f64),
+    Str(Rc<str>),
+    Bytes(Rc<[u8]>),
+    List(Rc<RefCell<Vec<Value<'a>>>>),
+    Tuple(Rc<[Value<'a>]>),
+    Dict(Rc<RefCell<Dict<'a>>>),
+    Set(Rc<RefCell<Dict<'a>>>),
+    DictView(Rc<RefCell<Dict<'a>>>, ViewKind),
+    Range(Rc<Range>),
+    Slice(Rc<[Value<'a>; 3]>),
+    Function(Rc<Function<'a>>),
+    Builtin(Rc<Builtin<'a>>),
+    BoundMethod(Rc<( Value<'a>, Value<'a> )>),
+    StaticMethod(Rc<Value<'a>>),
+    ClassMethod(Rc<Value<'a>>),
+    Property(Rc<( Value<'a>, Value<'a> )>),
+    Super(Rc<( Rc<Class<'a>>, Value<'a> )>),
+    Class(Rc<Class<'a>>),
+    Instance(Rc<Instance<'a>>),
+    Iterator(Rc<RefCell<Iter<'a>>>),
+    Generator(Rc<RefCell<Generator<'a>>>),
+    Cell(Cell<'a>),
+    Code(Rc<CodeObject>)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViewKind {
+    Keys,
+    Values,
+    Items
+}
+
+/// Native layout of the instances of a class. User classes share the layout of their base, which
+/// limits them to plain objects and exceptions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TypeKind {
+    Object,
+    Type,
+    NoneType,
+    Ellipsis,
+    NotImplemented,
+    Bool,
+    Int,
+    Float,
+    Str,
+    Bytes,
+    List,
+    Tuple,
+    Dict,
+    Set,
+    DictView,
+    Range,
+    Slice,
+    Function,
+    Builtin,
+    Method,
+    StaticMethod,
+    ClassMethod,
+    Property,
+    Super,
+    Iterator,
+    Generator,
+    Cell,
+    Code,
+    Exception
+}
+
+pub struct Class<'a> {
+    pub name: String,
+    pub base: Option<Rc<Class<'a>>>,
+    pub kind: TypeKind,
+    pub dict: RefCell<HashMap<String, Value<'a>>>,
+    /// Constructor of builtin types, user classes create an instance and call '__init__'.
+    pub new: Option<BuiltinFn<'a>>
+}
+
+pub struct Instance<'a> {
+    pub class: Rc<Class<'a>>,
+    pub dict: RefCell<HashMap<String, Value<'a>>>,
+    /// Frames an exception passed through, innermost first.
+    pub traceback: RefCell<Vec<TracebackEntry>>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TracebackEntry {
+    pub line: u32,
+    pub name: String
+}
+
+/// Parameter names of a function in the order of their slots: positional ones, the '*' parameter,
+/// keyword only ones and the '**' parameter.
+#[derive(Clone, Debug, Default)]
+pub struct Signature {
+    pub positional: Vec<String>,
+    pub posonly: usize,
+    pub vararg: Option<String>,
+    pub kwonly: Vec<String>,
+    pub kwarg: Option<String>
+}
+
+/// Code a function runs, statements or expression for the tree walking interpreter and code
+/// objects for the bytecode virtual machine.
+#[derive(Clone)]
+pub enum Code<'a> {
+    Body { scope: ScopeId, body: &'a [Stmt] },
+    Lambda { scope: ScopeId, body: &'a Expr },
+    Bytecode(Rc<CodeObject>)
+}
+
+pub struct Function<'a> {
+    pub name: String,
+    pub qualname: String,
+    pub signature: Signature,
+    /// Defaults of the last positional parameters.
+    pub defaults: Vec<Value<'a>>,
+    pub kw_defaults: HashMap<String, Value<'a>>,
+    pub code: Code<'a>,
+    pub is_generator: bool,
+    pub closure: HashMap<String, Cell<'a>>,
+    pub globals: Globals<'a>,
+    pub dict: RefCell<HashMap<String, Value<'a>>>
+}
+
+pub struct Builtin<'a> {
+    pub name: &'static str,
+    pub func: BuiltinFn<'a>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Range {
+    pub start: i64,
+    pub stop: i64,
+    pub step: i64
+}
+
+/// State of a native iterator.
+pub enum Iter<'a> {
+    List(Rc<RefCell<Vec<Value<'a>>>>, usize),
+    Values(Rc<[Value<'a>]>, usize),
+    Str(Rc<str>, usize),
+    Range(i64, Range),
+    Dict(Rc<RefCell<Dict<'a>>>, ViewKind, usize, usize),
+    Enumerate(Value<'a>, Int),
+    Zip(Vec<Value<'a>>),
+    Map(Value<'a>, Vec<Value<'a>>),
+    Filter(Value<'a>, Value<'a>),
+    Exhausted
+}
+
+pub struct Generator<'a> {
+    pub name: String,
+    /// Frame waiting for next resumption, missing while the generator runs or after it finished.
+    pub frame: Option<Suspension<'a>>,
+    pub running: bool
+}
+
+/// Frame of a suspended generator, kept in the form of the engine that created it.
+pub enum Suspension<'a> {
+    Tree(Box<Suspended<'a>>),
+    Bytecode(Box<vm::Frame<'a>>)
+}
+
+/// Value given to a suspended generator.
+pub enum Resume<'a> {
+    Send(Value<'a>),
+    Throw(Value<'a>)
+}
+
+/// Outcome of resuming a generator.
+pub enum Step<'a> {
+    Yield(Value<'a>),
+    Return(Value<'a>)
+}
+
+/// Key of dictionaries and sets. Values that compare equal have equal keys, so integral floats and
+/// booleans are keyed as integers. Objects without their own equality are keyed by identity and
+/// objects defining '__hash__' by the hash they return.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HashKey {
+    None,
+    Ellipsis,
+    Int(Int),
+    Float(u64),
+    Str(Rc<str>),
+    Bytes(Rc<[u8]>),
+    Tuple(Vec<HashKey>),
+    Identity(usize),
+    Custom(i64)
+}
+
+/// Dictionary keeping insertion order, also used for sets with 'None' as values.
+#[derive(Clone, Default)]
+pub struct Dict<'a> {
+    entries: Vec<Option<( HashKey, Value<'a>, Value<'a> )>>,
+    index: HashMap<HashKey, usize>
+}
+
+/// Services of the engine running code that the object model needs, as calling functions written
+/// in Python, resuming generators and writing output.
+pub trait Runtime<'a> {
+    fn builtins(&self) -> Rc<Builtins<'a>>;
+
+    fn call_function(&mut self, function: &Rc<Function<'a>>, args: Vec<Value<'a>>, kwargs: Vec<( String, Value<'a> )>) -> RunResult<'a>;
+
+    fn resume(&mut self, generator: &Rc<RefCell<Generator<'a>>>, input: Resume<'a>) -> Result<Step<'a>, Value<'a>>;
+
+    fn write(&mut self, text: &str);
+}
+
+/// Builtin types and the namespace searched after module globals.
+pub struct Builtins<'a> {
+    pub types: HashMap<TypeKind, Rc<Class<'a>>>,
+    pub exceptions: HashMap<&'static str, Rc<Class<'a>>>,
+    pub namespace: HashMap<String, Value<'a>>
+}
+
+
+impl<'a> Value<'a> {
+    pub fn str(s: &str) -> Value<'a> {
+        Value::Str( Rc::from(s) )
+    }
+
+    pub fn int(v: i64) -> Value<'a> {
+        Value::Int( Int::Small(v) )
+    }
+
+    pub fn list(values: Vec<Value<'a>>) -> Value<'a> {
+        Value::List( Rc::new( RefCell::new(values) ) )
+    }
+
+    pub fn tuple(values: Vec<Value<'a>>) -> Value<'a> {
+        Value::Tuple( Rc::from(values) )
+    }
+
+    pub fn dict(dict: Dict<'a>) -> Value<'a> {
+        Value::Dict( Rc::new( RefCell::new(dict) ) )
+    }
+
+    pub fn set(dict: Dict<'a>) -> Value<'a> {
+        Value::Set( Rc::new( RefCell::new(dict) ) )
+    }
+
+    pub fn builtin(name: &'static str, func: BuiltinFn<'a>) -> Value<'a> {
+        Value::Builtin( Rc::new( Builtin { name, func } ) )
+    }
+
+    pub fn iterator(iter: Iter<'a>) -> Value<'a> {
+        Value::Iterator( Rc::new( RefCell::new(iter) ) )
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, Value::None)
+    }
+
+    /// Address of the shared object, or None for values kept inline.
+    pub fn identity(&self) -> Option<usize> {
+        Some( match self {
+            Value::Str(s) => s.as_ptr() as usize,
+            Value::Bytes(b) => b.as_ptr() as usize,
+            Value::List(l) => Rc::as_ptr(l) as *const u8 as usize,
+            Value::Tuple(t) => t.as_ptr() as usize,
+            Value::Dict(d) | Value::Set(d) | Value::DictView(d, _) => Rc::as_ptr(d) as *const u8 as usize,
+            Value::Range(r) => Rc::as_ptr(r) as usize,
+            Value::Slice(s) => Rc::as_ptr(s) as *const u8 as usize,
+            Value::Function(f) => Rc::as_ptr(f) as *const u8 as usize,
+            Value::Builtin(b) => Rc::as_ptr(b) as *const u8 as usize,
+            Value::BoundMethod(m) | Value::Property(m) => Rc::as_ptr(m) as *const u8 as usize,
+            Value::StaticMethod(m) | Value::ClassMethod(m) => Rc::as_ptr(m) as *const u8 as usize,
+            Value::Super(s) => Rc::as_ptr(s) as *const u8 as usize,
+            Value::Class(c) => Rc::as_ptr(c) as *const u8 as usize,
+            Value::Instance(i) => Rc::as_ptr(i) as *const u8 as usize,
+            Value::Iterator(i) => Rc::as_ptr(i) as *const u8 as usize,
+            Value::Generator(g) => Rc::as_ptr(g) as *const u8 as usize,
+            Value::Cell(c) => Rc::as_ptr(c) as *const u8 as usize,
+            Value::Code(c) => Rc::as_ptr(c) as *const u8 as usize,
+            _ => return None
+        } )
+    }
+}
+
+impl fmt::Debug for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::None => write!(f, "None"),
+            Value::Bool(b) => write!(f, "{}", if *b { "True" } else { "False" }),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{:?}", x),
+            Value::Str(s) => write!(f, "{:?}", s),
+            Value::List(l) => write!(f, "{:?}", l.borrow()),
+            Value::Tuple(t) => write!(f, "{:?}", t),
+            Value::Class(c) => write!(f, "<class '{}'>", c.name),
+            Value::Instance(i) => write!(f, "<{} object>", i.class.name),
+            _ => write!(f, "<object at {:#x}>", self.identity().unwrap_or(0))
+        }
+    }
+}
+
+impl<'a> Class<'a> {
+    pub fn new(name: &str, base: Option<Rc<Class<'a>>>, kind: TypeKind, new: Option<BuiltinFn<'a>>) -> Class<'a> {
+        Class { name: name.to_string(), base, kind, dict: RefCell::new( HashMap::new() ), new }
+    }
+
+    /// Attribute of class or the first base defining it.
+    pub fn lookup(&self, name: &str) -> Option<Value<'a>> {
+        if let Some( v ) = self.dict.borrow().get(name) {
+            return Some( v.clone() )
+        }
+        self.base.as_ref().and_then( |b| b.lookup(name) )
+    }
+
+    pub fn is_subclass(self: &Rc<Self>, other: &Rc<Class<'a>>) -> bool {
+        let mut current = Some( self.clone() );
+        while let Some( c ) = current {
+            if Rc::ptr_eq(&c, other) {
+                return true
+            }
+            current = c.base.clone()
+        }
+        false
+    }
+
+    /// Classes from this one up to 'object'.
+    pub fn mro(self: &Rc<Self>) -> Vec<Rc<Class<'a>>> {
+        let mut res = Vec::new();
+        let mut current = Some( self.clone() );
+        while let Some( c ) = current {
+            current = c.base.clone();
+            res.push(c)
+        }
+        res
+    }
+}
+
+impl<'a> Instance<'a> {
+    pub fn new(class: Rc<Class<'a>>) -> Instance<'a> {
+        Instance { class, dict: RefCell::new( HashMap::new() ), traceback: RefCell::new( Vec::new() ) }
+    }
+}
+
+impl Signature {
+    /// Number of local slots filled by binding arguments.
+    pub fn slots(&self) -> usize {
+        self.positional.len() + self.kwonly.len() + self.vararg.iter().count() + self.kwarg.iter().count()
+    }
+
+    /// Parameter names in slot order.
+    pub fn names(&self) -> Vec<&str> {
+        self.positional.iter().chain(self.vararg.iter()).chain(self.kwonly.iter()).chain(self.kwarg.iter()).map( |s| s.as_str() ).collect()
+    }
+}
+
+impl Range {
+    pub fn len(&self) -> i64 {
+        let ( lo, hi, step ) = if self.step > 0 { ( self.start, self.stop, self.step ) } else { ( self.stop, self.start, -self.step ) };
+        if lo >= hi {
+            0
+        } else {
+            ( ( hi as i128 - lo as i128 - 1 ) / step as i128 + 1 ) as i64
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: i64) -> i64 {
+        self.start + index * self.step
+    }
+}
+
+impl<'a> Dict<'a> {
+    pub fn new() -> Dict<'a> {
+        Dict { entries: Vec::new(), index: HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn get(&self, key: &HashKey) -> Option<&Value<'a>> {
+        self.index.get(key).and_then( |i| self.entries[*i].as_ref() ).map( |e| &e.2 )
+    }
+
+    pub fn contains(&self, key: &HashKey) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// Sets value of key, an existing entry keeps its position and its original key object.
+    pub fn insert(&mut self, hash: HashKey, key: Value<'a>, value: Value<'a>) {
+        match self.index.get(&hash) {
+            Some( i ) => {
+                if let Some( e ) = self.entries[*i].as_mut() {
+                    e.2 = value
+                }
+            },
+            None => {
+                self.index.insert(hash.clone(), self.entries.len());
+                self.entries.push( Some( ( hash, key, value ) ) )
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &HashKey) -> Option<( Value<'a>, Value<'a> )> {
+        let i = self.index.remove(key)?;
+        let entry = self.entries[i].take().map( |e| ( e.1, e.2 ) );
+        if self.entries.len() > 16 && self.index.len() * 2 < self.entries.len() {
+            self.entries.retain( |e| e.is_some() );
+            for ( i, e ) in self.entries.iter().enumerate() {
+                if let Some( ( h, _, _ ) ) = e {
+                    self.index.insert(h.clone(), i);
+                }
+            }
+        }
+        entry
+    }
+
+    pub fn pop_last(&mut self) -> Option<( Value<'a>, Value<'a> )> {
+        while let Some( e ) = self.entries.pop() {
+            if let Some( ( h, k, v ) ) = e {
+                self.index.remove(&h);
+                return Some( ( k, v ) )
+            }
+        }
+        None
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear()
+    }
+
+    /// Number of slots, iterators walk slots by position so that they notice changes.
+    pub fn slots(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn slot(&self, i: usize) -> Option<( &Value<'a>, &Value<'a> )> {
+        self.entries.get(i).and_then( |e| e.as_ref() ).map( |e| ( &e.1, &e.2 ) )
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ( &HashKey, &Value<'a>, &Value<'a> )> {
+        self.entries.iter().flatten().map( |e| ( &e.0, &e.1, &e.2 ) )
+    }
+
+    pub fn keys(&self) -> Vec<Value<'a>> {
+        self.iter().map( |e| e.1.clone() ).collect()
+    }
+
+    pub fn values(&self) -> Vec<Value<'a>> {
+        self.iter().map( |e| e.2.clone() ).collect()
+    }
+
+    pub fn items(&self) -> Vec<( Value<'a>, Value<'a> )> {
+        self.iter().map( |e| ( e.1.clone(), e.2.clone() ) ).collect()
+    }
+}
+
+impl<'a> Builtins<'a> {
+    pub fn class(&self, kind: TypeKind) -> Rc<Class<'a>> {
+        self.types[&kind].clone()
+    }
+
+    pub fn exception(&self, name: &str) -> Rc<Class<'a>> {
+        self.exceptions[name].clone()
+    }
+}
+
+impl HashKey {
+    /// Hash of the key as returned by builtin 'hash'.
+    pub fn hash_value(&self) -> i64 {
+        match self {
+            HashKey::Int(Int::Small(v)) => if *v == -1 { -2 } else { *v },
+            HashKey::Custom(v) => *v,
+            _ => {
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                self.hash(&mut hasher);
+                ( hasher.finish() >> 1 ) as i64
+            }
+        }
+    }
+}
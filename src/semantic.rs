@@ -0,0 +1,4 @@
+pub mod symbols;
+pub mod validation;
+pub mod cfg;
+pub mod dataflow;
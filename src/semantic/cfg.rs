@@ -0,0 +1,729 @@
+use crate::ast::nodes::{ Mod, Stmt, StmtKind, Expr, ExprKind, Constant, ExceptHandler, MatchCase };
+
+
+pub type BlockId = usize;
+
+/// Reason control passes along an edge. Exception edges leave their block before its statement
+/// completed, all other edges leave after the last statement of the block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    Normal,
+    True,
+    False,
+    Loop,
+    Break,
+    Continue,
+    Return,
+    Exception
+}
+
+/// Element of a basic block. Compound statements appear by their header only, that is the test of
+/// 'if' and 'while', the iterable of 'for', the items of 'with' and the subject of 'match', their
+/// bodies are blocks of their own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CfgNode<'a> {
+    Stmt(&'a Stmt),
+    /// Binding of the target of a 'for' loop, done at start of every iteration.
+    LoopTarget(&'a Expr),
+    /// Matching the type of an 'except' clause and binding its name.
+    Handler(&'a ExceptHandler),
+    /// Matching the pattern of a 'case' clause, binding its names and testing its guard.
+    Case(&'a MatchCase)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BasicBlock<'a> {
+    pub id: BlockId,
+    pub nodes: Vec<CfgNode<'a>>,
+    pub successors: Vec<( BlockId, EdgeKind )>,
+    pub predecessors: Vec<( BlockId, EdgeKind )>
+}
+
+/// Control flow graph of a function or module body. Block 'entry' and block 'exit' are empty, every
+/// 'return' and every exception leaving the body ends in 'exit'. Statements inside 'try' are placed
+/// in blocks of their own, each with an exception edge to the handlers, 'finally' or the enclosing
+/// 'try'. Bodies of 'with' using 'suppress(...)' are handled the same way, as exceptions are
+/// suppressed when leaving 'with', other context managers are taken to let exceptions pass.
+/// Elsewhere only 'raise' has an exception edge. Statements of 'finally' appear once for
+/// every way of leaving 'try', that is falling through, exceptions and each jump passing through.
+/// Cases of 'match' are tested in order like 'elif', without an irrefutable case the last one
+/// falls through to the statement after 'match'.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ControlFlowGraph<'a> {
+    pub name: String,
+    pub span: ( u32, u32 ),
+    pub blocks: Vec<BasicBlock<'a>>,
+    pub entry: BlockId,
+    pub exit: BlockId
+}
+
+struct Loop {
+    header: BlockId,
+    after: BlockId,
+    finally_depth: usize
+}
+
+/// Body of 'finally' is built once for every way of reaching it, as CPython compiles it, so that
+/// each copy continues only where its entry came from. Pending jumps are 'return', 'break' and
+/// 'continue' passing through, each with entry of its copy, target and kind.
+struct Finally {
+    normal: BlockId,
+    exception: BlockId,
+    pending: Vec<( BlockId, BlockId, EdgeKind )>
+}
+
+struct Builder<'a> {
+    exit: BlockId,
+    blocks: Vec<BasicBlock<'a>>,
+    current: Option<BlockId>,
+    loops: Vec<Loop>,
+    handlers: Vec<BlockId>,
+    finallies: Vec<Finally>
+}
+
+
+/// Graphs of module body and of every function in it, nested ones and methods included. Functions
+/// are named by their qualified name like 'A.method' or 'outer.<locals>.inner'.
+pub fn build_graphs(module: &Mod) -> Vec<ControlFlowGraph<'_>> {
+    let mut res = Vec::new();
+    match module {
+        Mod::Module { body, .. } |
+        Mod::Interactive { body } => {
+            let end = body.last().map( |s| s.location.end ).unwrap_or(0);
+            res.push( ControlFlowGraph::build("<module>", ( 0, end ), body) );
+            collect_functions(body, "", &mut res)
+        },
+        _ => { }
+    }
+    res
+}
+
+fn collect_functions<'a>(body: &'a [Stmt], prefix: &str, res: &mut Vec<ControlFlowGraph<'a>>) {
+    for stmt in body {
+        match &stmt.node {
+            StmtKind::FunctionDef { name, body, .. } |
+            StmtKind::AsyncFunctionDef { name, body, .. } => {
+                let qualified = format!("{}{}", prefix, name);
+                res.push( ControlFlowGraph::build(&qualified, ( stmt.location.start, stmt.location.end ), body) );
+                collect_functions(body, &format!("{}.<locals>.", qualified), res)
+            },
+            StmtKind::ClassDef { name, body, .. } => collect_functions(body, &format!("{}{}.", prefix, name), res),
+            StmtKind::For { body, orelse, .. } |
+            StmtKind::AsyncFor { body, orelse, .. } |
+            StmtKind::While { body, orelse, .. } |
+            StmtKind::If { body, orelse, .. } => {
+                collect_functions(body, prefix, res);
+                collect_functions(orelse, prefix, res)
+            },
+            StmtKind::With { body, .. } |
+            StmtKind::AsyncWith { body, .. } => collect_functions(body, prefix, res),
+            StmtKind::Match { cases, .. } => {
+                for c in cases {
+                    collect_functions(&c.body, prefix, res)
+                }
+            },
+            StmtKind::Try { body, handlers, orelse, finalbody } |
+            StmtKind::TryStar { body, handlers, orelse, finalbody } => {
+                collect_functions(body, prefix, res);
+                for h in handlers {
+                    collect_functions(&h.body, prefix, res)
+                }
+                collect_functions(orelse, prefix, res);
+                collect_functions(finalbody, prefix, res)
+            },
+            _ => { }
+        }
+    }
+}
+
+/// Tests that are always true, like 'while True:' and 'while 1:', have no edge for leaving the loop.
+fn is_always_true(test: &Expr) -> bool {
+    match &test.node {
+        ExprKind::Constant { value: Constant::Bool(b), .. } => *b,
+        ExprKind::Constant { value: Constant::Int(i), .. } => i.chars().any( |c| c != '0' && c != '_' ),
+        _ => false
+    }
+}
+
+/// Context manager like 'contextlib.suppress(...)', which continues after 'with' on exceptions.
+fn is_suppress(expr: &Expr) -> bool {
+    match &expr.node {
+        ExprKind::Call { func, .. } => match &func.node {
+            ExprKind::Name { id, .. } => id == "suppress",
+            ExprKind::Attribute { attr, .. } => attr == "suppress",
+            _ => false
+        },
+        _ => false
+    }
+}
+
+
+impl<'a> ControlFlowGraph<'a> {
+    /// Builds graph of a body of statements, functions and classes defined in it are single statements.
+    pub fn build(name: &str, span: ( u32, u32 ), body: &'a [Stmt]) -> ControlFlowGraph<'a> {
+        let mut builder = Builder { exit: 1, blocks: Vec::new(), current: None, loops: Vec::new(), handlers: Vec::new(), finallies: Vec::new() };
+        let entry = builder.new_block();
+        let exit = builder.new_block();
+        let first = builder.new_block();
+        builder.edge(entry, first, EdgeKind::Normal);
+        builder.current = Some( first );
+        builder.build_body(body);
+        builder.jump(exit, EdgeKind::Normal);
+        ControlFlowGraph { name: name.to_string(), span, blocks: builder.blocks, entry, exit }
+    }
+
+    pub fn block(&self, id: BlockId) -> &BasicBlock<'a> {
+        &self.blocks[id]
+    }
+
+    /// Flags of blocks reachable from entry, by block id.
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut seen = vec![ false; self.blocks.len() ];
+        let mut stack = vec![ self.entry ];
+        while let Some( id ) = stack.pop() {
+            if !seen[id] {
+                seen[id] = true;
+                stack.extend( self.blocks[id].successors.iter().map( |( s, _ )| *s ) )
+            }
+        }
+        seen
+    }
+
+    /// Graph in the DOT language of Graphviz. Blocks show line number and first line of source of
+    /// each of their statements.
+    pub fn to_dot(&self, source: &str) -> String {
+        let chars : Vec<char> = source.chars().collect();
+        let mut res = format!("digraph \"{}\" {{\n    node [shape=box, fontname=\"monospace\"];\n", escape(&self.name));
+        for block in &self.blocks {
+            let mut label = match block.id {
+                id if id == self.entry => "entry".to_string(),
+                id if id == self.exit => "exit".to_string(),
+                id => format!("b{}", id)
+            };
+            if !block.nodes.is_empty() {
+                label.push_str("\\n");
+            }
+            for node in &block.nodes {
+                let line = match node {
+                    CfgNode::Stmt( s ) => format!("{}: {}", s.location.lineno, first_line(&chars, s.location.start, s.location.end)),
+                    CfgNode::LoopTarget( t ) => format!("{}: target {}", t.location.lineno, first_line(&chars, t.location.start, t.location.end)),
+                    CfgNode::Handler( h ) => format!("{}: {}", h.location.lineno, first_line(&chars, h.location.start, h.location.end)),
+                    CfgNode::Case( c ) => {
+                        let p = &c.pattern.location;
+                        format!("{}: case {}", p.lineno, first_line(&chars, p.start, p.end))
+                    }
+                };
+                label.push_str(&escape(&line));
+                label.push_str("\\l")
+            }
+            res.push_str(&format!("    b{} [label=\"{}\"];\n", block.id, label))
+        }
+        for block in &self.blocks {
+            for ( target, kind ) in &block.successors {
+                let attributes = match kind {
+                    EdgeKind::Normal => "",
+                    EdgeKind::True => " [label=\"true\"]",
+                    EdgeKind::False => " [label=\"false\"]",
+                    EdgeKind::Loop => " [label=\"loop\"]",
+                    EdgeKind::Break => " [label=\"break\"]",
+                    EdgeKind::Continue => " [label=\"continue\"]",
+                    EdgeKind::Return => " [label=\"return\"]",
+                    EdgeKind::Exception => " [label=\"exception\", style=dashed]"
+                };
+                res.push_str(&format!("    b{} -> b{}{};\n", block.id, target, attributes))
+            }
+        }
+        res.push_str("}\n");
+        res
+    }
+}
+
+fn first_line(chars: &[char], start: u32, end: u32) -> String {
+    let end = ( end as usize ).min(chars.len());
+    let start = ( start as usize ).min(end);
+    chars[start .. end].iter().take_while( |c| **c != '\n' ).collect::<String>().trim_end().to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+
+impl<'a> Builder<'a> {
+    fn new_block(&mut self) -> BlockId {
+        let id = self.blocks.len();
+        self.blocks.push( BasicBlock { id, nodes: Vec::new(), successors: Vec::new(), predecessors: Vec::new() } );
+        id
+    }
+
+    fn edge(&mut self, from: BlockId, to: BlockId, kind: EdgeKind) {
+        if !self.blocks[from].successors.contains(&( to, kind )) {
+            self.blocks[from].successors.push( ( to, kind ) );
+            self.blocks[to].predecessors.push( ( from, kind ) )
+        }
+    }
+
+    /// Edge from current block, nothing when current code is unreachable.
+    fn jump(&mut self, to: BlockId, kind: EdgeKind) {
+        if let Some( from ) = self.current {
+            self.edge(from, to, kind)
+        }
+    }
+
+    /// Continues in a new block following current one.
+    fn start_block(&mut self) -> BlockId {
+        let id = self.new_block();
+        self.jump(id, EdgeKind::Normal);
+        self.current = Some( id );
+        id
+    }
+
+    /// Continues in current block when it is still empty, otherwise in a new one.
+    fn empty_block(&mut self) -> BlockId {
+        match self.current {
+            Some( id ) if self.blocks[id].nodes.is_empty() => id,
+            _ => self.start_block()
+        }
+    }
+
+    /// Handlers or 'finally' of innermost 'try' or exit of innermost suppressing 'with', or exit of graph.
+    fn exception_target(&self) -> BlockId {
+        self.handlers.last().copied().unwrap_or(self.exit)
+    }
+
+    /// Appends node to current block and returns block. Inside 'try' every node gets a block of its
+    /// own with an edge to the handlers. Code following a jump starts a block without predecessors.
+    fn add_node(&mut self, node: CfgNode<'a>) -> BlockId {
+        let id = match self.current {
+            Some( id ) if self.blocks[id].nodes.is_empty() || self.handlers.is_empty() => id,
+            _ => self.start_block()
+        };
+        self.current = Some( id );
+        self.blocks[id].nodes.push(node);
+        if !self.handlers.is_empty() {
+            self.edge(id, self.exception_target(), EdgeKind::Exception)
+        }
+        id
+    }
+
+    /// Jump leaving statements between here and target through every 'finally' in between, each
+    /// 'finally' continues to the next one outward and the outermost one to target.
+    fn jump_through_finally(&mut self, target: BlockId, kind: EdgeKind, depth: usize) {
+        let mut to = target;
+        for i in depth .. self.finallies.len() {
+            let existing = self.finallies[i].pending.iter().find( |( _, t, k )| *t == to && *k == kind ).map( |p| p.0 );
+            to = match existing {
+                Some( entry ) => entry,
+                None => {
+                    let entry = self.new_block();
+                    self.finallies[i].pending.push( ( entry, to, kind ) );
+                    entry
+                }
+            }
+        }
+        self.jump(to, kind);
+        self.current = None
+    }
+
+    fn build_body(&mut self, body: &'a [Stmt]) {
+        for stmt in body {
+            self.build_stmt(stmt)
+        }
+    }
+
+    fn build_stmt(&mut self, stmt: &'a Stmt) {
+        match &stmt.node {
+            StmtKind::If { body, orelse, .. } => {
+                let condition = self.add_node(CfgNode::Stmt(stmt));
+                let after = self.new_block();
+                self.build_branch(condition, EdgeKind::True, body, after);
+                match orelse.is_empty() {
+                    true => self.edge(condition, after, EdgeKind::False),
+                    _ => self.build_branch(condition, EdgeKind::False, orelse, after)
+                }
+                self.current = Some( after )
+            },
+            StmtKind::While { test, body, orelse } => {
+                self.empty_block();
+                let header = self.add_node(CfgNode::Stmt(stmt));
+                let after = self.new_block();
+                self.build_loop(header, after, None, body);
+                if !is_always_true(test) {
+                    match orelse.is_empty() {
+                        true => self.edge(header, after, EdgeKind::False),
+                        _ => self.build_branch(header, EdgeKind::False, orelse, after)
+                    }
+                }
+                self.current = Some( after )
+            },
+            StmtKind::For { target, body, orelse, .. } |
+            StmtKind::AsyncFor { target, body, orelse, .. } => {
+                self.add_node(CfgNode::Stmt(stmt));
+                let header = self.start_block();
+                let after = self.new_block();
+                self.build_loop(header, after, Some( target ), body);
+                match orelse.is_empty() {
+                    true => self.edge(header, after, EdgeKind::False),
+                    _ => self.build_branch(header, EdgeKind::False, orelse, after)
+                }
+                self.current = Some( after )
+            },
+            StmtKind::With { items, body, .. } |
+            StmtKind::AsyncWith { items, body, .. } if items.iter().any( |i| is_suppress(&i.context_expr) ) => {
+                self.add_node(CfgNode::Stmt(stmt));
+                let after = self.new_block();
+                let exit = self.new_block();
+                self.handlers.push(exit);
+                self.start_block();
+                self.build_body(body);
+                self.handlers.pop();
+                self.jump(after, EdgeKind::Normal);
+                if !self.blocks[exit].predecessors.is_empty() {
+                    self.edge(exit, after, EdgeKind::Normal);
+                    self.edge(exit, self.exception_target(), EdgeKind::Exception)
+                }
+                self.current = Some( after )
+            },
+            StmtKind::With { body, .. } |
+            StmtKind::AsyncWith { body, .. } => {
+                self.add_node(CfgNode::Stmt(stmt));
+                self.build_body(body)
+            },
+            StmtKind::Match { cases, .. } => {
+                self.add_node(CfgNode::Stmt(stmt));
+                let after = self.new_block();
+                for ( i, case ) in cases.iter().enumerate() {
+                    let test = self.add_node(CfgNode::Case(case));
+                    self.build_branch(test, EdgeKind::True, &case.body, after);
+                    self.current = None;
+                    if case.is_irrefutable() {
+                        break
+                    }
+                    match i + 1 == cases.len() {
+                        true => self.edge(test, after, EdgeKind::False),
+                        _ => {
+                            let next = self.new_block();
+                            self.edge(test, next, EdgeKind::False);
+                            self.current = Some( next )
+                        }
+                    }
+                }
+                self.current = Some( after )
+            },
+            StmtKind::Try { body, handlers, orelse, finalbody } |
+            StmtKind::TryStar { body, handlers, orelse, finalbody } => self.build_try(body, handlers, orelse, finalbody),
+            StmtKind::Return { .. } => {
+                self.add_node(CfgNode::Stmt(stmt));
+                self.jump_through_finally(self.exit, EdgeKind::Return, 0)
+            },
+            StmtKind::Raise { .. } => {
+                self.add_node(CfgNode::Stmt(stmt));
+                self.jump(self.exception_target(), EdgeKind::Exception);
+                self.current = None
+            },
+            StmtKind::Break | StmtKind::Continue => {
+                self.add_node(CfgNode::Stmt(stmt));
+                let target = self.loops.last().map( |l| match stmt.node {
+                    StmtKind::Break => ( l.after, EdgeKind::Break, l.finally_depth ),
+                    _ => ( l.header, EdgeKind::Continue, l.finally_depth )
+                });
+                match target {
+                    Some( ( to, kind, depth ) ) => self.jump_through_finally(to, kind, depth),
+                    None => self.current = None
+                }
+            },
+            _ => {
+                self.add_node(CfgNode::Stmt(stmt));
+            }
+        }
+    }
+
+    /// Body of a branch starting with an edge from block 'from' and continuing at block 'after'.
+    fn build_branch(&mut self, from: BlockId, kind: EdgeKind, body: &'a [Stmt], after: BlockId) {
+        let start = self.new_block();
+        self.edge(from, start, kind);
+        self.current = Some( start );
+        self.build_body(body);
+        self.jump(after, EdgeKind::Normal)
+    }
+
+    fn build_loop(&mut self, header: BlockId, after: BlockId, target: Option<&'a Expr>, body: &'a [Stmt]) {
+        let start = self.new_block();
+        self.edge(header, start, EdgeKind::True);
+        self.current = Some( start );
+        if let Some( t ) = target {
+            self.add_node(CfgNode::LoopTarget(t));
+        }
+        self.loops.push( Loop { header, after, finally_depth: self.finallies.len() } );
+        self.build_body(body);
+        self.loops.pop();
+        self.jump(header, EdgeKind::Loop)
+    }
+
+    fn build_try(&mut self, body: &'a [Stmt], handlers: &'a [ExceptHandler], orelse: &'a [Stmt], finalbody: &'a [Stmt]) {
+        let after = self.new_block();
+        let finally_normal = match finalbody.is_empty() {
+            true => None,
+            _ => {
+                let normal = self.new_block();
+                let exception = self.new_block();
+                self.finallies.push( Finally { normal, exception, pending: Vec::new() } );
+                self.handlers.push(exception);
+                Some( normal )
+            }
+        };
+        let dispatch = match handlers.is_empty() {
+            true => None,
+            _ => {
+                let id = self.new_block();
+                self.handlers.push(id);
+                Some( id )
+            }
+        };
+        let continuation = finally_normal.unwrap_or(after);
+        self.empty_block();
+        self.build_body(body);
+        if dispatch.is_some() {
+            self.handlers.pop();
+        }
+        self.build_body(orelse);
+        self.jump(continuation, EdgeKind::Normal);
+        if let Some( dispatch ) = dispatch {
+            for h in handlers {
+                let start = self.new_block();
+                self.edge(dispatch, start, EdgeKind::Normal);
+                self.current = Some( start );
+                self.add_node(CfgNode::Handler(h));
+                self.build_body(&h.body);
+                self.jump(continuation, EdgeKind::Normal)
+            }
+            if handlers.iter().all( |h| h.type_.is_some() ) {
+                self.edge(dispatch, self.exception_target(), EdgeKind::Exception)
+            }
+        }
+        if finally_normal.is_some() {
+            self.handlers.pop();
+            let finally = self.finallies.pop().unwrap();
+            self.build_finally(finalbody, finally.normal, after, EdgeKind::Normal);
+            let reraise = self.exception_target();
+            self.build_finally(finalbody, finally.exception, reraise, EdgeKind::Exception);
+            for ( entry, to, kind ) in finally.pending {
+                self.build_finally(finalbody, entry, to, kind)
+            }
+        }
+        self.current = Some( after )
+    }
+
+    /// Copy of 'finally' starting at entry and continuing at target, when entry is reached at all.
+    /// An exception is raised again from an empty block, as exception edges leave before the
+    /// statement of their block.
+    fn build_finally(&mut self, finalbody: &'a [Stmt], entry: BlockId, target: BlockId, kind: EdgeKind) {
+        if self.blocks[entry].predecessors.is_empty() {
+            return
+        }
+        self.current = Some( entry );
+        self.build_body(finalbody);
+        if kind == EdgeKind::Exception && self.current.map( |c| !self.blocks[c].nodes.is_empty() ).unwrap_or(false) {
+            self.start_block();
+        }
+        self.jump(target, kind)
+    }
+}
+
+
+// UnitTests for control flow graph ////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::nodes::{ Mod, Stmt, StmtKind, MatchCase, Pattern, PatternKind, Location };
+    use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+    use crate::semantic::cfg::{ ControlFlowGraph, BasicBlock, BlockId, EdgeKind, CfgNode, build_graphs };
+
+
+    fn lower(source: &str) -> Mod {
+        let tree = crate::api::parse_module(source).tree.unwrap();
+        PythonCoreLowering::new(source).lower_mod(&tree).unwrap()
+    }
+
+    fn graph<'a>(module: &'a Mod, name: &str) -> ControlFlowGraph<'a> {
+        build_graphs(module).into_iter().find( |g| g.name == name ).unwrap()
+    }
+
+    /// Block holding statement starting on line.
+    fn block_of_line(graph: &ControlFlowGraph, line: u32) -> BlockId {
+        graph.blocks.iter().find( |b| b.nodes.iter().any( |n| matches!(n, CfgNode::Stmt( s ) if s.location.lineno == line) ) ).unwrap().id
+    }
+
+    #[test]
+    fn cfg_straight_line() {
+        let module = lower("def f():\n    a = 1\n    b = 2\n");
+        let g = graph(&module, "f");
+        assert_eq!(g.blocks.len(), 3);
+        assert_eq!(g.block(2).nodes.len(), 2);
+        assert_eq!(g.block(g.entry).successors, vec![ ( 2, EdgeKind::Normal ) ]);
+        assert_eq!(g.block(2).successors, vec![ ( g.exit, EdgeKind::Normal ) ]);
+    }
+
+    #[test]
+    fn cfg_if_elif_else() {
+        let module = lower("if a:\n    x = 1\nelif b:\n    x = 2\nelse:\n    x = 3\nprint(x)\n");
+        let g = graph(&module, "<module>");
+        let ( test, elif, last ) = ( block_of_line(&g, 1), block_of_line(&g, 3), block_of_line(&g, 7) );
+        assert_eq!(g.block(test).successors, vec![ ( block_of_line(&g, 2), EdgeKind::True ), ( elif, EdgeKind::False ) ]);
+        assert_eq!(g.block(elif).successors, vec![ ( block_of_line(&g, 4), EdgeKind::True ), ( block_of_line(&g, 6), EdgeKind::False ) ]);
+        assert_eq!(g.block(last).predecessors.len(), 2);
+        assert!(g.reachable().iter().all( |r| *r ));
+    }
+
+    #[test]
+    fn cfg_loops_with_break_continue_and_else() {
+        let module = lower("def f():\n    while a:\n        if b:\n            break\n        continue\n    else:\n        c()\n    d()\n");
+        let g = graph(&module, "f");
+        let ( header, done ) = ( block_of_line(&g, 2), block_of_line(&g, 8) );
+        assert_eq!(g.block(block_of_line(&g, 4)).successors, vec![ ( done, EdgeKind::Break ) ]);
+        assert_eq!(g.block(block_of_line(&g, 5)).successors, vec![ ( header, EdgeKind::Continue ) ]);
+        assert!(g.block(header).successors.contains(&( block_of_line(&g, 7), EdgeKind::False )));
+        assert_eq!(g.block(block_of_line(&g, 7)).successors, vec![ ( done, EdgeKind::Normal ) ]);
+    }
+
+    #[test]
+    fn cfg_for_loop_binds_target_in_body() {
+        let module = lower("for x in y:\n    pass\n");
+        let g = graph(&module, "<module>");
+        let body = block_of_line(&g, 2);
+        assert!(matches!(g.block(body).nodes[0], CfgNode::LoopTarget(..)));
+        let header = g.block(body).predecessors[0].0;
+        assert!(g.block(header).nodes.is_empty());
+        assert!(g.block(header).successors.contains(&( body, EdgeKind::True )));
+        assert!(g.block(body).successors.contains(&( header, EdgeKind::Loop )));
+    }
+
+    #[test]
+    fn cfg_while_true_has_no_exit_edge() {
+        let module = lower("def f():\n    while True:\n        pass\n    g()\n");
+        let g = graph(&module, "f");
+        assert_eq!(g.block(block_of_line(&g, 2)).successors.len(), 1);
+        assert!(!g.reachable()[block_of_line(&g, 4)]);
+    }
+
+    #[test]
+    fn cfg_code_after_jump_is_unreachable() {
+        let module = lower("def f():\n    return 1\n    g()\n\ndef h():\n    raise E\n    g()\n");
+        let f = graph(&module, "f");
+        assert!(!f.reachable()[block_of_line(&f, 3)]);
+        assert_eq!(f.block(block_of_line(&f, 2)).successors, vec![ ( f.exit, EdgeKind::Return ) ]);
+        let h = graph(&module, "h");
+        assert!(!h.reachable()[block_of_line(&h, 7)]);
+        assert_eq!(h.block(block_of_line(&h, 6)).successors, vec![ ( h.exit, EdgeKind::Exception ) ]);
+    }
+
+    #[test]
+    fn cfg_try_except_finally() {
+        let module = lower("def f():\n    try:\n        a()\n        return b\n    except E as e:\n        c()\n    finally:\n        d()\n    g()\n");
+        let g = graph(&module, "f");
+        let ( a, ret, c, fin ) = ( block_of_line(&g, 3), block_of_line(&g, 4), block_of_line(&g, 6), block_of_line(&g, 8) );
+        assert_ne!(a, ret);
+        let dispatch = g.block(a).successors.iter().find( |( _, k )| *k == EdgeKind::Exception ).unwrap().0;
+        assert!(matches!(g.block(g.block(c).predecessors[0].0).nodes[0], CfgNode::Handler(..)));
+        assert!(g.block(c).successors.contains(&( fin, EdgeKind::Normal )));
+        assert_eq!(g.block(fin).successors, vec![ ( block_of_line(&g, 9), EdgeKind::Normal ) ]);
+        let copies : Vec<&BasicBlock> = g.blocks.iter().filter( |b| b.nodes.iter().any( |n| matches!(n, CfgNode::Stmt( s ) if s.location.lineno == 8) ) ).collect();
+        assert_eq!(copies.len(), 3);
+        let exceptional = g.block(dispatch).successors.iter().find( |( _, k )| *k == EdgeKind::Exception ).unwrap().0;
+        assert!(g.block(exceptional).nodes.len() == 1 && g.block(exceptional).id != fin);
+        let returning = g.block(ret).successors.iter().find( |( _, k )| *k == EdgeKind::Return ).unwrap().0;
+        assert_eq!(g.block(returning).successors, vec![ ( g.exit, EdgeKind::Return ) ]);
+        assert!(g.reachable()[block_of_line(&g, 9)]);
+    }
+
+    #[test]
+    fn cfg_finally_reached_by_return_only() {
+        let module = lower("def f():\n    try:\n        return 1\n    finally:\n        pass\n    g()\n");
+        let g = graph(&module, "f");
+        assert!(!g.reachable()[block_of_line(&g, 6)]);
+    }
+
+    #[test]
+    fn cfg_break_through_finally() {
+        let module = lower("for x in y:\n    try:\n        break\n    finally:\n        a()\nb()\n");
+        let g = graph(&module, "<module>");
+        let fin = g.block(block_of_line(&g, 3)).successors.iter().find( |( _, k )| *k == EdgeKind::Break ).unwrap().0;
+        assert!(matches!(g.block(fin).nodes[0], CfgNode::Stmt( s ) if s.location.lineno == 5));
+        assert_eq!(g.block(fin).successors, vec![ ( block_of_line(&g, 6), EdgeKind::Break ) ]);
+    }
+
+    #[test]
+    fn cfg_qualified_names() {
+        let module = lower("def f():\n    def g(): pass\nclass A:\n    def m(self): pass\nif x:\n    async def h(): pass\n");
+        let names : Vec<String> = build_graphs(&module).into_iter().map( |g| g.name ).collect();
+        assert_eq!(names, vec![ "<module>", "f", "f.<locals>.g", "A.m", "h" ]);
+    }
+
+    #[test]
+    fn cfg_dot() {
+        let source = "def f(a):\n    if a:\n        return \"x\"\n    b()\n";
+        let module = lower(source);
+        assert_eq!(graph(&module, "f").to_dot(source), concat!(
+            "digraph \"f\" {\n",
+            "    node [shape=box, fontname=\"monospace\"];\n",
+            "    b0 [label=\"entry\"];\n",
+            "    b1 [label=\"exit\"];\n",
+            "    b2 [label=\"b2\\n2: if a:\\l\"];\n",
+            "    b3 [label=\"b3\\n4: b()\\l\"];\n",
+            "    b4 [label=\"b4\\n3: return \\\"x\\\"\\l\"];\n",
+            "    b0 -> b2;\n",
+            "    b2 -> b4 [label=\"true\"];\n",
+            "    b2 -> b3 [label=\"false\"];\n",
+            "    b3 -> b1;\n",
+            "    b4 -> b1 [label=\"return\"];\n",
+            "}\n"));
+    }
+
+    /// Module 'match s' with a case per pattern, the body of each case being the statement on the
+    /// line following the previous one and a last statement after 'match', as the parser does not
+    /// support 'match' yet.
+    fn match_module(source: &str, patterns: Vec<PatternKind>) -> Mod {
+        let mut body = match lower(source) {
+            Mod::Module { body, .. } => body,
+            _ => unreachable!()
+        };
+        let last = body.pop().unwrap();
+        let subject = match body.remove(0).node {
+            StmtKind::Expr { value } => value,
+            _ => unreachable!()
+        };
+        let cases = patterns.into_iter().zip(body).map( |( node, stmt )| MatchCase {
+            pattern: Pattern { node, location: Location::default() }, guard: None, body: vec![ stmt ]
+        }).collect();
+        let location = subject.location.clone();
+        Mod::Module { body: vec![ Stmt { node: StmtKind::Match { subject, cases }, location }, last ], type_ignores: Vec::new() }
+    }
+
+    #[test]
+    fn cfg_match_cases() {
+        let value = || match lower("1\n") {
+            Mod::Module { mut body, .. } => match body.remove(0).node {
+                StmtKind::Expr { value } => PatternKind::MatchValue { value },
+                _ => unreachable!()
+            },
+            _ => unreachable!()
+        };
+        let module = match_module("s\na()\nb()\nc()\n", vec![ value(), value() ]);
+        let g = graph(&module, "<module>");
+        let ( subject, a, b, c ) = ( block_of_line(&g, 1), block_of_line(&g, 2), block_of_line(&g, 3), block_of_line(&g, 4) );
+        assert!(matches!(g.block(subject).nodes[..], [ CfgNode::Stmt( s ), CfgNode::Case(..) ] if matches!(s.node, StmtKind::Match { .. })));
+        let second = g.block(subject).successors[1].0;
+        assert_eq!(g.block(subject).successors, vec![ ( a, EdgeKind::True ), ( second, EdgeKind::False ) ]);
+        assert!(matches!(g.block(second).nodes[..], [ CfgNode::Case(..) ]));
+        assert_eq!(g.block(second).successors, vec![ ( b, EdgeKind::True ), ( c, EdgeKind::False ) ]);
+        assert_eq!(g.block(c).predecessors.len(), 3);
+
+        let wildcard = PatternKind::MatchAs { pattern: None, name: None };
+        let module = match_module("s\na()\nb()\nc()\nd()\n", vec![ value(), wildcard, value() ]);
+        let g = graph(&module, "<module>");
+        let second = g.block(block_of_line(&g, 1)).successors[1].0;
+        assert_eq!(g.block(second).successors, vec![ ( block_of_line(&g, 3), EdgeKind::True ) ]);
+        assert!(!g.blocks.iter().any( |b| b.nodes.iter().any( |n| matches!(n, CfgNode::Stmt( s ) if s.location.lineno == 4) ) ));
+        assert_eq!(g.block(block_of_line(&g, 5)).predecessors.len(), 2);
+    }
+}
@@ -0,0 +1,550 @@
+use std::collections::{ HashMap, BTreeMap };
+use crate::ast::nodes::{ Mod, Stmt, StmtKind, Expr, ExprKind, ExprContext, Pattern, PatternKind };
+use crate::ast::visitor::{ Visitor, walk_expr, walk_arguments, walk_exprs, walk_pattern };
+use crate::semantic::cfg::{ ControlFlowGraph, CfgNode, EdgeKind, BlockId, build_graphs };
+use crate::semantic::symbols::{ SymbolTable, SymbolScope };
+use crate::diagnostics::Diagnostic;
+
+
+/// Access of a name by a node of the control flow graph, in the order of evaluation.
+#[derive(Clone, Debug, PartialEq)]
+enum Event {
+    Use(String, ( u32, u32 )),
+    /// Binding of name. Plain bindings are assignments of a value to a single name, which are
+    /// reported when the value is never read. Loop targets, unpacking, imports and definitions are not.
+    Def(String, ( u32, u32 ), bool),
+    Del(String)
+}
+
+/// Collects events of expressions evaluated in the current scope. Bodies of lambdas and
+/// comprehensions belong to scopes of their own and are skipped, except the outermost iterable.
+struct Events {
+    events: Vec<Event>
+}
+
+/// Liveness and reaching definitions of the local variables of one function, variables captured
+/// by nested scopes are left out as they are read and written at any time.
+struct FunctionFlow<'a, 'g> {
+    graph: &'g ControlFlowGraph<'a>,
+    locals: HashMap<String, usize>,
+    events: Vec<Vec<Event>>,
+    reachable: Vec<bool>
+}
+
+
+/// Warnings of dataflow analyses on a module without syntax errors: statements that are never
+/// executed, local variables whose assigned value is never read and local variables read where
+/// they may not have been assigned yet. Variables starting with '_' are never reported unused.
+pub fn analyze(module: &Mod, symbols: &SymbolTable) -> Vec<Diagnostic> {
+    let mut res = Vec::new();
+    for graph in build_graphs(module) {
+        report_unreachable(&graph, &mut res);
+        if graph.name == "<module>" {
+            continue
+        }
+        let scope = match symbols.scope_of_node(graph.span) {
+            Some( s ) => s,
+            None => continue
+        };
+        let flow = FunctionFlow::new(&graph, symbols, scope);
+        flow.report_unbound(symbols, scope, &mut res);
+        flow.report_unused(symbols, scope, &mut res);
+    }
+    res.sort_by_key( |d| ( d.start, d.end ) );
+    res
+}
+
+/// Reports first statement of every run of statements that can not be reached.
+fn report_unreachable(graph: &ControlFlowGraph, res: &mut Vec<Diagnostic>) {
+    let reachable = graph.reachable();
+    let mut dead : HashMap<u32, bool> = HashMap::new();
+    for block in &graph.blocks {
+        for node in &block.nodes {
+            if let CfgNode::Stmt( s ) = node {
+                *dead.entry(s.location.start).or_insert(true) &= !reachable[block.id]
+            }
+        }
+    }
+    report_unreachable_body(statements_of(graph), &dead, res)
+}
+
+/// Top level statements of graph, found as the statements whose start is not inside another one.
+fn statements_of<'a>(graph: &ControlFlowGraph<'a>) -> Vec<&'a Stmt> {
+    let mut all : Vec<&Stmt> = graph.blocks.iter().flat_map( |b| b.nodes.iter() ).filter_map( |n| match n {
+        CfgNode::Stmt( s ) => Some( *s ),
+        _ => None
+    }).collect();
+    all.sort_by_key( |s| s.location.start );
+    let mut res : Vec<&Stmt> = Vec::new();
+    for s in all {
+        if res.last().map( |l| s.location.start >= l.location.end ).unwrap_or(true) {
+            res.push(s)
+        }
+    }
+    res
+}
+
+fn report_unreachable_body(body: Vec<&Stmt>, dead: &HashMap<u32, bool>, res: &mut Vec<Diagnostic>) {
+    let mut previous_dead = false;
+    for stmt in body {
+        let is_dead = dead.get(&stmt.location.start).cloned().unwrap_or(false);
+        if is_dead && !previous_dead {
+            res.push( Diagnostic::warning(stmt.location.start, stmt.location.end, "unreachable code") )
+        }
+        if !is_dead {
+            report_unreachable_body(nested_statements(stmt), dead, res)
+        }
+        previous_dead = is_dead
+    }
+}
+
+/// Statements of the bodies of compound statement that are part of the same graph, in order.
+fn nested_statements(stmt: &Stmt) -> Vec<&Stmt> {
+    let bodies : Vec<&Vec<Stmt>> = match &stmt.node {
+        StmtKind::For { body, orelse, .. } |
+        StmtKind::AsyncFor { body, orelse, .. } |
+        StmtKind::While { body, orelse, .. } |
+        StmtKind::If { body, orelse, .. } => vec![ body, orelse ],
+        StmtKind::With { body, .. } |
+        StmtKind::AsyncWith { body, .. } => vec![ body ],
+        _ => Vec::new()
+    };
+    let mut res = Vec::new();
+    for body in bodies {
+        res.extend( flatten_try(body) )
+    }
+    res
+}
+
+/// Statements of body where 'try' statements, which are no nodes of the graph, are replaced by
+/// the statements of their parts.
+fn flatten_try(body: &[Stmt]) -> Vec<&Stmt> {
+    let mut res = Vec::new();
+    for stmt in body {
+        match &stmt.node {
+            StmtKind::Try { body, handlers, orelse, finalbody } |
+            StmtKind::TryStar { body, handlers, orelse, finalbody } => {
+                res.extend( flatten_try(body) );
+                for h in handlers {
+                    res.extend( flatten_try(&h.body) )
+                }
+                res.extend( flatten_try(orelse) );
+                res.extend( flatten_try(finalbody) )
+            },
+            _ => res.push(stmt)
+        }
+    }
+    res
+}
+
+fn span(expr: &Expr) -> ( u32, u32 ) {
+    ( expr.location.start, expr.location.end )
+}
+
+
+impl Events {
+    fn of_node(node: &CfgNode, symbols: &SymbolTable, scope: usize) -> Vec<Event> {
+        let mut collector = Events { events: Vec::new() };
+        match node {
+            CfgNode::Stmt( stmt ) => collector.statement(stmt),
+            CfgNode::LoopTarget( target ) => collector.visit_expr(target),
+            CfgNode::Case( case ) => {
+                collector.visit_pattern(&case.pattern);
+                if let Some( g ) = &case.guard {
+                    collector.visit_expr(g)
+                }
+            },
+            CfgNode::Handler( handler ) => {
+                if let Some( t ) = &handler.type_ {
+                    collector.visit_expr(t)
+                }
+                if let Some( name ) = &handler.name {
+                    let binding = symbols.lookup(scope, name).and_then( |s| s.bindings.iter()
+                        .find( |( start, _ )| *start >= handler.location.start && *start < handler.location.end ).cloned() );
+                    let span = binding.unwrap_or( ( handler.location.start, handler.location.end ) );
+                    collector.events.push( Event::Def(name.clone(), span, true) )
+                }
+            }
+        }
+        collector.events
+    }
+
+    /// Events of a simple statement or of the header of a compound statement.
+    fn statement(&mut self, stmt: &Stmt) {
+        let stmt_span = ( stmt.location.start, stmt.location.end );
+        match &stmt.node {
+            StmtKind::FunctionDef { name, args, decorator_list, returns, .. } |
+            StmtKind::AsyncFunctionDef { name, args, decorator_list, returns, .. } => {
+                walk_exprs(self, decorator_list);
+                walk_arguments(self, args);
+                if let Some( r ) = returns {
+                    self.visit_expr(r)
+                }
+                self.events.push( Event::Def(name.clone(), stmt_span, false) )
+            },
+            StmtKind::ClassDef { name, bases, keywords, decorator_list, .. } => {
+                walk_exprs(self, decorator_list);
+                walk_exprs(self, bases);
+                for k in keywords {
+                    self.visit_expr(&k.value)
+                }
+                self.events.push( Event::Def(name.clone(), stmt_span, false) )
+            },
+            StmtKind::Assign { targets, value, .. } => {
+                self.visit_expr(value);
+                for t in targets {
+                    self.target(t)
+                }
+            },
+            StmtKind::AugAssign { target, value, .. } => {
+                match &target.node {
+                    ExprKind::Name { id, .. } => {
+                        self.events.push( Event::Use(id.clone(), span(target)) );
+                        self.visit_expr(value);
+                        self.events.push( Event::Def(id.clone(), span(target), true) )
+                    },
+                    _ => {
+                        self.visit_expr(target);
+                        self.visit_expr(value)
+                    }
+                }
+            },
+            StmtKind::AnnAssign { target, value, .. } => {
+                if let Some( v ) = value {
+                    self.visit_expr(v);
+                    self.target(target)
+                }
+                else if !matches!(target.node, ExprKind::Name { .. }) {
+                    self.visit_expr(target)
+                }
+            },
+            StmtKind::For { iter, .. } |
+            StmtKind::AsyncFor { iter, .. } => self.visit_expr(iter),
+            StmtKind::While { test, .. } |
+            StmtKind::If { test, .. } => self.visit_expr(test),
+            StmtKind::Match { subject, .. } => self.visit_expr(subject),
+            StmtKind::With { items, .. } |
+            StmtKind::AsyncWith { items, .. } => {
+                for item in items {
+                    self.visit_expr(&item.context_expr);
+                    if let Some( t ) = &item.optional_vars {
+                        self.target(t)
+                    }
+                }
+            },
+            StmtKind::Import { names } |
+            StmtKind::ImportFrom { names, .. } => {
+                for a in names {
+                    let bound = match &a.asname {
+                        Some( n ) => n.clone(),
+                        None => a.name.split('.').next().unwrap_or("").to_string()
+                    };
+                    if bound != "*" {
+                        self.events.push( Event::Def(bound, ( a.location.start, a.location.end ), false) )
+                    }
+                }
+            },
+            StmtKind::Try { .. } |
+            StmtKind::TryStar { .. } |
+            StmtKind::Global { .. } |
+            StmtKind::Nonlocal { .. } => { },
+            _ => crate::ast::visitor::walk_stmt(self, stmt)
+        }
+    }
+
+    /// Target bound by an assignment, a single name is a plain binding.
+    fn target(&mut self, target: &Expr) {
+        match &target.node {
+            ExprKind::Name { id, .. } => self.events.push( Event::Def(id.clone(), span(target), true) ),
+            _ => self.visit_expr(target)
+        }
+    }
+}
+
+impl Visitor for Events {
+    fn visit_stmt(&mut self, _stmt: &Stmt) { }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.node {
+            ExprKind::Name { id, ctx } => {
+                match ctx {
+                    ExprContext::Load => self.events.push( Event::Use(id.clone(), span(expr)) ),
+                    ExprContext::Store => self.events.push( Event::Def(id.clone(), span(expr), false) ),
+                    ExprContext::Del => {
+                        self.events.push( Event::Use(id.clone(), span(expr)) );
+                        self.events.push( Event::Del(id.clone()) )
+                    }
+                }
+            },
+            ExprKind::NamedExpr { target, value } => {
+                self.visit_expr(value);
+                self.target(target)
+            },
+            ExprKind::Lambda { args, .. } => walk_arguments(self, args),
+            ExprKind::ListComp { generators, .. } |
+            ExprKind::SetComp { generators, .. } |
+            ExprKind::GeneratorExp { generators, .. } |
+            ExprKind::DictComp { generators, .. } => {
+                if let Some( g ) = generators.first() {
+                    self.visit_expr(&g.iter)
+                }
+            },
+            _ => walk_expr(self, expr)
+        }
+    }
+
+    /// Names captured by a pattern are bound, values and classes in it are read.
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        walk_pattern(self, pattern);
+        let name = match &pattern.node {
+            PatternKind::MatchAs { name, .. } |
+            PatternKind::MatchStar { name } |
+            PatternKind::MatchMapping { rest: name, .. } => name,
+            _ => &None
+        };
+        if let Some( n ) = name {
+            self.events.push( Event::Def(n.clone(), ( pattern.location.start, pattern.location.end ), false) )
+        }
+    }
+}
+
+
+impl<'a, 'g> FunctionFlow<'a, 'g> {
+    fn new(graph: &'g ControlFlowGraph<'a>, symbols: &SymbolTable, scope: usize) -> FunctionFlow<'a, 'g> {
+        let mut names : Vec<&String> = symbols.scope(scope).symbols.iter()
+            .filter( |s| s.scope == SymbolScope::Local ).map( |s| &s.name ).collect();
+        names.sort();
+        let locals = names.into_iter().enumerate().map( |( i, n )| ( n.clone(), i ) ).collect();
+        let events = graph.blocks.iter()
+            .map( |b| b.nodes.iter().flat_map( |n| Events::of_node(n, symbols, scope) ).collect() ).collect();
+        FunctionFlow { graph, locals, events, reachable: graph.reachable() }
+    }
+
+    fn local(&self, name: &str) -> Option<usize> {
+        self.locals.get(name).cloned()
+    }
+
+    /// Forward analysis of which locals may be bound and which may be unbound at start of every
+    /// block. Exception edges carry state at start of their block, other edges state at its end.
+    fn bound_states(&self, symbols: &SymbolTable, scope: usize) -> Vec<Option<( Vec<bool>, Vec<bool> )>> {
+        let count = self.locals.len();
+        let mut entry = ( vec![ true; count ], vec![ false; count ] );
+        for ( name, i ) in &self.locals {
+            if symbols.lookup(scope, name).map( |s| s.is_parameter ).unwrap_or(false) {
+                entry = ( set(entry.0, *i, false), set(entry.1, *i, true) )
+            }
+        }
+        let mut states : Vec<Option<( Vec<bool>, Vec<bool> )>> = vec![ None; self.graph.blocks.len() ];
+        states[self.graph.entry] = Some( entry );
+        let mut work = vec![ self.graph.entry ];
+        while let Some( id ) = work.pop() {
+            let start = states[id].clone().unwrap();
+            let mut end = start.clone();
+            for e in &self.events[id] {
+                self.apply_bound(e, &mut end)
+            }
+            for ( next, kind ) in &self.graph.blocks[id].successors {
+                let incoming = if *kind == EdgeKind::Exception { &start } else { &end };
+                let merged = match &states[*next] {
+                    Some( ( unbound, bound ) ) => ( or(unbound, &incoming.0), or(bound, &incoming.1) ),
+                    None => incoming.clone()
+                };
+                if states[*next].as_ref() != Some( &merged ) {
+                    states[*next] = Some( merged );
+                    work.push(*next)
+                }
+            }
+        }
+        states
+    }
+
+    /// A read that did not fail leaves the local bound, so only the first read of a path is reported.
+    fn apply_bound(&self, event: &Event, state: &mut ( Vec<bool>, Vec<bool> )) {
+        match event {
+            Event::Def(name, ..) => if let Some( i ) = self.local(name) {
+                state.0[i] = false;
+                state.1[i] = true
+            },
+            Event::Del(name) => if let Some( i ) = self.local(name) {
+                state.0[i] = true;
+                state.1[i] = false
+            },
+            Event::Use(name, _) => if let Some( i ) = self.local(name) {
+                state.0[i] = false;
+                state.1[i] = true
+            }
+        }
+    }
+
+    /// Reports reads of locals not bound on every path. Statements of 'finally' are found in several
+    /// blocks, a read is unbound for sure when it is in all of them.
+    fn report_unbound(&self, symbols: &SymbolTable, scope: usize, res: &mut Vec<Diagnostic>) {
+        let states = self.bound_states(symbols, scope);
+        let mut found : BTreeMap<( u32, u32 ), ( &str, bool, bool )> = BTreeMap::new();
+        for ( id, state ) in states.into_iter().enumerate() {
+            let mut state = match state {
+                Some( s ) if self.reachable[id] => s,
+                _ => continue
+            };
+            for e in &self.events[id] {
+                if let Event::Use(name, span) = e {
+                    if let Some( i ) = self.local(name) {
+                        let ( unbound, bound ) = ( state.0[i], state.1[i] );
+                        let entry = found.entry(*span).or_insert( ( name.as_str(), false, true ) );
+                        entry.1 |= unbound;
+                        entry.2 &= unbound && !bound
+                    }
+                }
+                self.apply_bound(e, &mut state)
+            }
+        }
+        for ( ( start, end ), ( name, unbound, always ) ) in found {
+            match ( unbound, always ) {
+                ( true, true ) => res.push( Diagnostic::warning(start, end, &format!("local variable '{}' is referenced before assignment", name)) ),
+                ( true, false ) => res.push( Diagnostic::warning(start, end, &format!("local variable '{}' is possibly unbound", name)) ),
+                _ => { }
+            }
+        }
+    }
+
+    /// Backward analysis of locals live at start of every block. Exception edges make locals live
+    /// in their target live already at start of their block.
+    fn live_states(&self) -> Vec<Vec<bool>> {
+        let count = self.locals.len();
+        let mut live = vec![ vec![ false; count ]; self.graph.blocks.len() ];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for id in ( 0 .. self.graph.blocks.len() ).rev() {
+                let start = self.live_at_start(id, &live, |_, _| { });
+                if start != live[id] {
+                    live[id] = start;
+                    changed = true
+                }
+            }
+        }
+        live
+    }
+
+    /// Liveness at start of block from liveness at start of successors. Function 'on_def' is called
+    /// for every binding with the liveness of the bound local following it.
+    fn live_at_start<F: FnMut(&Event, bool)>(&self, id: BlockId, live: &[Vec<bool>], mut on_def: F) -> Vec<bool> {
+        let mut state = vec![ false; self.locals.len() ];
+        let mut exceptional = vec![ false; self.locals.len() ];
+        for ( next, kind ) in &self.graph.blocks[id].successors {
+            match kind {
+                EdgeKind::Exception => exceptional = or(&exceptional, &live[*next]),
+                _ => state = or(&state, &live[*next])
+            }
+        }
+        for e in self.events[id].iter().rev() {
+            match e {
+                Event::Def(name, ..) | Event::Del(name) => if let Some( i ) = self.local(name) {
+                    on_def(e, state[i] || exceptional[i]);
+                    state[i] = false
+                },
+                Event::Use(name, _) => if let Some( i ) = self.local(name) {
+                    state[i] = true
+                }
+            }
+        }
+        or(&state, &exceptional)
+    }
+
+    /// Reports plain bindings of locals never read afterwards, in none of the copies of a binding
+    /// inside 'finally'.
+    fn report_unused(&self, symbols: &SymbolTable, scope: usize, res: &mut Vec<Diagnostic>) {
+        let live = self.live_states();
+        let mut found : BTreeMap<( u32, u32 ), ( String, bool )> = BTreeMap::new();
+        for id in 0 .. self.graph.blocks.len() {
+            if !self.reachable[id] {
+                continue
+            }
+            self.live_at_start(id, &live, |e, is_live| {
+                if let Event::Def(name, span, true) = e {
+                    found.entry(*span).or_insert_with( || ( name.clone(), false ) ).1 |= is_live
+                }
+            });
+        }
+        for ( ( start, end ), ( name, is_live ) ) in found {
+            if is_live || name.starts_with('_') {
+                continue
+            }
+            let message = match symbols.lookup(scope, &name).map( |s| s.is_referenced ).unwrap_or(false) {
+                true => format!("value assigned to local variable '{}' is never used", name),
+                _ => format!("local variable '{}' is assigned to but never used", name)
+            };
+            res.push( Diagnostic::warning(start, end, &message) )
+        }
+    }
+}
+
+fn set(mut bits: Vec<bool>, index: usize, value: bool) -> Vec<bool> {
+    bits[index] = value;
+    bits
+}
+
+fn or(a: &[bool], b: &[bool]) -> Vec<bool> {
+    a.iter().zip(b.iter()).map( |( x, y )| *x || *y ).collect()
+}
+
+
+// UnitTests for dataflow analyses /////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{ analyze, Mode };
+    use crate::ast::location::LineIndex;
+
+
+    /// Warnings as 'line:column: message'.
+    fn warnings(source: &str) -> Vec<String> {
+        let index = LineIndex::new(source);
+        analyze(source, Mode::Exec).iter().map( |d| {
+            let ( line, col ) = index.line_char_col(d.start);
+            format!("{}:{}: {}", line, col, d.message)
+        }).collect()
+    }
+
+    #[test]
+    fn dataflow_clean_function() {
+        assert!(warnings("def f(a, b=1):\n    c = a + b\n    for i in range(c):\n        print(i)\n    return c\n").is_empty());
+        assert!(warnings("def f(a):\n    a, b = g()\n    try:\n        x = 1\n    except E as _e:\n        x = 2\n    return x\n").is_empty());
+        assert!(warnings("def f():\n    try:\n        x = g()\n    finally:\n        h()\n    return x\n").is_empty());
+    }
+
+    #[test]
+    fn dataflow_unreachable_code() {
+        assert_eq!(warnings("def f():\n    return 1\n    a()\n    b()\n"), vec![ "3:4: unreachable code" ]);
+        assert_eq!(warnings("def f():\n    raise E\n    a()\n"), vec![ "3:4: unreachable code" ]);
+        assert_eq!(warnings("for x in y:\n    continue\n    a()\n"), vec![ "3:4: unreachable code" ]);
+        assert_eq!(warnings("def f(x):\n    if x:\n        return 1\n    else:\n        return 2\n    a()\n"), vec![ "6:4: unreachable code" ]);
+        assert_eq!(warnings("def f():\n    while True:\n        pass\n    a()\n"), vec![ "4:4: unreachable code" ]);
+        assert_eq!(warnings("def f():\n    try:\n        return 1\n    finally:\n        pass\n    a()\n"), vec![ "6:4: unreachable code" ]);
+        assert!(warnings("def f():\n    with suppress(E):\n        return 1\n    a()\n").is_empty());
+    }
+
+    #[test]
+    fn dataflow_unused_variables() {
+        assert_eq!(warnings("def f():\n    x = 1\n"), vec![ "2:4: local variable 'x' is assigned to but never used" ]);
+        assert_eq!(warnings("def f():\n    x = 1\n    x = 2\n    return x\n"), vec![ "2:4: value assigned to local variable 'x' is never used" ]);
+        assert_eq!(warnings("def f():\n    try:\n        g()\n    except E as e:\n        pass\n"), vec![ "4:16: local variable 'e' is assigned to but never used" ]);
+        assert_eq!(warnings("def f():\n    n = 0\n    n += 1\n"), vec![ "3:4: local variable 'n' is assigned to but never used" ]);
+        assert!(warnings("def f():\n    x = 1\n    def g():\n        return x\n    return g\n").is_empty());
+        assert!(warnings("def f():\n    global x\n    x = 1\n").is_empty());
+        assert!(warnings("def f():\n    _ = 1\n    for i in y:\n        pass\n").is_empty());
+        assert!(warnings("def f():\n    x = 0\n    while x < 3:\n        x += 1\n").is_empty());
+    }
+
+    #[test]
+    fn dataflow_unbound_variables() {
+        assert_eq!(warnings("def f():\n    print(x)\n    x = 1\n    return x\n"), vec![ "2:10: local variable 'x' is referenced before assignment" ]);
+        assert_eq!(warnings("def f(c):\n    if c:\n        x = 1\n    return x\n"), vec![ "4:11: local variable 'x' is possibly unbound" ]);
+        assert_eq!(warnings("def f(c):\n    if c:\n        x = 1\n    g(x)\n    return x\n"), vec![ "4:6: local variable 'x' is possibly unbound" ]);
+        assert_eq!(warnings("def f():\n    x = 1\n    del x\n    return x\n"), vec![ "4:11: local variable 'x' is referenced before assignment" ]);
+        assert_eq!(warnings("def f():\n    try:\n        x = g()\n    except E:\n        pass\n    return x\n"), vec![ "6:11: local variable 'x' is possibly unbound" ]);
+        assert_eq!(warnings("def f(y):\n    for i in y:\n        pass\n    return i\n"), vec![ "4:11: local variable 'i' is possibly unbound" ]);
+        assert!(warnings("def f():\n    x = 1\n    return [x for _ in y]\n").is_empty());
+        assert!(warnings("print(x)\nx = 1\n").is_empty());
+    }
+}
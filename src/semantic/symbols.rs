@@ -0,0 +1,797 @@
+use std::collections::{ HashMap, HashSet };
+use crate::ast::nodes::{ Mod, Stmt, StmtKind, Expr, ExprKind, ExprContext, Comprehension, ExceptHandler, Arguments, Arg, Alias };
+use crate::ast::visitor::{ Visitor, walk_stmt, walk_expr, walk_body, walk_exprs };
+use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+use crate::diagnostics::Diagnostic;
+use crate::api::parse_module;
+
+
+pub type ScopeId = usize;
+
+/// Kind of block that owns a namespace, as in CPython 'symtable'.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScopeKind {
+    Module,
+    Class,
+    Function,
+    Lambda,
+    Comprehension
+}
+
+/// Where a name is stored. 'Global' is used both for names declared 'global' and for names that are
+/// never bound in the scope or any enclosing function, 'Nonlocal' for names declared 'nonlocal',
+/// 'Free' for names found in an enclosing function and 'Cell' for locals used by nested scopes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SymbolScope {
+    Local,
+    Global,
+    Nonlocal,
+    Free,
+    Cell
+}
+
+/// Name in a scope with the way it is used. Spans point at each occurrence of the name in source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub scope: SymbolScope,
+    pub is_assigned: bool,
+    pub is_referenced: bool,
+    pub is_parameter: bool,
+    pub is_imported: bool,
+    pub is_annotated: bool,
+    pub is_declared_global: bool,
+    pub is_declared_nonlocal: bool,
+    pub bindings: Vec<( u32, u32 )>,
+    pub references: Vec<( u32, u32 )>,
+    pub declarations: Vec<( u32, u32 )>
+}
+
+#[derive(Clone, Debug)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    pub name: String,
+    pub span: ( u32, u32 ),
+    pub parent: Option<ScopeId>,
+    pub children: Vec<ScopeId>,
+    pub symbols: Vec<Symbol>,
+    pub is_generator: bool,
+    pub is_coroutine: bool,
+    pub has_import_star: bool,
+    pub needs_class_closure: bool,
+    index: HashMap<String, usize>
+}
+
+/// Scopes of a module with every name classified the way CPython does before compiling. Scope 0
+/// is the module, scopes of functions, classes, lambdas and comprehensions are found by the span of
+/// the node that creates them. Errors CPython raises while building its symbol table are kept in
+/// 'diagnostics'.
+#[derive(Clone, Debug)]
+pub struct SymbolTable {
+    pub scopes: Vec<Scope>,
+    pub diagnostics: Vec<Diagnostic>,
+    spans: HashMap<( u32, u32 ), ScopeId>
+}
+
+struct SymbolTableBuilder {
+    source: Vec<char>,
+    scopes: Vec<Scope>,
+    spans: HashMap<( u32, u32 ), ScopeId>,
+    stack: Vec<ScopeId>,
+    iteration_names: HashMap<ScopeId, HashSet<String>>,
+    declaration_statements: HashMap<( ScopeId, String ), ( u32, u32 )>,
+    in_iteration_target: bool,
+    diagnostics: Vec<Diagnostic>
+}
+
+
+impl Symbol {
+    fn new(name: &str) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            scope: SymbolScope::Global,
+            is_assigned: false,
+            is_referenced: false,
+            is_parameter: false,
+            is_imported: false,
+            is_annotated: false,
+            is_declared_global: false,
+            is_declared_nonlocal: false,
+            bindings: Vec::new(),
+            references: Vec::new(),
+            declarations: Vec::new()
+        }
+    }
+
+    /// Name is bound in its own scope by assignment, parameter, import, definition or deletion.
+    pub fn is_bound(&self) -> bool {
+        self.is_assigned || self.is_parameter || self.is_imported
+    }
+
+    /// Every span where the name occurs, sorted by position.
+    pub fn occurrences(&self) -> Vec<( u32, u32 )> {
+        let mut res : Vec<( u32, u32 )> = self.bindings.iter().chain(self.references.iter()).chain(self.declarations.iter()).cloned().collect();
+        res.sort();
+        res.dedup();
+        res
+    }
+}
+
+impl Scope {
+    fn new(kind: ScopeKind, name: &str, span: ( u32, u32 ), parent: Option<ScopeId>) -> Scope {
+        Scope {
+            kind,
+            name: name.to_string(),
+            span,
+            parent,
+            children: Vec::new(),
+            symbols: Vec::new(),
+            is_generator: false,
+            is_coroutine: false,
+            has_import_star: false,
+            needs_class_closure: false,
+            index: HashMap::new()
+        }
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&Symbol> {
+        self.index.get(name).map( |i| &self.symbols[*i] )
+    }
+
+    /// Functions, lambdas and comprehensions keep their locals in fast slots and can own cells.
+    pub fn is_function_like(&self) -> bool {
+        matches!(self.kind, ScopeKind::Function | ScopeKind::Lambda | ScopeKind::Comprehension)
+    }
+
+    fn symbol_mut(&mut self, name: &str) -> &mut Symbol {
+        let index = match self.index.get(name) {
+            Some( i ) => *i,
+            None => {
+                self.symbols.push( Symbol::new(name) );
+                self.index.insert(name.to_string(), self.symbols.len() - 1);
+                self.symbols.len() - 1
+            }
+        };
+        &mut self.symbols[index]
+    }
+}
+
+impl SymbolTable {
+    /// Builds symbol table of a lowered module. Source is needed to find the position of names that
+    /// have no node of their own, like the name of a function or the names of a 'global' statement.
+    pub fn build(module: &Mod, source: &str) -> SymbolTable {
+        let span = ( 0, source.chars().count() as u32 );
+        let mut builder = SymbolTableBuilder {
+            source: source.chars().collect(),
+            scopes: vec![ Scope::new(ScopeKind::Module, "top", span, None) ],
+            spans: HashMap::new(),
+            stack: vec![ 0 ],
+            iteration_names: HashMap::new(),
+            declaration_statements: HashMap::new(),
+            in_iteration_target: false,
+            diagnostics: Vec::new()
+        };
+        builder.spans.insert(span, 0);
+        builder.visit_mod(module);
+        builder.analyze(0, &HashSet::new(), &HashSet::new());
+        builder.diagnostics.sort_by_key( |d| d.start );
+        SymbolTable { scopes: builder.scopes, diagnostics: builder.diagnostics, spans: builder.spans }
+    }
+
+    /// Parses, lowers and builds symbol table of source, or returns the first syntax error.
+    pub fn from_source(source: &str) -> Result<SymbolTable, Diagnostic> {
+        let res = parse_module(source);
+        let tree = match res.tree {
+            Some( t ) => t,
+            None => return Err( res.diagnostics[0].clone() )
+        };
+        let module = PythonCoreLowering::new(source).lower_mod(&tree).map_err( |e| Diagnostic::from_error_text(&e, 0) )?;
+        Ok( SymbolTable::build(&module, source) )
+    }
+
+    pub fn root(&self) -> &Scope {
+        &self.scopes[0]
+    }
+
+    pub fn scope(&self, id: ScopeId) -> &Scope {
+        &self.scopes[id]
+    }
+
+    /// Scope created by the function, class, lambda or comprehension node with this span.
+    pub fn scope_of_node(&self, span: ( u32, u32 )) -> Option<ScopeId> {
+        self.spans.get(&span).cloned()
+    }
+
+    /// Scope of the node starting at position, no two scope creating nodes start at the same place.
+    pub fn scope_starting_at(&self, start: u32) -> Option<ScopeId> {
+        self.scopes.iter().position( |s| s.span.0 == start && s.parent.is_some() )
+    }
+
+    pub fn lookup(&self, scope: ScopeId, name: &str) -> Option<&Symbol> {
+        self.scopes[scope].lookup(name)
+    }
+
+    /// Scope and symbol where name used in scope is bound. Free names are followed outwards past
+    /// class scopes and global names are looked up in the module. None means a builtin or an
+    /// undefined name.
+    pub fn resolve(&self, scope: ScopeId, name: &str) -> Option<( ScopeId, &Symbol )> {
+        let symbol = self.scopes[scope].lookup(name);
+        match symbol.map( |s| s.scope ) {
+            Some( SymbolScope::Local ) | Some( SymbolScope::Cell ) => Some( ( scope, symbol.unwrap() ) ),
+            Some( SymbolScope::Free ) | Some( SymbolScope::Nonlocal ) => {
+                let mut current = self.scopes[scope].parent;
+                while let Some( id ) = current {
+                    let s = &self.scopes[id];
+                    if s.kind != ScopeKind::Class {
+                        if let Some( sym ) = s.lookup(name) {
+                            if matches!(sym.scope, SymbolScope::Local | SymbolScope::Cell) {
+                                return Some( ( id, sym ) )
+                            }
+                        }
+                    }
+                    current = s.parent
+                }
+                None
+            },
+            _ => self.scopes[0].lookup(name).filter( |s| s.is_bound() ).map( |s| ( 0, s ) )
+        }
+    }
+
+    /// Scope and symbol of the name occurrence that covers position.
+    pub fn symbol_at(&self, position: u32) -> Option<( ScopeId, &Symbol )> {
+        for ( id, scope ) in self.scopes.iter().enumerate() {
+            for symbol in &scope.symbols {
+                if symbol.occurrences().iter().any( |( s, e )| *s <= position && position < *e ) {
+                    return Some( ( id, symbol ) )
+                }
+            }
+        }
+        None
+    }
+}
+
+impl SymbolTableBuilder {
+    fn current(&self) -> ScopeId {
+        *self.stack.last().unwrap()
+    }
+
+    fn enter_scope(&mut self, kind: ScopeKind, name: &str, span: ( u32, u32 )) -> ScopeId {
+        let parent = self.current();
+        let id = self.scopes.len();
+        self.scopes.push( Scope::new(kind, name, span, Some( parent )) );
+        self.scopes[parent].children.push(id);
+        self.spans.insert(span, id);
+        self.stack.push(id);
+        id
+    }
+
+    fn exit_scope(&mut self) {
+        self.stack.pop();
+    }
+
+    fn error(&mut self, span: ( u32, u32 ), message: String) {
+        self.diagnostics.push( Diagnostic::error(span.0, span.1, &message) )
+    }
+
+    fn bind(&mut self, name: &str, span: ( u32, u32 )) {
+        let id = self.current();
+        let symbol = self.scopes[id].symbol_mut(name);
+        symbol.is_assigned = true;
+        symbol.bindings.push(span);
+        if self.in_iteration_target {
+            self.iteration_names.entry(id).or_default().insert( name.to_string() );
+        }
+    }
+
+    fn reference(&mut self, name: &str, span: ( u32, u32 )) {
+        let id = self.current();
+        let symbol = self.scopes[id].symbol_mut(name);
+        symbol.is_referenced = true;
+        symbol.references.push(span)
+    }
+
+    /// Span of the first whole word 'name' at or after position.
+    fn find_name(&self, from: u32, name: &str) -> ( u32, u32 ) {
+        let word : Vec<char> = name.chars().collect();
+        let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+        let mut i = from as usize;
+        while i + word.len() <= self.source.len() {
+            if self.source[i .. i + word.len()] == word[..]
+                && ( i == 0 || !is_identifier(self.source[i - 1]) )
+                && self.source.get(i + word.len()).map( |c| !is_identifier(*c) ).unwrap_or(true) {
+                return ( i as u32, ( i + word.len() ) as u32 )
+            }
+            i += 1
+        }
+        ( from, from )
+    }
+
+    fn add_parameters(&mut self, args: &Arguments) {
+        let mut all : Vec<&Arg> = args.posonlyargs.iter().chain(args.args.iter()).collect();
+        all.extend( args.vararg.as_deref() );
+        all.extend( args.kwonlyargs.iter() );
+        all.extend( args.kwarg.as_deref() );
+        let id = self.current();
+        for a in all {
+            let span = ( a.location.start, a.location.start + a.arg.chars().count() as u32 );
+            let symbol = self.scopes[id].symbol_mut(&a.arg);
+            if symbol.is_parameter {
+                self.error(span, format!("duplicate argument '{}' in function definition", a.arg));
+                continue
+            }
+            symbol.is_parameter = true;
+            symbol.bindings.push(span)
+        }
+    }
+
+    fn visit_annotations(&mut self, args: &Arguments) {
+        let all = args.posonlyargs.iter().chain(args.args.iter()).chain(args.vararg.as_deref()).chain(args.kwonlyargs.iter()).chain(args.kwarg.as_deref());
+        for a in all {
+            if let Some( annotation ) = &a.annotation {
+                self.visit_expr(annotation)
+            }
+        }
+    }
+
+    fn visit_defaults(&mut self, args: &Arguments) {
+        walk_exprs(self, &args.defaults);
+        for d in args.kw_defaults.iter().flatten() {
+            self.visit_expr(d)
+        }
+    }
+
+    fn declare(&mut self, stmt: &Stmt, names: &[String], is_global: bool) {
+        let id = self.current();
+        let keyword = if is_global { "global" } else { "nonlocal" };
+        let stmt_span = ( stmt.location.start, stmt.location.end );
+        if !is_global && self.scopes[id].kind == ScopeKind::Module {
+            self.error(stmt_span, "nonlocal declaration not allowed at module level".to_string());
+            return
+        }
+        let mut position = stmt.location.start + keyword.len() as u32;
+        for name in names {
+            let span = self.find_name(position, name);
+            position = span.1;
+            let symbol = self.scopes[id].symbol_mut(name);
+            let message = if symbol.is_parameter {
+                Some( format!("name '{}' is parameter and {}", name, keyword) )
+            }
+            else if symbol.is_referenced {
+                Some( format!("name '{}' is used prior to {} declaration", name, keyword) )
+            }
+            else if symbol.is_annotated {
+                Some( format!("annotated name '{}' can't be {}", name, keyword) )
+            }
+            else if symbol.is_assigned {
+                Some( format!("name '{}' is assigned to before {} declaration", name, keyword) )
+            }
+            else {
+                None
+            };
+            match is_global {
+                true => symbol.is_declared_global = true,
+                _ => symbol.is_declared_nonlocal = true
+            }
+            symbol.declarations.push(span);
+            self.declaration_statements.entry(( id, name.clone() )).or_insert(stmt_span);
+            if let Some( m ) = message {
+                self.error(stmt_span, m)
+            }
+        }
+    }
+
+    fn visit_comprehension_scope(&mut self, expr: &Expr, name: &str, generators: &[Comprehension], elts: &[&Expr]) {
+        if let Some( first ) = generators.first() {
+            self.visit_expr(&first.iter)
+        }
+        let id = self.enter_scope(ScopeKind::Comprehension, name, ( expr.location.start, expr.location.end ));
+        self.scopes[id].is_generator = name == "genexpr";
+        let symbol = self.scopes[id].symbol_mut(".0");
+        symbol.is_parameter = true;
+        for ( i, g ) in generators.iter().enumerate() {
+            if i > 0 {
+                self.visit_expr(&g.iter)
+            }
+            self.in_iteration_target = true;
+            self.visit_expr(&g.target);
+            self.in_iteration_target = false;
+            walk_exprs(self, &g.ifs)
+        }
+        for e in elts {
+            self.visit_expr(e)
+        }
+        self.exit_scope()
+    }
+
+    /// Binds target of ':=' inside comprehensions in the first enclosing function or module.
+    fn bind_named_expr_target(&mut self, target: &Expr) {
+        let ( name, span ) = match &target.node {
+            ExprKind::Name { id, .. } => ( id.clone(), ( target.location.start, target.location.end ) ),
+            _ => return self.visit_expr(target)
+        };
+        let current = self.current();
+        if self.scopes[current].kind != ScopeKind::Comprehension {
+            return self.bind(&name, span)
+        }
+        for i in ( 0 .. self.stack.len() ).rev() {
+            let id = self.stack[i];
+            match self.scopes[id].kind {
+                ScopeKind::Comprehension => {
+                    if self.iteration_names.get(&id).map( |n| n.contains(&name) ).unwrap_or(false) {
+                        return self.error(span, format!("assignment expression cannot rebind comprehension iteration variable '{}'", name))
+                    }
+                },
+                ScopeKind::Class => {
+                    return self.error(span, "assignment expression within a comprehension cannot be used in a class body".to_string())
+                },
+                kind => {
+                    let declared_global = self.scopes[id].lookup(&name).map( |s| s.is_declared_global ).unwrap_or(false);
+                    let inner = self.scopes[current].symbol_mut(&name);
+                    match kind == ScopeKind::Module || declared_global {
+                        true => inner.is_declared_global = true,
+                        _ => inner.is_declared_nonlocal = true
+                    }
+                    let outer = self.scopes[id].symbol_mut(&name);
+                    outer.is_assigned = true;
+                    outer.bindings.push(span);
+                    if kind == ScopeKind::Module {
+                        outer.is_declared_global = true
+                    }
+                    return
+                }
+            }
+        }
+    }
+
+    /// Classifies names of scope and its children, returns the free names scope needs from outside.
+    fn analyze(&mut self, id: ScopeId, bound: &HashSet<String>, global: &HashSet<String>) -> HashSet<String> {
+        let mut local : HashSet<String> = HashSet::new();
+        let mut free : HashSet<String> = HashSet::new();
+        let mut scope_bound = bound.clone();
+        let mut scope_global = global.clone();
+        let kind = self.scopes[id].kind;
+        let mut errors = Vec::new();
+        for symbol in self.scopes[id].symbols.iter_mut() {
+            let name = symbol.name.clone();
+            if symbol.is_declared_global {
+                if symbol.is_declared_nonlocal {
+                    errors.push( ( name.clone(), format!("name '{}' is nonlocal and global", name) ) )
+                }
+                symbol.scope = SymbolScope::Global;
+                scope_global.insert(name.clone());
+                scope_bound.remove(&name);
+            }
+            else if symbol.is_declared_nonlocal {
+                if !bound.contains(&name) {
+                    errors.push( ( name.clone(), format!("no binding for nonlocal '{}' found", name) ) )
+                }
+                symbol.scope = SymbolScope::Nonlocal;
+                free.insert(name);
+            }
+            else if symbol.is_bound() {
+                symbol.scope = SymbolScope::Local;
+                scope_global.remove(&name);
+                local.insert(name);
+            }
+            else if bound.contains(&name) {
+                symbol.scope = SymbolScope::Free;
+                free.insert(name);
+            }
+            else {
+                symbol.scope = SymbolScope::Global
+            }
+        }
+        for ( name, message ) in errors {
+            let span = self.declaration_statements.get(&( id, name )).cloned().unwrap_or( self.scopes[id].span );
+            self.error(span, message)
+        }
+        let ( child_bound, child_global ) = match kind {
+            ScopeKind::Class => {
+                let mut b = bound.clone();
+                b.insert( "__class__".to_string() );
+                ( b, global.clone() )
+            },
+            ScopeKind::Module => ( scope_bound, scope_global ),
+            _ => ( scope_bound.union(&local).cloned().collect(), scope_global )
+        };
+        let mut child_free : HashSet<String> = HashSet::new();
+        for child in self.scopes[id].children.clone() {
+            child_free.extend( self.analyze(child, &child_bound, &child_global) )
+        }
+        let scope = &mut self.scopes[id];
+        if scope.is_function_like() {
+            for symbol in scope.symbols.iter_mut() {
+                if symbol.scope == SymbolScope::Local && child_free.remove(&symbol.name) {
+                    symbol.scope = SymbolScope::Cell
+                }
+            }
+        }
+        if kind == ScopeKind::Class && child_free.remove("__class__") {
+            scope.needs_class_closure = true
+        }
+        let mut names : Vec<String> = child_free.iter().cloned().collect();
+        names.sort();
+        for name in names {
+            if scope.lookup(&name).is_none() && bound.contains(&name) {
+                scope.symbol_mut(&name).scope = SymbolScope::Free
+            }
+        }
+        free.extend(child_free);
+        free
+    }
+}
+
+impl Visitor for SymbolTableBuilder {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        let span = ( stmt.location.start, stmt.location.end );
+        match &stmt.node {
+            StmtKind::FunctionDef { name, args, body, decorator_list, returns, .. } |
+            StmtKind::AsyncFunctionDef { name, args, body, decorator_list, returns, .. } => {
+                walk_exprs(self, decorator_list);
+                self.visit_defaults(args);
+                self.visit_annotations(args);
+                if let Some( r ) = returns {
+                    self.visit_expr(r)
+                }
+                let name_span = self.find_name(stmt.location.start, name);
+                self.bind(name, name_span);
+                let id = self.enter_scope(ScopeKind::Function, name, span);
+                self.scopes[id].is_coroutine = matches!(stmt.node, StmtKind::AsyncFunctionDef { .. });
+                self.add_parameters(args);
+                walk_body(self, body);
+                self.exit_scope()
+            },
+            StmtKind::ClassDef { name, bases, keywords, body, decorator_list } => {
+                walk_exprs(self, decorator_list);
+                walk_exprs(self, bases);
+                for k in keywords {
+                    self.visit_keyword(k)
+                }
+                let name_span = self.find_name(stmt.location.start, name);
+                self.bind(name, name_span);
+                self.enter_scope(ScopeKind::Class, name, span);
+                walk_body(self, body);
+                self.exit_scope()
+            },
+            StmtKind::AnnAssign { target, annotation, value, simple } => {
+                if let ( ExprKind::Name { id, .. }, 1 ) = ( &target.node, simple ) {
+                    let scope = self.current();
+                    let declared = self.scopes[scope].lookup(id).map( |s| ( s.is_declared_global, s.is_declared_nonlocal ) );
+                    match declared {
+                        Some( ( true, _ ) ) => self.error(span, format!("annotated name '{}' can't be global", id)),
+                        Some( ( _, true ) ) => self.error(span, format!("annotated name '{}' can't be nonlocal", id)),
+                        _ => { }
+                    }
+                    self.scopes[scope].symbol_mut(id).is_annotated = true;
+                    self.bind(id, ( target.location.start, target.location.end ));
+                }
+                else {
+                    self.visit_expr(target)
+                }
+                self.visit_expr(annotation);
+                if let Some( v ) = value {
+                    self.visit_expr(v)
+                }
+            },
+            StmtKind::Global { names } => self.declare(stmt, names, true),
+            StmtKind::Nonlocal { names } => self.declare(stmt, names, false),
+            _ => walk_stmt(self, stmt)
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        let span = ( expr.location.start, expr.location.end );
+        match &expr.node {
+            ExprKind::Name { id, ctx } => {
+                match ctx {
+                    ExprContext::Load => {
+                        self.reference(id, span);
+                        let current = self.current();
+                        if id == "super" && self.scopes[current].is_function_like() {
+                            self.scopes[current].symbol_mut("__class__").is_referenced = true
+                        }
+                    },
+                    _ => self.bind(id, span)
+                }
+            },
+            ExprKind::Lambda { args, body } => {
+                self.visit_defaults(args);
+                self.enter_scope(ScopeKind::Lambda, "lambda", span);
+                self.add_parameters(args);
+                self.visit_expr(body);
+                self.exit_scope()
+            },
+            ExprKind::ListComp { elt, generators } => self.visit_comprehension_scope(expr, "listcomp", generators, &[ elt ]),
+            ExprKind::SetComp { elt, generators } => self.visit_comprehension_scope(expr, "setcomp", generators, &[ elt ]),
+            ExprKind::GeneratorExp { elt, generators } => self.visit_comprehension_scope(expr, "genexpr", generators, &[ elt ]),
+            ExprKind::DictComp { key, value, generators } => self.visit_comprehension_scope(expr, "dictcomp", generators, &[ key, value ]),
+            ExprKind::Yield { .. } |
+            ExprKind::YieldFrom { .. } => {
+                let current = self.current();
+                let description = match self.scopes[current].name.as_str() {
+                    "listcomp" => "list comprehension",
+                    "setcomp" => "set comprehension",
+                    "dictcomp" => "dict comprehension",
+                    _ => "generator expression"
+                };
+                if self.scopes[current].kind == ScopeKind::Comprehension {
+                    self.error(span, format!("'yield' inside {}", description))
+                }
+                self.scopes[current].is_generator = true;
+                walk_expr(self, expr)
+            },
+            ExprKind::NamedExpr { target, value } => {
+                self.visit_expr(value);
+                self.bind_named_expr_target(target)
+            },
+            _ => walk_expr(self, expr)
+        }
+    }
+
+    fn visit_alias(&mut self, alias: &Alias) {
+        let current = self.current();
+        if alias.name == "*" {
+            if self.scopes[current].kind != ScopeKind::Module {
+                self.error(( alias.location.start, alias.location.end ), "import * only allowed at module level".to_string())
+            }
+            self.scopes[current].has_import_star = true;
+            return
+        }
+        let ( name, span ) = match &alias.asname {
+            Some( n ) => ( n.clone(), ( alias.location.end - n.chars().count() as u32, alias.location.end ) ),
+            None => {
+                let first = alias.name.split('.').next().unwrap_or("").to_string();
+                let span = ( alias.location.start, alias.location.start + first.chars().count() as u32 );
+                ( first, span )
+            }
+        };
+        self.bind(&name, span);
+        self.scopes[current].symbol_mut(&name).is_imported = true
+    }
+
+    fn visit_except_handler(&mut self, handler: &ExceptHandler) {
+        if let Some( t ) = &handler.type_ {
+            self.visit_expr(t)
+        }
+        if let Some( name ) = &handler.name {
+            let from = handler.type_.as_ref().map( |t| t.location.end ).unwrap_or(handler.location.start);
+            let span = self.find_name(from, name);
+            self.bind(name, span)
+        }
+        walk_body(self, &handler.body)
+    }
+}
+
+
+// UnitTests for symbol table //////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::semantic::symbols::{ SymbolTable, ScopeKind, SymbolScope };
+
+
+    /// Symbols of every scope as 'scope: name=kind ...' lines, in the order scopes are created.
+    fn describe(source: &str) -> Vec<String> {
+        let table = SymbolTable::from_source(source).unwrap();
+        table.scopes.iter().map( |scope| {
+            let mut names : Vec<String> = scope.symbols.iter().map( |s| format!("{}={:?}", s.name, s.scope) ).collect();
+            names.sort();
+            format!("{}: {}", scope.name, names.join(" "))
+        } ).collect()
+    }
+
+    fn errors(source: &str) -> Vec<String> {
+        SymbolTable::from_source(source).unwrap().diagnostics.iter().map( |d| format!("{}: {}", d.start, d.message) ).collect()
+    }
+
+    #[test]
+    fn symbols_module_and_function() {
+        assert_eq!(describe("import os\nx = 1\ndef f(a, *b, c=x, **d):\n    y = a + x\n    return len(y)\n"), vec![
+            "top: f=Local os=Local x=Local",
+            "f: a=Local b=Local c=Local d=Local len=Global x=Global y=Local"
+        ]);
+    }
+
+    #[test]
+    fn symbols_closures_cell_and_free() {
+        assert_eq!(describe("def f():\n    x = 1\n    def g():\n        def h():\n            return x\n        return h\n    return g\n"), vec![
+            "top: f=Local",
+            "f: g=Local x=Cell",
+            "g: h=Local x=Free",
+            "h: x=Free"
+        ]);
+    }
+
+    #[test]
+    fn symbols_global_and_nonlocal() {
+        assert_eq!(describe("def f():\n    global a\n    a = 1\n    b = 2\n    def g():\n        nonlocal b\n        b = 3\n"), vec![
+            "top: f=Local",
+            "f: a=Global b=Cell g=Local",
+            "g: b=Nonlocal"
+        ]);
+    }
+
+    #[test]
+    fn symbols_class_scope_is_skipped() {
+        assert_eq!(describe("def f():\n    x = 1\n    class A:\n        x = 2\n        def m(self):\n            return x, super()\n"), vec![
+            "top: f=Local",
+            "f: A=Local x=Cell",
+            "A: m=Local x=Local",
+            "m: __class__=Free self=Local super=Global x=Free"
+        ]);
+        let table = SymbolTable::from_source("class A:\n    def m(self):\n        return super()\n").unwrap();
+        assert!(table.scope(1).needs_class_closure);
+    }
+
+    #[test]
+    fn symbols_lambda_and_comprehensions() {
+        assert_eq!(describe("def f(n):\n    g = lambda a, b=n: a + n\n    return [i * j for i in range(n) for j in i if j], {k: v for k, v in g}\n"), vec![
+            "top: f=Local",
+            "f: g=Local n=Cell range=Global",
+            "lambda: a=Local b=Local n=Free",
+            "listcomp: .0=Local i=Local j=Local",
+            "dictcomp: .0=Local k=Local v=Local"
+        ]);
+    }
+
+    #[test]
+    fn symbols_named_expression_in_comprehension() {
+        assert_eq!(describe("def f(a):\n    [y := x for x in a]\n    return y\n"), vec![
+            "top: f=Local",
+            "f: a=Local y=Cell",
+            "listcomp: .0=Local x=Local y=Nonlocal"
+        ]);
+        assert_eq!(describe("[y := x for x in a]\n"), vec![
+            "top: a=Global y=Global",
+            "listcomp: .0=Local x=Local y=Global"
+        ]);
+    }
+
+    #[test]
+    fn symbols_scope_lookup_by_node_span() {
+        let source = "def f():\n    pass\nclass C:\n    pass\n";
+        let table = SymbolTable::from_source(source).unwrap();
+        let id = table.scope_of_node(( 18, 35 )).unwrap();
+        assert_eq!(table.scope(id).kind, ScopeKind::Class);
+        assert_eq!(table.scope_starting_at(0).map( |i| table.scope(i).name.as_str() ), Some( "f" ));
+        assert_eq!(table.root().lookup("C").unwrap().bindings, vec![ ( 24, 25 ) ]);
+    }
+
+    #[test]
+    fn symbols_resolve_and_symbol_at() {
+        let source = "x = 1\ndef f(a):\n    global x\n    def g():\n        return a + x\n";
+        let table = SymbolTable::from_source(source).unwrap();
+        let ( scope, symbol ) = table.symbol_at(57).unwrap();
+        assert_eq!(( table.scope(scope).name.as_str(), symbol.name.as_str(), symbol.scope ), ( "g", "a", SymbolScope::Free ));
+        let ( bound, param ) = table.resolve(scope, "a").unwrap();
+        assert_eq!(( table.scope(bound).name.as_str(), param.bindings.clone() ), ( "f", vec![ ( 12, 13 ) ] ));
+        assert_eq!(table.resolve(scope, "x").map( |( id, _ )| id ), Some( 0 ));
+        assert_eq!(table.root().lookup("x").unwrap().occurrences().len(), 1);
+        assert_eq!(table.lookup(1, "x").unwrap().declarations, vec![ ( 27, 28 ) ]);
+        assert!(table.resolve(scope, "len").is_none());
+    }
+
+    #[test]
+    fn symbols_imports_and_except_names() {
+        let table = SymbolTable::from_source("import a.b as c, d.e\ntry:\n    pass\nexcept E as err:\n    pass\n").unwrap();
+        assert_eq!(table.root().lookup("c").unwrap().bindings, vec![ ( 14, 15 ) ]);
+        assert_eq!(table.root().lookup("d").unwrap().bindings, vec![ ( 17, 18 ) ]);
+        assert!(table.root().lookup("d").unwrap().is_imported);
+        assert_eq!(table.root().lookup("err").unwrap().bindings, vec![ ( 47, 50 ) ]);
+    }
+
+    #[test]
+    fn symbols_errors() {
+        assert_eq!(errors("def f(a):\n    global a\n"), vec![ "14: name 'a' is parameter and global" ]);
+        assert_eq!(errors("def f():\n    print(x)\n    global x\n"), vec![ "26: name 'x' is used prior to global declaration" ]);
+        assert_eq!(errors("def f():\n    x = 1\n    def g():\n        x = 2\n        nonlocal x\n"), vec![ "54: name 'x' is assigned to before nonlocal declaration" ]);
+        assert_eq!(errors("def f():\n    nonlocal x\n"), vec![ "13: no binding for nonlocal 'x' found" ]);
+        assert_eq!(errors("nonlocal x\n"), vec![ "0: nonlocal declaration not allowed at module level" ]);
+        assert_eq!(errors("def f(a, a):\n    pass\n"), vec![ "9: duplicate argument 'a' in function definition" ]);
+        assert_eq!(errors("def f():\n    from m import *\n"), vec![ "27: import * only allowed at module level" ]);
+        assert_eq!(errors("[(yield 1) for a in b]\n"), vec![ "2: 'yield' inside list comprehension" ]);
+        assert_eq!(errors("[a := 1 for a in b]\n"), vec![ "1: assignment expression cannot rebind comprehension iteration variable 'a'" ]);
+        assert_eq!(errors("class A:\n    [y := 1 for a in b]\n"), vec![ "14: assignment expression within a comprehension cannot be used in a class body" ]);
+        assert_eq!(errors("def f():\n    global x\n    x: int\n"), vec![ "26: annotated name 'x' can't be global" ]);
+    }
+}
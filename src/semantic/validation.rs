@@ -0,0 +1,738 @@
+use std::collections::HashSet;
+use crate::ast::nodes::{ Mod, Stmt, StmtKind, Expr, ExprKind, Constant, UnaryOperator, Keyword, Alias, WithItem };
+use crate::ast::visitor::{ Visitor, walk_stmt, walk_expr, walk_body, walk_exprs };
+use crate::semantic::symbols::SymbolTable;
+use crate::diagnostics::Diagnostic;
+
+
+const FUTURE_FEATURES: [&str; 10] = [ "nested_scopes", "generators", "division", "absolute_import", "with_statement",
+                                      "print_function", "unicode_literals", "barry_as_FLUFL", "generator_stop", "annotations" ];
+
+const EXCEPT_STAR_JUMP: &str = "'break', 'continue' and 'return' cannot appear in an except* block";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Module,
+    Class,
+    Function { is_async: bool, is_generator: bool },
+    Lambda,
+    Comprehension { is_generator_expression: bool }
+}
+
+/// Kind of an expression used as target of an assignment. Only these can be bound by any kind of
+/// assignment, though not every kind of assignment accepts all of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetKind {
+    Name,
+    Attribute,
+    Subscript,
+    Starred,
+    Tuple,
+    List
+}
+
+/// Statement or expression that binds a target, decides which kinds of target are allowed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TargetContext {
+    Assign,
+    AugAssign,
+    AnnAssign,
+    For,
+    WithItem,
+    Comprehension,
+    NamedExpr
+}
+
+struct Block {
+    kind: BlockKind,
+    span: ( u32, u32 ),
+    loops: u32,
+    /// Inside an 'except*' handler, which 'break', 'continue' and 'return' may not leave.
+    except_star: bool
+}
+
+/// Checks of the errors CPython reports after parsing, while it builds the abstract tree and while
+/// it compiles. Messages are the same as CPython uses.
+struct Validator<'a> {
+    source: Vec<char>,
+    symbols: &'a SymbolTable,
+    blocks: Vec<Block>,
+    future_imports: HashSet<u32>,
+    reported_comprehensions: HashSet<u32>,
+    syntax_errors: Vec<Diagnostic>,
+    compile_errors: Vec<Diagnostic>
+}
+
+
+/// Validates lowered module and returns errors in the order CPython would find them. Errors of
+/// the grammar come first, then errors of the symbol table and last errors of the compiler, each
+/// group sorted by position.
+pub fn validate(module: &Mod, symbols: &SymbolTable, source: &str) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        source: source.chars().collect(),
+        symbols,
+        blocks: vec![ Block { kind: BlockKind::Module, span: ( 0, 0 ), loops: 0, except_star: false } ],
+        future_imports: HashSet::new(),
+        reported_comprehensions: HashSet::new(),
+        syntax_errors: Vec::new(),
+        compile_errors: Vec::new()
+    };
+    if let Mod::Module { body, .. } = module {
+        validator.allow_leading_future_imports(body)
+    }
+    validator.visit_mod(module);
+    validator.syntax_errors.sort_by_key( |d| d.start );
+    validator.compile_errors.sort_by_key( |d| d.start );
+    let mut res = validator.syntax_errors;
+    res.extend( symbols.diagnostics.iter().cloned() );
+    res.extend( validator.compile_errors );
+    res
+}
+
+/// Short description of an expression used in messages like 'cannot assign to function call'.
+pub fn expression_description(expr: &Expr) -> &'static str {
+    match &expr.node {
+        ExprKind::Call { .. } => "function call",
+        ExprKind::Constant { value: Constant::None, .. } => "None",
+        ExprKind::Constant { value: Constant::Bool(true), .. } => "True",
+        ExprKind::Constant { value: Constant::Bool(false), .. } => "False",
+        ExprKind::Constant { value: Constant::Ellipsis, .. } => "ellipsis",
+        ExprKind::Constant { .. } => "literal",
+        ExprKind::JoinedStr { .. } |
+        ExprKind::FormattedValue { .. } => "f-string expression",
+        ExprKind::Compare { .. } => "comparison",
+        ExprKind::IfExp { .. } => "conditional expression",
+        ExprKind::Lambda { .. } => "lambda",
+        ExprKind::NamedExpr { .. } => "named expression",
+        ExprKind::Dict { .. } => "dict literal",
+        ExprKind::Set { .. } => "set display",
+        ExprKind::ListComp { .. } => "list comprehension",
+        ExprKind::SetComp { .. } => "set comprehension",
+        ExprKind::DictComp { .. } => "dict comprehension",
+        ExprKind::GeneratorExp { .. } => "generator expression",
+        ExprKind::Yield { .. } |
+        ExprKind::YieldFrom { .. } => "yield expression",
+        ExprKind::Await { .. } => "await expression",
+        ExprKind::Starred { .. } => "starred",
+        ExprKind::Name { .. } => "name",
+        ExprKind::Attribute { .. } => "attribute",
+        ExprKind::Subscript { .. } => "subscript",
+        ExprKind::Tuple { .. } => "tuple",
+        ExprKind::List { .. } => "list",
+        _ => "expression"
+    }
+}
+
+/// Expressions on the level of '|' in grammar, where CPython suggests '==' for a wrong '='.
+fn is_bitwise_or_level(expr: &Expr) -> bool {
+    !matches!(&expr.node, ExprKind::Compare { .. } | ExprKind::IfExp { .. } | ExprKind::Lambda { .. } | ExprKind::BoolOp { .. }
+                          | ExprKind::UnaryOp { op: UnaryOperator::Not, .. })
+}
+
+/// Classifies expression as assignment target, or 'None' when it can never be assigned to.
+pub fn target_kind(expr: &Expr) -> Option<TargetKind> {
+    match &expr.node {
+        ExprKind::Name { .. } => Some( TargetKind::Name ),
+        ExprKind::Attribute { .. } => Some( TargetKind::Attribute ),
+        ExprKind::Subscript { .. } => Some( TargetKind::Subscript ),
+        ExprKind::Starred { .. } => Some( TargetKind::Starred ),
+        ExprKind::Tuple { .. } => Some( TargetKind::Tuple ),
+        ExprKind::List { .. } => Some( TargetKind::List ),
+        _ => None
+    }
+}
+
+fn span(expr: &Expr) -> ( u32, u32 ) {
+    ( expr.location.start, expr.location.end )
+}
+
+
+impl Validator<'_> {
+    fn syntax_error(&mut self, span: ( u32, u32 ), message: &str) {
+        self.syntax_errors.push( Diagnostic::error(span.0, span.1, message) )
+    }
+
+    fn compile_error(&mut self, span: ( u32, u32 ), message: &str) {
+        self.compile_errors.push( Diagnostic::error(span.0, span.1, message) )
+    }
+
+    fn block(&mut self) -> &mut Block {
+        self.blocks.last_mut().unwrap()
+    }
+
+    /// Innermost block that is not a comprehension, as comprehensions are part of their function.
+    fn enclosing_kind(&self) -> BlockKind {
+        self.blocks.iter().rev().map( |b| b.kind ).find( |k| !matches!(k, BlockKind::Comprehension { .. }) ).unwrap_or(BlockKind::Module)
+    }
+
+    fn in_async_function(&self) -> bool {
+        matches!(self.enclosing_kind(), BlockKind::Function { is_async: true, .. })
+    }
+
+    fn with_block<F: FnOnce(&mut Self)>(&mut self, kind: BlockKind, span: ( u32, u32 ), f: F) {
+        self.blocks.push( Block { kind, span, loops: 0, except_star: false } );
+        f(self);
+        self.blocks.pop();
+    }
+
+    /// Docstring and '__future__' imports at the very beginning of module are the only allowed ones.
+    fn allow_leading_future_imports(&mut self, body: &[Stmt]) {
+        for ( i, stmt ) in body.iter().enumerate() {
+            match &stmt.node {
+                StmtKind::Expr { value } if i == 0 && matches!(value.node, ExprKind::Constant { value: Constant::Str(_), .. }) => { },
+                StmtKind::ImportFrom { module: Some( m ), level: 0, .. } if m == "__future__" => {
+                    self.future_imports.insert(stmt.location.start);
+                },
+                _ => return
+            }
+        }
+    }
+
+    fn check_future_import(&mut self, stmt: &Stmt, names: &[Alias]) {
+        let stmt_span = ( stmt.location.start, stmt.location.end );
+        if !self.future_imports.contains(&stmt.location.start) {
+            return self.compile_error(stmt_span, "from __future__ imports must occur at the beginning of the file")
+        }
+        for alias in names {
+            if alias.name == "braces" {
+                return self.compile_error(stmt_span, "not a chance")
+            }
+            if !FUTURE_FEATURES.contains(&alias.name.as_str()) {
+                return self.compile_error(stmt_span, &format!("future feature {} is not defined", alias.name))
+            }
+        }
+    }
+
+    fn check_debug_name(&mut self, name: &str, span: ( u32, u32 )) {
+        if name == "__debug__" {
+            self.syntax_error(span, "cannot assign to __debug__")
+        }
+    }
+
+    /// Checks target of a statement or expression binding it. Targets that can not hold several
+    /// names are reported with a message of their own, others are checked recursively.
+    fn check_target(&mut self, target: &Expr, context: TargetContext) {
+        let description = expression_description(target);
+        match ( context, target_kind(target) ) {
+            ( TargetContext::AugAssign, Some( TargetKind::Name | TargetKind::Attribute | TargetKind::Subscript ) ) |
+            ( TargetContext::AnnAssign, Some( TargetKind::Name | TargetKind::Attribute | TargetKind::Subscript ) ) |
+            ( TargetContext::NamedExpr, Some( TargetKind::Name ) ) => self.check_store_target(target, None, true),
+            ( TargetContext::AugAssign, _ ) => {
+                self.syntax_error(span(target), &format!("'{}' is an illegal expression for augmented assignment", description))
+            },
+            ( TargetContext::AnnAssign, Some( TargetKind::Tuple | TargetKind::List ) ) => {
+                self.syntax_error(span(target), &format!("only single target (not {}) can be annotated", description))
+            },
+            ( TargetContext::AnnAssign, _ ) => self.syntax_error(span(target), "illegal target for annotation"),
+            ( TargetContext::NamedExpr, _ ) => {
+                self.syntax_error(span(target), &format!("cannot use assignment expressions with {}", description))
+            },
+            ( TargetContext::Assign, _ ) => self.check_store_target(target, Some( target.location.end ), true),
+            ( TargetContext::For | TargetContext::WithItem | TargetContext::Comprehension, _ ) => self.check_store_target(target, None, true)
+        }
+    }
+
+    /// Checks target of an assignment. With 'hint_end' a wrong expression ending there is followed
+    /// directly by '=', for which CPython suggests '==' instead.
+    fn check_store_target(&mut self, target: &Expr, hint_end: Option<u32>, top: bool) {
+        match &target.node {
+            ExprKind::Name { id, .. } => self.check_debug_name(id, span(target)),
+            ExprKind::Attribute { value, attr, .. } => {
+                self.check_debug_name(attr, span(target));
+                self.visit_expr(value)
+            },
+            ExprKind::Subscript { value, slice, .. } => {
+                self.visit_expr(value);
+                self.visit_expr(slice)
+            },
+            ExprKind::Starred { value, .. } => {
+                match top {
+                    true => self.syntax_error(span(target), "starred assignment target must be in a list or tuple"),
+                    _ => self.check_store_target(value, hint_end, false)
+                }
+            },
+            ExprKind::Tuple { elts, .. } |
+            ExprKind::List { elts, .. } => {
+                if elts.iter().filter( |e| matches!(e.node, ExprKind::Starred { .. }) ).count() > 1 {
+                    return self.syntax_error(span(target), "multiple starred expressions in assignment")
+                }
+                for e in elts {
+                    self.check_store_target(e, hint_end, false)
+                }
+            },
+            // CPython's grammar has a message of its own for a yield without parentheses in a chain of '='.
+            ExprKind::Yield { .. } |
+            ExprKind::YieldFrom { .. } if top && hint_end.is_some() && !self.is_parenthesized(target) => {
+                self.syntax_error(span(target), "assignment to yield expression not possible")
+            },
+            ExprKind::Constant { value: Constant::None, .. } |
+            ExprKind::Constant { value: Constant::Bool(_), .. } => {
+                self.syntax_error(span(target), &format!("cannot assign to {}", expression_description(target)))
+            },
+            _ => {
+                let description = expression_description(target);
+                match hint_end == Some( target.location.end ) && is_bitwise_or_level(target) {
+                    true => self.syntax_error(span(target), &format!("cannot assign to {} here. Maybe you meant '==' instead of '='?", description)),
+                    _ => self.syntax_error(span(target), &format!("cannot assign to {}", description))
+                }
+            }
+        }
+    }
+
+    fn is_parenthesized(&self, expr: &Expr) -> bool {
+        self.source[.. expr.location.start as usize].iter().rev().find( |c| !c.is_whitespace() ) == Some( &'(' )
+    }
+
+    fn check_delete_target(&mut self, target: &Expr) {
+        match &target.node {
+            ExprKind::Name { id, .. } => {
+                if id == "__debug__" {
+                    self.syntax_error(span(target), "cannot delete __debug__")
+                }
+            },
+            ExprKind::Attribute { .. } |
+            ExprKind::Subscript { .. } => self.visit_expr(target),
+            ExprKind::Tuple { elts, .. } |
+            ExprKind::List { elts, .. } => {
+                for e in elts {
+                    self.check_delete_target(e)
+                }
+            },
+            _ => self.syntax_error(span(target), &format!("cannot delete {}", expression_description(target)))
+        }
+    }
+
+    /// Checks order of positional and keyword arguments of a call or class definition.
+    fn check_call_arguments(&mut self, args: &[Expr], keywords: &[Keyword]) {
+        for a in args {
+            if let ExprKind::GeneratorExp { .. } = a.node {
+                if args.len() + keywords.len() > 1 && self.source.get(a.location.start as usize) != Some( &'(' ) {
+                    self.syntax_error(span(a), "Generator expression must be parenthesized")
+                }
+            }
+            if matches!(a.node, ExprKind::Starred { .. }) {
+                continue
+            }
+            if let Some( k ) = keywords.iter().find( |k| k.location.start < a.location.start ) {
+                match k.arg {
+                    Some( _ ) => self.syntax_error(span(a), "positional argument follows keyword argument"),
+                    None => self.syntax_error(span(a), "positional argument follows keyword argument unpacking")
+                }
+            }
+        }
+        let mut seen : HashSet<&str> = HashSet::new();
+        for k in keywords {
+            if let Some( name ) = &k.arg {
+                if !seen.insert(name.as_str()) {
+                    self.syntax_error(( k.location.start, k.location.end ), &format!("keyword argument repeated: {}", name))
+                }
+            }
+        }
+    }
+
+    fn visit_comprehension_expr(&mut self, expr: &Expr, elts: &[&Expr], generators: &[crate::ast::nodes::Comprehension], is_generator_expression: bool) {
+        if let Some( first ) = generators.first() {
+            self.visit_expr(&first.iter)
+        }
+        let is_async = generators.iter().any( |g| g.is_async == 1 );
+        if is_async && !is_generator_expression && !self.in_async_function() {
+            self.report_asynchronous_comprehension(span(expr))
+        }
+        self.with_block(BlockKind::Comprehension { is_generator_expression }, span(expr), |v| {
+            for ( i, g ) in generators.iter().enumerate() {
+                if i > 0 {
+                    v.visit_expr(&g.iter)
+                }
+                v.check_target(&g.target, TargetContext::Comprehension);
+                walk_exprs(v, &g.ifs)
+            }
+            for e in elts {
+                match &e.node {
+                    ExprKind::Starred { .. } => v.syntax_error(span(e), "iterable unpacking cannot be used in comprehension"),
+                    _ => v.visit_expr(e)
+                }
+            }
+        })
+    }
+
+    /// Comprehensions other than generator expressions are asynchronous when they contain 'async for'
+    /// or 'await', which is only allowed inside an asynchronous function.
+    fn report_asynchronous_comprehension(&mut self, span: ( u32, u32 )) {
+        if self.reported_comprehensions.insert(span.0) {
+            self.compile_error(span, "asynchronous comprehension outside of an asynchronous function")
+        }
+    }
+
+    fn visit_elements(&mut self, elts: &[Expr]) {
+        for e in elts {
+            match &e.node {
+                ExprKind::Starred { value, .. } => self.visit_expr(value),
+                _ => self.visit_expr(e)
+            }
+        }
+    }
+}
+
+impl Visitor for Validator<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        let stmt_span = ( stmt.location.start, stmt.location.end );
+        match &stmt.node {
+            StmtKind::FunctionDef { name, args, body, decorator_list, returns, .. } |
+            StmtKind::AsyncFunctionDef { name, args, body, decorator_list, returns, .. } => {
+                self.check_debug_name(name, stmt_span);
+                let all = args.posonlyargs.iter().chain(args.args.iter()).chain(args.vararg.as_deref()).chain(args.kwonlyargs.iter()).chain(args.kwarg.as_deref());
+                if all.clone().any( |a| a.arg == "__debug__" ) {
+                    self.syntax_error(stmt_span, "cannot assign to __debug__")
+                }
+                walk_exprs(self, decorator_list);
+                self.visit_arguments(args);
+                if let Some( r ) = returns {
+                    self.visit_expr(r)
+                }
+                let is_async = matches!(stmt.node, StmtKind::AsyncFunctionDef { .. });
+                let is_generator = self.symbols.scope_of_node(stmt_span).map( |id| self.symbols.scope(id).is_generator ).unwrap_or(false);
+                self.with_block(BlockKind::Function { is_async, is_generator }, stmt_span, |v| walk_body(v, body))
+            },
+            StmtKind::ClassDef { name, bases, keywords, body, decorator_list } => {
+                self.check_debug_name(name, stmt_span);
+                walk_exprs(self, decorator_list);
+                self.check_call_arguments(bases, keywords);
+                self.visit_elements(bases);
+                for k in keywords {
+                    self.visit_keyword(k)
+                }
+                self.with_block(BlockKind::Class, stmt_span, |v| walk_body(v, body))
+            },
+            StmtKind::Return { value } => {
+                match self.enclosing_kind() {
+                    BlockKind::Function { is_async: true, is_generator: true } if value.is_some() => {
+                        self.compile_error(stmt_span, "'return' with value in async generator")
+                    },
+                    BlockKind::Function { .. } | BlockKind::Lambda if self.block().except_star => {
+                        self.compile_error(stmt_span, EXCEPT_STAR_JUMP)
+                    },
+                    BlockKind::Function { .. } | BlockKind::Lambda => { },
+                    _ => self.compile_error(stmt_span, "'return' outside function")
+                }
+                walk_stmt(self, stmt)
+            },
+            StmtKind::Assign { targets, value, .. } => {
+                for t in targets {
+                    self.check_target(t, TargetContext::Assign)
+                }
+                self.visit_expr(value)
+            },
+            StmtKind::AugAssign { target, value, .. } => {
+                self.check_target(target, TargetContext::AugAssign);
+                self.visit_expr(value)
+            },
+            StmtKind::AnnAssign { target, annotation, value, .. } => {
+                self.check_target(target, TargetContext::AnnAssign);
+                self.visit_expr(annotation);
+                if let Some( v ) = value {
+                    self.visit_expr(v)
+                }
+            },
+            StmtKind::Delete { targets } => {
+                for t in targets {
+                    self.check_delete_target(t)
+                }
+            },
+            StmtKind::For { target, iter, body, orelse, .. } |
+            StmtKind::AsyncFor { target, iter, body, orelse, .. } => {
+                if matches!(stmt.node, StmtKind::AsyncFor { .. }) && !self.in_async_function() {
+                    self.compile_error(stmt_span, "'async for' outside async function")
+                }
+                self.visit_expr(iter);
+                self.check_target(target, TargetContext::For);
+                self.block().loops += 1;
+                walk_body(self, body);
+                self.block().loops -= 1;
+                walk_body(self, orelse)
+            },
+            StmtKind::While { test, body, orelse } => {
+                self.visit_expr(test);
+                self.block().loops += 1;
+                walk_body(self, body);
+                self.block().loops -= 1;
+                walk_body(self, orelse)
+            },
+            StmtKind::AsyncWith { .. } => {
+                if !self.in_async_function() {
+                    self.compile_error(stmt_span, "'async with' outside async function")
+                }
+                walk_stmt(self, stmt)
+            },
+            StmtKind::TryStar { body, handlers, orelse, finalbody } => {
+                walk_body(self, body);
+                let outer = ( self.block().loops, self.block().except_star );
+                ( self.block().loops, self.block().except_star ) = ( 0, true );
+                for h in handlers {
+                    self.visit_except_handler(h)
+                }
+                ( self.block().loops, self.block().except_star ) = outer;
+                walk_body(self, orelse);
+                walk_body(self, finalbody)
+            },
+            StmtKind::Break => {
+                match ( self.block().loops, self.block().except_star ) {
+                    ( 0, true ) => self.compile_error(stmt_span, EXCEPT_STAR_JUMP),
+                    ( 0, _ ) => self.compile_error(stmt_span, "'break' outside loop"),
+                    _ => { }
+                }
+            },
+            StmtKind::Continue => {
+                match ( self.block().loops, self.block().except_star ) {
+                    ( 0, true ) => self.compile_error(stmt_span, EXCEPT_STAR_JUMP),
+                    ( 0, _ ) => self.compile_error(stmt_span, "'continue' not properly in loop"),
+                    _ => { }
+                }
+            },
+            StmtKind::Import { names } => {
+                for a in names {
+                    let bound = a.asname.clone().unwrap_or( a.name.split('.').next().unwrap_or("").to_string() );
+                    self.check_debug_name(&bound, stmt_span)
+                }
+            },
+            StmtKind::ImportFrom { module, names, level } => {
+                if module.as_deref() == Some( "__future__" ) && *level == 0 {
+                    self.check_future_import(stmt, names)
+                }
+                for a in names {
+                    self.check_debug_name(a.asname.as_ref().unwrap_or(&a.name), stmt_span)
+                }
+            },
+            _ => walk_stmt(self, stmt)
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.node {
+            ExprKind::Lambda { args, body } => {
+                self.visit_arguments(args);
+                self.with_block(BlockKind::Lambda, span(expr), |v| v.visit_expr(body))
+            },
+            ExprKind::ListComp { elt, generators } |
+            ExprKind::SetComp { elt, generators } => self.visit_comprehension_expr(expr, &[ elt ], generators, false),
+            ExprKind::GeneratorExp { elt, generators } => self.visit_comprehension_expr(expr, &[ elt ], generators, true),
+            ExprKind::DictComp { key, value, generators } => self.visit_comprehension_expr(expr, &[ key, value ], generators, false),
+            ExprKind::Yield { .. } |
+            ExprKind::YieldFrom { .. } => {
+                match self.enclosing_kind() {
+                    BlockKind::Module | BlockKind::Class => self.compile_error(span(expr), "'yield' outside function"),
+                    BlockKind::Function { is_async: true, .. } if matches!(expr.node, ExprKind::YieldFrom { .. }) => {
+                        self.compile_error(span(expr), "'yield from' inside async function")
+                    },
+                    _ => { }
+                }
+                walk_expr(self, expr)
+            },
+            ExprKind::Await { .. } => {
+                let innermost = &self.blocks[self.blocks.len() - 1];
+                match ( innermost.kind, innermost.span ) {
+                    ( BlockKind::Comprehension { is_generator_expression: true }, _ ) => { },
+                    ( BlockKind::Comprehension { .. }, comprehension ) => {
+                        if !self.in_async_function() {
+                            self.report_asynchronous_comprehension(comprehension)
+                        }
+                    },
+                    _ => match self.enclosing_kind() {
+                        BlockKind::Module | BlockKind::Class => self.compile_error(span(expr), "'await' outside function"),
+                        BlockKind::Function { is_async: false, .. } | BlockKind::Lambda => self.compile_error(span(expr), "'await' outside async function"),
+                        _ => { }
+                    }
+                }
+                walk_expr(self, expr)
+            },
+            ExprKind::Call { func, args, keywords } => {
+                self.check_call_arguments(args, keywords);
+                for k in keywords {
+                    if let Some( name ) = &k.arg {
+                        self.check_debug_name(name, span(expr))
+                    }
+                }
+                self.visit_expr(func);
+                self.visit_elements(args);
+                for k in keywords {
+                    self.visit_keyword(k)
+                }
+            },
+            ExprKind::Tuple { elts, .. } |
+            ExprKind::List { elts, .. } |
+            ExprKind::Set { elts } => self.visit_elements(elts),
+            ExprKind::NamedExpr { target, value } => {
+                self.visit_expr(value);
+                self.check_target(target, TargetContext::NamedExpr)
+            },
+            ExprKind::Starred { .. } => self.syntax_error(span(expr), "can't use starred expression here"),
+            _ => walk_expr(self, expr)
+        }
+    }
+
+    fn visit_with_item(&mut self, item: &WithItem) {
+        self.visit_expr(&item.context_expr);
+        if let Some( target ) = &item.optional_vars {
+            self.check_target(target, TargetContext::WithItem)
+        }
+    }
+}
+
+
+// UnitTests for compile time checks ///////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{ check, Mode };
+
+
+    /// First error as 'start: message', the way CPython would report it, or 'ok'.
+    fn first_error(source: &str) -> String {
+        match check(source, Mode::Exec).first() {
+            Some( d ) => format!("{}: {}", d.start, d.message),
+            None => "ok".to_string()
+        }
+    }
+
+    #[test]
+    fn validation_valid_source() {
+        assert_eq!(first_error("def f(a, *b, c=1):\n    for x in b:\n        if x:\n            break\n        continue\n    return [y for y in c]\n"), "ok");
+        assert_eq!(first_error("async def f():\n    await x\n    async with a:\n        pass\n    return [await y for y in z]\n"), "ok");
+        assert_eq!(first_error("'''doc'''\nfrom __future__ import annotations\nimport os\n"), "ok");
+        assert_eq!(first_error("while 1:\n    try:\n        pass\n    finally:\n        continue\n"), "ok");
+        assert_eq!(first_error("a, *b, c = d\nx[1:2] = y.z = 3\ndel x[1:2], y.z, (a, b)\n"), "ok");
+    }
+
+    #[test]
+    fn validation_outside_function_or_loop() {
+        assert_eq!(first_error("return 1\n"), "0: 'return' outside function");
+        assert_eq!(first_error("yield 1\n"), "0: 'yield' outside function");
+        assert_eq!(first_error("def f():\n    class A:\n        return 1\n"), "30: 'return' outside function");
+        assert_eq!(first_error("break\n"), "0: 'break' outside loop");
+        assert_eq!(first_error("continue\n"), "0: 'continue' not properly in loop");
+        assert_eq!(first_error("for x in y:\n    pass\nelse:\n    break\n"), "31: 'break' outside loop");
+        assert_eq!(first_error("while 1:\n    def g():\n        break\n"), "30: 'break' outside loop");
+        let jump = "'break', 'continue' and 'return' cannot appear in an except* block";
+        assert_eq!(first_error("for x in y:\n    try:\n        pass\n    except* E:\n        break\n"), format!("57: {}", jump));
+        assert_eq!(first_error("while 1:\n    try:\n        pass\n    except* E:\n        continue\n"), format!("54: {}", jump));
+        assert_eq!(first_error("def f():\n    try:\n        pass\n    except* E:\n        return 1\n"), format!("54: {}", jump));
+        assert_eq!(first_error("try:\n    pass\nexcept* E:\n    break\n"), format!("29: {}", jump));
+        assert_eq!(first_error("try:\n    pass\nexcept* E:\n    for x in y:\n        break\n    def f():\n        return\n"), "ok");
+        assert_eq!(first_error("for x in y:\n    try:\n        pass\n    except* E:\n        pass\n    break\n"), "ok");
+    }
+
+    #[test]
+    fn validation_async_rules() {
+        assert_eq!(first_error("await x\n"), "0: 'await' outside function");
+        assert_eq!(first_error("def f():\n    await x\n"), "13: 'await' outside async function");
+        assert_eq!(first_error("async def f():\n    lambda: await x\n"), "27: 'await' outside async function");
+        assert_eq!(first_error("def f():\n    [x async for x in y]\n"), "13: asynchronous comprehension outside of an asynchronous function");
+        assert_eq!(first_error("def f():\n    (await x for x in y)\n"), "ok");
+        assert_eq!(first_error("def f():\n    async with a: pass\n"), "13: 'async with' outside async function");
+        assert_eq!(first_error("async def f():\n    yield from x\n"), "19: 'yield from' inside async function");
+        assert_eq!(first_error("async def f():\n    yield 1\n    return 2\n"), "31: 'return' with value in async generator");
+    }
+
+    #[test]
+    fn validation_assignment_targets() {
+        assert_eq!(first_error("f() = 1\n"), "0: cannot assign to function call here. Maybe you meant '==' instead of '='?");
+        assert_eq!(first_error("(a, 1) = x\n"), "4: cannot assign to literal");
+        assert_eq!(first_error("a, f() = x\n"), "3: cannot assign to function call here. Maybe you meant '==' instead of '='?");
+        assert_eq!(first_error("None = 1\n"), "0: cannot assign to None");
+        assert_eq!(first_error("a < b = 1\n"), "0: cannot assign to comparison");
+        assert_eq!(first_error("a = yield = 1\n"), "4: assignment to yield expression not possible");
+        assert_eq!(first_error("def f():\n    (yield) = 1\n"), "14: cannot assign to yield expression here. Maybe you meant '==' instead of '='?");
+        assert_eq!(first_error("def f():\n    for (yield) in x: pass\n"), "18: cannot assign to yield expression");
+        assert_eq!(first_error("x.__debug__ = 1\n"), "0: cannot assign to __debug__");
+        assert_eq!(first_error("*a = 1\n"), "0: starred assignment target must be in a list or tuple");
+        assert_eq!(first_error("[a, *b, *c] = d\n"), "0: multiple starred expressions in assignment");
+    }
+
+    #[test]
+    fn validation_target_kind() {
+        use crate::ast::nodes::{ Mod, StmtKind };
+        use crate::ast::lowering::{ Lowering, PythonCoreLowering };
+        use crate::semantic::validation::{ target_kind, TargetKind };
+        let kinds = [ ( "a = 1\n", Some( TargetKind::Name ) ), ( "a.b = 1\n", Some( TargetKind::Attribute ) ),
+                      ( "a[0] = 1\n", Some( TargetKind::Subscript ) ), ( "a, b = 1\n", Some( TargetKind::Tuple ) ),
+                      ( "[a] = 1\n", Some( TargetKind::List ) ), ( "f() = 1\n", None ), ( "1 = 1\n", None ) ];
+        for ( source, kind ) in kinds {
+            let tree = crate::api::parse_module(source).tree.unwrap();
+            let module = PythonCoreLowering::new(source).lower_mod(&tree).unwrap();
+            match &module {
+                Mod::Module { body, .. } => match &body[0].node {
+                    StmtKind::Assign { targets, .. } => assert_eq!(target_kind(&targets[0]), kind, "{}", source),
+                    _ => panic!("not an assignment")
+                },
+                _ => panic!("not a module")
+            }
+        }
+    }
+
+    #[test]
+    fn validation_augmented_and_annotated_targets() {
+        assert_eq!(first_error("a.b += 1\na[0] -= 1\nx **= 2\na.b: int = 1\na[0]: int\n(x): int = 1\n"), "ok");
+        assert_eq!(first_error("f() += 1\n"), "0: 'function call' is an illegal expression for augmented assignment");
+        assert_eq!(first_error("(a, b) += x\n"), "0: 'tuple' is an illegal expression for augmented assignment");
+        assert_eq!(first_error("[a] |= x\n"), "0: 'list' is an illegal expression for augmented assignment");
+        assert_eq!(first_error("None += 1\n"), "0: 'None' is an illegal expression for augmented assignment");
+        assert_eq!(first_error("__debug__ += 1\n"), "0: cannot assign to __debug__");
+        assert_eq!(first_error("[a]: int = 1\n"), "0: only single target (not list) can be annotated");
+        assert_eq!(first_error("(a, b): int\n"), "0: only single target (not tuple) can be annotated");
+        assert_eq!(first_error("f(): int\n"), "0: illegal target for annotation");
+    }
+
+    #[test]
+    fn validation_loop_with_and_comprehension_targets() {
+        assert_eq!(first_error("for a, *b in x: pass\nwith a as (b, c), d as e.f: pass\n[x for a[0] in y]\n"), "ok");
+        assert_eq!(first_error("for f() in x: pass\n"), "4: cannot assign to function call");
+        assert_eq!(first_error("for a, 1 in x: pass\n"), "7: cannot assign to literal");
+        assert_eq!(first_error("for *a in x: pass\n"), "4: starred assignment target must be in a list or tuple");
+        assert_eq!(first_error("for [a, *b, *c] in x: pass\n"), "4: multiple starred expressions in assignment");
+        assert_eq!(first_error("with a as (b, f()): pass\n"), "14: cannot assign to function call");
+        assert_eq!(first_error("[x for None in y]\n"), "7: cannot assign to None");
+        assert_eq!(first_error("{k: v for __debug__ in y}\n"), "10: cannot assign to __debug__");
+    }
+
+    #[test]
+    fn validation_named_expression_targets() {
+        assert_eq!(first_error("(x := 1)\n"), "ok");
+        assert_eq!(first_error("(f() := 1)\n"), "1: cannot use assignment expressions with function call");
+        assert_eq!(first_error("(a.b := 1)\n"), "1: cannot use assignment expressions with attribute");
+        assert_eq!(first_error("((a, b) := 1)\n"), "1: cannot use assignment expressions with tuple");
+        assert_eq!(first_error("(__debug__ := 1)\n"), "1: cannot assign to __debug__");
+    }
+
+    #[test]
+    fn validation_delete_targets() {
+        assert_eq!(first_error("del f()\n"), "4: cannot delete function call");
+        assert_eq!(first_error("del (a, 1)\n"), "8: cannot delete literal");
+        assert_eq!(first_error("del a + b\n"), "4: cannot delete expression");
+        assert_eq!(first_error("del [a, *b]\n"), "8: cannot delete starred");
+        assert_eq!(first_error("del __debug__\n"), "4: cannot delete __debug__");
+    }
+
+    #[test]
+    fn validation_arguments() {
+        assert_eq!(first_error("def f(a=1, b): pass\n"), "11: non-default argument follows default argument");
+        assert_eq!(first_error("def f(*): pass\n"), "6: named arguments must follow bare *");
+        assert_eq!(first_error("f(a=1, a=2)\n"), "7: keyword argument repeated: a");
+        assert_eq!(first_error("f(a=1, b)\n"), "7: positional argument follows keyword argument");
+        assert_eq!(first_error("f(a, b for b in c)\n"), "5: Generator expression must be parenthesized");
+        assert_eq!(first_error("f(b for b in c)\n"), "ok");
+        assert_eq!(first_error("def f(a, a): pass\n"), "9: duplicate argument 'a' in function definition");
+        assert_eq!(first_error("[*a for a in b]\n"), "1: iterable unpacking cannot be used in comprehension");
+    }
+
+    #[test]
+    fn validation_future_imports() {
+        assert_eq!(first_error("import os\nfrom __future__ import annotations\n"), "10: from __future__ imports must occur at the beginning of the file");
+        assert_eq!(first_error("from __future__ import braces\n"), "0: not a chance");
+        assert_eq!(first_error("from __future__ import nope\n"), "0: future feature nope is not defined");
+    }
+
+    #[test]
+    fn validation_error_order() {
+        assert_eq!(first_error("return\nf() = 1\n"), "7: cannot assign to function call here. Maybe you meant '==' instead of '='?");
+        assert_eq!(first_error("return\nnonlocal x\n"), "7: nonlocal declaration not allowed at module level");
+    }
+}
@@ -0,0 +1,4 @@
+if a:
+    x = 1
+
+      y = 2
@@ -0,0 +1,16 @@
+name
+42
+3.14
+0x1F
+'single' "double"
+b'bytes'
+f'{value!r:>10}'
+...
+None
+True
+False
+(1, 2)
+[1, 2, 3]
+{'a': 1, **rest}
+{1, 2}
+()
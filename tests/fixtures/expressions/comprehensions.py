@@ -0,0 +1,6 @@
+[x for x in y if x]
+{k: v for k, v in items}
+{x for x in y}
+(x for x in y for z in x)
+lambda a, *b, c=1, **d: a
+(n := 10)
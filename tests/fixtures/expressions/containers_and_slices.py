@@ -0,0 +1,9 @@
+{}
+(1,)
+[1, 2,]
+{**a, 'b': 2}
+{*a, b}
+a[::2]
+a[1:, i]
+x = yield
+(yield)
@@ -0,0 +1,5 @@
+1_000_000
+br'\d' Rb"x" B'y'
+'it\'s'
+"a\
+b"
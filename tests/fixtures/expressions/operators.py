@@ -0,0 +1,8 @@
+a + b * c - d / e // f % g @ h ** i
+-a + ~b
+a << 1 | b >> 2 & c ^ d
+not a and b or c
+a < b <= c == d != e > f >= g
+a is not b
+a not in b
+x if y else z
@@ -0,0 +1,5 @@
+f(a, *args, key=value, **kwargs)
+obj.attr.method()
+seq[1:2, ::3]
+matrix[i][j]
+await fetch(url)
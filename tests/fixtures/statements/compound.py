@@ -0,0 +1,24 @@
+if a:
+    pass
+elif b:
+    x = 1
+else:
+    y = 2
+
+while x:
+    break
+else:
+    pass
+
+for i in range(10):
+    continue
+
+try:
+    a()
+except E as e:
+    b()
+finally:
+    c()
+
+with open(f) as g, h:
+    pass
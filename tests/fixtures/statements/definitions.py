@@ -0,0 +1,16 @@
+@decorator
+@module.decorator(arg)
+def function(a, b=1, /, c=2, *args, d, **kwargs) -> int:
+    return a
+
+
+class Example(Base, metaclass=Meta):
+    attribute = 1
+
+    def method(self):
+        yield self
+
+
+async def coroutine():
+    async with lock:
+        await other()
@@ -0,0 +1,6 @@
+match = re.match(p, s)
+if match:
+    print(match.group(1))
+match.x, match[0] = 1, 2
+match: int = 3
+match(a, b)
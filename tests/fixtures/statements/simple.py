@@ -0,0 +1,13 @@
+x = 1
+a, b = c = 1, 2
+x: int = 3
+x += 1
+del a, b
+pass
+import os.path as p, sys
+from . import a
+from ..m import (b as c, d)
+global g
+assert x, 'message'
+raise E from F
+x = 1; y = 2
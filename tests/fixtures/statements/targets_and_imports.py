@@ -0,0 +1,14 @@
+for x, y in z:
+    pass
+from m import (a, b,)
+@d
+async def f():
+    pass
+with a as b:
+    pass
+x = 1  # type: int
+import os  # type: ignore
+if a:
+    x = 1
+
+    y = 2
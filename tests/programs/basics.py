@@ -0,0 +1,52 @@
+def fib(n):
+    return n if n < 2 else fib(n - 1) + fib(n - 2)
+print(fib(15), [x * x for x in range(5)], {k: v for k, v in zip("ab", (1, 2))})
+def gen(n):
+    total = yield 0
+    for i in range(n):
+        got = yield i
+        if got:
+            total += got
+    return total
+g = gen(3)
+print(next(g), g.send(5), g.send(None), g.send(2))
+try:
+    g.send(3)
+except StopIteration as e:
+    print("stop", e.value)
+def outer():
+    x = 1
+    def inner():
+        nonlocal x
+        x += 1
+        return x
+    return inner
+f = outer()
+print(f(), f())
+class A:
+    y = 3
+    def __init__(self, v):
+        self.v = v
+    def get(self):
+        return self.v + self.y
+class B(A):
+    def get(self):
+        return super().get() * 2
+print(B(4).get(), isinstance(B(1), A))
+try:
+    try:
+        1 / 0
+    except ZeroDivisionError as e:
+        print("caught", e)
+        raise ValueError("bad")
+    finally:
+        print("finally")
+except ValueError as e:
+    print(repr(e), repr(e.__context__))
+def deleg():
+    r = yield from gen(2)
+    print("r", r)
+    yield from [7, 8]
+print(list(deleg()))
+a, *b, c = range(5)
+print(a, b, c, f"{a!r:>3}|{c}")
@@ -0,0 +1,74 @@
+def f(a, b=2, *args, c, d=4, **kw):
+    return (a, b, args, c, d, kw)
+print(f(1, c=3), f(1, 2, 3, 4, c=5, e=6), f(*[1, 2], **{"c": 9}))
+g = lambda x, y=10: x + y
+print(g(1), g(1, 2))
+def t():
+    try:
+        return 1
+    finally:
+        print("fin")
+print(t())
+for i in range(5):
+    try:
+        if i == 1:
+            continue
+        if i == 3:
+            break
+    finally:
+        print("loop", i)
+class CM:
+    def __enter__(self):
+        print("enter")
+        return self
+    def __exit__(self, t, v, tb):
+        print("exit", t.__name__ if t else None, v)
+        return True
+with CM() as c:
+    raise KeyError("k")
+print("after with")
+def gen():
+    try:
+        yield 1
+        yield 2
+    finally:
+        print("gen closed")
+x = gen()
+print(next(x))
+x.close()
+def thrower():
+    try:
+        yield 1
+    except ValueError as e:
+        print("got", e)
+        yield 2
+y = thrower()
+next(y)
+print(y.throw(ValueError("boom")))
+def cells(a):
+    def h():
+        return a
+    return h
+print(cells(5)())
+def rec(n):
+    return rec(n + 1)
+try:
+    rec(0)
+except RecursionError as e:
+    print("rec", e)
+print(sum(x for x in range(10) if x % 2), sorted({3, 1, 2}), (1, 2) < (1, 3))
+i = 0
+while i < 3:
+    i += 1
+else:
+    print("else", i)
+del i
+try:
+    i
+except NameError as e:
+    print(e)
+assert True
+try:
+    assert 1 == 2, "msg"
+except AssertionError as e:
+    print("assert", e)
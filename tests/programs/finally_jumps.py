@@ -0,0 +1,68 @@
+def f():
+    for i in range(3):
+        try:
+            return i
+        finally:
+            continue
+    return 9
+print(f())
+def g():
+    while True:
+        try:
+            return 1
+        finally:
+            break
+    return 2
+print(g())
+def nested():
+    for i in range(2):
+        for j in range(3):
+            try:
+                return (i, j)
+            finally:
+                if j < 2:
+                    continue
+                break
+    return "done"
+print(nested())
+def overridden():
+    try:
+        return 1
+    finally:
+        return 2
+print(overridden())
+def gen():
+    for i in range(3):
+        try:
+            yield i
+            return i
+        finally:
+            continue
+    yield "end"
+print(list(gen()))
+class CM:
+    def __enter__(self):
+        return self
+    def __exit__(self, t, v, tb):
+        print("exit", t)
+        return False
+def under_with():
+    for i in range(2):
+        with CM():
+            try:
+                return i
+            finally:
+                continue
+    return "after"
+print(under_with())
+def inner_try():
+    for i in range(2):
+        try:
+            return i
+        finally:
+            try:
+                pass
+            finally:
+                continue
+    return "inner"
+print(inner_try())
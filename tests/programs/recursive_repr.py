@@ -0,0 +1,21 @@
+x = []
+x.append(x)
+print(x, repr(x), str([x, 1]))
+d = {}
+d["self"] = d
+d["list"] = [d, x]
+print(d)
+v = {}
+v[1] = v.values()
+print(v)
+class Boom:
+    def __repr__(self):
+        raise ValueError("boom")
+y = [Boom()]
+y.append(y)
+try:
+    print(y)
+except ValueError as e:
+    print("error", e)
+y.pop(0)
+print(y)
@@ -0,0 +1,28 @@
+def a():
+    return b()
+def b():
+    x = 1
+    def c():
+        return y
+    y = c
+    del y
+    return c()
+def gen():
+    yield 1
+    raise StopIteration
+try:
+    list(gen())
+except RuntimeError as e:
+    print(e, type(e.__cause__).__name__)
+def u():
+    print(z)
+    z = 1
+try:
+    u()
+except UnboundLocalError as e:
+    print(e)
+class K:
+    def m(self):
+        return __class__
+print(K().m() is K)
+a()